@@ -0,0 +1,371 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::audio::{AsAudioBufferRef, AudioBuffer, AudioBufferRef};
+use symphonia_core::audio::{Signal, SignalSpec};
+use symphonia_core::checksum::{Crc32Le, Md5};
+use symphonia_core::codecs::{CodecDescriptor, CodecParameters, VerificationCheck};
+use symphonia_core::codecs::{Decoder, DecoderOptions, FinalizeResult, CODEC_TYPE_MONKEYS_AUDIO};
+use symphonia_core::errors::{decode_error, unsupported_error, Result};
+use symphonia_core::formats::Packet;
+use symphonia_core::io::{BufReader, Monitor, ReadBytes};
+use symphonia_core::support_codec;
+
+use log::{debug, log_enabled, warn};
+
+use super::entropy::{RangeDecoder, RiceState};
+use super::filter::FilterCascade;
+use super::header::DecoderConfig;
+use super::predictor::Predictor;
+
+/// The frame contains silence. For mono frames, either bit indicates silence.
+const APE_FRAMECODE_STEREO_SILENCE: u32 = 3;
+/// The frame contains a single channel that must be duplicated to form a stereo signal.
+const APE_FRAMECODE_PSEUDO_STEREO: u32 = 4;
+
+/// The length of the header prepended to each packet by the APE reader.
+const PACKET_HEADER_LEN: usize = 12;
+
+/// Monkey's Audio (APE) decoder.
+pub struct ApeDecoder {
+    params: CodecParameters,
+    config: DecoderConfig,
+    is_validating: bool,
+    md5: Option<Md5>,
+    max_blocks: usize,
+    bits_per_sample: u32,
+    /// The frame data converted from little-endian 32-bit words to a byte stream.
+    data: Vec<u8>,
+    filters: [FilterCascade; 2],
+    predictor: Predictor,
+    buf: AudioBuffer<i32>,
+}
+
+impl ApeDecoder {
+    fn decode_inner(&mut self, packet: &Packet) -> Result<()> {
+        let mut reader = BufReader::new(packet.buf());
+
+        let n_blocks = reader.read_u32()? as usize;
+        let skip = reader.read_u32()? as usize;
+        let len = reader.read_u32()? as usize;
+
+        if n_blocks > self.max_blocks {
+            return decode_error("ape: packet contains too many blocks");
+        }
+
+        if skip > 3 {
+            return decode_error("ape: invalid frame offset");
+        }
+
+        let frame = &packet.buf()[PACKET_HEADER_LEN..];
+
+        if skip + len > frame.len() {
+            return decode_error("ape: frame exceeds packet length");
+        }
+
+        // Feed the MD5 checksum the frame exactly as it is stored in the file.
+        if let Some(md5) = self.md5.as_mut() {
+            md5.process_buf_bytes(&frame[skip..skip + len]);
+        }
+
+        // The bitstream is a sequence of little-endian 32-bit words, convert it into a byte
+        // stream. An incomplete final word is padded with 0s.
+        self.data.clear();
+
+        for word in frame.chunks(4) {
+            let mut bytes = [0; 4];
+            bytes[..word.len()].copy_from_slice(word);
+            self.data.extend(bytes.iter().rev());
+        }
+
+        let mut bs = BufReader::new(&self.data[skip..]);
+
+        // Read the frame CRC, and the frame flags if present.
+        let mut crc = bs.read_be_u32()?;
+        let mut frame_flags = 0;
+
+        if crc & 0x8000_0000 != 0 {
+            crc &= !0x8000_0000;
+            frame_flags = bs.read_be_u32()?;
+        }
+
+        // The first byte of the range coded bitstream is ignored.
+        bs.ignore_bytes(1)?;
+
+        let n_channels = self.params.channels.unwrap().count();
+
+        let is_mono = n_channels == 1 || frame_flags & APE_FRAMECODE_PSEUDO_STEREO != 0;
+
+        let is_silent = if is_mono {
+            frame_flags & APE_FRAMECODE_STEREO_SILENCE != 0
+        }
+        else {
+            frame_flags & APE_FRAMECODE_STEREO_SILENCE == APE_FRAMECODE_STEREO_SILENCE
+        };
+
+        self.buf.clear();
+
+        if is_silent {
+            self.buf.render_silence(Some(n_blocks));
+        }
+        else {
+            self.buf.render_reserved(Some(n_blocks));
+
+            let pos = bs.pos() as usize;
+            let mut rc = RangeDecoder::new(&self.data[skip + pos..])?;
+
+            let version = self.config.version;
+
+            let mut rice_y = RiceState::default();
+            let mut rice_x = RiceState::default();
+
+            self.predictor.reset();
+
+            if is_mono {
+                let y = self.buf.chan_mut(0);
+
+                for sample in y.iter_mut() {
+                    *sample = if version >= 3990 {
+                        rc.decode_value_3990(&mut rice_y)?
+                    }
+                    else {
+                        rc.decode_value_3900(&mut rice_y)?
+                    };
+                }
+
+                self.filters[0].reset();
+                self.filters[0].apply(version, y);
+
+                self.predictor.decode_mono(y);
+
+                // Pseudo-stereo, duplicate the decoded channel.
+                if n_channels == 2 {
+                    let (left, right) = self.buf.chan_pair_mut(0, 1);
+                    right.copy_from_slice(left);
+                }
+            }
+            else {
+                let (y, x) = self.buf.chan_pair_mut(0, 1);
+
+                // The values of both channels are interleaved.
+                for (y, x) in y.iter_mut().zip(x.iter_mut()) {
+                    if version >= 3990 {
+                        *y = rc.decode_value_3990(&mut rice_y)?;
+                        *x = rc.decode_value_3990(&mut rice_x)?;
+                    }
+                    else {
+                        *y = rc.decode_value_3900(&mut rice_y)?;
+                        *x = rc.decode_value_3900(&mut rice_x)?;
+                    }
+                }
+
+                for filter in self.filters.iter_mut() {
+                    filter.reset();
+                }
+
+                self.filters[0].apply(version, y);
+                self.filters[1].apply(version, x);
+
+                self.predictor.decode_stereo(y, x);
+
+                // Decorrelate the channels. After decorrelation, Y becomes the left channel, and
+                // X becomes the right channel.
+                for (y, x) in y.iter_mut().zip(x.iter_mut()) {
+                    let left = x.wrapping_sub(*y / 2);
+                    let right = left.wrapping_add(*y);
+
+                    *y = left;
+                    *x = right;
+                }
+            }
+        }
+
+        // Verify the frame CRC. The CRC is calculated over the decoded samples as they would be
+        // stored in an interleaved WAVE file.
+        if !self.verify_crc(crc) {
+            return decode_error("ape: frame crc mismatch");
+        }
+
+        // The decoder uses a 32bit sample format as a common denominator, but that doesn't mean
+        // the encoded audio samples are actually 32bit. Shift all samples in the output buffer
+        // so that regardless the encoded bits/sample, the output is always 32bits/sample.
+        if self.bits_per_sample < 32 {
+            let shift = 32 - self.bits_per_sample;
+            self.buf.transform(|sample| sample << shift);
+        }
+
+        Ok(())
+    }
+
+    fn verify_crc(&self, expected: u32) -> bool {
+        let mut crc = Crc32Le::new(0xffff_ffff);
+
+        let n_channels = self.buf.spec().channels.count();
+        let n_bytes = self.bits_per_sample as usize / 8;
+
+        for i in 0..self.buf.frames() {
+            for ch in 0..n_channels {
+                let sample = self.buf.chan(ch)[i];
+
+                // 8-bit WAVE samples are unsigned.
+                let sample = if n_bytes == 1 { sample.wrapping_add(0x80) } else { sample };
+
+                crc.process_buf_bytes(&sample.to_le_bytes()[..n_bytes]);
+            }
+        }
+
+        !crc.crc() >> 1 == expected
+    }
+}
+
+impl Decoder for ApeDecoder {
+    fn try_new(params: &CodecParameters, options: &DecoderOptions) -> Result<Self> {
+        // This decoder only supports Monkey's Audio.
+        if params.codec != CODEC_TYPE_MONKEYS_AUDIO {
+            return unsupported_error("ape: invalid codec type");
+        }
+
+        // Obtain the extra data.
+        let config = match params.extra_data.as_ref() {
+            Some(buf) => DecoderConfig::read(buf)?,
+            _ => return unsupported_error("ape: missing extra data"),
+        };
+
+        if config.version < 3950 {
+            return unsupported_error("ape: file versions prior to 3.95 are not supported");
+        }
+
+        let sample_rate = match params.sample_rate {
+            Some(rate) => rate,
+            None => return unsupported_error("ape: sample rate is required"),
+        };
+
+        let channels = match params.channels {
+            Some(channels) if channels.count() == 1 || channels.count() == 2 => channels,
+            Some(_) => return unsupported_error("ape: only mono and stereo are supported"),
+            None => return unsupported_error("ape: channels are required"),
+        };
+
+        let bits_per_sample = match params.bits_per_sample {
+            Some(bps @ (8 | 16 | 24)) => bps,
+            Some(_) => return unsupported_error("ape: unsupported bits per sample"),
+            None => return unsupported_error("ape: bits per sample is required"),
+        };
+
+        let max_blocks = match params.max_frames_per_packet {
+            Some(max) if max <= 1 << 22 => max as usize,
+            Some(_) => return unsupported_error("ape: maximum frames per packet is too large"),
+            None => return unsupported_error("ape: maximum frames per packet is required"),
+        };
+
+        // The file MD5 checksum can only be verified if the data hashed before the first frame
+        // is known.
+        let md5 = match (&config.md5_prefix, params.verification_check) {
+            (Some(prefix), Some(VerificationCheck::Md5(_))) if options.verify => {
+                let mut md5 = Md5::default();
+                md5.process_buf_bytes(prefix);
+                Some(md5)
+            }
+            _ => None,
+        };
+
+        let spec = SignalSpec::new(sample_rate, channels);
+        let buf = AudioBuffer::new(max_blocks as u64, spec);
+
+        let filters = [
+            FilterCascade::new(config.compression_level),
+            FilterCascade::new(config.compression_level),
+        ];
+
+        Ok(ApeDecoder {
+            params: params.clone(),
+            config,
+            is_validating: options.verify,
+            md5,
+            max_blocks,
+            bits_per_sample,
+            data: Vec::new(),
+            filters,
+            predictor: Predictor::new(),
+            buf,
+        })
+    }
+
+    fn supported_codecs() -> &'static [CodecDescriptor] {
+        &[support_codec!(CODEC_TYPE_MONKEYS_AUDIO, "ape", "Monkey's Audio")]
+    }
+
+    fn reset(&mut self) {
+        // Every APE frame is decoded independently. However, the MD5 checksum is calculated over
+        // every frame in order, and can no longer be verified after a seek.
+        self.md5 = None;
+    }
+
+    fn codec_params(&self) -> &CodecParameters {
+        &self.params
+    }
+
+    fn decode(&mut self, packet: &Packet) -> Result<AudioBufferRef<'_>> {
+        if let Err(e) = self.decode_inner(packet) {
+            self.buf.clear();
+            Err(e)
+        }
+        else {
+            Ok(self.buf.as_audio_buffer_ref())
+        }
+    }
+
+    fn finalize(&mut self) -> FinalizeResult {
+        let mut result: FinalizeResult = Default::default();
+
+        // If verifying...
+        if self.is_validating {
+            // Try to get the expected MD5 checksum and compare it against the decoded checksum.
+            match (self.params.verification_check, self.md5.take()) {
+                (Some(VerificationCheck::Md5(expected)), Some(mut md5)) => {
+                    // Unlike other codecs, the MD5 checksum of an APE file is calculated over the
+                    // compressed audio frames, the stored WAVE header and trailer, and the APE
+                    // header and seek table.
+                    if let Some(suffix) = &self.config.md5_suffix {
+                        md5.process_buf_bytes(suffix);
+                    }
+
+                    let decoded = md5.md5();
+
+                    // Only generate the expected and decoded MD5 checksum strings if logging is
+                    // enabled at the debug level.
+                    if log_enabled!(log::Level::Debug) {
+                        use std::fmt::Write;
+
+                        let mut expected_s = String::with_capacity(32);
+                        let mut decoded_s = String::with_capacity(32);
+
+                        expected.iter().for_each(|b| write!(expected_s, "{:02x}", b).unwrap());
+                        decoded.iter().for_each(|b| write!(decoded_s, "{:02x}", b).unwrap());
+
+                        debug!("verification: expected md5 = {}", expected_s);
+                        debug!("verification: decoded md5  = {}", decoded_s);
+                    }
+
+                    result.verify_ok = Some(decoded == expected)
+                }
+                (Some(VerificationCheck::Md5(_)), None) => {
+                    warn!("verification requested but the md5 checksum could not be calculated");
+                }
+                _ => {
+                    warn!("verification requested but the expected md5 checksum was not provided");
+                }
+            }
+        }
+
+        result
+    }
+
+    fn last_decoded(&self) -> AudioBufferRef<'_> {
+        self.buf.as_audio_buffer_ref()
+    }
+}
@@ -0,0 +1,533 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::io::{Seek, SeekFrom};
+
+use symphonia_core::support_format;
+
+use symphonia_core::audio::Channels;
+use symphonia_core::codecs::{CodecParameters, VerificationCheck, CODEC_TYPE_MONKEYS_AUDIO};
+use symphonia_core::errors::{decode_error, end_of_stream_error, seek_error, unsupported_error};
+use symphonia_core::errors::{Result, SeekErrorKind};
+use symphonia_core::formats::prelude::*;
+use symphonia_core::io::*;
+use symphonia_core::meta::{GainInfo, Metadata, MetadataLog};
+use symphonia_core::probe::{Descriptor, Instantiate, QueryDescriptor};
+
+use symphonia_metadata::trailing::read_trailing_tags;
+
+use log::debug;
+
+use super::header::*;
+
+/// The APE start of stream marker: "MAC " in ASCII.
+const APE_STREAM_MARKER: [u8; 4] = *b"MAC ";
+
+/// The maximum number of audio blocks per APE frame that will be accepted.
+const APE_MAX_BLOCKS_PER_FRAME: u32 = 1 << 22;
+
+/// The maximum length of the stored WAVE header and trailer that will be retained for the purpose
+/// of verification.
+const APE_MAX_WAV_DATA_LEN: u32 = 1 << 20;
+
+/// The location of an APE frame within the stream.
+#[derive(Debug)]
+struct FrameInfo {
+    /// The absolute position of the first byte of the frame.
+    pos: u64,
+    /// The length of the frame in bytes, if known.
+    len: Option<u64>,
+    /// The number of bytes the start of the frame is offset from the 32-bit word it begins in.
+    skip: u32,
+    /// The timestamp of the first audio block of the frame.
+    ts: u64,
+    /// The number of audio blocks in the frame.
+    n_blocks: u32,
+}
+
+/// Monkey's Audio (APE) native format reader.
+///
+/// Each packet contains one complete APE frame. Since APE frames are not aligned to 32-bit
+/// words, but the bitstream is, each packet is prefixed with a 12 byte header containing the
+/// number of audio blocks in the frame, the number of bytes to skip from the start of the frame
+/// data to reach the first byte of the frame, and the length of the frame in bytes. All are stored
+/// as little-endian 32-bit integers. The frame data starts at the 32-bit word containing the first
+/// byte of the frame, and ends with the 32-bit word containing the last byte of the frame.
+pub struct ApeReader {
+    reader: MediaSourceStream,
+    tracks: Vec<Track>,
+    cues: Vec<Cue>,
+    metadata: MetadataLog,
+    frames: Vec<FrameInfo>,
+    next_frame: usize,
+}
+
+impl QueryDescriptor for ApeReader {
+    fn query() -> &'static [Descriptor] {
+        &[support_format!(
+            "ape",
+            "Monkey's Audio",
+            &["ape"],
+            &["audio/ape", "audio/x-ape", "audio/x-monkeys-audio"],
+            &[b"MAC "]
+        )]
+    }
+
+    fn score(_context: &[u8]) -> u8 {
+        255
+    }
+}
+
+impl FormatReader for ApeReader {
+    fn try_new(mut source: MediaSourceStream, _options: &FormatOptions) -> Result<Self> {
+        // All offsets in the seek table are relative to the start of the APE stream. There may be
+        // other data, such as an ID3v2 tag, before it.
+        let stream_start = source.pos();
+
+        let marker = source.read_quad_bytes()?;
+
+        if marker != APE_STREAM_MARKER {
+            return unsupported_error("ape: missing ape stream marker");
+        }
+
+        let header = ApeHeader::read(&mut source)?;
+
+        debug!("ape: {:?}", header);
+
+        if header.channels < 1 || header.channels > 2 {
+            return unsupported_error("ape: only mono and stereo streams are supported");
+        }
+
+        if header.sample_rate == 0 {
+            return decode_error("ape: invalid sample rate");
+        }
+
+        match header.bits_per_sample {
+            8 | 16 | 24 => (),
+            32 => return unsupported_error("ape: 32-bit streams are not supported"),
+            _ => return decode_error("ape: invalid bits per sample"),
+        }
+
+        if header.blocks_per_frame == 0 || header.blocks_per_frame > APE_MAX_BLOCKS_PER_FRAME {
+            return decode_error("ape: invalid number of blocks per frame");
+        }
+
+        if header.total_frames == 0 {
+            return decode_error("ape: no frames in stream");
+        }
+
+        if header.final_frame_blocks == 0 || header.final_frame_blocks > header.blocks_per_frame {
+            return decode_error("ape: invalid number of blocks in final frame");
+        }
+
+        if header.seek_table_len / 4 < header.total_frames {
+            return decode_error("ape: seek table is too small");
+        }
+
+        // For file versions prior to 3.98, the stored WAVE header precedes the seek table.
+        let mut wav_header = None;
+
+        if header.version < 3980 && header.has_wav_header() {
+            source.ignore_bytes(u64::from(header.wav_header_len))?;
+        }
+
+        // Read the seek table.
+        let seek_table = source.read_boxed_slice_exact(header.seek_table_len as usize)?;
+
+        // For file versions 3.98 and later, the stored WAVE header follows the seek table. Retain
+        // it since it is hashed by the MD5 checksum.
+        if header.version >= 3980 {
+            if header.wav_header_len <= APE_MAX_WAV_DATA_LEN {
+                wav_header = Some(source.read_boxed_slice_exact(header.wav_header_len as usize)?);
+            }
+            else {
+                source.ignore_bytes(u64::from(header.wav_header_len))?;
+            }
+        }
+
+        let first_frame_pos = source.pos();
+
+        // Read any trailing tags, and determine where the APE stream ends.
+        let mut metadata = MetadataLog::default();
+
+        let mut stream_end = match source.byte_len() {
+            Some(len) => Some(len),
+            None if source.is_seekable() => Some(source.seek(SeekFrom::End(0))?),
+            None => None,
+        };
+
+        if source.is_seekable() {
+            if let Some(end) = stream_end {
+                let tags = read_trailing_tags(&mut source, end)?;
+
+                for revision in tags.revisions {
+                    metadata.push(revision);
+                }
+
+                stream_end = Some(end - tags.len);
+            }
+        }
+
+        // The end of the compressed audio data. For file versions 3.98 and later, this is given
+        // by the descriptor. Otherwise, it is derived from the end of the stream.
+        let audio_end = match header.descriptor {
+            Some(ref descriptor) => Some(first_frame_pos + descriptor.audio_data_len),
+            None => stream_end.map(|end| end.saturating_sub(u64::from(header.wav_tail_len))),
+        };
+
+        // If the stored WAVE trailer is hashed by the MD5 checksum, try to retain it as well.
+        let wav_tail = match audio_end {
+            _ if header.wav_tail_len == 0 => Some(Box::default()),
+            Some(end) if source.is_seekable() && header.wav_tail_len <= APE_MAX_WAV_DATA_LEN => {
+                source.seek(SeekFrom::Start(end))?;
+                source.read_boxed_slice_exact(header.wav_tail_len as usize).ok()
+            }
+            _ => None,
+        };
+
+        // Build the frame index from the seek table.
+        let frames =
+            build_frame_index(&header, &seek_table, stream_start, first_frame_pos, audio_end)?;
+
+        // Return to the first frame.
+        if source.is_seekable() {
+            source.seek(SeekFrom::Start(first_frame_pos))?;
+        }
+
+        let channels = match header.channels {
+            1 => Channels::FRONT_LEFT,
+            _ => Channels::FRONT_LEFT | Channels::FRONT_RIGHT,
+        };
+
+        let n_frames = u64::from(header.blocks_per_frame) * u64::from(header.total_frames - 1)
+            + u64::from(header.final_frame_blocks);
+
+        // The data hashed by the MD5 checksum, in order, is: the stored WAVE header, the
+        // compressed audio frames, the stored WAVE trailer, the header, and the seek table.
+        // Everything but the compressed audio frames is passed to the decoder via. the extra data.
+        let md5 = match (&header.descriptor, &header.raw, wav_header, wav_tail) {
+            (Some(descriptor), Some(raw), Some(wav_header), Some(wav_tail))
+                if descriptor.md5 != [0; 16] =>
+            {
+                let mut suffix = Vec::with_capacity(wav_tail.len() + raw.len() + seek_table.len());
+                suffix.extend_from_slice(&wav_tail);
+                suffix.extend_from_slice(raw);
+                suffix.extend_from_slice(&seek_table);
+
+                Some((descriptor.md5, wav_header, suffix.into_boxed_slice()))
+            }
+            _ => None,
+        };
+
+        let mut config = DecoderConfig {
+            version: header.version,
+            compression_level: header.compression_level,
+            format_flags: header.format_flags,
+            md5_prefix: None,
+            md5_suffix: None,
+        };
+
+        let mut codec_params = CodecParameters::new();
+
+        if let Some((md5, prefix, suffix)) = md5 {
+            config.md5_prefix = Some(prefix);
+            config.md5_suffix = Some(suffix);
+            codec_params.with_verification_code(VerificationCheck::Md5(md5));
+        }
+
+        codec_params
+            .for_codec(CODEC_TYPE_MONKEYS_AUDIO)
+            .with_sample_rate(header.sample_rate)
+            .with_time_base(TimeBase::new(1, header.sample_rate))
+            .with_bits_per_sample(u32::from(header.bits_per_sample))
+            .with_channels(channels)
+            .with_n_frames(n_frames)
+            .with_max_frames_per_packet(u64::from(header.blocks_per_frame))
+            .with_extra_data(config.write());
+
+        let mut track = Track::new(0, codec_params);
+        track.gain = metadata.metadata().current().and_then(|rev| GainInfo::from_tags(rev.tags()));
+
+        Ok(ApeReader {
+            reader: source,
+            tracks: vec![track],
+            cues: Vec::new(),
+            metadata,
+            frames,
+            next_frame: 0,
+        })
+    }
+
+    fn next_packet(&mut self) -> Result<Packet> {
+        let frame = match self.frames.get(self.next_frame) {
+            Some(frame) => frame,
+            None => return end_of_stream_error(),
+        };
+
+        // Packets are read sequentially. Since the final 32-bit word of a frame is shared with
+        // the next frame, the stream will usually be positioned up-to 3 bytes past the start of
+        // the next frame. Otherwise, the reader was seeked.
+        let start = frame.pos - u64::from(frame.skip);
+        let pos = self.reader.pos();
+
+        if pos > start && pos - start < 4 {
+            self.reader.seek_buffered_rev((pos - start) as usize);
+        }
+        else if pos != start {
+            if self.reader.is_seekable() {
+                self.reader.seek(SeekFrom::Start(start))?;
+            }
+            else if pos < start {
+                self.reader.ignore_bytes(start - pos)?;
+            }
+            else {
+                return decode_error("ape: unable to rewind to start of frame");
+            }
+        }
+
+        let mut buf = Vec::new();
+
+        buf.extend_from_slice(&frame.n_blocks.to_le_bytes());
+        buf.extend_from_slice(&frame.skip.to_le_bytes());
+
+        match frame.len {
+            Some(len) => {
+                buf.extend_from_slice(&(len as u32).to_le_bytes());
+
+                // The bitstream is a sequence of 32-bit words. Read the frame up-to, and
+                // including, the word containing the last byte of the frame.
+                let data_len = (len + u64::from(frame.skip)) as usize;
+                let data_start = buf.len();
+
+                buf.resize(data_start + ((data_len + 3) & !3), 0);
+
+                self.reader.read_buf_exact(&mut buf[data_start..data_start + data_len])?;
+
+                // The final frame may not have a complete final word. In that case, the missing
+                // bytes remain zero.
+                let mut read = data_start + data_len;
+
+                while read < buf.len() {
+                    match self.reader.read_buf(&mut buf[read..])? {
+                        0 => break,
+                        n => read += n,
+                    }
+                }
+            }
+            None => {
+                // The length of the final frame is unknown, read until the end of the stream.
+                buf.extend_from_slice(&0u32.to_le_bytes());
+
+                let mut chunk = [0; 4096];
+
+                loop {
+                    match self.reader.read_buf(&mut chunk) {
+                        Ok(0) => break,
+                        Ok(len) => buf.extend_from_slice(&chunk[..len]),
+                        Err(ref err) if err.kind() == std::io::ErrorKind::UnexpectedEof => break,
+                        Err(err) => return Err(err.into()),
+                    }
+                }
+
+                // Amend the frame length.
+                let len = (buf.len() - 12).saturating_sub(frame.skip as usize) as u32;
+                buf[8..12].copy_from_slice(&len.to_le_bytes());
+            }
+        }
+
+        let packet = Packet::new_from_boxed_slice(
+            0,
+            frame.ts,
+            u64::from(frame.n_blocks),
+            buf.into_boxed_slice(),
+        );
+
+        self.next_frame += 1;
+
+        Ok(packet)
+    }
+
+    fn metadata(&mut self) -> Metadata<'_> {
+        self.metadata.metadata()
+    }
+
+    fn cues(&self) -> &[Cue] {
+        &self.cues
+    }
+
+    fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    fn seek(&mut self, _mode: SeekMode, to: SeekTo) -> Result<SeekedTo> {
+        let params = &self.tracks[0].codec_params;
+
+        let ts = match to {
+            // Frame timestamp given.
+            SeekTo::TimeStamp { ts, .. } => ts,
+            // Time value given, calculate frame timestamp from sample rate.
+            SeekTo::Time { time, .. } => {
+                // Use the sample rate to calculate the frame timestamp. If sample rate is not
+                // known, the seek cannot be completed.
+                if let Some(sample_rate) = params.sample_rate {
+                    TimeBase::new(1, sample_rate).calc_timestamp(time)
+                }
+                else {
+                    return seek_error(SeekErrorKind::Unseekable);
+                }
+            }
+        };
+
+        // If the total number of frames in the track is known, verify the desired frame timestamp
+        // does not exceed it.
+        if let Some(n_frames) = params.n_frames {
+            if ts > n_frames {
+                return seek_error(SeekErrorKind::OutOfRange);
+            }
+        }
+
+        debug!("seeking to frame_ts={}", ts);
+
+        // Every APE frame is independently decodable, therefore seek to the APE frame containing
+        // the desired timestamp.
+        let frame_idx = match self.frames.iter().rposition(|frame| frame.ts <= ts) {
+            Some(idx) => idx,
+            None => return seek_error(SeekErrorKind::OutOfRange),
+        };
+
+        let frame = &self.frames[frame_idx];
+
+        // If the reader does not support seeking, seeks can only be emulated in the forward
+        // direction by reading and discarding packets.
+        if !self.reader.is_seekable() {
+            let current_pos = self.reader.pos();
+
+            if frame.pos - u64::from(frame.skip) < current_pos && frame_idx < self.next_frame {
+                return seek_error(SeekErrorKind::ForwardOnly);
+            }
+        }
+
+        let actual_ts = frame.ts;
+
+        self.next_frame = frame_idx;
+
+        debug!("seeked to packet_ts={} (delta={})", actual_ts, actual_ts as i64 - ts as i64);
+
+        Ok(SeekedTo { track_id: 0, actual_ts, required_ts: ts })
+    }
+
+    fn into_inner(self: Box<Self>) -> MediaSourceStream {
+        self.reader
+    }
+}
+
+/// Builds the frame index from the seek table.
+fn build_frame_index(
+    header: &ApeHeader,
+    seek_table: &[u8],
+    stream_start: u64,
+    first_frame_pos: u64,
+    audio_end: Option<u64>,
+) -> Result<Vec<FrameInfo>> {
+    let n_frames = header.total_frames as usize;
+
+    let mut reader = BufReader::new(seek_table);
+    let mut frames: Vec<FrameInfo> = Vec::with_capacity(n_frames);
+
+    for i in 0..n_frames {
+        let offset = u64::from(reader.read_u32()?);
+
+        // The position of the first frame is always known, regardless of the first entry in the
+        // seek table.
+        let pos = if i == 0 { first_frame_pos } else { stream_start + offset };
+
+        if let Some(prev) = frames.last_mut() {
+            if pos < prev.pos {
+                return decode_error("ape: seek table is not monotonic");
+            }
+
+            prev.len = Some(pos - prev.pos);
+        }
+
+        let n_blocks =
+            if i == n_frames - 1 { header.final_frame_blocks } else { header.blocks_per_frame };
+
+        frames.push(FrameInfo {
+            pos,
+            len: None,
+            // Frames are aligned to 32-bit words relative to the first frame.
+            skip: ((pos - first_frame_pos) & 0x3) as u32,
+            ts: i as u64 * u64::from(header.blocks_per_frame),
+            n_blocks,
+        });
+    }
+
+    // The final frame extends to the end of the compressed audio data, if known.
+    if let Some(last) = frames.last_mut() {
+        last.len = match audio_end {
+            Some(end) if end > last.pos => Some(end - last.pos),
+            Some(_) => return decode_error("ape: final frame is out-of-bounds"),
+            None => None,
+        };
+    }
+
+    Ok(frames)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use symphonia_core::errors::Error;
+    use symphonia_core::formats::{FormatOptions, FormatReader};
+    use symphonia_core::io::MediaSourceStream;
+
+    use super::ApeReader;
+
+    /// Build a version 3.99 APE stream with a single frame of 4 bytes.
+    fn stream(bits_per_sample: u16) -> Vec<u8> {
+        let mut buf = b"MAC ".to_vec();
+        buf.extend_from_slice(&3990u16.to_le_bytes());
+
+        // Descriptor.
+        buf.extend_from_slice(&0u16.to_le_bytes());
+        for len in &[52u32, 24, 4, 0, 4, 0, 0] {
+            buf.extend_from_slice(&len.to_le_bytes());
+        }
+        buf.extend_from_slice(&[0; 16]);
+
+        // Header.
+        buf.extend_from_slice(&2000u16.to_le_bytes());
+        buf.extend_from_slice(&0u16.to_le_bytes());
+        buf.extend_from_slice(&73728u32.to_le_bytes());
+        buf.extend_from_slice(&1u32.to_le_bytes());
+        buf.extend_from_slice(&1u32.to_le_bytes());
+        buf.extend_from_slice(&bits_per_sample.to_le_bytes());
+        buf.extend_from_slice(&2u16.to_le_bytes());
+        buf.extend_from_slice(&44100u32.to_le_bytes());
+
+        // Seek table.
+        buf.extend_from_slice(&(buf.len() as u32 + 4).to_le_bytes());
+
+        // Frame.
+        buf.extend_from_slice(&[0; 4]);
+        buf
+    }
+
+    fn open(buf: Vec<u8>) -> Result<ApeReader, Error> {
+        let mss = MediaSourceStream::new(Box::new(Cursor::new(buf)), Default::default());
+        ApeReader::try_new(mss, &FormatOptions::default())
+    }
+
+    #[test]
+    fn verify_bits_per_sample() {
+        assert!(open(stream(16)).is_ok());
+        // The decoder does not support 32-bit streams, so neither must the reader.
+        assert!(matches!(open(stream(32)), Err(Error::Unsupported(_))));
+        assert!(matches!(open(stream(12)), Err(Error::DecodeError(_))));
+    }
+}
@@ -0,0 +1,255 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::errors::{decode_error, Result};
+
+/// The number of extra bits in the first byte of the range coded bitstream.
+const EXTRA_BITS: u32 = 7;
+
+/// The minimum range before normalization is required.
+const BOTTOM_VALUE: u32 = (1 << 31) >> 8;
+
+/// The symbol that signals an escaped overflow value.
+const MODEL_ELEMENTS: u32 = 64;
+
+/// Cumulative symbol frequencies used by file versions 3.95 through 3.97.
+const COUNTS_3970: [u32; 22] = [
+    0, 14824, 28224, 39348, 47855, 53994, 58171, 60926, 62682, 63786, 64463, 64878, 65126, 65276,
+    65365, 65419, 65450, 65469, 65480, 65487, 65491, 65493,
+];
+
+/// Cumulative symbol frequencies used by file versions 3.98 and later.
+const COUNTS_3980: [u32; 22] = [
+    0, 19578, 36160, 48417, 56323, 60899, 63265, 64435, 64971, 65232, 65351, 65416, 65447, 65466,
+    65476, 65482, 65485, 65488, 65490, 65491, 65492, 65493,
+];
+
+/// The adaptive Rice parameter state of one channel.
+#[derive(Copy, Clone)]
+pub struct RiceState {
+    k: u32,
+    ksum: u32,
+}
+
+impl Default for RiceState {
+    fn default() -> Self {
+        RiceState { k: 10, ksum: (1 << 10) * 16 }
+    }
+}
+
+impl RiceState {
+    fn update(&mut self, x: u32) {
+        let lim = if self.k > 0 { 1 << (self.k + 4) } else { 0 };
+
+        self.ksum = self
+            .ksum
+            .wrapping_add((x.wrapping_add(1) / 2).wrapping_sub(self.ksum.wrapping_add(16) >> 5));
+
+        if self.ksum < lim {
+            self.k -= 1;
+        }
+        else if self.k < 24 && self.ksum >= 1 << (self.k + 5) {
+            self.k += 1;
+        }
+    }
+}
+
+/// A range decoder for the APE entropy coded bitstream.
+///
+/// The bitstream is read from a buffer that has already been converted from little-endian 32-bit
+/// words into a sequence of bytes.
+pub struct RangeDecoder<'a> {
+    buf: &'a [u8],
+    pos: usize,
+    low: u32,
+    range: u32,
+    help: u32,
+    buffer: u32,
+}
+
+impl<'a> RangeDecoder<'a> {
+    /// Instantiate a new range decoder starting at the first byte of the buffer.
+    pub fn new(buf: &'a [u8]) -> Result<Self> {
+        let mut rc = RangeDecoder { buf, pos: 0, low: 0, range: 0, help: 0, buffer: 0 };
+
+        rc.buffer = u32::from(rc.next_byte()?);
+        rc.low = rc.buffer >> (8 - EXTRA_BITS);
+        rc.range = 1 << EXTRA_BITS;
+
+        Ok(rc)
+    }
+
+    fn next_byte(&mut self) -> Result<u8> {
+        match self.buf.get(self.pos) {
+            Some(&byte) => {
+                self.pos += 1;
+                Ok(byte)
+            }
+            None => decode_error("ape: range decoder overrun"),
+        }
+    }
+
+    #[inline(always)]
+    fn normalize(&mut self) -> Result<()> {
+        while self.range <= BOTTOM_VALUE {
+            self.buffer = (self.buffer << 8) | u32::from(self.next_byte()?);
+            self.low = (self.low << 8) | ((self.buffer >> 1) & 0xff);
+            self.range <<= 8;
+        }
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn decode_culfreq(&mut self, tot_f: u32) -> Result<u32> {
+        self.normalize()?;
+        self.help = self.range / tot_f;
+
+        if self.help == 0 {
+            return decode_error("ape: invalid range");
+        }
+
+        Ok(self.low / self.help)
+    }
+
+    #[inline(always)]
+    fn decode_culshift(&mut self, shift: u32) -> Result<u32> {
+        self.normalize()?;
+        self.help = self.range >> shift;
+
+        if self.help == 0 {
+            return decode_error("ape: invalid range");
+        }
+
+        Ok(self.low / self.help)
+    }
+
+    #[inline(always)]
+    fn update(&mut self, sy_f: u32, lt_f: u32) {
+        self.low = self.low.wrapping_sub(self.help.wrapping_mul(lt_f));
+        self.range = self.help.wrapping_mul(sy_f);
+    }
+
+    #[inline(always)]
+    fn decode_bits(&mut self, n: u32) -> Result<u32> {
+        let sym = self.decode_culshift(n)?;
+        self.update(1, sym);
+        Ok(sym)
+    }
+
+    fn decode_symbol(&mut self, counts: &[u32; 22]) -> Result<u32> {
+        let cf = self.decode_culshift(16)?;
+
+        if cf > 65492 {
+            if cf > 65535 {
+                return decode_error("ape: invalid symbol");
+            }
+
+            self.update(1, cf);
+            return Ok(cf - 65535 + 63);
+        }
+
+        // The number of symbols is small, and lower symbols are much more likely, so a linear
+        // search is sufficient.
+        let symbol = counts[1..].iter().position(|&count| count > cf).unwrap_or(20);
+
+        self.update(counts[symbol + 1] - counts[symbol], counts[symbol]);
+
+        Ok(symbol as u32)
+    }
+
+    /// Decode a value using the entropy coding scheme of file versions 3.95 through 3.98.
+    pub fn decode_value_3900(&mut self, rice: &mut RiceState) -> Result<i32> {
+        let mut overflow = self.decode_symbol(&COUNTS_3970)?;
+
+        let k = if overflow == MODEL_ELEMENTS - 1 {
+            overflow = 0;
+            self.decode_bits(5)?
+        }
+        else {
+            rice.k.saturating_sub(1)
+        };
+
+        let x = if k <= 16 {
+            self.decode_bits(k)?
+        }
+        else if k <= 31 {
+            let lo = self.decode_bits(16)?;
+            let hi = self.decode_bits(k - 16)?;
+            lo | (hi << 16)
+        }
+        else {
+            return decode_error("ape: invalid rice parameter");
+        };
+
+        let x = x.wrapping_add(overflow << k);
+
+        rice.update(x);
+
+        Ok(to_signed(x))
+    }
+
+    /// Decode a value using the entropy coding scheme of file versions 3.99 and later.
+    pub fn decode_value_3990(&mut self, rice: &mut RiceState) -> Result<i32> {
+        let pivot = (rice.ksum >> 5).max(1);
+
+        let mut overflow = self.decode_symbol(&COUNTS_3980)?;
+
+        if overflow == MODEL_ELEMENTS - 1 {
+            overflow = self.decode_bits(16)? << 16;
+            overflow |= self.decode_bits(16)?;
+        }
+
+        let base = if pivot < 0x10000 {
+            let base = self.decode_culfreq(pivot)?;
+            self.update(1, base);
+            base
+        }
+        else {
+            let mut base_hi = pivot;
+            let mut bbits = 0;
+
+            while base_hi & !0xffff != 0 {
+                base_hi >>= 1;
+                bbits += 1;
+            }
+
+            let hi = self.decode_culfreq(base_hi + 1)?;
+            self.update(1, hi);
+
+            let lo = self.decode_culfreq(1 << bbits)?;
+            self.update(1, lo);
+
+            (hi << bbits).wrapping_add(lo)
+        };
+
+        let x = base.wrapping_add(overflow.wrapping_mul(pivot));
+
+        rice.update(x);
+
+        Ok(to_signed(x))
+    }
+}
+
+/// Converts an unsigned, interleaved, value to a signed value.
+#[inline(always)]
+fn to_signed(x: u32) -> i32 {
+    ((x >> 1) ^ (x & 1).wrapping_sub(1)).wrapping_add(1) as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::to_signed;
+
+    #[test]
+    fn verify_to_signed() {
+        assert_eq!(to_signed(0), 0);
+        assert_eq!(to_signed(1), 1);
+        assert_eq!(to_signed(2), -1);
+        assert_eq!(to_signed(3), 2);
+        assert_eq!(to_signed(4), -2);
+    }
+}
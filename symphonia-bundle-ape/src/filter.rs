@@ -0,0 +1,170 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use super::header::CompressionLevel;
+
+/// The number of samples of history kept before the history window is moved back to the start of
+/// the history buffer.
+const HISTORY_SIZE: usize = 512;
+
+/// The maximum number of cascaded filters.
+const MAX_FILTERS: usize = 3;
+
+/// Gets the order and fractional bits of each filter in the cascade for a compression level. The
+/// filters are applied in the order they are returned, and the list ends at the first filter
+/// with an order of 0.
+fn filter_params(level: CompressionLevel) -> [(usize, u32); MAX_FILTERS] {
+    match level {
+        CompressionLevel::Fast => [(0, 0), (0, 0), (0, 0)],
+        CompressionLevel::Normal => [(16, 11), (0, 0), (0, 0)],
+        CompressionLevel::High => [(64, 11), (0, 0), (0, 0)],
+        CompressionLevel::ExtraHigh => [(32, 10), (256, 13), (0, 0)],
+        CompressionLevel::Insane => [(16, 11), (256, 13), (1280, 15)],
+    }
+}
+
+/// Returns the negated sign of `x`.
+#[inline(always)]
+fn ape_sign(x: i32) -> i32 {
+    (x < 0) as i32 - (x > 0) as i32
+}
+
+/// An adaptive sign-LMS (normalized neural network in the Monkey's Audio parlance) filter.
+struct NnFilter {
+    order: usize,
+    fracbits: u32,
+    coeffs: Vec<i16>,
+    /// The history of past (clipped) outputs.
+    delay: Vec<i16>,
+    /// The history of past adaption values.
+    adapt: Vec<i16>,
+    /// The index of the next entry in the delay and adapt histories.
+    pos: usize,
+    /// The running average of the absolute output.
+    avg: u32,
+}
+
+impl NnFilter {
+    fn new(order: usize, fracbits: u32) -> Self {
+        NnFilter {
+            order,
+            fracbits,
+            coeffs: vec![0; order],
+            delay: vec![0; HISTORY_SIZE + order],
+            adapt: vec![0; HISTORY_SIZE + order],
+            pos: order,
+            avg: 0,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.coeffs.iter_mut().for_each(|c| *c = 0);
+        self.delay[..self.order].iter_mut().for_each(|d| *d = 0);
+        self.adapt[..self.order].iter_mut().for_each(|a| *a = 0);
+        self.pos = self.order;
+        self.avg = 0;
+    }
+
+    fn apply(&mut self, version: u16, data: &mut [i32]) {
+        let order = self.order;
+        let round = 1i64 << (self.fracbits - 1);
+
+        for sample in data.iter_mut() {
+            let input = *sample;
+
+            let delay = &self.delay[self.pos - order..self.pos];
+            let adapt = &self.adapt[self.pos - order..self.pos];
+
+            // Compute the dot product of the coefficients and the delay history, and adapt the
+            // coefficients for the next sample in the same pass.
+            let sign = ape_sign(input);
+
+            let mut dot = 0i32;
+
+            for ((c, &d), &a) in self.coeffs.iter_mut().zip(delay).zip(adapt) {
+                dot = dot.wrapping_add(i32::from(*c) * i32::from(d));
+                *c = c.wrapping_add((sign * i32::from(a)) as i16);
+            }
+
+            let res = ((i64::from(dot) + round) >> self.fracbits) as i32;
+            let res = res.wrapping_add(input);
+
+            *sample = res;
+
+            // Update the output history.
+            self.delay[self.pos] = res.clamp(i32::from(i16::MIN), i32::from(i16::MAX)) as i16;
+
+            // Update the adaption history.
+            if version < 3980 {
+                self.adapt[self.pos] = if res == 0 { 0 } else { (((res >> 28) & 8) - 4) as i16 };
+                self.adapt[self.pos - 4] >>= 1;
+                self.adapt[self.pos - 8] >>= 1;
+            }
+            else {
+                let abs_res = res.unsigned_abs();
+
+                self.adapt[self.pos] = if abs_res != 0 {
+                    let avg = u64::from(self.avg);
+                    let abs = u64::from(abs_res);
+
+                    let shift = (abs > avg * 3) as u32
+                        + (abs_res > self.avg.wrapping_add(self.avg / 3)) as u32;
+
+                    (ape_sign(res) * (8 << shift)) as i16
+                }
+                else {
+                    0
+                };
+
+                self.avg =
+                    self.avg.wrapping_add((abs_res.wrapping_sub(self.avg) as i32 / 16) as u32);
+
+                self.adapt[self.pos - 1] >>= 1;
+                self.adapt[self.pos - 2] >>= 1;
+                self.adapt[self.pos - 8] >>= 1;
+            }
+
+            self.pos += 1;
+
+            // If the history buffers are full, move the history window back to the start.
+            if self.pos == self.delay.len() {
+                self.delay.copy_within(self.pos - order.., 0);
+                self.adapt.copy_within(self.pos - order.., 0);
+                self.pos = order;
+            }
+        }
+    }
+}
+
+/// The cascade of adaptive filters applied to one channel.
+pub struct FilterCascade {
+    filters: Vec<NnFilter>,
+}
+
+impl FilterCascade {
+    pub fn new(level: CompressionLevel) -> Self {
+        let filters = filter_params(level)
+            .iter()
+            .take_while(|(order, _)| *order > 0)
+            .map(|&(order, fracbits)| NnFilter::new(order, fracbits))
+            .collect();
+
+        FilterCascade { filters }
+    }
+
+    /// Resets the state of all filters. Must be called at the start of every APE frame.
+    pub fn reset(&mut self) {
+        self.filters.iter_mut().for_each(|filter| filter.reset());
+    }
+
+    /// Applies all filters, in order, to the data.
+    pub fn apply(&mut self, version: u16, data: &mut [i32]) {
+        for filter in self.filters.iter_mut() {
+            filter.apply(version, data);
+        }
+    }
+}
@@ -0,0 +1,352 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::errors::{decode_error, unsupported_error, Result};
+use symphonia_core::io::{BufReader, FiniteStream, ReadBytes};
+
+/// The oldest file version supported.
+pub const APE_MIN_VERSION: u16 = 3950;
+/// The newest file version supported.
+pub const APE_MAX_VERSION: u16 = 3990;
+
+/// The samples are 8-bit (obsolete, only used by file versions < 3.98).
+pub const MAC_FORMAT_FLAG_8_BIT: u16 = 1 << 0;
+/// A 32-bit peak level follows the header (obsolete, only used by file versions < 3.98).
+pub const MAC_FORMAT_FLAG_HAS_PEAK_LEVEL: u16 = 1 << 2;
+/// The samples are 24-bit (obsolete, only used by file versions < 3.98).
+pub const MAC_FORMAT_FLAG_24_BIT: u16 = 1 << 3;
+/// The number of seek table elements follows the peak level.
+pub const MAC_FORMAT_FLAG_HAS_SEEK_ELEMENTS: u16 = 1 << 4;
+/// The WAVE header is not stored and should be created on decompression.
+pub const MAC_FORMAT_FLAG_CREATE_WAV_HEADER: u16 = 1 << 5;
+
+/// The size of the descriptor introduced in file version 3.98.
+const APE_DESCRIPTOR_MIN_SIZE: u32 = 52;
+
+/// The size of the header used by file versions 3.98 and later.
+pub const APE_HEADER_SIZE: usize = 24;
+
+/// The compression level an APE stream was encoded with.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CompressionLevel {
+    Fast,
+    Normal,
+    High,
+    ExtraHigh,
+    Insane,
+}
+
+impl CompressionLevel {
+    pub fn from_u16(level: u16) -> Result<CompressionLevel> {
+        let level = match level {
+            1000 => CompressionLevel::Fast,
+            2000 => CompressionLevel::Normal,
+            3000 => CompressionLevel::High,
+            4000 => CompressionLevel::ExtraHigh,
+            5000 => CompressionLevel::Insane,
+            _ => return decode_error("ape: invalid compression level"),
+        };
+
+        Ok(level)
+    }
+
+    pub fn as_u16(&self) -> u16 {
+        match self {
+            CompressionLevel::Fast => 1000,
+            CompressionLevel::Normal => 2000,
+            CompressionLevel::High => 3000,
+            CompressionLevel::ExtraHigh => 4000,
+            CompressionLevel::Insane => 5000,
+        }
+    }
+}
+
+/// The file descriptor of file versions 3.98 and later.
+#[derive(Debug, Default)]
+pub struct ApeDescriptor {
+    /// The length of the header in bytes.
+    pub header_len: u32,
+    /// The length of the seek table in bytes.
+    pub seek_table_len: u32,
+    /// The length of the stored WAVE header in bytes.
+    pub wav_header_len: u32,
+    /// The length of the compressed audio data in bytes.
+    pub audio_data_len: u64,
+    /// The length of the stored WAVE trailer in bytes.
+    pub wav_tail_len: u32,
+    /// The MD5 checksum of the file.
+    pub md5: [u8; 16],
+}
+
+impl ApeDescriptor {
+    fn read<B: ReadBytes>(reader: &mut B) -> Result<ApeDescriptor> {
+        let _padding = reader.read_u16()?;
+
+        let descriptor_len = reader.read_u32()?;
+        let header_len = reader.read_u32()?;
+        let seek_table_len = reader.read_u32()?;
+        let wav_header_len = reader.read_u32()?;
+        let audio_data_len = u64::from(reader.read_u32()?);
+        let audio_data_len_high = u64::from(reader.read_u32()?);
+        let wav_tail_len = reader.read_u32()?;
+
+        let mut md5 = [0; 16];
+        reader.read_buf_exact(&mut md5)?;
+
+        if descriptor_len < APE_DESCRIPTOR_MIN_SIZE {
+            return decode_error("ape: invalid descriptor length");
+        }
+
+        // Skip any unknown fields at the end of the descriptor for forward compatibility.
+        reader.ignore_bytes(u64::from(descriptor_len - APE_DESCRIPTOR_MIN_SIZE))?;
+
+        Ok(ApeDescriptor {
+            header_len,
+            seek_table_len,
+            wav_header_len,
+            audio_data_len: (audio_data_len_high << 32) | audio_data_len,
+            wav_tail_len,
+            md5,
+        })
+    }
+}
+
+/// The APE file header. For file versions prior to 3.98, fields not stored in the header are
+/// derived from the file version and format flags.
+#[derive(Debug)]
+pub struct ApeHeader {
+    /// The file version multiplied by 1000 (e.g., 3990 for version 3.99).
+    pub version: u16,
+    /// The compression level.
+    pub compression_level: CompressionLevel,
+    /// The format flags.
+    pub format_flags: u16,
+    /// The number of audio blocks (frames in Symphonia terms) per APE frame.
+    pub blocks_per_frame: u32,
+    /// The number of audio blocks in the final APE frame.
+    pub final_frame_blocks: u32,
+    /// The total number of APE frames.
+    pub total_frames: u32,
+    /// The number of bits per sample.
+    pub bits_per_sample: u16,
+    /// The number of channels.
+    pub channels: u16,
+    /// The sample rate in Hz.
+    pub sample_rate: u32,
+    /// The raw header as hashed by the MD5 checksum. Only available for file versions 3.98 and
+    /// later.
+    pub raw: Option<Box<[u8]>>,
+    /// The file descriptor. Only available for file versions 3.98 and later.
+    pub descriptor: Option<ApeDescriptor>,
+    /// The length of the seek table in bytes.
+    pub seek_table_len: u32,
+    /// The length of the stored WAVE header in bytes.
+    pub wav_header_len: u32,
+    /// The length of the stored WAVE trailer in bytes.
+    pub wav_tail_len: u32,
+}
+
+impl ApeHeader {
+    /// Reads the descriptor (if present) and header of an APE file. The "MAC " marker must have
+    /// already been consumed.
+    pub fn read<B: ReadBytes>(reader: &mut B) -> Result<ApeHeader> {
+        let version = reader.read_u16()?;
+
+        if version < APE_MIN_VERSION {
+            return unsupported_error("ape: file versions prior to 3.95 are not supported");
+        }
+
+        if version > APE_MAX_VERSION {
+            return unsupported_error("ape: unknown file version");
+        }
+
+        let header = if version >= 3980 {
+            let descriptor = ApeDescriptor::read(reader)?;
+
+            if (descriptor.header_len as usize) < APE_HEADER_SIZE {
+                return decode_error("ape: invalid header length");
+            }
+
+            // Read the header as-is since it is a part of the MD5 checksum. Any trailing unknown
+            // fields are skipped.
+            let raw = reader.read_boxed_slice_exact(APE_HEADER_SIZE)?;
+            reader.ignore_bytes(u64::from(descriptor.header_len) - APE_HEADER_SIZE as u64)?;
+
+            let mut hdr = BufReader::new(&raw);
+
+            let compression_level = CompressionLevel::from_u16(hdr.read_u16()?)?;
+            let format_flags = hdr.read_u16()?;
+            let blocks_per_frame = hdr.read_u32()?;
+            let final_frame_blocks = hdr.read_u32()?;
+            let total_frames = hdr.read_u32()?;
+            let bits_per_sample = hdr.read_u16()?;
+            let channels = hdr.read_u16()?;
+            let sample_rate = hdr.read_u32()?;
+
+            ApeHeader {
+                version,
+                compression_level,
+                format_flags,
+                blocks_per_frame,
+                final_frame_blocks,
+                total_frames,
+                bits_per_sample,
+                channels,
+                sample_rate,
+                raw: Some(raw),
+                seek_table_len: descriptor.seek_table_len,
+                wav_header_len: descriptor.wav_header_len,
+                wav_tail_len: descriptor.wav_tail_len,
+                descriptor: Some(descriptor),
+            }
+        }
+        else {
+            let compression_level = CompressionLevel::from_u16(reader.read_u16()?)?;
+            let format_flags = reader.read_u16()?;
+            let channels = reader.read_u16()?;
+            let sample_rate = reader.read_u32()?;
+            let wav_header_len = reader.read_u32()?;
+            let wav_tail_len = reader.read_u32()?;
+            let total_frames = reader.read_u32()?;
+            let final_frame_blocks = reader.read_u32()?;
+
+            if format_flags & MAC_FORMAT_FLAG_HAS_PEAK_LEVEL != 0 {
+                // Ignore the peak level.
+                reader.ignore_bytes(4)?;
+            }
+
+            let seek_table_len = if format_flags & MAC_FORMAT_FLAG_HAS_SEEK_ELEMENTS != 0 {
+                reader.read_u32()?.saturating_mul(4)
+            }
+            else {
+                total_frames.saturating_mul(4)
+            };
+
+            let bits_per_sample = if format_flags & MAC_FORMAT_FLAG_8_BIT != 0 {
+                8
+            }
+            else if format_flags & MAC_FORMAT_FLAG_24_BIT != 0 {
+                24
+            }
+            else {
+                16
+            };
+
+            ApeHeader {
+                version,
+                compression_level,
+                format_flags,
+                // All supported file versions prior to 3.98 use the same frame size.
+                blocks_per_frame: 73728 * 4,
+                final_frame_blocks,
+                total_frames,
+                bits_per_sample,
+                channels,
+                sample_rate,
+                raw: None,
+                descriptor: None,
+                seek_table_len,
+                wav_header_len,
+                wav_tail_len,
+            }
+        };
+
+        Ok(header)
+    }
+
+    /// Returns true if the WAVE header is stored in the file.
+    pub fn has_wav_header(&self) -> bool {
+        self.format_flags & MAC_FORMAT_FLAG_CREATE_WAV_HEADER == 0
+    }
+}
+
+/// The configuration of the APE decoder as stored in the codec extra data.
+///
+/// The extra data starts with the same 6 bytes used by other implementations: the file version,
+/// compression level, and format flags as little-endian 16-bit integers. Optionally, this is
+/// followed by two length-prefixed blobs containing the data that is hashed by the file MD5
+/// checksum before, and after, the compressed audio frames.
+pub struct DecoderConfig {
+    pub version: u16,
+    pub compression_level: CompressionLevel,
+    pub format_flags: u16,
+    pub md5_prefix: Option<Box<[u8]>>,
+    pub md5_suffix: Option<Box<[u8]>>,
+}
+
+impl DecoderConfig {
+    pub fn read(buf: &[u8]) -> Result<DecoderConfig> {
+        if buf.len() < 6 {
+            return decode_error("ape: invalid extra data size");
+        }
+
+        let mut reader = BufReader::new(buf);
+
+        let version = reader.read_u16()?;
+        let compression_level = CompressionLevel::from_u16(reader.read_u16()?)?;
+        let format_flags = reader.read_u16()?;
+
+        let (md5_prefix, md5_suffix) = if reader.bytes_available() > 0 {
+            let prefix_len = reader.read_u32()? as usize;
+            let prefix = reader.read_boxed_slice_exact(prefix_len)?;
+            let suffix_len = reader.read_u32()? as usize;
+            let suffix = reader.read_boxed_slice_exact(suffix_len)?;
+            (Some(prefix), Some(suffix))
+        }
+        else {
+            (None, None)
+        };
+
+        Ok(DecoderConfig { version, compression_level, format_flags, md5_prefix, md5_suffix })
+    }
+
+    pub fn write(&self) -> Box<[u8]> {
+        let mut buf = Vec::with_capacity(6);
+
+        buf.extend_from_slice(&self.version.to_le_bytes());
+        buf.extend_from_slice(&self.compression_level.as_u16().to_le_bytes());
+        buf.extend_from_slice(&self.format_flags.to_le_bytes());
+
+        if let (Some(prefix), Some(suffix)) = (&self.md5_prefix, &self.md5_suffix) {
+            buf.extend_from_slice(&(prefix.len() as u32).to_le_bytes());
+            buf.extend_from_slice(prefix);
+            buf.extend_from_slice(&(suffix.len() as u32).to_le_bytes());
+            buf.extend_from_slice(suffix);
+        }
+
+        buf.into_boxed_slice()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CompressionLevel, DecoderConfig};
+
+    #[test]
+    fn verify_decoder_config_round_trip() {
+        let config = DecoderConfig {
+            version: 3990,
+            compression_level: CompressionLevel::ExtraHigh,
+            format_flags: 0,
+            md5_prefix: Some(vec![1, 2, 3].into_boxed_slice()),
+            md5_suffix: Some(vec![4, 5].into_boxed_slice()),
+        };
+
+        let buf = config.write();
+        assert_eq!(&buf[..6], &[0x96, 0x0f, 0xa0, 0x0f, 0x00, 0x00]);
+
+        let read = DecoderConfig::read(&buf).unwrap();
+        assert_eq!(read.version, 3990);
+        assert_eq!(read.compression_level, CompressionLevel::ExtraHigh);
+        assert_eq!(read.md5_prefix.as_deref(), Some(&[1u8, 2, 3][..]));
+        assert_eq!(read.md5_suffix.as_deref(), Some(&[4u8, 5][..]));
+
+        // Extra data without the verification blobs, as written by other implementations.
+        let read = DecoderConfig::read(&buf[..6]).unwrap();
+        assert!(read.md5_prefix.is_none() && read.md5_suffix.is_none());
+    }
+}
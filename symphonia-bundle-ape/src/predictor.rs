@@ -0,0 +1,201 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+/// The number of samples of history kept before the history window is moved back to the start of
+/// the history buffer.
+const HISTORY_SIZE: usize = 512;
+
+/// The size of the history window.
+const PREDICTOR_SIZE: usize = 50;
+
+// Offsets into the history window for each stage of the predictor.
+const Y_DELAY_A: usize = 50;
+const Y_DELAY_B: usize = 42;
+const X_DELAY_A: usize = 34;
+const X_DELAY_B: usize = 26;
+const Y_ADAPT_COEFFS_A: usize = 18;
+const X_ADAPT_COEFFS_A: usize = 14;
+const Y_ADAPT_COEFFS_B: usize = 10;
+const X_ADAPT_COEFFS_B: usize = 5;
+
+/// The initial stage A coefficients.
+const INITIAL_COEFFS_A: [i32; 4] = [360, 317, -109, 98];
+
+/// Returns the negated sign of `x`.
+#[inline(always)]
+fn ape_sign(x: i32) -> i32 {
+    (x < 0) as i32 - (x > 0) as i32
+}
+
+/// The offsets into the history window used by one channel.
+struct Offsets {
+    delay_a: usize,
+    delay_b: usize,
+    adapt_a: usize,
+    adapt_b: usize,
+}
+
+const Y_OFFSETS: Offsets = Offsets {
+    delay_a: Y_DELAY_A,
+    delay_b: Y_DELAY_B,
+    adapt_a: Y_ADAPT_COEFFS_A,
+    adapt_b: Y_ADAPT_COEFFS_B,
+};
+
+const X_OFFSETS: Offsets = Offsets {
+    delay_a: X_DELAY_A,
+    delay_b: X_DELAY_B,
+    adapt_a: X_ADAPT_COEFFS_A,
+    adapt_b: X_ADAPT_COEFFS_B,
+};
+
+/// The adaptive predictor used by file versions 3.95 and later.
+pub struct Predictor {
+    history: Box<[i32; HISTORY_SIZE + PREDICTOR_SIZE]>,
+    pos: usize,
+    coeffs_a: [[i32; 4]; 2],
+    coeffs_b: [[i32; 5]; 2],
+    filter_a: [i32; 2],
+    filter_b: [i32; 2],
+    last_a: [i32; 2],
+}
+
+impl Predictor {
+    pub fn new() -> Self {
+        Predictor {
+            history: Box::new([0; HISTORY_SIZE + PREDICTOR_SIZE]),
+            pos: 0,
+            coeffs_a: [INITIAL_COEFFS_A; 2],
+            coeffs_b: [[0; 5]; 2],
+            filter_a: [0; 2],
+            filter_b: [0; 2],
+            last_a: [0; 2],
+        }
+    }
+
+    /// Resets the predictor. Must be called at the start of every APE frame.
+    pub fn reset(&mut self) {
+        self.history[..PREDICTOR_SIZE].iter_mut().for_each(|h| *h = 0);
+        self.pos = 0;
+        self.coeffs_a = [INITIAL_COEFFS_A; 2];
+        self.coeffs_b = [[0; 5]; 2];
+        self.filter_a = [0; 2];
+        self.filter_b = [0; 2];
+        self.last_a = [0; 2];
+    }
+
+    fn advance(&mut self) {
+        self.pos += 1;
+
+        // If the history buffer is full, move the history window back to the start.
+        if self.pos == HISTORY_SIZE {
+            self.history.copy_within(HISTORY_SIZE.., 0);
+            self.pos = 0;
+        }
+    }
+
+    /// Runs the predictor on a single sample of one channel of a stereo stream.
+    #[inline(always)]
+    fn update_filter(&mut self, decoded: i32, filter: usize, offsets: &Offsets) -> i32 {
+        let buf = &mut self.history[self.pos..self.pos + PREDICTOR_SIZE + 1];
+
+        let Offsets { delay_a, delay_b, adapt_a, adapt_b } = *offsets;
+
+        // Stage A.
+        buf[delay_a] = self.last_a[filter];
+        buf[adapt_a] = ape_sign(buf[delay_a]);
+        buf[delay_a - 1] = buf[delay_a].wrapping_sub(buf[delay_a - 1]);
+        buf[adapt_a - 1] = ape_sign(buf[delay_a - 1]);
+
+        let coeffs_a = &mut self.coeffs_a[filter];
+
+        let prediction_a = (0..4)
+            .fold(0i32, |acc, i| acc.wrapping_add(buf[delay_a - i].wrapping_mul(coeffs_a[i])));
+
+        // Stage B, a scaled first-order filter.
+        buf[delay_b] =
+            self.filter_a[filter ^ 1].wrapping_sub(self.filter_b[filter].wrapping_mul(31) >> 5);
+        buf[adapt_b] = ape_sign(buf[delay_b]);
+        buf[delay_b - 1] = buf[delay_b].wrapping_sub(buf[delay_b - 1]);
+        buf[adapt_b - 1] = ape_sign(buf[delay_b - 1]);
+
+        self.filter_b[filter] = self.filter_a[filter ^ 1];
+
+        let coeffs_b = &mut self.coeffs_b[filter];
+
+        let prediction_b = (0..5)
+            .fold(0i32, |acc, i| acc.wrapping_add(buf[delay_b - i].wrapping_mul(coeffs_b[i])));
+
+        self.last_a[filter] =
+            decoded.wrapping_add(prediction_a.wrapping_add(prediction_b >> 1) >> 10);
+
+        self.filter_a[filter] =
+            self.last_a[filter].wrapping_add(self.filter_a[filter].wrapping_mul(31) >> 5);
+
+        // Adapt the coefficients.
+        let sign = ape_sign(decoded);
+
+        for (i, c) in coeffs_a.iter_mut().enumerate() {
+            *c = c.wrapping_add(buf[adapt_a - i].wrapping_mul(sign));
+        }
+
+        for (i, c) in coeffs_b.iter_mut().enumerate() {
+            *c = c.wrapping_add(buf[adapt_b - i].wrapping_mul(sign));
+        }
+
+        self.filter_a[filter]
+    }
+
+    /// Runs the predictor on both channels of a stereo stream.
+    pub fn decode_stereo(&mut self, y: &mut [i32], x: &mut [i32]) {
+        for (y, x) in y.iter_mut().zip(x.iter_mut()) {
+            *y = self.update_filter(*y, 0, &Y_OFFSETS);
+            *x = self.update_filter(*x, 1, &X_OFFSETS);
+
+            self.advance();
+        }
+    }
+
+    /// Runs the predictor on a mono stream.
+    pub fn decode_mono(&mut self, y: &mut [i32]) {
+        let mut current_a = self.last_a[0];
+
+        for sample in y.iter_mut() {
+            let a = *sample;
+
+            let buf = &mut self.history[self.pos..self.pos + PREDICTOR_SIZE + 1];
+
+            buf[Y_DELAY_A] = current_a;
+            buf[Y_DELAY_A - 1] = buf[Y_DELAY_A].wrapping_sub(buf[Y_DELAY_A - 1]);
+
+            let coeffs_a = &mut self.coeffs_a[0];
+
+            let prediction_a = (0..4).fold(0i32, |acc, i| {
+                acc.wrapping_add(buf[Y_DELAY_A - i].wrapping_mul(coeffs_a[i]))
+            });
+
+            current_a = a.wrapping_add(prediction_a >> 10);
+
+            buf[Y_ADAPT_COEFFS_A] = ape_sign(buf[Y_DELAY_A]);
+            buf[Y_ADAPT_COEFFS_A - 1] = ape_sign(buf[Y_DELAY_A - 1]);
+
+            let sign = ape_sign(a);
+
+            for (i, c) in coeffs_a.iter_mut().enumerate() {
+                *c = c.wrapping_add(buf[Y_ADAPT_COEFFS_A - i].wrapping_mul(sign));
+            }
+
+            self.advance();
+
+            self.filter_a[0] = current_a.wrapping_add(self.filter_a[0].wrapping_mul(31) >> 5);
+
+            *sample = self.filter_a[0];
+        }
+
+        self.last_a[0] = current_a;
+    }
+}
@@ -0,0 +1,362 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::audio::{AsAudioBufferRef, AudioBuffer, AudioBufferRef};
+use symphonia_core::audio::{Signal, SignalSpec};
+use symphonia_core::codecs::{CodecDescriptor, CodecParameters};
+use symphonia_core::codecs::{Decoder, DecoderOptions, FinalizeResult};
+use symphonia_core::codecs::{CODEC_TYPE_DSD_LSBF, CODEC_TYPE_DSD_LSBF_PLANAR};
+use symphonia_core::codecs::{CODEC_TYPE_DSD_MSBF, CODEC_TYPE_DSD_MSBF_PLANAR};
+use symphonia_core::errors::{decode_error, unsupported_error, Result};
+use symphonia_core::formats::Packet;
+use symphonia_core::support_codec;
+
+use super::filter::DsdToPcm;
+
+/// The maximum sample rate of the PCM output.
+const MAX_PCM_SAMPLE_RATE: u32 = 96_000;
+
+/// The DoP marker bytes. Consecutive DoP frames alternate between the two markers.
+const DOP_MARKERS: [u8; 2] = [0x05, 0xfa];
+
+/// The output mode of the DSD decoder.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DsdOutputMode {
+    /// Convert DSD to 32-bit floating point PCM.
+    ///
+    /// The DSD stream is low-pass filtered and decimated to a sample rate of 88.2 kHz for
+    /// 44.1 kHz-based DSD rates (DSD64, DSD128, DSD256, etc.), or 96 kHz for 48 kHz-based DSD
+    /// rates. Full-scale PCM corresponds to 100% modulation, therefore the 0 dB SACD reference
+    /// level (50% modulation) is -6 dBFS.
+    Pcm,
+    /// Pack DSD into PCM frames using the DSD over PCM (DoP) standard.
+    ///
+    /// The output consists of 24-bit PCM samples, left-justified in 32-bit signed integers, at
+    /// 1/16th of the DSD sample rate. Each sample carries 16 DSD bits, most-significant bit first,
+    /// beneath an alternating marker byte. The output must be passed bit-exact to a DoP-capable
+    /// DAC. Any processing, including volume control and dither, will corrupt the DSD stream.
+    Dop,
+}
+
+impl Default for DsdOutputMode {
+    fn default() -> Self {
+        DsdOutputMode::Pcm
+    }
+}
+
+/// The state of the decoder for a given output mode.
+enum Output {
+    Pcm { converters: Vec<DsdToPcm>, samples: Vec<f32>, buf: AudioBuffer<f32> },
+    Dop { marker: usize, carry: Vec<Option<u8>>, buf: AudioBuffer<i32> },
+}
+
+/// Direct Stream Digital (DSD) decoder.
+///
+/// Decodes interleaved or planar DSD, stored either least- or most-significant bit first, to PCM
+/// or DoP.
+pub struct DsdDecoder {
+    params: CodecParameters,
+    /// If true, channel data is planar. Otherwise, it is byte interleaved.
+    is_planar: bool,
+    /// If true, bytes are stored least-significant bit first.
+    is_lsbf: bool,
+    n_channels: usize,
+    /// The DSD bytes of one channel, most-significant bit first.
+    bytes: Vec<u8>,
+    output: Output,
+}
+
+impl DsdDecoder {
+    /// Instantiate a DSD decoder with the given output mode. `Decoder::try_new` selects the
+    /// output mode using `DecoderOptions::dsd_over_pcm`.
+    pub fn try_new_with_output_mode(
+        params: &CodecParameters,
+        _options: &DecoderOptions,
+        mode: DsdOutputMode,
+    ) -> Result<Self> {
+        let (is_planar, is_lsbf) = match params.codec {
+            CODEC_TYPE_DSD_LSBF => (false, true),
+            CODEC_TYPE_DSD_LSBF_PLANAR => (true, true),
+            CODEC_TYPE_DSD_MSBF => (false, false),
+            CODEC_TYPE_DSD_MSBF_PLANAR => (true, false),
+            _ => return unsupported_error("dsd: invalid codec type"),
+        };
+
+        let rate = match params.sample_rate {
+            Some(rate) if rate > 0 => rate,
+            _ => return unsupported_error("dsd: sample rate is required"),
+        };
+
+        let channels = match params.channels {
+            Some(channels) => channels,
+            None => return unsupported_error("dsd: channels are required"),
+        };
+
+        // The maximum number of DSD bits per channel in a packet.
+        let max_bits = match params.max_frames_per_packet {
+            Some(max) if max <= 1 << 24 => max,
+            Some(_) => return unsupported_error("dsd: maximum frames per packet is too large"),
+            None => return unsupported_error("dsd: maximum frames per packet is required"),
+        };
+
+        let n_channels = channels.count();
+
+        let output = match mode {
+            DsdOutputMode::Pcm => {
+                // Determine the number of decimate-by-2 stages needed to reach the output rate.
+                let mut n_stages = 1;
+
+                while rate / (8 << n_stages) > MAX_PCM_SAMPLE_RATE {
+                    n_stages += 1;
+                }
+
+                let decimation = 8 << n_stages;
+
+                if rate % decimation != 0 {
+                    return unsupported_error("dsd: unsupported sample rate");
+                }
+
+                let spec = SignalSpec::new(rate / decimation, channels);
+
+                Output::Pcm {
+                    converters: (0..n_channels).map(|_| DsdToPcm::new(n_stages)).collect(),
+                    samples: Vec::new(),
+                    buf: AudioBuffer::new(max_bits / u64::from(decimation) + 1, spec),
+                }
+            }
+            DsdOutputMode::Dop => {
+                if rate % 16 != 0 {
+                    return unsupported_error("dsd: unsupported sample rate");
+                }
+
+                let spec = SignalSpec::new(rate / 16, channels);
+
+                Output::Dop {
+                    marker: 0,
+                    carry: vec![None; n_channels],
+                    buf: AudioBuffer::new(max_bits / 16 + 1, spec),
+                }
+            }
+        };
+
+        Ok(DsdDecoder {
+            params: params.clone(),
+            is_planar,
+            is_lsbf,
+            n_channels,
+            bytes: Vec::new(),
+            output,
+        })
+    }
+
+    /// Gets the output mode of the decoder.
+    pub fn output_mode(&self) -> DsdOutputMode {
+        match self.output {
+            Output::Pcm { .. } => DsdOutputMode::Pcm,
+            Output::Dop { .. } => DsdOutputMode::Dop,
+        }
+    }
+
+    /// Copies the DSD bytes of a channel to the byte buffer, converting them to most-significant
+    /// bit first order.
+    fn read_channel(&mut self, data: &[u8], ch: usize) {
+        self.bytes.clear();
+
+        if self.is_planar {
+            let len = data.len() / self.n_channels;
+            self.bytes.extend_from_slice(&data[ch * len..(ch + 1) * len]);
+        }
+        else {
+            self.bytes.extend(data.iter().skip(ch).step_by(self.n_channels));
+        }
+
+        if self.is_lsbf {
+            self.bytes.iter_mut().for_each(|byte| *byte = byte.reverse_bits());
+        }
+    }
+
+    fn decode_inner(&mut self, packet: &Packet) -> Result<()> {
+        let data = packet.buf();
+
+        if data.len() % self.n_channels != 0 {
+            return decode_error("dsd: packet length is not a multiple of the channel count");
+        }
+
+        let len = data.len() / self.n_channels;
+
+        match &mut self.output {
+            Output::Pcm { buf, .. } => {
+                buf.clear();
+
+                if len == 0 {
+                    return Ok(());
+                }
+            }
+            Output::Dop { buf, carry, .. } => {
+                buf.clear();
+
+                // The number of DoP frames depends on whether a byte was left over from the
+                // previous packet.
+                let n_frames = (len + usize::from(carry[0].is_some())) / 2;
+
+                buf.render_reserved(Some(n_frames));
+            }
+        }
+
+        for ch in 0..self.n_channels {
+            self.read_channel(data, ch);
+
+            match &mut self.output {
+                Output::Pcm { converters, samples, buf } => {
+                    samples.clear();
+                    converters[ch].process(self.bytes.iter().copied(), samples);
+
+                    // All channels are processed identically and yield the same number of samples.
+                    if ch == 0 {
+                        buf.render_reserved(Some(samples.len()));
+                    }
+
+                    buf.chan_mut(ch).copy_from_slice(samples);
+                }
+                Output::Dop { marker, carry, buf } => {
+                    let mut bytes = carry[ch].take().into_iter().chain(self.bytes.iter().copied());
+
+                    let mut frame_marker = *marker;
+
+                    for sample in buf.chan_mut(ch).iter_mut() {
+                        // The frame count guarantees two bytes are available.
+                        let b1 = bytes.next().unwrap_or(0x69);
+                        let b2 = bytes.next().unwrap_or(0x69);
+
+                        *sample = i32::from_be_bytes([DOP_MARKERS[frame_marker], b1, b2, 0]);
+
+                        frame_marker ^= 1;
+                    }
+
+                    carry[ch] = bytes.next();
+                }
+            }
+        }
+
+        // Advance the DoP marker for the next packet.
+        if let Output::Dop { marker, buf, .. } = &mut self.output {
+            *marker ^= buf.frames() & 1;
+        }
+
+        Ok(())
+    }
+}
+
+impl Decoder for DsdDecoder {
+    fn try_new(params: &CodecParameters, options: &DecoderOptions) -> Result<Self> {
+        let mode = if options.dsd_over_pcm { DsdOutputMode::Dop } else { DsdOutputMode::Pcm };
+
+        DsdDecoder::try_new_with_output_mode(params, options, mode)
+    }
+
+    fn supported_codecs() -> &'static [CodecDescriptor] {
+        &[
+            support_codec!(CODEC_TYPE_DSD_LSBF, "dsd_lsbf", "DSD (LSB First) Interleaved"),
+            support_codec!(CODEC_TYPE_DSD_LSBF_PLANAR, "dsd_lsbf_planar", "DSD (LSB First) Planar"),
+            support_codec!(CODEC_TYPE_DSD_MSBF, "dsd_msbf", "DSD (MSB First) Interleaved"),
+            support_codec!(CODEC_TYPE_DSD_MSBF_PLANAR, "dsd_msbf_planar", "DSD (MSB First) Planar"),
+        ]
+    }
+
+    fn reset(&mut self) {
+        match &mut self.output {
+            Output::Pcm { converters, .. } => {
+                converters.iter_mut().for_each(|converter| converter.reset());
+            }
+            Output::Dop { marker, carry, .. } => {
+                *marker = 0;
+                carry.iter_mut().for_each(|byte| *byte = None);
+            }
+        }
+    }
+
+    fn codec_params(&self) -> &CodecParameters {
+        &self.params
+    }
+
+    fn decode(&mut self, packet: &Packet) -> Result<AudioBufferRef<'_>> {
+        if let Err(e) = self.decode_inner(packet) {
+            match &mut self.output {
+                Output::Pcm { buf, .. } => buf.clear(),
+                Output::Dop { buf, .. } => buf.clear(),
+            }
+            Err(e)
+        }
+        else {
+            Ok(self.last_decoded())
+        }
+    }
+
+    fn finalize(&mut self) -> FinalizeResult {
+        Default::default()
+    }
+
+    fn last_decoded(&self) -> AudioBufferRef<'_> {
+        match &self.output {
+            Output::Pcm { buf, .. } => buf.as_audio_buffer_ref(),
+            Output::Dop { buf, .. } => buf.as_audio_buffer_ref(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use symphonia_core::audio::{AudioBufferRef, Channels};
+    use symphonia_core::codecs::CodecType;
+
+    fn dop_params(codec: CodecType) -> CodecParameters {
+        let mut params = CodecParameters::new();
+
+        params
+            .for_codec(codec)
+            .with_sample_rate(2_822_400)
+            .with_channels(Channels::FRONT_LEFT | Channels::FRONT_RIGHT)
+            .with_max_frames_per_packet(64);
+
+        params
+    }
+
+    #[test]
+    fn verify_dop_packing() {
+        let params = dop_params(CODEC_TYPE_DSD_LSBF_PLANAR);
+
+        let mut decoder =
+            DsdDecoder::try_new_with_output_mode(&params, &Default::default(), DsdOutputMode::Dop)
+                .unwrap();
+
+        // Three bytes per channel, least-significant bit first. The third byte is carried over to
+        // the next packet.
+        let packet = Packet::new_from_slice(0, 0, 24, &[0x01, 0x02, 0x03, 0x80, 0x40, 0x20]);
+
+        match decoder.decode(&packet).unwrap() {
+            AudioBufferRef::S32(buf) => {
+                assert_eq!(buf.frames(), 1);
+                assert_eq!(buf.chan(0), &[0x0580_4000]);
+                assert_eq!(buf.chan(1), &[0x0501_0200]);
+            }
+            _ => panic!("unexpected sample format"),
+        }
+
+        let packet = Packet::new_from_slice(0, 24, 8, &[0x04, 0x10]);
+
+        match decoder.decode(&packet).unwrap() {
+            AudioBufferRef::S32(buf) => {
+                assert_eq!(buf.frames(), 1);
+                assert_eq!(buf.chan(0), &[0xfac0_2000_u32 as i32]);
+                assert_eq!(buf.chan(1), &[0xfa04_0800_u32 as i32]);
+            }
+            _ => panic!("unexpected sample format"),
+        }
+    }
+}
@@ -0,0 +1,503 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::io::{Seek, SeekFrom};
+
+use symphonia_core::support_format;
+
+use symphonia_core::audio::Channels;
+use symphonia_core::codecs::{CodecParameters, CODEC_TYPE_DSD_MSBF};
+use symphonia_core::errors::{decode_error, end_of_stream_error, seek_error, unsupported_error};
+use symphonia_core::errors::{Result, SeekErrorKind};
+use symphonia_core::formats::prelude::*;
+use symphonia_core::io::*;
+use symphonia_core::meta::{GainInfo, Metadata, MetadataBuilder, MetadataLog};
+use symphonia_core::meta::{StandardTagKey, Tag, Value};
+use symphonia_core::probe::{Descriptor, Instantiate, QueryDescriptor};
+
+use symphonia_metadata::id3v2;
+
+use log::{debug, warn};
+
+/// The DSDIFF stream marker: "FRM8" in ASCII.
+const DSDIFF_STREAM_MARKER: [u8; 4] = *b"FRM8";
+
+/// The number of bytes per channel in a packet.
+const DSDIFF_PACKET_BYTES_PER_CHANNEL: u64 = 4096;
+
+/// The maximum length of a metadata chunk that will be read.
+const DSDIFF_MAX_METADATA_CHUNK_LEN: u64 = 16 * 1024 * 1024;
+
+/// A DSDIFF chunk header.
+struct ChunkHeader {
+    id: [u8; 4],
+    len: u64,
+}
+
+impl ChunkHeader {
+    fn read<B: ReadBytes>(reader: &mut B) -> Result<ChunkHeader> {
+        let id = reader.read_quad_bytes()?;
+        let len = reader.read_be_u64()?;
+        Ok(ChunkHeader { id, len })
+    }
+
+    /// The length of the chunk body including the pad byte, if any.
+    fn padded_len(&self) -> u64 {
+        self.len + (self.len & 1)
+    }
+}
+
+/// The sound properties of a DSDIFF file.
+#[derive(Default)]
+struct Properties {
+    sample_rate: Option<u32>,
+    channels: Option<Channels>,
+    is_dsd: bool,
+}
+
+fn read_channels<B: ReadBytes>(reader: &mut B) -> Result<Channels> {
+    let n_channels = reader.read_be_u16()?;
+
+    if n_channels == 0 {
+        return decode_error("dsdiff: invalid channel count");
+    }
+
+    let mut channels = Channels::empty();
+    let mut is_valid = true;
+
+    for _ in 0..n_channels {
+        let channel = match &reader.read_quad_bytes()? {
+            b"SLFT" | b"MLFT" => Channels::FRONT_LEFT,
+            b"SRGT" | b"MRGT" => Channels::FRONT_RIGHT,
+            b"C   " => Channels::FRONT_CENTRE,
+            b"LFE " => Channels::LFE1,
+            b"LS  " => Channels::REAR_LEFT,
+            b"RS  " => Channels::REAR_RIGHT,
+            _ => Channels::empty(),
+        };
+
+        // Channels must be unique, and in the standard order for the channel bitmask to be a
+        // valid description of the layout.
+        if channel.is_empty() || channel.bits() <= channels.bits() {
+            is_valid = false;
+        }
+
+        channels |= channel;
+    }
+
+    if !is_valid {
+        // Fallback to a default layout based on the channel count.
+        let default = match n_channels {
+            1 => Channels::FRONT_LEFT,
+            2 => Channels::FRONT_LEFT | Channels::FRONT_RIGHT,
+            n => Channels::discrete(n),
+        };
+
+        warn!("dsdiff: unknown channel layout, using the default layout");
+
+        channels = default;
+    }
+
+    Ok(channels)
+}
+
+fn read_properties<B: ReadBytes>(reader: &mut B, len: u64) -> Result<Properties> {
+    if len < 4 {
+        return decode_error("dsdiff: invalid property chunk length");
+    }
+
+    if reader.read_quad_bytes()? != *b"SND " {
+        return unsupported_error("dsdiff: unsupported property type");
+    }
+
+    let mut props = Properties::default();
+    let mut read = 4;
+
+    while read < len {
+        let header = ChunkHeader::read(reader)?;
+
+        read += 12 + header.padded_len();
+
+        if read > len {
+            return decode_error("dsdiff: property chunk overrun");
+        }
+
+        match &header.id {
+            b"FS  " if header.len >= 4 => {
+                props.sample_rate = Some(reader.read_be_u32()?);
+                reader.ignore_bytes(header.padded_len() - 4)?;
+            }
+            b"CHNL" if header.len >= 2 && header.len <= 2 + 4 * 0xffff => {
+                let mut buf = vec![0; header.padded_len() as usize];
+                reader.read_buf_exact(&mut buf)?;
+                props.channels = Some(read_channels(&mut BufReader::new(&buf))?);
+            }
+            b"CMPR" if header.len >= 4 => {
+                let compression = reader.read_quad_bytes()?;
+
+                match &compression {
+                    b"DSD " => props.is_dsd = true,
+                    b"DST " => {
+                        return unsupported_error("dsdiff: dst compression is not supported")
+                    }
+                    _ => return unsupported_error("dsdiff: unsupported compression type"),
+                }
+
+                reader.ignore_bytes(header.padded_len() - 4)?;
+            }
+            _ => {
+                // Absolute start time, loudspeaker configuration, etc.
+                reader.ignore_bytes(header.padded_len())?;
+            }
+        }
+    }
+
+    Ok(props)
+}
+
+/// Reads a text string prefixed by a 32-bit length.
+fn read_counted_text<B: ReadBytes>(reader: &mut B) -> Result<String> {
+    let len = reader.read_be_u32()?;
+    let buf = reader.read_boxed_slice_exact(len as usize)?;
+    Ok(String::from_utf8_lossy(&buf).trim_end_matches('\0').to_string())
+}
+
+/// Reads the edited master information chunk.
+fn read_edited_master_info<B: ReadBytes>(reader: &mut B, len: u64, builder: &mut MetadataBuilder) {
+    let mut read = 0;
+
+    while read + 12 <= len {
+        let header = match ChunkHeader::read(reader) {
+            Ok(header) => header,
+            Err(_) => break,
+        };
+
+        read += 12 + header.padded_len();
+
+        if read > len {
+            warn!("dsdiff: malformed edited master information chunk");
+            break;
+        }
+
+        let mut buf = vec![0; header.padded_len() as usize];
+
+        if reader.read_buf_exact(&mut buf).is_err() {
+            break;
+        }
+
+        let (std_key, key) = match &header.id {
+            b"DIAR" => (StandardTagKey::Artist, "DIAR"),
+            b"DITI" => (StandardTagKey::TrackTitle, "DITI"),
+            _ => continue,
+        };
+
+        if let Ok(text) = read_counted_text(&mut BufReader::new(&buf)) {
+            builder.add_tag(Tag::new(Some(std_key), key, Value::from(text)));
+        }
+    }
+}
+
+/// Reads the comments chunk.
+fn read_comments<B: ReadBytes>(reader: &mut B, builder: &mut MetadataBuilder) -> Result<()> {
+    let n_comments = reader.read_be_u16()?;
+
+    for _ in 0..n_comments {
+        // The timestamp (year, month, day, hour, minutes), and the comment type and reference.
+        reader.ignore_bytes(2 + 1 + 1 + 1 + 1 + 2 + 2)?;
+
+        let len = reader.read_be_u32()?;
+        let buf = reader.read_boxed_slice_exact(len as usize)?;
+
+        if len & 1 == 1 {
+            reader.ignore_bytes(1)?;
+        }
+
+        let text = String::from_utf8_lossy(&buf).to_string();
+
+        builder.add_tag(Tag::new(Some(StandardTagKey::Comment), "COMT", Value::from(text)));
+    }
+
+    Ok(())
+}
+
+/// Reads a metadata chunk. Malformed metadata is not fatal.
+fn read_metadata_chunk<B: ReadBytes>(
+    reader: &mut B,
+    header: &ChunkHeader,
+    metadata: &mut MetadataLog,
+) -> Result<()> {
+    if header.padded_len() > DSDIFF_MAX_METADATA_CHUNK_LEN {
+        return reader.ignore_bytes(header.padded_len()).map_err(From::from);
+    }
+
+    let buf = reader.read_boxed_slice_exact(header.padded_len() as usize)?;
+
+    let mut builder = MetadataBuilder::new();
+
+    match &header.id {
+        b"DIIN" => read_edited_master_info(&mut BufReader::new(&buf), header.len, &mut builder),
+        b"COMT" => {
+            if read_comments(&mut BufReader::new(&buf), &mut builder).is_err() {
+                warn!("dsdiff: malformed comments chunk");
+            }
+        }
+        b"ID3 " => {
+            if let Err(err) = id3v2::read_id3v2(&mut BufReader::new(&buf), &mut builder) {
+                warn!("dsdiff: failed to read id3v2 tag: {}", err);
+            }
+        }
+        _ => return Ok(()),
+    }
+
+    metadata.push(builder.metadata());
+
+    Ok(())
+}
+
+/// Direct Stream Digital Interchange File Format (DSDIFF) format reader.
+///
+/// Only uncompressed DSD is supported. Each packet contains up-to 4096 bytes per channel of byte
+/// interleaved DSD. Timestamps are in units of DSD samples (bits).
+pub struct DsdiffReader {
+    reader: MediaSourceStream,
+    tracks: Vec<Track>,
+    cues: Vec<Cue>,
+    metadata: MetadataLog,
+    /// The position of the first byte of sound data.
+    data_start: u64,
+    n_channels: u64,
+    /// The total number of DSD samples per channel.
+    n_samples: u64,
+    /// The timestamp of the next packet.
+    next_ts: u64,
+}
+
+impl QueryDescriptor for DsdiffReader {
+    fn query() -> &'static [Descriptor] {
+        &[support_format!(
+            "dsdiff",
+            "Direct Stream Digital Interchange File Format",
+            &["dff"],
+            &["audio/x-dff", "audio/dff"],
+            &[b"FRM8"]
+        )]
+    }
+
+    fn score(_context: &[u8]) -> u8 {
+        255
+    }
+}
+
+impl FormatReader for DsdiffReader {
+    fn try_new(mut source: MediaSourceStream, _options: &FormatOptions) -> Result<Self> {
+        let marker = source.read_quad_bytes()?;
+
+        if marker != DSDIFF_STREAM_MARKER {
+            return unsupported_error("dsdiff: missing frm8 stream marker");
+        }
+
+        let form_len = source.read_be_u64()?;
+
+        if source.read_quad_bytes()? != *b"DSD " {
+            return unsupported_error("dsdiff: unsupported form type");
+        }
+
+        let form_end = source.pos() - 4 + form_len;
+
+        let mut metadata = MetadataLog::default();
+        let mut props = None;
+
+        // Read chunks until the sound data chunk.
+        let (data_start, data_len) = loop {
+            if source.pos() + 12 > form_end {
+                return decode_error("dsdiff: missing sound data chunk");
+            }
+
+            let header = ChunkHeader::read(&mut source)?;
+
+            match &header.id {
+                b"FVER" => {
+                    let version = source.read_be_u32()?;
+
+                    if version >> 24 != 1 {
+                        return unsupported_error("dsdiff: unsupported format version");
+                    }
+
+                    source.ignore_bytes(header.padded_len().saturating_sub(4))?;
+                }
+                b"PROP" => props = Some(read_properties(&mut source, header.len)?),
+                b"DSD " => break (source.pos(), header.len),
+                b"DST " => return unsupported_error("dsdiff: dst compression is not supported"),
+                _ => read_metadata_chunk(&mut source, &header, &mut metadata)?,
+            }
+        };
+
+        let props = match props {
+            Some(props) if props.is_dsd => props,
+            Some(_) => return unsupported_error("dsdiff: unsupported compression type"),
+            None => return decode_error("dsdiff: missing property chunk"),
+        };
+
+        let sample_rate = match props.sample_rate {
+            Some(rate) if rate > 0 => rate,
+            _ => return decode_error("dsdiff: missing sample rate"),
+        };
+
+        let channels = match props.channels {
+            Some(channels) => channels,
+            None => return decode_error("dsdiff: missing channels"),
+        };
+
+        let n_channels = channels.count() as u64;
+        let n_samples = (data_len / n_channels) * 8;
+
+        // Read any metadata chunks following the sound data chunk.
+        if source.is_seekable() {
+            let data_end = data_start + data_len + (data_len & 1);
+
+            source.seek(SeekFrom::Start(data_end))?;
+
+            while source.pos() + 12 <= form_end {
+                let header = match ChunkHeader::read(&mut source) {
+                    Ok(header) => header,
+                    Err(_) => break,
+                };
+
+                let result = match &header.id {
+                    b"DIIN" | b"COMT" | b"ID3 " => {
+                        read_metadata_chunk(&mut source, &header, &mut metadata)
+                    }
+                    _ => source.ignore_bytes(header.padded_len()).map_err(From::from),
+                };
+
+                if result.is_err() {
+                    warn!("dsdiff: malformed chunk after sound data");
+                    break;
+                }
+            }
+
+            source.seek(SeekFrom::Start(data_start))?;
+        }
+
+        debug!(
+            "dsdiff: sample_rate={}, channels={}, n_samples={}",
+            sample_rate,
+            channels.count(),
+            n_samples
+        );
+
+        let mut codec_params = CodecParameters::new();
+
+        codec_params
+            .for_codec(CODEC_TYPE_DSD_MSBF)
+            .with_sample_rate(sample_rate)
+            .with_time_base(TimeBase::new(1, sample_rate))
+            .with_bits_per_sample(1)
+            .with_channels(channels)
+            .with_n_frames(n_samples)
+            .with_max_frames_per_packet(DSDIFF_PACKET_BYTES_PER_CHANNEL * 8);
+
+        let mut track = Track::new(0, codec_params);
+        track.gain = metadata.metadata().current().and_then(|rev| GainInfo::from_tags(rev.tags()));
+
+        Ok(DsdiffReader {
+            reader: source,
+            tracks: vec![track],
+            cues: Vec::new(),
+            metadata,
+            data_start,
+            n_channels,
+            n_samples,
+            next_ts: 0,
+        })
+    }
+
+    fn next_packet(&mut self) -> Result<Packet> {
+        let ts = self.next_ts;
+
+        if ts >= self.n_samples {
+            return end_of_stream_error();
+        }
+
+        let dur = (self.n_samples - ts).min(DSDIFF_PACKET_BYTES_PER_CHANNEL * 8);
+
+        let pos = self.data_start + (ts / 8) * self.n_channels;
+
+        if self.reader.pos() != pos {
+            self.reader.seek(SeekFrom::Start(pos))?;
+        }
+
+        let buf = self.reader.read_boxed_slice_exact(((dur / 8) * self.n_channels) as usize)?;
+
+        self.next_ts += dur;
+
+        Ok(Packet::new_from_boxed_slice(0, ts, dur, buf))
+    }
+
+    fn metadata(&mut self) -> Metadata<'_> {
+        self.metadata.metadata()
+    }
+
+    fn cues(&self) -> &[Cue] {
+        &self.cues
+    }
+
+    fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    fn seek(&mut self, _mode: SeekMode, to: SeekTo) -> Result<SeekedTo> {
+        let params = &self.tracks[0].codec_params;
+
+        let ts = match to {
+            // Frame timestamp given.
+            SeekTo::TimeStamp { ts, .. } => ts,
+            // Time value given, calculate frame timestamp from sample rate.
+            SeekTo::Time { time, .. } => {
+                // Use the sample rate to calculate the frame timestamp. If sample rate is not
+                // known, the seek cannot be completed.
+                if let Some(sample_rate) = params.sample_rate {
+                    TimeBase::new(1, sample_rate).calc_timestamp(time)
+                }
+                else {
+                    return seek_error(SeekErrorKind::Unseekable);
+                }
+            }
+        };
+
+        if ts > self.n_samples {
+            return seek_error(SeekErrorKind::OutOfRange);
+        }
+
+        // Sound data is byte interleaved, therefore it is possible to seek to any byte.
+        let actual_ts = ts & !7;
+
+        let pos = self.data_start + (actual_ts / 8) * self.n_channels;
+
+        if !self.reader.is_seekable() {
+            // If the reader does not support seeking, seeks can only be emulated in the forward
+            // direction by reading and discarding data.
+            let current_pos = self.reader.pos();
+
+            if pos < current_pos {
+                return seek_error(SeekErrorKind::ForwardOnly);
+            }
+
+            self.reader.ignore_bytes(pos - current_pos)?;
+        }
+
+        self.next_ts = actual_ts;
+
+        debug!("seeked to packet_ts={} (delta={})", actual_ts, actual_ts as i64 - ts as i64);
+
+        Ok(SeekedTo { track_id: 0, actual_ts, required_ts: ts })
+    }
+
+    fn into_inner(self: Box<Self>) -> MediaSourceStream {
+        self.reader
+    }
+}
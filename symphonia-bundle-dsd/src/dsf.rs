@@ -0,0 +1,346 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::io::{Seek, SeekFrom};
+
+use symphonia_core::support_format;
+
+use symphonia_core::audio::Channels;
+use symphonia_core::codecs::CodecParameters;
+use symphonia_core::codecs::{CODEC_TYPE_DSD_LSBF_PLANAR, CODEC_TYPE_DSD_MSBF_PLANAR};
+use symphonia_core::errors::{decode_error, end_of_stream_error, seek_error, unsupported_error};
+use symphonia_core::errors::{Result, SeekErrorKind};
+use symphonia_core::formats::prelude::*;
+use symphonia_core::io::*;
+use symphonia_core::meta::{GainInfo, Metadata, MetadataBuilder, MetadataLog};
+use symphonia_core::probe::{Descriptor, Instantiate, QueryDescriptor};
+
+use symphonia_metadata::id3v2;
+
+use log::{debug, warn};
+
+/// The DSF stream marker: "DSD " in ASCII.
+const DSF_STREAM_MARKER: [u8; 4] = *b"DSD ";
+
+/// The length of the DSD chunk.
+const DSF_DSD_CHUNK_LEN: u64 = 28;
+
+/// The maximum block size per channel that will be accepted.
+const DSF_MAX_BLOCK_SIZE: u32 = 1 << 16;
+
+/// The DSF format chunk.
+#[derive(Debug)]
+struct FmtChunk {
+    channel_type: u32,
+    n_channels: u32,
+    sample_rate: u32,
+    bits_per_sample: u32,
+    n_samples: u64,
+    block_size: u32,
+}
+
+impl FmtChunk {
+    fn read<B: ReadBytes>(reader: &mut B) -> Result<FmtChunk> {
+        if reader.read_quad_bytes()? != *b"fmt " {
+            return decode_error("dsf: missing fmt chunk");
+        }
+
+        let len = reader.read_u64()?;
+
+        if len < 52 {
+            return decode_error("dsf: invalid fmt chunk length");
+        }
+
+        if reader.read_u32()? != 1 {
+            return unsupported_error("dsf: unsupported format version");
+        }
+
+        // Only raw DSD is defined.
+        if reader.read_u32()? != 0 {
+            return unsupported_error("dsf: unsupported format id");
+        }
+
+        let chunk = FmtChunk {
+            channel_type: reader.read_u32()?,
+            n_channels: reader.read_u32()?,
+            sample_rate: reader.read_u32()?,
+            bits_per_sample: reader.read_u32()?,
+            n_samples: reader.read_u64()?,
+            block_size: reader.read_u32()?,
+        };
+
+        // Reserved, and any unknown fields.
+        reader.ignore_bytes(len - 48)?;
+
+        Ok(chunk)
+    }
+
+    fn channels(&self) -> Result<Channels> {
+        let channels = match (self.channel_type, self.n_channels) {
+            (1, 1) => Channels::FRONT_LEFT,
+            (2, 2) => Channels::FRONT_LEFT | Channels::FRONT_RIGHT,
+            (3, 3) => Channels::FRONT_LEFT | Channels::FRONT_RIGHT | Channels::FRONT_CENTRE,
+            (4, 4) => {
+                Channels::FRONT_LEFT
+                    | Channels::FRONT_RIGHT
+                    | Channels::REAR_LEFT
+                    | Channels::REAR_RIGHT
+            }
+            (5, 4) => {
+                Channels::FRONT_LEFT
+                    | Channels::FRONT_RIGHT
+                    | Channels::FRONT_CENTRE
+                    | Channels::LFE1
+            }
+            (6, 5) => {
+                Channels::FRONT_LEFT
+                    | Channels::FRONT_RIGHT
+                    | Channels::FRONT_CENTRE
+                    | Channels::REAR_LEFT
+                    | Channels::REAR_RIGHT
+            }
+            (7, 6) => {
+                Channels::FRONT_LEFT
+                    | Channels::FRONT_RIGHT
+                    | Channels::FRONT_CENTRE
+                    | Channels::LFE1
+                    | Channels::REAR_LEFT
+                    | Channels::REAR_RIGHT
+            }
+            _ => return decode_error("dsf: invalid channel type"),
+        };
+
+        Ok(channels)
+    }
+}
+
+/// DSD Stream File (DSF) format reader.
+///
+/// Audio data is stored in blocks of a fixed size per channel. Each packet contains one block per
+/// channel, in channel order, trimmed to the number of valid bytes in the final block group.
+/// Timestamps are in units of DSD samples (bits).
+pub struct DsfReader {
+    reader: MediaSourceStream,
+    tracks: Vec<Track>,
+    cues: Vec<Cue>,
+    metadata: MetadataLog,
+    /// The position of the first block group.
+    data_start: u64,
+    /// The size of a block in bytes.
+    block_size: u64,
+    n_channels: u64,
+    /// The total number of DSD samples per channel.
+    n_samples: u64,
+    /// The index of the next block group.
+    next_block: u64,
+}
+
+impl QueryDescriptor for DsfReader {
+    fn query() -> &'static [Descriptor] {
+        &[support_format!(
+            "dsf",
+            "DSD Stream File",
+            &["dsf"],
+            &["audio/x-dsf", "audio/dsf"],
+            &[b"DSD "]
+        )]
+    }
+
+    fn score(_context: &[u8]) -> u8 {
+        255
+    }
+}
+
+impl FormatReader for DsfReader {
+    fn try_new(mut source: MediaSourceStream, _options: &FormatOptions) -> Result<Self> {
+        let marker = source.read_quad_bytes()?;
+
+        if marker != DSF_STREAM_MARKER {
+            return unsupported_error("dsf: missing dsd stream marker");
+        }
+
+        if source.read_u64()? != DSF_DSD_CHUNK_LEN {
+            return decode_error("dsf: invalid dsd chunk length");
+        }
+
+        // The total file size, and the position of the ID3v2 tag, if present.
+        let _ = source.read_u64()?;
+        let metadata_pos = source.read_u64()?;
+
+        let fmt = FmtChunk::read(&mut source)?;
+
+        debug!("dsf: {:?}", fmt);
+
+        let channels = fmt.channels()?;
+
+        let codec = match fmt.bits_per_sample {
+            1 => CODEC_TYPE_DSD_LSBF_PLANAR,
+            8 => CODEC_TYPE_DSD_MSBF_PLANAR,
+            _ => return decode_error("dsf: invalid bits per sample"),
+        };
+
+        if fmt.sample_rate == 0 {
+            return decode_error("dsf: invalid sample rate");
+        }
+
+        if fmt.block_size == 0 || fmt.block_size > DSF_MAX_BLOCK_SIZE {
+            return decode_error("dsf: invalid block size");
+        }
+
+        if source.read_quad_bytes()? != *b"data" {
+            return decode_error("dsf: missing data chunk");
+        }
+
+        // The data chunk length includes the chunk header.
+        let data_len = source.read_u64()?.saturating_sub(12);
+        let data_start = source.pos();
+
+        let block_size = u64::from(fmt.block_size);
+        let n_channels = u64::from(fmt.n_channels);
+
+        // The sample count may exceed the data actually present in the file.
+        let n_samples = fmt.n_samples.min((data_len / n_channels) * 8);
+
+        // Read the ID3v2 tag at the end of the file.
+        let mut metadata = MetadataLog::default();
+
+        if metadata_pos != 0 && source.is_seekable() {
+            source.seek(SeekFrom::Start(metadata_pos))?;
+
+            let mut builder = MetadataBuilder::new();
+
+            match id3v2::read_id3v2(&mut source, &mut builder) {
+                Ok(_) => metadata.push(builder.metadata()),
+                Err(err) => warn!("dsf: failed to read id3v2 tag: {}", err),
+            }
+
+            source.seek(SeekFrom::Start(data_start))?;
+        }
+
+        let mut codec_params = CodecParameters::new();
+
+        codec_params
+            .for_codec(codec)
+            .with_sample_rate(fmt.sample_rate)
+            .with_time_base(TimeBase::new(1, fmt.sample_rate))
+            .with_bits_per_sample(1)
+            .with_channels(channels)
+            .with_n_frames(n_samples)
+            .with_max_frames_per_packet(block_size * 8);
+
+        let mut track = Track::new(0, codec_params);
+        track.gain = metadata.metadata().current().and_then(|rev| GainInfo::from_tags(rev.tags()));
+
+        Ok(DsfReader {
+            reader: source,
+            tracks: vec![track],
+            cues: Vec::new(),
+            metadata,
+            data_start,
+            block_size,
+            n_channels,
+            n_samples,
+            next_block: 0,
+        })
+    }
+
+    fn next_packet(&mut self) -> Result<Packet> {
+        let ts = self.next_block * self.block_size * 8;
+
+        if ts >= self.n_samples {
+            return end_of_stream_error();
+        }
+
+        // The final block group is zero padded to the block size.
+        let dur = (self.n_samples - ts).min(self.block_size * 8);
+        let valid = ((dur + 7) / 8) as usize;
+
+        let pos = self.data_start + self.next_block * self.block_size * self.n_channels;
+
+        if self.reader.pos() != pos {
+            self.reader.seek(SeekFrom::Start(pos))?;
+        }
+
+        let block_size = self.block_size as usize;
+
+        let mut buf = vec![0; valid * self.n_channels as usize];
+
+        for block in buf.chunks_exact_mut(valid) {
+            self.reader.read_buf_exact(block)?;
+            self.reader.ignore_bytes((block_size - valid) as u64)?;
+        }
+
+        self.next_block += 1;
+
+        Ok(Packet::new_from_boxed_slice(0, ts, dur, buf.into_boxed_slice()))
+    }
+
+    fn metadata(&mut self) -> Metadata<'_> {
+        self.metadata.metadata()
+    }
+
+    fn cues(&self) -> &[Cue] {
+        &self.cues
+    }
+
+    fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    fn seek(&mut self, _mode: SeekMode, to: SeekTo) -> Result<SeekedTo> {
+        let params = &self.tracks[0].codec_params;
+
+        let ts = match to {
+            // Frame timestamp given.
+            SeekTo::TimeStamp { ts, .. } => ts,
+            // Time value given, calculate frame timestamp from sample rate.
+            SeekTo::Time { time, .. } => {
+                // Use the sample rate to calculate the frame timestamp. If sample rate is not
+                // known, the seek cannot be completed.
+                if let Some(sample_rate) = params.sample_rate {
+                    TimeBase::new(1, sample_rate).calc_timestamp(time)
+                }
+                else {
+                    return seek_error(SeekErrorKind::Unseekable);
+                }
+            }
+        };
+
+        if ts > self.n_samples {
+            return seek_error(SeekErrorKind::OutOfRange);
+        }
+
+        // Seek to the block group containing the desired timestamp.
+        let block = ts / (self.block_size * 8);
+
+        let pos = self.data_start + block * self.block_size * self.n_channels;
+
+        if !self.reader.is_seekable() {
+            // If the reader does not support seeking, seeks can only be emulated in the forward
+            // direction by reading and discarding data.
+            let current_pos = self.reader.pos();
+
+            if pos < current_pos {
+                return seek_error(SeekErrorKind::ForwardOnly);
+            }
+
+            self.reader.ignore_bytes(pos - current_pos)?;
+        }
+
+        self.next_block = block;
+
+        let actual_ts = block * self.block_size * 8;
+
+        debug!("seeked to packet_ts={} (delta={})", actual_ts, actual_ts as i64 - ts as i64);
+
+        Ok(SeekedTo { track_id: 0, actual_ts, required_ts: ts })
+    }
+
+    fn into_inner(self: Box<Self>) -> MediaSourceStream {
+        self.reader
+    }
+}
@@ -0,0 +1,256 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::f64::consts::PI;
+
+/// The number of bytes (8 taps per byte) of DSD history used by the first decimation stage.
+const STAGE1_BYTES: usize = 12;
+
+/// The number of taps of the intermediate decimate-by-2 stages.
+const HALFBAND_TAPS: usize = 47;
+
+/// The number of taps of the final decimate-by-2 stage.
+const FINAL_TAPS: usize = 95;
+
+/// The Kaiser window shape parameter used for all stages. Yields a stop-band attenuation of
+/// approximately 90 dB.
+const KAISER_BETA: f64 = 9.0;
+
+/// Computes the zeroth-order modified Bessel function of the first kind.
+fn bessel_i0(x: f64) -> f64 {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let mut k = 1.0;
+
+    while term > 1e-12 * sum {
+        term *= (x / (2.0 * k)) * (x / (2.0 * k));
+        sum += term;
+        k += 1.0;
+    }
+
+    sum
+}
+
+/// Designs a linear-phase low-pass FIR filter with unity DC gain using the windowed-sinc method.
+/// The cutoff frequency is a fraction of the sample rate.
+fn design_lowpass(n_taps: usize, cutoff: f64) -> Vec<f64> {
+    let center = (n_taps - 1) as f64 / 2.0;
+    let i0_beta = bessel_i0(KAISER_BETA);
+
+    let mut taps: Vec<f64> = (0..n_taps)
+        .map(|i| {
+            let t = i as f64 - center;
+
+            let sinc =
+                if t == 0.0 { 2.0 * cutoff } else { (2.0 * PI * cutoff * t).sin() / (PI * t) };
+
+            let r = t / center;
+            let window = bessel_i0(KAISER_BETA * (1.0 - r * r).max(0.0).sqrt()) / i0_beta;
+
+            sinc * window
+        })
+        .collect();
+
+    let sum: f64 = taps.iter().sum();
+    taps.iter_mut().for_each(|t| *t /= sum);
+
+    taps
+}
+
+/// The first decimation stage. Converts 1-bit DSD to multi-bit PCM while decimating by 8.
+///
+/// Since the input is 1-bit, the filter output for each byte of history can be pre-computed for
+/// all 256 byte values. Filtering is then reduced to one table look-up per byte of history.
+struct ByteFilter {
+    /// The filter output for each byte of history and each byte value.
+    table: Vec<[f32; 256]>,
+}
+
+impl ByteFilter {
+    fn new() -> Self {
+        // The output of this stage is at 1/8th of the DSD rate. The cutoff is placed at the
+        // Nyquist frequency of the output since the later stages remove everything above the
+        // pass-band, and only images folding into the final pass-band must be attenuated here.
+        let taps = design_lowpass(8 * STAGE1_BYTES, 1.0 / 16.0);
+
+        let table = (0..STAGE1_BYTES)
+            .map(|j| {
+                let mut entry = [0.0; 256];
+
+                for (value, out) in entry.iter_mut().enumerate() {
+                    // Bits are stored most-significant bit first, therefore the least-significant
+                    // bit is the most recent.
+                    let sum: f64 = (0..8)
+                        .map(|bit| {
+                            let tap = taps[8 * j + bit];
+                            if (value >> bit) & 1 == 1 {
+                                tap
+                            }
+                            else {
+                                -tap
+                            }
+                        })
+                        .sum();
+
+                    *out = sum as f32;
+                }
+
+                entry
+            })
+            .collect();
+
+        ByteFilter { table }
+    }
+}
+
+/// A decimate-by-2 FIR filter.
+struct Decimator {
+    taps: Vec<f32>,
+    /// The history of input samples. The history is stored twice so that a contiguous slice of
+    /// the most recent samples is always available.
+    history: Vec<f32>,
+    pos: usize,
+    /// If true, the next input sample produces an output sample.
+    phase: bool,
+}
+
+impl Decimator {
+    fn new(n_taps: usize, cutoff: f64) -> Self {
+        let taps = design_lowpass(n_taps, cutoff).into_iter().map(|t| t as f32).collect();
+
+        Decimator { taps, history: vec![0.0; 2 * n_taps], pos: 0, phase: false }
+    }
+
+    fn reset(&mut self) {
+        self.history.iter_mut().for_each(|s| *s = 0.0);
+        self.pos = 0;
+        self.phase = false;
+    }
+
+    /// Filters and decimates `input`, appending the output samples to `output`.
+    fn process(&mut self, input: &[f32], output: &mut Vec<f32>) {
+        let n_taps = self.taps.len();
+
+        for &sample in input {
+            self.history[self.pos] = sample;
+            self.history[self.pos + n_taps] = sample;
+
+            self.pos = if self.pos == 0 { n_taps - 1 } else { self.pos - 1 };
+
+            if self.phase {
+                let window = &self.history[self.pos + 1..self.pos + 1 + n_taps];
+                output.push(window.iter().zip(&self.taps).map(|(&x, &h)| x * h).sum());
+            }
+
+            self.phase = !self.phase;
+        }
+    }
+}
+
+/// A DSD to PCM converter for a single channel.
+///
+/// The DSD stream is decimated by 8 in a first stage that operates on whole bytes, followed by
+/// zero or more decimate-by-2 stages. A 100% modulated DSD stream converts to a full-scale PCM
+/// signal, therefore, the nominal 0 dB level of a SACD (50% modulation) converts to -6 dBFS.
+///
+/// All filters are linear-phase, therefore the output is delayed by the combined group delay of
+/// the filters.
+pub struct DsdToPcm {
+    stage1: ByteFilter,
+    /// The most recent bytes of the DSD stream, newest first.
+    history: [u8; STAGE1_BYTES],
+    stages: Vec<Decimator>,
+    buf: [Vec<f32>; 2],
+}
+
+impl DsdToPcm {
+    /// Instantiate a converter that decimates the DSD stream by `8 * 2^n_stages`.
+    pub fn new(n_stages: u32) -> Self {
+        let stages = (0..n_stages)
+            .map(|i| {
+                if i == n_stages - 1 {
+                    // The final stage must attenuate everything above the Nyquist frequency of
+                    // the output, while keeping the audible band flat.
+                    Decimator::new(FINAL_TAPS, 0.22)
+                }
+                else {
+                    Decimator::new(HALFBAND_TAPS, 0.25)
+                }
+            })
+            .collect();
+
+        DsdToPcm {
+            stage1: ByteFilter::new(),
+            // An idle DSD stream is an alternating pattern of 1s and 0s.
+            history: [0x69; STAGE1_BYTES],
+            stages,
+            buf: Default::default(),
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.history = [0x69; STAGE1_BYTES];
+        self.stages.iter_mut().for_each(|stage| stage.reset());
+    }
+
+    /// Converts a sequence of most-significant bit first DSD bytes, appending the PCM samples to
+    /// `output`.
+    pub fn process<I>(&mut self, input: I, output: &mut Vec<f32>)
+    where
+        I: Iterator<Item = u8>,
+    {
+        let [a, b] = &mut self.buf;
+
+        a.clear();
+
+        for byte in input {
+            self.history.copy_within(..STAGE1_BYTES - 1, 1);
+            self.history[0] = byte;
+
+            let sum = self
+                .history
+                .iter()
+                .zip(&self.stage1.table)
+                .map(|(&value, entry)| entry[usize::from(value)])
+                .sum();
+
+            a.push(sum);
+        }
+
+        for stage in self.stages.iter_mut() {
+            b.clear();
+            stage.process(a, b);
+            std::mem::swap(a, b);
+        }
+
+        output.extend_from_slice(a);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DsdToPcm;
+
+    #[test]
+    fn verify_dsd_to_pcm_dc() {
+        // A stream of all 1s is a positive full-scale DC signal, while an idle pattern has no DC.
+        let mut converter = DsdToPcm::new(2);
+        let mut output = Vec::new();
+
+        converter.process(std::iter::repeat(0xff).take(512), &mut output);
+
+        assert_eq!(output.len(), 128);
+        assert!((output[127] - 1.0).abs() < 1e-3);
+
+        converter.reset();
+        output.clear();
+
+        converter.process(std::iter::repeat(0x69).take(512), &mut output);
+
+        assert!(output.iter().all(|s| s.abs() < 1e-3));
+    }
+}
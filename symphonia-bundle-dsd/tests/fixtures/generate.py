@@ -0,0 +1,105 @@
+#!/usr/bin/env python3
+#
+# Generates the DSF and DSDIFF test fixtures in this directory.
+#
+# Both fixtures contain the same two channels of DSD64 audio. The DSD bytes of each channel follow a
+# simple pattern, see `dsd_byte`, so that the tests can verify every byte of every packet. The
+# fixtures are deliberately small, and do not contain a meaningful DSD signal.
+#
+# Usage: python3 generate.py
+
+import os
+import struct
+
+SAMPLE_RATE = 2822400
+
+# The number of DSD bytes per channel. Neither fixture's sound data is a multiple of its packet
+# size, so the final packet is always partial.
+N_BYTES = 10000
+
+
+def dsd_byte(ch, i):
+    """Returns the i-th DSD byte of a channel, most-significant bit first."""
+    return (i * 7 + ch * 101) & 0xff
+
+
+def reverse_bits(byte):
+    return int('{:08b}'.format(byte)[::-1], 2)
+
+
+def id3v2_tag(frames):
+    body = b''
+    for frame_id, text in frames:
+        data = b'\x03' + text.encode('utf-8')
+        body += frame_id + struct.pack('>I', len(data)) + b'\x00\x00' + data
+    size = len(body)
+    syncsafe = bytes([(size >> 21) & 0x7f, (size >> 14) & 0x7f, (size >> 7) & 0x7f, size & 0x7f])
+    return b'ID3\x04\x00\x00' + syncsafe + body
+
+
+def generate_dsf(path):
+    # Block size per channel, as used by all DSF files.
+    block_size = 4096
+    n_samples = N_BYTES * 8
+    n_blocks = -(-N_BYTES // block_size)
+
+    data = b''
+    for block in range(n_blocks):
+        for ch in range(2):
+            for i in range(block * block_size, (block + 1) * block_size):
+                # DSF stores 1-bit samples least-significant bit first, and zero pads the final
+                # block.
+                data += bytes([reverse_bits(dsd_byte(ch, i)) if i < N_BYTES else 0])
+
+    tag = id3v2_tag([(b'TIT2', 'DSF Fixture'), (b'TPE1', 'Symphonia')])
+
+    fmt = struct.pack('<4sQIIIIIIQI4x', b'fmt ', 52, 1, 0, 2, 2, SAMPLE_RATE, 1, n_samples,
+                      block_size)
+    data_chunk = struct.pack('<4sQ', b'data', 12 + len(data)) + data
+
+    file_len = 28 + len(fmt) + len(data_chunk) + len(tag)
+    dsd = struct.pack('<4sQQQ', b'DSD ', 28, file_len, 28 + len(fmt) + len(data_chunk))
+
+    with open(path, 'wb') as f:
+        f.write(dsd + fmt + data_chunk + tag)
+
+
+def chunk(chunk_id, body):
+    return struct.pack('>4sQ', chunk_id, len(body)) + body + (b'\x00' if len(body) & 1 else b'')
+
+
+def counted_text(text):
+    data = text.encode('utf-8')
+    return struct.pack('>I', len(data)) + data
+
+
+def generate_dff(path):
+    fver = chunk(b'FVER', struct.pack('>I', 0x01050000))
+
+    prop = chunk(b'PROP', b'SND ' +
+                 chunk(b'FS  ', struct.pack('>I', SAMPLE_RATE)) +
+                 chunk(b'CHNL', struct.pack('>H4s4s', 2, b'SLFT', b'SRGT')) +
+                 chunk(b'CMPR', b'DSD ' + b'\x0enot compressed\x00') +
+                 chunk(b'ABSS', struct.pack('>HBBI', 0, 0, 0, 0)))
+
+    diin = chunk(b'DIIN', chunk(b'DITI', counted_text('DFF Fixture')))
+
+    comment = b'A comment'
+    comt = chunk(b'COMT', struct.pack('>HHBBBBHHI', 1, 2022, 1, 1, 0, 0, 0, 0, len(comment)) +
+                 comment + (b'\x00' if len(comment) & 1 else b''))
+
+    data = bytes(dsd_byte(ch, i) for i in range(N_BYTES) for ch in range(2))
+    sound = chunk(b'DSD ', data)
+
+    tag = chunk(b'ID3 ', id3v2_tag([(b'TPE1', 'Symphonia')]))
+
+    form = b'DSD ' + fver + prop + diin + comt + sound + tag
+
+    with open(path, 'wb') as f:
+        f.write(struct.pack('>4sQ', b'FRM8', len(form)) + form)
+
+
+if __name__ == '__main__':
+    here = os.path.dirname(os.path.abspath(__file__))
+    generate_dsf(os.path.join(here, 'stereo.dsf'))
+    generate_dff(os.path.join(here, 'stereo.dff'))
@@ -0,0 +1,233 @@
+use symphonia_bundle_dsd::{DsdDecoder, DsdOutputMode, DsdiffReader, DsfReader};
+use symphonia_core::audio::{AudioBufferRef, Channels, Signal};
+use symphonia_core::codecs::{Decoder, DecoderOptions};
+use symphonia_core::codecs::{CODEC_TYPE_DSD_LSBF_PLANAR, CODEC_TYPE_DSD_MSBF};
+use symphonia_core::errors::Error;
+use symphonia_core::formats::{FormatOptions, FormatReader, Packet, SeekMode, SeekTo};
+use symphonia_core::io::MediaSourceStream;
+use symphonia_core::meta::{StandardTagKey, Value};
+
+// The fixtures are generated by `fixtures/generate.py`. Both contain two channels of DSD64 audio,
+// 10000 bytes per channel, following the pattern of `dsd_byte`.
+
+const STEREO_DSF: &[u8] = include_bytes!("fixtures/stereo.dsf");
+const STEREO_DFF: &[u8] = include_bytes!("fixtures/stereo.dff");
+
+/// The number of DSD bytes per channel in the fixtures.
+const N_BYTES: u64 = 10000;
+
+/// Returns the i-th DSD byte of a channel, most-significant bit first.
+fn dsd_byte(ch: u64, i: u64) -> u8 {
+    ((i * 7 + ch * 101) & 0xff) as u8
+}
+
+fn open<R: FormatReader>(data: &'static [u8]) -> R {
+    let source = MediaSourceStream::new(Box::new(std::io::Cursor::new(data)), Default::default());
+    R::try_new(source, &FormatOptions::default()).unwrap()
+}
+
+/// Returns the standard tags of all metadata revisions.
+fn std_tags(reader: &mut dyn FormatReader) -> Vec<(StandardTagKey, String)> {
+    let mut metadata = reader.metadata();
+    let mut tags = Vec::new();
+
+    loop {
+        if let Some(rev) = metadata.current() {
+            for tag in rev.tags() {
+                if let (Some(key), Value::String(value)) = (tag.std_key, &tag.value) {
+                    tags.push((key, value.clone()));
+                }
+            }
+        }
+
+        if metadata.pop().is_none() {
+            break;
+        }
+    }
+
+    tags
+}
+
+/// Verifies that a DSF packet contains one block per channel, least-significant bit first.
+fn verify_dsf_packet(packet: &Packet) {
+    let len = ((packet.dur() + 7) / 8) as usize;
+    let first = packet.ts() / 8;
+
+    assert_eq!(packet.buf().len(), 2 * len);
+
+    for (ch, block) in packet.buf().chunks_exact(len).enumerate() {
+        for (i, byte) in block.iter().enumerate() {
+            assert_eq!(byte.reverse_bits(), dsd_byte(ch as u64, first + i as u64));
+        }
+    }
+}
+
+/// Verifies that a DSDIFF packet contains byte interleaved channels, most-significant bit first.
+fn verify_dff_packet(packet: &Packet) {
+    let first = packet.ts() / 8;
+
+    assert_eq!(packet.buf().len() as u64, 2 * (packet.dur() / 8));
+
+    for (i, frame) in packet.buf().chunks_exact(2).enumerate() {
+        assert_eq!(frame[0], dsd_byte(0, first + i as u64));
+        assert_eq!(frame[1], dsd_byte(1, first + i as u64));
+    }
+}
+
+#[test]
+fn verify_dsf_reader() {
+    let mut reader: DsfReader = open(STEREO_DSF);
+
+    let params = reader.tracks()[0].codec_params.clone();
+    assert_eq!(params.codec, CODEC_TYPE_DSD_LSBF_PLANAR);
+    assert_eq!(params.sample_rate, Some(2_822_400));
+    assert_eq!(params.channels, Some(Channels::FRONT_LEFT | Channels::FRONT_RIGHT));
+    assert_eq!(params.n_frames, Some(N_BYTES * 8));
+    assert_eq!(params.max_frames_per_packet, Some(4096 * 8));
+
+    // The ID3v2 tag at the end of the file.
+    let tags = std_tags(&mut reader);
+    assert!(tags.contains(&(StandardTagKey::TrackTitle, "DSF Fixture".to_string())));
+    assert!(tags.contains(&(StandardTagKey::Artist, "Symphonia".to_string())));
+
+    // One packet per block group. The final block group is trimmed to the valid bytes.
+    let mut durations = Vec::new();
+
+    loop {
+        let packet = match reader.next_packet() {
+            Ok(packet) => packet,
+            Err(Error::IoError(err)) if err.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(err) => panic!("unexpected error {:?}", err),
+        };
+
+        assert_eq!(packet.ts(), durations.iter().sum::<u64>());
+        verify_dsf_packet(&packet);
+        durations.push(packet.dur());
+    }
+
+    assert_eq!(durations, [32768, 32768, 14464]);
+}
+
+#[test]
+fn verify_dsf_seek() {
+    let mut reader: DsfReader = open(STEREO_DSF);
+
+    // Seeks land on the start of the block group containing the timestamp.
+    let seeked =
+        reader.seek(SeekMode::Accurate, SeekTo::TimeStamp { ts: 40000, track_id: 0 }).unwrap();
+
+    assert_eq!(seeked.actual_ts, 32768);
+    assert_eq!(seeked.required_ts, 40000);
+
+    let packet = reader.next_packet().unwrap();
+    assert_eq!(packet.ts(), 32768);
+    verify_dsf_packet(&packet);
+
+    // Seek backwards to the final block group.
+    let seeked =
+        reader.seek(SeekMode::Accurate, SeekTo::TimeStamp { ts: 70000, track_id: 0 }).unwrap();
+    assert_eq!(seeked.actual_ts, 65536);
+
+    let packet = reader.next_packet().unwrap();
+    assert_eq!((packet.ts(), packet.dur()), (65536, 14464));
+    verify_dsf_packet(&packet);
+
+    let result = reader.seek(SeekMode::Accurate, SeekTo::TimeStamp { ts: 80001, track_id: 0 });
+    assert!(result.is_err());
+}
+
+#[test]
+fn verify_dff_reader() {
+    let mut reader: DsdiffReader = open(STEREO_DFF);
+
+    let params = reader.tracks()[0].codec_params.clone();
+    assert_eq!(params.codec, CODEC_TYPE_DSD_MSBF);
+    assert_eq!(params.sample_rate, Some(2_822_400));
+    assert_eq!(params.channels, Some(Channels::FRONT_LEFT | Channels::FRONT_RIGHT));
+    assert_eq!(params.n_frames, Some(N_BYTES * 8));
+
+    // The edited master information and comment chunks before the sound data, and the ID3v2 tag
+    // after it.
+    let tags = std_tags(&mut reader);
+    assert!(tags.contains(&(StandardTagKey::TrackTitle, "DFF Fixture".to_string())));
+    assert!(tags.contains(&(StandardTagKey::Comment, "A comment".to_string())));
+    assert!(tags.contains(&(StandardTagKey::Artist, "Symphonia".to_string())));
+
+    let mut durations = Vec::new();
+
+    loop {
+        let packet = match reader.next_packet() {
+            Ok(packet) => packet,
+            Err(Error::IoError(err)) if err.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(err) => panic!("unexpected error {:?}", err),
+        };
+
+        assert_eq!(packet.ts(), durations.iter().sum::<u64>());
+        verify_dff_packet(&packet);
+        durations.push(packet.dur());
+    }
+
+    assert_eq!(durations, [32768, 32768, 14464]);
+}
+
+#[test]
+fn verify_dff_seek() {
+    let mut reader: DsdiffReader = open(STEREO_DFF);
+
+    // Sound data is byte interleaved, therefore seeks land on a byte boundary.
+    let seeked =
+        reader.seek(SeekMode::Accurate, SeekTo::TimeStamp { ts: 12345, track_id: 0 }).unwrap();
+
+    assert_eq!(seeked.actual_ts, 12344);
+
+    let packet = reader.next_packet().unwrap();
+    assert_eq!((packet.ts(), packet.dur()), (12344, 32768));
+    verify_dff_packet(&packet);
+
+    let packet = reader.next_packet().unwrap();
+    assert_eq!((packet.ts(), packet.dur()), (45112, 32768));
+    verify_dff_packet(&packet);
+
+    // The final packet is trimmed to the end of the sound data.
+    let packet = reader.next_packet().unwrap();
+    assert_eq!((packet.ts(), packet.dur()), (77880, 2120));
+    verify_dff_packet(&packet);
+
+    let result = reader.seek(SeekMode::Accurate, SeekTo::TimeStamp { ts: 80001, track_id: 0 });
+    assert!(result.is_err());
+}
+
+#[test]
+fn verify_decoder_output_mode() {
+    let mut reader: DsfReader = open(STEREO_DSF);
+
+    let params = reader.tracks()[0].codec_params.clone();
+
+    // DoP is selected by the decoder options.
+    let options = DecoderOptions { dsd_over_pcm: true, ..Default::default() };
+    let mut decoder = DsdDecoder::try_new(&params, &options).unwrap();
+    assert_eq!(decoder.output_mode(), DsdOutputMode::Dop);
+
+    let packet = reader.next_packet().unwrap();
+
+    match decoder.decode(&packet).unwrap() {
+        AudioBufferRef::S32(buf) => {
+            assert_eq!(buf.frames(), 2048);
+            assert_eq!(buf.spec().rate, 2_822_400 / 16);
+
+            for ch in 0..2 {
+                for (i, &sample) in buf.chan(ch).iter().enumerate() {
+                    let marker = if i & 1 == 0 { 0x05 } else { 0xfa };
+                    let b1 = dsd_byte(ch as u64, 2 * i as u64);
+                    let b2 = dsd_byte(ch as u64, 2 * i as u64 + 1);
+                    assert_eq!(sample, i32::from_be_bytes([marker, b1, b2, 0]));
+                }
+            }
+        }
+        _ => panic!("unexpected sample format"),
+    }
+
+    // Otherwise, DSD is converted to PCM.
+    let decoder = DsdDecoder::try_new(&params, &Default::default()).unwrap();
+    assert_eq!(decoder.output_mode(), DsdOutputMode::Pcm);
+}
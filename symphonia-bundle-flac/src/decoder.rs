@@ -8,15 +8,16 @@
 use std::cmp;
 use std::num::Wrapping;
 
-use symphonia_core::audio::{AsAudioBufferRef, AudioBuffer, AudioBufferRef};
-use symphonia_core::audio::{Signal, SignalSpec};
+use symphonia_core::audio::{AsAudioBufferRef, AudioBuffer, AudioBufferMut, AudioBufferRef};
+use symphonia_core::audio::{InterleavedBufferMut, Signal, SignalSpec};
 use symphonia_core::codecs::{
     CodecDescriptor, CodecParameters, VerificationCheck, CODEC_TYPE_FLAC,
 };
 use symphonia_core::codecs::{Decoder, DecoderOptions, FinalizeResult};
-use symphonia_core::errors::{decode_error, unsupported_error, Result};
+use symphonia_core::conv::dither::Dynamic;
+use symphonia_core::errors::{decode_error, limit_error, unsupported_error, Result};
 use symphonia_core::formats::Packet;
-use symphonia_core::io::{BitReaderLtr, BufReader, ReadBitsLtr};
+use symphonia_core::io::{BitReaderLtr, BufReader, ReadBitsLtr, ReadBytes};
 use symphonia_core::support_codec;
 use symphonia_core::units::TimeBase;
 use symphonia_core::util::bits::sign_extend_leq32_to_i32;
@@ -29,7 +30,7 @@ use super::validate::Validator;
 
 fn decorrelate_left_side(left: &[i32], side: &mut [i32]) {
     for (s, l) in side.iter_mut().zip(left) {
-        *s = *l - *s;
+        *s = l.wrapping_sub(*s);
     }
 }
 
@@ -68,14 +69,25 @@ fn decorrelate_mid_side(mid: &mut [i32], side: &mut [i32]) {
         //      - R = (2*M + (S%2) - S) / 2
         let mid = (*m << 1) | (*s & 1);
         let side = *s;
-        *m = (mid + side) >> 1;
-        *s = (mid - side) >> 1;
+        *m = mid.wrapping_add(side) >> 1;
+        *s = mid.wrapping_sub(side) >> 1;
+    }
+}
+
+fn decorrelate_mid_side_wide(mid: &mut [i32], side: &mut [i32], wide_side: &[i64]) {
+    for ((m, s), &ws) in mid.iter_mut().zip(side).zip(wide_side) {
+        // Identical to the narrow case, except that the side channel has 33 bits per sample and
+        // the intermediate computations require 34 bits. The decorrelated samples are guaranteed
+        // to fit in 32 bits.
+        let mid = (i64::from(*m) << 1) | (ws & 1);
+        *m = ((mid + ws) >> 1) as i32;
+        *s = ((mid - ws) >> 1) as i32;
     }
 }
 
 fn decorrelate_right_side(right: &[i32], side: &mut [i32]) {
     for (s, r) in side.iter_mut().zip(right) {
-        *s += *r;
+        *s = s.wrapping_add(*r);
     }
 }
 
@@ -84,16 +96,30 @@ pub struct FlacDecoder {
     params: CodecParameters,
     is_validating: bool,
     validator: Validator,
+    /// If true, the decoder verifies the CRC16 of each frame since the demuxer does not.
+    is_verifying_crc: bool,
     buf: AudioBuffer<i32>,
+    /// Scratch buffer for side channels that require more than 32 bits per sample.
+    wide_buf: Vec<i64>,
 }
 
 impl FlacDecoder {
-    fn decode_inner(&mut self, packet: &Packet) -> Result<()> {
+    /// Decodes a packet into `dst`, or the internal audio buffer if `dst` is `None`. The decoded
+    /// samples are not yet shifted to occupy the full 32 bits per sample, instead, the required
+    /// shift is returned.
+    fn decode_inner(&mut self, packet: &Packet, dst: Option<&mut AudioBuffer<i32>>) -> Result<u32> {
         let mut reader = packet.as_buf_reader();
 
         // Synchronize to a frame and get the synchronization code.
         let sync = sync_frame(&mut reader)?;
 
+        // If the packet data integrity is not guaranteed by the demuxer, verify the frame CRC16
+        // before decoding anything. The CRC covers the entire frame, starting at the
+        // synchronization code, up-to the frame footer.
+        if self.is_verifying_crc {
+            verify_frame_crc(&packet.buf()[reader.pos() as usize - 2..])?;
+        }
+
         let header = read_frame_header(&mut reader, sync)?;
 
         // Use the bits per sample and sample rate as stated in the frame header, falling back to
@@ -108,6 +134,36 @@ impl FlacDecoder {
             return decode_error("flac: bits per sample not provided");
         };
 
+        // The number of channels in a frame may not differ from that of the stream.
+        let n_channels = match header.channel_assignment {
+            ChannelAssignment::Independant(channels) => channels as usize,
+            _ => 2,
+        };
+
+        if n_channels != self.buf.spec().channels.count() {
+            return decode_error("flac: frame channel count does not match the stream");
+        }
+
+        // A frame may contain more samples than the stream information block states if the stream
+        // information block is incorrect. Grow the audio buffer if that is the case.
+        let n_samples = usize::from(header.block_num_samples);
+
+        let buf = match dst {
+            Some(dst) => {
+                if n_samples > dst.capacity() {
+                    return limit_error("flac: frame block size exceeds the destination capacity");
+                }
+                dst
+            }
+            None => {
+                if n_samples > self.buf.capacity() {
+                    warn!("flac: frame block size exceeds the stream maximum");
+                    self.buf = AudioBuffer::new(n_samples as u64, *self.buf.spec());
+                }
+                &mut self.buf
+            }
+        };
+
         // trace!("frame: [{:?}] strategy={:?}, n_samples={}, bps={}, channels={:?}",
         //     header.block_sequence,
         //     header.blocking_strategy,
@@ -116,8 +172,8 @@ impl FlacDecoder {
         //     &header.channel_assignment);
 
         // Reserve a writeable chunk in the buffer equal to the number of samples in the block.
-        self.buf.clear();
-        self.buf.render_reserved(Some(header.block_num_samples as usize));
+        buf.clear();
+        buf.render_reserved(Some(n_samples));
 
         // Only Bitstream reading for subframes.
         {
@@ -128,31 +184,38 @@ impl FlacDecoder {
             match header.channel_assignment {
                 ChannelAssignment::Independant(channels) => {
                     for i in 0..channels as usize {
-                        read_subframe(&mut bs, bits_per_sample, self.buf.chan_mut(i))?;
+                        read_subframe(&mut bs, bits_per_sample, buf.chan_mut(i))?;
                     }
                 }
                 // For Left/Side, Mid/Side, and Right/Side channel configurations, the Side
                 // (Difference) channel requires an extra bit per sample.
                 ChannelAssignment::LeftSide => {
-                    let (left, side) = self.buf.chan_pair_mut(0, 1);
+                    let (left, side) = buf.chan_pair_mut(0, 1);
 
                     read_subframe(&mut bs, bits_per_sample, left)?;
-                    read_subframe(&mut bs, bits_per_sample + 1, side)?;
+                    read_side_subframe(&mut bs, bits_per_sample + 1, side, &mut self.wide_buf)?;
 
                     decorrelate_left_side(left, side);
                 }
                 ChannelAssignment::MidSide => {
-                    let (mid, side) = self.buf.chan_pair_mut(0, 1);
+                    let (mid, side) = buf.chan_pair_mut(0, 1);
 
                     read_subframe(&mut bs, bits_per_sample, mid)?;
-                    read_subframe(&mut bs, bits_per_sample + 1, side)?;
 
-                    decorrelate_mid_side(mid, side);
+                    // The mid/side decorrelation requires all bits of the side channel.
+                    if bits_per_sample < 32 {
+                        read_subframe(&mut bs, bits_per_sample + 1, side)?;
+                        decorrelate_mid_side(mid, side);
+                    }
+                    else {
+                        read_subframe_wide(&mut bs, bits_per_sample + 1, side, &mut self.wide_buf)?;
+                        decorrelate_mid_side_wide(mid, side, &self.wide_buf);
+                    }
                 }
                 ChannelAssignment::RightSide => {
-                    let (side, right) = self.buf.chan_pair_mut(0, 1);
+                    let (side, right) = buf.chan_pair_mut(0, 1);
 
-                    read_subframe(&mut bs, bits_per_sample + 1, side)?;
+                    read_side_subframe(&mut bs, bits_per_sample + 1, side, &mut self.wide_buf)?;
                     read_subframe(&mut bs, bits_per_sample, right)?;
 
                     decorrelate_right_side(right, side);
@@ -162,18 +225,13 @@ impl FlacDecoder {
 
         // Feed the validator if validation is enabled.
         if self.is_validating {
-            self.validator.update(&self.buf, bits_per_sample);
+            self.validator.update(buf, bits_per_sample);
         }
 
         // The decoder uses a 32bit sample format as a common denominator, but that doesn't mean
-        // the encoded audio samples are actually 32bit. Shift all samples in the output buffer
-        // so that regardless the encoded bits/sample, the output is always 32bits/sample.
-        if bits_per_sample < 32 {
-            let shift = 32 - bits_per_sample;
-            self.buf.transform(|sample| sample << shift);
-        }
-
-        Ok(())
+        // the encoded audio samples are actually 32bit. All samples must be shifted so that
+        // regardless the encoded bits/sample, the output is always 32bits/sample.
+        Ok(32 - bits_per_sample)
     }
 }
 
@@ -212,16 +270,14 @@ impl Decoder for FlacDecoder {
         let spec = SignalSpec::new(info.sample_rate, info.channels);
         let buf = AudioBuffer::new(u64::from(info.block_len_max), spec);
 
-        // TODO: Verify packet integrity if the demuxer is not.
-        // if !params.packet_data_integrity {
-        //     return unsupported_error("flac: packet integrity is required");
-        // }
-
         Ok(FlacDecoder {
-            params,
             is_validating: options.verify,
             validator: Default::default(),
+            // Verify packet integrity if the demuxer is not.
+            is_verifying_crc: !params.packet_data_integrity,
             buf,
+            wide_buf: Vec::new(),
+            params,
         })
     }
 
@@ -238,12 +294,106 @@ impl Decoder for FlacDecoder {
     }
 
     fn decode(&mut self, packet: &Packet) -> Result<AudioBufferRef<'_>> {
-        if let Err(e) = self.decode_inner(packet) {
-            self.buf.clear();
-            Err(e)
+        match self.decode_inner(packet, None) {
+            Ok(shift) => {
+                shift_samples(shift, &mut self.buf);
+                Ok(self.buf.as_audio_buffer_ref())
+            }
+            Err(e) => {
+                self.buf.clear();
+                Err(e)
+            }
         }
-        else {
-            Ok(self.buf.as_audio_buffer_ref())
+    }
+
+    fn decode_into(
+        &mut self,
+        packet: &Packet,
+        dst: AudioBufferMut<'_>,
+        dither: &mut Dynamic,
+    ) -> Result<usize> {
+        // Check the caller's buffer before the packet is consumed.
+        if dst.spec() != self.buf.spec() {
+            return unsupported_error("flac: destination signal specification does not match");
+        }
+
+        if dst.capacity() < self.buf.capacity() {
+            return limit_error("flac: destination buffer capacity is too small");
+        }
+
+        match dst {
+            // The frame is decoded directly into the caller's buffer if it uses the native sample
+            // format of the decoder.
+            AudioBufferMut::S32(dst) => {
+                self.buf.clear();
+
+                match self.decode_inner(packet, Some(dst)) {
+                    Ok(shift) => {
+                        shift_samples(shift, dst);
+                        Ok(dst.frames())
+                    }
+                    Err(e) => {
+                        dst.clear();
+                        Err(e)
+                    }
+                }
+            }
+            dst => {
+                let decoded = self.decode(packet)?;
+                decoded.copy_to(dst, dither)?;
+                Ok(decoded.frames())
+            }
+        }
+    }
+
+    fn decode_interleaved(
+        &mut self,
+        packet: &Packet,
+        dst: InterleavedBufferMut<'_>,
+        dither: &mut Dynamic,
+    ) -> Result<usize> {
+        // Check the caller's buffer before the packet is consumed.
+        if dst.len() < self.buf.capacity() * self.buf.spec().channels.count() {
+            return limit_error("flac: destination buffer is too small");
+        }
+
+        match dst {
+            // Subframes are decoded in planar order. However, the samples of each channel are
+            // shifted while they are interleaved into a caller's buffer of the native sample
+            // format, instead of in a separate pass.
+            InterleavedBufferMut::S32(dst) => {
+                let shift = match self.decode_inner(packet, None) {
+                    Ok(shift) => shift,
+                    Err(e) => {
+                        self.buf.clear();
+                        return Err(e);
+                    }
+                };
+
+                let n_channels = self.buf.spec().channels.count();
+                let n_frames = self.buf.frames();
+
+                // The internal buffer does not contain the fully decoded audio, and must be
+                // cleared regardless of the outcome.
+                let result = if dst.len() < n_frames * n_channels {
+                    limit_error("flac: destination buffer is too small")
+                }
+                else {
+                    for ch in 0..n_channels {
+                        let src = self.buf.chan(ch);
+
+                        for (d, &s) in dst[ch..].iter_mut().step_by(n_channels).zip(src) {
+                            *d = s << shift;
+                        }
+                    }
+                    Ok(n_frames)
+                };
+
+                self.buf.clear();
+
+                result
+            }
+            dst => self.decode(packet)?.copy_to_interleaved(dst, dither),
         }
     }
 
@@ -286,6 +436,13 @@ impl Decoder for FlacDecoder {
     }
 }
 
+/// Shifts the samples in `buf` left by `shift` bits.
+fn shift_samples(shift: u32, buf: &mut AudioBuffer<i32>) {
+    if shift > 0 {
+        buf.transform(|sample| sample << shift);
+    }
+}
+
 // Subframe business
 
 #[derive(Debug)]
@@ -296,7 +453,17 @@ enum SubFrameType {
     Linear(u32),
 }
 
-fn read_subframe<B: ReadBitsLtr>(bs: &mut B, frame_bps: u32, buf: &mut [i32]) -> Result<()> {
+struct SubFrameHeader {
+    subframe_type: SubFrameType,
+    bps: u32,
+    dropped_bps: u32,
+}
+
+fn read_subframe_header<B: ReadBitsLtr>(
+    bs: &mut B,
+    frame_bps: u32,
+    n_samples: usize,
+) -> Result<SubFrameHeader> {
     // First sub-frame bit must always 0.
     if bs.read_bool()? {
         return decode_error("flac: subframe padding is not 0");
@@ -322,6 +489,16 @@ fn read_subframe<B: ReadBitsLtr>(bs: &mut B, frame_bps: u32, buf: &mut [i32]) ->
         }
     };
 
+    // The warm-up samples of a predictor may not exceed the number of samples in the block.
+    match subframe_type {
+        SubFrameType::FixedLinear(order) | SubFrameType::Linear(order)
+            if order as usize > n_samples =>
+        {
+            return decode_error("flac: predictor order exceeds block size");
+        }
+        _ => (),
+    }
+
     // Bit 7 of the sub-frame header designates if there are any dropped (wasted in FLAC terms)
     // bits per sample in the audio sub-block. If the bit is set, unary decode the number of
     // dropped bits per sample.
@@ -332,6 +509,10 @@ fn read_subframe<B: ReadBitsLtr>(bs: &mut B, frame_bps: u32, buf: &mut [i32]) ->
     // the encoder will truncate `dropped_bps` of lower order bits for every sample in a sub-block.
     // The decoder simply needs to shift left all samples by `dropped_bps` after decoding the
     // sub-frame and obtaining the truncated audio sub-block samples.
+    if dropped_bps > frame_bps {
+        return decode_error("flac: subframe wasted bits exceeds bits per sample");
+    }
+
     let bps = frame_bps - dropped_bps;
 
     // trace!("\tsubframe: type={:?}, bps={}, dropped_bps={}",
@@ -339,6 +520,13 @@ fn read_subframe<B: ReadBitsLtr>(bs: &mut B, frame_bps: u32, buf: &mut [i32]) ->
     //     bps,
     //     dropped_bps);
 
+    Ok(SubFrameHeader { subframe_type, bps, dropped_bps })
+}
+
+fn read_subframe<B: ReadBitsLtr>(bs: &mut B, frame_bps: u32, buf: &mut [i32]) -> Result<()> {
+    let SubFrameHeader { subframe_type, bps, dropped_bps } =
+        read_subframe_header(bs, frame_bps, buf.len())?;
+
     match subframe_type {
         SubFrameType::Constant => decode_constant(bs, bps, buf)?,
         SubFrameType::Verbatim => decode_verbatim(bs, bps, buf)?,
@@ -352,6 +540,84 @@ fn read_subframe<B: ReadBitsLtr>(bs: &mut B, frame_bps: u32, buf: &mut [i32]) ->
     Ok(())
 }
 
+/// Reads a side (difference) channel subframe. For 32 bits per sample audio, the side channel
+/// requires 33 bits per sample. In that case, the subframe is decoded with the wide decoder and
+/// truncated to 32 bits. This is lossless for the left/side and right/side decorrelation since it
+/// only uses wrapping additions and subtractions, and the decorrelated samples fit in 32 bits.
+fn read_side_subframe<B: ReadBitsLtr>(
+    bs: &mut B,
+    frame_bps: u32,
+    buf: &mut [i32],
+    wide_buf: &mut Vec<i64>,
+) -> Result<()> {
+    if frame_bps <= 32 {
+        read_subframe(bs, frame_bps, buf)
+    }
+    else {
+        read_subframe_wide(bs, frame_bps, buf, wide_buf)?;
+
+        for (sample, &wide) in buf.iter_mut().zip(wide_buf.iter()) {
+            *sample = wide as i32;
+        }
+
+        Ok(())
+    }
+}
+
+/// Reads a subframe with more than 32 bits per sample into `wide_buf`. The residuals of such a
+/// subframe still fit within 32 bits, therefore, `buf` is used as scratch space for them.
+fn read_subframe_wide<B: ReadBitsLtr>(
+    bs: &mut B,
+    frame_bps: u32,
+    buf: &mut [i32],
+    wide_buf: &mut Vec<i64>,
+) -> Result<()> {
+    let SubFrameHeader { subframe_type, bps, dropped_bps } =
+        read_subframe_header(bs, frame_bps, buf.len())?;
+
+    wide_buf.clear();
+    wide_buf.resize(buf.len(), 0);
+
+    match subframe_type {
+        SubFrameType::Constant => {
+            let const_sample = bs.read_bits_leq64_signed(bps)?;
+
+            for sample in wide_buf.iter_mut() {
+                *sample = const_sample;
+            }
+        }
+        SubFrameType::Verbatim => decode_verbatim_wide(bs, bps, wide_buf)?,
+        SubFrameType::FixedLinear(order) => {
+            decode_verbatim_wide(bs, bps, &mut wide_buf[..order as usize])?;
+            decode_residual(bs, order, buf)?;
+            copy_residuals_wide(order as usize, buf, wide_buf);
+
+            fixed_predict_wide(order as usize, wide_buf);
+        }
+        SubFrameType::Linear(order) => {
+            decode_verbatim_wide(bs, bps, &mut wide_buf[..order as usize])?;
+
+            let mut qlp_coeffs = [0i32; 32];
+
+            let coeff_shift = read_qlp_coeffs(bs, &mut qlp_coeffs[..order as usize])?;
+
+            decode_residual(bs, order, buf)?;
+            copy_residuals_wide(order as usize, buf, wide_buf);
+
+            lpc_predict_wide(&qlp_coeffs[..order as usize], coeff_shift, wide_buf);
+        }
+    }
+
+    // Shift the samples to account for the dropped bits.
+    if dropped_bps > 0 {
+        for sample in wide_buf.iter_mut() {
+            *sample = sample.wrapping_shl(dropped_bps);
+        }
+    }
+
+    Ok(())
+}
+
 #[inline(always)]
 fn samples_shl(shift: u32, buf: &mut [i32]) {
     if shift > 0 {
@@ -379,6 +645,20 @@ fn decode_verbatim<B: ReadBitsLtr>(bs: &mut B, bps: u32, buf: &mut [i32]) -> Res
     Ok(())
 }
 
+fn decode_verbatim_wide<B: ReadBitsLtr>(bs: &mut B, bps: u32, buf: &mut [i64]) -> Result<()> {
+    for sample in buf.iter_mut() {
+        *sample = bs.read_bits_leq64_signed(bps)?;
+    }
+
+    Ok(())
+}
+
+fn copy_residuals_wide(order: usize, residuals: &[i32], buf: &mut [i64]) {
+    for (sample, &residual) in buf[order..].iter_mut().zip(&residuals[order..]) {
+        *sample = i64::from(residual);
+    }
+}
+
 fn decode_fixed_linear<B: ReadBitsLtr>(
     bs: &mut B,
     bps: u32,
@@ -401,14 +681,11 @@ fn decode_fixed_linear<B: ReadBitsLtr>(
     Ok(())
 }
 
-fn decode_linear<B: ReadBitsLtr>(bs: &mut B, bps: u32, order: u32, buf: &mut [i32]) -> Result<()> {
-    // The order of the Linear Predictor should be between 1 and 32.
-    debug_assert!(order > 0 && order <= 32);
-
-    // The first `order` samples are encoded verbatim to warm-up the LPC decoder.
-    decode_verbatim(bs, bps, &mut buf[0..order as usize])?;
-
-    // Quantized linear predictor (QLP) coefficients precision in bits.
+/// Reads the quantized linear predictor (QLP) coefficient precision, shift, and coefficients.
+/// The coefficients are stored in reverse order in `coeffs`. Returns the coefficient shift, which
+/// may be negative.
+fn read_qlp_coeffs<B: ReadBitsLtr>(bs: &mut B, coeffs: &mut [i32]) -> Result<i32> {
+    // QLP coefficients precision in bits.
     let qlp_precision = bs.read_bits_leq32(4)? + 1;
     if qlp_precision > 15 {
         return decode_error("flac: qlp precision set to reserved value");
@@ -417,59 +694,74 @@ fn decode_linear<B: ReadBitsLtr>(bs: &mut B, bps: u32, order: u32, buf: &mut [i3
     // QLP coefficients bit shift [-16, 15].
     let qlp_coeff_shift = sign_extend_leq32_to_i32(bs.read_bits_leq32(5)?, 5);
 
-    if qlp_coeff_shift >= 0 {
-        // Pick the best sized linear predictor to use based on the order. Most if not all FLAC
-        // streams apppear to have an order <= 12. Specializing a predictor for orders <= 6 and
-        // <= 12 appears to give the best performance.
-        //
-        // TODO: Reduce code duplication here.
-        if order <= 4 {
-            let mut qlp_coeffs = [0i32; 4];
+    for c in coeffs.iter_mut().rev() {
+        *c = sign_extend_leq32_to_i32(bs.read_bits_leq32(qlp_precision)?, qlp_precision);
+    }
 
-            for c in qlp_coeffs[4 - order as usize..4].iter_mut().rev() {
-                *c = sign_extend_leq32_to_i32(bs.read_bits_leq32(qlp_precision)?, qlp_precision);
-            }
+    Ok(qlp_coeff_shift)
+}
 
-            decode_residual(bs, order, buf)?;
+/// Shifts the predicted value right by the coefficient shift. A negative shift is a left shift of
+/// the predicted value.
+///
+/// The predicted value is not shifted left until after the sum of products is calculated since
+/// shifting the coefficients instead could overflow the sum. The shifted value wraps, as does the
+/// reconstructed sample, such that malformed streams may not cause a panic.
+#[inline(always)]
+fn shift_prediction(predicted: i64, coeff_shift: i32) -> i64 {
+    if coeff_shift >= 0 {
+        predicted >> coeff_shift
+    }
+    else {
+        predicted.wrapping_shl(coeff_shift.unsigned_abs())
+    }
+}
 
-            lpc_predict_4(order as usize, &qlp_coeffs, qlp_coeff_shift as u32, buf)?;
-        }
-        else if order <= 8 {
-            let mut qlp_coeffs = [0i32; 8];
+fn decode_linear<B: ReadBitsLtr>(bs: &mut B, bps: u32, order: u32, buf: &mut [i32]) -> Result<()> {
+    // The order of the Linear Predictor should be between 1 and 32.
+    debug_assert!(order > 0 && order <= 32);
 
-            for c in qlp_coeffs[8 - order as usize..8].iter_mut().rev() {
-                *c = sign_extend_leq32_to_i32(bs.read_bits_leq32(qlp_precision)?, qlp_precision);
-            }
+    // The first `order` samples are encoded verbatim to warm-up the LPC decoder.
+    decode_verbatim(bs, bps, &mut buf[0..order as usize])?;
 
-            decode_residual(bs, order, buf)?;
+    // Pick the best sized linear predictor to use based on the order. Most if not all FLAC
+    // streams apppear to have an order <= 12. Specializing a predictor for orders <= 6 and
+    // <= 12 appears to give the best performance.
+    if order <= 4 {
+        let mut qlp_coeffs = [0i32; 4];
 
-            lpc_predict_8(order as usize, &qlp_coeffs, qlp_coeff_shift as u32, buf)?;
-        }
-        else if order <= 12 {
-            let mut qlp_coeffs = [0i32; 12];
+        let coeff_shift = read_qlp_coeffs(bs, &mut qlp_coeffs[4 - order as usize..4])?;
 
-            for c in qlp_coeffs[12 - order as usize..12].iter_mut().rev() {
-                *c = sign_extend_leq32_to_i32(bs.read_bits_leq32(qlp_precision)?, qlp_precision);
-            }
+        decode_residual(bs, order, buf)?;
 
-            decode_residual(bs, order, buf)?;
+        lpc_predict_4(order as usize, &qlp_coeffs, coeff_shift, buf)?;
+    }
+    else if order <= 8 {
+        let mut qlp_coeffs = [0i32; 8];
 
-            lpc_predict_12(order as usize, &qlp_coeffs, qlp_coeff_shift as u32, buf)?;
-        }
-        else {
-            let mut qlp_coeffs = [0i32; 32];
+        let coeff_shift = read_qlp_coeffs(bs, &mut qlp_coeffs[8 - order as usize..8])?;
 
-            for c in qlp_coeffs[32 - order as usize..32].iter_mut().rev() {
-                *c = sign_extend_leq32_to_i32(bs.read_bits_leq32(qlp_precision)?, qlp_precision);
-            }
+        decode_residual(bs, order, buf)?;
 
-            decode_residual(bs, order, buf)?;
+        lpc_predict_8(order as usize, &qlp_coeffs, coeff_shift, buf)?;
+    }
+    else if order <= 12 {
+        let mut qlp_coeffs = [0i32; 12];
 
-            lpc_predict_32(order as usize, &qlp_coeffs, qlp_coeff_shift as u32, buf)?;
-        }
+        let coeff_shift = read_qlp_coeffs(bs, &mut qlp_coeffs[12 - order as usize..12])?;
+
+        decode_residual(bs, order, buf)?;
+
+        lpc_predict_12(order as usize, &qlp_coeffs, coeff_shift, buf)?;
     }
     else {
-        return unsupported_error("flac: lpc shifts less than 0 are not supported");
+        let mut qlp_coeffs = [0i32; 32];
+
+        let coeff_shift = read_qlp_coeffs(bs, &mut qlp_coeffs[32 - order as usize..32])?;
+
+        decode_residual(bs, order, buf)?;
+
+        lpc_predict_32(order as usize, &qlp_coeffs, coeff_shift, buf)?;
     }
 
     Ok(())
@@ -556,9 +848,18 @@ fn decode_rice_partition<B: ReadBitsLtr>(
 
         // Read each rice encoded residual and store in buffer.
         for sample in buf.iter_mut() {
-            let q = bs.read_unary_zeros()?;
-            let r = bs.read_bits_leq32(rice_param)?;
-            *sample = rice_signed_to_i32((q << rice_param) | r);
+            let q = u64::from(bs.read_unary_zeros()?);
+            let r = u64::from(bs.read_bits_leq32(rice_param)?);
+
+            // Residuals are limited to 32 bits, but the quotient is unbounded. Use 64-bit
+            // arithmetic so that an out-of-range residual can be detected.
+            let word = (q << rice_param) | r;
+
+            if word > u64::from(u32::MAX) {
+                return decode_error("flac: residual exceeds 32 bits");
+            }
+
+            *sample = rice_signed_to_i32(word as u32);
         }
     }
     else {
@@ -639,7 +940,7 @@ fn fixed_predict(order: u32, buf: &mut [i32]) -> Result<()> {
         // s(i) = 1*s(i),
         1 => {
             for i in 1..buf.len() {
-                buf[i] += buf[i - 1];
+                buf[i] = buf[i].wrapping_add(buf[i - 1]);
             }
         }
         // A 2nd order predictor uses the polynomial: s(i) = 2*s(i-1) - 1*s(i-2).
@@ -647,7 +948,7 @@ fn fixed_predict(order: u32, buf: &mut [i32]) -> Result<()> {
             for i in 2..buf.len() {
                 let a = Wrapping(-1) * Wrapping(i64::from(buf[i - 2]));
                 let b = Wrapping(2) * Wrapping(i64::from(buf[i - 1]));
-                buf[i] += (a + b).0 as i32;
+                buf[i] = buf[i].wrapping_add((a + b).0 as i32);
             }
         }
         // A 3rd order predictor uses the polynomial: s(i) = 3*s(i-1) - 3*s(i-2) + 1*s(i-3).
@@ -656,7 +957,7 @@ fn fixed_predict(order: u32, buf: &mut [i32]) -> Result<()> {
                 let a = Wrapping(1) * Wrapping(i64::from(buf[i - 3]));
                 let b = Wrapping(-3) * Wrapping(i64::from(buf[i - 2]));
                 let c = Wrapping(3) * Wrapping(i64::from(buf[i - 1]));
-                buf[i] += (a + b + c).0 as i32;
+                buf[i] = buf[i].wrapping_add((a + b + c).0 as i32);
             }
         }
         // A 4th order predictor uses the polynomial:
@@ -667,7 +968,7 @@ fn fixed_predict(order: u32, buf: &mut [i32]) -> Result<()> {
                 let b = Wrapping(4) * Wrapping(i64::from(buf[i - 3]));
                 let c = Wrapping(-6) * Wrapping(i64::from(buf[i - 2]));
                 let d = Wrapping(4) * Wrapping(i64::from(buf[i - 1]));
-                buf[i] += (a + b + c + d).0 as i32;
+                buf[i] = buf[i].wrapping_add((a + b + c + d).0 as i32);
             }
         }
         _ => unreachable!(),
@@ -686,7 +987,7 @@ macro_rules! lpc_predictor {
         fn $func_name(
             order: usize,
             coeffs: &[i32; $order],
-            coeff_shift: u32,
+            coeff_shift: i32,
             buf: &mut [i32],
         ) -> Result<()> {
             // Order must be less than or equal to the number of coefficients.
@@ -706,7 +1007,7 @@ macro_rules! lpc_predictor {
                     .map(|(&c, &sample)| c as i64 * sample as i64)
                     .sum::<i64>();
 
-                buf[i] += (predicted >> coeff_shift) as i32;
+                buf[i] = buf[i].wrapping_add(shift_prediction(predicted, coeff_shift) as i32);
             }
 
             if buf.len() <= $order {
@@ -733,7 +1034,7 @@ macro_rules! lpc_predictor {
                     predicted += a + b + c + d;
                 }
 
-                buf[i] += (predicted >> coeff_shift) as i32;
+                buf[i] = buf[i].wrapping_add(shift_prediction(predicted, coeff_shift) as i32);
             }
 
             Ok(())
@@ -741,7 +1042,92 @@ macro_rules! lpc_predictor {
     };
 }
 
+/// Fixed predictor for subframes with more than 32 bits per sample.
+fn fixed_predict_wide(order: usize, buf: &mut [i64]) {
+    // The coefficients of the fixed predictors, in the same order as the samples.
+    const COEFFS: [&[i64]; 5] = [&[], &[1], &[-1, 2], &[1, -3, 3], &[-1, 4, -6, 4]];
+
+    let coeffs = COEFFS[order];
+
+    for i in order..buf.len() {
+        let predicted = coeffs
+            .iter()
+            .zip(&buf[i - order..i])
+            .fold(0i64, |acc, (&c, &sample)| acc.wrapping_add(c.wrapping_mul(sample)));
+
+        buf[i] = buf[i].wrapping_add(predicted);
+    }
+}
+
+/// Linear predictor for subframes with more than 32 bits per sample. Coefficients must be stored
+/// in reverse order.
+fn lpc_predict_wide(coeffs: &[i32], coeff_shift: i32, buf: &mut [i64]) {
+    let order = coeffs.len();
+
+    for i in order..buf.len() {
+        let predicted = coeffs
+            .iter()
+            .zip(&buf[i - order..i])
+            .fold(0i64, |acc, (&c, &sample)| acc.wrapping_add(i64::from(c).wrapping_mul(sample)));
+
+        buf[i] = buf[i].wrapping_add(shift_prediction(predicted, coeff_shift));
+    }
+}
+
 lpc_predictor!(lpc_predict_32, 32);
 lpc_predictor!(lpc_predict_12, 12);
 lpc_predictor!(lpc_predict_8, 8);
 lpc_predictor!(lpc_predict_4, 4);
+
+#[cfg(test)]
+mod tests {
+    use symphonia_core::io::BitReaderLtr;
+
+    use super::decode_linear;
+
+    /// Packs a sequence of (value, width) fields into bytes, most-significant bit first.
+    fn pack(fields: &[(u32, u32)]) -> Vec<u8> {
+        let mut bits = Vec::new();
+
+        for &(value, width) in fields {
+            bits.extend((0..width).rev().map(|i| (value >> i) & 1 == 1));
+        }
+
+        bits.chunks(8)
+            .map(|byte| {
+                byte.iter().enumerate().fold(0, |acc, (i, &bit)| acc | (u8::from(bit) << (7 - i)))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn verify_lpc_negative_shift_does_not_overflow() {
+        let mut fields = Vec::new();
+
+        // 32 warm-up samples of the minimum 32-bit value.
+        fields.extend(std::iter::repeat((0x8000_0000, 32)).take(32));
+
+        // A QLP precision of 15 bits, a shift of -16, and 32 coefficients of the minimum 15-bit
+        // value. Shifting the coefficients before summing the products would overflow 64 bits.
+        fields.push((14, 4));
+        fields.push((0x10, 5));
+        fields.extend(std::iter::repeat((0x4000, 15)).take(32));
+
+        // Rice coded residuals with a parameter of 0 and a single partition. All residuals are 0.
+        fields.push((0, 2));
+        fields.push((0, 4));
+        fields.push((0, 4));
+        fields.extend(std::iter::repeat((1, 1)).take(32));
+
+        let data = pack(&fields);
+
+        let mut bs = BitReaderLtr::new(&data);
+        let mut buf = [0i32; 64];
+
+        decode_linear(&mut bs, 32, 32, &mut buf).unwrap();
+
+        // The sum of products is 2^50. Shifted left by 16, it wraps to 0.
+        assert!(buf[..32].iter().all(|&s| s == i32::MIN));
+        assert_eq!(buf[32], 0);
+    }
+}
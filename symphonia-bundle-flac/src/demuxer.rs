@@ -14,9 +14,11 @@ use symphonia_core::errors::{decode_error, seek_error, unsupported_error, Result
 use symphonia_core::formats::prelude::*;
 use symphonia_core::formats::util::{SeekIndex, SeekSearchResult};
 use symphonia_core::io::*;
-use symphonia_core::meta::{Metadata, MetadataBuilder, MetadataLog};
+use symphonia_core::meta::{GainInfo, Metadata, MetadataBuilder, MetadataLog};
 use symphonia_core::probe::{Descriptor, Instantiate, QueryDescriptor};
 
+use symphonia_metadata::trailing::read_trailing_metadata;
+
 use symphonia_utils_xiph::flac::metadata::*;
 
 use log::{debug, info};
@@ -35,6 +37,8 @@ pub struct FlacReader {
     cues: Vec<Cue>,
     index: Option<SeekIndex>,
     first_frame_offset: u64,
+    /// The position where the audio data ends, and trailing tags, if any, begin.
+    audio_end: Option<u64>,
     parser: PacketParser,
 }
 
@@ -118,11 +122,27 @@ impl FlacReader {
             }
         }
 
+        let revision = metadata_builder.metadata();
+
+        // Derive the gain of the track from the Vorbis comment.
+        if let Some(track) = tracks.first_mut() {
+            track.gain = GainInfo::from_tags(revision.tags());
+        }
+
         // Commit any read metadata to the metadata log.
         let mut metadata = MetadataLog::default();
-        metadata.push(metadata_builder.metadata());
-
-        Ok(FlacReader { reader, metadata, tracks, cues, index, first_frame_offset: 0, parser })
+        metadata.push(revision);
+
+        Ok(FlacReader {
+            reader,
+            metadata,
+            tracks,
+            cues,
+            index,
+            first_frame_offset: 0,
+            audio_end: None,
+            parser,
+        })
     }
 }
 
@@ -166,11 +186,22 @@ impl FormatReader for FlacReader {
         // metadata blocks have been read.
         flac.first_frame_offset = flac.reader.pos();
 
+        // Tags appended after the audio data are not part of the FLAC specification, but are
+        // commonly added by taggers for other formats. Exclude them from the audio data so that
+        // they are not scanned for frames.
+        flac.audio_end = read_trailing_metadata(&mut flac.reader, &mut flac.metadata)?;
+
         Ok(flac)
     }
 
     fn next_packet(&mut self) -> Result<Packet> {
-        let parsed = self.parser.parse(&mut self.reader)?;
+        let parsed = match self.audio_end {
+            Some(end) => {
+                let len = end.saturating_sub(self.reader.pos());
+                self.parser.parse(&mut ScopedStream::new(&mut self.reader, len))?
+            }
+            None => self.parser.parse(&mut self.reader)?,
+        };
 
         Ok(Packet::new_from_boxed_slice(0, parsed.ts, parsed.dur, parsed.buf))
     }
@@ -229,7 +260,10 @@ impl FormatReader for FlacReader {
             // lower bound is set to the byte offset of the first frame, while the upper bound is
             // set to the length of the stream.
             let mut start_byte_offset = self.first_frame_offset;
-            let mut end_byte_offset = self.reader.seek(SeekFrom::End(0))?;
+            let mut end_byte_offset = match self.audio_end {
+                Some(end) => end,
+                None => self.reader.seek(SeekFrom::End(0))?,
+            };
 
             // If there is an index, use it to refine the binary search range.
             if let Some(ref index) = self.index {
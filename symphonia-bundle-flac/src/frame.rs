@@ -5,7 +5,7 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use symphonia_core::checksum::Crc8Ccitt;
+use symphonia_core::checksum::{Crc16Ansi, Crc8Ccitt};
 use symphonia_core::errors::{decode_error, Result};
 use symphonia_core::io::{MediaSourceStream, Monitor, MonitorStream, ReadBytes};
 
@@ -178,6 +178,7 @@ pub fn read_frame_header<B: ReadBytes>(reader: &mut B, sync: u16) -> Result<Fram
         0x4 => Some(16),
         0x5 => Some(20),
         0x6 => Some(24),
+        0x7 => Some(32),
         _ => {
             return decode_error("flac: bits per sample set to reserved value");
         }
@@ -212,6 +213,25 @@ pub fn read_frame_header<B: ReadBytes>(reader: &mut B, sync: u16) -> Result<Fram
     })
 }
 
+/// Verifies the CRC16 of a complete frame. The buffer must start at the frame synchronization
+/// code and end with the frame footer.
+pub fn verify_frame_crc(buf: &[u8]) -> Result<()> {
+    if buf.len() < 2 {
+        return decode_error("flac: frame is too short");
+    }
+
+    let (frame, footer) = buf.split_at(buf.len() - 2);
+
+    let mut crc16 = Crc16Ansi::new(0);
+    crc16.process_buf_bytes(frame);
+
+    if crc16.crc() != u16::from_be_bytes([footer[0], footer[1]]) && cfg!(not(fuzzing)) {
+        return decode_error("flac: computed frame CRC does not match expected CRC");
+    }
+
+    Ok(())
+}
+
 /// A very quick check if the provided buffer is likely be a FLAC frame header.
 pub fn is_likely_frame_header(buf: &[u8]) -> bool {
     // let is_variable = (buf[1] & 0x1) == 1;
@@ -228,7 +248,7 @@ pub fn is_likely_frame_header(buf: &[u8]) -> bool {
         return false;
     }
 
-    if (buf[1] & 0x0e == 0x6) || (buf[1] & 0x0e == 0x0e) {
+    if buf[1] & 0x0e == 0x6 {
         return false;
     }
 
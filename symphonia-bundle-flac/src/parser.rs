@@ -9,7 +9,7 @@ use std::cmp::min;
 use std::collections::VecDeque;
 
 use symphonia_core::checksum::Crc16Ansi;
-use symphonia_core::errors::{Error, Result};
+use symphonia_core::errors::{end_of_stream_error, Error, Result};
 use symphonia_core::io::{BufReader, Monitor, ReadBytes};
 use symphonia_core::util::bits;
 use symphonia_utils_xiph::flac::metadata::StreamInfo;
@@ -44,6 +44,8 @@ pub struct PacketParser {
     n_frames: usize,
     last_seq: u64,
     last_read_err: Option<Error>,
+    /// The blocking strategy of the stream, if known. True if the stream is variable-blocksize.
+    is_variable: Option<bool>,
 }
 
 impl Default for PacketParser {
@@ -58,6 +60,7 @@ impl Default for PacketParser {
             n_frames: 0,
             last_seq: 0,
             last_read_err: None,
+            is_variable: None,
         }
     }
 }
@@ -95,6 +98,7 @@ impl PacketParser {
     /// Reset the packet parser for a new stream.
     pub fn hard_reset(&mut self, stream_info: StreamInfo) {
         self.stream_info = stream_info;
+        self.is_variable = None;
         self.soft_reset()
     }
 
@@ -137,7 +141,14 @@ impl PacketParser {
 
         // trace!("fetch data: buf_write={}, new_buf_write={}", self.buf_write, new_buf_write);
 
-        self.buf_write += reader.read_buf(&mut self.buf[self.buf_write..new_buf_write])?;
+        let len = reader.read_buf(&mut self.buf[self.buf_write..new_buf_write])?;
+
+        // A reader limited to the audio data may return 0 bytes instead of an error at its end.
+        if len == 0 {
+            return end_of_stream_error();
+        }
+
+        self.buf_write += len;
 
         Ok(())
     }
@@ -250,19 +261,29 @@ impl PacketParser {
 
         let iter = self.fragments.iter().zip(&mut indicies[0..n_fragments]).enumerate();
 
-        let is_fixed = self.stream_info.block_len_min == self.stream_info.block_len_max;
+        // The blocking strategy may not change within a stream. If a frame has been parsed, use
+        // its blocking strategy. Otherwise, guess it from the stream information block.
+        let is_fixed = match self.is_variable {
+            Some(is_variable) => !is_variable,
+            None => self.stream_info.block_len_min == self.stream_info.block_len_max,
+        };
 
         for (i, (fragment, index)) in iter {
             // Stream parameter scoring: The optional parameters of the stream information block
-            // match that of the fragment.
-            if let Some(sample_rate) = fragment.header.sample_rate {
-                if let Some(bps) = fragment.header.bits_per_sample {
-                    if sample_rate == self.stream_info.sample_rate
-                        && bps == self.stream_info.bits_per_sample
-                    {
-                        score_par |= 1 << i;
-                    }
-                }
+            // match that of the fragment. If a parameter is not provided by the fragment, then it
+            // is taken from the stream information block, and therefore always matches.
+            let is_sample_rate_match = match fragment.header.sample_rate {
+                Some(sample_rate) => sample_rate == self.stream_info.sample_rate,
+                None => true,
+            };
+
+            let is_bps_match = match fragment.header.bits_per_sample {
+                Some(bps) => bps == self.stream_info.bits_per_sample,
+                None => true,
+            };
+
+            if is_sample_rate_match && is_bps_match {
+                score_par |= 1 << i;
             }
 
             // Fragment length scoring: The fragment's sample length is within the range provided in
@@ -381,8 +402,27 @@ impl PacketParser {
                         let dur = u64::from(frag.header.block_num_samples);
 
                         let ts = match &frag.header.block_sequence {
-                            BlockSequence::BySample(sample) => *sample,
-                            BlockSequence::ByFrame(frame) => u64::from(*frame) * dur,
+                            BlockSequence::BySample(sample) => {
+                                self.is_variable = Some(true);
+                                self.last_seq = *sample;
+                                *sample
+                            }
+                            BlockSequence::ByFrame(frame) => {
+                                self.is_variable = Some(false);
+                                self.last_seq = u64::from(*frame);
+
+                                // In a fixed-blocksize stream, all frames except the last have the
+                                // same length. Since the last frame may be shorter, use the stream's
+                                // block length if it is known.
+                                let block_len = match self.stream_info.block_len_max {
+                                    len if len > 0 && len == self.stream_info.block_len_min => {
+                                        u64::from(len)
+                                    }
+                                    _ => dur,
+                                };
+
+                                u64::from(*frame) * block_len
+                            }
                         };
 
                         // Remove the fragments that have been consumed to parse this frame.
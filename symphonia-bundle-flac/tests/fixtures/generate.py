@@ -0,0 +1,387 @@
+#!/usr/bin/env python3
+#
+# Generates the FLAC test fixtures in this directory.
+#
+# Each fixture exercises a decoding corner case that the reference encoder rarely, or never,
+# produces. The fixtures are encoded by hand, and the STREAMINFO MD5 is computed from the source
+# samples. Therefore, a decoder that reproduces the MD5 decoded the fixture correctly.
+#
+# Usage: python3 generate.py
+
+import hashlib
+import math
+import os
+import struct
+
+
+class BitWriter:
+    def __init__(self):
+        self.bits = []
+
+    def write(self, value, width):
+        value &= (1 << width) - 1
+        for i in reversed(range(width)):
+            self.bits.append((value >> i) & 1)
+
+    def write_unary(self, value):
+        self.bits.extend([0] * value)
+        self.bits.append(1)
+
+    def to_bytes(self):
+        bits = self.bits + [0] * (-len(self.bits) % 8)
+        out = bytearray()
+        for i in range(0, len(bits), 8):
+            byte = 0
+            for bit in bits[i:i + 8]:
+                byte = (byte << 1) | bit
+            out.append(byte)
+        return bytes(out)
+
+
+def crc8(data):
+    crc = 0
+    for byte in data:
+        crc ^= byte
+        for _ in range(8):
+            crc = ((crc << 1) ^ 0x07) & 0xff if crc & 0x80 else (crc << 1) & 0xff
+    return crc
+
+
+def crc16(data):
+    crc = 0
+    for byte in data:
+        crc ^= byte << 8
+        for _ in range(8):
+            crc = ((crc << 1) ^ 0x8005) & 0xffff if crc & 0x8000 else (crc << 1) & 0xffff
+    return crc
+
+
+def utf8_encode(value):
+    if value < 0x80:
+        return bytes([value])
+    n = 2
+    while value >= 1 << (5 * n + 1):
+        n += 1
+    out = []
+    for _ in range(n - 1):
+        out.append(0x80 | (value & 0x3f))
+        value >>= 6
+    out.append(((0xff00 >> n) & 0xff) | value)
+    return bytes(reversed(out))
+
+
+def zigzag(value):
+    return 2 * value if value >= 0 else -2 * value - 1
+
+
+def signed_width(values):
+    width = 0
+    for v in values:
+        while not (-(1 << (width - 1)) <= v < (1 << (width - 1)) if width > 0 else v == 0):
+            width += 1
+    return width
+
+
+def write_residual(bw, residual, order, n_samples, partition_order=0, method=0, escape=()):
+    bw.write(method, 2)
+    bw.write(partition_order, 4)
+    param_width = 4 if method == 0 else 5
+    n_part = n_samples >> partition_order
+    start = 0
+    for p in range(1 << partition_order):
+        end = (p + 1) * n_part - order
+        part = residual[start:end]
+        start = end
+        if p in escape:
+            width = signed_width(part)
+            bw.write((1 << param_width) - 1, param_width)
+            bw.write(width, 5)
+            for v in part:
+                bw.write(v, width)
+            continue
+        best = None
+        for k in range((1 << param_width) - 1):
+            size = sum((zigzag(v) >> k) + 1 + k for v in part)
+            if best is None or size < best[0]:
+                best = (size, k)
+        k = best[1]
+        bw.write(k, param_width)
+        for v in part:
+            u = zigzag(v)
+            assert u < 1 << 32
+            bw.write_unary(u >> k)
+            bw.write(u, k)
+
+
+FIXED_COEFFS = [[], [1], [2, -1], [3, -3, 1], [4, -6, 4, -1]]
+
+
+def predict(samples, coeffs, shift):
+    order = len(coeffs)
+    residual = []
+    for i in range(order, len(samples)):
+        acc = sum(c * samples[i - 1 - j] for j, c in enumerate(coeffs))
+        pred = acc >> shift if shift >= 0 else acc << -shift
+        r = samples[i] - pred
+        assert -(1 << 31) <= r < (1 << 31), "residual exceeds 32 bits"
+        residual.append(r)
+    return residual
+
+
+def subframe(bw, bps, samples, kind, wasted=0, **kw):
+    bw.write(0, 1)
+    assert all(s % (1 << wasted) == 0 for s in samples)
+    samples = [s >> wasted for s in samples]
+    sbps = bps - wasted
+
+    if kind == 'constant':
+        type_enc = 0x00
+    elif kind == 'verbatim':
+        type_enc = 0x01
+    elif kind == 'fixed':
+        type_enc = 0x08 | kw['order']
+    elif kind == 'lpc':
+        type_enc = 0x20 | (len(kw['coeffs']) - 1)
+    bw.write(type_enc, 6)
+
+    if wasted > 0:
+        bw.write(1, 1)
+        bw.write_unary(wasted - 1)
+    else:
+        bw.write(0, 1)
+
+    rk = {k: kw[k] for k in ('partition_order', 'method', 'escape') if k in kw}
+
+    if kind == 'constant':
+        assert len(set(samples)) == 1
+        bw.write(samples[0], sbps)
+    elif kind == 'verbatim':
+        for s in samples:
+            bw.write(s, sbps)
+    elif kind == 'fixed':
+        order = kw['order']
+        for s in samples[:order]:
+            bw.write(s, sbps)
+        write_residual(bw, predict(samples, FIXED_COEFFS[order], 0), order, len(samples), **rk)
+    elif kind == 'lpc':
+        coeffs, precision, shift = kw['coeffs'], kw['precision'], kw['shift']
+        order = len(coeffs)
+        for s in samples[:order]:
+            bw.write(s, sbps)
+        bw.write(precision - 1, 4)
+        bw.write(shift, 5)
+        for c in coeffs:
+            assert -(1 << (precision - 1)) <= c < (1 << (precision - 1))
+            bw.write(c, precision)
+        write_residual(bw, predict(samples, coeffs, shift), order, len(samples), **rk)
+
+
+BLOCK_SIZE_CODES = {192: 1, 576: 2, 1152: 3, 2304: 4, 4608: 5, 256: 8, 512: 9, 1024: 10, 2048: 11,
+                    4096: 12, 8192: 13, 16384: 14, 32768: 15}
+
+SAMPLE_RATE_CODES = {88200: 1, 176400: 2, 192000: 3, 8000: 4, 16000: 5, 22050: 6, 24000: 7,
+                     32000: 8, 44100: 9, 48000: 10, 96000: 11}
+
+BPS_CODES = {8: 1, 12: 2, 16: 4, 20: 5, 24: 6, 32: 7}
+
+ASSIGNMENT = {'left_side': 8, 'right_side': 9, 'mid_side': 10}
+
+
+def frame(seq, variable, rate, bps, channels, subframes, assignment=None, header_params=True):
+    """Encodes a frame. `channels` are the coded channels, and `subframes` a list of (kind, kw)."""
+    n = len(channels[0])
+    hdr = bytearray(struct.pack('>H', 0xfff8 | int(variable)))
+
+    extra = b''
+    if n in BLOCK_SIZE_CODES:
+        bs_code = BLOCK_SIZE_CODES[n]
+    elif n <= 256:
+        bs_code, extra = 6, bytes([n - 1])
+    else:
+        bs_code, extra = 7, struct.pack('>H', n - 1)
+
+    if header_params and rate in SAMPLE_RATE_CODES:
+        sr_code, sr_extra = SAMPLE_RATE_CODES[rate], b''
+    elif header_params and rate % 10 == 0 and rate // 10 < 65536:
+        sr_code, sr_extra = 0xe, struct.pack('>H', rate // 10)
+    else:
+        sr_code, sr_extra = 0, b''
+
+    bps_code = BPS_CODES.get(bps, 0) if header_params else 0
+    ch_code = ASSIGNMENT[assignment] if assignment else len(channels) - 1
+
+    hdr.append((bs_code << 4) | sr_code)
+    hdr.append((ch_code << 4) | (bps_code << 1))
+    hdr += utf8_encode(seq)
+    hdr += extra + sr_extra
+    hdr.append(crc8(hdr))
+
+    bw = BitWriter()
+    for i, (samples, (kind, kw)) in enumerate(zip(channels, subframes)):
+        side = (assignment == 'left_side' and i == 1) or (assignment == 'right_side' and i == 0) \
+            or (assignment == 'mid_side' and i == 1)
+        subframe(bw, bps + int(side), samples, kind, **kw)
+
+    data = bytes(hdr) + bw.to_bytes()
+    return data + struct.pack('>H', crc16(data))
+
+
+def stream(rate, bps, n_channels, block_min, block_max, frames, pcm):
+    n_samples = len(pcm[0])
+
+    width = (bps + 7) // 8
+    md5 = hashlib.md5()
+    for i in range(n_samples):
+        for ch in pcm:
+            md5.update((ch[i] & ((1 << (8 * width)) - 1)).to_bytes(width, 'little'))
+
+    info = BitWriter()
+    info.write(block_min, 16)
+    info.write(block_max, 16)
+    info.write(min(len(f) for f in frames), 24)
+    info.write(max(len(f) for f in frames), 24)
+    info.write(rate, 20)
+    info.write(n_channels - 1, 3)
+    info.write(bps - 1, 5)
+    info.write(n_samples, 36)
+    info = info.to_bytes() + md5.digest()
+
+    out = b'fLaC' + bytes([0x80, 0, 0, len(info)]) + info
+    return out + b''.join(frames)
+
+
+def sine(n, amp, freq, rate, phase=0.0, offset=0):
+    return [int(round(amp * math.sin(2 * math.pi * freq * (i + offset) / rate + phase)))
+            for i in range(n)]
+
+
+def negative_lpc_shift():
+    """16-bit mono, fixed blocksize. LPC subframes use negative quantization shifts."""
+    rate, bps, block = 44100, 16, 1024
+    pcm = sine(3500, 12000, 440, rate)
+    # Small amplitude signal for the extreme -16 shift.
+    pcm[2048:3072] = [(i // 7) % 3 - 1 for i in range(1024)]
+
+    frames = []
+    for f, start in enumerate(range(0, len(pcm), block)):
+        s = pcm[start:start + block]
+        if f == 2:
+            kw = dict(coeffs=[1], precision=2, shift=-16)
+        elif f == 3:
+            kw = dict(coeffs=[1, 0], precision=15, shift=-1, partition_order=2)
+        else:
+            kw = dict(coeffs=[1, -1, 0], precision=4, shift=-1, partition_order=3)
+        frames.append(frame(f, False, rate, bps, [s], [('lpc', kw)]))
+
+    return stream(rate, bps, 1, block, block, frames, [pcm])
+
+
+def stereo_32bit():
+    """32-bit stereo. The side channel of each decorrelated frame requires 33 bits per sample."""
+    rate, bps, block = 48000, 32, 1152
+    n = 4 * block
+    w = 2 * math.pi * 100 / rate
+    left = sine(n, 2**31 - 1, 100, rate)
+    right = [-l - 1 if l > -2**31 else l for l in left]
+    # Wasted bits in the final frame.
+    for i in range(3 * block, n):
+        left[i] &= ~0xff
+        right[i] &= ~0xff
+
+    c1 = int(round(2 * math.cos(w) * 2**13))
+    recurrence = dict(coeffs=[c1, -2**13], precision=15, shift=13)
+
+    frames = []
+    for f in range(4):
+        l = left[f * block:(f + 1) * block]
+        r = right[f * block:(f + 1) * block]
+        side = [a - b for a, b in zip(l, r)]
+        mid = [(a + b) >> 1 for a, b in zip(l, r)]
+
+        if f == 0:
+            chans, subs, asg = [l, side], [('fixed', dict(order=2)), ('verbatim', {})], 'left_side'
+        elif f == 1:
+            chans = [side, r]
+            subs = [('lpc', dict(recurrence, partition_order=1)), ('fixed', dict(order=3))]
+            asg = 'right_side'
+        elif f == 2:
+            chans = [mid, side]
+            subs = [('fixed', dict(order=1, method=1)), ('lpc', recurrence)]
+            asg = 'mid_side'
+        else:
+            chans = [mid, side]
+            subs = [('fixed', dict(order=2, wasted=7)), ('fixed', dict(order=2, wasted=8))]
+            asg = 'mid_side'
+
+        frames.append(frame(f, False, rate, bps, chans, subs, asg))
+
+    return stream(rate, bps, 2, block, block, frames, [left, right])
+
+
+def variable_blocksize():
+    """16-bit stereo, variable blocksize with a wide range of block sizes."""
+    rate, bps = 44100, 16
+    sizes = [16, 4608, 192, 777, 576, 1000, 256, 4096, 5]
+    n = sum(sizes)
+    left = sine(n, 20000, 1000, rate)
+    right = sine(n, 15000, 330, rate, phase=1.0)
+
+    frames = []
+    pos = 0
+    for i, size in enumerate(sizes):
+        l = left[pos:pos + size]
+        r = right[pos:pos + size]
+        order = 2 if size >= 16 else 1
+        subs = [('fixed', dict(order=order)), ('fixed', dict(order=order))]
+        if i % 3 == 1:
+            side = [a - b for a, b in zip(l, r)]
+            frames.append(frame(pos, True, rate, bps, [l, side], subs, 'left_side'))
+        else:
+            frames.append(frame(pos, True, rate, bps, [l, r], subs))
+        pos += size
+
+    return stream(rate, bps, 2, min(sizes[:-1]), max(sizes), frames, [left, right])
+
+
+def streaminfo_params():
+    """14-bit mono at 7350 Hz. Frame headers defer the sample rate and bit depth to STREAMINFO.
+    Also exercises escaped partitions, the 5-bit Rice parameter method, and constant subframes."""
+    rate, bps, block = 7350, 14, 2048
+    pcm = sine(4 * block, 8000, 50, rate)
+    # Noise burst to force large Rice parameters.
+    seed = 1
+    for i in range(block, 2 * block):
+        seed = (seed * 1103515245 + 12345) & 0x7fffffff
+        pcm[i] = (seed >> 8) % 16384 - 8192
+    pcm[2 * block:3 * block] = [-1234] * block
+    # Zero residuals in the final partitions.
+    pcm[3 * block + 1024:] = [0] * 1024
+
+    frames = []
+    for f in range(4):
+        s = pcm[f * block:(f + 1) * block]
+        if f == 0:
+            sub = ('fixed', dict(order=4, partition_order=4, escape=(0, 5, 15)))
+        elif f == 1:
+            sub = ('fixed', dict(order=0, method=1, partition_order=1))
+        elif f == 2:
+            sub = ('constant', {})
+        else:
+            sub = ('fixed', dict(order=1, partition_order=2, escape=(2, 3)))
+        frames.append(frame(f, False, rate, bps, [s], [sub], header_params=False))
+
+    return stream(rate, bps, 1, block, block, frames, [pcm])
+
+
+FIXTURES = {
+    'negative_lpc_shift.flac': negative_lpc_shift,
+    'stereo_32bit.flac': stereo_32bit,
+    'variable_blocksize.flac': variable_blocksize,
+    'streaminfo_params.flac': streaminfo_params,
+}
+
+if __name__ == '__main__':
+    here = os.path.dirname(os.path.abspath(__file__))
+    for name, func in FIXTURES.items():
+        with open(os.path.join(here, name), 'wb') as f:
+            f.write(func())
@@ -0,0 +1,275 @@
+use symphonia_bundle_flac::{FlacDecoder, FlacReader};
+use symphonia_core::audio::{AsAudioBufferMut, AudioBuffer, AudioBufferRef, Channels};
+use symphonia_core::audio::{Signal, SignalSpec};
+use symphonia_core::codecs::{Decoder, DecoderOptions};
+use symphonia_core::conv::dither::Dynamic;
+use symphonia_core::errors::{Error, Result};
+use symphonia_core::formats::{FormatOptions, FormatReader, Packet, SeekMode, SeekTo};
+use symphonia_core::io::MediaSourceStream;
+use symphonia_core::meta::StandardTagKey;
+
+// The synthetic fixtures are generated by `fixtures/generate.py` to exercise corner cases that
+// encoders rarely produce. The files in `fixtures/reference` were produced by libFLAC. Each file's
+// stream information block contains the MD5 checksum of the source audio, therefore, a successful
+// verification means the file was decoded bit-exactly.
+
+fn open<T: AsRef<[u8]> + Send + Sync + 'static>(data: T) -> FlacReader {
+    let source = MediaSourceStream::new(Box::new(std::io::Cursor::new(data)), Default::default());
+    FlacReader::try_new(source, &FormatOptions::default()).unwrap()
+}
+
+/// Decodes the entire fixture with verification enabled, and returns the number of decoded frames.
+fn decode_and_verify<T: AsRef<[u8]> + Send + Sync + 'static>(data: T) -> u64 {
+    let mut reader = open(data);
+
+    let params = reader.tracks()[0].codec_params.clone();
+
+    let mut decoder =
+        FlacDecoder::try_new(&params, &DecoderOptions { verify: true, ..Default::default() })
+            .unwrap();
+
+    let mut n_frames = 0;
+    let mut next_ts = 0;
+
+    loop {
+        let packet = match reader.next_packet() {
+            Ok(packet) => packet,
+            Err(Error::IoError(err)) if err.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(err) => panic!("unexpected error {:?}", err),
+        };
+
+        assert_eq!(packet.ts(), next_ts);
+        next_ts += packet.dur();
+
+        let decoded = decoder.decode(&packet).unwrap();
+
+        assert_eq!(decoded.frames() as u64, packet.dur());
+        n_frames += packet.dur();
+    }
+
+    assert_eq!(Some(n_frames), params.n_frames);
+    assert_eq!(decoder.finalize().verify_ok, Some(true));
+
+    n_frames
+}
+
+#[test]
+fn verify_negative_lpc_shift() {
+    assert_eq!(decode_and_verify(include_bytes!("fixtures/negative_lpc_shift.flac")), 3500);
+}
+
+#[test]
+fn verify_stereo_32bit() {
+    assert_eq!(decode_and_verify(include_bytes!("fixtures/stereo_32bit.flac")), 4608);
+}
+
+#[test]
+fn verify_variable_blocksize() {
+    assert_eq!(decode_and_verify(include_bytes!("fixtures/variable_blocksize.flac")), 11526);
+}
+
+#[test]
+fn verify_streaminfo_params() {
+    assert_eq!(decode_and_verify(include_bytes!("fixtures/streaminfo_params.flac")), 8192);
+}
+
+#[test]
+fn verify_reference_pop() {
+    assert_eq!(decode_and_verify(include_bytes!("fixtures/reference/pop.flac")), 100);
+}
+
+#[test]
+fn verify_reference_short() {
+    assert_eq!(decode_and_verify(include_bytes!("fixtures/reference/short.flac")), 4);
+}
+
+#[test]
+fn verify_reference_wasted_bits() {
+    assert_eq!(decode_and_verify(include_bytes!("fixtures/reference/wasted_bits.flac")), 4410);
+}
+
+#[test]
+fn verify_reference_sine_440hz_stereo() {
+    assert_eq!(
+        decode_and_verify(include_bytes!("fixtures/reference/sine_440hz_stereo.flac")),
+        44100
+    );
+}
+
+#[test]
+fn verify_reference_24bit() {
+    let data = include_bytes!("fixtures/reference/audacity24bit_level8.flac");
+    let reader = open(data);
+
+    assert_eq!(reader.tracks()[0].codec_params.bits_per_sample, Some(24));
+    assert!(decode_and_verify(data) > 0);
+}
+
+/// Verifies every committed fixture, such that a fixture added without a dedicated test is still
+/// decoded and checked against its MD5 checksum.
+#[test]
+fn verify_all_fixtures() {
+    let root = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+
+    let mut paths = Vec::new();
+
+    for dir in &[root.clone(), root.join("reference")] {
+        paths.extend(
+            std::fs::read_dir(dir)
+                .unwrap()
+                .map(|entry| entry.unwrap().path())
+                .filter(|path| path.extension().map_or(false, |ext| ext == "flac")),
+        );
+    }
+
+    paths.sort();
+
+    assert_eq!(paths.len(), 9);
+
+    for path in paths {
+        assert!(decode_and_verify(std::fs::read(&path).unwrap()) > 0, "{}", path.display());
+    }
+}
+
+#[test]
+fn verify_decode_into() -> Result<()> {
+    let data = include_bytes!("fixtures/reference/sine_440hz_stereo.flac");
+
+    let mut reader = open(data);
+    let params = reader.tracks()[0].codec_params.clone();
+
+    let mut decoder = FlacDecoder::try_new(&params, &DecoderOptions::default())?;
+    let mut direct = FlacDecoder::try_new(&params, &DecoderOptions::default())?;
+    let mut converted = FlacDecoder::try_new(&params, &DecoderOptions::default())?;
+    let mut interleaved = FlacDecoder::try_new(&params, &DecoderOptions::default())?;
+
+    let capacity = decoder.last_decoded().capacity() as u64;
+    let spec = *decoder.last_decoded().spec();
+
+    let mut direct_buf = AudioBuffer::<i32>::new(capacity, spec);
+    let mut converted_buf = AudioBuffer::<f32>::new(capacity, spec);
+    let mut interleaved_buf = vec![0i32; 2 * capacity as usize];
+    let mut expected_f32 = AudioBuffer::<f32>::new(capacity, spec);
+
+    let mut dither = Dynamic::default();
+
+    while let Ok(packet) = reader.next_packet() {
+        let expected = decoder.decode(&packet)?;
+
+        // The native sample format is decoded directly into the caller's buffer.
+        let n_frames =
+            direct.decode_into(&packet, direct_buf.as_audio_buffer_mut(), &mut dither)?;
+
+        assert_eq!(n_frames, expected.frames());
+        assert_eq!(direct.last_decoded().frames(), 0);
+
+        let n_frames = interleaved.decode_interleaved(
+            &packet,
+            (&mut interleaved_buf[..]).into(),
+            &mut dither,
+        )?;
+
+        assert_eq!(n_frames, expected.frames());
+
+        let n_frames =
+            converted.decode_into(&packet, converted_buf.as_audio_buffer_mut(), &mut dither)?;
+
+        assert_eq!(n_frames, expected.frames());
+
+        expected.copy_to(expected_f32.as_audio_buffer_mut(), &mut dither)?;
+
+        if let AudioBufferRef::S32(expected) = expected {
+            for ch in 0..2 {
+                assert_eq!(direct_buf.chan(ch), expected.chan(ch));
+                assert_eq!(converted_buf.chan(ch), expected_f32.chan(ch));
+
+                for (i, &s) in expected.chan(ch).iter().enumerate() {
+                    assert_eq!(interleaved_buf[2 * i + ch], s);
+                }
+            }
+        }
+        else {
+            panic!("expected signed 32-bit samples");
+        }
+    }
+
+    // An incompatible buffer is rejected without decoding.
+    let mut mono = AudioBuffer::<i32>::new(capacity, SignalSpec::new(44100, Channels::FRONT_LEFT));
+    let packet = Packet::new_from_slice(0, 0, 0, &[]);
+
+    assert!(direct.decode_into(&packet, mono.as_audio_buffer_mut(), &mut dither).is_err());
+
+    // Including buffers that would be converted into.
+    let mut small = AudioBuffer::<f32>::new(capacity - 1, spec);
+    let mut short = vec![0f32; 2 * capacity as usize - 1];
+
+    assert!(converted.decode_into(&packet, small.as_audio_buffer_mut(), &mut dither).is_err());
+    assert!(converted.decode_interleaved(&packet, (&mut short[..]).into(), &mut dither).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn verify_variable_blocksize_seek() {
+    let mut reader = open(include_bytes!("fixtures/variable_blocksize.flac"));
+
+    // The frame containing sample 6000 starts at sample 5593.
+    let seeked =
+        reader.seek(SeekMode::Accurate, SeekTo::TimeStamp { ts: 6000, track_id: 0 }).unwrap();
+
+    assert_eq!(seeked.actual_ts, 5593);
+    assert_eq!(reader.next_packet().unwrap().ts(), 5593);
+}
+
+#[test]
+fn verify_frame_crc_without_packet_integrity() -> Result<()> {
+    let mut reader = open(include_bytes!("fixtures/negative_lpc_shift.flac"));
+
+    let mut params = reader.tracks()[0].codec_params.clone();
+    params.with_packet_data_integrity(false);
+
+    let mut decoder = FlacDecoder::try_new(&params, &DecoderOptions::default())?;
+
+    let packet = reader.next_packet()?;
+
+    // An intact packet must decode.
+    assert_eq!(decoder.decode(&packet)?.frames(), 1024);
+
+    // A corrupted packet must be rejected since the demuxer did not verify it.
+    let mut buf = packet.buf().to_vec();
+    buf[100] ^= 0x10;
+
+    let corrupt = Packet::new_from_slice(0, packet.ts(), packet.dur(), &buf);
+
+    match decoder.decode(&corrupt) {
+        Err(Error::DecodeError(_)) => (),
+        _ => panic!("expected a decode error"),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn verify_trailing_tags() {
+    let mut data = include_bytes!("fixtures/negative_lpc_shift.flac").to_vec();
+
+    // Append an ID3v1 tag whose title looks like the start of a FLAC frame.
+    let mut id3v1 = [0; 128];
+    id3v1[..3].copy_from_slice(b"TAG");
+    id3v1[3..9].copy_from_slice(&[0xff, 0xf8, 0xc9, 0x18, 0x00, 0x00]);
+    id3v1[33..38].copy_from_slice(b"Tests");
+    id3v1[127] = 255;
+    data.extend_from_slice(&id3v1);
+
+    assert_eq!(decode_and_verify(data.clone()), 3500);
+
+    let mut reader = open(data);
+
+    // The trailing tags are read after the Vorbis comment block, and are therefore the latest.
+    let mut metadata = reader.metadata();
+    let revision = metadata.skip_to_latest().unwrap();
+
+    let artist = revision.tags().iter().find(|tag| tag.std_key == Some(StandardTagKey::Artist));
+
+    assert_eq!(artist.map(|tag| tag.value.to_string()), Some("Tests".to_string()));
+}
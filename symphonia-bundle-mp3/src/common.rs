@@ -282,6 +282,13 @@ impl FrameHeader {
         self.version == MpegVersion::Mpeg1
     }
 
+    /// Returns true if this is a free bit-rate frame with an unknown bit-rate and frame size, false
+    /// otherwise.
+    #[inline(always)]
+    pub fn is_free_format(&self) -> bool {
+        self.bitrate == 0
+    }
+
     /// Returns true if this a MPEG2.5 frame, false otherwise.
     #[inline(always)]
     pub fn is_mpeg2p5(&self) -> bool {
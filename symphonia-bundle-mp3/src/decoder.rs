@@ -28,8 +28,9 @@ impl Mp3Decoder {
 
         let header = header::read_frame_header(&mut reader)?;
 
-        // The packet should be the size stated in the header.
-        if header.frame_size != reader.bytes_available() as usize {
+        // The packet should be the size stated in the header. The size of a free bit-rate frame is
+        // not stated in the header, and is therefore the size of the packet.
+        if !header.is_free_format() && header.frame_size != reader.bytes_available() as usize {
             return decode_error("mp3: invalid packet length");
         }
 
@@ -9,19 +9,27 @@ use symphonia_core::support_format;
 
 use symphonia_core::checksum::Crc16AnsiLe;
 use symphonia_core::codecs::{CodecParameters, CODEC_TYPE_MP3};
-use symphonia_core::errors::{seek_error, Result, SeekErrorKind};
+use symphonia_core::errors::{
+    decode_error, end_of_stream_error, seek_error, Result, SeekErrorKind,
+};
 use symphonia_core::formats::prelude::*;
 use symphonia_core::io::*;
-use symphonia_core::meta::{Metadata, MetadataLog};
+use symphonia_core::meta::{GainInfo, Metadata, MetadataLog};
 use symphonia_core::probe::{Descriptor, Instantiate, QueryDescriptor};
 
+use symphonia_metadata::id3v2::{Id3v2Extras, MpegLocationTable};
+use symphonia_metadata::trailing::read_trailing_metadata;
+
 use std::io::{Seek, SeekFrom};
 
 use log::{debug, info, warn};
 
 use super::common::{FrameHeader, SAMPLES_PER_GRANULE};
 use super::header;
-use super::header::MPEG_HEADER_LEN;
+use super::header::{FREE_FORMAT_HEADER_MASK, MPEG_HEADER_LEN};
+
+/// The maximum length of a free bit-rate frame that will be searched for the next frame header.
+const MAX_FREE_FORMAT_FRAME_LEN: usize = 8 * 1024;
 
 /// MPEG1 and MPEG2 audio elementary stream reader.
 ///
@@ -33,7 +41,15 @@ pub struct Mp3Reader {
     metadata: MetadataLog,
     options: FormatOptions,
     first_frame_pos: u64,
+    /// The position of the first MPEG frame, including a Xing/Info or VBRI frame, if present.
+    audio_start: u64,
+    /// The position where the audio data ends, and trailing tags, if any, begin.
+    audio_end: Option<u64>,
+    /// The MPEG location lookup table from the ID3v2 tag, if present.
+    mpeg_locations: Option<MpegLocationTable>,
     next_packet_ts: u64,
+    /// For free bit-rate streams, the length of an unpadded frame excluding the frame header.
+    free_format_len: Option<usize>,
 }
 
 impl QueryDescriptor for Mp3Reader {
@@ -64,8 +80,18 @@ impl QueryDescriptor for Mp3Reader {
 
 impl FormatReader for Mp3Reader {
     fn try_new(mut source: MediaSourceStream, options: &FormatOptions) -> Result<Self> {
+        // Read any tags appended after the audio data so that they are not mistaken for frames.
+        let mut metadata = MetadataLog::default();
+
+        let audio_end = read_trailing_metadata(&mut source, &mut metadata)?;
+
         // Try to read the first MPEG frame.
-        let (header, packet) = read_mpeg_frame_strict(&mut source)?;
+        let mut free_format_len = None;
+
+        let (header, packet) =
+            read_mpeg_frame_strict(&mut source, &mut free_format_len, audio_end)?;
+
+        let audio_start = source.pos() - (MPEG_HEADER_LEN + header.frame_size) as u64;
 
         // Use the header to populate the codec parameters.
         let mut params = CodecParameters::new();
@@ -78,12 +104,18 @@ impl FormatReader for Mp3Reader {
 
         let audio_frames_per_mpeg_frame = SAMPLES_PER_GRANULE * header.n_granules() as u64;
 
+        params.with_max_frames_per_packet(audio_frames_per_mpeg_frame);
+
+        let mut lame_gain = None;
+
         // Check if there is a Xing/Info tag contained in the first frame.
         if let Some(info_tag) = try_read_info_tag(&packet, &header) {
             // The LAME tag contains ReplayGain and padding information.
             let (delay, padding) = if let Some(lame_tag) = info_tag.lame {
                 params.with_delay(lame_tag.enc_delay).with_padding(lame_tag.enc_padding);
 
+                lame_gain = lame_tag.gain_info();
+
                 (lame_tag.enc_delay, lame_tag.enc_padding)
             }
             else {
@@ -122,7 +154,9 @@ impl FormatReader for Mp3Reader {
             if source.is_seekable() {
                 info!("estimating duration from bitrate, may be inaccurate for vbr files");
 
-                if let Some(n_mpeg_frames) = estimate_num_mpeg_frames(&mut source) {
+                if let Some(n_mpeg_frames) =
+                    estimate_num_mpeg_frames(&mut source, free_format_len, audio_end)
+                {
                     params.with_n_frames(n_mpeg_frames * audio_frames_per_mpeg_frame);
                 }
             }
@@ -130,21 +164,78 @@ impl FormatReader for Mp3Reader {
 
         let first_frame_pos = source.pos();
 
+        // Tagged gains are preferred over the gains in the LAME tag since they are more likely to
+        // be accurate and include album gain. Gains in a leading ID3v2 tag are applied when the
+        // probed metadata is attached.
+        let mut track = Track::new(0, params);
+
+        track.gain = metadata
+            .metadata()
+            .current()
+            .and_then(|rev| GainInfo::from_tags(rev.tags()))
+            .or(lame_gain);
+
         Ok(Mp3Reader {
             reader: source,
-            tracks: vec![Track::new(0, params)],
+            tracks: vec![track],
             cues: Vec::new(),
-            metadata: Default::default(),
+            metadata,
             options: *options,
             first_frame_pos,
+            audio_start,
+            audio_end,
+            mpeg_locations: None,
             next_packet_ts: 0,
+            free_format_len,
         })
     }
 
+    fn attach_probed_metadata(&mut self, metadata: &MetadataLog) {
+        let params = &self.tracks[0].codec_params;
+
+        let sample_rate = params.sample_rate.unwrap_or(1);
+        let audio_frames_per_mpeg_frame = params.max_frames_per_packet.unwrap_or(0);
+
+        // Chapter and event times are relative to the start of the audible samples. Without
+        // gapless playback, the encoder delay preceeds them.
+        let delay = if self.options.enable_gapless { 0 } else { params.delay.unwrap_or(0) };
+
+        for rev in metadata.revisions() {
+            // Read the chapters, event timing codes, and seek table of a leading ID3v2 tag.
+            let extras = Id3v2Extras::from_revision(rev);
+
+            if !extras.chapters.is_empty() || extras.events.is_some() {
+                let mut cues = extras.chapters.to_cues(sample_rate);
+
+                if let Some(events) = &extras.events {
+                    events.add_to_cues(&mut cues, sample_rate, audio_frames_per_mpeg_frame);
+                }
+
+                cues.iter_mut().for_each(|cue| cue.start_ts += u64::from(delay));
+
+                self.cues = cues;
+            }
+
+            if extras.mpeg_locations.is_some() {
+                self.mpeg_locations = extras.mpeg_locations;
+            }
+
+            if let Some(gain) = GainInfo::from_tags(rev.tags()) {
+                self.tracks[0].gain = Some(gain);
+            }
+        }
+    }
+
     fn next_packet(&mut self) -> Result<Packet> {
         let (header, packet) = loop {
             // Read the next MPEG frame.
-            let (header, packet) = read_mpeg_frame(&mut self.reader)?;
+            let (header, packet) =
+                read_mpeg_frame(&mut self.reader, &mut self.free_format_len, self.audio_end)?;
+
+            // A frame that extends into the trailing tags is either truncated or a false sync.
+            if self.audio_end.map_or(false, |end| self.reader.pos() > end) {
+                return end_of_stream_error();
+            }
 
             // Check if the packet contains a Xing, Info, or VBRI tag.
             if is_maybe_info_tag(&packet, &header) {
@@ -249,6 +340,12 @@ impl FormatReader for Mp3Reader {
             self.next_packet_ts = 0;
         }
 
+        // If there is a MPEG location lookup table, skip ahead to the nearest reference point
+        // instead of parsing every frame before it. Leave room for reference frames.
+        if self.mpeg_locations.is_some() && self.reader.is_seekable() {
+            self.seek_to_location_ref(required_ts, MAX_REF_FRAMES as u64)?;
+        }
+
         let mut frames: [FramePos; MAX_REF_FRAMES] = Default::default();
         let mut n_frames = 0;
 
@@ -256,11 +353,23 @@ impl FormatReader for Mp3Reader {
         // reached.
         loop {
             // Parse the next frame header.
-            let header = header::parse_frame_header(header::sync_frame(&mut self.reader)?)?;
+            let mut header = header::parse_frame_header(header::sync_frame(&mut self.reader)?)?;
+
+            if header.is_free_format() {
+                match self.free_format_len {
+                    Some(len) => header::set_free_format_frame_size(&mut header, len),
+                    None => return seek_error(SeekErrorKind::Unseekable),
+                }
+            }
 
             // Position of the frame header.
             let frame_pos = self.reader.pos() - std::mem::size_of::<u32>() as u64;
 
+            // The seek cannot continue into the trailing tags.
+            if self.audio_end.map_or(false, |end| frame_pos >= end) {
+                return seek_error(SeekErrorKind::OutOfRange);
+            }
+
             // Calculate the duration of the frame.
             let duration = SAMPLES_PER_GRANULE * header.n_granules() as u64;
 
@@ -348,9 +457,74 @@ impl FormatReader for Mp3Reader {
     }
 }
 
+impl Mp3Reader {
+    /// Seeks forward to the last reference point of the MPEG location lookup table that preceeds
+    /// the frame containing `required_ts` by atleast `margin` frames. If the reference point is not
+    /// a frame, the table is assumed to be inaccurate and the stream is left as-is.
+    fn seek_to_location_ref(&mut self, required_ts: u64, margin: u64) -> Result<()> {
+        let table = match &self.mpeg_locations {
+            Some(table) => table,
+            None => return Ok(()),
+        };
+
+        let duration = match self.tracks[0].codec_params.max_frames_per_packet {
+            Some(duration) if duration > 0 => duration,
+            _ => return Ok(()),
+        };
+
+        // The table counts the Xing/Info or VBRI frame, but it does not produce a packet.
+        let n_info_frames = if self.first_frame_pos > self.audio_start { 1 } else { 0 };
+
+        let frame = (required_ts / duration + n_info_frames).saturating_sub(margin);
+
+        let location = match table.find_ref(frame) {
+            Some(location) if location.frame >= n_info_frames => *location,
+            _ => return Ok(()),
+        };
+
+        let ts = (location.frame - n_info_frames) * duration;
+        let pos = self.audio_start + location.byte_offset;
+
+        // Only skip forward, and never into the trailing tags.
+        if ts <= self.next_packet_ts || self.audio_end.map_or(false, |end| pos >= end) {
+            return Ok(());
+        }
+
+        let prev_pos = self.reader.pos();
+
+        self.reader.seek(SeekFrom::Start(pos))?;
+
+        let is_frame = match self.reader.read_be_u32() {
+            Ok(sync) => header::parse_frame_header(sync).is_ok(),
+            Err(_) => false,
+        };
+
+        if is_frame {
+            debug!("using mllt reference point with ts={} @ pos={}", ts, pos);
+
+            self.reader.seek_buffered_rev(MPEG_HEADER_LEN);
+            self.next_packet_ts = ts;
+        }
+        else {
+            warn!("mllt reference point is not a frame, ignoring");
+
+            self.reader.seek(SeekFrom::Start(prev_pos))?;
+        }
+
+        Ok(())
+    }
+}
+
 /// Reads a MPEG frame and returns the header and buffer.
-fn read_mpeg_frame(reader: &mut MediaSourceStream) -> Result<(FrameHeader, Vec<u8>)> {
-    let (header, header_word) = loop {
+///
+/// If the frame is a free bit-rate frame, and the length of a free bit-rate frame is not yet known,
+/// it is measured and returned via. `free_format_len`.
+fn read_mpeg_frame(
+    reader: &mut MediaSourceStream,
+    free_format_len: &mut Option<usize>,
+    audio_end: Option<u64>,
+) -> Result<(FrameHeader, Vec<u8>)> {
+    let (mut header, header_word) = loop {
         // Sync to the next frame header.
         let sync = header::sync_frame(reader)?;
 
@@ -362,6 +536,23 @@ fn read_mpeg_frame(reader: &mut MediaSourceStream) -> Result<(FrameHeader, Vec<u
         warn!("invalid mpeg audio header");
     };
 
+    // The size of a free bit-rate frame is not stated in the frame header.
+    if header.is_free_format() {
+        let len = match free_format_len {
+            Some(len) => *len,
+            None => {
+                let len = measure_free_format_len(reader, &header, header_word, audio_end)?;
+
+                debug!("free bit-rate stream with unpadded frame length of {} bytes", len);
+
+                *free_format_len = Some(len);
+                len
+            }
+        };
+
+        header::set_free_format_frame_size(&mut header, len);
+    }
+
     // Allocate frame buffer.
     let mut packet = vec![0u8; MPEG_HEADER_LEN + header.frame_size];
     packet[0..MPEG_HEADER_LEN].copy_from_slice(&header_word.to_be_bytes());
@@ -373,15 +564,87 @@ fn read_mpeg_frame(reader: &mut MediaSourceStream) -> Result<(FrameHeader, Vec<u
     Ok((header, packet))
 }
 
+/// Measures the length of an unpadded free bit-rate frame, excluding the frame header, from the
+/// distance between the frame header that was just read and the next frame header of the stream.
+/// If there is no next frame header, then the frame is assumed to extend to the end of the audio
+/// data. The stream position is not changed.
+fn measure_free_format_len(
+    reader: &mut MediaSourceStream,
+    header: &FrameHeader,
+    header_word: u32,
+    audio_end: Option<u64>,
+) -> Result<usize> {
+    let start = reader.pos();
+
+    // The minimum length of a frame, excluding the frame header.
+    let min_len = header.side_info_len();
+
+    let mut sync = 0u32;
+    let mut len = 0;
+
+    let result = loop {
+        if len > MAX_FREE_FORMAT_FRAME_LEN {
+            break decode_error("mp3: free bit-rate frame is too large");
+        }
+
+        // If the end of the audio data is reached without finding the next frame header, then
+        // this is the last, or only, frame of the stream.
+        let is_end = match audio_end {
+            Some(end) if reader.pos() >= end => true,
+            _ => match reader.read_u8() {
+                Ok(byte) => {
+                    sync = (sync << 8) | u32::from(byte);
+                    false
+                }
+                Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => true,
+                Err(err) => break Err(err.into()),
+            },
+        };
+
+        if is_end {
+            if len < min_len {
+                break decode_error("mp3: unable to measure free bit-rate frame length");
+            }
+
+            break Ok(len - if header.has_padding { 1 } else { 0 });
+        }
+
+        len += 1;
+
+        // The next frame header must be after the side information, and have the same fixed
+        // header bits as the current frame header.
+        if len >= MPEG_HEADER_LEN + min_len
+            && (sync & FREE_FORMAT_HEADER_MASK) == (header_word & FREE_FORMAT_HEADER_MASK)
+            && header::check_header(sync)
+        {
+            let frame_size = len - MPEG_HEADER_LEN;
+            break Ok(frame_size - if header.has_padding { 1 } else { 0 });
+        }
+    };
+
+    reader.seek_buffered(start);
+
+    result
+}
+
 /// Reads a MPEG frame and checks if the next frame begins after the packet.
-fn read_mpeg_frame_strict(reader: &mut MediaSourceStream) -> Result<(FrameHeader, Vec<u8>)> {
+fn read_mpeg_frame_strict(
+    reader: &mut MediaSourceStream,
+    free_format_len: &mut Option<usize>,
+    audio_end: Option<u64>,
+) -> Result<(FrameHeader, Vec<u8>)> {
     loop {
         // Read the next MPEG frame.
-        let (header, packet) = read_mpeg_frame(reader)?;
+        let (header, packet) = read_mpeg_frame(reader, free_format_len, audio_end)?;
 
         // Get the position before trying to read the next header.
         let pos = reader.pos();
 
+        // The last frame of the audio data is not followed by another frame.
+        if audio_end.map_or(false, |end| pos >= end) {
+            break Ok((header, packet));
+        }
+
         // Read a sync word from the stream. If this read fails then the file may have ended and
         // this check cannot be performed.
         if let Ok(sync) = header::read_frame_header_word_no_sync(reader) {
@@ -392,6 +655,9 @@ fn read_mpeg_frame_strict(reader: &mut MediaSourceStream) -> Result<(FrameHeader
             {
                 warn!("skipping junk at {} bytes", pos - packet.len() as u64);
 
+                // The free bit-rate frame length may have been measured from junk.
+                *free_format_len = None;
+
                 // Seek back to the second byte of the rejected packet to prevent syncing to the
                 // same spot again.
                 reader.seek_buffered_rev(packet.len() + MPEG_HEADER_LEN - 1);
@@ -446,8 +712,13 @@ fn read_main_data_begin<B: ReadBytes>(reader: &mut B, header: &FrameHeader) -> R
     Ok(main_data_begin)
 }
 
-/// Estimates the total number of MPEG frames in the media source stream.
-fn estimate_num_mpeg_frames(reader: &mut MediaSourceStream) -> Option<u64> {
+/// Estimates the total number of MPEG frames in the media source stream, where the audio data ends
+/// at the position `end`.
+fn estimate_num_mpeg_frames(
+    reader: &mut MediaSourceStream,
+    free_format_len: Option<usize>,
+    end: Option<u64>,
+) -> Option<u64> {
     const MAX_FRAMES: u32 = 16;
     const MAX_LEN: usize = 16 * 1024;
 
@@ -466,8 +737,8 @@ fn estimate_num_mpeg_frames(reader: &mut MediaSourceStream) -> Option<u64> {
     let mut total_frame_len = 0;
     let mut total_frames = 0;
 
-    let total_len = match reader.byte_len() {
-        Some(len) => len - start_pos,
+    let total_len = match end {
+        Some(end) => end.saturating_sub(start_pos),
         _ => return None,
     };
 
@@ -476,7 +747,14 @@ fn estimate_num_mpeg_frames(reader: &mut MediaSourceStream) -> Option<u64> {
         let header_val = break_on_err!(reader.read_be_u32());
 
         // Parse the frame header.
-        let header = break_on_err!(header::parse_frame_header(header_val));
+        let mut header = break_on_err!(header::parse_frame_header(header_val));
+
+        if header.is_free_format() {
+            match free_format_len {
+                Some(len) => header::set_free_format_frame_size(&mut header, len),
+                None => break None,
+            }
+        }
 
         // Tabulate the size.
         total_frame_len += MPEG_HEADER_LEN + header.frame_size;
@@ -513,6 +791,24 @@ struct LameTag {
     enc_padding: u32,
 }
 
+impl LameTag {
+    /// Gets the ReplayGain information of the LAME tag. The radio and audiophile gains are the
+    /// track and album gains, respectively.
+    fn gain_info(&self) -> Option<GainInfo> {
+        if self.replaygain_radio.is_none() && self.replaygain_audiophile.is_none() {
+            return None;
+        }
+
+        Some(GainInfo {
+            track_gain: self.replaygain_radio,
+            track_peak: self.replaygain_peak,
+            album_gain: self.replaygain_audiophile,
+            album_peak: None,
+            reference_loudness: Some(-18.0),
+        })
+    }
+}
+
 /// The Xing/Info time additional information for regarding a MP3 file.
 #[allow(dead_code)]
 struct XingInfoTag {
@@ -594,10 +890,10 @@ fn try_read_info_tag_inner(buf: &[u8], header: &FrameHeader) -> Result<Option<Xi
         // Lowpass filter value.
         let _lowpass = reader.read_u8()?;
 
-        // Replay gain peak in 9.23 (bit) fixed-point format.
+        // Replay gain peak in 9.23 (bit) fixed-point format, where 1.0 is full scale.
         let replaygain_peak = match reader.read_be_u32()? {
             0 => None,
-            peak => Some(32767.0 * (peak as f32 / 2.0f32.powi(23))),
+            peak => Some(peak as f32 / 2.0f32.powi(23)),
         };
 
         // Radio replay gain.
@@ -800,3 +1096,186 @@ fn is_maybe_vbri_tag(buf: &[u8]) -> bool {
     // The bytes preceeding the VBRI tag (mostly the side information) should be all 0.
     !buf[MPEG_HEADER_LEN..VBRI_TAG_OFFSET].iter().any(|&b| b != 0)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Generates a free bit-rate MPEG1 layer 3, 44.1 kHz, mono stream of `n_frames` silent frames
+    /// that are 400 bytes long, excluding the frame header.
+    fn free_format_stream(n_frames: usize) -> Vec<u8> {
+        let mut buf = Vec::new();
+
+        for _ in 0..n_frames {
+            buf.extend_from_slice(&0xfffb_00c0u32.to_be_bytes());
+            buf.extend_from_slice(&[0; 400]);
+        }
+
+        buf
+    }
+
+    fn open(buf: Vec<u8>) -> Result<Mp3Reader> {
+        let source =
+            MediaSourceStream::new(Box::new(std::io::Cursor::new(buf)), Default::default());
+        Mp3Reader::try_new(source, &FormatOptions { enable_gapless: false, ..Default::default() })
+    }
+
+    fn read_packet_lens(reader: &mut Mp3Reader) -> Vec<usize> {
+        let mut lens = Vec::new();
+
+        while let Ok(packet) = reader.next_packet() {
+            lens.push(packet.buf().len());
+        }
+
+        lens
+    }
+
+    #[test]
+    fn verify_free_format_stream() {
+        let mut reader = open(free_format_stream(3)).unwrap();
+        assert_eq!(read_packet_lens(&mut reader), vec![404; 3]);
+    }
+
+    #[test]
+    fn verify_free_format_single_frame() {
+        // A single frame has no next frame header to measure the frame length with.
+        let mut reader = open(free_format_stream(1)).unwrap();
+        assert_eq!(read_packet_lens(&mut reader), vec![404]);
+
+        // The frame ends where the trailing tags begin.
+        let mut buf = free_format_stream(1);
+        buf.extend_from_slice(b"TAG");
+        buf.extend_from_slice(&[0; 125]);
+
+        let mut reader = open(buf).unwrap();
+        assert_eq!(read_packet_lens(&mut reader), vec![404]);
+    }
+
+    #[test]
+    fn verify_free_format_truncated() {
+        // The last frame is truncated, therefore its length is measured to the end of the stream.
+        let mut buf = free_format_stream(1);
+        buf.truncate(300);
+
+        let mut reader = open(buf).unwrap();
+        assert_eq!(read_packet_lens(&mut reader), vec![300]);
+
+        // Too short to contain the side information.
+        let mut buf = free_format_stream(1);
+        buf.truncate(16);
+
+        assert!(open(buf).is_err());
+    }
+
+    /// Makes an ID3v2.4 frame.
+    fn id3v2_frame(id: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        let mut buf = id.to_vec();
+        buf.extend_from_slice(&syncsafe(body.len()));
+        buf.extend_from_slice(&[0, 0]);
+        buf.extend_from_slice(body);
+        buf
+    }
+
+    fn syncsafe(len: usize) -> [u8; 4] {
+        [
+            (len >> 21) as u8 & 0x7f,
+            (len >> 14) as u8 & 0x7f,
+            (len >> 7) as u8 & 0x7f,
+            len as u8 & 0x7f,
+        ]
+    }
+
+    /// Generates an ID3v2.4 tag with a chapter, event timing codes, a MPEG location lookup table,
+    /// and a track gain.
+    fn id3v2_tag() -> Vec<u8> {
+        let mut chap = b"ch0\0".to_vec();
+        chap.extend_from_slice(&500u32.to_be_bytes());
+        chap.extend_from_slice(&1000u32.to_be_bytes());
+        chap.extend_from_slice(&[0xff; 8]);
+        chap.extend_from_slice(&id3v2_frame(b"TIT2", b"\x03Intro"));
+
+        // A "main part start" event at 750 ms.
+        let etco = b"\x02\x03\x00\x00\x02\xee";
+
+        // A reference point every frame with no deviation from 404 bytes and 26 ms.
+        let mllt = b"\x00\x01\x00\x01\x94\x00\x00\x1a\x08\x08\x00\x00\x00\x00";
+
+        // A track gain of -6.5 dB.
+        let rva2 = b"track\x00\x01\xf3\x00\x00";
+
+        let mut frames = Vec::new();
+        frames.extend_from_slice(&id3v2_frame(b"CHAP", &chap));
+        frames.extend_from_slice(&id3v2_frame(b"ETCO", etco));
+        frames.extend_from_slice(&id3v2_frame(b"MLLT", mllt));
+        frames.extend_from_slice(&id3v2_frame(b"RVA2", rva2));
+
+        let mut buf = b"ID3\x04\x00\x00".to_vec();
+        buf.extend_from_slice(&syncsafe(frames.len()));
+        buf.extend_from_slice(&frames);
+        buf
+    }
+
+    #[test]
+    fn verify_probed_id3v2_extras() {
+        use symphonia_core::probe::{Hint, Probe};
+        use symphonia_metadata::id3v2::Id3v2Reader;
+
+        let mut probe = Probe::default();
+        probe.register_all::<Mp3Reader>();
+        probe.register_all::<Id3v2Reader>();
+
+        let mut buf = id3v2_tag();
+        buf.extend_from_slice(&free_format_stream(3));
+
+        // The extras must be available even if the leading tag cannot be read again.
+        let source = ReadOnlySource::new(std::io::Cursor::new(buf));
+        let mss = MediaSourceStream::new(Box::new(source), Default::default());
+
+        let format_opts = FormatOptions { enable_gapless: false, ..Default::default() };
+
+        let mut probed =
+            probe.format(&Hint::new(), mss, &format_opts, &Default::default()).unwrap();
+
+        let cues = probed.format.cues();
+
+        assert_eq!(cues.len(), 1);
+        assert_eq!(cues[0].start_ts, 22050);
+        assert_eq!(cues[0].tags[0].value.to_string(), "Intro");
+        assert_eq!(cues[0].points.len(), 1);
+        assert_eq!(cues[0].points[0].start_offset_ts, 11025);
+
+        let gain = probed.format.default_track().unwrap().gain.unwrap();
+
+        assert_eq!(gain.track_gain, Some(-6.5));
+
+        // The tags of the leading tag are still returned by the probe.
+        let metadata = probed.metadata.get().unwrap();
+
+        assert!(metadata.current().unwrap().tags().iter().any(|tag| tag.key == "RVA2:track"));
+    }
+
+    #[test]
+    fn verify_attach_probed_metadata() {
+        use symphonia_core::meta::{MetadataReader, MetadataRevision};
+        use symphonia_metadata::id3v2::Id3v2Reader;
+
+        let mut mss =
+            MediaSourceStream::new(Box::new(std::io::Cursor::new(id3v2_tag())), Default::default());
+
+        let mut log = MetadataLog::default();
+        log.push(Id3v2Reader::new(&Default::default()).read_all(&mut mss).unwrap());
+
+        // A revision without any ID3v2 extras does not replace those already attached.
+        log.push(MetadataRevision::default());
+
+        let mut reader = open(free_format_stream(3)).unwrap();
+
+        assert!(reader.cues().is_empty());
+        assert!(reader.mpeg_locations.is_none());
+
+        reader.attach_probed_metadata(&log);
+
+        assert_eq!(reader.cues().len(), 1);
+        assert_eq!(reader.mpeg_locations.as_ref().map(|table| table.refs.len()), Some(2));
+    }
+}
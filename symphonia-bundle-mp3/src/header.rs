@@ -5,7 +5,7 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use symphonia_core::errors::{decode_error, unsupported_error, Result};
+use symphonia_core::errors::{decode_error, Result};
 use symphonia_core::io::ReadBytes;
 
 use crate::common::*;
@@ -124,9 +124,10 @@ pub fn parse_frame_header(header: u32) -> Result<FrameHeader> {
     };
 
     let bitrate = match ((header & 0xf000) >> 12, version, layer) {
-        // "Free" bit-rate. Note, this is NOT variable bit-rate and is not a mandatory feature of
-        // MP3 decoders.
-        (0b0000, _, _) => return unsupported_error("mp3: free bit-rate is not supported"),
+        // "Free" bit-rate. Note, this is NOT variable bit-rate. The bit-rate is fixed, but not one
+        // of the standard bit-rates. The bit-rate, and therefore frame size, can only be
+        // determined by measuring the distance between consecutive frame headers.
+        (0b0000, _, _) => 0,
         // Invalid bit-rate.
         (0b1111, _, _) => return decode_error("mp3: invalid bit-rate"),
         // MPEG 1 bit-rates.
@@ -199,11 +200,16 @@ pub fn parse_frame_header(header: u32) -> Result<FrameHeader> {
 
     let has_crc = header & 0x1_0000 == 0;
 
-    // Calculate the size of the frame excluding this header.
-    let frame_size = (if version == MpegVersion::Mpeg1 { 144 } else { 72 } * bitrate / sample_rate)
-        as usize
-        + if has_padding { 1 } else { 0 }
-        - 4;
+    // Calculate the size of the frame excluding this header. For free bit-rate frames the size is
+    // unknown until it is measured.
+    let frame_size = if bitrate > 0 {
+        (if version == MpegVersion::Mpeg1 { 144 } else { 72 } * bitrate / sample_rate) as usize
+            + if has_padding { 1 } else { 0 }
+            - 4
+    }
+    else {
+        0
+    };
 
     Ok(FrameHeader {
         version,
@@ -221,6 +227,19 @@ pub fn parse_frame_header(header: u32) -> Result<FrameHeader> {
     })
 }
 
+/// The mask of the frame header bits that must be the same for all frames of a free bit-rate
+/// stream: the sync word, version, layer, bit-rate, and sample rate.
+pub const FREE_FORMAT_HEADER_MASK: u32 = 0xfffe_fc00;
+
+/// Set the size of a free bit-rate frame given the length of an unpadded frame, excluding the
+/// frame header, and derive the bit-rate from it.
+pub fn set_free_format_frame_size(header: &mut FrameHeader, len: usize) {
+    let slot_len = if header.is_mpeg1() { 144 } else { 72 };
+
+    header.bitrate = ((len + MPEG_HEADER_LEN) as u32 * header.sample_rate) / slot_len;
+    header.frame_size = len + if header.has_padding { 1 } else { 0 };
+}
+
 /// Synchronize the stream to the start of the next MPEG audio frame header, then read and return
 /// the frame header or an error.
 #[inline]
@@ -235,3 +254,22 @@ pub fn read_frame_header<B: ReadBytes>(reader: &mut B) -> Result<FrameHeader> {
 pub fn read_frame_header_word_no_sync<B: ReadBytes>(reader: &mut B) -> Result<u32> {
     Ok(reader.read_be_u32()?)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_frame_header, set_free_format_frame_size};
+
+    #[test]
+    fn verify_free_format_frame_size() {
+        // MPEG1 layer 3, free bit-rate, 48 kHz, padded.
+        let mut header = parse_frame_header(0xfffb_0600).unwrap();
+
+        assert!(header.is_free_format());
+
+        set_free_format_frame_size(&mut header, 380);
+
+        assert!(!header.is_free_format());
+        assert_eq!(header.bitrate, 128_000);
+        assert_eq!(header.frame_size, 381);
+    }
+}
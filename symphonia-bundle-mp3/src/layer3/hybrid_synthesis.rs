@@ -11,6 +11,7 @@
 use std::{convert::TryInto, f64};
 
 use lazy_static::lazy_static;
+use symphonia_core::dsp::simd;
 
 use super::{BlockType, GranuleChannel};
 use crate::common::*;
@@ -304,10 +305,9 @@ pub(super) fn hybrid_synthesis(
             // Overlap the lower half of the IMDCT output (values 0..18) with the upper values of
             // the IMDCT (values 18..36) of the /previous/ iteration of the IMDCT. While doing this
             // also apply the window.
-            for i in 0..18 {
-                sub_band[i] = overlap[sb][i] + (output[i] * window[i]);
-                overlap[sb][i] = output[18 + i] * window[18 + i];
-            }
+            sub_band.copy_from_slice(&overlap[sb]);
+            simd::mul_acc(sub_band, &output[..18], &window[..18]);
+            simd::mul(&mut overlap[sb], &output[18..], &window[18..]);
         }
     }
 
@@ -7,6 +7,8 @@
 
 //! The `synthesis` module implements the polyphase synthesis filterbank of the MPEG audio standard.
 
+use symphonia_core::dsp::simd;
+
 /// Synthesis window D[i], defined in Table B.3 of ISO/IEC 11172-3.
 #[allow(clippy::unreadable_literal)]
 #[rustfmt::skip]
@@ -314,10 +316,8 @@ pub fn synthesis(state: &mut SynthesisState, in_samples: &mut [f32; 576], out: &
 
             let k = j << 6;
 
-            for i in 0..32 {
-                o_vec[i] += v0[i] * SYNTHESIS_D[k + i + 0];
-                o_vec[i] += v1[i] * SYNTHESIS_D[k + i + 32];
-            }
+            simd::mul_acc(&mut o_vec, v0, &SYNTHESIS_D[k..k + 32]);
+            simd::mul_acc(&mut o_vec, v1, &SYNTHESIS_D[k + 32..k + 64]);
         }
 
         // Clamp and copy the PCM samples from o_vec to the output buffer.
@@ -58,6 +58,74 @@ impl fmt::Display for M4AType {
     }
 }
 
+/// A program config element (PCE).
+struct ProgramConfig {
+    /// The total number of channels, including LFE channels.
+    channels: usize,
+}
+
+impl ProgramConfig {
+    fn read<B: ReadBitsLtr>(bs: &mut B) -> Result<Self> {
+        let _element_instance_tag = bs.read_bits_leq32(4)?;
+        let _object_type = bs.read_bits_leq32(2)?;
+        let _sampling_frequency_index = bs.read_bits_leq32(4)?;
+
+        let num_front_channel_elements = bs.read_bits_leq32(4)?;
+        let num_side_channel_elements = bs.read_bits_leq32(4)?;
+        let num_back_channel_elements = bs.read_bits_leq32(4)?;
+        let num_lfe_channel_elements = bs.read_bits_leq32(2)?;
+        let num_assoc_data_elements = bs.read_bits_leq32(3)?;
+        let num_valid_cc_elements = bs.read_bits_leq32(4)?;
+
+        if bs.read_bool()? {
+            let _mono_mixdown_element_number = bs.read_bits_leq32(4)?;
+        }
+
+        if bs.read_bool()? {
+            let _stereo_mixdown_element_number = bs.read_bits_leq32(4)?;
+        }
+
+        // The matrix-mixdown coefficients only apply to 3/2 programs, which are not supported.
+        if bs.read_bool()? {
+            let _matrix_mixdown_idx = bs.read_bits_leq32(2)?;
+            let _pseudo_surround_enable = bs.read_bool()?;
+        }
+
+        let mut channels = 0;
+
+        // Front, side, and back elements may be either single or channel pair elements.
+        let num_elements =
+            num_front_channel_elements + num_side_channel_elements + num_back_channel_elements;
+
+        for _ in 0..num_elements {
+            let is_cpe = bs.read_bool()?;
+            let _tag_select = bs.read_bits_leq32(4)?;
+            channels += if is_cpe { 2 } else { 1 };
+        }
+
+        for _ in 0..num_lfe_channel_elements {
+            let _tag_select = bs.read_bits_leq32(4)?;
+            channels += 1;
+        }
+
+        for _ in 0..num_assoc_data_elements {
+            let _tag_select = bs.read_bits_leq32(4)?;
+        }
+
+        for _ in 0..num_valid_cc_elements {
+            let _cc_element_is_ind_sw = bs.read_bool()?;
+            let _tag_select = bs.read_bits_leq32(4)?;
+        }
+
+        bs.realign();
+
+        let comment_field_bytes = bs.read_bits_leq32(8)?;
+        bs.ignore_bits(comment_field_bytes * 8)?;
+
+        Ok(ProgramConfig { channels })
+    }
+}
+
 struct M4AInfo {
     otype: M4AType,
     srate: u32,
@@ -166,7 +234,11 @@ impl M4AInfo {
                 let extension_flag = bs.read_bool()?;
 
                 if self.channels == 0 {
-                    return unsupported_error("aac: program config element");
+                    let pce = ProgramConfig::read(&mut bs)?;
+
+                    validate!(pce.channels > 0);
+
+                    self.channels = pce.channels;
                 }
 
                 if (self.otype == M4AType::Scalable) || (self.otype == M4AType::ER_AAC_Scalable) {
@@ -1533,7 +1605,7 @@ impl AacDecoder {
                 }
                 5 => {
                     // ID_PCE
-                    return unsupported_error("aac: program config");
+                    let _pce = ProgramConfig::read(bs)?;
                 }
                 6 => {
                     // ID_FIL
@@ -1898,3 +1970,100 @@ const AAC_SUBBAND_INFO: [GASubbandInfo; 12] = [
         short_bands: &SWB_OFFSET_8K_SHORT,
     }, //8K
 ];
+
+#[cfg(test)]
+mod tests {
+    use symphonia_core::io::{BitReaderLtr, ReadBitsLtr};
+
+    use super::{M4AInfo, ProgramConfig};
+
+    /// Packs a sequence of (value, width) fields into bytes, most-significant bit first. The last
+    /// byte is padded with 0s.
+    fn pack(fields: &[(u32, u32)]) -> Vec<u8> {
+        let mut bits = Vec::new();
+
+        for &(value, width) in fields {
+            bits.extend((0..width).rev().map(|i| (value >> i) & 1 == 1));
+        }
+
+        bits.chunks(8)
+            .map(|byte| {
+                byte.iter().enumerate().fold(0, |acc, (i, &bit)| acc | (u8::from(bit) << (7 - i)))
+            })
+            .collect()
+    }
+
+    /// Gets the fields of a program config element with the given front, back, and LFE elements,
+    /// where `true` is a channel pair element. Matrix-mixdown is signalled with index 1 and
+    /// pseudo-surround enabled, and the comment is "hi".
+    fn pce_fields(front: &[bool], back: &[bool], n_lfe: u32) -> Vec<(u32, u32)> {
+        let mut fields = vec![
+            (0, 4),                  // element_instance_tag
+            (1, 2),                  // object_type
+            (4, 4),                  // sampling_frequency_index
+            (front.len() as u32, 4), // num_front_channel_elements
+            (0, 4),                  // num_side_channel_elements
+            (back.len() as u32, 4),  // num_back_channel_elements
+            (n_lfe, 2),              // num_lfe_channel_elements
+            (0, 3),                  // num_assoc_data_elements
+            (0, 4),                  // num_valid_cc_elements
+            (0, 1),                  // mono_mixdown_present
+            (0, 1),                  // stereo_mixdown_present
+            (1, 1),                  // matrix_mixdown_idx_present
+            (1, 2),                  // matrix_mixdown_idx
+            (1, 1),                  // pseudo_surround_enable
+        ];
+
+        for (i, &is_cpe) in front.iter().chain(back).enumerate() {
+            fields.push((u32::from(is_cpe), 1));
+            fields.push((i as u32, 4));
+        }
+
+        fields.extend(std::iter::repeat((0, 4)).take(n_lfe as usize));
+
+        fields
+    }
+
+    #[test]
+    fn verify_read_program_config() {
+        // A 5.1 program with matrix-mixdown. The element is followed by a sentinel byte to verify
+        // the element was read entirely, including the byte alignment and comment.
+        let mut fields = pce_fields(&[false, true], &[true], 1);
+
+        let n_bits: u32 = fields.iter().map(|&(_, width)| width).sum();
+        fields.push((0, (8 - n_bits % 8) % 8));
+        fields.extend_from_slice(&[(2, 8), (u32::from(b'h'), 8), (u32::from(b'i'), 8), (0xa5, 8)]);
+
+        let buf = pack(&fields);
+        let mut bs = BitReaderLtr::new(&buf);
+
+        let pce = ProgramConfig::read(&mut bs).unwrap();
+
+        assert_eq!(pce.channels, 6);
+        assert_eq!(bs.read_bits_leq32(8).unwrap(), 0xa5);
+
+        // A truncated element is an error.
+        let mut bs = BitReaderLtr::new(&buf[..4]);
+        assert!(ProgramConfig::read(&mut bs).is_err());
+    }
+
+    #[test]
+    fn verify_read_audio_specific_config_with_program_config() {
+        // An AAC-LC audio specific config at 44.1 kHz with a channel configuration of 0, followed
+        // by a GASpecificConfig and a stereo program config element with matrix-mixdown.
+        let mut fields = vec![(2, 5), (4, 4), (0, 4), (0, 1), (0, 1), (0, 1)];
+
+        fields.extend(pce_fields(&[true], &[], 0));
+
+        let n_bits: u32 = fields.iter().map(|&(_, width)| width).sum();
+        fields.push((0, (8 - n_bits % 8) % 8));
+        fields.extend_from_slice(&[(2, 8), (u32::from(b'h'), 8), (u32::from(b'i'), 8)]);
+
+        let mut info = M4AInfo::new();
+        info.read(&pack(&fields)).unwrap();
+
+        assert_eq!(info.srate, 44_100);
+        assert_eq!(info.channels, 2);
+        assert_eq!(info.samples, 1024);
+    }
+}
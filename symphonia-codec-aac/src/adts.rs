@@ -10,12 +10,16 @@ use symphonia_core::support_format;
 
 use symphonia_core::audio::Channels;
 use symphonia_core::codecs::{CodecParameters, CODEC_TYPE_AAC};
-use symphonia_core::errors::{decode_error, seek_error, Result, SeekErrorKind};
+use symphonia_core::errors::{
+    decode_error, end_of_stream_error, seek_error, Result, SeekErrorKind,
+};
 use symphonia_core::formats::prelude::*;
 use symphonia_core::io::*;
-use symphonia_core::meta::{Metadata, MetadataLog};
+use symphonia_core::meta::{GainInfo, Metadata, MetadataLog};
 use symphonia_core::probe::{Descriptor, Instantiate, QueryDescriptor};
 
+use symphonia_metadata::trailing::read_trailing_metadata;
+
 use std::io::{Seek, SeekFrom};
 
 use super::common::{map_channels, M4AType, AAC_SAMPLE_RATES, M4A_TYPES};
@@ -33,6 +37,8 @@ pub struct AdtsReader {
     cues: Vec<Cue>,
     metadata: MetadataLog,
     first_frame_pos: u64,
+    /// The position where the audio data ends, and trailing tags, if any, begin.
+    audio_end: Option<u64>,
     next_packet_ts: u64,
 }
 
@@ -126,6 +132,11 @@ impl AdtsHeader {
 
 impl FormatReader for AdtsReader {
     fn try_new(mut source: MediaSourceStream, _options: &FormatOptions) -> Result<Self> {
+        // Read any tags appended after the audio data so that they are not mistaken for frames.
+        let mut metadata = MetadataLog::default();
+
+        let audio_end = read_trailing_metadata(&mut source, &mut metadata)?;
+
         let header = AdtsHeader::read(&mut source)?;
 
         // Use the header to populate the codec parameters.
@@ -142,12 +153,16 @@ impl FormatReader for AdtsReader {
 
         let first_frame_pos = source.pos();
 
+        let mut track = Track::new(0, params);
+        track.gain = metadata.metadata().current().and_then(|rev| GainInfo::from_tags(rev.tags()));
+
         Ok(AdtsReader {
             reader: source,
-            tracks: vec![Track::new(0, params)],
+            tracks: vec![track],
             cues: Vec::new(),
-            metadata: Default::default(),
+            metadata,
             first_frame_pos,
+            audio_end,
             next_packet_ts: 0,
         })
     }
@@ -156,6 +171,11 @@ impl FormatReader for AdtsReader {
         // Parse the header to get the calculated frame size.
         let header = AdtsHeader::read(&mut self.reader)?;
 
+        // A frame that extends into the trailing tags is either truncated or a false sync.
+        if self.audio_end.map_or(false, |end| self.reader.pos() + header.frame_len as u64 > end) {
+            return end_of_stream_error();
+        }
+
         // TODO: Support multiple AAC packets per ADTS packet.
 
         let ts = self.next_packet_ts;
@@ -229,6 +249,11 @@ impl FormatReader for AdtsReader {
             // Parse the next frame header.
             let header = AdtsHeader::read(&mut self.reader)?;
 
+            // The seek cannot continue into the trailing tags.
+            if self.audio_end.map_or(false, |end| self.reader.pos() > end) {
+                return seek_error(SeekErrorKind::OutOfRange);
+            }
+
             // TODO: Support multiple AAC packets per ADTS packet.
 
             // If the next frame's timestamp would exceed the desired timestamp, rewind back to the
@@ -16,7 +16,8 @@
 
 use symphonia_core::support_codec;
 
-use symphonia_core::audio::{AsAudioBufferRef, AudioBuffer, AudioBufferRef, Signal, SignalSpec};
+use symphonia_core::audio::{AsAudioBufferRef, AudioBuffer, AudioBufferMut, AudioBufferRef};
+use symphonia_core::audio::{InterleavedBufferMut, Signal, SignalSpec};
 use symphonia_core::codecs::{CodecDescriptor, CodecParameters, CodecType};
 use symphonia_core::codecs::{Decoder, DecoderOptions, FinalizeResult};
 // Signed Int PCM codecs
@@ -32,53 +33,81 @@ use symphonia_core::codecs::{CODEC_TYPE_PCM_F32BE, CODEC_TYPE_PCM_F32LE};
 use symphonia_core::codecs::{CODEC_TYPE_PCM_F64BE, CODEC_TYPE_PCM_F64LE};
 // G711 ALaw and MuLaw PCM cdoecs.
 use symphonia_core::codecs::{CODEC_TYPE_PCM_ALAW, CODEC_TYPE_PCM_MULAW};
+use symphonia_core::conv::dither::Dynamic;
 use symphonia_core::conv::FromSample;
-use symphonia_core::errors::{unsupported_error, Result};
+use symphonia_core::errors::{limit_error, unsupported_error, Result};
 use symphonia_core::formats::Packet;
 use symphonia_core::io::ReadBytes;
 
 macro_rules! read_pcm_signed {
-    ($buf:expr, $read:expr, $shift:expr) => {
-        $buf.fill(|audio_planes, idx| -> Result<()> {
-            for plane in audio_planes.planes() {
-                plane[idx] = (($read as u32) << $shift) as i32;
-            }
-            Ok(())
-        })
+    ($sink:expr, $read:expr, $shift:expr) => {
+        $sink.fill_with(|| Ok((($read as u32) << $shift) as i32))
     };
 }
 
 macro_rules! read_pcm_unsigned {
-    ($buf:expr, $read:expr, $shift:expr) => {
-        $buf.fill(|audio_planes, idx| -> Result<()> {
-            for plane in audio_planes.planes() {
-                plane[idx] = (($read as u32) << $shift).wrapping_add(0x80000000) as i32
-            }
-            Ok(())
-        })
+    ($sink:expr, $read:expr, $shift:expr) => {
+        $sink.fill_with(|| Ok((($read as u32) << $shift).wrapping_add(0x80000000) as i32))
     };
 }
 
 macro_rules! read_pcm_floating {
-    ($buf:expr, $read:expr) => {
-        $buf.fill(|audio_planes, idx| -> Result<()> {
-            for plane in audio_planes.planes() {
-                plane[idx] = i32::from_sample($read);
-            }
-            Ok(())
-        })
+    ($sink:expr, $read:expr) => {
+        $sink.fill_with(|| Ok(i32::from_sample($read)))
     };
 }
 
 macro_rules! read_pcm_transfer_func {
-    ($buf:expr, $func:expr) => {
-        $buf.fill(|audio_planes, idx| -> Result<()> {
+    ($sink:expr, $func:expr) => {
+        $sink.fill_with(|| Ok(i32::from_sample($func)))
+    };
+}
+
+/// A destination for decoded PCM samples.
+trait PcmSink {
+    /// Replaces the contents of the sink with the samples returned by `read` in interleaved
+    /// channel order, until `read` fails or the sink is full. Returns the number of frames written.
+    fn fill_with<F: FnMut() -> Result<i32>>(&mut self, read: F) -> usize;
+}
+
+impl PcmSink for AudioBuffer<i32> {
+    fn fill_with<F: FnMut() -> Result<i32>>(&mut self, mut read: F) -> usize {
+        // Reading fails at the end of the packet, this is not an error.
+        let _ = self.fill(|audio_planes, idx| -> Result<()> {
             for plane in audio_planes.planes() {
-                plane[idx] = i32::from_sample($func);
+                plane[idx] = read()?;
             }
             Ok(())
-        })
-    };
+        });
+
+        self.frames()
+    }
+}
+
+/// A caller-owned slice of interleaved samples.
+struct Interleaved<'a> {
+    buf: &'a mut [i32],
+    n_channels: usize,
+}
+
+impl PcmSink for Interleaved<'_> {
+    fn fill_with<F: FnMut() -> Result<i32>>(&mut self, mut read: F) -> usize {
+        let mut n_frames = 0;
+
+        for frame in self.buf.chunks_exact_mut(self.n_channels) {
+            for sample in frame.iter_mut() {
+                match read() {
+                    Ok(value) => *sample = value,
+                    // A partially read frame is discarded.
+                    Err(_) => return n_frames,
+                }
+            }
+
+            n_frames += 1;
+        }
+
+        n_frames
+    }
 }
 
 // alaw_to_linear and mulaw_to_linear are adaptations of alaw2linear and ulaw2linear from g711.c by
@@ -159,63 +188,67 @@ pub struct PcmDecoder {
     buf: AudioBuffer<i32>,
 }
 
-impl PcmDecoder {
-    fn decode_inner(&mut self, packet: &Packet) -> Result<()> {
-        let mut stream = packet.as_buf_reader();
-
-        // Signed or unsigned integer PCM codecs must be shifted to expand the sample into the
-        // entire i32 range. Only floating point samples may exceed 32 bits per coded sample, but
-        // they cannot be shifted, so int_shift = 0.
-        let int_shift = if self.sample_width <= 32 { 32 - self.sample_width } else { 0 };
-
-        let _ = match self.params.codec {
-            CODEC_TYPE_PCM_S32LE => read_pcm_signed!(self.buf, stream.read_u32()?, int_shift),
-            CODEC_TYPE_PCM_S32BE => read_pcm_signed!(self.buf, stream.read_be_u32()?, int_shift),
-            CODEC_TYPE_PCM_S24LE => read_pcm_signed!(self.buf, stream.read_u24()?, int_shift),
-            CODEC_TYPE_PCM_S24BE => read_pcm_signed!(self.buf, stream.read_be_u24()?, int_shift),
-            CODEC_TYPE_PCM_S16LE => read_pcm_signed!(self.buf, stream.read_u16()?, int_shift),
-            CODEC_TYPE_PCM_S16BE => read_pcm_signed!(self.buf, stream.read_be_u16()?, int_shift),
-            CODEC_TYPE_PCM_S8 => read_pcm_signed!(self.buf, stream.read_u8()?, int_shift),
-            CODEC_TYPE_PCM_U32LE => read_pcm_unsigned!(self.buf, stream.read_u32()?, int_shift),
-            CODEC_TYPE_PCM_U32BE => read_pcm_unsigned!(self.buf, stream.read_be_u32()?, int_shift),
-            CODEC_TYPE_PCM_U24LE => read_pcm_unsigned!(self.buf, stream.read_u24()?, int_shift),
-            CODEC_TYPE_PCM_U24BE => read_pcm_unsigned!(self.buf, stream.read_be_u24()?, int_shift),
-            CODEC_TYPE_PCM_U16LE => read_pcm_unsigned!(self.buf, stream.read_u16()?, int_shift),
-            CODEC_TYPE_PCM_U16BE => read_pcm_unsigned!(self.buf, stream.read_be_u16()?, int_shift),
-            CODEC_TYPE_PCM_U8 => read_pcm_unsigned!(self.buf, stream.read_u8()?, int_shift),
-            CODEC_TYPE_PCM_F32LE => read_pcm_floating!(self.buf, stream.read_f32()?),
-            CODEC_TYPE_PCM_F32BE => read_pcm_floating!(self.buf, stream.read_be_f32()?),
-            CODEC_TYPE_PCM_F64LE => read_pcm_floating!(self.buf, stream.read_f64()?),
-            CODEC_TYPE_PCM_F64BE => read_pcm_floating!(self.buf, stream.read_be_f64()?),
-            CODEC_TYPE_PCM_ALAW => {
-                read_pcm_transfer_func!(self.buf, alaw_to_linear(stream.read_u8()?))
-            }
-            CODEC_TYPE_PCM_MULAW => {
-                read_pcm_transfer_func!(self.buf, mulaw_to_linear(stream.read_u8()?))
-            }
-            // CODEC_TYPE_PCM_S32LE_PLANAR =>
-            // CODEC_TYPE_PCM_S32BE_PLANAR =>
-            // CODEC_TYPE_PCM_S24LE_PLANAR =>
-            // CODEC_TYPE_PCM_S24BE_PLANAR =>
-            // CODEC_TYPE_PCM_S16LE_PLANAR =>
-            // CODEC_TYPE_PCM_S16BE_PLANAR =>
-            // CODEC_TYPE_PCM_S8_PLANAR    =>
-            // CODEC_TYPE_PCM_U32LE_PLANAR =>
-            // CODEC_TYPE_PCM_U32BE_PLANAR =>
-            // CODEC_TYPE_PCM_U24LE_PLANAR =>
-            // CODEC_TYPE_PCM_U24BE_PLANAR =>
-            // CODEC_TYPE_PCM_U16LE_PLANAR =>
-            // CODEC_TYPE_PCM_U16BE_PLANAR =>
-            // CODEC_TYPE_PCM_U8_PLANAR    =>
-            // CODEC_TYPE_PCM_F32LE_PLANAR =>
-            // CODEC_TYPE_PCM_F32BE_PLANAR =>
-            // CODEC_TYPE_PCM_F64LE_PLANAR =>
-            // CODEC_TYPE_PCM_F64BE_PLANAR =>
-            _ => return unsupported_error("pcm: codec is unsupported."),
-        };
+/// Decodes a packet of PCM samples into `sink`, and returns the number of decoded frames.
+fn decode_pcm<S: PcmSink>(
+    codec: CodecType,
+    sample_width: u32,
+    packet: &Packet,
+    sink: &mut S,
+) -> Result<usize> {
+    let mut stream = packet.as_buf_reader();
+
+    // Signed or unsigned integer PCM codecs must be shifted to expand the sample into the
+    // entire i32 range. Only floating point samples may exceed 32 bits per coded sample, but
+    // they cannot be shifted, so int_shift = 0.
+    let int_shift = if sample_width <= 32 { 32 - sample_width } else { 0 };
+
+    let n_frames = match codec {
+        CODEC_TYPE_PCM_S32LE => read_pcm_signed!(sink, stream.read_u32()?, int_shift),
+        CODEC_TYPE_PCM_S32BE => read_pcm_signed!(sink, stream.read_be_u32()?, int_shift),
+        CODEC_TYPE_PCM_S24LE => read_pcm_signed!(sink, stream.read_u24()?, int_shift),
+        CODEC_TYPE_PCM_S24BE => read_pcm_signed!(sink, stream.read_be_u24()?, int_shift),
+        CODEC_TYPE_PCM_S16LE => read_pcm_signed!(sink, stream.read_u16()?, int_shift),
+        CODEC_TYPE_PCM_S16BE => read_pcm_signed!(sink, stream.read_be_u16()?, int_shift),
+        CODEC_TYPE_PCM_S8 => read_pcm_signed!(sink, stream.read_u8()?, int_shift),
+        CODEC_TYPE_PCM_U32LE => read_pcm_unsigned!(sink, stream.read_u32()?, int_shift),
+        CODEC_TYPE_PCM_U32BE => read_pcm_unsigned!(sink, stream.read_be_u32()?, int_shift),
+        CODEC_TYPE_PCM_U24LE => read_pcm_unsigned!(sink, stream.read_u24()?, int_shift),
+        CODEC_TYPE_PCM_U24BE => read_pcm_unsigned!(sink, stream.read_be_u24()?, int_shift),
+        CODEC_TYPE_PCM_U16LE => read_pcm_unsigned!(sink, stream.read_u16()?, int_shift),
+        CODEC_TYPE_PCM_U16BE => read_pcm_unsigned!(sink, stream.read_be_u16()?, int_shift),
+        CODEC_TYPE_PCM_U8 => read_pcm_unsigned!(sink, stream.read_u8()?, int_shift),
+        CODEC_TYPE_PCM_F32LE => read_pcm_floating!(sink, stream.read_f32()?),
+        CODEC_TYPE_PCM_F32BE => read_pcm_floating!(sink, stream.read_be_f32()?),
+        CODEC_TYPE_PCM_F64LE => read_pcm_floating!(sink, stream.read_f64()?),
+        CODEC_TYPE_PCM_F64BE => read_pcm_floating!(sink, stream.read_be_f64()?),
+        CODEC_TYPE_PCM_ALAW => {
+            read_pcm_transfer_func!(sink, alaw_to_linear(stream.read_u8()?))
+        }
+        CODEC_TYPE_PCM_MULAW => {
+            read_pcm_transfer_func!(sink, mulaw_to_linear(stream.read_u8()?))
+        }
+        // CODEC_TYPE_PCM_S32LE_PLANAR =>
+        // CODEC_TYPE_PCM_S32BE_PLANAR =>
+        // CODEC_TYPE_PCM_S24LE_PLANAR =>
+        // CODEC_TYPE_PCM_S24BE_PLANAR =>
+        // CODEC_TYPE_PCM_S16LE_PLANAR =>
+        // CODEC_TYPE_PCM_S16BE_PLANAR =>
+        // CODEC_TYPE_PCM_S8_PLANAR    =>
+        // CODEC_TYPE_PCM_U32LE_PLANAR =>
+        // CODEC_TYPE_PCM_U32BE_PLANAR =>
+        // CODEC_TYPE_PCM_U24LE_PLANAR =>
+        // CODEC_TYPE_PCM_U24BE_PLANAR =>
+        // CODEC_TYPE_PCM_U16LE_PLANAR =>
+        // CODEC_TYPE_PCM_U16BE_PLANAR =>
+        // CODEC_TYPE_PCM_U8_PLANAR    =>
+        // CODEC_TYPE_PCM_F32LE_PLANAR =>
+        // CODEC_TYPE_PCM_F32BE_PLANAR =>
+        // CODEC_TYPE_PCM_F64LE_PLANAR =>
+        // CODEC_TYPE_PCM_F64BE_PLANAR =>
+        _ => return unsupported_error("pcm: codec is unsupported."),
+    };
 
-        Ok(())
-    }
+    Ok(n_frames)
 }
 
 impl Decoder for PcmDecoder {
@@ -460,7 +493,7 @@ impl Decoder for PcmDecoder {
     }
 
     fn decode(&mut self, packet: &Packet) -> Result<AudioBufferRef<'_>> {
-        if let Err(e) = self.decode_inner(packet) {
+        if let Err(e) = decode_pcm(self.params.codec, self.sample_width, packet, &mut self.buf) {
             self.buf.clear();
             Err(e)
         }
@@ -469,6 +502,66 @@ impl Decoder for PcmDecoder {
         }
     }
 
+    fn decode_into(
+        &mut self,
+        packet: &Packet,
+        dst: AudioBufferMut<'_>,
+        dither: &mut Dynamic,
+    ) -> Result<usize> {
+        // Check the caller's buffer before the packet is consumed.
+        if dst.spec() != self.buf.spec() {
+            return unsupported_error("pcm: destination signal specification does not match");
+        }
+
+        if dst.capacity() < self.buf.capacity() {
+            return limit_error("pcm: destination buffer capacity is too small");
+        }
+
+        match dst {
+            // The samples are decoded directly into the caller's buffer if it uses the native
+            // sample format of the decoder.
+            AudioBufferMut::S32(dst) => {
+                self.buf.clear();
+
+                decode_pcm(self.params.codec, self.sample_width, packet, dst).map_err(|e| {
+                    dst.clear();
+                    e
+                })
+            }
+            dst => {
+                let decoded = self.decode(packet)?;
+                decoded.copy_to(dst, dither)?;
+                Ok(decoded.frames())
+            }
+        }
+    }
+
+    fn decode_interleaved(
+        &mut self,
+        packet: &Packet,
+        dst: InterleavedBufferMut<'_>,
+        dither: &mut Dynamic,
+    ) -> Result<usize> {
+        let n_channels = self.buf.spec().channels.count();
+
+        // Check the caller's buffer before the packet is consumed.
+        if dst.len() < self.buf.capacity() * n_channels {
+            return limit_error("pcm: destination buffer is too small");
+        }
+
+        match dst {
+            // PCM samples are interleaved, therefore they may be decoded directly into an
+            // interleaved buffer of the native sample format of the decoder.
+            InterleavedBufferMut::S32(dst) => {
+                self.buf.clear();
+
+                let mut sink = Interleaved { buf: dst, n_channels };
+                decode_pcm(self.params.codec, self.sample_width, packet, &mut sink)
+            }
+            dst => self.decode(packet)?.copy_to_interleaved(dst, dither),
+        }
+    }
+
     fn finalize(&mut self) -> FinalizeResult {
         Default::default()
     }
@@ -477,3 +570,60 @@ impl Decoder for PcmDecoder {
         self.buf.as_audio_buffer_ref()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use symphonia_core::audio::{AsAudioBufferMut, Channels};
+
+    use super::*;
+
+    #[test]
+    fn verify_decode_into() {
+        let mut params = CodecParameters::new();
+        params
+            .for_codec(CODEC_TYPE_PCM_S16LE)
+            .with_sample_rate(8000)
+            .with_channels(Channels::FRONT_LEFT | Channels::FRONT_RIGHT)
+            .with_bits_per_sample(16)
+            .with_max_frames_per_packet(4);
+
+        // Three stereo frames, and a trailing partial frame.
+        let data = [1, 0, 2, 0, 3, 0, 4, 0, 5, 0, 6, 0, 7, 0];
+        let packet = Packet::new_from_slice(0, 0, 3, &data);
+
+        let mut decoder = PcmDecoder::try_new(&params, &Default::default()).unwrap();
+        let mut dither = Dynamic::default();
+
+        let spec = *decoder.last_decoded().spec();
+
+        // Planar, and the native sample format.
+        let mut planar = AudioBuffer::<i32>::new(4, spec);
+        let n_frames =
+            decoder.decode_into(&packet, planar.as_audio_buffer_mut(), &mut dither).unwrap();
+
+        assert_eq!(n_frames, 3);
+        assert_eq!(planar.chan(0), &[1 << 16, 3 << 16, 5 << 16]);
+        assert_eq!(planar.chan(1), &[2 << 16, 4 << 16, 6 << 16]);
+
+        // Interleaved, and the native sample format.
+        let mut interleaved = [0i32; 8];
+        let n_frames = decoder
+            .decode_interleaved(&packet, (&mut interleaved[..]).into(), &mut dither)
+            .unwrap();
+
+        assert_eq!(n_frames, 3);
+        assert_eq!(&interleaved[..6], &[1 << 16, 2 << 16, 3 << 16, 4 << 16, 5 << 16, 6 << 16]);
+
+        // Interleaved, and converted.
+        let mut converted = [0i16; 8];
+        let n_frames =
+            decoder.decode_interleaved(&packet, (&mut converted[..]).into(), &mut dither).unwrap();
+
+        assert_eq!(n_frames, 3);
+        assert_eq!(&converted[..6], &[1, 2, 3, 4, 5, 6]);
+
+        // A buffer that may not fit the largest packet is rejected.
+        let mut small = [0i32; 6];
+        assert!(decoder.decode_interleaved(&packet, (&mut small[..]).into(), &mut dither).is_err());
+    }
+}
@@ -0,0 +1,93 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::errors::{decode_error, Result};
+
+/// A most-significant bit first reader for Speex packets.
+///
+/// In addition to reading bits, the Speex bitstream requires the ability to peek at the next bit
+/// and to know the exact number of bits remaining in the packet, since frames are packed
+/// back-to-back without any framing.
+pub struct SpeexBits<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> SpeexBits<'a> {
+    pub fn new(buf: &'a [u8]) -> Self {
+        SpeexBits { buf, pos: 0 }
+    }
+
+    /// Gets the number of bits remaining in the packet.
+    pub fn remaining(&self) -> usize {
+        8 * self.buf.len() - self.pos
+    }
+
+    /// Reads up-to 32 bits.
+    pub fn read(&mut self, n: u32) -> Result<u32> {
+        if n as usize > self.remaining() {
+            return decode_error("speex: packet overflow");
+        }
+
+        let mut value = 0;
+
+        for _ in 0..n {
+            let bit = (self.buf[self.pos >> 3] >> (7 - (self.pos & 0x7))) & 1;
+            value = (value << 1) | u32::from(bit);
+            self.pos += 1;
+        }
+
+        Ok(value)
+    }
+
+    /// Reads a single bit as a boolean.
+    pub fn read_bool(&mut self) -> Result<bool> {
+        Ok(self.read(1)? == 1)
+    }
+
+    /// Gets the value of the next bit without consuming it. Returns `None` if there are no more
+    /// bits remaining.
+    pub fn peek_bool(&self) -> Option<bool> {
+        if self.remaining() > 0 {
+            Some((self.buf[self.pos >> 3] >> (7 - (self.pos & 0x7))) & 1 == 1)
+        }
+        else {
+            None
+        }
+    }
+
+    /// Skips the given number of bits.
+    pub fn skip(&mut self, n: usize) -> Result<()> {
+        if n > self.remaining() {
+            return decode_error("speex: packet overflow");
+        }
+
+        self.pos += n;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SpeexBits;
+
+    #[test]
+    fn verify_speex_bits() {
+        let mut bits = SpeexBits::new(&[0b1011_0010, 0b0111_1111]);
+
+        assert_eq!(bits.remaining(), 16);
+        assert_eq!(bits.peek_bool(), Some(true));
+        assert_eq!(bits.read(1).unwrap(), 1);
+        assert_eq!(bits.read(4).unwrap(), 0b0110);
+        assert_eq!(bits.read(6).unwrap(), 0b010_011);
+        assert!(bits.skip(4).is_ok());
+        assert_eq!(bits.remaining(), 1);
+        assert!(bits.read(2).is_err());
+        assert!(bits.read_bool().unwrap());
+        assert_eq!(bits.peek_bool(), None);
+    }
+}
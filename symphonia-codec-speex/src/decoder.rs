@@ -0,0 +1,145 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::audio::{AsAudioBufferRef, AudioBuffer, AudioBufferRef, Channels};
+use symphonia_core::audio::{Signal, SignalSpec};
+use symphonia_core::codecs::{CodecDescriptor, CodecParameters, CODEC_TYPE_SPEEX};
+use symphonia_core::codecs::{Decoder, DecoderOptions, FinalizeResult};
+use symphonia_core::errors::{unsupported_error, Result};
+use symphonia_core::formats::Packet;
+use symphonia_core::support_codec;
+
+use log::warn;
+
+use crate::bits::SpeexBits;
+use crate::header::SpeexHeader;
+use crate::sb::BandDecoder;
+use crate::stereo::StereoState;
+
+/// Speex decoder.
+pub struct SpeexDecoder {
+    params: CodecParameters,
+    header: SpeexHeader,
+    band: BandDecoder,
+    stereo: StereoState,
+    frame_size: usize,
+    /// The mono output of a single frame.
+    frame: Vec<f32>,
+    buf: AudioBuffer<f32>,
+}
+
+impl SpeexDecoder {
+    fn decode_inner(&mut self, packet: &Packet) -> Result<()> {
+        let mut bits = SpeexBits::new(packet.buf());
+
+        let fs = self.frame_size;
+        let fpp = self.header.frames_per_packet as usize;
+
+        self.buf.clear();
+        self.buf.render_reserved(Some(fpp * fs));
+
+        let mut n_frames = 0;
+
+        // A packet may contain less frames than the header indicates if it is terminated early.
+        while n_frames < fpp {
+            match self.band.decode(&mut bits, &mut self.frame, &mut self.stereo) {
+                Ok(true) => (),
+                Ok(false) => break,
+                Err(err) if n_frames == 0 => return Err(err),
+                Err(err) => {
+                    warn!("{}", err);
+                    break;
+                }
+            }
+
+            let range = n_frames * fs..(n_frames + 1) * fs;
+
+            if self.header.n_channels == 2 {
+                let (left, right) = self.buf.chan_pair_mut(0, 1);
+                self.stereo.apply(&self.frame, &mut left[range.clone()], &mut right[range]);
+            }
+            else {
+                self.buf.chan_mut(0)[range].copy_from_slice(&self.frame);
+            }
+
+            n_frames += 1;
+        }
+
+        self.buf.truncate(n_frames * fs);
+
+        // The decoder operates on 16-bit sample values.
+        self.buf.transform(|sample| sample / 32768.0);
+
+        Ok(())
+    }
+}
+
+impl Decoder for SpeexDecoder {
+    fn try_new(params: &CodecParameters, _options: &DecoderOptions) -> Result<Self> {
+        // This decoder only supports Speex.
+        if params.codec != CODEC_TYPE_SPEEX {
+            return unsupported_error("speex: invalid codec type");
+        }
+
+        // The Speex header is required.
+        let header = match params.extra_data.as_ref() {
+            Some(buf) => SpeexHeader::read(buf)?,
+            _ => return unsupported_error("speex: missing extra data"),
+        };
+
+        let channels = match header.n_channels {
+            1 => Channels::FRONT_LEFT,
+            _ => Channels::FRONT_LEFT | Channels::FRONT_RIGHT,
+        };
+
+        let frame_size = header.mode.frame_size();
+
+        let spec = SignalSpec::new(header.rate, channels);
+        let buf = AudioBuffer::new((header.frames_per_packet as usize * frame_size) as u64, spec);
+
+        Ok(SpeexDecoder {
+            params: params.clone(),
+            band: BandDecoder::new(header.mode),
+            header,
+            stereo: Default::default(),
+            frame_size,
+            frame: vec![0.0; frame_size],
+            buf,
+        })
+    }
+
+    fn supported_codecs() -> &'static [CodecDescriptor] {
+        &[support_codec!(CODEC_TYPE_SPEEX, "speex", "Speex")]
+    }
+
+    fn reset(&mut self) {
+        self.band = BandDecoder::new(self.header.mode);
+        self.stereo = Default::default();
+    }
+
+    fn codec_params(&self) -> &CodecParameters {
+        &self.params
+    }
+
+    fn decode(&mut self, packet: &Packet) -> Result<AudioBufferRef<'_>> {
+        if let Err(e) = self.decode_inner(packet) {
+            self.buf.clear();
+            Err(e)
+        }
+        else {
+            Ok(self.buf.as_audio_buffer_ref())
+        }
+    }
+
+    fn finalize(&mut self) -> FinalizeResult {
+        Default::default()
+    }
+
+    fn last_decoded(&self) -> AudioBufferRef<'_> {
+        self.buf.as_audio_buffer_ref()
+    }
+}
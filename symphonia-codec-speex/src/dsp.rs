@@ -0,0 +1,418 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::f32::consts::PI;
+
+/// The maximum LPC order used by any Speex mode.
+pub const MAX_LPC_ORDER: usize = 10;
+
+/// The order of the QMF filter bank used to split the spectrum of the sub-band modes.
+pub const QMF_ORDER: usize = 64;
+
+/// The QMF filter bank prototype filter.
+#[allow(clippy::excessive_precision)]
+#[rustfmt::skip]
+const QMF_H0: [f32; QMF_ORDER] = [
+    3.596189e-05, -0.0001123515, -0.0001104587, 0.0002790277,
+    0.0002298438, -0.0005953563, -0.0003823631, 0.00113826,
+    0.0005308539, -0.001986177, -0.0006243724, 0.003235877,
+    0.0005743159, -0.004989147, -0.0002584767, 0.007367171,
+    -0.0004857935, -0.01050689, 0.001894714, 0.01459396,
+    -0.004313674, -0.01994365, 0.00828756, 0.02716055,
+    -0.01485397, -0.03764973, 0.026447, 0.05543245,
+    -0.05095487, -0.09779096, 0.1382363, 0.4600981,
+    0.4600981, 0.1382363, -0.09779096, -0.05095487,
+    0.05543245, 0.026447, -0.03764973, -0.01485397,
+    0.02716055, 0.00828756, -0.01994365, -0.004313674,
+    0.01459396, 0.001894714, -0.01050689, -0.0004857935,
+    0.007367171, -0.0002584767, -0.004989147, 0.0005743159,
+    0.003235877, -0.0006243724, -0.001986177, 0.0005308539,
+    0.00113826, -0.0003823631, -0.0005953563, 0.0002298438,
+    0.0002790277, -0.0001104587, -0.0001123515, 3.596189e-05,
+];
+
+/// The interpolation filters used by the pitch enhancer to obtain fractional pitch delays.
+#[rustfmt::skip]
+const SHIFT_FILT: [[f32; 7]; 3] = [
+    [-0.011915, 0.046995, -0.152373, 0.614108, 0.614108, -0.152373, 0.046995],
+    [-0.0324855, 0.0859768, -0.2042986, 0.9640297, 0.2086420, -0.0302054, -0.0063646],
+    [-0.0063646, -0.0302054, 0.2086420, 0.9640297, -0.2042986, 0.0859768, -0.0324855],
+];
+
+/// The numerator (zeros) of the output high-pass filters.
+const HIGHPASS_ZCOEF: [[f32; 3]; 2] = [[0.96446, -1.92879, 0.96446], [0.98645, -1.97277, 0.98645]];
+
+/// The denominator (poles) of the output high-pass filters.
+const HIGHPASS_PCOEF: [[f32; 3]; 2] = [[1.0, -1.92683, 0.93071], [1.0, -1.97226, 0.97332]];
+
+/// Generates a uniformly distributed pseudo-random value with standard deviation `std`.
+pub fn rand(std: f32, seed: &mut u32) -> f32 {
+    *seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+
+    // Build a float in the range [1, 2) from the seed.
+    let ran = f32::from_bits(0x3f80_0000 | (*seed & 0x007f_ffff)) - 1.5;
+
+    3.4642 * std * ran
+}
+
+/// Computes the RMS of a signal.
+pub fn compute_rms(x: &[f32]) -> f32 {
+    let sum: f32 = x.iter().map(|&s| s * s).sum();
+    (0.1 + sum / x.len() as f32).sqrt()
+}
+
+/// Computes the inner product of two signals.
+fn inner_prod(x: &[f32], y: &[f32]) -> f32 {
+    x.iter().zip(y).map(|(&a, &b)| a * b).sum()
+}
+
+/// Applies bandwidth expansion to a set of LPC coefficients.
+pub fn bw_lpc(gamma: f32, lpc_in: &[f32], lpc_out: &mut [f32]) {
+    let mut tmp = gamma;
+
+    for (out, &lpc) in lpc_out.iter_mut().zip(lpc_in) {
+        *out = tmp * lpc;
+        tmp *= gamma;
+    }
+}
+
+/// Clamps all values to the range `[-limit, limit]`, replacing NaNs with 0.
+pub fn sanitize(x: &mut [f32], limit: f32) {
+    for s in x.iter_mut() {
+        *s = if s.is_nan() { 0.0 } else { s.clamp(-limit, limit) };
+    }
+}
+
+/// Filters a signal, in-place, with an all-pole (synthesis) filter.
+pub fn iir_mem(buf: &mut [f32], den: &[f32], mem: &mut [f32]) {
+    let order = den.len();
+
+    for s in buf.iter_mut() {
+        let yi = *s + mem[0];
+        let nyi = -yi;
+
+        for j in 0..order - 1 {
+            mem[j] = mem[j + 1] + den[j] * nyi;
+        }
+
+        mem[order - 1] = den[order - 1] * nyi;
+
+        *s = yi;
+    }
+}
+
+/// Applies the output high-pass filter to a signal in-place.
+pub fn highpass(buf: &mut [f32], is_wideband: bool, mem: &mut [f32; 2]) {
+    let num = &HIGHPASS_ZCOEF[usize::from(is_wideband)];
+    let den = &HIGHPASS_PCOEF[usize::from(is_wideband)];
+
+    for s in buf.iter_mut() {
+        let x = *s;
+        let vout = num[0] * x + mem[0];
+
+        mem[0] = mem[1] + num[1] * x - den[1] * vout;
+        mem[1] = num[2] * x - den[2] * vout;
+
+        *s = vout;
+    }
+}
+
+/// Converts a set of line spectral pairs into LPC coefficients.
+pub fn lsp_to_lpc(freq: &[f32], ak: &mut [f32]) {
+    let order = freq.len();
+    let m = order >> 1;
+
+    let mut wp = [0f32; 2 * MAX_LPC_ORDER + 2];
+    let mut x_freq = [0f32; MAX_LPC_ORDER];
+
+    for (x, &f) in x_freq.iter_mut().zip(freq) {
+        *x = f.cos();
+    }
+
+    let mut xin1 = 1.0;
+    let mut xin2 = 1.0;
+
+    // Reconstruct P(z) and Q(z) by cascading second order polynomials of the form
+    // 1 - 2xz^-1 + z^-2, where x is the LSP coefficient.
+    for j in 0..=order {
+        for i in 0..m {
+            let n = 4 * i;
+
+            let xout1 = xin1 - 2.0 * x_freq[2 * i] * wp[n] + wp[n + 1];
+            let xout2 = xin2 - 2.0 * x_freq[2 * i + 1] * wp[n + 2] + wp[n + 3];
+
+            wp[n + 1] = wp[n];
+            wp[n + 3] = wp[n + 2];
+            wp[n] = xin1;
+            wp[n + 2] = xin2;
+
+            xin1 = xout1;
+            xin2 = xout2;
+        }
+
+        let xout1 = xin1 + wp[4 * m];
+        let xout2 = xin2 - wp[4 * m + 1];
+
+        if j > 0 {
+            ak[j - 1] = (xout1 + xout2) * 0.5;
+        }
+
+        wp[4 * m] = xin1;
+        wp[4 * m + 1] = xin2;
+
+        xin1 = 0.0;
+        xin2 = 0.0;
+    }
+}
+
+/// Interpolates between two sets of line spectral pairs for the given sub-frame, and enforces a
+/// minimum spacing between them to ensure stability.
+pub fn lsp_interpolate(
+    old_lsp: &[f32],
+    new_lsp: &[f32],
+    lsp: &mut [f32],
+    subframe: usize,
+    n_subframes: usize,
+    margin: f32,
+) {
+    let len = lsp.len();
+    let tmp = (1.0 + subframe as f32) / n_subframes as f32;
+
+    for ((l, &old), &new) in lsp.iter_mut().zip(old_lsp).zip(new_lsp) {
+        *l = (1.0 - tmp) * old + tmp * new;
+    }
+
+    if lsp[0] < margin {
+        lsp[0] = margin;
+    }
+
+    if lsp[len - 1] > PI - margin {
+        lsp[len - 1] = PI - margin;
+    }
+
+    for i in 1..len - 1 {
+        if lsp[i] < lsp[i - 1] + margin {
+            lsp[i] = lsp[i - 1] + margin;
+        }
+
+        if lsp[i] > lsp[i + 1] - margin {
+            lsp[i] = 0.5 * (lsp[i] + lsp[i + 1] - margin);
+        }
+    }
+}
+
+/// Synthesizes a full-band signal from the low and high sub-band signals using a QMF filter bank.
+pub fn qmf_synth(
+    x1: &[f32],
+    x2: &[f32],
+    y: &mut [f32],
+    mem1: &mut [f32; QMF_ORDER],
+    mem2: &mut [f32; QMF_ORDER],
+) {
+    let a = &QMF_H0;
+
+    let m2 = QMF_ORDER >> 1;
+    let n2 = x1.len();
+
+    let mut xx1 = vec![0.0; m2 + n2];
+    let mut xx2 = vec![0.0; m2 + n2];
+
+    for i in 0..n2 {
+        xx1[i] = x1[n2 - 1 - i];
+        xx2[i] = x2[n2 - 1 - i];
+    }
+
+    for i in 0..m2 {
+        xx1[n2 + i] = mem1[2 * i + 1];
+        xx2[n2 + i] = mem2[2 * i + 1];
+    }
+
+    for i in (0..n2).step_by(2) {
+        let mut y0 = 0.0;
+        let mut y1 = 0.0;
+        let mut y2 = 0.0;
+        let mut y3 = 0.0;
+
+        let mut x10 = xx1[n2 - 2 - i];
+        let mut x20 = xx2[n2 - 2 - i];
+
+        for j in (0..m2).step_by(2) {
+            let a0 = a[2 * j];
+            let a1 = a[2 * j + 1];
+            let x11 = xx1[n2 - 1 + j - i];
+            let x21 = xx2[n2 - 1 + j - i];
+
+            y0 += a0 * (x11 - x21);
+            y1 += a1 * (x11 + x21);
+            y2 += a0 * (x10 - x20);
+            y3 += a1 * (x10 + x20);
+
+            let a0 = a[2 * j + 2];
+            let a1 = a[2 * j + 3];
+            x10 = xx1[n2 + j - i];
+            x20 = xx2[n2 + j - i];
+
+            y0 += a0 * (x10 - x20);
+            y1 += a1 * (x10 + x20);
+            y2 += a0 * (x11 - x21);
+            y3 += a1 * (x11 + x21);
+        }
+
+        y[2 * i] = 2.0 * y0;
+        y[2 * i + 1] = 2.0 * y1;
+        y[2 * i + 2] = 2.0 * y2;
+        y[2 * i + 3] = 2.0 * y3;
+    }
+
+    for i in 0..m2 {
+        mem1[2 * i + 1] = xx1[i];
+        mem2[2 * i + 1] = xx2[i];
+    }
+}
+
+/// Interpolates the excitation at a fractional pitch delay close to `pitch`. The excitation starts
+/// at `buf[pos]`, and `pitch` may be negative to look forward.
+fn interp_pitch(buf: &[f32], pos: usize, interp: &mut [f32], pitch: isize) {
+    let len = interp.len();
+
+    let at = |offset: isize| buf[(pos as isize + offset) as usize];
+
+    let exc = &buf[pos..pos + len];
+
+    let mut corr = [[0f32; 7]; 4];
+
+    for (i, c) in corr[0].iter_mut().enumerate() {
+        let start = (pos as isize - pitch - 3 + i as isize) as usize;
+        *c = inner_prod(exc, &buf[start..start + len]);
+    }
+
+    for i in 0..3 {
+        for j in 0..7 {
+            let i1 = 3usize.saturating_sub(j);
+            let i2 = (10 - j).min(7);
+
+            corr[i + 1][j] = (i1..i2).map(|k| SHIFT_FILT[i][k] * corr[0][j + k - 3]).sum();
+        }
+    }
+
+    let mut maxi = 0;
+    let mut maxj = 0;
+    let mut maxcorr = corr[0][0];
+
+    for (i, row) in corr.iter().enumerate() {
+        for (j, &c) in row.iter().enumerate() {
+            if c > maxcorr {
+                maxcorr = c;
+                maxi = i;
+                maxj = j;
+            }
+        }
+    }
+
+    let delay = pitch - maxj as isize + 3;
+
+    for (i, out) in interp.iter_mut().enumerate() {
+        let i = i as isize;
+
+        *out = if maxi > 0 {
+            (0..7).map(|k| at(i - delay + k - 3) * SHIFT_FILT[maxi - 1][k as usize]).sum()
+        }
+        else {
+            at(i - delay)
+        };
+    }
+}
+
+/// Enhances the periodicity of the excitation starting at `buf[pos]` with a comb filter and writes
+/// the result to `new_exc`.
+pub fn multicomb(
+    buf: &[f32],
+    pos: usize,
+    new_exc: &mut [f32],
+    pitch: usize,
+    max_pitch: usize,
+    comb_gain: f32,
+) {
+    let nsf = new_exc.len();
+
+    let mut iexc = [0f32; 160];
+    let (iexc0, iexc1) = iexc[..2 * nsf].split_at_mut(nsf);
+
+    let corr_pitch = pitch as isize;
+
+    interp_pitch(buf, pos, iexc0, corr_pitch);
+
+    if pitch > max_pitch {
+        interp_pitch(buf, pos, iexc1, 2 * corr_pitch);
+    }
+    else {
+        interp_pitch(buf, pos, iexc1, -corr_pitch);
+    }
+
+    let exc = &buf[pos..pos + nsf];
+
+    let iexc0_mag = (1000.0 + inner_prod(iexc0, iexc0)).sqrt();
+    let iexc1_mag = (1000.0 + inner_prod(iexc1, iexc1)).sqrt();
+    let exc_mag = (1.0 + inner_prod(exc, exc)).sqrt();
+
+    let corr0 = inner_prod(iexc0, exc).max(0.0);
+    let corr1 = inner_prod(iexc1, exc).max(0.0);
+
+    let pgain1 = if corr0 > iexc0_mag * exc_mag { 1.0 } else { corr0 / exc_mag / iexc0_mag };
+    let pgain2 = if corr1 > iexc1_mag * exc_mag { 1.0 } else { corr1 / exc_mag / iexc1_mag };
+
+    let gg1 = exc_mag / iexc0_mag;
+    let gg2 = exc_mag / iexc1_mag;
+
+    let (c1, c2) = if comb_gain > 0.0 {
+        let c1 = 0.4 * comb_gain + 0.07;
+        (c1, 0.5 + 1.72 * (c1 - 0.07))
+    }
+    else {
+        (0.0, 0.0)
+    };
+
+    let g1 = c1 / (1.0 - c2 * pgain1 * pgain1).max(c1);
+    let g2 = c1 / (1.0 - c2 * pgain2 * pgain2).max(c1);
+
+    let (gain0, gain1) = if pitch > max_pitch {
+        (0.7 * g1 * gg1, 0.3 * g2 * gg2)
+    }
+    else {
+        (0.6 * g1 * gg1, 0.6 * g2 * gg2)
+    };
+
+    for (i, out) in new_exc.iter_mut().enumerate() {
+        *out = exc[i] + gain0 * iexc0[i] + gain1 * iexc1[i];
+    }
+
+    let new_ener = compute_rms(new_exc).max(1.0);
+    let old_ener = compute_rms(exc).max(1.0).min(new_ener);
+
+    let ngain = old_ener / new_ener;
+
+    new_exc.iter_mut().for_each(|s| *s *= ngain);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_lsp_to_lpc_flat() {
+        // Evenly spaced LSPs correspond to a flat spectrum, and therefore an LPC filter whose
+        // coefficients are all 0.
+        let lsp: Vec<f32> = (0..10).map(|i| PI * (i as f32 + 1.0) / 11.0).collect();
+        let mut ak = [1.0; 10];
+
+        lsp_to_lpc(&lsp, &mut ak);
+
+        for a in ak.iter() {
+            assert!(a.abs() < 1e-5, "{:?}", ak);
+        }
+    }
+}
@@ -0,0 +1,120 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::errors::{decode_error, unsupported_error, Result};
+use symphonia_core::io::{BufReader, ReadBytes};
+
+use crate::modes::{Mode, BITSTREAM_VERSION};
+
+/// The signature of a Speex header.
+const SPEEX_HEADER_SIGNATURE: &[u8] = b"Speex   ";
+
+/// The size of a Speex header.
+pub const SPEEX_HEADER_SIZE: usize = 80;
+
+/// The maximum number of frames per packet. The reference encoder supports up-to 10.
+const MAX_FRAMES_PER_PACKET: u32 = 10;
+
+/// The Speex header.
+#[derive(Debug)]
+pub struct SpeexHeader {
+    pub rate: u32,
+    pub mode: Mode,
+    pub n_channels: u32,
+    pub frames_per_packet: u32,
+}
+
+impl SpeexHeader {
+    /// Reads a Speex header.
+    pub fn read(buf: &[u8]) -> Result<SpeexHeader> {
+        if buf.len() < SPEEX_HEADER_SIZE {
+            return decode_error("speex: header too small");
+        }
+
+        let mut reader = BufReader::new(buf);
+
+        let mut signature = [0; 8];
+        reader.read_buf_exact(&mut signature)?;
+
+        if signature != *SPEEX_HEADER_SIGNATURE {
+            return decode_error("speex: invalid header signature");
+        }
+
+        // The version string (20 bytes), the version identifier, and the header size.
+        reader.ignore_bytes(20 + 4 + 4)?;
+
+        let rate = reader.read_u32()?;
+
+        if rate == 0 {
+            return decode_error("speex: invalid sample rate");
+        }
+
+        let mode = match Mode::from_id(reader.read_u32()?) {
+            Some(mode) => mode,
+            None => return decode_error("speex: invalid mode"),
+        };
+
+        if reader.read_u32()? != BITSTREAM_VERSION {
+            return unsupported_error("speex: unsupported bitstream version");
+        }
+
+        // The reference decoder clamps the number of channels.
+        let n_channels = reader.read_u32()?.clamp(1, 2);
+
+        // The bitrate, and frame size.
+        reader.ignore_bytes(4 + 4)?;
+
+        // Variable bitrate flag.
+        let _vbr = reader.read_u32()?;
+
+        let frames_per_packet = match reader.read_u32()? {
+            0 => 1,
+            fpp if fpp <= MAX_FRAMES_PER_PACKET => fpp,
+            _ => return decode_error("speex: too many frames per packet"),
+        };
+
+        Ok(SpeexHeader { rate, mode, n_channels, frames_per_packet })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(rate: u32, frames_per_packet: u32) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(SPEEX_HEADER_SIZE);
+        buf.extend_from_slice(SPEEX_HEADER_SIGNATURE);
+        buf.extend_from_slice(&[0; 20]);
+
+        // Version identifier, header size, rate, mode, bitstream version, channels, bitrate, frame
+        // size, VBR, frames per packet, extra headers, and 2 reserved fields.
+        for value in
+            [1, 80, rate, 1, BITSTREAM_VERSION, 2, u32::MAX, 320, 0, frames_per_packet, 0, 0, 0]
+        {
+            buf.extend_from_slice(&value.to_le_bytes());
+        }
+
+        buf
+    }
+
+    #[test]
+    fn verify_header_limits() {
+        let hdr = SpeexHeader::read(&header(16000, 0)).unwrap();
+        assert_eq!(hdr.rate, 16000);
+        assert_eq!(hdr.n_channels, 2);
+        assert_eq!(hdr.frames_per_packet, 1);
+
+        assert_eq!(SpeexHeader::read(&header(16000, 10)).unwrap().frames_per_packet, 10);
+
+        // A crafted header must not cause an unbounded allocation in the decoder.
+        assert!(SpeexHeader::read(&header(16000, 11)).is_err());
+        assert!(SpeexHeader::read(&header(16000, u32::MAX)).is_err());
+
+        assert!(SpeexHeader::read(&header(0, 1)).is_err());
+        assert!(SpeexHeader::read(&header(16000, 1)[..SPEEX_HEADER_SIZE - 1]).is_err());
+    }
+}
@@ -0,0 +1,264 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use crate::tables::*;
+
+/// The Speex modes.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Mode {
+    /// Narrowband, 8kHz.
+    Narrowband,
+    /// Wideband, 16kHz.
+    Wideband,
+    /// Ultra-wideband, 32kHz.
+    UltraWideband,
+}
+
+impl Mode {
+    /// Gets the mode from the mode number stored in the Speex header.
+    pub fn from_id(id: u32) -> Option<Mode> {
+        match id {
+            0 => Some(Mode::Narrowband),
+            1 => Some(Mode::Wideband),
+            2 => Some(Mode::UltraWideband),
+            _ => None,
+        }
+    }
+
+    /// Gets the number of samples in a frame.
+    pub fn frame_size(&self) -> usize {
+        match self {
+            Mode::Narrowband => 160,
+            Mode::Wideband => 320,
+            Mode::UltraWideband => 640,
+        }
+    }
+}
+
+/// The bitstream version of all modes.
+pub const BITSTREAM_VERSION: u32 = 4;
+
+/// Line spectral pair quantizers.
+#[derive(Copy, Clone)]
+pub enum LspQuant {
+    /// Narrowband, 30 bits.
+    Nb,
+    /// Narrowband low bit-rate, 18 bits.
+    Lbr,
+    /// High-band, 12 bits.
+    High,
+}
+
+/// Parameters of the 3-tap long-term (pitch) predictor.
+#[derive(Copy, Clone)]
+pub struct LtpParams {
+    pub gain_cdbk: &'static [i8],
+    pub gain_bits: u32,
+    pub pitch_bits: u32,
+}
+
+/// Long-term (pitch) predictors.
+#[derive(Copy, Clone)]
+pub enum LtpQuant {
+    /// The pitch gain is fixed for the frame, and the pitch is the open-loop pitch.
+    Forced,
+    /// A 3-tap predictor.
+    ThreeTap(LtpParams),
+}
+
+/// Parameters of a split vector quantized innovation codebook.
+#[derive(Copy, Clone)]
+pub struct SplitCbParams {
+    pub subvect_size: usize,
+    pub n_subvect: usize,
+    pub shape_cb: &'static [i8],
+    pub shape_bits: u32,
+    pub have_sign: bool,
+}
+
+/// Innovation (fixed codebook) quantizers.
+#[derive(Copy, Clone)]
+pub enum InnovQuant {
+    /// Random noise.
+    Noise,
+    /// Split vector quantized codebook.
+    SplitCb(SplitCbParams),
+}
+
+/// A narrowband sub-mode.
+pub struct NbSubmode {
+    /// The open-loop pitch margin, or `None` if the pitch is not relative to an open-loop pitch.
+    pub lbr_pitch: Option<usize>,
+    /// Use a global, forced, pitch gain.
+    pub forced_pitch_gain: bool,
+    /// The number of bits of the per-subframe gain correction (0, 1, or 3).
+    pub have_subframe_gain: u32,
+    /// Apply a second innovation codebook.
+    pub double_codebook: bool,
+    pub lsp: LspQuant,
+    pub ltp: LtpQuant,
+    pub innovation: InnovQuant,
+    /// The gain of the enhancer's comb filter, or a negative value if it is disabled.
+    pub comb_gain: f32,
+}
+
+/// A sub-band (high-band) sub-mode.
+pub struct SbSubmode {
+    /// Apply a second innovation codebook.
+    pub double_codebook: bool,
+    /// The innovation codebook, or `None` if the high-band is folded from the low-band.
+    pub innovation: Option<SplitCbParams>,
+}
+
+const LTP_PARAMS_NB: LtpParams =
+    LtpParams { gain_cdbk: &GAIN_CDBK_NB, gain_bits: 7, pitch_bits: 7 };
+const LTP_PARAMS_VLBR: LtpParams =
+    LtpParams { gain_cdbk: &GAIN_CDBK_LBR, gain_bits: 5, pitch_bits: 0 };
+const LTP_PARAMS_LBR: LtpParams =
+    LtpParams { gain_cdbk: &GAIN_CDBK_LBR, gain_bits: 5, pitch_bits: 7 };
+
+const fn split_cb(
+    subvect_size: usize,
+    n_subvect: usize,
+    shape_cb: &'static [i8],
+    shape_bits: u32,
+    have_sign: bool,
+) -> SplitCbParams {
+    SplitCbParams { subvect_size, n_subvect, shape_cb, shape_bits, have_sign }
+}
+
+const SPLIT_CB_NB_VLBR: SplitCbParams = split_cb(10, 4, &EXC_10_16, 4, false);
+const SPLIT_CB_NB_ULBR: SplitCbParams = split_cb(20, 2, &EXC_20_32, 5, false);
+const SPLIT_CB_NB_LBR: SplitCbParams = split_cb(10, 4, &EXC_10_32, 5, false);
+const SPLIT_CB_NB: SplitCbParams = split_cb(5, 8, &EXC_5_64, 6, false);
+const SPLIT_CB_NB_MED: SplitCbParams = split_cb(8, 5, &EXC_8_128, 7, false);
+const SPLIT_CB_SB: SplitCbParams = split_cb(5, 8, &EXC_5_256, 8, false);
+const SPLIT_CB_HIGH: SplitCbParams = split_cb(8, 5, &HEXC_8_128, 7, true);
+const SPLIT_CB_HIGH_LBR: SplitCbParams = split_cb(10, 4, &HEXC_10_32, 5, false);
+
+/// The narrowband sub-modes indexed by sub-mode number. Sub-mode 0 is the null mode.
+pub const NB_SUBMODES: [Option<NbSubmode>; 9] = [
+    None,
+    // 2150 bps "vocoder-like" mode for comfort noise.
+    Some(NbSubmode {
+        lbr_pitch: Some(0),
+        forced_pitch_gain: true,
+        have_subframe_gain: 0,
+        double_codebook: false,
+        lsp: LspQuant::Lbr,
+        ltp: LtpQuant::Forced,
+        innovation: InnovQuant::Noise,
+        comb_gain: -1.0,
+    }),
+    // 5.95 kbps very low bit-rate mode.
+    Some(NbSubmode {
+        lbr_pitch: Some(0),
+        forced_pitch_gain: false,
+        have_subframe_gain: 0,
+        double_codebook: false,
+        lsp: LspQuant::Lbr,
+        ltp: LtpQuant::ThreeTap(LTP_PARAMS_VLBR),
+        innovation: InnovQuant::SplitCb(SPLIT_CB_NB_VLBR),
+        comb_gain: 0.6,
+    }),
+    // 8 kbps low bit-rate mode.
+    Some(NbSubmode {
+        lbr_pitch: None,
+        forced_pitch_gain: false,
+        have_subframe_gain: 1,
+        double_codebook: false,
+        lsp: LspQuant::Lbr,
+        ltp: LtpQuant::ThreeTap(LTP_PARAMS_LBR),
+        innovation: InnovQuant::SplitCb(SPLIT_CB_NB_LBR),
+        comb_gain: 0.55,
+    }),
+    // 11 kbps medium bit-rate mode.
+    Some(NbSubmode {
+        lbr_pitch: None,
+        forced_pitch_gain: false,
+        have_subframe_gain: 1,
+        double_codebook: false,
+        lsp: LspQuant::Lbr,
+        ltp: LtpQuant::ThreeTap(LTP_PARAMS_LBR),
+        innovation: InnovQuant::SplitCb(SPLIT_CB_NB_MED),
+        comb_gain: 0.45,
+    }),
+    // 15 kbps high bit-rate mode.
+    Some(NbSubmode {
+        lbr_pitch: None,
+        forced_pitch_gain: false,
+        have_subframe_gain: 3,
+        double_codebook: false,
+        lsp: LspQuant::Nb,
+        ltp: LtpQuant::ThreeTap(LTP_PARAMS_NB),
+        innovation: InnovQuant::SplitCb(SPLIT_CB_NB),
+        comb_gain: 0.25,
+    }),
+    // 18.2 kbps high bit-rate mode.
+    Some(NbSubmode {
+        lbr_pitch: None,
+        forced_pitch_gain: false,
+        have_subframe_gain: 3,
+        double_codebook: false,
+        lsp: LspQuant::Nb,
+        ltp: LtpQuant::ThreeTap(LTP_PARAMS_NB),
+        innovation: InnovQuant::SplitCb(SPLIT_CB_SB),
+        comb_gain: 0.15,
+    }),
+    // 24.6 kbps high bit-rate mode.
+    Some(NbSubmode {
+        lbr_pitch: None,
+        forced_pitch_gain: false,
+        have_subframe_gain: 3,
+        double_codebook: true,
+        lsp: LspQuant::Nb,
+        ltp: LtpQuant::ThreeTap(LTP_PARAMS_NB),
+        innovation: InnovQuant::SplitCb(SPLIT_CB_NB),
+        comb_gain: 0.05,
+    }),
+    // 3.95 kbps very low bit-rate mode.
+    Some(NbSubmode {
+        lbr_pitch: Some(0),
+        forced_pitch_gain: true,
+        have_subframe_gain: 0,
+        double_codebook: false,
+        lsp: LspQuant::Lbr,
+        ltp: LtpQuant::Forced,
+        innovation: InnovQuant::SplitCb(SPLIT_CB_NB_ULBR),
+        comb_gain: 0.5,
+    }),
+];
+
+/// The wideband high-band sub-modes indexed by sub-mode number. Sub-mode 0 is the null mode.
+pub const WB_SUBMODES: [Option<SbSubmode>; 8] = [
+    None,
+    Some(SbSubmode { double_codebook: false, innovation: None }),
+    Some(SbSubmode { double_codebook: false, innovation: Some(SPLIT_CB_HIGH_LBR) }),
+    Some(SbSubmode { double_codebook: false, innovation: Some(SPLIT_CB_HIGH) }),
+    Some(SbSubmode { double_codebook: true, innovation: Some(SPLIT_CB_HIGH) }),
+    None,
+    None,
+    None,
+];
+
+/// The ultra-wideband high-band sub-modes indexed by sub-mode number. Sub-mode 0 is the null
+/// mode.
+pub const UWB_SUBMODES: [Option<SbSubmode>; 8] = [
+    None,
+    Some(SbSubmode { double_codebook: false, innovation: None }),
+    None,
+    None,
+    None,
+    None,
+    None,
+    None,
+];
+
+/// The number of bits used by each wideband sub-mode, including the wideband bit and sub-mode
+/// number. A narrowband decoder uses this table to skip the high-band layers. A value of 0
+/// indicates an invalid sub-mode.
+pub const WB_SKIP_TABLE: [usize; 8] = [0, 36, 112, 192, 352, 0, 0, 0];
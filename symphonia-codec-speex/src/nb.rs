@@ -0,0 +1,429 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::errors::{decode_error, Result};
+
+use crate::bits::SpeexBits;
+use crate::dsp::*;
+use crate::modes::{LtpQuant, NB_SUBMODES, WB_SKIP_TABLE};
+use crate::quant::*;
+use crate::stereo::StereoState;
+
+/// The number of samples in a narrowband frame.
+pub const NB_FRAME_SIZE: usize = 160;
+/// The number of samples in a narrowband sub-frame.
+pub const NB_SUBFRAME_SIZE: usize = 40;
+/// The number of sub-frames in a narrowband frame.
+pub const NB_NB_SUBFRAMES: usize = 4;
+
+const NB_ORDER: usize = 10;
+const NB_PITCH_START: usize = 17;
+const NB_PITCH_END: usize = 144;
+/// The maximum pitch for which the enhancer also uses the excitation one pitch period ahead.
+const NB_ENH_MAX_PITCH: usize = 40;
+
+/// The offset of the current frame's excitation within the excitation buffer.
+const NB_EXC_OFFSET: usize = 2 * NB_PITCH_END + NB_SUBFRAME_SIZE + 6;
+/// The length of the excitation buffer.
+const NB_DEC_BUFFER: usize = NB_FRAME_SIZE + 2 * NB_PITCH_END + NB_SUBFRAME_SIZE + 12;
+
+/// The minimum spacing between line spectral pairs.
+const LSP_MARGIN: f32 = 0.002;
+
+/// Sub-frame gain corrections of sub-modes with a 3-bit gain correction.
+const EXC_GAIN_QUANT_SCAL3: [f32; 8] =
+    [0.061130, 0.163546, 0.310413, 0.428220, 0.555887, 0.719055, 0.938694, 1.326874];
+
+/// Sub-frame gain corrections of sub-modes with a 1-bit gain correction.
+const EXC_GAIN_QUANT_SCAL1: [f32; 2] = [0.70469, 1.05127];
+
+/// The in-band request identifier of a stereo message.
+const SPEEX_INBAND_STEREO: u32 = 9;
+
+/// Handles a Speex in-band request.
+fn read_inband_request(bits: &mut SpeexBits<'_>, stereo: &mut StereoState) -> Result<()> {
+    let id = bits.read(4)?;
+
+    if id == SPEEX_INBAND_STEREO {
+        return stereo.read(bits);
+    }
+
+    // All other requests are ignored, but they must be skipped.
+    let len = match id {
+        0..=1 => 1,
+        2..=7 => 4,
+        8..=9 => 8,
+        10..=11 => 16,
+        12..=13 => 32,
+        _ => 64,
+    };
+
+    bits.skip(len)
+}
+
+/// Skips a wideband layer after reading its wideband bit. Returns `false` if the layer exceeds
+/// the packet.
+fn skip_wideband_layer(bits: &mut SpeexBits<'_>) -> Result<bool> {
+    let len = WB_SKIP_TABLE[bits.read(3)? as usize];
+
+    if len == 0 {
+        return decode_error("speex: invalid wideband sub-mode");
+    }
+
+    // The length includes the wideband bit and the sub-mode that were already read.
+    Ok(bits.skip(len - 4).is_ok())
+}
+
+/// Narrowband CELP decoder.
+pub struct NbDecoder {
+    /// The excitation of the current frame, preceded by the excitation of the previous frames.
+    exc_buf: [f32; NB_DEC_BUFFER],
+    old_qlsp: [f32; NB_ORDER],
+    interp_qlpc: [f32; NB_ORDER],
+    mem_sp: [f32; NB_ORDER],
+    mem_hp: [f32; 2],
+    pi_gain: [f32; NB_NB_SUBFRAMES],
+    /// The innovation of the current frame, used by the high-band of the sub-band modes.
+    innov: [f32; NB_FRAME_SIZE],
+    first: bool,
+    seed: u32,
+    voc_m1: f32,
+    voc_m2: f32,
+    voc_mean: f32,
+    voc_offset: usize,
+    dtx: bool,
+    is_wideband: bool,
+}
+
+impl NbDecoder {
+    /// Instantiate a narrowband decoder. If `is_wideband` is true, the decoder decodes the
+    /// low-band of a sub-band mode.
+    pub fn new(is_wideband: bool) -> Self {
+        NbDecoder {
+            exc_buf: [0.0; NB_DEC_BUFFER],
+            old_qlsp: [0.0; NB_ORDER],
+            interp_qlpc: [0.0; NB_ORDER],
+            mem_sp: [0.0; NB_ORDER],
+            mem_hp: [0.0; 2],
+            pi_gain: [0.0; NB_NB_SUBFRAMES],
+            innov: [0.0; NB_FRAME_SIZE],
+            first: true,
+            seed: 1000,
+            voc_m1: 0.0,
+            voc_m2: 0.0,
+            voc_mean: 0.0,
+            voc_offset: 0,
+            dtx: false,
+            is_wideband,
+        }
+    }
+
+    /// Gets the response of the synthesis filter of each sub-frame at the Nyquist frequency.
+    pub fn pi_gain(&self) -> &[f32; NB_NB_SUBFRAMES] {
+        &self.pi_gain
+    }
+
+    /// Gets the RMS of the excitation of each sub-frame.
+    pub fn exc_rms(&self) -> [f32; NB_NB_SUBFRAMES] {
+        let exc = &self.exc_buf[NB_EXC_OFFSET..NB_EXC_OFFSET + NB_FRAME_SIZE];
+
+        let mut rms = [0.0; NB_NB_SUBFRAMES];
+
+        for (r, sub) in rms.iter_mut().zip(exc.chunks_exact(NB_SUBFRAME_SIZE)) {
+            *r = compute_rms(sub);
+        }
+
+        rms
+    }
+
+    /// Gets the innovation of the last decoded frame.
+    pub fn innov(&self) -> &[f32] {
+        &self.innov
+    }
+
+    /// Gets if discontinuous transmission is active.
+    pub fn dtx(&self) -> bool {
+        self.dtx
+    }
+
+    /// Decodes a frame into `out`. Returns `false` if the packet contains no more frames.
+    pub fn decode(
+        &mut self,
+        bits: &mut SpeexBits<'_>,
+        out: &mut [f32],
+        stereo: &mut StereoState,
+    ) -> Result<bool> {
+        // Search for the next narrowband frame while handling in-band requests and skipping any
+        // wideband layers.
+        let m = loop {
+            if bits.remaining() < 5 {
+                return Ok(false);
+            }
+
+            if bits.read_bool()? {
+                if !skip_wideband_layer(bits)? || bits.remaining() < 5 {
+                    return Ok(false);
+                }
+
+                if bits.read_bool()? {
+                    if !skip_wideband_layer(bits)? || bits.remaining() == 0 {
+                        return Ok(false);
+                    }
+
+                    if bits.read_bool()? {
+                        return decode_error("speex: more than two wideband layers");
+                    }
+                }
+            }
+
+            if bits.remaining() < 4 {
+                return Ok(false);
+            }
+
+            match bits.read(4)? {
+                // Terminator.
+                15 => return Ok(false),
+                // Speex in-band request.
+                14 => read_inband_request(bits, stereo)?,
+                // User in-band request.
+                13 => {
+                    let len = bits.read(4)? as usize;
+                    bits.skip(5 + 8 * len)?;
+                }
+                9..=12 => return decode_error("speex: invalid narrowband sub-mode"),
+                m => break m as usize,
+            }
+        };
+
+        // Shift the excitation buffer by one frame.
+        self.exc_buf.copy_within(NB_FRAME_SIZE.., 0);
+
+        let submode = match &NB_SUBMODES[m] {
+            Some(submode) => submode,
+            None => {
+                self.decode_null(out);
+                return Ok(true);
+            }
+        };
+
+        let mut qlsp = [0.0; NB_ORDER];
+
+        lsp_unquant(submode.lsp, &mut qlsp, bits)?;
+
+        if self.first {
+            self.old_qlsp = qlsp;
+        }
+
+        // Open-loop pitch for the low bit-rate sub-modes.
+        let mut ol_pitch = 0;
+
+        if submode.lbr_pitch.is_some() {
+            ol_pitch = NB_PITCH_START + bits.read(7)? as usize;
+        }
+
+        let mut ol_pitch_coef = 0.0;
+
+        if submode.forced_pitch_gain {
+            ol_pitch_coef = 0.066667 * bits.read(4)? as f32;
+        }
+
+        // Global excitation gain.
+        let ol_gain = (bits.read(5)? as f32 / 3.5).exp();
+
+        if m == 1 {
+            self.dtx = bits.read(4)? == 15;
+        }
+        else if m > 1 {
+            self.dtx = false;
+        }
+
+        let mut best_pitch = 40;
+        let mut best_pitch_gain = 0.0;
+
+        for sub in 0..NB_NB_SUBFRAMES {
+            let offset = NB_SUBFRAME_SIZE * sub;
+            let pos = NB_EXC_OFFSET + offset;
+
+            self.exc_buf[pos..pos + NB_SUBFRAME_SIZE].iter_mut().for_each(|s| *s = 0.0);
+
+            // Adaptive codebook contribution. The pitch is coded relative to the minimum pitch.
+            let pit_min = match submode.lbr_pitch {
+                Some(0) => ol_pitch,
+                Some(margin) => (ol_pitch + 1).saturating_sub(margin).max(NB_PITCH_START),
+                None => NB_PITCH_START,
+            };
+
+            let mut exc32 = [0.0; NB_SUBFRAME_SIZE];
+
+            let (pitch, pitch_gain) = match &submode.ltp {
+                LtpQuant::Forced => {
+                    forced_pitch_unquant(&mut self.exc_buf, pos, &mut exc32, pit_min, ol_pitch_coef)
+                }
+                LtpQuant::ThreeTap(params) => {
+                    pitch_unquant_3tap(bits, params, &self.exc_buf, pos, &mut exc32, pit_min)?
+                }
+            };
+
+            // Ensure that a crafted packet cannot cause the excitation to blow up.
+            sanitize(&mut exc32, 32000.0);
+
+            let tmp = gain_3tap_to_1tap(&pitch_gain);
+
+            let bp = best_pitch as isize;
+            let p = pitch as isize;
+
+            if (tmp > best_pitch_gain
+                && (2 * bp - p).abs() >= 3
+                && (3 * bp - p).abs() >= 4
+                && (4 * bp - p).abs() >= 5)
+                || (tmp > 0.6 * best_pitch_gain
+                    && ((bp - 2 * p).abs() < 3 || (bp - 3 * p).abs() < 4 || (bp - 4 * p).abs() < 5))
+                || (0.67 * tmp > best_pitch_gain
+                    && ((2 * bp - p).abs() < 3 || (3 * bp - p).abs() < 4 || (4 * bp - p).abs() < 5))
+            {
+                best_pitch = pitch;
+
+                if tmp > best_pitch_gain {
+                    best_pitch_gain = tmp;
+                }
+            }
+
+            // Sub-frame gain correction.
+            let ener = match submode.have_subframe_gain {
+                3 => EXC_GAIN_QUANT_SCAL3[bits.read(3)? as usize] * ol_gain,
+                1 => EXC_GAIN_QUANT_SCAL1[bits.read(1)? as usize] * ol_gain,
+                _ => ol_gain,
+            };
+
+            // Fixed codebook contribution.
+            let mut innov = [0.0; NB_SUBFRAME_SIZE];
+
+            innovation_unquant(bits, &submode.innovation, &mut innov, &mut self.seed)?;
+
+            innov.iter_mut().for_each(|s| *s *= ener);
+
+            if submode.double_codebook {
+                let mut innov2 = [0.0; NB_SUBFRAME_SIZE];
+
+                innovation_unquant(bits, &submode.innovation, &mut innov2, &mut self.seed)?;
+
+                for (s, &s2) in innov.iter_mut().zip(&innov2) {
+                    *s += 0.454545 * ener * s2;
+                }
+            }
+
+            let exc = &mut self.exc_buf[pos..pos + NB_SUBFRAME_SIZE];
+
+            for ((e, &a), &i) in exc.iter_mut().zip(&exc32).zip(&innov) {
+                *e = a + i;
+            }
+
+            self.innov[offset..offset + NB_SUBFRAME_SIZE].copy_from_slice(&innov);
+
+            // Vocoder mode.
+            if m == 1 {
+                let g = (1.5 * (ol_pitch_coef - 0.2)).clamp(0.0, 1.0);
+
+                exc.iter_mut().for_each(|s| *s = 0.0);
+
+                while self.voc_offset < NB_SUBFRAME_SIZE {
+                    exc[self.voc_offset] = (2.0 * ol_pitch as f32).sqrt() * g * ol_gain;
+                    self.voc_offset += ol_pitch;
+                }
+
+                self.voc_offset -= NB_SUBFRAME_SIZE;
+
+                for (e, &i) in exc.iter_mut().zip(&innov) {
+                    let exci = *e;
+
+                    *e = 0.7 * exci + 0.3 * self.voc_m1 + (1.0 - 0.85 * g) * i
+                        - 0.15 * g * self.voc_m2;
+
+                    self.voc_m1 = exci;
+                    self.voc_m2 = i;
+                    self.voc_mean = 0.8 * self.voc_mean + 0.2 * *e;
+
+                    *e -= self.voc_mean;
+                }
+            }
+        }
+
+        let out = &mut out[..NB_FRAME_SIZE];
+
+        // Enhance the periodicity of the excitation.
+        if submode.comb_gain > 0.0 {
+            let (out0, out1) = out.split_at_mut(2 * NB_SUBFRAME_SIZE);
+
+            let pos0 = NB_EXC_OFFSET - NB_SUBFRAME_SIZE;
+            let pos1 = NB_EXC_OFFSET + NB_SUBFRAME_SIZE;
+
+            multicomb(&self.exc_buf, pos0, out0, best_pitch, NB_ENH_MAX_PITCH, submode.comb_gain);
+            multicomb(&self.exc_buf, pos1, out1, best_pitch, NB_ENH_MAX_PITCH, submode.comb_gain);
+        }
+        else {
+            let start = NB_EXC_OFFSET - NB_SUBFRAME_SIZE;
+            out.copy_from_slice(&self.exc_buf[start..start + NB_FRAME_SIZE]);
+        }
+
+        // Synthesis.
+        let mut interp_qlsp = [0.0; NB_ORDER];
+        let mut ak = [0.0; NB_ORDER];
+
+        for (sub, sp) in out.chunks_exact_mut(NB_SUBFRAME_SIZE).enumerate() {
+            lsp_interpolate(
+                &self.old_qlsp,
+                &qlsp,
+                &mut interp_qlsp,
+                sub,
+                NB_NB_SUBFRAMES,
+                LSP_MARGIN,
+            );
+
+            lsp_to_lpc(&interp_qlsp, &mut ak);
+
+            // Response of the analysis filter at the Nyquist frequency.
+            self.pi_gain[sub] = 1.0 + ak.chunks_exact(2).map(|a| a[1] - a[0]).sum::<f32>();
+
+            // The synthesis filter is delayed by one sub-frame.
+            iir_mem(sp, &self.interp_qlpc, &mut self.mem_sp);
+
+            self.interp_qlpc = ak;
+        }
+
+        highpass(out, self.is_wideband, &mut self.mem_hp);
+
+        self.old_qlsp = qlsp;
+        self.first = false;
+
+        Ok(true)
+    }
+
+    /// Decodes a frame in the null sub-mode where only the background noise is synthesized.
+    fn decode_null(&mut self, out: &mut [f32]) {
+        let mut lpc = [0.0; NB_ORDER];
+
+        bw_lpc(0.93, &self.interp_qlpc, &mut lpc);
+
+        let exc = &mut self.exc_buf[NB_EXC_OFFSET..NB_EXC_OFFSET + NB_FRAME_SIZE];
+
+        let innov_gain = compute_rms(exc);
+
+        for s in exc.iter_mut() {
+            *s = rand(innov_gain, &mut self.seed);
+        }
+
+        self.first = true;
+
+        let out = &mut out[..NB_FRAME_SIZE];
+
+        out.copy_from_slice(exc);
+
+        iir_mem(out, &lpc, &mut self.mem_sp);
+
+        self.innov = [0.0; NB_FRAME_SIZE];
+    }
+}
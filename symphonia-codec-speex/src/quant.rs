@@ -0,0 +1,174 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::errors::Result;
+
+use crate::bits::SpeexBits;
+use crate::dsp::rand;
+use crate::modes::{InnovQuant, LspQuant, LtpParams, SplitCbParams};
+use crate::tables::*;
+
+/// Adds a scaled codebook entry to a set of line spectral pairs.
+fn add_lsp_cdbk(lsp: &mut [f32], cdbk: &[i8], id: u32, scale: f32) {
+    let order = lsp.len();
+    let entry = &cdbk[id as usize * order..(id as usize + 1) * order];
+
+    for (l, &c) in lsp.iter_mut().zip(entry) {
+        *l += scale * f32::from(c);
+    }
+}
+
+/// Reads and unquantizes a set of line spectral pairs.
+pub fn lsp_unquant(quant: LspQuant, lsp: &mut [f32], bits: &mut SpeexBits<'_>) -> Result<()> {
+    match quant {
+        LspQuant::Nb | LspQuant::Lbr => {
+            for (i, l) in lsp.iter_mut().enumerate() {
+                *l = 0.25 * i as f32 + 0.25;
+            }
+
+            add_lsp_cdbk(lsp, &LSP_CDBK_NB, bits.read(6)?, 0.0039062);
+
+            let (low, high) = lsp.split_at_mut(5);
+
+            if let LspQuant::Nb = quant {
+                add_lsp_cdbk(low, &LSP_CDBK_NB_LOW1, bits.read(6)?, 0.0019531);
+                add_lsp_cdbk(low, &LSP_CDBK_NB_LOW2, bits.read(6)?, 0.00097656);
+                add_lsp_cdbk(high, &LSP_CDBK_NB_HIGH1, bits.read(6)?, 0.0019531);
+                add_lsp_cdbk(high, &LSP_CDBK_NB_HIGH2, bits.read(6)?, 0.00097656);
+            }
+            else {
+                add_lsp_cdbk(low, &LSP_CDBK_NB_LOW1, bits.read(6)?, 0.0019531);
+                add_lsp_cdbk(high, &LSP_CDBK_NB_HIGH1, bits.read(6)?, 0.0019531);
+            }
+        }
+        LspQuant::High => {
+            for (i, l) in lsp.iter_mut().enumerate() {
+                *l = 0.3125 * i as f32 + 0.75;
+            }
+
+            add_lsp_cdbk(lsp, &LSP_CDBK_HIGH1, bits.read(6)?, 0.0039062);
+            add_lsp_cdbk(lsp, &LSP_CDBK_HIGH2, bits.read(6)?, 0.0019531);
+        }
+    }
+
+    Ok(())
+}
+
+/// Converts the gains of a 3-tap pitch predictor into an equivalent single-tap gain.
+pub fn gain_3tap_to_1tap(g: &[f32; 3]) -> f32 {
+    let side = |g: f32| if g > 0.0 { g } else { -0.5 * g };
+    g[1].abs() + side(g[0]) + side(g[2])
+}
+
+/// Reads a 3-tap pitch predictor and computes the adaptive codebook contribution of the
+/// sub-frame starting at `exc[pos]` into `exc_out`. Returns the pitch and the predictor gains.
+pub fn pitch_unquant_3tap(
+    bits: &mut SpeexBits<'_>,
+    params: &LtpParams,
+    exc: &[f32],
+    pos: usize,
+    exc_out: &mut [f32],
+    start: usize,
+) -> Result<(usize, [f32; 3])> {
+    let nsf = exc_out.len();
+
+    let pitch = bits.read(params.pitch_bits)? as usize + start;
+    let gain_index = bits.read(params.gain_bits)? as usize;
+
+    let entry = &params.gain_cdbk[4 * gain_index..4 * gain_index + 3];
+
+    let mut gain = [0.0; 3];
+
+    for (g, &c) in gain.iter_mut().zip(entry) {
+        *g = 0.015625 * f32::from(c) + 0.5;
+    }
+
+    exc_out.iter_mut().for_each(|s| *s = 0.0);
+
+    for i in 0..3 {
+        let pp = pitch + 1 - i;
+        let g = gain[2 - i];
+
+        let end1 = nsf.min(pp);
+
+        for (j, out) in exc_out[..end1].iter_mut().enumerate() {
+            *out += g * exc[pos + j - pp];
+        }
+
+        let end2 = nsf.min(pp + pitch);
+
+        for (j, out) in exc_out.iter_mut().enumerate().take(end2).skip(end1) {
+            *out += g * exc[pos + j - pp - pitch];
+        }
+    }
+
+    Ok((pitch, gain))
+}
+
+/// Computes the adaptive codebook contribution of the sub-frame starting at `exc[pos]` into
+/// `exc_out` using a forced pitch gain. The excitation is updated in-place.
+pub fn forced_pitch_unquant(
+    exc: &mut [f32],
+    pos: usize,
+    exc_out: &mut [f32],
+    start: usize,
+    pitch_coef: f32,
+) -> (usize, [f32; 3]) {
+    let pitch_coef = pitch_coef.min(0.99);
+
+    for (i, out) in exc_out.iter_mut().enumerate() {
+        *out = exc[pos + i - start] * pitch_coef;
+        exc[pos + i] = *out;
+    }
+
+    (start, [0.0, pitch_coef, 0.0])
+}
+
+/// Reads a split vector quantized innovation and adds it to `exc`.
+pub fn split_cb_unquant(
+    bits: &mut SpeexBits<'_>,
+    params: &SplitCbParams,
+    exc: &mut [f32],
+) -> Result<()> {
+    let mut signs = [false; 10];
+    let mut indices = [0; 10];
+
+    for (sign, ind) in signs.iter_mut().zip(indices.iter_mut()).take(params.n_subvect) {
+        *sign = params.have_sign && bits.read_bool()?;
+        *ind = bits.read(params.shape_bits)? as usize;
+    }
+
+    let size = params.subvect_size;
+
+    for (i, (&sign, &ind)) in signs.iter().zip(&indices).take(params.n_subvect).enumerate() {
+        let s = if sign { -0.03125 } else { 0.03125 };
+
+        let entry = &params.shape_cb[ind * size..(ind + 1) * size];
+
+        for (out, &c) in exc[i * size..(i + 1) * size].iter_mut().zip(entry) {
+            *out += s * f32::from(c);
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads an innovation and adds it to `exc`.
+pub fn innovation_unquant(
+    bits: &mut SpeexBits<'_>,
+    quant: &InnovQuant,
+    exc: &mut [f32],
+    seed: &mut u32,
+) -> Result<()> {
+    match quant {
+        InnovQuant::Noise => {
+            exc.iter_mut().for_each(|s| *s = rand(1.0, seed));
+            Ok(())
+        }
+        InnovQuant::SplitCb(params) => split_cb_unquant(bits, params, exc),
+    }
+}
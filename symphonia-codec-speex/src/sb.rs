@@ -0,0 +1,330 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::errors::{decode_error, Result};
+
+use crate::bits::SpeexBits;
+use crate::dsp::*;
+use crate::modes::{LspQuant, Mode, SbSubmode, UWB_SUBMODES, WB_SUBMODES};
+use crate::nb::{NbDecoder, NB_NB_SUBFRAMES};
+use crate::quant::{lsp_unquant, split_cb_unquant};
+use crate::stereo::StereoState;
+
+/// The LPC order of the high-band.
+const SB_ORDER: usize = 8;
+
+/// The number of sub-frames in a sub-band frame.
+const SB_NB_SUBFRAMES: usize = 4;
+
+/// The minimum spacing between line spectral pairs.
+const LSP_MARGIN: f32 = 0.05;
+
+/// The excitation value synthesized by the null sub-mode.
+const VERY_SMALL: f32 = 1e-15;
+
+/// The quantized high-band excitation gains.
+#[rustfmt::skip]
+const GC_QUANT_BOUND: [f32; 16] = [
+    0.97979, 1.28384, 1.68223, 2.20426, 2.88829, 3.78458, 4.95900, 6.49787,
+    8.51428, 11.15642, 14.61846, 19.15484, 25.09895, 32.88761, 43.09325, 56.46588,
+];
+
+/// A decoder for any Speex mode.
+pub enum BandDecoder {
+    Nb(Box<NbDecoder>),
+    Sb(Box<SbDecoder>),
+}
+
+impl BandDecoder {
+    /// Instantiate a decoder for the given mode.
+    pub fn new(mode: Mode) -> Self {
+        match mode {
+            Mode::Narrowband => BandDecoder::Nb(Box::new(NbDecoder::new(false))),
+            _ => BandDecoder::Sb(Box::new(SbDecoder::new(mode))),
+        }
+    }
+
+    /// Decodes a frame into `out`. Returns `false` if the packet contains no more frames.
+    pub fn decode(
+        &mut self,
+        bits: &mut SpeexBits<'_>,
+        out: &mut [f32],
+        stereo: &mut StereoState,
+    ) -> Result<bool> {
+        match self {
+            BandDecoder::Nb(nb) => nb.decode(bits, out, stereo),
+            BandDecoder::Sb(sb) => sb.decode(bits, out, stereo),
+        }
+    }
+
+    fn pi_gain(&self) -> &[f32; NB_NB_SUBFRAMES] {
+        match self {
+            BandDecoder::Nb(nb) => nb.pi_gain(),
+            BandDecoder::Sb(sb) => &sb.pi_gain,
+        }
+    }
+
+    fn exc_rms(&self) -> [f32; NB_NB_SUBFRAMES] {
+        match self {
+            BandDecoder::Nb(nb) => nb.exc_rms(),
+            BandDecoder::Sb(sb) => sb.exc_rms,
+        }
+    }
+
+    fn innov(&self) -> &[f32] {
+        match self {
+            BandDecoder::Nb(nb) => nb.innov(),
+            BandDecoder::Sb(sb) => &sb.innov,
+        }
+    }
+
+    fn dtx(&self) -> bool {
+        match self {
+            BandDecoder::Nb(nb) => nb.dtx(),
+            BandDecoder::Sb(sb) => sb.low.dtx(),
+        }
+    }
+}
+
+/// Sub-band CELP decoder.
+///
+/// The wideband and ultra-wideband modes split the spectrum in two halves with a QMF filter bank.
+/// The low-band is coded with the next lower mode, and the high-band with a simplified CELP
+/// coder that borrows the innovation of the low-band.
+pub struct SbDecoder {
+    low: BandDecoder,
+    submodes: &'static [Option<SbSubmode>; 8],
+    subframe_size: usize,
+    folding_gain: f32,
+    /// The low-band output.
+    low_out: Vec<f32>,
+    /// The high-band output.
+    high_out: Vec<f32>,
+    /// The excitation of the previous sub-frame.
+    exc_buf: Vec<f32>,
+    /// The innovation of the current frame interleaved with 0s, used by the next higher mode.
+    innov: Vec<f32>,
+    g0_mem: [f32; QMF_ORDER],
+    g1_mem: [f32; QMF_ORDER],
+    old_qlsp: [f32; SB_ORDER],
+    interp_qlpc: [f32; SB_ORDER],
+    mem_sp: [f32; SB_ORDER],
+    pi_gain: [f32; SB_NB_SUBFRAMES],
+    exc_rms: [f32; SB_NB_SUBFRAMES],
+    last_ener: f32,
+    first: bool,
+    seed: u32,
+}
+
+impl SbDecoder {
+    /// Instantiate a decoder for a sub-band mode.
+    pub fn new(mode: Mode) -> Self {
+        let (low, submodes, folding_gain) = match mode {
+            Mode::Wideband => (BandDecoder::Nb(Box::new(NbDecoder::new(true))), &WB_SUBMODES, 0.9),
+            Mode::UltraWideband => (BandDecoder::new(Mode::Wideband), &UWB_SUBMODES, 0.7),
+            Mode::Narrowband => unreachable!(),
+        };
+
+        let frame_size = mode.frame_size() / 2;
+        let subframe_size = frame_size / SB_NB_SUBFRAMES;
+
+        SbDecoder {
+            low,
+            submodes,
+            subframe_size,
+            folding_gain,
+            low_out: vec![0.0; frame_size],
+            high_out: vec![0.0; frame_size],
+            exc_buf: vec![0.0; subframe_size],
+            innov: vec![0.0; 2 * frame_size],
+            g0_mem: [0.0; QMF_ORDER],
+            g1_mem: [0.0; QMF_ORDER],
+            old_qlsp: [0.0; SB_ORDER],
+            interp_qlpc: [0.0; SB_ORDER],
+            mem_sp: [0.0; SB_ORDER],
+            pi_gain: [0.0; SB_NB_SUBFRAMES],
+            exc_rms: [0.0; SB_NB_SUBFRAMES],
+            last_ener: 0.0,
+            first: true,
+            seed: 1000,
+        }
+    }
+
+    /// Decodes a frame into `out`. Returns `false` if the packet contains no more frames.
+    pub fn decode(
+        &mut self,
+        bits: &mut SpeexBits<'_>,
+        out: &mut [f32],
+        stereo: &mut StereoState,
+    ) -> Result<bool> {
+        if !self.low.decode(bits, &mut self.low_out, stereo)? {
+            return Ok(false);
+        }
+
+        // A missing wideband bit indicates a narrowband frame, and the null sub-mode.
+        let submode_id = if bits.peek_bool().unwrap_or(false) {
+            bits.read_bool()?;
+            bits.read(3)? as usize
+        }
+        else {
+            0
+        };
+
+        let submode = match &self.submodes[submode_id] {
+            Some(submode) => submode,
+            None if submode_id != 0 => {
+                return decode_error("speex: invalid sub-band sub-mode");
+            }
+            None => {
+                self.decode_null();
+                qmf_synth(&self.low_out, &self.high_out, out, &mut self.g0_mem, &mut self.g1_mem);
+                return Ok(true);
+            }
+        };
+
+        let low_pi_gain = *self.low.pi_gain();
+        let low_exc_rms = self.low.exc_rms();
+
+        let mut qlsp = [0.0; SB_ORDER];
+
+        lsp_unquant(LspQuant::High, &mut qlsp, bits)?;
+
+        if self.first {
+            self.old_qlsp = qlsp;
+        }
+
+        let sfs = self.subframe_size;
+
+        let mut interp_qlsp = [0.0; SB_ORDER];
+        let mut ak = [0.0; SB_ORDER];
+        let mut exc_ener_sum = 0.0;
+
+        for sub in 0..SB_NB_SUBFRAMES {
+            let offset = sfs * sub;
+
+            let innov_save = &mut self.innov[2 * offset..2 * (offset + sfs)];
+
+            innov_save.iter_mut().for_each(|s| *s = 0.0);
+
+            lsp_interpolate(
+                &self.old_qlsp,
+                &qlsp,
+                &mut interp_qlsp,
+                sub,
+                SB_NB_SUBFRAMES,
+                LSP_MARGIN,
+            );
+
+            lsp_to_lpc(&interp_qlsp, &mut ak);
+
+            // Compare the responses of the low and high-band filters in the middle of the band.
+            let mut rh = 1.0;
+
+            self.pi_gain[sub] = 1.0;
+
+            for a in ak.chunks_exact(2) {
+                rh += a[1] - a[0];
+                self.pi_gain[sub] += a[0] + a[1];
+            }
+
+            let rl = low_pi_gain[sub];
+
+            let filter_ratio = (rl + 0.01) / (rh + 0.01);
+
+            let mut exc = [0.0; 80];
+            let exc = &mut exc[..sfs];
+
+            match &submode.innovation {
+                None => {
+                    // Fold the low-band innovation into the high-band.
+                    let g = (0.125 * (bits.read(5)? as f32 - 10.0)).exp() / filter_ratio;
+
+                    let low_innov = &self.low.innov()[offset..offset + sfs];
+
+                    for (i, (e, &l)) in exc.iter_mut().zip(low_innov).enumerate() {
+                        let s = self.folding_gain * l * g;
+                        *e = if i & 1 == 0 { s } else { -s };
+                    }
+                }
+                Some(params) => {
+                    let el = low_exc_rms[sub];
+
+                    let mut gc = 0.87360 * GC_QUANT_BOUND[bits.read(4)? as usize];
+
+                    if sfs == 80 {
+                        gc *= std::f32::consts::SQRT_2;
+                    }
+
+                    let scale = gc * el / filter_ratio;
+
+                    split_cb_unquant(bits, params, exc)?;
+
+                    exc.iter_mut().for_each(|s| *s *= scale);
+
+                    if submode.double_codebook {
+                        let mut innov2 = [0.0; 80];
+                        let innov2 = &mut innov2[..sfs];
+
+                        split_cb_unquant(bits, params, innov2)?;
+
+                        for (e, &s) in exc.iter_mut().zip(innov2.iter()) {
+                            *e += 0.4 * scale * s;
+                        }
+                    }
+                }
+            }
+
+            for (s, &e) in innov_save.iter_mut().step_by(2).zip(exc.iter()) {
+                *s = e;
+            }
+
+            // The synthesis filter is delayed by one sub-frame.
+            let sp = &mut self.high_out[offset..offset + sfs];
+
+            sp.copy_from_slice(&self.exc_buf);
+
+            iir_mem(sp, &self.interp_qlpc, &mut self.mem_sp);
+
+            self.exc_buf.copy_from_slice(exc);
+            self.interp_qlpc = ak;
+
+            let rms = compute_rms(&self.exc_buf);
+
+            self.exc_rms[sub] = rms;
+
+            exc_ener_sum += rms * rms / SB_NB_SUBFRAMES as f32;
+        }
+
+        self.last_ener = exc_ener_sum.sqrt();
+
+        qmf_synth(&self.low_out, &self.high_out, out, &mut self.g0_mem, &mut self.g1_mem);
+
+        self.old_qlsp = qlsp;
+        self.first = false;
+
+        Ok(true)
+    }
+
+    /// Synthesizes the high-band of a frame in the null sub-mode.
+    fn decode_null(&mut self) {
+        if self.low.dtx() {
+            // Comfort noise.
+            for s in self.high_out.iter_mut() {
+                *s = rand(self.last_ener, &mut self.seed);
+            }
+        }
+        else {
+            self.high_out.iter_mut().for_each(|s| *s = VERY_SMALL);
+        }
+
+        self.first = true;
+
+        iir_mem(&mut self.high_out, &self.interp_qlpc, &mut self.mem_sp);
+
+        self.innov.iter_mut().for_each(|s| *s = 0.0);
+    }
+}
@@ -0,0 +1,60 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::errors::Result;
+
+use crate::bits::SpeexBits;
+
+/// The quantized ratios of the energy of the mid channel to the sum of the energies of the left
+/// and right channels.
+const E_RATIO_QUANT: [f32; 4] = [0.25, 0.315, 0.397, 0.5];
+
+/// Intensity stereo state.
+///
+/// Speex encodes stereo audio as a mono signal, and periodically transmits the balance between
+/// the left and right channels as an in-band message.
+pub struct StereoState {
+    balance: f32,
+    e_ratio: f32,
+    smooth_left: f32,
+    smooth_right: f32,
+}
+
+impl Default for StereoState {
+    fn default() -> Self {
+        StereoState { balance: 1.0, e_ratio: 0.5, smooth_left: 1.0, smooth_right: 1.0 }
+    }
+}
+
+impl StereoState {
+    /// Reads a stereo in-band message.
+    pub fn read(&mut self, bits: &mut SpeexBits<'_>) -> Result<()> {
+        let sign = if bits.read_bool()? { -1.0 } else { 1.0 };
+        let dexp = bits.read(5)? as f32;
+
+        self.balance = (sign * 0.25 * dexp).exp();
+        self.e_ratio = E_RATIO_QUANT[bits.read(2)? as usize];
+
+        Ok(())
+    }
+
+    /// Generates the left and right channels from the mono channel.
+    pub fn apply(&mut self, mono: &[f32], left: &mut [f32], right: &mut [f32]) {
+        let e_right = 1.0 / (self.e_ratio * (1.0 + self.balance)).sqrt();
+        let e_left = self.balance.sqrt() * e_right;
+
+        // The reference decoder interleaves the output in-place, and therefore processes the
+        // samples in reverse. Since the gains are smoothed, the same order must be used.
+        for i in (0..mono.len()).rev() {
+            self.smooth_left = 0.98 * self.smooth_left + 0.02 * e_left;
+            self.smooth_right = 0.98 * self.smooth_right + 0.02 * e_right;
+
+            left[i] = self.smooth_left * mono[i];
+            right[i] = self.smooth_right * mono[i];
+        }
+    }
+}
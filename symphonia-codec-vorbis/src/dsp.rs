@@ -6,6 +6,7 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 use symphonia_core::dsp::mdct::Imdct;
+use symphonia_core::dsp::simd;
 
 use super::window::Windows;
 
@@ -153,9 +154,5 @@ fn overlap_add(out: &mut [f32], left: &[f32], right: &[f32], win: &[f32]) {
     assert!(left.len() == win.len());
     assert!(left.len() == out.len());
 
-    let iter = left.iter().zip(right).zip(win.iter().rev()).zip(win).zip(out);
-
-    for ((((&s0, &s1), &w0), &w1), out) in iter {
-        *out = s0 * w0 + s1 * w1;
-    }
+    simd::overlap_add(out, left, right, win);
 }
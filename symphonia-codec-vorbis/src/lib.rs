@@ -16,12 +16,13 @@
 // Disable to better express the specification.
 #![allow(clippy::collapsible_else_if)]
 
-use symphonia_core::audio::{AsAudioBufferRef, AudioBuffer, AudioBufferRef};
+use symphonia_core::audio::{AsAudioBufferRef, AudioBuffer, AudioBufferMut, AudioBufferRef};
 use symphonia_core::audio::{Signal, SignalSpec};
 use symphonia_core::codecs::{CodecDescriptor, CodecParameters, CODEC_TYPE_VORBIS};
 use symphonia_core::codecs::{Decoder, DecoderOptions, FinalizeResult};
+use symphonia_core::conv::dither::Dynamic;
 use symphonia_core::dsp::mdct::Imdct;
-use symphonia_core::errors::{decode_error, unsupported_error, Result};
+use symphonia_core::errors::{decode_error, limit_error, unsupported_error, Result};
 use symphonia_core::formats::Packet;
 use symphonia_core::io::{BitReaderRtl, BufReader, FiniteBitStream, ReadBitsRtl, ReadBytes};
 use symphonia_core::support_codec;
@@ -67,7 +68,8 @@ pub struct VorbisDecoder {
 }
 
 impl VorbisDecoder {
-    fn decode_inner(&mut self, packet: &Packet) -> Result<()> {
+    /// Decodes a packet into `dst`, or the internal audio buffer if `dst` is `None`.
+    fn decode_inner(&mut self, packet: &Packet, dst: Option<&mut AudioBuffer<f32>>) -> Result<()> {
         let mut bs = BitReaderRtl::new(packet.buf());
 
         // Section 4.3.1 - Packet Type, Mode, and Window Decode
@@ -226,7 +228,12 @@ impl VorbisDecoder {
         }
 
         // Combined Section 4.3.7 and 4.3.8 - Inverse MDCT and Overlap-add (Synthesis)
-        self.buf.clear();
+        let buf = match dst {
+            Some(dst) => dst,
+            None => &mut self.buf,
+        };
+
+        buf.clear();
 
         // Calculate the output length and reserve space in the output buffer. If there was no
         // previous packet, then return an empty audio buffer since the decoder will need another
@@ -241,7 +248,7 @@ impl VorbisDecoder {
             };
 
             let render_len = (prev_block_n + n) / 4;
-            self.buf.render_reserved(Some(render_len));
+            buf.render_reserved(Some(render_len));
         }
 
         // Render all the audio channels.
@@ -251,12 +258,12 @@ impl VorbisDecoder {
                 &self.dsp.lapping_state,
                 &self.dsp.windows,
                 imdct,
-                self.buf.chan_mut(map_vorbis_channel(self.ident.n_channels, i)),
+                buf.chan_mut(map_vorbis_channel(self.ident.n_channels, i)),
             );
         }
 
         // Trim
-        self.buf.trim(packet.trim_start() as usize, packet.trim_end() as usize);
+        buf.trim(packet.trim_start() as usize, packet.trim_end() as usize);
 
         // Save the new lapping state.
         self.dsp.lapping_state = Some(LappingState { prev_block_flag: mode.block_flag });
@@ -338,7 +345,7 @@ impl Decoder for VorbisDecoder {
     }
 
     fn decode(&mut self, packet: &Packet) -> Result<AudioBufferRef<'_>> {
-        if let Err(e) = self.decode_inner(packet) {
+        if let Err(e) = self.decode_inner(packet, None) {
             self.buf.clear();
             Err(e)
         }
@@ -347,6 +354,42 @@ impl Decoder for VorbisDecoder {
         }
     }
 
+    fn decode_into(
+        &mut self,
+        packet: &Packet,
+        dst: AudioBufferMut<'_>,
+        dither: &mut Dynamic,
+    ) -> Result<usize> {
+        // Check the caller's buffer before the packet is consumed.
+        if dst.spec() != self.buf.spec() {
+            return unsupported_error("vorbis: destination signal specification does not match");
+        }
+
+        if dst.capacity() < self.buf.capacity() {
+            return limit_error("vorbis: destination buffer capacity is too small");
+        }
+
+        match dst {
+            // The audio is synthesized directly into the caller's buffer if it uses the native
+            // sample format of the decoder.
+            AudioBufferMut::F32(dst) => {
+                self.buf.clear();
+
+                if let Err(e) = self.decode_inner(packet, Some(dst)) {
+                    dst.clear();
+                    return Err(e);
+                }
+
+                Ok(dst.frames())
+            }
+            dst => {
+                let decoded = self.decode(packet)?;
+                decoded.copy_to(dst, dither)?;
+                Ok(decoded.frames())
+            }
+        }
+    }
+
     fn finalize(&mut self) -> FinalizeResult {
         Default::default()
     }
@@ -410,11 +453,6 @@ fn read_ident_header<B: ReadBytes>(reader: &mut B) -> Result<IdentHeader> {
         return decode_error("vorbis: number of channels cannot be 0");
     }
 
-    // This is a Symphonia limitation.
-    if n_channels > 32 {
-        return unsupported_error("vorbis: only a maximum of 32 channels are supported");
-    }
-
     let sample_rate = reader.read_u32()?;
 
     if sample_rate == 0 {
@@ -12,84 +12,570 @@ use std::borrow::Cow;
 use std::fmt;
 use std::marker::PhantomData;
 use std::mem;
+use std::ops::Range;
 use std::vec::Vec;
 
 use arrayvec::ArrayVec;
-use bitflags::bitflags;
 
+use crate::conv::dither::{DitherType, Dynamic};
 use crate::conv::{ConvertibleSample, IntoSample};
-use crate::errors::Result;
-use crate::sample::{i24, u24, Sample};
+use crate::dsp::simd;
+use crate::errors::{limit_error, unsupported_error, Result};
+use crate::sample::{i24, u24, Sample, SampleFormat};
 use crate::units::Duration;
 
 /// The maximum number of audio plane slices `AudioPlanes` or `AudioPlanesMut` will store on the
 /// stack before storing the slices on the heap.
 const AUDIO_PLANES_STORAGE_STACK_LIMIT: usize = 8;
 
-bitflags! {
-    /// A bitmask representing the audio channels in an audio buffer or signal.
+/// A set of audio channels in an audio buffer or signal.
+///
+/// Channels may be positioned, ambisonic, or discrete. Positioned channels are assigned to a
+/// loudspeaker position, and are represented by a bitmask. The first 18 positions are guaranteed
+/// to be identical to those specified by Microsoft's WAVEFORMATEXTENSIBLE structure. Positions
+/// after 18 are defined by Symphonia and no order is guaranteed.
+///
+/// Ambisonic channels are the components of a full-sphere ambisonic sound field of a given order,
+/// using Ambisonic Channel Number (ACN) ordering and SN3D normalization (AmbiX). Discrete channels
+/// have no defined position or meaning.
+///
+/// Within an audio frame, ambisonic components are stored first, in ACN order, followed by the
+/// positioned channels, in bit order, followed by the discrete channels.
+///
+/// For compatibility, the positioned channels have the same interface as a `bitflags` bitmask.
+#[derive(Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Channels {
+    /// The positioned channel bitmask.
+    bits: u32,
+    /// The ambisonic order plus 1, or 0 if there are no ambisonic channels.
+    ambisonic: u8,
+    /// The number of discrete channels.
+    discrete: u16,
+}
+
+/// The names of the positioned channels, in bit order.
+const CHANNEL_NAMES: [&str; 26] = [
+    "FRONT_LEFT",
+    "FRONT_RIGHT",
+    "FRONT_CENTRE",
+    "LFE1",
+    "REAR_LEFT",
+    "REAR_RIGHT",
+    "FRONT_LEFT_CENTRE",
+    "FRONT_RIGHT_CENTRE",
+    "REAR_CENTRE",
+    "SIDE_LEFT",
+    "SIDE_RIGHT",
+    "TOP_CENTRE",
+    "TOP_FRONT_LEFT",
+    "TOP_FRONT_CENTRE",
+    "TOP_FRONT_RIGHT",
+    "TOP_REAR_LEFT",
+    "TOP_REAR_CENTRE",
+    "TOP_REAR_RIGHT",
+    "REAR_LEFT_CENTRE",
+    "REAR_RIGHT_CENTRE",
+    "FRONT_LEFT_WIDE",
+    "FRONT_RIGHT_WIDE",
+    "FRONT_LEFT_HIGH",
+    "FRONT_CENTRE_HIGH",
+    "FRONT_RIGHT_HIGH",
+    "LFE2",
+];
+
+/// The positioned channels defined by WAVEFORMATEXTENSIBLE.
+const WAVE_CHANNEL_MASK: u32 = 0x0003_ffff;
+
+/// The `kAudioChannelLayoutTag_UseChannelBitmap` layout tag code.
+const CAF_USE_CHANNEL_BITMAP: u32 = 1;
+/// The `kAudioChannelLayoutTag_DiscreteInOrder` layout tag code.
+const CAF_DISCRETE_IN_ORDER: u32 = 147;
+/// The `kAudioChannelLayoutTag_HOA_ACN_SN3D` layout tag code.
+const CAF_HOA_ACN_SN3D: u32 = 190;
+
+/// Core Audio channel layout tags, and their positioned channel bitmask, for layouts whose channel
+/// order is the bit order. The first tag for a bitmask is preferred.
+const CAF_LAYOUT_TAGS: [(u32, u32); 15] = [
+    // Mono: C (Symphonia uses the front-left channel for mono).
+    (0x0064_0001, 0x001),
+    // Stereo: L R
+    (0x0065_0002, 0x003),
+    // MPEG_3_0_A: L R C
+    (0x0071_0003, 0x007),
+    // Quadraphonic: L R Ls Rs
+    (0x006c_0004, 0x033),
+    // MPEG_4_0_A: L R C Cs
+    (0x0073_0004, 0x107),
+    // MPEG_5_0_A: L R C Ls Rs
+    (0x0075_0005, 0x037),
+    // MPEG_5_1_A: L R C LFE Ls Rs
+    (0x0079_0006, 0x03f),
+    // MPEG_6_1_A: L R C LFE Ls Rs Cs
+    (0x007d_0007, 0x13f),
+    // MPEG_7_1_A: L R C LFE Ls Rs Lc Rc
+    (0x007e_0008, 0x0ff),
+    // ITU_2_1: L R Cs
+    (0x0083_0003, 0x103),
+    // ITU_2_2: L R Ls Rs
+    (0x0084_0004, 0x033),
+    // DVD_4: L R LFE
+    (0x0085_0003, 0x00b),
+    // DVD_5: L R LFE Cs
+    (0x0086_0004, 0x10b),
+    // DVD_6: L R LFE Ls Rs
+    (0x0087_0005, 0x03b),
+    // DVD_10: L R C LFE
+    (0x0088_0004, 0x00f),
+];
+
+impl Channels {
+    /// Front-left (left) or the Mono channel.
+    pub const FRONT_LEFT: Channels = Channels::from_bits_truncate(0x0000_0001);
+    /// Front-right (right) channel.
+    pub const FRONT_RIGHT: Channels = Channels::from_bits_truncate(0x0000_0002);
+    /// Front-centre (centre) channel.
+    pub const FRONT_CENTRE: Channels = Channels::from_bits_truncate(0x0000_0004);
+    /// Low frequency channel 1.
+    pub const LFE1: Channels = Channels::from_bits_truncate(0x0000_0008);
+    /// Rear-left (surround rear left) channel.
+    pub const REAR_LEFT: Channels = Channels::from_bits_truncate(0x0000_0010);
+    /// Rear-right (surround rear right) channel.
+    pub const REAR_RIGHT: Channels = Channels::from_bits_truncate(0x0000_0020);
+    /// Front left-of-centre (left center) channel.
+    pub const FRONT_LEFT_CENTRE: Channels = Channels::from_bits_truncate(0x0000_0040);
+    /// Front right-of-centre (right center) channel.
+    pub const FRONT_RIGHT_CENTRE: Channels = Channels::from_bits_truncate(0x0000_0080);
+    /// Rear-centre (surround rear centre) channel.
+    pub const REAR_CENTRE: Channels = Channels::from_bits_truncate(0x0000_0100);
+    /// Side left (surround left) channel.
+    pub const SIDE_LEFT: Channels = Channels::from_bits_truncate(0x0000_0200);
+    /// Side right (surround right) channel.
+    pub const SIDE_RIGHT: Channels = Channels::from_bits_truncate(0x0000_0400);
+    /// Top centre channel.
+    pub const TOP_CENTRE: Channels = Channels::from_bits_truncate(0x0000_0800);
+    /// Top front-left channel.
+    pub const TOP_FRONT_LEFT: Channels = Channels::from_bits_truncate(0x0000_1000);
+    /// Top centre channel.
+    pub const TOP_FRONT_CENTRE: Channels = Channels::from_bits_truncate(0x0000_2000);
+    /// Top front-right channel.
+    pub const TOP_FRONT_RIGHT: Channels = Channels::from_bits_truncate(0x0000_4000);
+    /// Top rear-left channel.
+    pub const TOP_REAR_LEFT: Channels = Channels::from_bits_truncate(0x0000_8000);
+    /// Top rear-centre channel.
+    pub const TOP_REAR_CENTRE: Channels = Channels::from_bits_truncate(0x0001_0000);
+    /// Top rear-right channel.
+    pub const TOP_REAR_RIGHT: Channels = Channels::from_bits_truncate(0x0002_0000);
+    /// Rear left-of-centre channel.
+    pub const REAR_LEFT_CENTRE: Channels = Channels::from_bits_truncate(0x0004_0000);
+    /// Rear right-of-centre channel.
+    pub const REAR_RIGHT_CENTRE: Channels = Channels::from_bits_truncate(0x0008_0000);
+    /// Front left-wide channel.
+    pub const FRONT_LEFT_WIDE: Channels = Channels::from_bits_truncate(0x0010_0000);
+    /// Front right-wide channel.
+    pub const FRONT_RIGHT_WIDE: Channels = Channels::from_bits_truncate(0x0020_0000);
+    /// Front left-high channel.
+    pub const FRONT_LEFT_HIGH: Channels = Channels::from_bits_truncate(0x0040_0000);
+    /// Front centre-high channel.
+    pub const FRONT_CENTRE_HIGH: Channels = Channels::from_bits_truncate(0x0080_0000);
+    /// Front right-high channel.
+    pub const FRONT_RIGHT_HIGH: Channels = Channels::from_bits_truncate(0x0100_0000);
+    /// Low frequency channel 2.
+    pub const LFE2: Channels = Channels::from_bits_truncate(0x0200_0000);
+
+    /// Gets an empty set of channels.
+    pub const fn empty() -> Channels {
+        Channels { bits: 0, ambisonic: 0, discrete: 0 }
+    }
+
+    /// Gets the set of all positioned channels.
+    pub const fn all() -> Channels {
+        Channels::from_bits_truncate(!0)
+    }
+
+    /// Gets the positioned channel bitmask.
+    pub const fn bits(&self) -> u32 {
+        self.bits
+    }
+
+    /// Instantiates a set of positioned channels from a bitmask. Returns `None` if the bitmask
+    /// contains undefined positions.
+    pub fn from_bits(bits: u32) -> Option<Channels> {
+        if bits & !Channels::all().bits == 0 {
+            Some(Channels::from_bits_truncate(bits))
+        }
+        else {
+            None
+        }
+    }
+
+    /// Instantiates a set of positioned channels from a bitmask, ignoring undefined positions.
+    pub const fn from_bits_truncate(bits: u32) -> Channels {
+        Channels { bits: bits & ((1 << CHANNEL_NAMES.len()) - 1), ambisonic: 0, discrete: 0 }
+    }
+
+    /// Instantiates a set of positioned channels from a bitmask, retaining undefined positions.
+    ///
+    /// Unlike `bitflags`, this function is safe since undefined positions cannot cause undefined
+    /// behaviour. However, undefined positions are still counted as channels.
+    pub const fn from_bits_unchecked(bits: u32) -> Channels {
+        Channels { bits, ambisonic: 0, discrete: 0 }
+    }
+
+    /// Instantiates a set of `count` discrete channels.
+    pub const fn discrete(count: u16) -> Channels {
+        Channels { bits: 0, ambisonic: 0, discrete: count }
+    }
+
+    /// Instantiates the set of `(order + 1)^2` ambisonic components of an ambisonic sound field of
+    /// the given order. Returns `None` if the order is greater than the maximum order of 254.
+    pub fn ambisonic(order: u8) -> Option<Channels> {
+        let ambisonic = order.checked_add(1)?;
+        Some(Channels { bits: 0, ambisonic, discrete: 0 })
+    }
+
+    /// Instantiates `Channels` from a WAVEFORMATEXTENSIBLE channel mask and channel count.
+    ///
+    /// As per the specification, the channels assigned by the mask are the first channels in a
+    /// frame, and any further channels are unassigned. Unassigned channels are discrete. If the
+    /// mask assigns more channels than the channel count, the excess assignments are ignored.
+    /// Reserved bits of the mask are ignored.
+    pub fn from_wave_channel_mask(mask: u32, n_channels: u16) -> Channels {
+        let mut bits = mask & WAVE_CHANNEL_MASK;
+
+        // Drop the highest assigned positions in excess of the channel count.
+        while bits.count_ones() > u32::from(n_channels) {
+            bits &= !(1 << (31 - bits.leading_zeros()));
+        }
+
+        let discrete = n_channels - bits.count_ones() as u16;
+
+        Channels { bits, ambisonic: 0, discrete }
+    }
+
+    /// Gets the WAVEFORMATEXTENSIBLE channel mask of the positioned channels.
+    ///
+    /// Only the positions defined by WAVEFORMATEXTENSIBLE are included in the mask. Any other
+    /// channel is unassigned. Since the assigned channels of a WAVEFORMATEXTENSIBLE stream must be
+    /// stored before the unassigned channels, the mask is only a complete description of the
+    /// channels if there are no ambisonic components, and no positions outside of the mask.
+    pub fn to_wave_channel_mask(&self) -> u32 {
+        self.bits & WAVE_CHANNEL_MASK
+    }
+
+    /// Instantiates `Channels` from a Core Audio Format (CAF) channel layout tag. If the tag is
+    /// `kAudioChannelLayoutTag_UseChannelBitmap`, the channels are described by `bitmap`.
+    ///
+    /// Only layouts whose channel order is the same as the order of channels in an audio frame are
+    /// supported. Returns `None` for other layouts, and for layouts described by channel
+    /// descriptions.
+    pub fn from_caf_layout_tag(tag: u32, bitmap: u32) -> Option<Channels> {
+        let n_channels = (tag & 0xffff) as u16;
+
+        match tag >> 16 {
+            CAF_USE_CHANNEL_BITMAP => {
+                // The Core Audio channel bitmap is identical to the WAVEFORMATEXTENSIBLE mask.
+                Some(Channels::from_bits_truncate(bitmap & WAVE_CHANNEL_MASK))
+            }
+            CAF_DISCRETE_IN_ORDER => Some(Channels::discrete(n_channels)),
+            CAF_HOA_ACN_SN3D => {
+                let order = (f64::from(n_channels).sqrt() as u16).checked_sub(1)?;
+
+                if (order + 1) * (order + 1) == n_channels && order < 255 {
+                    Channels::ambisonic(order as u8)
+                }
+                else {
+                    None
+                }
+            }
+            _ => CAF_LAYOUT_TAGS
+                .iter()
+                .find(|&&(caf_tag, _)| caf_tag == tag)
+                .map(|&(_, bits)| Channels::from_bits_truncate(bits)),
+        }
+    }
+
+    /// Gets the Core Audio Format (CAF) channel layout tag, and channel bitmap, that describe the
+    /// channels. The channel bitmap is 0 unless the tag is `kAudioChannelLayoutTag_UseChannelBitmap`.
     ///
-    /// The first 18 defined channels are guaranteed to be identical to those specified by
-    /// Microsoft's WAVEFORMATEXTENSIBLE structure. Channels after 18 are defined by Symphonia and
-    /// no order is guaranteed.
-    #[derive(Default)]
-    pub struct Channels: u32 {
-        /// Front-left (left) or the Mono channel.
-        const FRONT_LEFT         = 0x0000_0001;
-        /// Front-right (right) channel.
-        const FRONT_RIGHT        = 0x0000_0002;
-        /// Front-centre (centre) channel.
-        const FRONT_CENTRE       = 0x0000_0004;
-        /// Low frequency channel 1.
-        const LFE1               = 0x0000_0008;
-        /// Rear-left (surround rear left) channel.
-        const REAR_LEFT          = 0x0000_0010;
-        /// Rear-right (surround rear right) channel.
-        const REAR_RIGHT         = 0x0000_0020;
-        /// Front left-of-centre (left center) channel.
-        const FRONT_LEFT_CENTRE  = 0x0000_0040;
-        /// Front right-of-centre (right center) channel.
-        const FRONT_RIGHT_CENTRE = 0x0000_0080;
-        /// Rear-centre (surround rear centre) channel.
-        const REAR_CENTRE        = 0x0000_0100;
-        /// Side left (surround left) channel.
-        const SIDE_LEFT          = 0x0000_0200;
-        /// Side right (surround right) channel.
-        const SIDE_RIGHT         = 0x0000_0400;
-        /// Top centre channel.
-        const TOP_CENTRE         = 0x0000_0800;
-        /// Top front-left channel.
-        const TOP_FRONT_LEFT     = 0x0000_1000;
-        /// Top centre channel.
-        const TOP_FRONT_CENTRE   = 0x0000_2000;
-        /// Top front-right channel.
-        const TOP_FRONT_RIGHT    = 0x0000_4000;
-        /// Top rear-left channel.
-        const TOP_REAR_LEFT      = 0x0000_8000;
-        /// Top rear-centre channel.
-        const TOP_REAR_CENTRE    = 0x0001_0000;
-        /// Top rear-right channel.
-        const TOP_REAR_RIGHT     = 0x0002_0000;
-        /// Rear left-of-centre channel.
-        const REAR_LEFT_CENTRE   = 0x0004_0000;
-        /// Rear right-of-centre channel.
-        const REAR_RIGHT_CENTRE  = 0x0008_0000;
-        /// Front left-wide channel.
-        const FRONT_LEFT_WIDE    = 0x0010_0000;
-        /// Front right-wide channel.
-        const FRONT_RIGHT_WIDE   = 0x0020_0000;
-        /// Front left-high channel.
-        const FRONT_LEFT_HIGH    = 0x0040_0000;
-        /// Front centre-high channel.
-        const FRONT_CENTRE_HIGH  = 0x0080_0000;
-        /// Front right-high channel.
-        const FRONT_RIGHT_HIGH   = 0x0100_0000;
-        /// Low frequency channel 2.
-        const LFE2               = 0x0200_0000;
-    }
-}
-
-/// An iterator over individual channels within a `Channels` bitmask.
+    /// Returns `None` if the channels are a mix of positioned, ambisonic, and discrete channels, or
+    /// contain positions that are not defined by Core Audio.
+    pub fn to_caf_layout_tag(&self) -> Option<(u32, u32)> {
+        if self.count() > 0xffff {
+            return None;
+        }
+
+        let n_channels = self.count() as u32;
+
+        if self.ambisonic == 0 && self.discrete == 0 {
+            if let Some(&(tag, _)) = CAF_LAYOUT_TAGS.iter().find(|&&(_, bits)| bits == self.bits) {
+                Some((tag, 0))
+            }
+            else if self.bits & !WAVE_CHANNEL_MASK == 0 {
+                Some((CAF_USE_CHANNEL_BITMAP << 16, self.bits))
+            }
+            else {
+                None
+            }
+        }
+        else if self.bits == 0 && self.discrete == 0 {
+            Some((CAF_HOA_ACN_SN3D << 16 | n_channels, 0))
+        }
+        else if self.bits == 0 && self.ambisonic == 0 {
+            Some((CAF_DISCRETE_IN_ORDER << 16 | n_channels, 0))
+        }
+        else {
+            None
+        }
+    }
+
+    /// Gets the positioned channels only.
+    pub fn positioned(&self) -> Channels {
+        Channels::from_bits_unchecked(self.bits)
+    }
+
+    /// Gets the ambisonic order, or `None` if there are no ambisonic channels.
+    pub fn ambisonic_order(&self) -> Option<u8> {
+        self.ambisonic.checked_sub(1)
+    }
+
+    /// Gets the number of discrete channels.
+    pub fn discrete_count(&self) -> usize {
+        usize::from(self.discrete)
+    }
+
+    /// Gets the number of ambisonic components.
+    fn ambisonic_count(&self) -> usize {
+        usize::from(self.ambisonic) * usize::from(self.ambisonic)
+    }
+
+    /// Returns `true` if there are no channels.
+    pub const fn is_empty(&self) -> bool {
+        self.bits == 0 && self.ambisonic == 0 && self.discrete == 0
+    }
+
+    /// Returns `true` if all positioned channels are present.
+    pub const fn is_all(&self) -> bool {
+        self.bits & Channels::all().bits == Channels::all().bits
+    }
+
+    /// Returns `true` if all channels in `other` are contained in this set of channels.
+    pub const fn contains(&self, other: Channels) -> bool {
+        (self.bits & other.bits) == other.bits
+            && self.ambisonic >= other.ambisonic
+            && self.discrete >= other.discrete
+    }
+
+    /// Returns `true` if there are channels in common with `other`.
+    pub const fn intersects(&self, other: Channels) -> bool {
+        (self.bits & other.bits) != 0
+            || (self.ambisonic > 0 && other.ambisonic > 0)
+            || (self.discrete > 0 && other.discrete > 0)
+    }
+
+    /// Inserts the channels in `other`.
+    pub fn insert(&mut self, other: Channels) {
+        *self |= other;
+    }
+
+    /// Removes the channels in `other`.
+    pub fn remove(&mut self, other: Channels) {
+        *self -= other;
+    }
+
+    /// Inserts the channels in `other` that are not present, and removes those that are.
+    pub fn toggle(&mut self, other: Channels) {
+        *self ^= other;
+    }
+
+    /// Inserts the channels in `other` if `value` is `true`, otherwise, removes them.
+    pub fn set(&mut self, other: Channels, value: bool) {
+        if value {
+            self.insert(other);
+        }
+        else {
+            self.remove(other);
+        }
+    }
+
+    /// Gets the union of this set of channels and `other`. Equivalent to `|`.
+    #[must_use]
+    pub fn union(self, other: Channels) -> Channels {
+        self | other
+    }
+
+    /// Gets the intersection of this set of channels and `other`. Equivalent to `&`.
+    #[must_use]
+    pub fn intersection(self, other: Channels) -> Channels {
+        self & other
+    }
+
+    /// Gets the channels in this set of channels that are not in `other`. Equivalent to `-`.
+    #[must_use]
+    pub fn difference(self, other: Channels) -> Channels {
+        self - other
+    }
+
+    /// Gets the channels in either this set of channels or `other`, but not both. Equivalent to
+    /// `^`.
+    #[must_use]
+    pub fn symmetric_difference(self, other: Channels) -> Channels {
+        self ^ other
+    }
+
+    /// Gets the positioned channels that are not in this set of channels. Equivalent to `!`.
+    #[must_use]
+    pub fn complement(self) -> Channels {
+        !self
+    }
+
+    /// Gets the number of channels.
+    pub fn count(self) -> usize {
+        self.bits.count_ones() as usize + self.ambisonic_count() + self.discrete_count()
+    }
+
+    /// Gets an iterator over individual positioned channels.
+    pub fn iter(&self) -> ChannelsIter {
+        ChannelsIter { channels: self.positioned() }
+    }
+
+    /// Gets an iterator over the labels of all channels, in the order the channels are stored in
+    /// an audio frame.
+    pub fn labels(&self) -> ChannelLabels {
+        ChannelLabels {
+            positioned: self.iter(),
+            ambisonic: 0..self.ambisonic_count() as u16,
+            discrete: 0..self.discrete,
+        }
+    }
+
+    /// Gets the index of a channel within an audio frame, or `None` if the channel is not present.
+    pub fn index_of(&self, label: ChannelLabel) -> Option<usize> {
+        let n_ambisonic = self.ambisonic_count();
+
+        match label {
+            ChannelLabel::Ambisonic(acn) if usize::from(acn) < n_ambisonic => {
+                Some(usize::from(acn))
+            }
+            ChannelLabel::Positioned(channel) if channel.count() == 1 && self.contains(channel) => {
+                Some(n_ambisonic + (self.bits & (channel.bits - 1)).count_ones() as usize)
+            }
+            ChannelLabel::Discrete(index) if index < self.discrete => {
+                Some(n_ambisonic + self.bits.count_ones() as usize + usize::from(index))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl std::ops::BitOr for Channels {
+    type Output = Channels;
+
+    /// Gets the union of two sets of channels. The ambisonic and discrete channels of the union
+    /// are the larger of the respective ambisonic and discrete channels of the operands.
+    fn bitor(self, rhs: Channels) -> Channels {
+        Channels {
+            bits: self.bits | rhs.bits,
+            ambisonic: self.ambisonic.max(rhs.ambisonic),
+            discrete: self.discrete.max(rhs.discrete),
+        }
+    }
+}
+
+impl std::ops::BitOrAssign for Channels {
+    fn bitor_assign(&mut self, rhs: Channels) {
+        *self = *self | rhs;
+    }
+}
+
+impl std::ops::BitAnd for Channels {
+    type Output = Channels;
+
+    /// Gets the intersection of two sets of channels. The ambisonic and discrete channels of the
+    /// intersection are the smaller of the respective ambisonic and discrete channels of the
+    /// operands.
+    fn bitand(self, rhs: Channels) -> Channels {
+        Channels {
+            bits: self.bits & rhs.bits,
+            ambisonic: self.ambisonic.min(rhs.ambisonic),
+            discrete: self.discrete.min(rhs.discrete),
+        }
+    }
+}
+
+impl std::ops::BitAndAssign for Channels {
+    fn bitand_assign(&mut self, rhs: Channels) {
+        *self = *self & rhs;
+    }
+}
+
+impl std::ops::Sub for Channels {
+    type Output = Channels;
+
+    /// Gets the channels in `self` that are not in `rhs`. The ambisonic or discrete channels are
+    /// removed entirely if `rhs` contains any ambisonic or discrete channels, respectively.
+    fn sub(self, rhs: Channels) -> Channels {
+        Channels {
+            bits: self.bits & !rhs.bits,
+            ambisonic: if rhs.ambisonic > 0 { 0 } else { self.ambisonic },
+            discrete: if rhs.discrete > 0 { 0 } else { self.discrete },
+        }
+    }
+}
+
+impl std::ops::SubAssign for Channels {
+    fn sub_assign(&mut self, rhs: Channels) {
+        *self = *self - rhs;
+    }
+}
+
+impl std::ops::BitXor for Channels {
+    type Output = Channels;
+
+    /// Gets the channels in either set of channels, but not both.
+    fn bitxor(self, rhs: Channels) -> Channels {
+        (self - rhs) | (rhs - self)
+    }
+}
+
+impl std::ops::BitXorAssign for Channels {
+    fn bitxor_assign(&mut self, rhs: Channels) {
+        *self = *self ^ rhs;
+    }
+}
+
+impl std::ops::Not for Channels {
+    type Output = Channels;
+
+    /// Gets the positioned channels that are not in the set of channels. Ambisonic and discrete
+    /// channels have no complement.
+    fn not(self) -> Channels {
+        Channels::all() - self
+    }
+}
+
+impl Extend<Channels> for Channels {
+    fn extend<T: IntoIterator<Item = Channels>>(&mut self, iter: T) {
+        for channels in iter {
+            self.insert(channels);
+        }
+    }
+}
+
+impl std::iter::FromIterator<Channels> for Channels {
+    fn from_iter<T: IntoIterator<Item = Channels>>(iter: T) -> Channels {
+        let mut channels = Channels::empty();
+        channels.extend(iter);
+        channels
+    }
+}
+
+impl From<Layout> for Channels {
+    fn from(layout: Layout) -> Self {
+        layout.into_channels()
+    }
+}
+
+/// An iterator over individual positioned channels within a `Channels` bitmask.
 pub struct ChannelsIter {
     channels: Channels,
 }
@@ -98,9 +584,9 @@ impl Iterator for ChannelsIter {
     type Item = Channels;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if !self.channels.is_empty() {
-            let channel = Channels::from_bits_truncate(1 << self.channels.bits.trailing_zeros());
-            self.channels ^= channel;
+        if self.channels.bits != 0 {
+            let channel = Channels::from_bits_unchecked(1 << self.channels.bits.trailing_zeros());
+            self.channels.bits ^= channel.bits;
             Some(channel)
         }
         else {
@@ -109,26 +595,103 @@ impl Iterator for ChannelsIter {
     }
 }
 
-impl Channels {
-    /// Gets the number of channels.
-    pub fn count(self) -> usize {
-        self.bits.count_ones() as usize
-    }
+/// The label of an individual channel.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ChannelLabel {
+    /// A positioned channel. Contains exactly one position.
+    Positioned(Channels),
+    /// An ambisonic component, identified by its Ambisonic Channel Number (ACN).
+    Ambisonic(u16),
+    /// A discrete channel, identified by its index amongst the discrete channels.
+    Discrete(u16),
+}
 
-    /// Gets an iterator over individual channels.
-    pub fn iter(&self) -> ChannelsIter {
-        ChannelsIter { channels: *self }
+/// An iterator over the labels of all channels within a `Channels`, in frame order.
+pub struct ChannelLabels {
+    positioned: ChannelsIter,
+    ambisonic: Range<u16>,
+    discrete: Range<u16>,
+}
+
+impl Iterator for ChannelLabels {
+    type Item = ChannelLabel;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(acn) = self.ambisonic.next() {
+            Some(ChannelLabel::Ambisonic(acn))
+        }
+        else if let Some(channel) = self.positioned.next() {
+            Some(ChannelLabel::Positioned(channel))
+        }
+        else {
+            self.discrete.next().map(ChannelLabel::Discrete)
+        }
     }
 }
 
 impl fmt::Display for Channels {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{:#032b}", self.bits)
+        write!(f, "{:#032b}", self.bits)?;
+
+        if let Some(order) = self.ambisonic_order() {
+            write!(f, " + ambisonic order {}", order)?;
+        }
+
+        if self.discrete > 0 {
+            write!(f, " + {} discrete", self.discrete)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Channels {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut names = Vec::new();
+
+        if let Some(order) = self.ambisonic_order() {
+            names.push(format!("AMBISONIC({})", order));
+        }
+
+        for channel in self.iter() {
+            match CHANNEL_NAMES.get(channel.bits.trailing_zeros() as usize) {
+                Some(name) => names.push(name.to_string()),
+                None => names.push(format!("{:#x}", channel.bits)),
+            }
+        }
+
+        if self.discrete > 0 {
+            names.push(format!("DISCRETE({})", self.discrete));
+        }
+
+        if names.is_empty() {
+            write!(f, "(empty)")
+        }
+        else {
+            write!(f, "{}", names.join(" | "))
+        }
     }
 }
 
+macro_rules! impl_channels_fmt {
+    ($trait:path) => {
+        /// Formats the positioned channel bitmask.
+        impl $trait for Channels {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                <u32 as $trait>::fmt(&self.bits, f)
+            }
+        }
+    };
+}
+
+impl_channels_fmt!(fmt::Binary);
+impl_channels_fmt!(fmt::Octal);
+impl_channels_fmt!(fmt::LowerHex);
+impl_channels_fmt!(fmt::UpperHex);
+
 /// `Layout` describes common audio channel configurations.
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum Layout {
     /// Single centre channel.
     Mono,
@@ -136,27 +699,80 @@ pub enum Layout {
     Stereo,
     /// Left and Right channels with a single low-frequency channel.
     TwoPointOne,
-    /// Front Left and Right, Rear Left and Right, and a single low-frequency channel.
+    /// Front Left, Right, and Centre channels.
+    ThreePointZero,
+    /// Front Left and Right, and Rear Left and Right channels.
+    Quad,
+    /// Front Left, Right, and Centre, and Rear Left and Right channels.
+    FivePointZero,
+    /// Front Left, Right, and Centre, Rear Left and Right, and a single low-frequency channel.
     FivePointOne,
+    /// Front Left, Right, and Centre, Side Left and Right, Rear Centre, and a single low-frequency
+    /// channel.
+    SixPointOne,
+    /// Front Left, Right, and Centre, Side Left and Right, Rear Left and Right, and a single
+    /// low-frequency channel.
+    SevenPointOne,
+    /// 5.1 with Top Front Left and Right channels.
+    FivePointOnePointTwo,
+    /// 5.1 with Top Front Left and Right, and Top Rear Left and Right channels.
+    FivePointOnePointFour,
+    /// 7.1 with Top Front Left and Right channels.
+    SevenPointOnePointTwo,
+    /// 7.1 with Top Front Left and Right, and Top Rear Left and Right channels.
+    SevenPointOnePointFour,
 }
 
 impl Layout {
+    /// All layouts.
+    const ALL: [Layout; 13] = [
+        Layout::Mono,
+        Layout::Stereo,
+        Layout::TwoPointOne,
+        Layout::ThreePointZero,
+        Layout::Quad,
+        Layout::FivePointZero,
+        Layout::FivePointOne,
+        Layout::SixPointOne,
+        Layout::SevenPointOne,
+        Layout::FivePointOnePointTwo,
+        Layout::FivePointOnePointFour,
+        Layout::SevenPointOnePointTwo,
+        Layout::SevenPointOnePointFour,
+    ];
+
     /// Converts a channel `Layout` into a `Channels` bit mask.
-    fn into_channels(self) -> Channels {
+    pub fn into_channels(self) -> Channels {
+        let stereo = Channels::FRONT_LEFT | Channels::FRONT_RIGHT;
+        let three = stereo | Channels::FRONT_CENTRE;
+        let rear = Channels::REAR_LEFT | Channels::REAR_RIGHT;
+        let side = Channels::SIDE_LEFT | Channels::SIDE_RIGHT;
+        let top_front = Channels::TOP_FRONT_LEFT | Channels::TOP_FRONT_RIGHT;
+        let top_rear = Channels::TOP_REAR_LEFT | Channels::TOP_REAR_RIGHT;
+
         match self {
             Layout::Mono => Channels::FRONT_LEFT,
-            Layout::Stereo => Channels::FRONT_LEFT | Channels::FRONT_RIGHT,
-            Layout::TwoPointOne => Channels::FRONT_LEFT | Channels::FRONT_RIGHT | Channels::LFE1,
-            Layout::FivePointOne => {
-                Channels::FRONT_LEFT
-                    | Channels::FRONT_RIGHT
-                    | Channels::FRONT_CENTRE
-                    | Channels::REAR_LEFT
-                    | Channels::REAR_RIGHT
-                    | Channels::LFE1
+            Layout::Stereo => stereo,
+            Layout::TwoPointOne => stereo | Channels::LFE1,
+            Layout::ThreePointZero => three,
+            Layout::Quad => stereo | rear,
+            Layout::FivePointZero => three | rear,
+            Layout::FivePointOne => three | rear | Channels::LFE1,
+            Layout::SixPointOne => three | side | Channels::REAR_CENTRE | Channels::LFE1,
+            Layout::SevenPointOne => three | side | rear | Channels::LFE1,
+            Layout::FivePointOnePointTwo => three | rear | Channels::LFE1 | top_front,
+            Layout::FivePointOnePointFour => three | rear | Channels::LFE1 | top_front | top_rear,
+            Layout::SevenPointOnePointTwo => three | side | rear | Channels::LFE1 | top_front,
+            Layout::SevenPointOnePointFour => {
+                three | side | rear | Channels::LFE1 | top_front | top_rear
             }
         }
     }
+
+    /// Gets the `Layout` with exactly the channels `channels`, if any.
+    pub fn from_channels(channels: Channels) -> Option<Layout> {
+        Layout::ALL.iter().copied().find(|layout| layout.into_channels() == channels)
+    }
 }
 
 /// `SignalSpec` describes the characteristics of a Signal.
@@ -367,32 +983,287 @@ impl<S: Sample> AudioBuffer<S> {
             planes.push(&mut channel[..self.n_frames]);
         }
 
-        planes
+        planes
+    }
+
+    /// Converts the contents of an AudioBuffer into an equivalent destination AudioBuffer of a
+    /// different type. If the types are the same then this is a copy operation.
+    ///
+    /// No dither is applied. To dither lossy conversions, use `convert_with_dither` instead.
+    pub fn convert<T: Sample>(&self, dest: &mut AudioBuffer<T>)
+    where
+        S: IntoSample<T>,
+    {
+        assert!(dest.n_frames == self.n_frames);
+        assert!(dest.n_capacity == self.n_capacity);
+        assert!(dest.spec == self.spec);
+
+        for c in 0..self.spec.channels.count() {
+            let begin = c * self.n_capacity;
+            let end = begin + self.n_frames;
+
+            for (d, s) in dest.buf[begin..end].iter_mut().zip(&self.buf[begin..end]) {
+                *d = (*s).into_sample();
+            }
+        }
+    }
+
+    /// Converts the contents of an AudioBuffer into an equivalent destination AudioBuffer of a
+    /// different type. If the conversion is lossy, such as from floating-point to integer samples,
+    /// or from 24-bit to 16-bit samples, `dither` is applied before the conversion.
+    ///
+    /// The state of `dither` is preserved across successive conversions. Therefore, the output
+    /// only depends on the seed of `dither` and the audio previously converted with it.
+    pub fn convert_with_dither<T: Sample>(&self, dest: &mut AudioBuffer<T>, dither: &mut Dynamic)
+    where
+        S: IntoSample<T>,
+    {
+        assert!(dest.n_frames == self.n_frames);
+        assert!(dest.n_capacity == self.n_capacity);
+        assert!(dest.spec == self.spec);
+
+        dither.set_sample_rate(self.spec.rate);
+
+        for c in 0..self.spec.channels.count() {
+            let begin = c * self.n_capacity;
+            let end = begin + self.n_frames;
+
+            dither.set_channel(c);
+
+            for (d, s) in dest.buf[begin..end].iter_mut().zip(&self.buf[begin..end]) {
+                *d = (*s).into_sample_dithered(dither);
+            }
+        }
+    }
+
+    /// Makes an equivalent AudioBuffer of a different type.
+    pub fn make_equivalent<E: Sample>(&self) -> AudioBuffer<E> {
+        AudioBuffer::<E>::new(self.n_capacity as Duration, self.spec)
+    }
+
+    /// Multiplies all samples in the buffer by the linear gain `gain`.
+    pub fn apply_gain(&mut self, gain: f64)
+    where
+        S: ConvertibleSample + IntoSample<f64>,
+    {
+        for ch in 0..self.spec.channels.count() {
+            self.apply_channel_gain(ch, gain);
+        }
+    }
+
+    /// Multiplies all samples in the buffer by the gain `gain_db`, in decibels.
+    pub fn apply_gain_db(&mut self, gain_db: f64)
+    where
+        S: ConvertibleSample + IntoSample<f64>,
+    {
+        self.apply_gain(10f64.powf(gain_db / 20.0));
+    }
+
+    /// Multiplies all samples of a single channel by the linear gain `gain`.
+    pub fn apply_channel_gain(&mut self, channel: usize, gain: f64)
+    where
+        S: ConvertibleSample + IntoSample<f64>,
+    {
+        for s in self.chan_mut(channel) {
+            *s = S::from_sample((*s).into_sample() * gain);
+        }
+    }
+
+    /// Mixes the audio data of `src`, multiplied by the linear gain `gain`, into the buffer
+    /// starting at frame `offset`. The source buffer may be of a different sample format.
+    ///
+    /// The buffers must have the same channels, and all frames of `src` must fall within the
+    /// frames of this buffer.
+    pub fn mix<F>(&mut self, src: &AudioBuffer<F>, offset: usize, gain: f64)
+    where
+        F: Sample + IntoSample<f64>,
+        S: ConvertibleSample + IntoSample<f64>,
+    {
+        assert!(self.spec.channels == src.spec.channels, "channels do not match");
+        assert!(offset + src.n_frames <= self.n_frames, "source exceeds the destination");
+
+        for ch in 0..self.spec.channels.count() {
+            let dst = &mut self.chan_mut(ch)[offset..];
+
+            for (d, &s) in dst.iter_mut().zip(src.chan(ch)) {
+                let mixed: f64 = (*d).into_sample() + gain * s.into_sample();
+                *d = S::from_sample(mixed);
+            }
+        }
+    }
+
+    /// Fades in the first `n_frames` of the buffer from silence using the gain curve `curve`.
+    pub fn fade_in(&mut self, n_frames: usize, curve: FadeCurve)
+    where
+        S: ConvertibleSample + IntoSample<f64>,
+    {
+        assert!(n_frames <= self.n_frames, "fade exceeds the buffer");
+
+        for ch in 0..self.spec.channels.count() {
+            for (i, s) in self.chan_mut(ch)[..n_frames].iter_mut().enumerate() {
+                let gain = curve.gain(i as f64 / n_frames as f64);
+                *s = S::from_sample((*s).into_sample() * gain);
+            }
+        }
+    }
+
+    /// Fades out the last `n_frames` of the buffer to silence using the gain curve `curve`.
+    pub fn fade_out(&mut self, n_frames: usize, curve: FadeCurve)
+    where
+        S: ConvertibleSample + IntoSample<f64>,
+    {
+        assert!(n_frames <= self.n_frames, "fade exceeds the buffer");
+
+        let start = self.n_frames - n_frames;
+
+        for ch in 0..self.spec.channels.count() {
+            for (i, s) in self.chan_mut(ch)[start..].iter_mut().enumerate() {
+                let gain = curve.gain(1.0 - i as f64 / n_frames as f64);
+                *s = S::from_sample((*s).into_sample() * gain);
+            }
+        }
+    }
+
+    /// Splices `src` onto the end of the buffer with a crossfade. The last `n_frames` of the
+    /// buffer are faded out while the first `n_frames` of `src` are faded in using the gain curve
+    /// `curve`, and the remaining frames of `src` are appended. The source buffer may be of a
+    /// different sample format.
+    ///
+    /// The buffers must have the same channels, both buffers must contain at least `n_frames`, and
+    /// the capacity of this buffer must be sufficient to store the spliced audio.
+    pub fn crossfade<F>(&mut self, src: &AudioBuffer<F>, n_frames: usize, curve: FadeCurve)
+    where
+        F: Sample + IntoSample<f64> + IntoSample<S>,
+        S: ConvertibleSample + IntoSample<f64>,
+    {
+        assert!(self.spec.channels == src.spec.channels, "channels do not match");
+        assert!(n_frames <= self.n_frames && n_frames <= src.n_frames, "crossfade too long");
+        assert!(
+            self.n_frames + src.n_frames - n_frames <= self.n_capacity,
+            "capacity will be exceeded"
+        );
+
+        let start = self.n_frames - n_frames;
+
+        for ch in 0..self.spec.channels.count() {
+            let dst = &mut self.chan_mut(ch)[start..];
+
+            for (i, (d, &s)) in dst.iter_mut().zip(src.chan(ch)).enumerate() {
+                let t = i as f64 / n_frames as f64;
+
+                let d_val: f64 = (*d).into_sample();
+                let s_val: f64 = s.into_sample();
+
+                *d = S::from_sample(curve.gain(1.0 - t) * d_val + curve.gain(t) * s_val);
+            }
+        }
+
+        self.append_frames(src, n_frames..src.n_frames);
+    }
+
+    /// Copies a single channel of the buffer into `dst`, converting the samples to the sample
+    /// format of `dst`. Returns the number of samples copied.
+    ///
+    /// The length of `dst` must be greater than or equal to the number of frames in the buffer.
+    pub fn extract_channel<T>(&self, channel: usize, dst: &mut [T]) -> usize
+    where
+        S: IntoSample<T>,
+        T: Sample,
+    {
+        assert!(dst.len() >= self.n_frames, "destination buffer is too small");
+
+        for (d, &s) in dst.iter_mut().zip(self.chan(channel)) {
+            *d = s.into_sample();
+        }
+
+        self.n_frames
+    }
+
+    /// Replaces the samples of a single channel of the buffer with the samples in `src`,
+    /// converting the samples to the sample format of the buffer.
+    ///
+    /// The length of `src` must equal the number of frames in the buffer.
+    pub fn insert_channel<F>(&mut self, channel: usize, src: &[F])
+    where
+        F: Sample + IntoSample<S>,
+    {
+        assert!(src.len() == self.n_frames, "source length does not match the buffer");
+
+        for (d, &s) in self.chan_mut(channel).iter_mut().zip(src) {
+            *d = s.into_sample();
+        }
+    }
+
+    /// Appends all frames of `src` to the end of the buffer, converting the samples to the sample
+    /// format of the buffer.
+    ///
+    /// The buffers must have the same channels, and the capacity of this buffer must be sufficient
+    /// to store the appended frames. The buffer is never reallocated.
+    pub fn append<F>(&mut self, src: &AudioBuffer<F>)
+    where
+        F: Sample + IntoSample<S>,
+    {
+        self.append_frames(src, 0..src.n_frames);
     }
 
-    /// Converts the contents of an AudioBuffer into an equivalent destination AudioBuffer of a
-    /// different type. If the types are the same then this is a copy operation.
-    pub fn convert<T: Sample>(&self, dest: &mut AudioBuffer<T>)
+    /// Appends the frames of `src` in `range` to the end of the buffer, converting the samples to
+    /// the sample format of the buffer.
+    ///
+    /// The buffers must have the same channels, and the capacity of this buffer must be sufficient
+    /// to store the appended frames. The buffer is never reallocated.
+    pub fn append_frames<F>(&mut self, src: &AudioBuffer<F>, range: Range<usize>)
     where
-        S: IntoSample<T>,
+        F: Sample + IntoSample<S>,
     {
-        assert!(dest.n_frames == self.n_frames);
-        assert!(dest.n_capacity == self.n_capacity);
-        assert!(dest.spec == self.spec);
+        assert!(self.spec.channels == src.spec.channels, "channels do not match");
+        assert!(range.start <= range.end && range.end <= src.n_frames, "invalid frame range");
 
-        for c in 0..self.spec.channels.count() {
-            let begin = c * self.n_capacity;
-            let end = begin + self.n_frames;
+        let start = self.n_frames;
+        self.render_reserved(Some(range.len()));
 
-            for (d, s) in dest.buf[begin..end].iter_mut().zip(&self.buf[begin..end]) {
-                *d = (*s).into_sample();
+        for ch in 0..self.spec.channels.count() {
+            let dst = &mut self.chan_mut(ch)[start..];
+
+            for (d, &s) in dst.iter_mut().zip(&src.chan(ch)[range.clone()]) {
+                *d = s.into_sample();
             }
         }
     }
 
-    /// Makes an equivalent AudioBuffer of a different type.
-    pub fn make_equivalent<E: Sample>(&self) -> AudioBuffer<E> {
-        AudioBuffer::<E>::new(self.n_capacity as Duration, self.spec)
+    /// Retains only the frames in `range`, and moves them to the start of the buffer. The buffer is
+    /// never reallocated.
+    pub fn retain_frames(&mut self, range: Range<usize>) {
+        assert!(range.start <= range.end && range.end <= self.n_frames, "invalid frame range");
+
+        self.truncate(range.end);
+        self.shift(range.start);
+    }
+}
+
+/// The shape of the gain curve of a fade.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FadeCurve {
+    /// The gain changes linearly. A crossfade between correlated signals has a constant amplitude.
+    Linear,
+    /// The gain follows a quarter period of a sine wave. A crossfade between uncorrelated signals
+    /// has a constant power.
+    EqualPower,
+    /// The gain follows a half period of a raised cosine, and changes slowly at the start and end
+    /// of the fade.
+    SCurve,
+}
+
+impl FadeCurve {
+    /// Gets the gain of the curve at the normalized position `t`, in the range [0, 1], of a fade
+    /// in. The gain of a fade out at `t` is the gain of a fade in at `1 - t`.
+    pub fn gain(self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+
+        match self {
+            FadeCurve::Linear => t,
+            FadeCurve::EqualPower => (0.5 * std::f64::consts::PI * t).sin(),
+            FadeCurve::SCurve => 0.5 - 0.5 * (std::f64::consts::PI * t).cos(),
+        }
     }
 }
 
@@ -413,6 +1284,40 @@ macro_rules! impl_audio_buffer_ref_func {
     };
 }
 
+macro_rules! impl_audio_buffer_mut_func {
+    ($var:expr, $buf:ident,$expr:expr) => {
+        match $var {
+            AudioBufferMut::U8($buf) => $expr,
+            AudioBufferMut::U16($buf) => $expr,
+            AudioBufferMut::U24($buf) => $expr,
+            AudioBufferMut::U32($buf) => $expr,
+            AudioBufferMut::S8($buf) => $expr,
+            AudioBufferMut::S16($buf) => $expr,
+            AudioBufferMut::S24($buf) => $expr,
+            AudioBufferMut::S32($buf) => $expr,
+            AudioBufferMut::F32($buf) => $expr,
+            AudioBufferMut::F64($buf) => $expr,
+        }
+    };
+}
+
+macro_rules! impl_interleaved_buffer_mut_func {
+    ($var:expr, $buf:ident,$expr:expr) => {
+        match $var {
+            InterleavedBufferMut::U8($buf) => $expr,
+            InterleavedBufferMut::U16($buf) => $expr,
+            InterleavedBufferMut::U24($buf) => $expr,
+            InterleavedBufferMut::U32($buf) => $expr,
+            InterleavedBufferMut::S8($buf) => $expr,
+            InterleavedBufferMut::S16($buf) => $expr,
+            InterleavedBufferMut::S24($buf) => $expr,
+            InterleavedBufferMut::S32($buf) => $expr,
+            InterleavedBufferMut::F32($buf) => $expr,
+            InterleavedBufferMut::F64($buf) => $expr,
+        }
+    };
+}
+
 /// `AudioBufferRef` is a copy-on-write reference to an `AudioBuffer` of any type.
 #[derive(Clone)]
 pub enum AudioBufferRef<'a> {
@@ -444,6 +1349,57 @@ impl<'a> AudioBufferRef<'a> {
     pub fn frames(&self) -> usize {
         impl_audio_buffer_ref_func!(self, buf, buf.frames())
     }
+
+    /// Converts the audio data to the sample format of the caller-owned audio buffer `dst`, and
+    /// copies it into `dst`, replacing its contents. If the conversion is lossy, `dither` is
+    /// applied before the conversion.
+    ///
+    /// The buffers must have the same `SignalSpec`, and the capacity of `dst` must be greater than
+    /// or equal to the number of frames in this buffer. Otherwise, an error is returned and `dst`
+    /// is not modified.
+    pub fn copy_to(&self, dst: AudioBufferMut, dither: &mut Dynamic) -> Result<()> {
+        let is_dithered = dither.dither_type() != DitherType::Identity;
+
+        // Common conversions have vectorized implementations if they are not dithered.
+        match (self, dst) {
+            (AudioBufferRef::F32(src), AudioBufferMut::S16(dst)) if !is_dithered => {
+                convert_planar_with(src, dst, simd::f32_to_i16)
+            }
+            (AudioBufferRef::S16(src), AudioBufferMut::F32(dst)) => {
+                convert_planar_with(src, dst, simd::i16_to_f32)
+            }
+            (AudioBufferRef::F32(src), AudioBufferMut::S32(dst)) if !is_dithered => {
+                convert_planar_with(src, dst, simd::f32_to_i32)
+            }
+            (AudioBufferRef::S32(src), AudioBufferMut::F32(dst)) => {
+                convert_planar_with(src, dst, simd::i32_to_f32)
+            }
+            (src, dst) => impl_audio_buffer_ref_func!(
+                src,
+                src,
+                impl_audio_buffer_mut_func!(dst, dst, convert_planar(src, dst, dither))
+            ),
+        }
+    }
+
+    /// Converts the audio data to the sample format of the caller-owned slice `dst`, and copies it
+    /// into `dst` in interleaved channel order. If the conversion is lossy, `dither` is applied
+    /// before the conversion. Returns the number of frames copied.
+    ///
+    /// The length of `dst` must be greater than or equal to the number of frames in this buffer
+    /// multiplied by the number of channels. Otherwise, an error is returned and `dst` is not
+    /// modified.
+    pub fn copy_to_interleaved(
+        &self,
+        dst: InterleavedBufferMut,
+        dither: &mut Dynamic,
+    ) -> Result<usize> {
+        impl_audio_buffer_ref_func!(
+            self,
+            src,
+            impl_interleaved_buffer_mut_func!(dst, dst, convert_interleaved(src, dst, dither))
+        )
+    }
 }
 
 /// `AsAudioBufferRef` is a trait implemented for `AudioBuffer`s that may be referenced in an
@@ -474,6 +1430,232 @@ impl_as_audio_buffer_ref!(i32, AudioBufferRef::S32);
 impl_as_audio_buffer_ref!(f32, AudioBufferRef::F32);
 impl_as_audio_buffer_ref!(f64, AudioBufferRef::F64);
 
+/// `AudioBufferMut` is a mutable reference to a caller-owned `AudioBuffer` of any type.
+pub enum AudioBufferMut<'a> {
+    U8(&'a mut AudioBuffer<u8>),
+    U16(&'a mut AudioBuffer<u16>),
+    U24(&'a mut AudioBuffer<u24>),
+    U32(&'a mut AudioBuffer<u32>),
+    S8(&'a mut AudioBuffer<i8>),
+    S16(&'a mut AudioBuffer<i16>),
+    S24(&'a mut AudioBuffer<i24>),
+    S32(&'a mut AudioBuffer<i32>),
+    F32(&'a mut AudioBuffer<f32>),
+    F64(&'a mut AudioBuffer<f64>),
+}
+
+impl<'a> AudioBufferMut<'a> {
+    /// Gets the signal specification for the buffer.
+    pub fn spec(&self) -> &SignalSpec {
+        impl_audio_buffer_mut_func!(self, buf, buf.spec())
+    }
+
+    /// Gets the total capacity of the buffer. The capacity is the maximum number of audio frames
+    /// a buffer can store.
+    pub fn capacity(&self) -> usize {
+        impl_audio_buffer_mut_func!(self, buf, buf.capacity())
+    }
+
+    /// Gets the number of frames in the buffer.
+    pub fn frames(&self) -> usize {
+        impl_audio_buffer_mut_func!(self, buf, buf.frames())
+    }
+
+    /// Gets the sample format of the buffer.
+    pub fn sample_format(&self) -> SampleFormat {
+        impl_audio_buffer_mut_func!(self, buf, sample_format_of(&**buf))
+    }
+}
+
+/// `AsAudioBufferMut` is a trait implemented for `AudioBuffer`s that may be referenced in an
+/// `AudioBufferMut`.
+pub trait AsAudioBufferMut {
+    /// Get an `AudioBufferMut` reference.
+    fn as_audio_buffer_mut(&mut self) -> AudioBufferMut<'_>;
+}
+
+macro_rules! impl_as_audio_buffer_mut {
+    ($fmt:ty, $ref:path) => {
+        impl AsAudioBufferMut for AudioBuffer<$fmt> {
+            fn as_audio_buffer_mut(&mut self) -> AudioBufferMut<'_> {
+                $ref(self)
+            }
+        }
+    };
+}
+
+impl_as_audio_buffer_mut!(u8, AudioBufferMut::U8);
+impl_as_audio_buffer_mut!(u16, AudioBufferMut::U16);
+impl_as_audio_buffer_mut!(u24, AudioBufferMut::U24);
+impl_as_audio_buffer_mut!(u32, AudioBufferMut::U32);
+impl_as_audio_buffer_mut!(i8, AudioBufferMut::S8);
+impl_as_audio_buffer_mut!(i16, AudioBufferMut::S16);
+impl_as_audio_buffer_mut!(i24, AudioBufferMut::S24);
+impl_as_audio_buffer_mut!(i32, AudioBufferMut::S32);
+impl_as_audio_buffer_mut!(f32, AudioBufferMut::F32);
+impl_as_audio_buffer_mut!(f64, AudioBufferMut::F64);
+
+/// `InterleavedBufferMut` is a mutable reference to a caller-owned slice of interleaved samples of
+/// any type.
+///
+/// An `InterleavedBufferMut` may be created from a mutable slice of any sample type using `From`.
+pub enum InterleavedBufferMut<'a> {
+    U8(&'a mut [u8]),
+    U16(&'a mut [u16]),
+    U24(&'a mut [u24]),
+    U32(&'a mut [u32]),
+    S8(&'a mut [i8]),
+    S16(&'a mut [i16]),
+    S24(&'a mut [i24]),
+    S32(&'a mut [i32]),
+    F32(&'a mut [f32]),
+    F64(&'a mut [f64]),
+}
+
+impl<'a> InterleavedBufferMut<'a> {
+    /// Gets the number of samples the buffer may store.
+    pub fn len(&self) -> usize {
+        impl_interleaved_buffer_mut_func!(self, buf, buf.len())
+    }
+
+    /// Returns `true` if the buffer cannot store any samples.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Gets the sample format of the buffer.
+    pub fn sample_format(&self) -> SampleFormat {
+        impl_interleaved_buffer_mut_func!(self, buf, slice_sample_format_of(buf))
+    }
+}
+
+macro_rules! impl_interleaved_buffer_mut_from {
+    ($fmt:ty, $ref:path) => {
+        impl<'a> From<&'a mut [$fmt]> for InterleavedBufferMut<'a> {
+            fn from(buf: &'a mut [$fmt]) -> Self {
+                $ref(buf)
+            }
+        }
+    };
+}
+
+impl_interleaved_buffer_mut_from!(u8, InterleavedBufferMut::U8);
+impl_interleaved_buffer_mut_from!(u16, InterleavedBufferMut::U16);
+impl_interleaved_buffer_mut_from!(u24, InterleavedBufferMut::U24);
+impl_interleaved_buffer_mut_from!(u32, InterleavedBufferMut::U32);
+impl_interleaved_buffer_mut_from!(i8, InterleavedBufferMut::S8);
+impl_interleaved_buffer_mut_from!(i16, InterleavedBufferMut::S16);
+impl_interleaved_buffer_mut_from!(i24, InterleavedBufferMut::S24);
+impl_interleaved_buffer_mut_from!(i32, InterleavedBufferMut::S32);
+impl_interleaved_buffer_mut_from!(f32, InterleavedBufferMut::F32);
+impl_interleaved_buffer_mut_from!(f64, InterleavedBufferMut::F64);
+
+fn sample_format_of<S: Sample>(_: &AudioBuffer<S>) -> SampleFormat {
+    S::FORMAT
+}
+
+fn slice_sample_format_of<S: Sample>(_: &[S]) -> SampleFormat {
+    S::FORMAT
+}
+
+/// Checks if the audio data from `src` can be copied into `dst` in planar channel order.
+fn check_planar_dst<F: Sample, T: Sample>(
+    src: &AudioBuffer<F>,
+    dst: &AudioBuffer<T>,
+) -> Result<()> {
+    if dst.spec != src.spec {
+        return unsupported_error("audio: destination signal specification does not match");
+    }
+
+    if dst.capacity() < src.frames() {
+        return limit_error("audio: destination buffer capacity is too small");
+    }
+
+    Ok(())
+}
+
+/// Converts, dithers, and copies the audio data from `src` into `dst` in planar channel order.
+fn convert_planar<F, T>(
+    src: &AudioBuffer<F>,
+    dst: &mut AudioBuffer<T>,
+    dither: &mut Dynamic,
+) -> Result<()>
+where
+    F: Sample + IntoSample<T>,
+    T: Sample,
+{
+    check_planar_dst(src, dst)?;
+
+    dst.clear();
+    dst.render_reserved(Some(src.frames()));
+
+    dither.set_sample_rate(src.spec.rate);
+
+    for ch in 0..src.spec.channels.count() {
+        dither.set_channel(ch);
+
+        for (d, s) in dst.chan_mut(ch).iter_mut().zip(src.chan(ch)) {
+            *d = (*s).into_sample_dithered(dither);
+        }
+    }
+
+    Ok(())
+}
+
+/// Converts and copies the audio data from `src` into `dst` in planar channel order using a
+/// vectorized conversion kernel. The conversion must not require a dither.
+fn convert_planar_with<F, T>(
+    src: &AudioBuffer<F>,
+    dst: &mut AudioBuffer<T>,
+    kernel: fn(&[F], &mut [T]),
+) -> Result<()>
+where
+    F: Sample,
+    T: Sample,
+{
+    check_planar_dst(src, dst)?;
+
+    dst.clear();
+    dst.render_reserved(Some(src.frames()));
+
+    for ch in 0..src.spec.channels.count() {
+        kernel(src.chan(ch), dst.chan_mut(ch));
+    }
+
+    Ok(())
+}
+
+/// Converts, dithers, and copies the audio data from `src` into `dst` in interleaved channel order.
+/// Returns the number of frames copied.
+fn convert_interleaved<F, T>(
+    src: &AudioBuffer<F>,
+    dst: &mut [T],
+    dither: &mut Dynamic,
+) -> Result<usize>
+where
+    F: Sample + IntoSample<T>,
+    T: Sample,
+{
+    let n_channels = src.spec.channels.count();
+    let n_frames = src.frames();
+
+    if dst.len() < n_frames * n_channels {
+        return limit_error("audio: destination buffer is too small");
+    }
+
+    dither.set_sample_rate(src.spec.rate);
+
+    for ch in 0..n_channels {
+        dither.set_channel(ch);
+
+        for (d, s) in dst[ch..].iter_mut().step_by(n_channels).zip(src.chan(ch)) {
+            *d = (*s).into_sample_dithered(dither);
+        }
+    }
+
+    Ok(n_frames)
+}
+
 /// The `Signal` trait provides methods for rendering and transforming contiguous buffers of audio
 /// data.
 pub trait Signal<S: Sample> {
@@ -695,6 +1877,7 @@ impl<S: Sample> Signal<S> for AudioBuffer<S> {
 pub struct SampleBuffer<S: Sample> {
     buf: Box<[S]>,
     n_written: usize,
+    dither: Dynamic,
 }
 
 impl<S: Sample> SampleBuffer<S> {
@@ -715,7 +1898,7 @@ impl<S: Sample> SampleBuffer<S> {
         // Allocate enough memory for all the samples and fill the buffer with silence.
         let buf = vec![S::MID; n_samples as usize].into_boxed_slice();
 
-        SampleBuffer { buf, n_written: 0 }
+        SampleBuffer { buf, n_written: 0, dither: Default::default() }
     }
 
     /// Gets the number of written samples.
@@ -748,6 +1931,17 @@ impl<S: Sample> SampleBuffer<S> {
         self.n_written = 0;
     }
 
+    /// Sets the dither applied when audio data is copied into the buffer using a lossy conversion.
+    /// By default, no dither is applied.
+    pub fn set_dither(&mut self, dither: Dynamic) {
+        self.dither = dither;
+    }
+
+    /// Gets the dithering algorithm applied on lossy conversions.
+    pub fn dither_type(&self) -> DitherType {
+        self.dither.dither_type()
+    }
+
     /// Copies all audio data from the source `AudioBufferRef` in planar channel order into the
     /// `SampleBuffer`. The two buffers must be equivalent.
     pub fn copy_planar_ref(&mut self, src: AudioBufferRef)
@@ -782,11 +1976,15 @@ impl<S: Sample> SampleBuffer<S> {
         // of samples that will be copied from the source buffer.
         assert!(self.capacity() >= n_samples);
 
+        self.dither.set_sample_rate(src.spec.rate);
+
         for ch in 0..n_channels {
             let ch_slice = src.chan(ch);
 
+            self.dither.set_channel(ch);
+
             for (dst, src) in self.buf[ch * n_frames..].iter_mut().zip(ch_slice) {
-                *dst = (*src).into_sample();
+                *dst = (*src).into_sample_dithered(&mut self.dither);
             }
         }
 
@@ -827,12 +2025,16 @@ impl<S: Sample> SampleBuffer<S> {
         // of samples that will be copied from the source buffer.
         assert!(self.capacity() >= n_samples);
 
+        self.dither.set_sample_rate(src.spec.rate);
+
         // Interleave the source buffer channels into the sample buffer.
         for ch in 0..n_channels {
             let ch_slice = src.chan(ch);
 
+            self.dither.set_channel(ch);
+
             for (dst, src) in self.buf[ch..].iter_mut().step_by(n_channels).zip(ch_slice) {
-                *dst = (*src).into_sample();
+                *dst = (*src).into_sample_dithered(&mut self.dither);
             }
         }
 
@@ -975,6 +2177,7 @@ impl RawSample for f64 {
 pub struct RawSampleBuffer<S: Sample + RawSample> {
     buf: Box<[S::RawType]>,
     n_written: usize,
+    dither: Dynamic,
     // Might take your heart.
     sample_format: PhantomData<S>,
 }
@@ -1000,7 +2203,12 @@ impl<S: Sample + RawSample> RawSampleBuffer<S> {
         // Allocate enough memory for all the samples and fill the buffer with silence.
         let buf = vec![S::MID.into_raw_sample(); n_samples as usize].into_boxed_slice();
 
-        RawSampleBuffer { buf, n_written: 0, sample_format: PhantomData }
+        RawSampleBuffer {
+            buf,
+            n_written: 0,
+            dither: Default::default(),
+            sample_format: PhantomData,
+        }
     }
 
     /// Gets the number of written samples.
@@ -1023,6 +2231,17 @@ impl<S: Sample + RawSample> RawSampleBuffer<S> {
         self.n_written = 0;
     }
 
+    /// Sets the dither applied when audio data is copied into the buffer using a lossy conversion.
+    /// By default, no dither is applied.
+    pub fn set_dither(&mut self, dither: Dynamic) {
+        self.dither = dither;
+    }
+
+    /// Gets the dithering algorithm applied on lossy conversions.
+    pub fn dither_type(&self) -> DitherType {
+        self.dither.dither_type()
+    }
+
     /// Gets an immutable slice to the bytes of the sample's written in the `RawSampleBuffer`.
     pub fn as_bytes(&self) -> &[u8] {
         // Get a slice to the written raw samples in the buffer, and convert from &[RawType] to
@@ -1065,13 +2284,17 @@ impl<S: Sample + RawSample> RawSampleBuffer<S> {
         // of samples that will be copied from the source buffer.
         assert!(self.capacity() >= n_samples);
 
+        self.dither.set_sample_rate(src.spec.rate);
+
         let dst_buf = &mut self.buf[..n_samples];
 
         for (ch, dst_ch) in dst_buf.chunks_exact_mut(src.n_frames).enumerate() {
             let src_ch = src.chan(ch);
 
+            self.dither.set_channel(ch);
+
             for (&s, d) in src_ch.iter().zip(dst_ch) {
-                *d = s.into_sample().into_raw_sample();
+                *d = s.into_sample_dithered(&mut self.dither).into_raw_sample();
             }
         }
 
@@ -1136,6 +2359,8 @@ impl<S: Sample + RawSample> RawSampleBuffer<S> {
         // of samples that will be copied from the source buffer.
         assert!(self.capacity() >= n_samples);
 
+        self.dither.set_sample_rate(src.spec.rate);
+
         // The destination buffer slice.
         let dst_buf = &mut self.buf[..n_samples];
 
@@ -1145,12 +2370,14 @@ impl<S: Sample + RawSample> RawSampleBuffer<S> {
             0 => (),
             // Mono
             1 => {
+                self.dither.set_channel(0);
+
                 for (&s, d) in src.chan(0).iter().zip(dst_buf) {
-                    *d = s.into_sample().into_raw_sample();
+                    *d = s.into_sample_dithered(&mut self.dither).into_raw_sample();
                 }
             }
-            // Stereo
-            2 => {
+            // Stereo, if not dithered. Dithers may have per-channel state.
+            2 if self.dither.dither_type() == DitherType::Identity => {
                 let l_buf = src.chan(0);
                 let r_buf = src.chan(1);
 
@@ -1159,14 +2386,16 @@ impl<S: Sample + RawSample> RawSampleBuffer<S> {
                     d[1] = r.into_sample().into_raw_sample();
                 }
             }
-            // 3+ channels
+            // Stereo if dithered, or 3+ channels
             _ => {
                 for ch in 0..n_channels {
                     let src_ch = src.chan(ch);
                     let dst_ch_iter = dst_buf[ch..].iter_mut().step_by(n_channels);
 
+                    self.dither.set_channel(ch);
+
                     for (&s, d) in src_ch.iter().zip(dst_ch_iter) {
-                        *d = s.into_sample().into_raw_sample();
+                        *d = s.into_sample_dithered(&mut self.dither).into_raw_sample();
                     }
                 }
             }
@@ -1225,3 +2454,420 @@ impl<S: Sample + RawSample> RawSampleBuffer<S> {
         self.n_written = n_samples;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conv::dither::DitherType;
+
+    #[test]
+    fn verify_channels() {
+        // First-order ambisonics with a head-locked stereo pair, and 2 discrete channels.
+        let channels = Channels::ambisonic(1).unwrap()
+            | Channels::FRONT_LEFT
+            | Channels::FRONT_RIGHT
+            | Channels::discrete(2);
+
+        assert_eq!(channels.count(), 8);
+        assert_eq!(channels.ambisonic_order(), Some(1));
+        assert_eq!(channels.discrete_count(), 2);
+        assert_eq!(channels.iter().count(), 2);
+        assert_eq!(
+            format!("{:?}", channels),
+            "AMBISONIC(1) | FRONT_LEFT | FRONT_RIGHT | DISCRETE(2)"
+        );
+
+        let labels: Vec<ChannelLabel> = channels.labels().collect();
+        assert_eq!(labels[0], ChannelLabel::Ambisonic(0));
+        assert_eq!(labels[4], ChannelLabel::Positioned(Channels::FRONT_LEFT));
+        assert_eq!(labels[7], ChannelLabel::Discrete(1));
+
+        for (i, &label) in labels.iter().enumerate() {
+            assert_eq!(channels.index_of(label), Some(i));
+        }
+
+        assert_eq!(channels.index_of(ChannelLabel::Ambisonic(4)), None);
+        assert!(channels.contains(Channels::ambisonic(0).unwrap() | Channels::discrete(1)));
+        assert!(!channels.contains(Channels::ambisonic(2).unwrap()));
+        assert_eq!(
+            channels - Channels::discrete(1),
+            Channels::ambisonic(1).unwrap() | Layout::Stereo.into()
+        );
+
+        // More than 32 channels.
+        assert_eq!(Channels::discrete(300).count(), 300);
+        assert_eq!(Channels::ambisonic(14).unwrap().count(), 225);
+        assert_eq!(Channels::ambisonic(254).unwrap().count(), 255 * 255);
+        assert_eq!(Channels::ambisonic(255), None);
+
+        // WAVEFORMATEXTENSIBLE channel masks.
+        let channels = Channels::from_wave_channel_mask(0x63f, 10);
+        assert_eq!(channels.positioned(), Layout::SevenPointOne.into_channels());
+        assert_eq!(channels.discrete_count(), 2);
+        assert_eq!(channels.to_wave_channel_mask(), 0x63f);
+        assert_eq!(Channels::from_wave_channel_mask(0x3f, 2), Layout::Stereo.into());
+        assert_eq!(Channels::from_wave_channel_mask(0, 4), Channels::discrete(4));
+
+        // Core Audio channel layout tags.
+        let five_one = Layout::FivePointOne.into_channels();
+        assert_eq!(Channels::from_caf_layout_tag(0x0079_0006, 0), Some(five_one));
+        assert_eq!(five_one.to_caf_layout_tag(), Some((0x0079_0006, 0)));
+        assert_eq!(Channels::from_caf_layout_tag(0x0001_0000, 0x63f), Some(channels.positioned()));
+        assert_eq!(channels.positioned().to_caf_layout_tag(), Some((0x0001_0000, 0x63f)));
+        assert_eq!(Channels::from_caf_layout_tag(0x00be_0010, 0), Channels::ambisonic(3));
+        assert_eq!(Channels::from_caf_layout_tag(0x00be_0005, 0), None);
+        assert_eq!(Channels::discrete(3).to_caf_layout_tag(), Some((0x0093_0003, 0)));
+        assert_eq!(channels.to_caf_layout_tag(), None);
+
+        // Named layouts.
+        assert_eq!(Layout::SevenPointOnePointFour.into_channels().count(), 12);
+        assert_eq!(Layout::from_channels(five_one), Some(Layout::FivePointOne));
+        assert_eq!(Layout::from_channels(Channels::discrete(2)), None);
+    }
+
+    #[test]
+    fn verify_channels_bitflags_interface() {
+        let stereo = Channels::FRONT_LEFT | Channels::FRONT_RIGHT;
+
+        assert_eq!(stereo.bits(), 0x3);
+        assert_eq!(Channels::from_bits(0x3), Some(stereo));
+        assert_eq!(Channels::from_bits(1 << 31), None);
+        assert_eq!(Channels::from_bits_truncate(0x8000_0003), stereo);
+        assert_eq!(Channels::from_bits_unchecked(0x8000_0003).bits(), 0x8000_0003);
+        assert_eq!(format!("{:?}", Channels::from_bits_unchecked(1 << 31)), "0x80000000");
+
+        assert!(Channels::empty().is_empty());
+        assert!(Channels::all().is_all());
+        assert!(!stereo.is_all());
+        assert!(stereo.intersects(Channels::FRONT_LEFT));
+        assert!(!stereo.intersects(Channels::LFE1));
+
+        assert_eq!(stereo.union(Channels::LFE1), Layout::TwoPointOne.into());
+        assert_eq!(stereo.intersection(Channels::FRONT_RIGHT), Channels::FRONT_RIGHT);
+        assert_eq!(stereo.difference(Channels::FRONT_RIGHT), Channels::FRONT_LEFT);
+        assert_eq!(
+            stereo.symmetric_difference(Channels::FRONT_RIGHT | Channels::LFE1),
+            Channels::FRONT_LEFT | Channels::LFE1
+        );
+        assert_eq!(stereo.complement() | stereo, Channels::all());
+        assert_eq!(!Channels::all(), Channels::empty());
+
+        let mut channels = stereo;
+        channels.toggle(Channels::FRONT_LEFT | Channels::LFE1);
+        assert_eq!(channels, Channels::FRONT_RIGHT | Channels::LFE1);
+        channels.set(Channels::LFE1, false);
+        assert_eq!(channels, Channels::FRONT_RIGHT);
+        channels.set(Channels::FRONT_LEFT, true);
+        assert_eq!(channels, stereo);
+
+        let collected: Channels = stereo.iter().chain(Some(Channels::LFE1)).collect();
+        assert_eq!(collected, Layout::TwoPointOne.into());
+
+        assert_eq!(format!("{:b}", stereo), "11");
+        assert_eq!(format!("{:o}", Channels::SIDE_LEFT), "1000");
+        assert_eq!(format!("{:x}", Channels::LFE2), "2000000");
+        assert_eq!(format!("{:#X}", Channels::TOP_REAR_RIGHT), "0x20000");
+
+        // Ambisonic and discrete channels are not positioned, and have no complement.
+        let channels = Channels::ambisonic(1).unwrap() | Channels::discrete(2);
+        assert_eq!(!channels, Channels::all());
+        assert_eq!(channels ^ Channels::discrete(1), Channels::ambisonic(1).unwrap());
+    }
+
+    #[test]
+    fn verify_channels_round_trip() {
+        for &layout in Layout::ALL.iter() {
+            let channels = layout.into_channels();
+
+            assert_eq!(Layout::from_channels(channels), Some(layout));
+
+            let mask = channels.to_wave_channel_mask();
+            assert_eq!(Channels::from_wave_channel_mask(mask, channels.count() as u16), channels);
+
+            let (tag, bitmap) = channels.to_caf_layout_tag().unwrap();
+            assert_eq!(Channels::from_caf_layout_tag(tag, bitmap), Some(channels));
+        }
+
+        for order in [0, 1, 3, 14] {
+            let channels = Channels::ambisonic(order).unwrap();
+            let (tag, bitmap) = channels.to_caf_layout_tag().unwrap();
+            assert_eq!(Channels::from_caf_layout_tag(tag, bitmap), Some(channels));
+        }
+
+        for count in [1, 2, 300] {
+            let channels = Channels::discrete(count);
+            let (tag, bitmap) = channels.to_caf_layout_tag().unwrap();
+            assert_eq!(Channels::from_caf_layout_tag(tag, bitmap), Some(channels));
+            assert_eq!(Channels::from_wave_channel_mask(0, count), channels);
+        }
+
+        // No channels are described by an empty channel bitmap.
+        let (tag, bitmap) = Channels::empty().to_caf_layout_tag().unwrap();
+        assert_eq!((tag, bitmap), (0x0001_0000, 0));
+        assert_eq!(Channels::from_caf_layout_tag(tag, bitmap), Some(Channels::empty()));
+        assert_eq!(Channels::from_wave_channel_mask(0, 0), Channels::empty());
+    }
+
+    #[test]
+    fn verify_copy_to_caller_buffers() {
+        let spec = SignalSpec::new(48000, Channels::FRONT_LEFT | Channels::FRONT_RIGHT);
+
+        let mut src = AudioBuffer::<f32>::new(64, spec);
+        src.render_reserved(Some(37));
+
+        for ch in 0..2 {
+            for (i, s) in src.chan_mut(ch).iter_mut().enumerate() {
+                *s = ((i + 7 * ch) as f32 * 0.3).sin() * 1.1;
+            }
+        }
+
+        let mut expected = SampleBuffer::<i16>::new(64, spec);
+        expected.copy_interleaved_ref(src.as_audio_buffer_ref());
+
+        let mut dither = Dynamic::default();
+
+        // Interleaved.
+        let mut interleaved = vec![0i16; 2 * 64];
+        let n_frames = src
+            .as_audio_buffer_ref()
+            .copy_to_interleaved((&mut interleaved[..]).into(), &mut dither)
+            .unwrap();
+
+        assert_eq!(n_frames, 37);
+        assert_eq!(&interleaved[..2 * 37], expected.samples());
+
+        // Planar, replacing any existing content.
+        let mut planar = AudioBuffer::<i16>::new(64, spec);
+        planar.render_silence(Some(50));
+
+        src.as_audio_buffer_ref().copy_to(planar.as_audio_buffer_mut(), &mut dither).unwrap();
+
+        assert_eq!(planar.frames(), 37);
+
+        for ch in 0..2 {
+            for (i, &s) in planar.chan(ch).iter().enumerate() {
+                assert_eq!(s, interleaved[2 * i + ch]);
+            }
+        }
+
+        // A lossy integer conversion with a triangular dither adds at most 1 LSB of error.
+        let mut wide = AudioBuffer::<i32>::new(64, spec);
+        wide.render_reserved(Some(37));
+        src.convert(&mut wide);
+
+        let mut dither = Dynamic::new(DitherType::Triangular);
+        wide.as_audio_buffer_ref().copy_to(planar.as_audio_buffer_mut(), &mut dither).unwrap();
+
+        for ch in 0..2 {
+            for (&a, &b) in planar.chan(ch).iter().zip(expected.samples()[ch..].iter().step_by(2)) {
+                assert!((i32::from(a) - i32::from(b)).abs() <= 1);
+            }
+        }
+    }
+
+    #[test]
+    fn verify_convert_dither() {
+        let spec = SignalSpec::new(44100, Channels::FRONT_LEFT | Channels::FRONT_RIGHT);
+
+        // A constant level of a quarter of a 16-bit step.
+        let mut src = AudioBuffer::<f32>::new(4096, spec);
+        src.render_reserved(None);
+
+        for ch in 0..2 {
+            src.chan_mut(ch).fill(0.25 / 32768.0);
+        }
+
+        // Without a dither, every sample is truncated to 0.
+        let mut dst = src.make_equivalent::<i16>();
+        dst.render_reserved(None);
+        src.convert(&mut dst);
+
+        assert!(dst.chan(0).iter().chain(dst.chan(1)).all(|&s| s == 0));
+
+        // A dither preserves the level on average.
+        let mut dither = Dynamic::with_seed(DitherType::TriangularFullAmplitude, 7);
+        src.convert_with_dither(&mut dst, &mut dither);
+
+        for ch in 0..2 {
+            let mean = dst.chan(ch).iter().map(|&s| f64::from(s)).sum::<f64>() / 4096.0;
+            assert!(dst.chan(ch).iter().all(|&s| (-1..=1).contains(&s)));
+            assert!((mean - 0.25).abs() < 0.05);
+        }
+
+        // The output only depends on the seed of the dither, and the audio previously converted
+        // with it.
+        let mut again = src.make_equivalent::<i16>();
+        again.render_reserved(None);
+        src.convert_with_dither(
+            &mut again,
+            &mut Dynamic::with_seed(DitherType::TriangularFullAmplitude, 7),
+        );
+
+        assert_eq!(again.chan(0), dst.chan(0));
+        assert_eq!(again.chan(1), dst.chan(1));
+
+        src.convert_with_dither(&mut again, &mut dither);
+
+        assert_ne!(again.chan(0), dst.chan(0));
+
+        // Integer narrowing, such as 24-bit to 16-bit, is also dithered.
+        let mut wide = src.make_equivalent::<i24>();
+        wide.render_reserved(None);
+
+        for ch in 0..2 {
+            wide.chan_mut(ch).fill(i24::from(64));
+        }
+
+        let mut narrow = wide.make_equivalent::<i16>();
+        narrow.render_reserved(None);
+        wide.convert(&mut narrow);
+
+        assert!(narrow.chan(0).iter().all(|&s| s == 0));
+
+        let mut dither = Dynamic::new(DitherType::TriangularFullAmplitude);
+        wide.convert_with_dither(&mut narrow, &mut dither);
+
+        let mean = narrow.chan(0).iter().map(|&s| f64::from(s)).sum::<f64>() / 4096.0;
+        assert!((mean - 0.25).abs() < 0.05);
+
+        // Lossless conversions are never dithered.
+        let mut float = src.make_equivalent::<f64>();
+        float.render_reserved(None);
+        src.convert_with_dither(&mut float, &mut dither);
+
+        assert!(float.chan(0).iter().all(|&s| s == f64::from(0.25f32 / 32768.0)));
+
+        // The typed copies into a sample buffer may be used generically, and apply the dither of
+        // the sample buffer.
+        fn copy_to_i16<F: Sample + IntoSample<i16>>(
+            buf: &mut SampleBuffer<i16>,
+            src: &AudioBuffer<F>,
+        ) {
+            buf.copy_interleaved_typed(src);
+        }
+
+        let mut samples = SampleBuffer::<i16>::new(4096, spec);
+
+        copy_to_i16(&mut samples, &src);
+        assert!(samples.samples().iter().all(|&s| s == 0));
+
+        samples.set_dither(Dynamic::new(DitherType::TriangularFullAmplitude));
+
+        copy_to_i16(&mut samples, &src);
+        assert!(samples.samples().iter().any(|&s| s != 0));
+
+        // A planar copy applies the given dither to integer narrowing.
+        let mut planar = AudioBuffer::<i16>::new(4096, spec);
+        let mut dither = Dynamic::new(DitherType::TriangularFullAmplitude);
+        wide.as_audio_buffer_ref().copy_to(planar.as_audio_buffer_mut(), &mut dither).unwrap();
+
+        assert!(planar.chan(0).iter().any(|&s| s != 0));
+    }
+
+    #[test]
+    fn verify_copy_to_incompatible_buffers() {
+        let spec = SignalSpec::new(48000, Channels::FRONT_LEFT | Channels::FRONT_RIGHT);
+
+        let mut src = AudioBuffer::<f32>::new(64, spec);
+        src.render_silence(Some(37));
+
+        let mut dither = Dynamic::default();
+
+        // Mismatched signal specification.
+        let mono = SignalSpec::new(48000, Channels::FRONT_LEFT);
+
+        let mut dst = AudioBuffer::<i16>::new(64, mono);
+        dst.render_reserved(Some(5));
+        dst.chan_mut(0).fill(7);
+
+        assert!(src.as_audio_buffer_ref().copy_to(dst.as_audio_buffer_mut(), &mut dither).is_err());
+        assert_eq!(dst.chan(0), &[7; 5]);
+
+        // Insufficient capacity, for both the vectorized and generic conversions.
+        let mut dst = AudioBuffer::<i16>::new(16, spec);
+        dst.render_reserved(Some(5));
+
+        assert!(src.as_audio_buffer_ref().copy_to(dst.as_audio_buffer_mut(), &mut dither).is_err());
+        assert_eq!(dst.frames(), 5);
+
+        let mut dst = AudioBuffer::<u8>::new(16, spec);
+        dst.render_reserved(Some(5));
+
+        assert!(src.as_audio_buffer_ref().copy_to(dst.as_audio_buffer_mut(), &mut dither).is_err());
+        assert_eq!(dst.frames(), 5);
+
+        // Insufficient length.
+        let mut interleaved = [3i16; 2 * 37 - 1];
+
+        assert!(src
+            .as_audio_buffer_ref()
+            .copy_to_interleaved((&mut interleaved[..]).into(), &mut dither)
+            .is_err());
+        assert!(interleaved.iter().all(|&s| s == 3));
+    }
+
+    #[test]
+    fn verify_buffer_operations() {
+        let spec = SignalSpec::new(48000, Channels::FRONT_LEFT | Channels::FRONT_RIGHT);
+
+        let mut a = AudioBuffer::<f32>::new(64, spec);
+        a.render_reserved(Some(16));
+        a.chan_mut(0).iter_mut().for_each(|s| *s = 0.5);
+        a.chan_mut(1).iter_mut().for_each(|s| *s = -0.5);
+
+        let mut b = AudioBuffer::<i16>::new(64, spec);
+        b.render_reserved(Some(16));
+        b.insert_channel(0, &[0.25f32; 16]);
+        b.insert_channel(1, &[0.25f32; 16]);
+
+        // Gain.
+        a.apply_gain_db(-6.0206);
+        assert!((a.chan(0)[0] - 0.25).abs() < 1e-5);
+        a.apply_channel_gain(1, 2.0);
+        assert!((a.chan(1)[0] + 0.5).abs() < 1e-5);
+
+        // Mixing a buffer of a different sample format.
+        let mut half = b.clone();
+        half.retain_frames(0..8);
+        a.mix(&half, 8, 1.0);
+        assert!((a.chan(0)[7] - 0.25).abs() < 1e-5);
+        assert!((a.chan(0)[8] - 0.5).abs() < 1e-5);
+        assert!((a.chan(1)[8] + 0.25).abs() < 1e-5);
+
+        // Fades.
+        let mut c = b.clone();
+        c.fade_in(4, FadeCurve::Linear);
+        assert_eq!(c.chan(0)[0], 0);
+        assert_eq!(c.chan(0)[2], 4096);
+        assert_eq!(c.chan(0)[4], 8192);
+        c.fade_out(4, FadeCurve::Linear);
+        assert_eq!(c.chan(0)[12], 8192);
+        assert_eq!(c.chan(0)[14], 4096);
+
+        // An equal-power crossfade has constant power.
+        for &t in &[0.0, 0.1, 0.5, 0.9, 1.0] {
+            let curve = FadeCurve::EqualPower;
+            assert!((curve.gain(t).powi(2) + curve.gain(1.0 - t).powi(2) - 1.0).abs() < 1e-12);
+        }
+
+        // A linear crossfade between identical signals is seamless.
+        let mut d = b.clone();
+        d.crossfade(&b, 8, FadeCurve::Linear);
+        assert_eq!(d.frames(), 24);
+        assert!(d.chan(0).iter().all(|&s| (i32::from(s) - 8192).abs() <= 1));
+
+        // Appending and retaining frame ranges.
+        let mut e = AudioBuffer::<f64>::new(64, spec);
+        e.append(&a);
+        e.append_frames(&b, 4..6);
+        assert_eq!(e.frames(), 18);
+        assert_eq!(e.chan(1)[17], 0.25);
+
+        e.retain_frames(7..10);
+        assert_eq!(e.frames(), 3);
+
+        let mut extracted = [0i16; 3];
+        assert_eq!(e.extract_channel(0, &mut extracted), 3);
+        assert_eq!(extracted, [8192, 16384, 16384]);
+    }
+}
@@ -12,8 +12,11 @@ use std::collections::HashMap;
 use std::default::Default;
 use std::fmt;
 
-use crate::audio::{AudioBufferRef, Channels, Layout};
-use crate::errors::{unsupported_error, Result};
+use crate::audio::SignalSpec;
+use crate::audio::{AudioBufferMut, AudioBufferRef, Channels, InterleavedBufferMut, Layout};
+use crate::conv::dither::Dynamic;
+use crate::dsp::mix::DownmixLevels;
+use crate::errors::{limit_error, unsupported_error, Result};
 use crate::formats::Packet;
 use crate::sample::SampleFormat;
 use crate::units::TimeBase;
@@ -178,6 +181,18 @@ pub const CODEC_TYPE_ADPCM_IMA_WAV: CodecType = CodecType(0x204);
 /// ADPCM IMA QuickTime
 pub const CODEC_TYPE_ADPCM_IMA_QT: CodecType = CodecType(0x205);
 
+// 1-bit Direct Stream Digital (DSD) audio codecs
+//-----------------------------------------------
+
+/// DSD least-significant bit first interleaved
+pub const CODEC_TYPE_DSD_LSBF: CodecType = CodecType(0x300);
+/// DSD least-significant bit first planar
+pub const CODEC_TYPE_DSD_LSBF_PLANAR: CodecType = CodecType(0x301);
+/// DSD most-significant bit first interleaved
+pub const CODEC_TYPE_DSD_MSBF: CodecType = CodecType(0x302);
+/// DSD most-significant bit first planar
+pub const CODEC_TYPE_DSD_MSBF_PLANAR: CodecType = CodecType(0x303);
+
 // Compressed lossy audio codecs
 //------------------------------
 
@@ -284,6 +299,10 @@ pub struct CodecParameters {
     /// The channel layout.
     pub channel_layout: Option<Layout>,
 
+    /// The downmix levels signalled by the stream, if provided by the decoder. See
+    /// `ChannelMixer::with_levels`.
+    pub downmix: Option<DownmixLevels>,
+
     /// The number of leading frames inserted by the encoder that should be skipped during playback.
     pub delay: Option<u32>,
 
@@ -317,6 +336,7 @@ impl CodecParameters {
             bits_per_coded_sample: None,
             channels: None,
             channel_layout: None,
+            downmix: None,
             delay: None,
             padding: None,
             max_frames_per_packet: None,
@@ -386,6 +406,12 @@ impl CodecParameters {
         self
     }
 
+    /// Provide the downmix levels.
+    pub fn with_downmix(&mut self, levels: DownmixLevels) -> &mut Self {
+        self.downmix = Some(levels);
+        self
+    }
+
     /// Provide the number of delay frames.
     pub fn with_delay(&mut self, delay: u32) -> &mut Self {
         self.delay = Some(delay);
@@ -443,6 +469,10 @@ pub struct FinalizeResult {
 pub struct DecoderOptions {
     /// The decoded audio should be verified if possible during the decode process.
     pub verify: bool,
+    /// Direct Stream Digital (DSD) audio should be packed into PCM frames using the DSD over PCM
+    /// (DoP) standard instead of being converted to PCM. Ignored by decoders of other codecs.
+    /// Default: `false`.
+    pub dsd_over_pcm: bool,
 }
 
 /// A `Decoder` implements a codec's decode algorithm. It consumes `Packet`s and produces
@@ -482,6 +512,86 @@ pub trait Decoder: Send + Sync {
     /// Implementors of decoders *must* `clear` the internal buffer if an error occurs.
     fn decode(&mut self, packet: &Packet) -> Result<AudioBufferRef>;
 
+    /// Decodes a `Packet` of audio data directly into the caller-owned audio buffer `dst`, and
+    /// returns the number of decoded frames. The contents of `dst` are replaced.
+    ///
+    /// The decoded audio is converted to the sample format of `dst` while it is copied out of the
+    /// decoder. If the conversion is lossy, `dither` is applied before the conversion. Since the
+    /// state of `dither` is preserved between packets, the same instance should be used for every
+    /// packet.
+    ///
+    /// The `SignalSpec` of `dst` must match the `SignalSpec` of the decoded audio, and the capacity
+    /// of `dst` must be sufficient to store the largest packet the decoder may produce. This may
+    /// be determined from the `max_frames_per_packet` codec parameter, or from the capacity of the
+    /// buffer returned by `last_decoded`. If `dst` is incompatible, an error is returned and `dst`
+    /// is not modified. Otherwise, errors should be handled identically to `decode`, and the
+    /// contents of `dst` should be discarded.
+    ///
+    /// `dst` is checked against the `SignalSpec` and capacity of the buffer returned by
+    /// `last_decoded` before the packet is decoded. If the decoder has not decoded a packet yet,
+    /// and that buffer is unused, the sample rate, channels, and maximum frames per packet codec
+    /// parameters are used instead, if known. If `dst` is rejected by this check, the packet is
+    /// not consumed, and may be decoded again with a compatible buffer. However, if the decoded
+    /// audio still does not fit `dst`, for example, because the `SignalSpec` changed, the packet
+    /// is consumed and lost.
+    ///
+    /// Decoders may override the default implementation to decode directly into `dst` if it uses
+    /// the native sample format of the decoder. In that case, the audio buffer returned by
+    /// `last_decoded` will be empty.
+    fn decode_into(
+        &mut self,
+        packet: &Packet,
+        dst: AudioBufferMut,
+        dither: &mut Dynamic,
+    ) -> Result<usize> {
+        let (spec, max_frames) = expected_output(self);
+
+        if spec.map_or(false, |spec| spec != *dst.spec()) {
+            return unsupported_error("codec: destination signal specification does not match");
+        }
+
+        if max_frames.map_or(false, |max_frames| dst.capacity() < max_frames) {
+            return limit_error("codec: destination buffer capacity is too small");
+        }
+
+        let decoded = self.decode(packet)?;
+        decoded.copy_to(dst, dither)?;
+        Ok(decoded.frames())
+    }
+
+    /// Decodes a `Packet` of audio data directly into the caller-owned slice `dst` in interleaved
+    /// channel order, and returns the number of decoded frames.
+    ///
+    /// The decoded audio is converted to the sample format of `dst` while it is copied out of the
+    /// decoder. If the conversion is lossy, `dither` is applied before the conversion. Since the
+    /// state of `dither` is preserved between packets, the same instance should be used for every
+    /// packet.
+    ///
+    /// The length of `dst` must be sufficient to store the largest packet the decoder may produce.
+    /// If an error is returned, the contents of `dst` are undefined. Errors should be handled
+    /// identically to `decode`.
+    ///
+    /// Like `decode_into`, the length of `dst` is checked before the packet is decoded, and the
+    /// packet is only consumed if `dst` passes the check.
+    ///
+    /// Like `decode_into`, decoders may override the default implementation, after which the audio
+    /// buffer returned by `last_decoded` will be empty.
+    fn decode_interleaved(
+        &mut self,
+        packet: &Packet,
+        dst: InterleavedBufferMut,
+        dither: &mut Dynamic,
+    ) -> Result<usize> {
+        if let (Some(spec), Some(max_frames)) = expected_output(self) {
+            if dst.len() < max_frames * spec.channels.count() {
+                return limit_error("codec: destination buffer is too small");
+            }
+        }
+
+        let decoded = self.decode(packet)?;
+        decoded.copy_to_interleaved(dst, dither)
+    }
+
     /// Optionally, obtain post-decode information such as the verification status.
     fn finalize(&mut self) -> FinalizeResult;
 
@@ -493,6 +603,25 @@ pub trait Decoder: Send + Sync {
     fn last_decoded(&self) -> AudioBufferRef;
 }
 
+/// Gets the `SignalSpec`, and the maximum number of frames per packet, of the audio `decoder` is
+/// expected to produce, if known.
+fn expected_output<D: Decoder + ?Sized>(decoder: &D) -> (Option<SignalSpec>, Option<usize>) {
+    let last = decoder.last_decoded();
+
+    if last.capacity() > 0 {
+        return (Some(*last.spec()), Some(last.capacity()));
+    }
+
+    let params = decoder.codec_params();
+
+    let spec = match (params.sample_rate, params.channels) {
+        (Some(rate), Some(channels)) => Some(SignalSpec::new(rate, channels)),
+        _ => None,
+    };
+
+    (spec, params.max_frames_per_packet.map(|max_frames| max_frames as usize))
+}
+
 /// A `CodecDescriptor` stores a description of a single logical codec. Common information such as
 /// the `CodecType`, a short name, and a long name are provided. The `CodecDescriptor` also provides
 /// an instantiation function. When the instantiation function is called, a `Decoder` for the codec
@@ -576,3 +705,98 @@ macro_rules! support_codec {
         }
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::{AsAudioBufferMut, AsAudioBufferRef, AudioBuffer, Signal};
+
+    /// A decoder that produces one frame of silence per packet, and counts the decoded packets.
+    struct CountingDecoder {
+        params: CodecParameters,
+        buf: AudioBuffer<f32>,
+        n_decoded: usize,
+    }
+
+    impl Decoder for CountingDecoder {
+        fn try_new(params: &CodecParameters, _options: &DecoderOptions) -> Result<Self> {
+            Ok(CountingDecoder { params: params.clone(), buf: AudioBuffer::unused(), n_decoded: 0 })
+        }
+
+        fn supported_codecs() -> &'static [CodecDescriptor] {
+            &[]
+        }
+
+        fn reset(&mut self) {}
+
+        fn codec_params(&self) -> &CodecParameters {
+            &self.params
+        }
+
+        fn decode(&mut self, _packet: &Packet) -> Result<AudioBufferRef<'_>> {
+            if self.buf.is_unused() {
+                let spec = SignalSpec::new(44100, Channels::FRONT_LEFT | Channels::FRONT_RIGHT);
+                self.buf = AudioBuffer::new(4, spec);
+            }
+
+            self.buf.clear();
+            self.buf.render_reserved(Some(1));
+            self.n_decoded += 1;
+
+            Ok(self.buf.as_audio_buffer_ref())
+        }
+
+        fn finalize(&mut self) -> FinalizeResult {
+            Default::default()
+        }
+
+        fn last_decoded(&self) -> AudioBufferRef<'_> {
+            self.buf.as_audio_buffer_ref()
+        }
+    }
+
+    #[test]
+    fn verify_decode_into_checks_dst_before_decoding() {
+        let stereo = SignalSpec::new(44100, Channels::FRONT_LEFT | Channels::FRONT_RIGHT);
+        let mono = SignalSpec::new(44100, Channels::FRONT_LEFT);
+
+        let mut params = CodecParameters::new();
+        params.with_sample_rate(44100).with_channels(stereo.channels).with_max_frames_per_packet(4);
+
+        let mut decoder = CountingDecoder::try_new(&params, &Default::default()).unwrap();
+        let mut dither = Dynamic::default();
+        let packet = Packet::new_from_slice(0, 0, 1, &[]);
+
+        // Before the first packet, the codec parameters are used. Incompatible buffers are
+        // rejected without consuming the packet.
+        let mut small = AudioBuffer::<i16>::new(3, stereo);
+        let mut wrong_spec = AudioBuffer::<i16>::new(4, mono);
+        let mut short = [0i16; 7];
+
+        assert!(decoder.decode_into(&packet, small.as_audio_buffer_mut(), &mut dither).is_err());
+        assert!(decoder
+            .decode_into(&packet, wrong_spec.as_audio_buffer_mut(), &mut dither)
+            .is_err());
+        assert!(decoder.decode_interleaved(&packet, (&mut short[..]).into(), &mut dither).is_err());
+        assert_eq!(decoder.n_decoded, 0);
+
+        let mut dst = AudioBuffer::<i16>::new(4, stereo);
+        assert_eq!(
+            decoder.decode_into(&packet, dst.as_audio_buffer_mut(), &mut dither).unwrap(),
+            1
+        );
+        assert_eq!(decoder.n_decoded, 1);
+
+        // Afterwards, the last decoded buffer is used.
+        assert!(decoder.decode_into(&packet, small.as_audio_buffer_mut(), &mut dither).is_err());
+        assert!(decoder.decode_interleaved(&packet, (&mut short[..]).into(), &mut dither).is_err());
+        assert_eq!(decoder.n_decoded, 1);
+
+        let mut interleaved = [0i16; 8];
+        let n_frames = decoder
+            .decode_interleaved(&packet, (&mut interleaved[..]).into(), &mut dither)
+            .unwrap();
+        assert_eq!(n_frames, 1);
+        assert_eq!(decoder.n_decoded, 2);
+    }
+}
@@ -19,6 +19,7 @@ pub mod dither {
     //! Dithering is only applied on lossy conversions. Therefore the `dither` module will only
     //! apply a dither to the following down-conversions:
     //!
+    //! * { `f64`, `f32` } to { `i32`, `u32`, `i24`, `u24`, `i16`, `u16`, `i8`, `u8` }
     //! * { `i32`, `u32` } to { `i24`, `u24`, `i16`, `u16`, `i8`, `u8` }
     //! * { `i24`, `u24` } to { `i16`, `u16`, `i8`, `u8` }
     //! * { `i16`, `u16` } to { `i8`, `u8` }
@@ -26,9 +27,12 @@ pub mod dither {
     //! Multiple dithering algorithms are provided, each drawing noise from a different probability
     //! distribution. In addition to different distributions, a dithering algorithm may also shape
     //! the noise such that the bulk of the noise is placed in an inaudible frequency range.
+    //!
+    //! All noise is generated by a seeded pseudo-random number generator. Therefore, a conversion
+    //! using the same dithering algorithm and seed always produces the same output.
     use super::FromSample;
-    use crate::sample::Sample;
     use crate::sample::{i24, u24};
+    use crate::sample::{Sample, SampleFormat};
     use std::marker::PhantomData;
 
     mod prng {
@@ -104,7 +108,7 @@ pub mod dither {
     impl RandomNoise {
         /// Instantiate a noise sample from a random 32-bit source.
         pub fn from(random: i32, n_bits: u32) -> Self {
-            RandomNoise(random >> (32 - n_bits))
+            RandomNoise(random.checked_shr(32 - n_bits).unwrap_or(0))
         }
     }
 
@@ -189,8 +193,13 @@ pub mod dither {
 
     impl<F: Sample, T: Sample> Rectangular<F, T> {
         pub fn new() -> Self {
+            Self::with_seed(DEFAULT_SEED)
+        }
+
+        /// Instantiate the dither with a seed for the noise generator.
+        pub fn with_seed(seed: u64) -> Self {
             Rectangular {
-                prng: prng::Xoshiro128pp::new(0xb2c1_01f4_425b_987e),
+                prng: prng::Xoshiro128pp::new(seed),
                 from_type: PhantomData,
                 to_type: PhantomData,
             }
@@ -202,9 +211,8 @@ pub mod dither {
         RandomNoise: AddNoise<F>,
     {
         fn dither(&mut self, sample: F) -> F {
-            // A dither should be applied if and only if the effective number of bits of the source
-            // sample format is greater than that of the destination sample format.
-            debug_assert!(F::EFF_BITS > T::EFF_BITS);
+            // A dither should be applied if and only if the conversion is lossy.
+            debug_assert!(is_lossy::<F, T>());
 
             // The number of low-order bits being truncated by the conversion will be dithered.
             let dither_bits = 32 - T::EFF_BITS;
@@ -222,20 +230,37 @@ pub mod dither {
     }
 
     /// `Triangular` implements a dither using a triangular distribution of noise without shaping.
+    ///
+    /// By default, the noise spans 1 step of the destination sample format. A dither instantiated
+    /// with `with_full_amplitude` spans 2 steps, which fully decorrelates the power of the
+    /// quantization error from the source signal.
     pub struct Triangular<F: Sample, T: Sample> {
         prng: prng::Xoshiro128pp,
+        extra_bits: u32,
         from_type: PhantomData<F>,
         to_type: PhantomData<T>,
     }
 
     impl<F: Sample, T: Sample> Triangular<F, T> {
         pub fn new() -> Self {
+            Self::with_seed(DEFAULT_SEED)
+        }
+
+        /// Instantiate the dither with a seed for the noise generator.
+        pub fn with_seed(seed: u64) -> Self {
             Triangular {
-                prng: prng::Xoshiro128pp::new(0xb2c1_01f4_425b_987e),
+                prng: prng::Xoshiro128pp::new(seed),
+                extra_bits: 0,
                 from_type: PhantomData,
                 to_type: PhantomData,
             }
         }
+
+        /// Instantiate the dither with a seed for the noise generator, and noise spanning 2 steps
+        /// of the destination sample format.
+        pub fn with_full_amplitude(seed: u64) -> Self {
+            Triangular { extra_bits: 1, ..Self::with_seed(seed) }
+        }
     }
 
     impl<F: Sample, T: Sample> Dither<F, T> for Triangular<F, T>
@@ -243,7 +268,7 @@ pub mod dither {
         RandomNoise: AddNoise<F>,
     {
         fn dither(&mut self, sample: F) -> F {
-            debug_assert!(F::EFF_BITS > T::EFF_BITS);
+            debug_assert!(is_lossy::<F, T>());
 
             let dither_bits = 32 - T::EFF_BITS;
 
@@ -251,7 +276,7 @@ pub mod dither {
             let tpdf = (self.prng.next() as i32 >> 1) + (self.prng.next() as i32 >> 1);
 
             // Add the noise to the sample.
-            let noise = RandomNoise::from(tpdf, dither_bits);
+            let noise = RandomNoise::from(tpdf, dither_bits + self.extra_bits);
             noise.add_noise(sample)
         }
     }
@@ -263,6 +288,7 @@ pub mod dither {
     }
 
     /// Enumeration of dither algorithms.
+    #[derive(Copy, Clone, Debug, PartialEq, Eq)]
     pub enum DitherType {
         /// No dithering.
         Identity,
@@ -270,6 +296,212 @@ pub mod dither {
         Rectangular,
         /// Apply triangular dithering. See `Triangular` for more details.
         Triangular,
+        /// Apply triangular dithering with noise spanning 2 steps of the destination sample
+        /// format. See `Triangular::with_full_amplitude` for more details.
+        TriangularFullAmplitude,
+        /// Apply triangular dithering with a high-pass spectrum. The noise is the difference of
+        /// successive uniformly distributed random values. It has the same power as a full
+        /// amplitude triangular dither, but the bulk of the noise is placed in the upper
+        /// frequencies.
+        HighPassTriangular,
+        /// Apply full amplitude triangular dithering, and shape the quantization noise using the
+        /// 5-tap Lipshitz error feedback filter. See `Dynamic::set_sample_rate` for the supported
+        /// sample rates.
+        Lipshitz,
+        /// Apply full amplitude triangular dithering, and shape the quantization noise using the
+        /// 9-tap F-weighted error feedback filter. The noise is shaped to follow the inverse of
+        /// the F-weighted equal-loudness curve. See `Dynamic::set_sample_rate` for the supported
+        /// sample rates.
+        FWeighted,
+    }
+
+    impl Default for DitherType {
+        fn default() -> Self {
+            DitherType::Identity
+        }
+    }
+
+    /// The default seed of the noise generator.
+    const DEFAULT_SEED: u64 = 0xb2c1_01f4_425b_987e;
+
+    /// The coefficients of the Lipshitz noise shaping filter, designed for 44.1 kHz.
+    const LIPSHITZ: [f64; 5] = [2.033, -2.165, 1.959, -1.590, 0.6149];
+
+    /// The coefficients of Wannamaker's F-weighted noise shaping filter, designed for 44.1 kHz.
+    const F_WEIGHTED: [f64; 9] =
+        [2.412, -3.370, 3.937, -4.174, 3.353, -2.205, 1.281, -0.569, 0.0847];
+
+    /// The range of sample rates the noise shaping filters may be used at. The filters place the
+    /// noise at fixed fractions of the sample rate. Outside of this range, the noise would be
+    /// moved into the most audible frequencies, or out of the audible range entirely.
+    const NOISE_SHAPING_RATES: std::ops::RangeInclusive<u32> = 44_100..=48_000;
+
+    /// Returns `true` if a conversion from sample format `F` to `T` loses precision.
+    fn is_lossy<F: Sample, T: Sample>() -> bool {
+        !is_float::<T>() && (is_float::<F>() || F::EFF_BITS > T::EFF_BITS)
+    }
+
+    /// Returns `true` if `S` is a floating-point sample format.
+    fn is_float<S: Sample>() -> bool {
+        matches!(S::FORMAT, SampleFormat::F32 | SampleFormat::F64)
+    }
+
+    /// The dither state of a single channel.
+    #[derive(Clone, Default)]
+    struct ChannelState {
+        /// The previous uniformly distributed random value.
+        prev: f64,
+        /// The quantization errors of the previous samples, most recent first.
+        errors: [f64; F_WEIGHTED.len()],
+    }
+
+    /// `Dynamic` applies the dithering algorithm selected at runtime by a `DitherType`.
+    ///
+    /// Unlike the other dithering algorithms, `Dynamic` is not bound to a source and destination
+    /// sample format. Therefore, a single instance may be used for a conversion chosen at runtime,
+    /// and the state of the noise generator is preserved across successive buffers. A dither is
+    /// only applied if the conversion is lossy.
+    ///
+    /// The high-pass triangular and noise shaped dithers filter the noise of each channel
+    /// separately. Before dithering the samples of a channel, the channel must be selected with
+    /// `set_channel`. The noise shaping filters are only valid for certain sample rates, and are
+    /// only applied if the sample rate set with `set_sample_rate` is supported. The conversions
+    /// provided by Symphonia select the channel and set the sample rate automatically.
+    pub struct Dynamic {
+        dither_type: DitherType,
+        prng: prng::Xoshiro128pp,
+        channels: Vec<ChannelState>,
+        channel: usize,
+        sample_rate: Option<u32>,
+    }
+
+    impl Dynamic {
+        pub fn new(dither_type: DitherType) -> Self {
+            Self::with_seed(dither_type, DEFAULT_SEED)
+        }
+
+        /// Instantiate a `Dynamic` dither with a seed for the noise generator.
+        pub fn with_seed(dither_type: DitherType, seed: u64) -> Self {
+            Dynamic {
+                dither_type,
+                prng: prng::Xoshiro128pp::new(seed),
+                channels: vec![Default::default()],
+                channel: 0,
+                sample_rate: None,
+            }
+        }
+
+        /// Gets the dithering algorithm.
+        pub fn dither_type(&self) -> DitherType {
+            self.dither_type
+        }
+
+        /// Selects the channel of the samples that will be dithered next.
+        pub fn set_channel(&mut self, channel: usize) {
+            if channel >= self.channels.len() {
+                self.channels.resize(channel + 1, Default::default());
+            }
+
+            self.channel = channel;
+        }
+
+        /// Sets the sample rate of the samples that will be dithered.
+        ///
+        /// The noise shaping filters of the `Lipshitz` and `FWeighted` dithers are designed for a
+        /// sample rate of 44.1 kHz, and are also applied at sample rates up to 48 kHz. At any other
+        /// sample rate, these dithers fall back to a full amplitude triangular dither without noise
+        /// shaping. If the sample rate is never set, it is assumed to be supported.
+        pub fn set_sample_rate(&mut self, sample_rate: u32) {
+            if self.sample_rate != Some(sample_rate) {
+                // The quantization errors of another sample rate are not meaningful.
+                self.channels.iter_mut().for_each(|state| state.errors = Default::default());
+                self.sample_rate = Some(sample_rate);
+            }
+        }
+
+        /// Returns `true` if the noise shaping filter can be applied at the current sample rate.
+        fn is_noise_shaping_supported(&self) -> bool {
+            self.sample_rate.map_or(true, |rate| NOISE_SHAPING_RATES.contains(&rate))
+        }
+
+        /// Gets a uniformly distributed random value in the range [0, 1).
+        #[inline]
+        fn uniform(&mut self) -> f64 {
+            f64::from(self.prng.next()) / 4_294_967_296.0
+        }
+    }
+
+    impl<F: Sample, T: Sample> Dither<F, T> for Dynamic
+    where
+        F: FromSample<f64>,
+        f64: FromSample<F>,
+    {
+        fn dither(&mut self, sample: F) -> F {
+            if self.dither_type == DitherType::Identity || !is_lossy::<F, T>() {
+                return sample;
+            }
+
+            // The quantization step of the destination sample format, relative to full-scale.
+            let step = 2f64.powi(1 - T::EFF_BITS as i32);
+
+            let noise = match self.dither_type {
+                DitherType::Rectangular => self.uniform() - 0.5,
+                DitherType::Triangular => 0.5 * (self.uniform() - self.uniform()),
+                DitherType::HighPassTriangular => {
+                    let random = self.uniform();
+                    let state = &mut self.channels[self.channel];
+                    let noise = random - state.prev;
+                    state.prev = random;
+                    noise
+                }
+                _ => self.uniform() - self.uniform(),
+            };
+
+            let coefficients: &[f64] = match self.dither_type {
+                DitherType::Lipshitz if self.is_noise_shaping_supported() => &LIPSHITZ,
+                DitherType::FWeighted if self.is_noise_shaping_supported() => &F_WEIGHTED,
+                _ => &[],
+            };
+
+            let state = &mut self.channels[self.channel];
+
+            // Subtract the filtered quantization error of the previous samples.
+            let feedback: f64 = coefficients.iter().zip(&state.errors).map(|(c, e)| c * e).sum();
+            let shaped = f64::from_sample(sample) - feedback;
+
+            // Conversions from floating-point samples round to the nearest step, while conversions
+            // between integer samples truncate. Offset the latter by half a step so both round.
+            let offset = if is_float::<F>() { 0.0 } else { 0.5 * step };
+
+            let dithered = shaped + noise * step + offset;
+
+            if !coefficients.is_empty() {
+                // Quantize in the same manner as the conversion to find the quantization error.
+                let level = if is_float::<F>() {
+                    (dithered / step).round()
+                }
+                else {
+                    (dithered / step).floor()
+                };
+
+                let quantized = (level * step).clamp(-1.0, 1.0 - step);
+
+                // Limit the error such that clipping does not destabilize the filter.
+                let error = (quantized - shaped).clamp(-1.5 * step, 1.5 * step);
+
+                let n_errors = state.errors.len();
+                state.errors.copy_within(..n_errors - 1, 1);
+                state.errors[0] = error;
+            }
+
+            F::from_sample(dithered)
+        }
+    }
+
+    impl Default for Dynamic {
+        fn default() -> Self {
+            Self::new(DitherType::Identity)
+        }
     }
 
     /// `MaybeDither` conditionally applies a dither to a sample depending on the source and
@@ -315,8 +547,8 @@ pub mod dither {
     dither_maybe!(u8, i16);
     dither_maybe!(u8, i24);
     dither_maybe!(u8, i32);
-    dither_never!(u8, f32);
-    dither_never!(u8, f64);
+    dither_maybe!(u8, f32);
+    dither_maybe!(u8, f64);
 
     // Dither table for conversions to u16
     dither_never!(u16, u8);
@@ -327,8 +559,8 @@ pub mod dither {
     dither_never!(u16, i16);
     dither_maybe!(u16, i24);
     dither_maybe!(u16, i32);
-    dither_never!(u16, f32);
-    dither_never!(u16, f64);
+    dither_maybe!(u16, f32);
+    dither_maybe!(u16, f64);
 
     // Dither table for conversions to u24
     dither_never!(u24, u8);
@@ -339,8 +571,8 @@ pub mod dither {
     dither_never!(u24, i16);
     dither_never!(u24, i24);
     dither_maybe!(u24, i32);
-    dither_never!(u24, f32);
-    dither_never!(u24, f64);
+    dither_maybe!(u24, f32);
+    dither_maybe!(u24, f64);
 
     // Dither table for conversions to u32
     dither_never!(u32, u8);
@@ -351,8 +583,8 @@ pub mod dither {
     dither_never!(u32, i16);
     dither_never!(u32, i24);
     dither_never!(u32, i32);
-    dither_never!(u32, f32);
-    dither_never!(u32, f64);
+    dither_maybe!(u32, f32);
+    dither_maybe!(u32, f64);
 
     // Dither table for conversions to i8
     dither_never!(i8, u8);
@@ -363,8 +595,8 @@ pub mod dither {
     dither_maybe!(i8, i16);
     dither_maybe!(i8, i24);
     dither_maybe!(i8, i32);
-    dither_never!(i8, f32);
-    dither_never!(i8, f64);
+    dither_maybe!(i8, f32);
+    dither_maybe!(i8, f64);
 
     // Dither table for conversions to i16
     dither_never!(i16, u8);
@@ -375,8 +607,8 @@ pub mod dither {
     dither_never!(i16, i16);
     dither_maybe!(i16, i24);
     dither_maybe!(i16, i32);
-    dither_never!(i16, f32);
-    dither_never!(i16, f64);
+    dither_maybe!(i16, f32);
+    dither_maybe!(i16, f64);
 
     // Dither table for conversions to i24
     dither_never!(i24, u8);
@@ -387,8 +619,8 @@ pub mod dither {
     dither_never!(i24, i16);
     dither_never!(i24, i24);
     dither_maybe!(i24, i32);
-    dither_never!(i24, f32);
-    dither_never!(i24, f64);
+    dither_maybe!(i24, f32);
+    dither_maybe!(i24, f64);
 
     // Dither table for conversions to i32
     dither_never!(i32, u8);
@@ -399,8 +631,8 @@ pub mod dither {
     dither_never!(i32, i16);
     dither_never!(i32, i24);
     dither_never!(i32, i32);
-    dither_never!(i32, f32);
-    dither_never!(i32, f64);
+    dither_maybe!(i32, f32);
+    dither_maybe!(i32, f64);
 
     // Dither table for conversions to f32
     dither_never!(f32, u8);
@@ -430,9 +662,20 @@ pub mod dither {
 /// `FromSample` implements a conversion from `Sample` type `F` to `Self`.
 ///
 /// This may be a lossy conversion if converting from a sample type of higher precision to one of
-/// lower precision. No dithering is applied.
+/// lower precision. No dithering is applied by `from_sample`, use `from_sample_dithered` instead.
 pub trait FromSample<F> {
     fn from_sample(val: F) -> Self;
+
+    /// Converts `val`, applying `dither` before the conversion if it is lossy.
+    ///
+    /// The default implementation does not apply a dither. All conversions between the built-in
+    /// sample types implement dithering.
+    fn from_sample_dithered(val: F, _dither: &mut dither::Dynamic) -> Self
+    where
+        Self: Sized,
+    {
+        Self::from_sample(val)
+    }
 }
 
 macro_rules! converter {
@@ -442,6 +685,11 @@ macro_rules! converter {
             fn from_sample($sample: $from) -> Self {
                 $func
             }
+
+            #[inline]
+            fn from_sample_dithered(val: $from, dither: &mut dither::Dynamic) -> Self {
+                Self::from_sample(dither::Dither::<$from, $to>::dither(dither, val))
+            }
         }
     };
 }
@@ -593,9 +841,19 @@ converter!(f64, f64, s, s);
 /// `IntoSample` implements a conversion from `Self` to `Sample` type `T`.
 ///
 /// This may be a lossy conversion if converting from a sample type of higher precision to one of
-/// lower precision. No dithering is applied.
+/// lower precision. No dithering is applied by `into_sample`, use `into_sample_dithered` instead.
 pub trait IntoSample<T> {
     fn into_sample(self) -> T;
+
+    /// Converts `self`, applying `dither` before the conversion if it is lossy.
+    ///
+    /// The default implementation does not apply a dither.
+    fn into_sample_dithered(self, _dither: &mut dither::Dynamic) -> T
+    where
+        Self: Sized,
+    {
+        self.into_sample()
+    }
 }
 
 impl<F, T: FromSample<F>> IntoSample<T> for F {
@@ -603,6 +861,11 @@ impl<F, T: FromSample<F>> IntoSample<T> for F {
     fn into_sample(self) -> T {
         T::from_sample(self)
     }
+
+    #[inline]
+    fn into_sample_dithered(self, dither: &mut dither::Dynamic) -> T {
+        T::from_sample_dithered(self, dither)
+    }
 }
 
 /// `ReversibleSample` is a trait that when implemented for `Self`, that `Sample` type implements
@@ -642,6 +905,7 @@ impl<S> ConvertibleSample for S where
 
 #[cfg(test)]
 mod tests {
+    use super::dither::{Dither, DitherType, Dynamic, Triangular};
     use super::FromSample;
     use crate::sample::{i24, u24, Sample};
     use std::{i16, i32, i8, u16, u32, u8};
@@ -1075,4 +1339,249 @@ mod tests {
         assert_eq!(f32::from_sample(0f64), 0.0);
         assert_eq!(f32::from_sample(-1.0f64), -1.0);
     }
+
+    /// Gets the sum of the energy of the DFT bins in `bins` of `x`.
+    fn band_energy(x: &[f64], bins: std::ops::Range<usize>) -> f64 {
+        let n = x.len() as f64;
+
+        bins.map(|k| {
+            let w = 2.0 * std::f64::consts::PI * k as f64 / n;
+            let (re, im) = x.iter().enumerate().fold((0.0, 0.0), |(re, im), (i, e)| {
+                (re + e * (w * i as f64).cos(), im + e * (w * i as f64).sin())
+            });
+            re * re + im * im
+        })
+        .sum()
+    }
+
+    /// Gets the mean and variance of `x`.
+    fn mean_and_variance(x: &[f64]) -> (f64, f64) {
+        let n = x.len() as f64;
+        let mean = x.iter().sum::<f64>() / n;
+        let variance = x.iter().map(|v| (v - mean) * (v - mean)).sum::<f64>() / n;
+        (mean, variance)
+    }
+
+    /// Dithers `n` samples of the constant level `x` for a conversion to `i16`, and returns the
+    /// noise added to each sample, and the error after quantization, in steps of `i16`.
+    fn dither_constant(dither: &mut Dynamic, x: f64, n: usize) -> (Vec<f64>, Vec<f64>) {
+        let lsb = 1.0 / 32768.0;
+
+        (0..n)
+            .map(|_| {
+                let dithered = Dither::<f64, i16>::dither(dither, x * lsb);
+                let quantized = f64::from(i16::from_sample(dithered));
+                (dithered / lsb - x, quantized - x)
+            })
+            .unzip()
+    }
+
+    /// Converts `src` to `i16` samples using the given dither.
+    fn dither_to_i16(src: &[f64], dither_type: DitherType, seed: u64) -> Vec<i16> {
+        let mut dither = Dynamic::with_seed(dither_type, seed);
+        src.iter().map(|&s| i16::from_sample(Dither::<f64, i16>::dither(&mut dither, s))).collect()
+    }
+
+    #[test]
+    fn verify_dither() {
+        // A quiet, slowly varying signal that would otherwise be heavily truncated.
+        let src: Vec<f64> = (0..8192).map(|i| 3.3 / 32768.0 * (i as f64 * 0.01).sin()).collect();
+
+        let lsb = 1.0 / 32768.0;
+
+        // The energy of the error below approximately 2 kHz at a sample rate of 44.1 kHz.
+        let low_freq_error = |dst: &[i16]| -> f64 {
+            let err: Vec<f64> =
+                dst.iter().zip(&src).map(|(&d, &s)| f64::from(d) * lsb - s).collect();
+
+            band_energy(&err, 1..err.len() / 22)
+        };
+
+        let types = [
+            DitherType::Rectangular,
+            DitherType::Triangular,
+            DitherType::TriangularFullAmplitude,
+            DitherType::HighPassTriangular,
+            DitherType::Lipshitz,
+            DitherType::FWeighted,
+        ];
+
+        for &dither_type in types.iter() {
+            let a = dither_to_i16(&src, dither_type, 1);
+            let b = dither_to_i16(&src, dither_type, 1);
+            let c = dither_to_i16(&src, dither_type, 2);
+
+            // Dither is deterministic for a given seed.
+            assert_eq!(a, b);
+            assert_ne!(a, c);
+
+            // The error is bounded.
+            for (&d, &s) in a.iter().zip(&src) {
+                assert!((f64::from(d) * lsb - s).abs() <= 24.0 * lsb);
+            }
+        }
+
+        // Noise shaping moves the error out of low frequencies.
+        let tpdf = low_freq_error(&dither_to_i16(&src, DitherType::Triangular, 1));
+
+        for &dither_type in [DitherType::Lipshitz, DitherType::FWeighted].iter() {
+            assert!(low_freq_error(&dither_to_i16(&src, dither_type, 1)) < 0.25 * tpdf);
+        }
+
+        // Lossless conversions are never dithered.
+        let mut dither = Dynamic::new(DitherType::FWeighted);
+        assert_eq!(Dither::<i16, i32>::dither(&mut dither, 1234), 1234);
+        assert_eq!(Dither::<f32, f64>::dither(&mut dither, 0.25), 0.25);
+    }
+
+    #[test]
+    fn verify_rectangular_dither() {
+        let mut dither = Dynamic::new(DitherType::Rectangular);
+
+        // The noise is uniformly distributed over 1 step.
+        let (noise, _) = dither_constant(&mut dither, 0.0, 1 << 16);
+        let (mean, variance) = mean_and_variance(&noise);
+
+        assert!(noise.iter().all(|n| n.abs() <= 0.5));
+        assert!(mean.abs() < 0.01);
+        assert!((variance - 1.0 / 12.0).abs() < 0.005);
+
+        // The quantized signal is unbiased, but the power of the quantization error depends on the
+        // signal.
+        for &x in [0.0, 0.25, 0.5, 0.75].iter() {
+            let (_, error) = dither_constant(&mut dither, 100.0 + x, 1 << 16);
+            let (mean, variance) = mean_and_variance(&error);
+
+            assert!(mean.abs() < 0.01);
+            assert!((variance - x * (1.0 - x)).abs() < 0.01);
+        }
+    }
+
+    #[test]
+    fn verify_triangular_dither() {
+        // The noise has a triangular distribution spanning 1 step.
+        let mut dither = Dynamic::new(DitherType::Triangular);
+
+        let (noise, _) = dither_constant(&mut dither, 0.0, 1 << 16);
+        let (mean, variance) = mean_and_variance(&noise);
+
+        assert!(noise.iter().all(|n| n.abs() <= 0.5));
+        assert!(mean.abs() < 0.01);
+        assert!((variance - 1.0 / 24.0).abs() < 0.003);
+
+        // At full amplitude, the noise spans 2 steps.
+        let mut dither = Dynamic::new(DitherType::TriangularFullAmplitude);
+
+        let (noise, _) = dither_constant(&mut dither, 0.0, 1 << 16);
+        let (mean, variance) = mean_and_variance(&noise);
+
+        assert!(noise.iter().all(|n| n.abs() <= 1.0));
+        assert!(mean.abs() < 0.01);
+        assert!((variance - 1.0 / 6.0).abs() < 0.01);
+
+        // The quantized signal is unbiased, and the power of the quantization error is independent
+        // of the signal.
+        for &x in [0.0, 0.25, 0.5, 0.75].iter() {
+            let (_, error) = dither_constant(&mut dither, 100.0 + x, 1 << 16);
+            let (mean, variance) = mean_and_variance(&error);
+
+            assert!(mean.abs() < 0.01);
+            assert!((variance - 0.25).abs() < 0.01);
+        }
+
+        // The noise is white.
+        let (noise, _) = dither_constant(&mut dither, 0.0, 4096);
+        let low = band_energy(&noise, 1..512);
+        let high = band_energy(&noise, 1536..2048);
+
+        assert!(low > 0.75 * high && low < 1.25 * high);
+    }
+
+    #[test]
+    fn verify_triangular_dither_amplitude() {
+        let lsb = 1.0 / 32768.0;
+
+        let mut dither = Triangular::<f64, i16>::new();
+        let noise: Vec<f64> = (0..1 << 16).map(|_| dither.dither(0.0) / lsb).collect();
+
+        assert!(noise.iter().all(|n| n.abs() <= 0.5));
+        assert!(noise.iter().any(|n| n.abs() > 0.4));
+
+        let mut dither = Triangular::<f64, i16>::with_full_amplitude(1);
+        let noise: Vec<f64> = (0..1 << 16).map(|_| dither.dither(0.0) / lsb).collect();
+
+        assert!(noise.iter().all(|n| n.abs() <= 1.0));
+        assert!(noise.iter().any(|n| n.abs() > 0.8));
+    }
+
+    #[test]
+    fn verify_high_pass_triangular_dither() {
+        let mut dither = Dynamic::new(DitherType::HighPassTriangular);
+
+        // The noise has the same distribution as a full amplitude triangular dither.
+        let (noise, _) = dither_constant(&mut dither, 0.0, 1 << 16);
+        let (mean, variance) = mean_and_variance(&noise);
+
+        assert!(noise.iter().all(|n| n.abs() <= 1.0));
+        assert!(mean.abs() < 0.01);
+        assert!((variance - 1.0 / 6.0).abs() < 0.01);
+
+        // Successive noise samples are negatively correlated.
+        let lag1 = noise.windows(2).map(|w| w[0] * w[1]).sum::<f64>() / noise.len() as f64;
+        assert!((lag1 / variance + 0.5).abs() < 0.02);
+
+        // The bulk of the noise is in the upper frequencies. The noise has a spectrum proportional
+        // to 1 - cos(w), therefore the lowest quarter of the band has approximately 1/19th of the
+        // energy of the highest quarter.
+        let (noise, _) = dither_constant(&mut dither, 0.0, 4096);
+        let low = band_energy(&noise, 1..512);
+        let high = band_energy(&noise, 1536..2048);
+
+        assert!(low < 0.1 * high);
+    }
+
+    #[test]
+    fn verify_noise_shaped_dither_sample_rate() {
+        let src: Vec<f64> = (0..4096).map(|i| 3.3 / 32768.0 * (i as f64 * 0.01).sin()).collect();
+
+        let convert = |dither: &mut Dynamic| -> Vec<i16> {
+            src.iter().map(|&s| i16::from_sample(Dither::<f64, i16>::dither(dither, s))).collect()
+        };
+
+        let mut tpdf = Dynamic::new(DitherType::TriangularFullAmplitude);
+        let tpdf = convert(&mut tpdf);
+
+        for &dither_type in [DitherType::Lipshitz, DitherType::FWeighted].iter() {
+            // The noise is shaped at supported sample rates.
+            for &rate in [44_100, 48_000].iter() {
+                let mut dither = Dynamic::new(dither_type);
+                dither.set_sample_rate(rate);
+                assert_ne!(convert(&mut dither), tpdf);
+            }
+
+            // Otherwise, the dither falls back to a triangular dither without noise shaping.
+            for &rate in [8_000, 22_050, 96_000, 192_000].iter() {
+                let mut dither = Dynamic::new(dither_type);
+                dither.set_sample_rate(rate);
+                assert_eq!(convert(&mut dither), tpdf);
+            }
+        }
+    }
+
+    #[test]
+    fn verify_from_sample_dithered() {
+        let lsb = 1.0 / 32768.0;
+
+        // Lossy conversions are dithered.
+        let mut dither = Dynamic::new(DitherType::TriangularFullAmplitude);
+        let dithered: Vec<i16> =
+            (0..1024).map(|_| i16::from_sample_dithered(0.25 * lsb, &mut dither)).collect();
+
+        assert!(dithered.iter().all(|&s| (-1..=1).contains(&s)));
+        assert!(dithered.iter().any(|&s| s != 0));
+
+        // Lossless conversions are not.
+        assert_eq!(i32::from_sample_dithered(1234i16, &mut dither), i32::from_sample(1234i16));
+        assert_eq!(f32::from_sample_dithered(0.25f64, &mut dither), 0.25);
+    }
 }
@@ -0,0 +1,826 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! The `analysis` module implements the detection of common defects in decoded audio.
+//!
+//! An [`Analyzer`] detects leading and trailing silence, runs of clipped samples, DC offset,
+//! phase-inverted stereo, and dropouts in a stream of audio. All positions are reported as
+//! timestamps in a given time base, usually that of the track being analyzed.
+
+use crate::audio::{AudioBuffer, AudioBufferRef, ChannelLabel, Channels, Signal, SignalSpec};
+use crate::conv::IntoSample;
+use crate::sample::Sample;
+use crate::units::{Duration, Time, TimeBase, TimeStamp};
+
+/// Converts a level in dBFS to a linear amplitude.
+fn db_to_amplitude(db: f64) -> f64 {
+    10f64.powf(db / 20.0)
+}
+
+/// `AnalyzerOptions` is a common set of options that an `Analyzer` uses to detect defects.
+#[derive(Copy, Clone, Debug)]
+pub struct AnalyzerOptions {
+    /// The level, in dBFS, below which every channel of a frame must be for the frame to be silent.
+    /// The default is -60 dBFS.
+    pub silence_threshold: f64,
+    /// The minimum duration of leading or trailing silence that is reported. The default is 100 ms.
+    pub min_silence: Time,
+    /// The minimum number of consecutive clipped samples in a channel that is reported as a run of
+    /// clipping. The default is 3 samples.
+    pub min_clip_run: usize,
+    /// The absolute DC offset, in dBFS, above which a channel is reported to have a DC offset. The
+    /// default is -50 dBFS.
+    pub dc_offset_threshold: f64,
+    /// The correlation between the left and right channels below which the stereo image is
+    /// reported to be phase-inverted. The default is -0.5.
+    pub phase_inversion_threshold: f64,
+    /// The minimum duration of digital silence, between non-silent audio, that is reported as a
+    /// dropout. The default is 1 ms.
+    pub min_dropout: Time,
+}
+
+impl Default for AnalyzerOptions {
+    fn default() -> Self {
+        AnalyzerOptions {
+            silence_threshold: -60.0,
+            min_silence: Time::new(0, 0.1),
+            min_clip_run: 3,
+            dc_offset_threshold: -50.0,
+            phase_inversion_threshold: -0.5,
+            min_dropout: Time::new(0, 0.001),
+        }
+    }
+}
+
+/// A `Region` is a span of audio.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Region {
+    /// The timestamp of the first frame of the region.
+    pub ts: TimeStamp,
+    /// The duration of the region.
+    pub dur: Duration,
+}
+
+/// The analysis of a single channel.
+#[derive(Clone, Debug, Default)]
+pub struct ChannelAnalysis {
+    /// The maximum absolute sample value, where 1.0 is full scale.
+    pub peak: f64,
+    /// The total number of clipped samples, including those not part of a reported run.
+    pub clipped_samples: u64,
+    /// The runs of consecutive clipped samples.
+    pub clip_runs: Vec<Region>,
+    /// The mean sample value.
+    pub dc_offset: f64,
+    /// If `true`, the DC offset exceeds the threshold.
+    pub has_dc_offset: bool,
+}
+
+/// `Analysis` is the result of analyzing a stream of audio with an `Analyzer`.
+#[derive(Clone, Debug, Default)]
+pub struct Analysis {
+    /// The duration of the audio analyzed.
+    pub dur: Duration,
+    /// The silence at the start of the audio, if it was long enough to be reported. If the audio
+    /// is entirely silent, the leading silence spans all the audio.
+    pub leading_silence: Option<Region>,
+    /// The silence at the end of the audio, if it was long enough to be reported.
+    pub trailing_silence: Option<Region>,
+    /// The analysis of each channel, in the order of the audio buffers.
+    pub channels: Vec<ChannelAnalysis>,
+    /// The correlation, between -1 and 1, of the front left and right channels. `None` if the audio
+    /// is not stereo, or either channel is digital silence.
+    pub stereo_correlation: Option<f64>,
+    /// If `true`, the right channel is an approximate inverse of the left channel.
+    pub phase_inverted: bool,
+    /// The regions of digital silence in all channels that were preceded and followed by audio,
+    /// and the regions of audio that were missing.
+    pub dropouts: Vec<Region>,
+}
+
+impl Analysis {
+    /// Returns `true` if any defect was detected.
+    pub fn has_defects(&self) -> bool {
+        self.leading_silence.is_some()
+            || self.trailing_silence.is_some()
+            || self.phase_inverted
+            || !self.dropouts.is_empty()
+            || self.channels.iter().any(|c| !c.clip_runs.is_empty() || c.has_dc_offset)
+    }
+}
+
+/// The per-channel state of an `Analyzer`.
+#[derive(Clone, Default)]
+struct ChannelState {
+    peak: f64,
+    sum: f64,
+    clipped_samples: u64,
+    /// The frame index at which the current run of clipped samples started.
+    clip_run_start: u64,
+    /// The length of the current run of clipped samples.
+    clip_run_len: u64,
+    /// The completed runs of clipped samples as frame index and length pairs.
+    clip_runs: Vec<(u64, u64)>,
+}
+
+impl ChannelState {
+    fn end_clip_run(&mut self, min_clip_run: u64) {
+        if self.clip_run_len > 0 && self.clip_run_len >= min_clip_run {
+            self.clip_runs.push((self.clip_run_start, self.clip_run_len));
+        }
+
+        self.clip_run_len = 0;
+    }
+}
+
+/// `Analyzer` detects silence, clipping, DC offset, phase inversion, and dropouts in audio.
+pub struct Analyzer {
+    spec: SignalSpec,
+    time_base: TimeBase,
+    options: AnalyzerOptions,
+    channels: Vec<ChannelState>,
+    /// The indices of the front left and right channels, if the audio is stereo.
+    stereo: Option<(usize, usize)>,
+    /// The sums of the squares of the left and right channels, and the sum of their product.
+    stereo_sums: [f64; 3],
+    /// The peak absolute value of each frame of the buffer being analyzed.
+    frame_peaks: Vec<f64>,
+    /// The number of frames analyzed, including missing frames.
+    n_frames: u64,
+    /// The number of frames that were missing.
+    n_missing_frames: u64,
+    /// The frame index of the first frame that was not silent.
+    first_sound: Option<u64>,
+    /// The frame index after the last frame that was not silent.
+    last_sound_end: u64,
+    /// If `true`, a frame that was not digital silence was analyzed.
+    seen_signal: bool,
+    /// The frame index at which the current run of digital silence started.
+    zero_run_start: Option<u64>,
+    /// If `true`, the current run of digital silence includes missing frames.
+    zero_run_missing: bool,
+    /// The dropouts detected as frame index and length pairs.
+    dropouts: Vec<(u64, u64)>,
+}
+
+impl Analyzer {
+    /// Instantiates an `Analyzer` for audio with the given signal specification. Positions are
+    /// reported in the given time base.
+    pub fn new(spec: SignalSpec, time_base: TimeBase, options: &AnalyzerOptions) -> Self {
+        // If there are exactly two channels, treat them as left and right regardless of their
+        // positions. Otherwise, use the front left and right channels, if present.
+        let stereo = if spec.channels.count() == 2 {
+            Some((0, 1))
+        }
+        else {
+            spec.channels
+                .index_of(ChannelLabel::Positioned(Channels::FRONT_LEFT))
+                .zip(spec.channels.index_of(ChannelLabel::Positioned(Channels::FRONT_RIGHT)))
+        };
+
+        Analyzer {
+            spec,
+            time_base,
+            options: *options,
+            channels: vec![Default::default(); spec.channels.count()],
+            stereo,
+            stereo_sums: [0.0; 3],
+            frame_peaks: Vec::new(),
+            n_frames: 0,
+            n_missing_frames: 0,
+            first_sound: None,
+            last_sound_end: 0,
+            seen_signal: false,
+            zero_run_start: None,
+            zero_run_missing: false,
+            dropouts: Vec::new(),
+        }
+    }
+
+    /// Gets the signal specification of the audio being analyzed.
+    pub fn spec(&self) -> &SignalSpec {
+        &self.spec
+    }
+
+    /// Analyzes a buffer of audio.
+    ///
+    /// The signal specification of the buffer must match that of the analyzer.
+    pub fn process<S>(&mut self, buf: &AudioBuffer<S>)
+    where
+        S: Sample + IntoSample<f64>,
+    {
+        assert_eq!(buf.spec(), &self.spec, "buffer signal specification must match the analyzer");
+
+        let n_frames = buf.frames();
+
+        // A sample is clipped if it is within one quantization step of full scale. Since decoders
+        // may output audio in a sample format with more precision than the source, the step is at
+        // least that of 16-bit audio.
+        let clip_level = 1.0 - 2f64.powi(1 - S::EFF_BITS.min(16) as i32);
+        let min_clip_run = self.options.min_clip_run as u64;
+
+        self.frame_peaks.clear();
+        self.frame_peaks.resize(n_frames, 0.0);
+
+        for (c, state) in self.channels.iter_mut().enumerate() {
+            for (i, (&sample, frame_peak)) in
+                buf.chan(c).iter().zip(self.frame_peaks.iter_mut()).enumerate()
+            {
+                let x: f64 = sample.into_sample();
+                let abs = x.abs();
+
+                state.peak = state.peak.max(abs);
+                state.sum += x;
+
+                *frame_peak = frame_peak.max(abs);
+
+                if abs >= clip_level {
+                    if state.clip_run_len == 0 {
+                        state.clip_run_start = self.n_frames + i as u64;
+                    }
+
+                    state.clip_run_len += 1;
+                    state.clipped_samples += 1;
+                }
+                else {
+                    state.end_clip_run(min_clip_run);
+                }
+            }
+        }
+
+        if let Some((l, r)) = self.stereo {
+            for (&l, &r) in buf.chan(l).iter().zip(buf.chan(r)) {
+                let l: f64 = l.into_sample();
+                let r: f64 = r.into_sample();
+
+                self.stereo_sums[0] += l * l;
+                self.stereo_sums[1] += r * r;
+                self.stereo_sums[2] += l * r;
+            }
+        }
+
+        let silence_level = db_to_amplitude(self.options.silence_threshold);
+        let min_dropout = self.frames(self.options.min_dropout);
+
+        for (i, &peak) in self.frame_peaks.iter().enumerate() {
+            let frame = self.n_frames + i as u64;
+
+            if peak >= silence_level {
+                self.first_sound.get_or_insert(frame);
+                self.last_sound_end = frame + 1;
+            }
+
+            if peak == 0.0 {
+                if self.seen_signal && self.zero_run_start.is_none() {
+                    self.zero_run_start = Some(frame);
+                }
+            }
+            else {
+                if let Some(start) = self.zero_run_start.take() {
+                    if frame - start >= min_dropout {
+                        self.dropouts.push((start, frame - start));
+                    }
+
+                    self.zero_run_missing = false;
+                }
+
+                self.seen_signal = true;
+            }
+        }
+
+        self.n_frames += n_frames as u64;
+    }
+
+    /// Sets the timestamp, in the time base, of the next buffer to be analyzed.
+    ///
+    /// If the timestamp is after the end of the audio analyzed so far, the audio in between is
+    /// missing, for example because a packet could not be decoded. Missing audio is reported as a
+    /// dropout, together with any digital silence adjoining it. A timestamp before the end of the
+    /// audio analyzed so far is ignored.
+    pub fn set_position(&mut self, ts: TimeStamp) {
+        let time = self.time_base.calc_time(ts);
+
+        let rate = u64::from(self.spec.rate);
+        let frame = time.seconds * rate + (time.frac * rate as f64).round() as u64;
+
+        if frame <= self.n_frames {
+            return;
+        }
+
+        // Runs of clipped samples do not continue across missing audio.
+        let min_clip_run = self.options.min_clip_run as u64;
+
+        for state in self.channels.iter_mut() {
+            state.end_clip_run(min_clip_run);
+        }
+
+        // Missing audio is treated as a run of digital silence that is always a dropout.
+        self.zero_run_start.get_or_insert(self.n_frames);
+        self.zero_run_missing = true;
+
+        self.n_missing_frames += frame - self.n_frames;
+        self.n_frames = frame;
+    }
+
+    /// Analyzes a buffer of audio of any sample format.
+    ///
+    /// The signal specification of the buffer must match that of the analyzer.
+    pub fn process_ref(&mut self, buf: &AudioBufferRef<'_>) {
+        match buf {
+            AudioBufferRef::U8(buf) => self.process(buf),
+            AudioBufferRef::U16(buf) => self.process(buf),
+            AudioBufferRef::U24(buf) => self.process(buf),
+            AudioBufferRef::U32(buf) => self.process(buf),
+            AudioBufferRef::S8(buf) => self.process(buf),
+            AudioBufferRef::S16(buf) => self.process(buf),
+            AudioBufferRef::S24(buf) => self.process(buf),
+            AudioBufferRef::S32(buf) => self.process(buf),
+            AudioBufferRef::F32(buf) => self.process(buf),
+            AudioBufferRef::F64(buf) => self.process(buf),
+        }
+    }
+
+    /// Clears all analysis.
+    pub fn reset(&mut self) {
+        *self = Analyzer::new(self.spec, self.time_base, &self.options);
+    }
+
+    /// Gets the analysis of all audio processed so far.
+    pub fn analysis(&self) -> Analysis {
+        let min_silence = self.frames(self.options.min_silence);
+
+        let (leading, trailing) = match self.first_sound {
+            Some(first) => ((0, first), (self.last_sound_end, self.n_frames - self.last_sound_end)),
+            None => ((0, self.n_frames), (0, 0)),
+        };
+
+        let silence = |(start, len): (u64, u64)| {
+            if len > 0 && len >= min_silence {
+                Some(self.region(start, len))
+            }
+            else {
+                None
+            }
+        };
+
+        let dc_offset_level = db_to_amplitude(self.options.dc_offset_threshold);
+        let min_clip_run = self.options.min_clip_run as u64;
+
+        let channels = self
+            .channels
+            .iter()
+            .map(|state| {
+                // Include the run of clipped samples at the end of the audio.
+                let mut state = state.clone();
+                state.end_clip_run(min_clip_run);
+
+                let n_frames = self.n_frames - self.n_missing_frames;
+
+                let dc_offset = if n_frames > 0 { state.sum / n_frames as f64 } else { 0.0 };
+
+                ChannelAnalysis {
+                    peak: state.peak,
+                    clipped_samples: state.clipped_samples,
+                    clip_runs: state
+                        .clip_runs
+                        .iter()
+                        .map(|&(start, len)| self.region(start, len))
+                        .collect(),
+                    dc_offset,
+                    has_dc_offset: dc_offset.abs() > dc_offset_level,
+                }
+            })
+            .collect();
+
+        let mut dropouts: Vec<Region> =
+            self.dropouts.iter().map(|&(start, len)| self.region(start, len)).collect();
+
+        // Missing audio at the end is a dropout, unlike trailing digital silence.
+        if let (Some(start), true) = (self.zero_run_start, self.zero_run_missing) {
+            if self.n_frames - start >= self.frames(self.options.min_dropout) {
+                dropouts.push(self.region(start, self.n_frames - start));
+            }
+        }
+
+        let [ll, rr, lr] = self.stereo_sums;
+
+        let stereo_correlation = if self.stereo.is_some() && ll > 0.0 && rr > 0.0 {
+            Some(lr / (ll * rr).sqrt())
+        }
+        else {
+            None
+        };
+
+        Analysis {
+            dur: self.ts(self.n_frames),
+            leading_silence: silence(leading),
+            trailing_silence: silence(trailing),
+            channels,
+            stereo_correlation,
+            phase_inverted: stereo_correlation
+                .map_or(false, |corr| corr < self.options.phase_inversion_threshold),
+            dropouts,
+        }
+    }
+
+    /// Converts a frame index to a timestamp in the time base.
+    fn ts(&self, frame: u64) -> TimeStamp {
+        let rate = u64::from(self.spec.rate);
+        let time = Time::new(frame / rate, (frame % rate) as f64 / rate as f64);
+
+        self.time_base.calc_timestamp(time)
+    }
+
+    /// Converts a span of frames to a region in the time base.
+    fn region(&self, start: u64, len: u64) -> Region {
+        let ts = self.ts(start);
+        Region { ts, dur: self.ts(start + len) - ts }
+    }
+
+    /// Converts a time to a number of frames, rounding up.
+    fn frames(&self, time: Time) -> u64 {
+        let rate = u64::from(self.spec.rate);
+        time.seconds * rate + (time.frac * rate as f64).ceil() as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::Layout;
+
+    fn buffer(spec: SignalSpec, left: &[f32], right: &[f32]) -> AudioBuffer<f32> {
+        let mut buf = AudioBuffer::<f32>::new(left.len() as u64, spec);
+        buf.render_reserved(Some(left.len()));
+        buf.chan_mut(0).copy_from_slice(left);
+        buf.chan_mut(1).copy_from_slice(right);
+        buf
+    }
+
+    #[test]
+    fn verify_analyzer() {
+        let spec = SignalSpec::new_with_layout(1000, Layout::Stereo);
+
+        // Positions are reported in milliseconds.
+        let mut analyzer = Analyzer::new(spec, TimeBase::new(1, 1000), &Default::default());
+
+        // 200 ms of leading silence, with a quiet noise floor.
+        let left = vec![0.0001; 200];
+        analyzer.process(&buffer(spec, &left, &left));
+
+        // 500 ms of phase-inverted audio with an offset of 0.1, a 5 ms dropout at 400 ms, and 4
+        // clipped samples at 300 ms.
+        let mut left: Vec<f32> = (0..500)
+            .map(|i| {
+                if i % 2 == 0 {
+                    0.5
+                }
+                else {
+                    -0.3
+                }
+            })
+            .collect();
+
+        for s in &mut left[200..205] {
+            *s = 0.0;
+        }
+
+        for s in &mut left[100..104] {
+            *s = 1.0;
+        }
+
+        let mut right: Vec<f32> = left.iter().map(|s| -s).collect();
+
+        // A single clipped sample is not a run.
+        right[0] = -1.0;
+
+        // Split the audio across buffers to verify the state is preserved.
+        analyzer.process(&buffer(spec, &left[..202], &right[..202]));
+        analyzer.process(&buffer(spec, &left[202..], &right[202..]));
+
+        // 300 ms of trailing digital silence.
+        let left = vec![0.0; 300];
+        analyzer.process(&buffer(spec, &left, &left));
+
+        let analysis = analyzer.analysis();
+
+        assert_eq!(analysis.dur, 1000);
+        assert_eq!(analysis.leading_silence, Some(Region { ts: 0, dur: 200 }));
+        assert_eq!(analysis.trailing_silence, Some(Region { ts: 700, dur: 300 }));
+        assert_eq!(analysis.dropouts, vec![Region { ts: 400, dur: 5 }]);
+
+        assert_eq!(analysis.channels[0].clip_runs, vec![Region { ts: 300, dur: 4 }]);
+        assert_eq!(analysis.channels[1].clip_runs, vec![Region { ts: 300, dur: 4 }]);
+        assert_eq!(analysis.channels[1].clipped_samples, 5);
+
+        assert!(analysis.channels[0].has_dc_offset);
+        assert!(analysis.channels[0].dc_offset > 0.0);
+        assert!(analysis.channels[1].dc_offset < 0.0);
+
+        assert!(analysis.stereo_correlation.unwrap() < -0.99);
+        assert!(analysis.phase_inverted);
+        assert!(analysis.has_defects());
+
+        // Clean audio has no defects.
+        analyzer.reset();
+
+        let left: Vec<f32> = (0..1000).map(|i| 0.5 * (i as f32 * 0.1).sin()).collect();
+        analyzer.process(&buffer(spec, &left, &left));
+
+        let analysis = analyzer.analysis();
+
+        assert!(!analysis.has_defects());
+        assert!((analysis.stereo_correlation.unwrap() - 1.0).abs() < 1e-9);
+    }
+
+    /// Returns an analyzer for stereo audio at 1 kHz, reporting positions in milliseconds.
+    fn new_analyzer(options: &AnalyzerOptions) -> (SignalSpec, Analyzer) {
+        let spec = SignalSpec::new_with_layout(1000, Layout::Stereo);
+        (spec, Analyzer::new(spec, TimeBase::new(1, 1000), options))
+    }
+
+    /// Returns `n` frames of a tone that is well above the silence threshold and never clipped.
+    fn tone(n: usize) -> Vec<f32> {
+        (0..n).map(|i| 0.5 * (i as f32 * 0.1).sin() + 0.25).collect()
+    }
+
+    #[test]
+    fn verify_silence_threshold() {
+        // -60 dBFS is an amplitude of 0.001. Frames below the threshold are silent.
+        for &(level, silent) in &[(0.0009, true), (0.0011, false)] {
+            let (spec, mut analyzer) = new_analyzer(&Default::default());
+
+            let quiet = vec![level; 200];
+            analyzer.process(&buffer(spec, &quiet, &quiet));
+
+            let audio = tone(500);
+            analyzer.process(&buffer(spec, &audio, &audio));
+            analyzer.process(&buffer(spec, &quiet, &quiet));
+
+            let analysis = analyzer.analysis();
+
+            if silent {
+                assert_eq!(analysis.leading_silence, Some(Region { ts: 0, dur: 200 }));
+                assert_eq!(analysis.trailing_silence, Some(Region { ts: 700, dur: 200 }));
+            }
+            else {
+                assert_eq!(analysis.leading_silence, None);
+                assert_eq!(analysis.trailing_silence, None);
+            }
+        }
+
+        // A frame is silent only if every channel is below the threshold.
+        let (spec, mut analyzer) = new_analyzer(&Default::default());
+
+        analyzer.process(&buffer(spec, &[0.0; 200], &[0.002; 200]));
+        analyzer.process(&buffer(spec, &tone(500), &tone(500)));
+
+        assert_eq!(analyzer.analysis().leading_silence, None);
+
+        // Audio that is entirely silent is all leading silence.
+        let (spec, mut analyzer) = new_analyzer(&Default::default());
+
+        analyzer.process(&buffer(spec, &[0.0; 300], &[0.0; 300]));
+
+        let analysis = analyzer.analysis();
+
+        assert_eq!(analysis.leading_silence, Some(Region { ts: 0, dur: 300 }));
+        assert_eq!(analysis.trailing_silence, None);
+    }
+
+    #[test]
+    fn verify_min_silence_across_buffers() {
+        // The default minimum silence is 100 ms. Split the silence across buffers such that the
+        // minimum is only met by the frames of both buffers.
+        for &(len, reported) in &[(100, true), (99, false)] {
+            let (spec, mut analyzer) = new_analyzer(&Default::default());
+
+            let split = len / 2;
+
+            let silence = vec![0.0; len];
+            analyzer.process(&buffer(spec, &silence[..split], &silence[..split]));
+            analyzer.process(&buffer(spec, &silence[split..], &silence[split..]));
+
+            let audio = tone(200);
+            analyzer.process(&buffer(spec, &audio, &audio));
+
+            analyzer.process(&buffer(spec, &silence[..split], &silence[..split]));
+            analyzer.process(&buffer(spec, &silence[split..], &silence[split..]));
+
+            let analysis = analyzer.analysis();
+            let end = len as u64 + 200;
+
+            if reported {
+                assert_eq!(analysis.leading_silence, Some(Region { ts: 0, dur: len as u64 }));
+                assert_eq!(analysis.trailing_silence, Some(Region { ts: end, dur: len as u64 }));
+            }
+            else {
+                assert_eq!(analysis.leading_silence, None);
+                assert_eq!(analysis.trailing_silence, None);
+            }
+        }
+    }
+
+    #[test]
+    fn verify_clip_runs() {
+        let options = AnalyzerOptions { min_clip_run: 3, ..Default::default() };
+        let (spec, mut analyzer) = new_analyzer(&options);
+
+        let mut left = tone(300);
+
+        // A run of 3 clipped samples that spans the boundary between buffers.
+        for s in &mut left[98..101] {
+            *s = 1.0;
+        }
+
+        // A run of 2 clipped samples is too short to be reported.
+        for s in &mut left[150..152] {
+            *s = -1.0;
+        }
+
+        // A run of clipped samples at the end of the audio.
+        for s in &mut left[296..] {
+            *s = 1.0;
+        }
+
+        let right = tone(300);
+
+        analyzer.process(&buffer(spec, &left[..100], &right[..100]));
+        analyzer.process(&buffer(spec, &left[100..], &right[100..]));
+
+        let analysis = analyzer.analysis();
+
+        assert_eq!(
+            analysis.channels[0].clip_runs,
+            vec![Region { ts: 98, dur: 3 }, Region { ts: 296, dur: 4 }]
+        );
+        assert_eq!(analysis.channels[0].clipped_samples, 9);
+        assert_eq!(analysis.channels[0].peak, 1.0);
+
+        assert!(analysis.channels[1].clip_runs.is_empty());
+        assert_eq!(analysis.channels[1].clipped_samples, 0);
+
+        // Samples within one 16-bit quantization step of full scale are clipped.
+        let (spec, mut analyzer) = new_analyzer(&Default::default());
+
+        let near = 1.0 - 2f32.powi(-15);
+        let below = 1.0 - 2f32.powi(-14);
+
+        analyzer.process(&buffer(spec, &[near; 3], &[below; 3]));
+
+        let analysis = analyzer.analysis();
+
+        assert_eq!(analysis.channels[0].clip_runs, vec![Region { ts: 0, dur: 3 }]);
+        assert!(analysis.channels[1].clip_runs.is_empty());
+    }
+
+    #[test]
+    fn verify_dc_offset() {
+        // -50 dBFS is an amplitude of approximately 0.00316.
+        for &(offset, has_dc_offset) in &[(0.004, true), (-0.004, true), (0.003, false)] {
+            let (spec, mut analyzer) = new_analyzer(&Default::default());
+
+            // A square wave with a mean of 0, plus the offset.
+            let audio: Vec<f32> =
+                (0..1000).map(|i| if i % 2 == 0 { 0.5 } else { -0.5 } + offset).collect();
+
+            analyzer.process(&buffer(spec, &audio[..300], &audio[..300]));
+            analyzer.process(&buffer(spec, &audio[300..], &audio[300..]));
+
+            let analysis = analyzer.analysis();
+
+            assert!((analysis.channels[0].dc_offset - f64::from(offset)).abs() < 1e-6);
+            assert_eq!(analysis.channels[0].has_dc_offset, has_dc_offset);
+            assert_eq!(analysis.has_defects(), has_dc_offset);
+        }
+    }
+
+    #[test]
+    fn verify_phase_inversion() {
+        let audio: Vec<f32> = (0..1000).map(|i| 0.5 * (i as f32 * 0.1).sin()).collect();
+        let inverted: Vec<f32> = audio.iter().map(|s| -s).collect();
+
+        // Quadrature audio is uncorrelated.
+        let quadrature: Vec<f32> = (0..1000).map(|i| 0.5 * (i as f32 * 0.1).cos()).collect();
+
+        // Audio that is partially inverted, with a correlation of approximately -0.45.
+        let partial: Vec<f32> = audio.iter().zip(&quadrature).map(|(a, q)| -0.5 * a + q).collect();
+
+        for &(right, inverted) in &[(&inverted, true), (&quadrature, false), (&partial, false)] {
+            let (spec, mut analyzer) = new_analyzer(&Default::default());
+
+            analyzer.process(&buffer(spec, &audio[..500], &right[..500]));
+            analyzer.process(&buffer(spec, &audio[500..], &right[500..]));
+
+            assert_eq!(analyzer.analysis().phase_inverted, inverted);
+        }
+
+        // The threshold is configurable.
+        let options = AnalyzerOptions { phase_inversion_threshold: -0.4, ..Default::default() };
+        let (spec, mut analyzer) = new_analyzer(&options);
+
+        analyzer.process(&buffer(spec, &audio, &partial));
+
+        assert!(analyzer.analysis().phase_inverted);
+
+        // The correlation is undefined if either channel is digital silence.
+        let (spec, mut analyzer) = new_analyzer(&Default::default());
+
+        analyzer.process(&buffer(spec, &audio, &[0.0; 1000]));
+
+        let analysis = analyzer.analysis();
+
+        assert_eq!(analysis.stereo_correlation, None);
+        assert!(!analysis.phase_inverted);
+    }
+
+    #[test]
+    fn verify_dropouts() {
+        let options = AnalyzerOptions { min_dropout: Time::new(0, 0.0045), ..Default::default() };
+        let (spec, mut analyzer) = new_analyzer(&options);
+
+        // Leading digital silence is not a dropout.
+        let mut audio = vec![0.0; 50];
+        audio.extend(tone(400));
+
+        // A dropout of 5 ms that spans the boundary between buffers.
+        for s in &mut audio[198..203] {
+            *s = 0.0;
+        }
+
+        // A dropout of 4 ms is too short to be reported.
+        for s in &mut audio[300..304] {
+            *s = 0.0;
+        }
+
+        // Silence that is not digital silence is not a dropout.
+        for s in &mut audio[350..360] {
+            *s = 0.0001;
+        }
+
+        // A dropout is only detected if it is in all channels. Here, the right channel splits the
+        // 8 ms of digital silence in the left channel into two runs that are too short.
+        for s in &mut audio[400..408] {
+            *s = 0.0;
+        }
+
+        let mut right = audio.clone();
+        right[404] = 0.25;
+
+        analyzer.process(&buffer(spec, &audio[..200], &right[..200]));
+        analyzer.process(&buffer(spec, &audio[200..], &right[200..]));
+
+        // Trailing digital silence is not a dropout.
+        analyzer.process(&buffer(spec, &[0.0; 50], &[0.0; 50]));
+
+        assert_eq!(analyzer.analysis().dropouts, vec![Region { ts: 198, dur: 5 }]);
+    }
+
+    #[test]
+    fn verify_missing_audio() {
+        let (spec, mut analyzer) = new_analyzer(&Default::default());
+
+        let audio = tone(100);
+        let clipped = vec![1.0; 10];
+
+        // Audio is missing at the start, between two buffers, and at the end.
+        analyzer.set_position(20);
+        analyzer.process(&buffer(spec, &audio, &audio));
+
+        // A timestamp within the audio analyzed so far is ignored.
+        analyzer.set_position(100);
+        analyzer.process(&buffer(spec, &clipped, &audio[..10]));
+
+        // Digital silence adjoining missing audio is part of the dropout. Runs of clipped samples
+        // are split by missing audio.
+        analyzer.process(&buffer(spec, &[0.0; 5], &[0.0; 5]));
+        analyzer.set_position(150);
+        analyzer.process(&buffer(spec, &clipped, &audio[..10]));
+        analyzer.set_position(200);
+
+        let analysis = analyzer.analysis();
+
+        assert_eq!(analysis.dur, 200);
+        assert_eq!(
+            analysis.dropouts,
+            vec![
+                Region { ts: 0, dur: 20 },
+                Region { ts: 130, dur: 20 },
+                Region { ts: 160, dur: 40 }
+            ]
+        );
+        assert_eq!(
+            analysis.channels[0].clip_runs,
+            vec![Region { ts: 120, dur: 10 }, Region { ts: 150, dur: 10 }]
+        );
+
+        // The DC offset excludes missing audio.
+        let sum: f64 = audio.iter().chain(&clipped).chain(&clipped).map(|&s| f64::from(s)).sum();
+
+        assert!((analysis.channels[0].dc_offset - sum / 125.0).abs() < 1e-9);
+    }
+}
@@ -5,34 +5,106 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-//! The `complex` module implements a 32-bit floating point complex number.
+//! The `complex` module implements 32-bit and 64-bit floating point complex numbers.
+
+use core::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+
+/// `Float` is a floating point type that may be used as the component of a `Complex` number, and
+/// by the generic transforms and window functions of the `dsp` module.
+pub trait Float:
+    Copy
+    + Default
+    + PartialOrd
+    + core::fmt::Debug
+    + Send
+    + Sync
+    + 'static
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+    + AddAssign
+    + SubAssign
+    + MulAssign
+    + DivAssign
+{
+    /// The value 0.
+    const ZERO: Self;
+    /// The value 1.
+    const ONE: Self;
+
+    /// Converts a `f64` to `Self`, rounding if necessary.
+    fn from_f64(val: f64) -> Self;
+
+    /// Converts `self` to a `f64`.
+    fn to_f64(self) -> f64;
+}
+
+impl Float for f32 {
+    const ZERO: Self = 0.0;
+    const ONE: Self = 1.0;
+
+    #[inline(always)]
+    fn from_f64(val: f64) -> Self {
+        val as f32
+    }
+
+    #[inline(always)]
+    fn to_f64(self) -> f64 {
+        f64::from(self)
+    }
+}
+
+impl Float for f64 {
+    const ZERO: Self = 0.0;
+    const ONE: Self = 1.0;
+
+    #[inline(always)]
+    fn from_f64(val: f64) -> Self {
+        val
+    }
+
+    #[inline(always)]
+    fn to_f64(self) -> f64 {
+        self
+    }
+}
 
 /// A complex number.
+///
+/// The components are 32-bit floating point numbers by default.
 #[derive(Copy, Clone, Default, Debug, PartialEq)]
 #[repr(C)]
-pub struct Complex {
+pub struct Complex<T = f32> {
     /// The real component.
-    pub re: f32,
+    pub re: T,
     /// The imaginary component.
-    pub im: f32,
+    pub im: T,
 }
 
-impl Complex {
+impl<T: Float> Complex<T> {
     /// Create a new complex number.
     #[inline(always)]
-    pub fn new(re: f32, im: f32) -> Self {
+    pub fn new(re: T, im: T) -> Self {
         Self { re, im }
     }
 
     /// Create a complex number with a value of `0 + j1`.
     #[inline(always)]
     pub fn j() -> Self {
-        Self { re: 0.0, im: 1.0 }
+        Self { re: T::ZERO, im: T::ONE }
+    }
+
+    /// Create a complex number with a magnitude of 1 and the phase `theta` in radians.
+    #[inline(always)]
+    pub fn from_phase(theta: f64) -> Self {
+        Self { re: T::from_f64(theta.cos()), im: T::from_f64(theta.sin()) }
     }
 
     /// Scale the complex number.
     #[inline(always)]
-    pub fn scale(&self, scale: f32) -> Self {
+    pub fn scale(&self, scale: T) -> Self {
         Self { re: self.re * scale, im: self.im * scale }
     }
 
@@ -45,8 +117,8 @@ impl Complex {
     }
 }
 
-impl core::ops::Add for Complex {
-    type Output = Complex;
+impl<T: Float> Add for Complex<T> {
+    type Output = Complex<T>;
 
     #[inline(always)]
     fn add(self, rhs: Self) -> Self::Output {
@@ -54,15 +126,15 @@ impl core::ops::Add for Complex {
     }
 }
 
-impl core::ops::AddAssign for Complex {
+impl<T: Float> AddAssign for Complex<T> {
     #[inline(always)]
     fn add_assign(&mut self, rhs: Self) {
         *self = *self + rhs;
     }
 }
 
-impl core::ops::Sub for Complex {
-    type Output = Complex;
+impl<T: Float> Sub for Complex<T> {
+    type Output = Complex<T>;
 
     #[inline(always)]
     fn sub(self, rhs: Self) -> Self::Output {
@@ -70,15 +142,15 @@ impl core::ops::Sub for Complex {
     }
 }
 
-impl core::ops::SubAssign for Complex {
+impl<T: Float> SubAssign for Complex<T> {
     #[inline(always)]
     fn sub_assign(&mut self, rhs: Self) {
         *self = *self - rhs;
     }
 }
 
-impl core::ops::Mul for Complex {
-    type Output = Complex;
+impl<T: Float> Mul for Complex<T> {
+    type Output = Complex<T>;
 
     #[inline(always)]
     fn mul(self, rhs: Self) -> Self::Output {
@@ -89,15 +161,15 @@ impl core::ops::Mul for Complex {
     }
 }
 
-impl core::ops::MulAssign for Complex {
+impl<T: Float> MulAssign for Complex<T> {
     #[inline(always)]
     fn mul_assign(&mut self, rhs: Self) {
         *self = *self * rhs;
     }
 }
 
-impl core::ops::Div for Complex {
-    type Output = Complex;
+impl<T: Float> Div for Complex<T> {
+    type Output = Complex<T>;
 
     #[inline(always)]
     fn div(self, rhs: Self) -> Self::Output {
@@ -110,27 +182,27 @@ impl core::ops::Div for Complex {
     }
 }
 
-impl core::ops::DivAssign for Complex {
+impl<T: Float> DivAssign for Complex<T> {
     #[inline(always)]
     fn div_assign(&mut self, rhs: Self) {
         *self = *self / rhs;
     }
 }
 
-impl core::ops::Mul<f32> for Complex {
-    type Output = Complex;
+impl<T: Float> Mul<T> for Complex<T> {
+    type Output = Complex<T>;
 
     #[inline(always)]
-    fn mul(self, rhs: f32) -> Self::Output {
+    fn mul(self, rhs: T) -> Self::Output {
         Self::Output { re: self.re * rhs, im: self.im * rhs }
     }
 }
 
-impl core::ops::Div<f32> for Complex {
-    type Output = Complex;
+impl<T: Float> Div<T> for Complex<T> {
+    type Output = Complex<T>;
 
     #[inline(always)]
-    fn div(self, rhs: f32) -> Self::Output {
+    fn div(self, rhs: T) -> Self::Output {
         Self::Output { re: self.re / rhs, im: self.im / rhs }
     }
 }
@@ -9,13 +9,20 @@
 //!
 //! The complex (I)FFT in this module supports a size up-to 65536. The FFT is implemented using the
 //! radix-2 Cooley-Tukey algorithm.
+//!
+//! The arbitrary-size complex (I)FFT supports any size in single or double precision. It is
+//! implemented using a mixed-radix Cooley-Tukey algorithm, or Bluestein's algorithm for sizes with
+//! large prime factors.
 
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
 use std::convert::TryInto;
 use std::f32;
+use std::sync::{Arc, Mutex};
 
 use lazy_static::lazy_static;
 
-use super::complex::Complex;
+use super::complex::{Complex, Float};
 
 macro_rules! fft_twiddle_table {
     ($bi:expr, $name:ident) => {
@@ -214,20 +221,311 @@ impl Fft {
 
             let twiddle = fft_twiddle_factors(n);
 
-            for ((e, o), w) in
-                even.chunks_exact_mut(2).zip(odd.chunks_exact_mut(2)).zip(twiddle.chunks_exact(2))
-            {
-                let p0 = e[0];
-                let q0 = o[0] * w[0];
+            super::simd::fft_butterfly(even, odd, &twiddle[..n_half]);
+        }
+    }
+}
+
+/// Gets the twiddle factors, `exp(-j2πk/n)` for `k` in `0..n`, of a transform of size `n`.
+///
+/// Twiddle factors are computed in double precision and cached. The cache is shared by all
+/// transforms of the same size and precision.
+pub(crate) fn twiddle_factors<T: Float>(n: usize) -> Arc<Vec<Complex<T>>> {
+    type TwiddleCache = HashMap<(TypeId, usize), Arc<dyn Any + Send + Sync>>;
+
+    lazy_static! {
+        static ref TWIDDLE_CACHE: Mutex<TwiddleCache> = Mutex::new(HashMap::new());
+    }
+
+    let mut cache = TWIDDLE_CACHE.lock().unwrap();
+
+    let entry = cache.entry((TypeId::of::<T>(), n)).or_insert_with(|| {
+        let theta = -2.0 * std::f64::consts::PI / n as f64;
+        let table: Vec<Complex<T>> =
+            (0..n).map(|k| Complex::from_phase(theta * k as f64)).collect();
+        Arc::new(table)
+    });
 
-                e[0] = p0 + q0;
-                o[0] = p0 - q0;
+    Arc::clone(entry).downcast::<Vec<Complex<T>>>().unwrap()
+}
+
+/// The largest prime factor computed using a mixed-radix butterfly. Sizes with larger prime
+/// factors are computed using Bluestein's algorithm.
+const MAX_RADIX: usize = 13;
+
+/// Factors `n` into radices, preferring radix-4.
+fn factorize(mut n: usize) -> Vec<usize> {
+    let mut factors = Vec::new();
+
+    while n % 4 == 0 {
+        factors.push(4);
+        n /= 4;
+    }
+
+    let mut p = 2;
+
+    while n > 1 {
+        while n % p == 0 {
+            factors.push(p);
+            n /= p;
+        }
 
-                let p1 = e[1];
-                let q1 = o[1] * w[1];
+        p += if p == 2 { 1 } else { 2 };
 
-                e[1] = p1 + q1;
-                o[1] = p1 - q1;
+        if p * p > n && n > 1 {
+            factors.push(n);
+            break;
+        }
+    }
+
+    factors
+}
+
+enum FftKind<T: Float> {
+    MixedRadix {
+        factors: Vec<usize>,
+        twiddle: Arc<Vec<Complex<T>>>,
+        scratch: Vec<Complex<T>>,
+    },
+    Bluestein {
+        inner: Box<ArbitraryFft<T>>,
+        chirp: Vec<Complex<T>>,
+        kernel: Vec<Complex<T>>,
+        buf: Vec<Complex<T>>,
+    },
+}
+
+/// A complex Fast Fourier Transform (FFT) of any size.
+///
+/// Sizes with only small prime factors are computed using a mixed-radix Cooley-Tukey algorithm.
+/// All other sizes are computed using Bluestein's algorithm. The FFT may be computed in single
+/// (`f32`) or double (`f64`) precision.
+///
+/// For power-of-two sizes, [`Fft`] is faster in single precision.
+pub struct ArbitraryFft<T: Float = f32> {
+    n: usize,
+    kind: FftKind<T>,
+    work: Vec<Complex<T>>,
+}
+
+impl<T: Float> ArbitraryFft<T> {
+    /// Instantiate a N-point FFT. The value of `n` must be greater than 0.
+    pub fn new(n: usize) -> Self {
+        assert!(n > 0, "n must be greater than 0");
+
+        let factors = factorize(n);
+
+        let kind = if factors.iter().all(|&p| p <= MAX_RADIX) {
+            let max_radix = factors.iter().copied().max().unwrap_or(1);
+
+            FftKind::MixedRadix {
+                factors,
+                twiddle: twiddle_factors(n),
+                scratch: vec![Default::default(); max_radix],
+            }
+        }
+        else {
+            // The linear convolution of the chirped input and the chirp kernel requires a FFT of
+            // at-least 2N - 1 points.
+            let m = (2 * n - 1).next_power_of_two();
+
+            // The chirp, exp(-jπk²/N). The exponent is reduced modulo 2N to preserve precision.
+            let chirp: Vec<Complex<T>> = (0..n as u64)
+                .map(|k| {
+                    let k2 = (k * k) % (2 * n as u64);
+                    Complex::from_phase(-std::f64::consts::PI * k2 as f64 / n as f64)
+                })
+                .collect();
+
+            let mut inner = ArbitraryFft::new(m);
+
+            let mut kernel = vec![Complex::default(); m];
+
+            kernel[0] = chirp[0].conj();
+
+            for k in 1..n {
+                kernel[k] = chirp[k].conj();
+                kernel[m - k] = chirp[k].conj();
+            }
+
+            inner.fft_inplace(&mut kernel);
+
+            FftKind::Bluestein {
+                inner: Box::new(inner),
+                chirp,
+                kernel,
+                buf: vec![Default::default(); m],
+            }
+        };
+
+        ArbitraryFft { n, kind, work: vec![Default::default(); n] }
+    }
+
+    /// Get the size of the FFT.
+    pub fn size(&self) -> usize {
+        self.n
+    }
+
+    /// Calculate the FFT.
+    pub fn fft(&mut self, x: &[Complex<T>], y: &mut [Complex<T>]) {
+        assert_eq!(x.len(), self.n);
+        assert_eq!(y.len(), self.n);
+
+        forward(&mut self.kind, x, y);
+    }
+
+    /// Calculate the FFT in-place.
+    pub fn fft_inplace(&mut self, x: &mut [Complex<T>]) {
+        assert_eq!(x.len(), self.n);
+
+        self.work.copy_from_slice(x);
+
+        forward(&mut self.kind, &self.work, x);
+    }
+
+    /// Calculate the inverse FFT.
+    pub fn ifft(&mut self, x: &[Complex<T>], y: &mut [Complex<T>]) {
+        assert_eq!(x.len(), self.n);
+        assert_eq!(y.len(), self.n);
+
+        // The inverse FFT is the conjugate of the forward FFT of the conjugated input.
+        for (w, x) in self.work.iter_mut().zip(x) {
+            *w = x.conj();
+        }
+
+        forward(&mut self.kind, &self.work, y);
+
+        let c = T::ONE / T::from_f64(self.n as f64);
+
+        for y in y.iter_mut() {
+            *y = y.conj().scale(c);
+        }
+    }
+
+    /// Calculate the inverse FFT in-place.
+    pub fn ifft_inplace(&mut self, x: &mut [Complex<T>]) {
+        assert_eq!(x.len(), self.n);
+
+        for (w, x) in self.work.iter_mut().zip(x.iter()) {
+            *w = x.conj();
+        }
+
+        forward(&mut self.kind, &self.work, x);
+
+        let c = T::ONE / T::from_f64(self.n as f64);
+
+        for x in x.iter_mut() {
+            *x = x.conj().scale(c);
+        }
+    }
+}
+
+/// Calculate the forward FFT of `x` into `y`.
+fn forward<T: Float>(kind: &mut FftKind<T>, x: &[Complex<T>], y: &mut [Complex<T>]) {
+    match kind {
+        FftKind::MixedRadix { factors, twiddle, scratch } => {
+            mixed_radix(x, 1, y, factors, twiddle, 1, scratch);
+        }
+        FftKind::Bluestein { inner, chirp, kernel, buf } => {
+            let n = x.len();
+
+            for ((b, &x), &w) in buf.iter_mut().zip(x).zip(chirp.iter()) {
+                *b = x * w;
+            }
+
+            for b in buf[n..].iter_mut() {
+                *b = Default::default();
+            }
+
+            inner.fft_inplace(buf);
+
+            for (b, &k) in buf.iter_mut().zip(kernel.iter()) {
+                *b *= k;
+            }
+
+            inner.ifft_inplace(buf);
+
+            for ((y, &b), &w) in y.iter_mut().zip(buf.iter()).zip(chirp.iter()) {
+                *y = b * w;
+            }
+        }
+    }
+}
+
+/// Recursive decimation-in-time mixed-radix FFT of the `y.len()` elements of `x` spaced by
+/// `stride` elements.
+fn mixed_radix<T: Float>(
+    x: &[Complex<T>],
+    stride: usize,
+    y: &mut [Complex<T>],
+    factors: &[usize],
+    twiddle: &[Complex<T>],
+    tw_stride: usize,
+    scratch: &mut [Complex<T>],
+) {
+    let n = y.len();
+
+    if n == 1 {
+        y[0] = x[0];
+        return;
+    }
+
+    let p = factors[0];
+    let m = n / p;
+
+    // Transform each of the P decimated sub-sequences.
+    for (r, y) in y.chunks_exact_mut(m).enumerate() {
+        mixed_radix(
+            &x[r * stride..],
+            stride * p,
+            y,
+            &factors[1..],
+            twiddle,
+            tw_stride * p,
+            scratch,
+        );
+    }
+
+    // The twiddle table contains the N-th roots of unity. The P-th roots of unity are spaced by
+    // N/P entries.
+    let len = twiddle.len();
+    let root_stride = len / p;
+
+    for k in 0..m {
+        let t = &mut scratch[..p];
+
+        for (r, t) in t.iter_mut().enumerate() {
+            *t = y[r * m + k] * twiddle[(r * k * tw_stride) % len];
+        }
+
+        match p {
+            2 => {
+                y[k] = t[0] + t[1];
+                y[m + k] = t[0] - t[1];
+            }
+            4 => {
+                let a = t[0] + t[2];
+                let b = t[0] - t[2];
+                let c = t[1] + t[3];
+                // -j * (t1 - t3)
+                let d = t[1] - t[3];
+                let d = Complex::new(d.im, -d.re);
+
+                y[k] = a + c;
+                y[m + k] = b + d;
+                y[2 * m + k] = a - c;
+                y[3 * m + k] = b - d;
+            }
+            _ => {
+                for q in 0..p {
+                    let mut sum = t[0];
+
+                    for (r, &t) in t.iter().enumerate().skip(1) {
+                        sum += t * twiddle[((r * q) % p) * root_stride];
+                    }
+
+                    y[q * m + k] = sum;
+                }
             }
         }
     }
@@ -627,4 +925,56 @@ mod tests {
             assert!(check_complex(a, e, 0.000001));
         }
     }
+
+    /// Compute a naive DFT in double precision.
+    fn dft_naive_f64(x: &[Complex<f64>]) -> Vec<Complex<f64>> {
+        let n = x.len();
+
+        (0..n)
+            .map(|k| {
+                x.iter().enumerate().fold(Complex::default(), |acc, (j, &x)| {
+                    let theta = -2.0 * f64::consts::PI * ((j * k) % n) as f64 / n as f64;
+                    acc + x * Complex::from_phase(theta)
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn verify_arbitrary_fft() {
+        // Mixed-radix sizes, prime sizes, and sizes that require Bluestein's algorithm.
+        for &n in &[1, 2, 3, 4, 5, 6, 7, 12, 15, 16, 30, 49, 60, 64, 120, 17, 97, 202] {
+            let x: Vec<Complex<f64>> = (0..n)
+                .map(|i| Complex::new((i as f64 * 0.37).sin(), (i as f64 * 0.71).cos()))
+                .collect();
+
+            let expected = dft_naive_f64(&x);
+
+            let mut fft = ArbitraryFft::<f64>::new(n);
+
+            let mut actual = vec![Complex::default(); n];
+            fft.fft(&x, &mut actual);
+
+            for (&a, &e) in actual.iter().zip(expected.iter()) {
+                assert!((a - e).re.abs() < 1e-9 && (a - e).im.abs() < 1e-9, "n = {}", n);
+            }
+
+            fft.ifft_inplace(&mut actual);
+
+            for (&a, &e) in actual.iter().zip(x.iter()) {
+                assert!((a - e).re.abs() < 1e-12 && (a - e).im.abs() < 1e-12, "n = {}", n);
+            }
+        }
+
+        // Single precision matches the radix-2 FFT.
+        let mut expected = [Default::default(); TEST_VECTOR.len()];
+        Fft::new(TEST_VECTOR.len()).fft(&TEST_VECTOR, &mut expected);
+
+        let mut actual = TEST_VECTOR;
+        ArbitraryFft::<f32>::new(TEST_VECTOR.len()).fft_inplace(&mut actual);
+
+        for (&a, &e) in actual.iter().zip(expected.iter()) {
+            assert!(check_complex(a, e, 0.0001));
+        }
+    }
 }
@@ -0,0 +1,531 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! The `loudness` module implements loudness and true-peak measurement as defined in
+//! ITU-R BS.1770-4 and EBU R128.
+//!
+//! A [`LoudnessMeter`] measures the momentary, short-term, and integrated loudness, the loudness
+//! range (EBU Tech 3342), and the sample and true peaks of a stream of audio. The measurements may
+//! be converted into ReplayGain 2.0 tags.
+
+use std::collections::VecDeque;
+use std::f64::consts::PI;
+
+use crate::audio::{AudioBuffer, AudioBufferRef, ChannelLabel, Channels, Signal, SignalSpec};
+use crate::conv::IntoSample;
+use crate::meta::{StandardTagKey, Tag, Value};
+use crate::sample::Sample;
+
+/// The loudness, in LUFS, that ReplayGain 2.0 gains normalise to.
+const REPLAYGAIN_REFERENCE_LOUDNESS: f64 = -18.0;
+
+/// The absolute gating threshold in LUFS.
+const ABSOLUTE_GATE: f64 = -70.0;
+
+/// The relative gating threshold, in LU, for the integrated loudness.
+const INTEGRATED_RELATIVE_GATE: f64 = -10.0;
+
+/// The relative gating threshold, in LU, for the loudness range.
+const RANGE_RELATIVE_GATE: f64 = -20.0;
+
+/// The number of 100 ms steps in a momentary (400 ms) block.
+const MOMENTARY_STEPS: usize = 4;
+
+/// The number of 100 ms steps in a short-term (3 s) block.
+const SHORT_TERM_STEPS: usize = 30;
+
+/// The oversampling factor of the true-peak meter.
+const TRUE_PEAK_FACTOR: usize = 4;
+
+/// The number of taps per phase of the true-peak interpolation filter.
+const TRUE_PEAK_TAPS: usize = 12;
+
+/// Converts a mean-square energy to a loudness in LUFS.
+fn energy_to_loudness(energy: f64) -> f64 {
+    -0.691 + 10.0 * energy.log10()
+}
+
+/// Converts a loudness in LUFS to a mean-square energy.
+fn loudness_to_energy(loudness: f64) -> f64 {
+    10f64.powf((loudness + 0.691) / 10.0)
+}
+
+/// Gets the BS.1770 weighting of a channel.
+///
+/// Discrete channels are weighted as front channels. Of the ambisonic components, only the
+/// omnidirectional component (W) is measured.
+fn channel_weight(channel: ChannelLabel) -> f64 {
+    match channel {
+        ChannelLabel::Positioned(Channels::LFE1) | ChannelLabel::Positioned(Channels::LFE2) => 0.0,
+        ChannelLabel::Positioned(Channels::SIDE_LEFT)
+        | ChannelLabel::Positioned(Channels::SIDE_RIGHT)
+        | ChannelLabel::Positioned(Channels::REAR_LEFT)
+        | ChannelLabel::Positioned(Channels::REAR_RIGHT) => 1.41,
+        ChannelLabel::Ambisonic(acn) if acn > 0 => 0.0,
+        _ => 1.0,
+    }
+}
+
+/// A direct form I biquad filter.
+#[derive(Clone, Default)]
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    x: [f64; 2],
+    y: [f64; 2],
+}
+
+impl Biquad {
+    fn new(b: [f64; 3], a: [f64; 2]) -> Self {
+        Biquad { b, a, ..Default::default() }
+    }
+
+    #[inline]
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b[0] * x + self.b[1] * self.x[0] + self.b[2] * self.x[1]
+            - self.a[0] * self.y[0]
+            - self.a[1] * self.y[1];
+
+        self.x = [x, self.x[0]];
+        self.y = [y, self.y[0]];
+
+        y
+    }
+}
+
+/// Instantiates the two stages of the K-weighting filter, a high-shelf pre-filter and a high-pass
+/// RLB filter, for the given sample rate.
+fn k_weighting(sample_rate: u32) -> (Biquad, Biquad) {
+    let rate = f64::from(sample_rate);
+
+    // The stage 1 shelving filter.
+    let f0 = 1681.974450955533;
+    let g = 3.999843853973347;
+    let q = 0.7071752369554196;
+
+    let k = (PI * f0 / rate).tan();
+    let vh = 10f64.powf(g / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1.0 + k / q + k * k;
+
+    let shelf = Biquad::new(
+        [(vh + vb * k / q + k * k) / a0, 2.0 * (k * k - vh) / a0, (vh - vb * k / q + k * k) / a0],
+        [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+    );
+
+    // The stage 2 high-pass filter.
+    let f0 = 38.13547087602444;
+    let q = 0.5003270373238773;
+
+    let k = (PI * f0 / rate).tan();
+    let a0 = 1.0 + k / q + k * k;
+
+    let highpass =
+        Biquad::new([1.0, -2.0, 1.0], [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0]);
+
+    (shelf, highpass)
+}
+
+/// Generates the polyphase interpolation filter of the true-peak meter. The filter is a
+/// Hann-windowed sinc low-pass filter with a cut-off at the Nyquist frequency of the input.
+fn true_peak_filter() -> Vec<[f64; TRUE_PEAK_TAPS]> {
+    let len = TRUE_PEAK_FACTOR * TRUE_PEAK_TAPS;
+    let centre = (len - 1) as f64 / 2.0;
+
+    let mut phases = vec![[0.0; TRUE_PEAK_TAPS]; TRUE_PEAK_FACTOR];
+
+    for n in 0..len {
+        let t = (n as f64 - centre) / TRUE_PEAK_FACTOR as f64;
+
+        let sinc = if t == 0.0 { 1.0 } else { (PI * t).sin() / (PI * t) };
+        let window = 0.5 - 0.5 * (2.0 * PI * (n as f64 + 0.5) / len as f64).cos();
+
+        phases[n % TRUE_PEAK_FACTOR][n / TRUE_PEAK_FACTOR] = sinc * window;
+    }
+
+    phases
+}
+
+/// The per-channel state of a `LoudnessMeter`.
+#[derive(Clone)]
+struct ChannelState {
+    weight: f64,
+    shelf: Biquad,
+    highpass: Biquad,
+    /// The most recent input samples, newest last, for the true-peak interpolation filter.
+    history: [f64; TRUE_PEAK_TAPS],
+}
+
+/// `LoudnessMeter` measures loudness and true peak as defined in ITU-R BS.1770-4 and EBU R128.
+///
+/// Audio is measured in steps of 100 ms. Momentary loudness is measured over the last 400 ms, and
+/// short-term loudness over the last 3 s. Integrated loudness and loudness range are measured over
+/// all audio processed so far.
+pub struct LoudnessMeter {
+    spec: SignalSpec,
+    channels: Vec<ChannelState>,
+    filter: Vec<[f64; TRUE_PEAK_TAPS]>,
+    /// The number of frames per 100 ms step.
+    step_len: usize,
+    /// The number of frames accumulated in the current step.
+    step_frames: usize,
+    /// The weighted sum of squares of the current step.
+    step_energy: f64,
+    /// The weighted sums of squares of the most recent steps, newest last.
+    steps: VecDeque<f64>,
+    /// The mean-square energies of every momentary block.
+    momentary_blocks: Vec<f64>,
+    /// The mean-square energies of every short-term block.
+    short_term_blocks: Vec<f64>,
+    sample_peak: f64,
+    true_peak: f64,
+}
+
+impl LoudnessMeter {
+    /// Instantiates a `LoudnessMeter` for audio with the given signal specification.
+    pub fn new(spec: SignalSpec) -> Self {
+        let channels = spec
+            .channels
+            .labels()
+            .map(|channel| {
+                let (shelf, highpass) = k_weighting(spec.rate);
+
+                ChannelState {
+                    weight: channel_weight(channel),
+                    shelf,
+                    highpass,
+                    history: [0.0; TRUE_PEAK_TAPS],
+                }
+            })
+            .collect();
+
+        LoudnessMeter {
+            spec,
+            channels,
+            filter: true_peak_filter(),
+            step_len: ((f64::from(spec.rate) / 10.0).round() as usize).max(1),
+            step_frames: 0,
+            step_energy: 0.0,
+            steps: VecDeque::with_capacity(SHORT_TERM_STEPS),
+            momentary_blocks: Vec::new(),
+            short_term_blocks: Vec::new(),
+            sample_peak: 0.0,
+            true_peak: 0.0,
+        }
+    }
+
+    /// Gets the signal specification of the audio being measured.
+    pub fn spec(&self) -> &SignalSpec {
+        &self.spec
+    }
+
+    /// Measures a buffer of audio.
+    ///
+    /// The signal specification of the buffer must match that of the meter.
+    pub fn process<S>(&mut self, buf: &AudioBuffer<S>)
+    where
+        S: Sample + IntoSample<f64>,
+    {
+        assert_eq!(buf.spec(), &self.spec, "buffer signal specification must match the meter");
+
+        let n_frames = buf.frames();
+        let mut frame = 0;
+
+        while frame < n_frames {
+            let len = (self.step_len - self.step_frames).min(n_frames - frame);
+
+            for (c, state) in self.channels.iter_mut().enumerate() {
+                let mut sum = 0.0;
+
+                for &sample in &buf.chan(c)[frame..frame + len] {
+                    let x: f64 = sample.into_sample();
+
+                    // Sample peak.
+                    self.sample_peak = self.sample_peak.max(x.abs());
+
+                    // True peak.
+                    state.history.copy_within(1.., 0);
+                    state.history[TRUE_PEAK_TAPS - 1] = x;
+
+                    for phase in self.filter.iter() {
+                        let y: f64 =
+                            phase.iter().zip(state.history.iter()).map(|(h, x)| h * x).sum();
+                        self.true_peak = self.true_peak.max(y.abs());
+                    }
+
+                    // K-weighted energy.
+                    let y = state.highpass.process(state.shelf.process(x));
+                    sum += y * y;
+                }
+
+                self.step_energy += state.weight * sum;
+            }
+
+            frame += len;
+            self.step_frames += len;
+
+            if self.step_frames == self.step_len {
+                self.end_step();
+            }
+        }
+    }
+
+    /// Measures a buffer of audio of any sample format.
+    ///
+    /// The signal specification of the buffer must match that of the meter.
+    pub fn process_ref(&mut self, buf: &AudioBufferRef<'_>) {
+        match buf {
+            AudioBufferRef::U8(buf) => self.process(buf),
+            AudioBufferRef::U16(buf) => self.process(buf),
+            AudioBufferRef::U24(buf) => self.process(buf),
+            AudioBufferRef::U32(buf) => self.process(buf),
+            AudioBufferRef::S8(buf) => self.process(buf),
+            AudioBufferRef::S16(buf) => self.process(buf),
+            AudioBufferRef::S24(buf) => self.process(buf),
+            AudioBufferRef::S32(buf) => self.process(buf),
+            AudioBufferRef::F32(buf) => self.process(buf),
+            AudioBufferRef::F64(buf) => self.process(buf),
+        }
+    }
+
+    fn end_step(&mut self) {
+        if self.steps.len() == SHORT_TERM_STEPS {
+            self.steps.pop_front();
+        }
+
+        self.steps.push_back(self.step_energy);
+
+        self.step_energy = 0.0;
+        self.step_frames = 0;
+
+        if let Some(energy) = self.window_energy(MOMENTARY_STEPS) {
+            self.momentary_blocks.push(energy);
+        }
+
+        if let Some(energy) = self.window_energy(SHORT_TERM_STEPS) {
+            self.short_term_blocks.push(energy);
+        }
+    }
+
+    /// Gets the mean-square energy of the last `n_steps` steps, if that many steps were measured.
+    fn window_energy(&self, n_steps: usize) -> Option<f64> {
+        if self.steps.len() < n_steps {
+            return None;
+        }
+
+        let sum: f64 = self.steps.iter().rev().take(n_steps).sum();
+
+        Some(sum / (n_steps * self.step_len) as f64)
+    }
+
+    /// Clears all measurements.
+    pub fn reset(&mut self) {
+        *self = LoudnessMeter::new(self.spec);
+    }
+
+    /// Gets the momentary loudness, in LUFS, of the last 400 ms of audio. Returns negative
+    /// infinity if less than 400 ms of audio was measured.
+    pub fn momentary(&self) -> f64 {
+        self.window_energy(MOMENTARY_STEPS).map_or(f64::NEG_INFINITY, energy_to_loudness)
+    }
+
+    /// Gets the short-term loudness, in LUFS, of the last 3 s of audio. Returns negative infinity
+    /// if less than 3 s of audio was measured.
+    pub fn short_term(&self) -> f64 {
+        self.window_energy(SHORT_TERM_STEPS).map_or(f64::NEG_INFINITY, energy_to_loudness)
+    }
+
+    /// Gets the gated integrated loudness, in LUFS, of all audio measured. Returns negative
+    /// infinity if there was no audio above the absolute gate.
+    pub fn integrated(&self) -> f64 {
+        gated_loudness(&[self])
+    }
+
+    /// Gets the loudness range, in LU, of all audio measured as defined in EBU Tech 3342.
+    pub fn loudness_range(&self) -> f64 {
+        let absolute = loudness_to_energy(ABSOLUTE_GATE);
+
+        let gated: Vec<f64> =
+            self.short_term_blocks.iter().copied().filter(|&energy| energy > absolute).collect();
+
+        if gated.is_empty() {
+            return 0.0;
+        }
+
+        let relative = loudness_to_energy(
+            energy_to_loudness(gated.iter().sum::<f64>() / gated.len() as f64)
+                + RANGE_RELATIVE_GATE,
+        );
+
+        let mut loudness: Vec<f64> =
+            gated.into_iter().filter(|&energy| energy > relative).map(energy_to_loudness).collect();
+
+        if loudness.is_empty() {
+            return 0.0;
+        }
+
+        loudness.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        let percentile = |p: f64| loudness[((loudness.len() - 1) as f64 * p).round() as usize];
+
+        percentile(0.95) - percentile(0.10)
+    }
+
+    /// Gets the maximum absolute sample value of all audio measured, where 1.0 is full scale.
+    pub fn sample_peak(&self) -> f64 {
+        self.sample_peak
+    }
+
+    /// Gets the maximum absolute value of the 4x oversampled audio measured, where 1.0 is full
+    /// scale.
+    pub fn true_peak(&self) -> f64 {
+        self.true_peak.max(self.sample_peak)
+    }
+
+    /// Gets the true peak in dBTP.
+    pub fn true_peak_dbtp(&self) -> f64 {
+        20.0 * self.true_peak().log10()
+    }
+
+    /// Gets the ReplayGain 2.0 track gain and track peak tags of the audio measured.
+    pub fn replaygain_track_tags(&self) -> Vec<Tag> {
+        replaygain_tags(
+            self.integrated(),
+            self.true_peak(),
+            (StandardTagKey::ReplayGainTrackGain, "REPLAYGAIN_TRACK_GAIN"),
+            (StandardTagKey::ReplayGainTrackPeak, "REPLAYGAIN_TRACK_PEAK"),
+        )
+    }
+}
+
+/// Gets the gated integrated loudness, in LUFS, of the audio measured by several meters as if it
+/// were a single stream. This is the album loudness of a set of tracks.
+pub fn integrated_loudness(meters: &[&LoudnessMeter]) -> f64 {
+    gated_loudness(meters)
+}
+
+/// Gets the ReplayGain 2.0 album gain and album peak tags of the tracks measured by several meters.
+pub fn replaygain_album_tags(meters: &[&LoudnessMeter]) -> Vec<Tag> {
+    let peak = meters.iter().map(|meter| meter.true_peak()).fold(0.0, f64::max);
+
+    replaygain_tags(
+        gated_loudness(meters),
+        peak,
+        (StandardTagKey::ReplayGainAlbumGain, "REPLAYGAIN_ALBUM_GAIN"),
+        (StandardTagKey::ReplayGainAlbumPeak, "REPLAYGAIN_ALBUM_PEAK"),
+    )
+}
+
+fn gated_loudness(meters: &[&LoudnessMeter]) -> f64 {
+    let absolute = loudness_to_energy(ABSOLUTE_GATE);
+
+    let gated = || {
+        meters
+            .iter()
+            .flat_map(|meter| meter.momentary_blocks.iter().copied())
+            .filter(move |&energy| energy > absolute)
+    };
+
+    let (sum, count) = gated().fold((0.0, 0), |(sum, count), energy| (sum + energy, count + 1));
+
+    if count == 0 {
+        return f64::NEG_INFINITY;
+    }
+
+    let relative =
+        loudness_to_energy(energy_to_loudness(sum / count as f64) + INTEGRATED_RELATIVE_GATE);
+
+    let (sum, count) = gated()
+        .filter(|&energy| energy > relative)
+        .fold((0.0, 0), |(sum, count), energy| (sum + energy, count + 1));
+
+    energy_to_loudness(sum / count as f64)
+}
+
+fn replaygain_tags(
+    loudness: f64,
+    peak: f64,
+    gain_key: (StandardTagKey, &str),
+    peak_key: (StandardTagKey, &str),
+) -> Vec<Tag> {
+    // Without a measurable loudness there is no meaningful gain.
+    if !loudness.is_finite() {
+        return Vec::new();
+    }
+
+    let gain = REPLAYGAIN_REFERENCE_LOUDNESS - loudness;
+
+    vec![
+        Tag::new(Some(gain_key.0), gain_key.1, Value::from(format!("{:.2} dB", gain))),
+        Tag::new(Some(peak_key.0), peak_key.1, Value::from(format!("{:.6}", peak))),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::Layout;
+    use crate::meta::GainInfo;
+
+    fn sine(meter: &mut LoudnessMeter, freq: f64, amplitude: f64, phase: f64, seconds: f64) {
+        let spec = *meter.spec();
+        let n_frames = (seconds * f64::from(spec.rate)) as usize;
+
+        let mut buf = AudioBuffer::<f32>::new(n_frames as u64, spec);
+        buf.render_reserved(Some(n_frames));
+
+        for c in 0..spec.channels.count() {
+            for (i, s) in buf.chan_mut(c).iter_mut().enumerate() {
+                let t = i as f64 / f64::from(spec.rate);
+                *s = (amplitude * (2.0 * PI * freq * t + phase).sin()) as f32;
+            }
+        }
+
+        meter.process(&buf);
+    }
+
+    #[test]
+    fn verify_integrated_loudness() {
+        // EBU Tech 3341 case 1: a stereo 1 kHz sine at -23 dBFS measures -23 LUFS.
+        let mut meter = LoudnessMeter::new(SignalSpec::new_with_layout(48000, Layout::Stereo));
+
+        sine(&mut meter, 1000.0, 10f64.powf(-23.0 / 20.0), 0.0, 5.0);
+
+        assert!((meter.integrated() + 23.0).abs() < 0.1);
+        assert!((meter.momentary() + 23.0).abs() < 0.1);
+        assert!((meter.short_term() + 23.0).abs() < 0.1);
+
+        let tags = meter.replaygain_track_tags();
+        let gain = GainInfo::from_tags(&tags).unwrap();
+
+        assert!((gain.track_gain.unwrap() - 5.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn verify_loudness_range() {
+        // EBU Tech 3342 case 1: 20 s at -20 LUFS followed by 20 s at -30 LUFS has a range of
+        // 10 LU.
+        let mut meter = LoudnessMeter::new(SignalSpec::new_with_layout(8000, Layout::Stereo));
+
+        sine(&mut meter, 1000.0, 10f64.powf(-20.0 / 20.0), 0.0, 20.0);
+        sine(&mut meter, 1000.0, 10f64.powf(-30.0 / 20.0), 0.0, 20.0);
+
+        assert!((meter.loudness_range() - 10.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn verify_true_peak() {
+        // A sine at a quarter of the sample rate, sampled 45 degrees off its peaks, has a sample
+        // peak 3 dB below its true peak.
+        let mut meter = LoudnessMeter::new(SignalSpec::new_with_layout(48000, Layout::Mono));
+
+        sine(&mut meter, 12000.0, 1.0, PI / 4.0, 1.0);
+
+        assert!((meter.sample_peak() - std::f64::consts::FRAC_1_SQRT_2).abs() < 1e-3);
+        assert!(meter.true_peak_dbtp().abs() < 0.3);
+    }
+}
@@ -9,9 +9,91 @@
 //!
 //! The MDCT in this module is implemented in-terms of a forward FFT.
 
-use super::complex::Complex;
+use super::complex::{Complex, Float};
 use super::fft::*;
 
+/// The Modified Discrete Cosine Transform (MDCT).
+///
+/// The MDCT may be computed in single (`f32`) or double (`f64`) precision.
+pub struct Mdct<T: Float = f32> {
+    fft: ArbitraryFft<T>,
+    pre_twiddle: Box<[Complex<T>]>,
+    post_twiddle: Box<[Complex<T>]>,
+    buf: Box<[Complex<T>]>,
+}
+
+impl<T: Float> Mdct<T> {
+    /// Instantiate a N-point MDCT with no scaling.
+    ///
+    /// The value of `n` is the number of spectral samples and must be even.
+    pub fn new(n: usize) -> Self {
+        Mdct::new_scaled(n, 1.0)
+    }
+
+    /// Instantiate a N-point MDCT with scaling.
+    ///
+    /// The value of `n` is the number of spectral samples and must be even.
+    pub fn new_scaled(n: usize, scale: f64) -> Self {
+        // The MDCT is computed as a DCT-IV using a complex FFT of size N/2.
+        assert!(n > 0 && n % 2 == 0, "n must be even and greater than 0");
+
+        let n2 = n / 2;
+        let pi_n = std::f64::consts::PI / n as f64;
+
+        let pre_twiddle = (0..n2).map(|k| Complex::from_phase(-pi_n * (k as f64 + 0.25))).collect();
+
+        let post_twiddle = (0..n2)
+            .map(|k| Complex::from_phase(-pi_n * k as f64).scale(T::from_f64(scale)))
+            .collect();
+
+        Mdct {
+            fft: ArbitraryFft::new(n2),
+            pre_twiddle,
+            post_twiddle,
+            buf: vec![Default::default(); n2].into_boxed_slice(),
+        }
+    }
+
+    /// Performs the N-point Modified Discrete Cosine Transform.
+    ///
+    /// The number of input samples provided by the slice `src` must be 2N, where N is the value
+    /// that the MDCT was instantiated with. The length of the output slice, `spec`, must be N.
+    /// Failing to meet these requirements will throw an assertion.
+    pub fn mdct(&mut self, src: &[T], spec: &mut [T]) {
+        let n2 = self.fft.size();
+        let n = 2 * n2;
+
+        assert_eq!(src.len(), 2 * n);
+        assert_eq!(spec.len(), n);
+
+        // Split the input into quarters (a, b, c, d). The MDCT is the DCT-IV of the N samples
+        // (-c_r - d, a - b_r), where _r denotes reversal.
+        let fold = |i: usize| {
+            if i < n2 {
+                -src[n + n2 - 1 - i] - src[n + n2 + i]
+            }
+            else {
+                src[i - n2] - src[n - 1 - (i - n2)]
+            }
+        };
+
+        // Pre-FFT twiddling and packing of the even and reversed odd samples into complex values.
+        for (k, (z, &w)) in self.buf.iter_mut().zip(self.pre_twiddle.iter()).enumerate() {
+            *z = Complex::new(fold(2 * k), fold(n - 1 - 2 * k)) * w;
+        }
+
+        self.fft.fft_inplace(&mut self.buf);
+
+        // Post-FFT twiddling and unpacking.
+        for (k, (&z, &w)) in self.buf.iter().zip(self.post_twiddle.iter()).enumerate() {
+            let val = z * w;
+
+            spec[2 * k] = val.re;
+            spec[n - 1 - 2 * k] = -val.im;
+        }
+    }
+}
+
 /// The Inverse Modified Discrete Transform (IMDCT).
 pub struct Imdct {
     fft: Fft,
@@ -176,6 +258,62 @@ mod tests {
         }
     }
 
+    #[test]
+    fn verify_mdct() {
+        // A non power-of-two size.
+        const N: usize = 12;
+
+        let src: Vec<f64> = (0..2 * N).map(|i| (i as f64 * 0.61).sin() + 0.1).collect();
+
+        let scale = (2.0f64 / N as f64).sqrt();
+
+        let mut actual = vec![0.0; N];
+        Mdct::<f64>::new_scaled(N, scale).mdct(&src, &mut actual);
+
+        let pi_n = f64::consts::PI / N as f64;
+
+        for (k, &actual) in actual.iter().enumerate() {
+            let expected: f64 = src
+                .iter()
+                .enumerate()
+                .map(|(i, &x)| {
+                    x * (pi_n * (i as f64 + 0.5 + N as f64 / 2.0) * (k as f64 + 0.5)).cos()
+                })
+                .sum();
+
+            assert!((actual - scale * expected).abs() < 1e-12);
+        }
+
+        // Time-domain aliasing cancellation: the overlap-added IMDCT of the MDCT of consecutive
+        // windowed blocks reconstructs the signal.
+        let window: Vec<f32> = super::super::window::sine(64);
+        let signal: Vec<f32> = (0..96).map(|i| (i as f32 * 0.3).cos()).collect();
+
+        let mut mdct = Mdct::<f32>::new(32);
+        let mut imdct = Imdct::new_scaled(32, 2.0 / 32.0);
+
+        let mut out = vec![0f32; 96];
+
+        for block in 0..2 {
+            let windowed: Vec<f32> =
+                signal[32 * block..][..64].iter().zip(&window).map(|(x, w)| x * w).collect();
+
+            let mut spec = vec![0f32; 32];
+            mdct.mdct(&windowed, &mut spec);
+
+            let mut time = vec![0f32; 64];
+            imdct.imdct(&spec, &mut time);
+
+            for (o, (t, w)) in out[32 * block..][..64].iter_mut().zip(time.iter().zip(&window)) {
+                *o += t * w;
+            }
+        }
+
+        for (&actual, &expected) in out[32..64].iter().zip(&signal[32..64]) {
+            assert!((actual - expected).abs() < 1e-4);
+        }
+    }
+
     #[test]
     fn verify_imdct() {
         #[rustfmt::skip]
@@ -0,0 +1,587 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! The `mix` module implements channel remixing.
+//!
+//! A [`ChannelMixer`] maps the channels of an `AudioBuffer` to a different set of channels using a
+//! mixing matrix. The standard matrices downmix using the coefficients of ITU-R BS.775, upmix by
+//! routing each channel to its equivalent output channel, and may be adjusted by the downmix levels
+//! signalled in a bitstream.
+
+use std::f32::consts::FRAC_1_SQRT_2;
+
+use crate::audio::{AudioBuffer, ChannelLabel, Channels, Signal};
+use crate::sample::Sample;
+
+/// `DownmixLevels` are the gains applied to channels that are not present in the output of a
+/// downmix.
+///
+/// Some codecs signal the downmix levels the content was mastered for, such as the `cmixlev` and
+/// `surmixlev` fields of AC-3, or the matrix-mixdown of an AAC program config element. A decoder
+/// that reads these levels provides them in the `downmix` field of its `CodecParameters`. The
+/// decoders in Symphonia do not currently provide downmix levels, however, an application that
+/// reads them itself may convert them with `from_ac3` or `from_aac_matrix_mixdown`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct DownmixLevels {
+    /// The linear gain of the centre channel when mixed into the left and right channels.
+    pub centre: f32,
+    /// The linear gain of a surround channel when mixed into the front channels.
+    pub surround: f32,
+    /// The linear gain of the low-frequency channel when mixed into the left and right channels.
+    pub lfe: f32,
+    /// If true, the surround channels are mixed out-of-phase into the left channel and in-phase
+    /// into the right channel so that they may be recovered by a matrix surround decoder.
+    pub pseudo_surround: bool,
+    /// If true, the downmix is attenuated such that it cannot clip.
+    pub normalize: bool,
+}
+
+impl Default for DownmixLevels {
+    /// The ITU-R BS.775 downmix levels. The centre and surround channels are attenuated by 3 dB,
+    /// and the low-frequency channel is discarded.
+    fn default() -> Self {
+        DownmixLevels {
+            centre: FRAC_1_SQRT_2,
+            surround: FRAC_1_SQRT_2,
+            lfe: 0.0,
+            pseudo_surround: false,
+            normalize: false,
+        }
+    }
+}
+
+impl DownmixLevels {
+    /// Gets the downmix levels signalled by the `cmixlev` and `surmixlev` fields of an AC-3
+    /// bitstream information header. Reserved values select the default level.
+    pub fn from_ac3(cmixlev: u8, surmixlev: u8) -> Self {
+        let centre = match cmixlev {
+            1 => 0.595, // -4.5 dB
+            2 => 0.5,   // -6 dB
+            _ => FRAC_1_SQRT_2,
+        };
+
+        let surround = match surmixlev {
+            1 => 0.5,
+            2 => 0.0,
+            _ => FRAC_1_SQRT_2,
+        };
+
+        DownmixLevels { centre, surround, ..Default::default() }
+    }
+
+    /// Gets the downmix levels signalled by the `matrix_mixdown_idx` and `pseudo_surround_enable`
+    /// fields of an AAC program config element, as defined in ISO/IEC 14496-3.
+    pub fn from_aac_matrix_mixdown(matrix_mixdown_idx: u8, pseudo_surround: bool) -> Self {
+        let surround = match matrix_mixdown_idx & 0x3 {
+            0 => FRAC_1_SQRT_2,
+            1 => 0.5,
+            2 => 0.5 * FRAC_1_SQRT_2,
+            _ => 0.0,
+        };
+
+        DownmixLevels {
+            centre: FRAC_1_SQRT_2,
+            surround,
+            lfe: 0.0,
+            pseudo_surround,
+            normalize: true,
+        }
+    }
+}
+
+/// Gets the index of `channel` within the channels of an audio buffer with the channels `channels`.
+fn channel_index(channels: Channels, channel: Channels) -> usize {
+    channels.index_of(ChannelLabel::Positioned(channel)).expect("channel must be present")
+}
+
+/// Gets the output channels, and their gains, that a channel should be mixed into if the channel
+/// is not present in the output. The first candidate whose channels are all present in the output
+/// is used.
+fn fallbacks(channel: Channels, levels: &DownmixLevels) -> Vec<Vec<(Channels, f32)>> {
+    const H: f32 = FRAC_1_SQRT_2;
+
+    let c = levels.centre;
+    let s = levels.surround;
+
+    // A surround channel mixed into the front channels. Pseudo-surround mixes all surround
+    // channels out-of-phase into the left channel, and in-phase into the right channel.
+    let front = |gain: f32, left: bool| {
+        if levels.pseudo_surround {
+            vec![(Channels::FRONT_LEFT, -gain), (Channels::FRONT_RIGHT, gain)]
+        }
+        else if left {
+            vec![(Channels::FRONT_LEFT, gain)]
+        }
+        else {
+            vec![(Channels::FRONT_RIGHT, gain)]
+        }
+    };
+
+    let surround = |alternatives: &[Channels], left: bool| {
+        let mut candidates: Vec<_> = alternatives.iter().map(|&alt| vec![(alt, 1.0)]).collect();
+
+        if s > 0.0 {
+            candidates.push(front(s, left));
+        }
+
+        candidates
+    };
+
+    match channel {
+        Channels::FRONT_CENTRE => {
+            vec![vec![(Channels::FRONT_LEFT, c), (Channels::FRONT_RIGHT, c)]]
+        }
+        Channels::FRONT_LEFT_CENTRE | Channels::FRONT_LEFT_WIDE => {
+            vec![vec![(Channels::FRONT_LEFT, 1.0)]]
+        }
+        Channels::FRONT_RIGHT_CENTRE | Channels::FRONT_RIGHT_WIDE => {
+            vec![vec![(Channels::FRONT_RIGHT, 1.0)]]
+        }
+        Channels::FRONT_LEFT_HIGH | Channels::TOP_FRONT_LEFT => {
+            vec![vec![(Channels::FRONT_LEFT, H)]]
+        }
+        Channels::FRONT_RIGHT_HIGH | Channels::TOP_FRONT_RIGHT => {
+            vec![vec![(Channels::FRONT_RIGHT, H)]]
+        }
+        Channels::FRONT_CENTRE_HIGH | Channels::TOP_FRONT_CENTRE => vec![
+            vec![(Channels::FRONT_CENTRE, H)],
+            vec![(Channels::FRONT_LEFT, H * c), (Channels::FRONT_RIGHT, H * c)],
+        ],
+        Channels::TOP_CENTRE => vec![
+            vec![(Channels::FRONT_CENTRE, H)],
+            vec![(Channels::FRONT_LEFT, 0.5), (Channels::FRONT_RIGHT, 0.5)],
+        ],
+        // Side and rear channels are interchangeable. For example, 5.1 content may use either.
+        Channels::SIDE_LEFT => surround(&[Channels::REAR_LEFT], true),
+        Channels::SIDE_RIGHT => surround(&[Channels::REAR_RIGHT], false),
+        Channels::REAR_LEFT => surround(&[Channels::SIDE_LEFT], true),
+        Channels::REAR_RIGHT => surround(&[Channels::SIDE_RIGHT], false),
+        Channels::REAR_LEFT_CENTRE => surround(&[Channels::REAR_LEFT, Channels::SIDE_LEFT], true),
+        Channels::REAR_RIGHT_CENTRE => {
+            surround(&[Channels::REAR_RIGHT, Channels::SIDE_RIGHT], false)
+        }
+        Channels::REAR_CENTRE => {
+            let mut candidates = vec![
+                vec![(Channels::REAR_LEFT, H), (Channels::REAR_RIGHT, H)],
+                vec![(Channels::SIDE_LEFT, H), (Channels::SIDE_RIGHT, H)],
+            ];
+
+            if s > 0.0 {
+                if levels.pseudo_surround {
+                    candidates.push(front(s, true));
+                }
+                else {
+                    candidates
+                        .push(vec![(Channels::FRONT_LEFT, s * H), (Channels::FRONT_RIGHT, s * H)]);
+                }
+            }
+
+            candidates
+        }
+        Channels::TOP_REAR_LEFT => vec![
+            vec![(Channels::REAR_LEFT, H)],
+            vec![(Channels::SIDE_LEFT, H)],
+            vec![(Channels::FRONT_LEFT, s * H)],
+        ],
+        Channels::TOP_REAR_RIGHT => vec![
+            vec![(Channels::REAR_RIGHT, H)],
+            vec![(Channels::SIDE_RIGHT, H)],
+            vec![(Channels::FRONT_RIGHT, s * H)],
+        ],
+        Channels::TOP_REAR_CENTRE => vec![
+            vec![(Channels::REAR_CENTRE, H)],
+            vec![(Channels::REAR_LEFT, 0.5), (Channels::REAR_RIGHT, 0.5)],
+            vec![(Channels::SIDE_LEFT, 0.5), (Channels::SIDE_RIGHT, 0.5)],
+            vec![(Channels::FRONT_LEFT, 0.5 * s), (Channels::FRONT_RIGHT, 0.5 * s)],
+        ],
+        Channels::LFE1 => vec![
+            vec![(Channels::LFE2, 1.0)],
+            vec![(Channels::FRONT_LEFT, levels.lfe), (Channels::FRONT_RIGHT, levels.lfe)],
+        ],
+        Channels::LFE2 => vec![
+            vec![(Channels::LFE1, 1.0)],
+            vec![(Channels::FRONT_LEFT, levels.lfe), (Channels::FRONT_RIGHT, levels.lfe)],
+        ],
+        _ => Vec::new(),
+    }
+}
+
+/// `ChannelMixer` remixes audio from one set of channels to another using a mixing matrix.
+#[derive(Clone, Debug)]
+pub struct ChannelMixer {
+    input: Channels,
+    output: Channels,
+    /// The mixing matrix, in row-major order, with a row per output channel and a column per input
+    /// channel.
+    matrix: Vec<f32>,
+}
+
+impl ChannelMixer {
+    /// Instantiates a `ChannelMixer` that remixes the channels `input` to the channels `output`
+    /// using the ITU-R BS.775 downmix levels.
+    pub fn new(input: Channels, output: Channels) -> Self {
+        ChannelMixer::with_levels(input, output, &Default::default())
+    }
+
+    /// Instantiates a `ChannelMixer` that remixes the channels `input` to the channels `output`
+    /// using the provided downmix levels.
+    ///
+    /// Channels present in both the input and output are passed through. A channel missing from
+    /// the output is mixed into the nearest output channels. A mono input is treated as a centre
+    /// channel, and is mixed into the left and right channels if there is no centre channel in the
+    /// output. A mono output is the sum of a stereo downmix with each channel attenuated by 3 dB.
+    /// No channels are synthesized when upmixing. Ambisonic components and discrete channels are
+    /// only passed through.
+    pub fn with_levels(input: Channels, output: Channels, levels: &DownmixLevels) -> Self {
+        let n_in = input.count();
+        let n_out = output.count();
+
+        let mut mixer = ChannelMixer { input, output, matrix: vec![0.0; n_in * n_out] };
+
+        // Ambisonic components and discrete channels have no position. They are passed through if
+        // present in the output, and dropped otherwise.
+        for (i, label) in input.labels().enumerate() {
+            if let ChannelLabel::Positioned(_) = label {
+                continue;
+            }
+
+            if let Some(o) = output.index_of(label) {
+                mixer.matrix[o * n_in + i] = 1.0;
+            }
+        }
+
+        let input_pos = input.positioned();
+        let output_pos = output.positioned();
+
+        let n_in_pos = input_pos.count();
+        let n_out_pos = output_pos.count();
+
+        if n_in_pos == 0 || n_out_pos == 0 {
+            return mixer;
+        }
+
+        let stereo = Channels::FRONT_LEFT | Channels::FRONT_RIGHT;
+
+        if n_out_pos == 1 && n_in_pos > 1 {
+            // Downmix to stereo, then sum the stereo channels.
+            let downmix = ChannelMixer::with_levels(input_pos, stereo, levels);
+            let o = channel_index(output, output_pos);
+
+            for (j, channel) in input_pos.iter().enumerate() {
+                let i = channel_index(input, channel);
+                mixer.matrix[o * n_in + i] =
+                    FRAC_1_SQRT_2 * (downmix.matrix[j] + downmix.matrix[n_in_pos + j]);
+            }
+        }
+        else if n_in_pos == 1 {
+            // A mono input is a centre channel. If there is no centre channel in the output, it is
+            // mixed into the left and right channels, otherwise the first output channel.
+            let targets = if n_out_pos > 1 && output.contains(Channels::FRONT_CENTRE) {
+                vec![(Channels::FRONT_CENTRE, 1.0)]
+            }
+            else if n_out_pos > 1 && output.contains(stereo) {
+                vec![(Channels::FRONT_LEFT, FRAC_1_SQRT_2), (Channels::FRONT_RIGHT, FRAC_1_SQRT_2)]
+            }
+            else {
+                vec![(Channels::from_bits_truncate(1 << output.bits().trailing_zeros()), 1.0)]
+            };
+
+            let i = channel_index(input, input_pos);
+
+            for (target, gain) in targets {
+                mixer.matrix[channel_index(output, target) * n_in + i] += gain;
+            }
+        }
+        else {
+            for channel in input.iter() {
+                let i = channel_index(input, channel);
+
+                if output.contains(channel) {
+                    mixer.matrix[channel_index(output, channel) * n_in + i] += 1.0;
+                    continue;
+                }
+
+                let candidates = fallbacks(channel, levels);
+
+                let targets = candidates
+                    .iter()
+                    .find(|targets| targets.iter().all(|&(target, _)| output.contains(target)));
+
+                if let Some(targets) = targets {
+                    for &(target, gain) in targets {
+                        mixer.matrix[channel_index(output, target) * n_in + i] += gain;
+                    }
+                }
+            }
+        }
+
+        if levels.normalize {
+            mixer.normalize();
+        }
+
+        mixer
+    }
+
+    /// Instantiates a `ChannelMixer` that remixes the channels `input` to the channels `output`
+    /// using a user-supplied mixing matrix.
+    ///
+    /// The matrix is in row-major order, with a row per output channel and a column per input
+    /// channel. Rows and columns are ordered in the same order as the channels of an audio buffer.
+    pub fn from_matrix(input: Channels, output: Channels, matrix: &[f32]) -> Self {
+        assert_eq!(matrix.len(), input.count() * output.count(), "invalid matrix dimensions");
+
+        ChannelMixer { input, output, matrix: matrix.to_vec() }
+    }
+
+    /// Gets the input channels.
+    pub fn input(&self) -> Channels {
+        self.input
+    }
+
+    /// Gets the output channels.
+    pub fn output(&self) -> Channels {
+        self.output
+    }
+
+    /// Gets the mixing matrix in row-major order.
+    pub fn matrix(&self) -> &[f32] {
+        &self.matrix
+    }
+
+    /// Gets the gain with which the input channel `input` is mixed into the output channel
+    /// `output`. Returns 0 if either channel is not present.
+    pub fn gain(&self, input: Channels, output: Channels) -> f32 {
+        if !self.input.contains(input) || !self.output.contains(output) || input.count() != 1 {
+            return 0.0;
+        }
+
+        let n_in = self.input.count();
+
+        self.matrix[channel_index(self.output, output) * n_in + channel_index(self.input, input)]
+    }
+
+    /// Attenuates the mixing matrix such that the sum of the absolute gains of each output channel
+    /// does not exceed 1, and therefore the output cannot clip.
+    pub fn normalize(&mut self) {
+        let n_in = self.input.count().max(1);
+
+        let max_sum = self
+            .matrix
+            .chunks_exact(n_in)
+            .map(|row| row.iter().map(|gain| gain.abs()).sum::<f32>())
+            .fold(0.0, f32::max);
+
+        if max_sum > 1.0 {
+            self.matrix.iter_mut().for_each(|gain| *gain /= max_sum);
+        }
+    }
+
+    /// Remixes `input` into `output`. The output buffer is cleared, and then filled with the same
+    /// number of frames as the input.
+    ///
+    /// The channels of the input and output buffers must match the input and output channels of
+    /// the mixer, and the capacity of the output buffer must be large enough for the input.
+    pub fn mix<S>(&self, input: &AudioBuffer<S>, output: &mut AudioBuffer<S>)
+    where
+        S: Sample + core::ops::Mul<Output = S> + From<f32>,
+    {
+        assert_eq!(input.spec().channels, self.input, "input channels must match the mixer");
+        assert_eq!(output.spec().channels, self.output, "output channels must match the mixer");
+        assert!(output.capacity() >= input.frames(), "output buffer is too small");
+
+        let n_in = self.input.count();
+
+        output.clear();
+        output.render_reserved(Some(input.frames()));
+
+        for (o, row) in self.matrix.chunks_exact(n_in.max(1)).enumerate() {
+            let dst = output.chan_mut(o);
+
+            dst.iter_mut().for_each(|sample| *sample = S::MID);
+
+            for (i, &gain) in row.iter().enumerate() {
+                if gain == 0.0 {
+                    continue;
+                }
+
+                let gain = S::from(gain);
+
+                for (out, &sample) in dst.iter_mut().zip(input.chan(i)) {
+                    *out = *out + sample * gain;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::{Layout, SignalSpec};
+
+    const FIVE_POINT_ONE: Channels = Channels::from_bits_truncate(
+        Channels::FRONT_LEFT.bits()
+            | Channels::FRONT_RIGHT.bits()
+            | Channels::FRONT_CENTRE.bits()
+            | Channels::LFE1.bits()
+            | Channels::SIDE_LEFT.bits()
+            | Channels::SIDE_RIGHT.bits(),
+    );
+
+    const STEREO: Channels =
+        Channels::from_bits_truncate(Channels::FRONT_LEFT.bits() | Channels::FRONT_RIGHT.bits());
+
+    #[test]
+    fn verify_bs775_downmix() {
+        let h = FRAC_1_SQRT_2;
+
+        // 5.1 to stereo.
+        let mixer = ChannelMixer::new(FIVE_POINT_ONE, STEREO);
+
+        #[rustfmt::skip]
+        assert_eq!(
+            mixer.matrix(),
+            &[
+            //  FL   FR   FC   LFE  SL   SR
+                1.0, 0.0, h,   0.0, h,   0.0,
+                0.0, 1.0, h,   0.0, 0.0, h,
+            ]
+        );
+
+        // 5.1 to mono.
+        let mixer = ChannelMixer::new(FIVE_POINT_ONE, Channels::FRONT_LEFT);
+
+        for (&actual, &expected) in mixer.matrix().iter().zip(&[h, h, 1.0, 0.0, 0.5, 0.5]) {
+            assert!((actual - expected).abs() < 1e-6);
+        }
+
+        // 7.1 to 5.1 (rear).
+        let seven_point_one = FIVE_POINT_ONE | Channels::REAR_LEFT | Channels::REAR_RIGHT;
+        let five_point_one_rear = STEREO
+            | Channels::FRONT_CENTRE
+            | Channels::LFE1
+            | Channels::REAR_LEFT
+            | Channels::REAR_RIGHT;
+
+        let mixer = ChannelMixer::new(seven_point_one, five_point_one_rear);
+
+        assert_eq!(mixer.gain(Channels::SIDE_LEFT, Channels::REAR_LEFT), 1.0);
+        assert_eq!(mixer.gain(Channels::REAR_LEFT, Channels::REAR_LEFT), 1.0);
+        assert_eq!(mixer.gain(Channels::SIDE_LEFT, Channels::FRONT_LEFT), 0.0);
+
+        // Ambisonic and discrete channels are passed through, and positioned channels are mixed.
+        let input =
+            Channels::ambisonic(1).unwrap() | Channels::FRONT_CENTRE | Channels::discrete(2);
+        let output = Channels::ambisonic(1).unwrap() | STEREO | Channels::discrete(1);
+        let mixer = ChannelMixer::new(input, output);
+
+        #[rustfmt::skip]
+        let expected = [
+            1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0,
+            0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0,
+            0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0,
+            0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0,
+            0.0, 0.0, 0.0, 0.0, h,   0.0, 0.0,
+            0.0, 0.0, 0.0, 0.0, h,   0.0, 0.0,
+            0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0,
+        ];
+
+        assert_eq!(mixer.matrix(), &expected[..]);
+    }
+
+    #[test]
+    fn verify_upmix_and_levels() {
+        let h = FRAC_1_SQRT_2;
+
+        // Mono to stereo.
+        let mixer = ChannelMixer::new(Channels::FRONT_LEFT, STEREO);
+        assert_eq!(mixer.matrix(), &[h, h]);
+
+        // Mono to 5.1 is routed to the centre channel.
+        let mixer = ChannelMixer::new(Channels::FRONT_LEFT, FIVE_POINT_ONE);
+        assert_eq!(mixer.gain(Channels::FRONT_LEFT, Channels::FRONT_CENTRE), 1.0);
+        assert_eq!(mixer.gain(Channels::FRONT_LEFT, Channels::FRONT_LEFT), 0.0);
+
+        // Stereo to 5.1 passes the front channels through.
+        let mixer = ChannelMixer::new(STEREO, FIVE_POINT_ONE);
+        assert_eq!(mixer.matrix(), &[1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]);
+
+        // AAC matrix-mixdown with pseudo surround.
+        let levels = DownmixLevels::from_aac_matrix_mixdown(1, true);
+        let mixer = ChannelMixer::with_levels(FIVE_POINT_ONE, STEREO, &levels);
+
+        let norm = 1.0 + h + 2.0 * 0.5;
+
+        #[rustfmt::skip]
+        let expected = [
+            1.0, 0.0, h, 0.0, -0.5, -0.5,
+            0.0, 1.0, h, 0.0,  0.5,  0.5,
+        ];
+
+        for (&actual, &expected) in mixer.matrix().iter().zip(&expected) {
+            assert!((actual - expected / norm).abs() < 1e-6);
+        }
+
+        // AC-3 levels.
+        let levels = DownmixLevels::from_ac3(2, 2);
+        let mixer = ChannelMixer::with_levels(FIVE_POINT_ONE, STEREO, &levels);
+
+        assert_eq!(mixer.gain(Channels::FRONT_CENTRE, Channels::FRONT_LEFT), 0.5);
+        assert_eq!(mixer.gain(Channels::SIDE_LEFT, Channels::FRONT_LEFT), 0.0);
+
+        // Ambisonic and discrete channels are passed through, and positioned channels are mixed.
+        let input =
+            Channels::ambisonic(1).unwrap() | Channels::FRONT_CENTRE | Channels::discrete(2);
+        let output = Channels::ambisonic(1).unwrap() | STEREO | Channels::discrete(1);
+        let mixer = ChannelMixer::new(input, output);
+
+        #[rustfmt::skip]
+        let expected = [
+            1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0,
+            0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0,
+            0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0,
+            0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0,
+            0.0, 0.0, 0.0, 0.0, h,   0.0, 0.0,
+            0.0, 0.0, 0.0, 0.0, h,   0.0, 0.0,
+            0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0,
+        ];
+
+        assert_eq!(mixer.matrix(), &expected[..]);
+    }
+
+    #[test]
+    fn verify_mix() {
+        let mut input =
+            AudioBuffer::<f32>::new(4, SignalSpec::new_with_layout(48000, Layout::FivePointOne));
+        input.render_reserved(None);
+
+        for c in 0..6 {
+            input.chan_mut(c).iter_mut().for_each(|s| *s = (c + 1) as f32);
+        }
+
+        let mixer = ChannelMixer::new(input.spec().channels, STEREO);
+
+        let mut output = AudioBuffer::<f32>::new(4, SignalSpec::new(48000, STEREO));
+        mixer.mix(&input, &mut output);
+
+        // FL + h * FC + h * RL, where the 5.1 layout uses the rear channels.
+        let h = FRAC_1_SQRT_2;
+
+        assert_eq!(output.frames(), 4);
+        assert!(output.chan(0).iter().all(|&s| (s - (1.0 + 3.0 * h + 5.0 * h)).abs() < 1e-6));
+        assert!(output.chan(1).iter().all(|&s| (s - (2.0 + 3.0 * h + 6.0 * h)).abs() < 1e-6));
+
+        // User-supplied matrix that swaps the channels.
+        let mixer = ChannelMixer::from_matrix(STEREO, STEREO, &[0.0, 1.0, 1.0, 0.0]);
+
+        let mut swapped = AudioBuffer::<f32>::new(4, SignalSpec::new(48000, STEREO));
+        mixer.mix(&output, &mut swapped);
+
+        assert_eq!(swapped.chan(0), output.chan(1));
+        assert_eq!(swapped.chan(1), output.chan(0));
+    }
+}
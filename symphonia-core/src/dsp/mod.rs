@@ -7,6 +7,13 @@
 
 //! The `dsp` module provides efficient implementations of common signal processing algorithms.
 
+pub mod analysis;
 pub mod complex;
 pub mod fft;
+pub mod loudness;
 pub mod mdct;
+pub mod mix;
+pub mod resample;
+pub mod rfft;
+pub mod simd;
+pub mod window;
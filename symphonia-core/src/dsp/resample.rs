@@ -0,0 +1,591 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! The `resample` module implements sample-rate conversion.
+//!
+//! A [`Resampler`] converts a stream of audio, delivered as successive `AudioBuffer`s, from one
+//! sample rate to another. The conversion ratio may be rational (e.g., 44.1 kHz to 48 kHz), or an
+//! arbitrary fraction. The highest quality conversion uses windowed-sinc interpolation with a
+//! polyphase filter bank, while linear and cubic interpolation are provided for when speed is
+//! more important than quality.
+
+use std::f64::consts::PI;
+
+use crate::audio::{AudioBuffer, Signal, SignalSpec};
+use crate::sample::Sample;
+
+use super::window::bessel_i0;
+
+/// `ResampleSample` is implemented for the sample formats a `Resampler` may operate on.
+pub trait ResampleSample: Sample + core::ops::Mul<Output = Self> {
+    /// Converts a `f64` filter coefficient into the sample format.
+    fn from_f64(value: f64) -> Self;
+}
+
+impl ResampleSample for f32 {
+    #[inline(always)]
+    fn from_f64(value: f64) -> Self {
+        value as f32
+    }
+}
+
+impl ResampleSample for f64 {
+    #[inline(always)]
+    fn from_f64(value: f64) -> Self {
+        value
+    }
+}
+
+/// `ResampleQuality` is a preset that trades conversion quality for CPU usage.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ResampleQuality {
+    /// Linear interpolation. Very fast, but introduces significant aliasing and attenuates high
+    /// frequencies.
+    Linear,
+    /// Cubic (Catmull-Rom) interpolation. Fast, but introduces aliasing.
+    Cubic,
+    /// Windowed-sinc interpolation with a short filter.
+    Low,
+    /// Windowed-sinc interpolation with a medium length filter. Suitable for playback.
+    Medium,
+    /// Windowed-sinc interpolation with a long filter. Suitable for mastering.
+    High,
+}
+
+impl Default for ResampleQuality {
+    fn default() -> Self {
+        ResampleQuality::Medium
+    }
+}
+
+/// The parameters of a windowed-sinc filter.
+struct SincParams {
+    /// The number of filter taps on each side of the interpolation point when upsampling.
+    half_len: usize,
+    /// The maximum number of filter phases.
+    n_phases: usize,
+    /// The cutoff frequency relative to the Nyquist frequency.
+    cutoff: f64,
+    /// The beta parameter of the Kaiser window.
+    beta: f64,
+}
+
+impl ResampleQuality {
+    fn sinc_params(self) -> Option<SincParams> {
+        match self {
+            ResampleQuality::Linear | ResampleQuality::Cubic => None,
+            ResampleQuality::Low => {
+                Some(SincParams { half_len: 8, n_phases: 128, cutoff: 0.85, beta: 6.0 })
+            }
+            ResampleQuality::Medium => {
+                Some(SincParams { half_len: 16, n_phases: 512, cutoff: 0.91, beta: 8.6 })
+            }
+            ResampleQuality::High => {
+                Some(SincParams { half_len: 32, n_phases: 2048, cutoff: 0.95, beta: 12.0 })
+            }
+        }
+    }
+}
+
+/// The interpolation method of a `Resampler`.
+enum Interpolator<S: ResampleSample> {
+    Linear,
+    Cubic,
+    Sinc {
+        /// The filter bank. Each row of `n_taps` coefficients is a filter phase.
+        table: Vec<S>,
+        n_taps: usize,
+        n_phases: usize,
+        /// If true, there is one phase for every possible fractional position. Otherwise, there
+        /// is an additional phase, and the output is interpolated between adjacent phases.
+        exact: bool,
+    },
+}
+
+impl<S: ResampleSample> Interpolator<S> {
+    /// Interpolates a sample at the fractional position `frac / den` following `taps[half_len - 1]`.
+    /// The `taps` slice starts `half_len - 1` samples before the integer position.
+    #[inline]
+    fn interpolate(&self, taps: &[S], frac: u64, den: u64) -> S {
+        match self {
+            Interpolator::Linear => {
+                let f = S::from_f64(frac as f64 / den as f64);
+                taps[0] + (taps[1] - taps[0]) * f
+            }
+            Interpolator::Cubic => {
+                let f = frac as f64 / den as f64;
+                let f2 = f * f;
+                let f3 = f2 * f;
+
+                // Catmull-Rom spline coefficients.
+                let c0 = S::from_f64(0.5 * (-f3 + 2.0 * f2 - f));
+                let c1 = S::from_f64(0.5 * (3.0 * f3 - 5.0 * f2 + 2.0));
+                let c2 = S::from_f64(0.5 * (-3.0 * f3 + 4.0 * f2 + f));
+                let c3 = S::from_f64(0.5 * (f3 - f2));
+
+                taps[0] * c0 + taps[1] * c1 + taps[2] * c2 + taps[3] * c3
+            }
+            Interpolator::Sinc { table, n_taps, n_phases, exact } => {
+                let taps = &taps[..*n_taps];
+
+                if *exact {
+                    let phase = frac as usize;
+                    dot(taps, &table[phase * n_taps..(phase + 1) * n_taps])
+                }
+                else {
+                    let pos = frac as f64 * *n_phases as f64 / den as f64;
+                    let phase = pos as usize;
+                    let w = S::from_f64(pos - phase as f64);
+
+                    let y0 = dot(taps, &table[phase * n_taps..(phase + 1) * n_taps]);
+                    let y1 = dot(taps, &table[(phase + 1) * n_taps..(phase + 2) * n_taps]);
+
+                    y0 + (y1 - y0) * w
+                }
+            }
+        }
+    }
+}
+
+#[inline(always)]
+fn dot<S: ResampleSample>(a: &[S], b: &[S]) -> S {
+    a.iter().zip(b).fold(S::MID, |acc, (&a, &b)| acc + a * b)
+}
+
+/// Generates a polyphase windowed-sinc filter bank of `n_rows` phases, where row `p` is the filter
+/// for the fractional position `p / n_phases`. Returns the filter bank and the number of taps on
+/// each side of the interpolation point.
+fn sinc_table<S: ResampleSample>(
+    params: &SincParams,
+    ratio: f64,
+    n_phases: usize,
+    n_rows: usize,
+) -> (Vec<S>, usize) {
+    // When downsampling, the cutoff frequency must be lowered to the output Nyquist frequency, and
+    // the filter lengthened proportionally to maintain the transition band.
+    let scale = ratio.min(1.0);
+
+    // If the sample rate is unchanged, the filter is an identity.
+    let cutoff = if ratio == 1.0 { 1.0 } else { params.cutoff * scale };
+
+    let half_width = params.half_len as f64 / scale;
+    let half_len = half_width.ceil() as usize;
+    let n_taps = 2 * half_len;
+
+    let i0_beta = bessel_i0(params.beta);
+
+    let mut table = Vec::with_capacity(n_rows * n_taps);
+    let mut row = vec![0.0; n_taps];
+
+    for p in 0..n_rows {
+        let frac = p as f64 / n_phases as f64;
+
+        for (j, h) in row.iter_mut().enumerate() {
+            // The distance between the interpolation point and the tap.
+            let x = frac - (j as f64 - (half_len - 1) as f64);
+
+            *h = if x.abs() >= half_width {
+                0.0
+            }
+            else {
+                let sinc = if x == 0.0 { 1.0 } else { (PI * cutoff * x).sin() / (PI * cutoff * x) };
+
+                let r = x / half_width;
+                let window = bessel_i0(params.beta * (1.0 - r * r).sqrt()) / i0_beta;
+
+                cutoff * sinc * window
+            };
+        }
+
+        // Normalize each phase to unity gain at DC.
+        let sum: f64 = row.iter().sum();
+
+        table.extend(row.iter().map(|&h| S::from_f64(h / sum)));
+    }
+
+    (table, half_len)
+}
+
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        let t = a % b;
+        a = b;
+        b = t;
+    }
+    a
+}
+
+/// `Resampler` converts audio from one sample rate to another.
+///
+/// Audio is resampled in a streaming manner. Each call to [`Resampler::resample`] consumes an
+/// `AudioBuffer` and returns as many output frames as can be computed. Since the interpolation
+/// filter requires future input, the output lags the input by [`Resampler::latency`] input frames.
+/// At the end of the stream, [`Resampler::flush`] returns the remaining output frames. The output
+/// is time-aligned with the input, such that the first output frame corresponds to the first
+/// input frame, and an input of `N` frames yields `ceil(N * ratio)` output frames in total.
+pub struct Resampler<S: ResampleSample> {
+    out_spec: SignalSpec,
+    ratio: f64,
+    interp: Interpolator<S>,
+    /// The number of input frames on each side of the interpolation point used by the filter.
+    half_len: usize,
+    /// The integer part of the input step per output frame.
+    step_int: usize,
+    /// The fractional part of the input step per output frame, in units of `1 / den`.
+    step_frac: u64,
+    den: u64,
+    /// The buffered input of each channel.
+    history: Vec<Vec<S>>,
+    /// The integer part of the input position of the next output frame, relative to the history.
+    pos: usize,
+    /// The fractional part of the input position of the next output frame, in units of `1 / den`.
+    frac: u64,
+    /// The input positions of the output frames being rendered.
+    positions: Vec<(usize, u64)>,
+    out: AudioBuffer<S>,
+}
+
+impl<S: ResampleSample> Resampler<S> {
+    /// Instantiates a `Resampler` that converts audio with the signal specification `spec` to the
+    /// sample rate `out_rate`.
+    pub fn new(spec: SignalSpec, out_rate: u32, quality: ResampleQuality) -> Self {
+        assert!(spec.rate > 0, "input sample rate must be non-zero");
+        assert!(out_rate > 0, "output sample rate must be non-zero");
+
+        // Reduce the ratio such that the input step per output frame is in_rate / out_rate.
+        let g = gcd(u64::from(spec.rate), u64::from(out_rate));
+        let num = u64::from(spec.rate) / g;
+        let den = u64::from(out_rate) / g;
+
+        let ratio = f64::from(out_rate) / f64::from(spec.rate);
+
+        Resampler::with_step(spec, out_rate, ratio, num, den, quality)
+    }
+
+    /// Instantiates a `Resampler` that converts audio with the signal specification `spec` by the
+    /// ratio of the output sample rate to the input sample rate, `ratio`.
+    ///
+    /// Since the ratio may not correspond to an integer output sample rate, the sample rate of the
+    /// output is rounded.
+    pub fn new_fractional(spec: SignalSpec, ratio: f64, quality: ResampleQuality) -> Self {
+        assert!(spec.rate > 0, "input sample rate must be non-zero");
+        assert!(ratio.is_finite() && ratio > 0.0, "ratio must be positive");
+
+        // Represent the input step in 32.32 fixed-point.
+        let den = 1u64 << 32;
+        let num = (den as f64 / ratio).round() as u64;
+
+        assert!(num > 0, "ratio is too large");
+
+        let out_rate = (f64::from(spec.rate) * ratio).round().max(1.0) as u32;
+
+        Resampler::with_step(spec, out_rate, ratio, num, den, quality)
+    }
+
+    fn with_step(
+        spec: SignalSpec,
+        out_rate: u32,
+        ratio: f64,
+        num: u64,
+        den: u64,
+        quality: ResampleQuality,
+    ) -> Self {
+        let (interp, half_len) = match quality.sinc_params() {
+            None if quality == ResampleQuality::Linear => (Interpolator::Linear, 1),
+            None => (Interpolator::Cubic, 2),
+            Some(params) => {
+                // If there are few enough possible fractional positions, use a filter phase for
+                // each. Otherwise, interpolate between the phases of a finer filter bank.
+                let exact = den <= params.n_phases as u64;
+
+                let (n_phases, n_rows) = if exact {
+                    (den as usize, den as usize)
+                }
+                else {
+                    (params.n_phases, params.n_phases + 1)
+                };
+
+                let (table, half_len) = sinc_table(&params, ratio, n_phases, n_rows);
+
+                (Interpolator::Sinc { table, n_taps: 2 * half_len, n_phases, exact }, half_len)
+            }
+        };
+
+        let n_channels = spec.channels.count();
+
+        let mut out_spec = spec;
+        out_spec.rate = out_rate;
+
+        Resampler {
+            out_spec,
+            ratio,
+            interp,
+            half_len,
+            step_int: (num / den) as usize,
+            step_frac: num % den,
+            den,
+            history: vec![vec![S::MID; half_len - 1]; n_channels],
+            pos: half_len - 1,
+            frac: 0,
+            positions: Vec::new(),
+            out: AudioBuffer::unused(),
+        }
+    }
+
+    /// Gets the signal specification of the output.
+    pub fn output_spec(&self) -> &SignalSpec {
+        &self.out_spec
+    }
+
+    /// Gets the ratio of the output sample rate to the input sample rate.
+    pub fn ratio(&self) -> f64 {
+        self.ratio
+    }
+
+    /// Gets the latency of the resampler in input frames. This is the number of input frames that
+    /// must be buffered before the output frame corresponding to an input frame can be computed.
+    pub fn latency(&self) -> usize {
+        self.half_len
+    }
+
+    /// Resamples `input` and returns a reference to a buffer containing the output frames that
+    /// could be computed. The returned buffer may be empty.
+    ///
+    /// The input must have the same number of channels as the `Resampler` was instantiated with.
+    pub fn resample(&mut self, input: &AudioBuffer<S>) -> &AudioBuffer<S> {
+        assert_eq!(
+            input.spec().channels.count(),
+            self.history.len(),
+            "number of channels must not change"
+        );
+
+        for (c, history) in self.history.iter_mut().enumerate() {
+            history.extend_from_slice(input.chan(c));
+        }
+
+        // An output frame can only be computed if all input frames within the filter are buffered.
+        let end = self.history[0].len().saturating_sub(self.half_len);
+
+        self.render(end);
+
+        &self.out
+    }
+
+    /// Flushes the resampler at the end of a stream and returns a reference to a buffer containing
+    /// the remaining output frames. Afterwards, the resampler is reset and may be used for a new
+    /// stream.
+    pub fn flush(&mut self) -> &AudioBuffer<S> {
+        let end = self.history[0].len();
+
+        // Pad the input with silence so that the output frames up-to the end of the input can be
+        // computed.
+        for history in self.history.iter_mut() {
+            history.resize(end + self.half_len, S::MID);
+        }
+
+        self.render(end);
+        self.reset();
+
+        &self.out
+    }
+
+    /// Resets the resampler, discarding all buffered input. The output buffer is not cleared.
+    pub fn reset(&mut self) {
+        for history in self.history.iter_mut() {
+            history.clear();
+            history.resize(self.half_len - 1, S::MID);
+        }
+
+        self.pos = self.half_len - 1;
+        self.frac = 0;
+    }
+
+    /// Renders all output frames with an integer input position less-than `end`.
+    fn render(&mut self, end: usize) {
+        self.positions.clear();
+
+        while self.pos < end {
+            self.positions.push((self.pos, self.frac));
+
+            self.pos += self.step_int;
+            self.frac += self.step_frac;
+
+            if self.frac >= self.den {
+                self.frac -= self.den;
+                self.pos += 1;
+            }
+        }
+
+        let n_frames = self.positions.len();
+
+        // Grow the output buffer if required.
+        if self.out.capacity() < n_frames || self.out.spec() != &self.out_spec {
+            let duration = n_frames.max(1).next_power_of_two() as u64;
+            self.out = AudioBuffer::new(duration, self.out_spec);
+        }
+
+        self.out.clear();
+        self.out.render_reserved(Some(n_frames));
+
+        let offset = self.half_len - 1;
+
+        for (c, history) in self.history.iter().enumerate() {
+            let dst = self.out.chan_mut(c);
+
+            for (out, &(pos, frac)) in dst.iter_mut().zip(&self.positions) {
+                *out = self.interp.interpolate(&history[pos - offset..], frac, self.den);
+            }
+        }
+
+        // Discard the buffered input that precedes the filter of the next output frame.
+        let len = self.history[0].len();
+        let consumed = (self.pos - offset).min(len);
+
+        for history in self.history.iter_mut() {
+            history.drain(..consumed);
+        }
+
+        self.pos -= consumed;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::{Channels, Signal};
+
+    fn sine(rate: u32, freq: f64, n_frames: usize) -> AudioBuffer<f64> {
+        let spec = SignalSpec::new(rate, Channels::FRONT_LEFT | Channels::FRONT_RIGHT);
+
+        let mut buf = AudioBuffer::new(n_frames as u64, spec);
+        buf.render_reserved(None);
+
+        let (l, r) = buf.chan_pair_mut(0, 1);
+
+        for (i, (l, r)) in l.iter_mut().zip(r).enumerate() {
+            *l = (2.0 * PI * freq * i as f64 / f64::from(rate)).sin();
+            *r = -*l;
+        }
+
+        buf
+    }
+
+    /// Resamples `input` in blocks of `block_len` frames, and returns the output of each channel.
+    fn resample_blocks(
+        resampler: &mut Resampler<f64>,
+        input: &AudioBuffer<f64>,
+        block_len: usize,
+    ) -> Vec<Vec<f64>> {
+        let mut output = vec![Vec::new(); 2];
+
+        let mut append = |buf: &AudioBuffer<f64>| {
+            for (c, output) in output.iter_mut().enumerate() {
+                output.extend_from_slice(buf.chan(c));
+            }
+        };
+
+        let mut block = input.clone();
+        let mut start = 0;
+
+        while start < input.frames() {
+            let end = (start + block_len).min(input.frames());
+
+            block.clone_from(input);
+            block.truncate(end);
+            block.shift(start);
+
+            append(resampler.resample(&block));
+
+            start = end;
+        }
+
+        append(resampler.flush());
+
+        output
+    }
+
+    #[test]
+    fn verify_resample_streaming() {
+        let input = sine(44100, 1000.0, 10000);
+
+        for &quality in
+            [ResampleQuality::Linear, ResampleQuality::Cubic, ResampleQuality::Medium].iter()
+        {
+            let mut resampler = Resampler::new(*input.spec(), 48000, quality);
+
+            let a = resample_blocks(&mut resampler, &input, 1000);
+            let b = resample_blocks(&mut resampler, &input, 317);
+
+            // ceil(10000 * 48000 / 44100) = 10885.
+            assert_eq!(a[0].len(), 10885);
+            assert_eq!(a, b);
+        }
+    }
+
+    #[test]
+    fn verify_resample_accuracy() {
+        let input = sine(44100, 1000.0, 20000);
+
+        let mut resampler = Resampler::new(*input.spec(), 48000, ResampleQuality::High);
+
+        assert_eq!(resampler.output_spec().rate, 48000);
+        assert_eq!(resampler.latency(), 32);
+
+        let output = resample_blocks(&mut resampler, &input, 4096);
+
+        // Away from the edges, the output should be the same sine wave at the new sample rate.
+        for (i, (&l, &r)) in output[0].iter().zip(&output[1]).enumerate().skip(100).take(20000) {
+            let expected = (2.0 * PI * 1000.0 * i as f64 / 48000.0).sin();
+
+            assert!((l - expected).abs() < 1e-4, "frame {}: {} != {}", i, l, expected);
+            assert!((r + expected).abs() < 1e-4, "frame {}: {} != {}", i, r, -expected);
+        }
+    }
+
+    #[test]
+    fn verify_resample_anti_aliasing() {
+        let rms = |x: &[f64]| (x.iter().map(|x| x * x).sum::<f64>() / x.len() as f64).sqrt();
+
+        // A tone below the output Nyquist frequency passes.
+        let input = sine(48000, 1000.0, 24000);
+
+        let mut resampler = Resampler::new(*input.spec(), 16000, ResampleQuality::Medium);
+        let output = resample_blocks(&mut resampler, &input, 1024);
+
+        assert_eq!(output[0].len(), 8000);
+        assert!((rms(&output[0][1000..7000]) - 0.5f64.sqrt()).abs() < 1e-3);
+
+        // A tone above the output Nyquist frequency is rejected.
+        let input = sine(48000, 12000.0, 24000);
+
+        let mut resampler = Resampler::new(*input.spec(), 16000, ResampleQuality::Medium);
+        let output = resample_blocks(&mut resampler, &input, 1024);
+
+        assert!(rms(&output[0][1000..7000]) < 1e-3);
+    }
+
+    #[test]
+    fn verify_resample_fractional() {
+        let input = sine(44100, 440.0, 1000);
+
+        let mut resampler = Resampler::new_fractional(*input.spec(), 1.5, ResampleQuality::Low);
+
+        assert_eq!(resampler.output_spec().rate, 66150);
+
+        let output = resample_blocks(&mut resampler, &input, 100);
+
+        assert_eq!(output[0].len(), 1500);
+
+        // Resampling at the same rate is an identity operation.
+        let mut resampler = Resampler::new(*input.spec(), 44100, ResampleQuality::High);
+        let output = resample_blocks(&mut resampler, &input, 100);
+
+        for (&a, &b) in output[0].iter().zip(input.chan(0)) {
+            assert!((a - b).abs() < 1e-12);
+        }
+    }
+}
@@ -0,0 +1,139 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! The `rfft` module implements the Fast Fourier Transform (FFT) of a real-valued signal.
+//!
+//! The real FFT of N samples is computed using a complex FFT of N/2 points by packing the even and
+//! odd samples into the real and imaginary components of the complex input.
+
+use std::sync::Arc;
+
+use super::complex::{Complex, Float};
+use super::fft::{twiddle_factors, ArbitraryFft};
+
+/// The real-valued Fast Fourier Transform (FFT).
+///
+/// The FFT of N real samples has N/2 + 1 unique complex bins, from DC to the Nyquist frequency.
+/// The remaining bins are the complex conjugates of these bins.
+pub struct RealFft<T: Float = f32> {
+    fft: ArbitraryFft<T>,
+    twiddle: Arc<Vec<Complex<T>>>,
+    buf: Vec<Complex<T>>,
+}
+
+impl<T: Float> RealFft<T> {
+    /// Instantiate a N-point real FFT. The value of `n` must be even and greater than 0.
+    pub fn new(n: usize) -> Self {
+        assert!(n > 0 && n % 2 == 0, "n must be even and greater than 0");
+
+        RealFft {
+            fft: ArbitraryFft::new(n / 2),
+            twiddle: twiddle_factors(n),
+            buf: vec![Default::default(); n / 2],
+        }
+    }
+
+    /// Get the size of the FFT.
+    pub fn size(&self) -> usize {
+        2 * self.fft.size()
+    }
+
+    /// Calculate the FFT of N real samples, `x`, into N/2 + 1 complex bins, `y`.
+    pub fn fft(&mut self, x: &[T], y: &mut [Complex<T>]) {
+        let n2 = self.fft.size();
+
+        assert_eq!(x.len(), 2 * n2);
+        assert_eq!(y.len(), n2 + 1);
+
+        // Pack the even and odd samples into the real and imaginary components.
+        for (z, x) in self.buf.iter_mut().zip(x.chunks_exact(2)) {
+            *z = Complex::new(x[0], x[1]);
+        }
+
+        self.fft.fft_inplace(&mut self.buf);
+
+        let half = T::from_f64(0.5);
+
+        // Separate the spectra of the even and odd samples, and combine them.
+        for (k, y) in y.iter_mut().enumerate() {
+            let z = self.buf[k % n2];
+            let zc = self.buf[(n2 - k) % n2].conj();
+
+            let even = (z + zc).scale(half);
+            let odd = (z - zc).scale(half);
+
+            // Multiply the odd spectrum by -j.
+            let odd = Complex::new(odd.im, -odd.re);
+
+            *y = even + self.twiddle[k] * odd;
+        }
+    }
+
+    /// Calculate the inverse FFT of N/2 + 1 complex bins, `x`, into N real samples, `y`.
+    ///
+    /// The imaginary components of the DC and Nyquist bins are ignored.
+    pub fn ifft(&mut self, x: &[Complex<T>], y: &mut [T]) {
+        let n2 = self.fft.size();
+
+        assert_eq!(x.len(), n2 + 1);
+        assert_eq!(y.len(), 2 * n2);
+
+        let half = T::from_f64(0.5);
+
+        // Recombine the spectra of the even and odd samples.
+        for (k, z) in self.buf.iter_mut().enumerate() {
+            let xk = x[k];
+            let xc = x[n2 - k].conj();
+
+            let even = (xk + xc).scale(half);
+            let odd = (xk - xc).scale(half) * self.twiddle[k].conj();
+
+            // Multiply the odd spectrum by j.
+            *z = even + Complex::new(-odd.im, odd.re);
+        }
+
+        self.fft.ifft_inplace(&mut self.buf);
+
+        // Unpack the even and odd samples.
+        for (y, z) in y.chunks_exact_mut(2).zip(self.buf.iter()) {
+            y[0] = z.re;
+            y[1] = z.im;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_real_fft() {
+        for &n in &[2, 4, 6, 16, 30, 64, 194] {
+            let x: Vec<f64> = (0..n).map(|i| (i as f64 * 0.37).sin() + 0.25).collect();
+
+            let mut fft = RealFft::<f64>::new(n);
+
+            let mut actual = vec![Complex::default(); n / 2 + 1];
+            fft.fft(&x, &mut actual);
+
+            // Compare against the complex FFT of the real signal.
+            let mut expected: Vec<Complex<f64>> = x.iter().map(|&x| Complex::new(x, 0.0)).collect();
+            ArbitraryFft::new(n).fft_inplace(&mut expected);
+
+            for (&a, &e) in actual.iter().zip(expected.iter()) {
+                assert!((a - e).re.abs() < 1e-9 && (a - e).im.abs() < 1e-9, "n = {}", n);
+            }
+
+            let mut y = vec![0.0; n];
+            fft.ifft(&actual, &mut y);
+
+            for (&a, &e) in y.iter().zip(x.iter()) {
+                assert!((a - e).abs() < 1e-12, "n = {}", n);
+            }
+        }
+    }
+}
@@ -0,0 +1,388 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! The `simd` module provides vectorized implementations of hot signal processing kernels.
+//!
+//! If the `simd` feature is enabled, the fastest implementation supported by the CPU is selected at
+//! runtime. SSE2 and AVX2 are supported on x86 and x86-64, and NEON is supported on AArch64. If the
+//! feature is disabled, or the CPU supports none of these instruction sets, portable scalar
+//! implementations are used. Kernels for which an instruction set offers no benefit over the scalar
+//! implementation, such as most of the SSE2 kernels, use the scalar implementation instead.
+//!
+//! All implementations produce results that are bit-identical to the scalar implementations, with
+//! the exception of the sign and payload of NaNs.
+//!
+//! Setting the environment variable `SYMPHONIA_SIMD` to `scalar` before the first kernel is called
+//! forces the scalar implementations to be used.
+
+use super::complex::Complex;
+
+mod scalar;
+
+#[cfg(all(feature = "simd", target_arch = "aarch64"))]
+mod neon;
+#[cfg(all(feature = "simd", any(target_arch = "x86", target_arch = "x86_64")))]
+mod x86;
+
+type OverlapAddFn = fn(&mut [f32], &[f32], &[f32], &[f32]);
+
+/// A set of kernels implemented using a single instruction set.
+#[derive(Copy, Clone)]
+struct Kernels {
+    name: &'static str,
+    mul: fn(&mut [f32], &[f32], &[f32]),
+    mul_acc: fn(&mut [f32], &[f32], &[f32]),
+    overlap_add: OverlapAddFn,
+    fft_butterfly: fn(&mut [Complex], &mut [Complex], &[Complex]),
+    f32_to_i16: fn(&[f32], &mut [i16]),
+    i16_to_f32: fn(&[i16], &mut [f32]),
+    f32_to_i32: fn(&[f32], &mut [i32]),
+    i32_to_f32: fn(&[i32], &mut [f32]),
+}
+
+/// Gets the kernels of every instruction set supported by the CPU, fastest first. The scalar
+/// kernels are always last.
+#[cfg(any(feature = "simd", test))]
+#[allow(clippy::vec_init_then_push)]
+fn supported() -> Vec<Kernels> {
+    #[allow(unused_mut)]
+    let mut kernels = Vec::new();
+
+    #[cfg(all(feature = "simd", any(target_arch = "x86", target_arch = "x86_64")))]
+    kernels.extend(x86::detect());
+
+    #[cfg(all(feature = "simd", target_arch = "aarch64"))]
+    kernels.extend(neon::detect());
+
+    kernels.push(scalar::KERNELS);
+    kernels
+}
+
+#[cfg(feature = "simd")]
+fn kernels() -> &'static Kernels {
+    use lazy_static::lazy_static;
+
+    lazy_static! {
+        static ref KERNELS: Kernels = {
+            match std::env::var("SYMPHONIA_SIMD") {
+                Ok(value) if value.eq_ignore_ascii_case("scalar") => scalar::KERNELS,
+                _ => supported()[0],
+            }
+        };
+    }
+
+    &KERNELS
+}
+
+#[cfg(not(feature = "simd"))]
+fn kernels() -> &'static Kernels {
+    &scalar::KERNELS
+}
+
+/// Gets the name of the instruction set used by the kernels. One of `avx2`, `sse2`, `neon`, or
+/// `scalar`.
+pub fn instruction_set() -> &'static str {
+    kernels().name
+}
+
+/// Multiplies `a` and `b` element-wise, and writes the products to `dst`.
+pub fn mul(dst: &mut [f32], a: &[f32], b: &[f32]) {
+    assert!(dst.len() == a.len() && dst.len() == b.len());
+    (kernels().mul)(dst, a, b)
+}
+
+/// Multiplies `a` and `b` element-wise, and adds the products to `acc`.
+pub fn mul_acc(acc: &mut [f32], a: &[f32], b: &[f32]) {
+    assert!(acc.len() == a.len() && acc.len() == b.len());
+    (kernels().mul_acc)(acc, a, b)
+}
+
+/// Overlaps and adds the windowed `left` and `right` signals. The `left` signal is windowed by
+/// `win` in reverse order, and the `right` signal is windowed by `win` in forward order.
+pub fn overlap_add(out: &mut [f32], left: &[f32], right: &[f32], win: &[f32]) {
+    assert!(out.len() == left.len() && out.len() == right.len() && out.len() == win.len());
+    (kernels().overlap_add)(out, left, right, win)
+}
+
+/// Computes the radix-2 butterflies of a decimation-in-time FFT stage.
+pub(crate) fn fft_butterfly(even: &mut [Complex], odd: &mut [Complex], twiddle: &[Complex]) {
+    assert!(even.len() == odd.len() && even.len() == twiddle.len());
+    (kernels().fft_butterfly)(even, odd, twiddle)
+}
+
+/// Converts a slice of `f32` samples to `i16` samples. Equivalent to `FromSample`.
+pub fn f32_to_i16(src: &[f32], dst: &mut [i16]) {
+    assert_eq!(src.len(), dst.len());
+    (kernels().f32_to_i16)(src, dst)
+}
+
+/// Converts a slice of `i16` samples to `f32` samples. Equivalent to `FromSample`.
+pub fn i16_to_f32(src: &[i16], dst: &mut [f32]) {
+    assert_eq!(src.len(), dst.len());
+    (kernels().i16_to_f32)(src, dst)
+}
+
+/// Converts a slice of `f32` samples to `i32` samples. Equivalent to `FromSample`.
+pub fn f32_to_i32(src: &[f32], dst: &mut [i32]) {
+    assert_eq!(src.len(), dst.len());
+    (kernels().f32_to_i32)(src, dst)
+}
+
+/// Converts a slice of `i32` samples to `f32` samples. Equivalent to `FromSample`.
+pub fn i32_to_f32(src: &[i32], dst: &mut [f32]) {
+    assert_eq!(src.len(), dst.len());
+    (kernels().i32_to_f32)(src, dst)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Generates `n` pseudo-random values in the range [-scale, scale), including values that
+    /// round to exactly half-way between two integers after conversion.
+    fn values(n: usize, scale: f32) -> Vec<f32> {
+        let mut state = 0x1234_5678u32;
+
+        let mut values: Vec<f32> = (0..n)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                scale * ((state as f32 / u32::MAX as f32) * 2.0 - 1.0)
+            })
+            .collect();
+
+        // Edge cases for conversions.
+        let edges = [
+            0.0,
+            -0.0,
+            1.0,
+            -1.0,
+            1.5,
+            -1.5,
+            0.5 / 32768.0,
+            -0.5 / 32768.0,
+            2.5 / 32768.0,
+            f32::NAN,
+            0.5 / 2147483648.0,
+            -1.5 / 2147483648.0,
+        ];
+
+        for (v, &e) in values.iter_mut().zip(edges.iter()) {
+            *v = e;
+        }
+
+        values
+    }
+
+    fn assert_bits_eq(a: &[f32], b: &[f32], name: &str) {
+        for (a, b) in a.iter().zip(b) {
+            assert!(a.to_bits() == b.to_bits() || (a.is_nan() && b.is_nan()), "{} mismatch", name);
+        }
+    }
+
+    fn assert_complex_bits_eq(a: &[Complex], b: &[Complex], name: &str) {
+        for (a, b) in a.iter().zip(b) {
+            assert_bits_eq(&[a.re, a.im], &[b.re, b.im], name);
+        }
+    }
+
+    #[test]
+    fn verify_kernels_bit_exact() {
+        // Odd lengths exercise the scalar tails of the vectorized kernels.
+        for &n in &[0, 1, 3, 7, 18, 32, 67, 1024 + 13] {
+            let a = values(n, 1.0);
+            let b = values(n, 2.0).into_iter().rev().collect::<Vec<_>>();
+            let c = values(n, 1.5).into_iter().map(|v| v * 0.5).collect::<Vec<_>>();
+
+            let ca: Vec<Complex> =
+                a.iter().zip(&b).map(|(&re, &im)| Complex::new(re, im)).collect();
+            let cb: Vec<Complex> =
+                b.iter().zip(&c).map(|(&re, &im)| Complex::new(re, im)).collect();
+            let cw: Vec<Complex> =
+                c.iter().zip(&a).map(|(&re, &im)| Complex::new(re, im)).collect();
+
+            let ints16: Vec<i16> = a.iter().map(|&v| (v * 32767.0) as i16).collect();
+            let ints32: Vec<i32> = a.iter().map(|&v| (v * 2147483647.0) as i32).collect();
+
+            let scalar = scalar::KERNELS;
+
+            for kernels in supported() {
+                let name = kernels.name;
+
+                let mut expected = vec![0.0; n];
+                let mut actual = vec![0.0; n];
+
+                (scalar.mul)(&mut expected, &a, &b);
+                (kernels.mul)(&mut actual, &a, &b);
+                assert_bits_eq(&actual, &expected, name);
+
+                let mut expected = c.clone();
+                let mut actual = c.clone();
+
+                (scalar.mul_acc)(&mut expected, &a, &b);
+                (kernels.mul_acc)(&mut actual, &a, &b);
+                assert_bits_eq(&actual, &expected, name);
+
+                (scalar.overlap_add)(&mut expected, &a, &b, &c);
+                (kernels.overlap_add)(&mut actual, &a, &b, &c);
+                assert_bits_eq(&actual, &expected, name);
+
+                let (mut even0, mut odd0) = (ca.clone(), cb.clone());
+                let (mut even1, mut odd1) = (ca.clone(), cb.clone());
+
+                (scalar.fft_butterfly)(&mut even0, &mut odd0, &cw);
+                (kernels.fft_butterfly)(&mut even1, &mut odd1, &cw);
+                assert_complex_bits_eq(&even1, &even0, name);
+                assert_complex_bits_eq(&odd1, &odd0, name);
+
+                // Conversions of in-range, out-of-range, and half-way values.
+                for src in &[a.clone(), b.clone()] {
+                    let mut expected = vec![0; n];
+                    let mut actual = vec![0; n];
+
+                    (scalar.f32_to_i16)(src, &mut expected);
+                    (kernels.f32_to_i16)(src, &mut actual);
+                    assert_eq!(actual, expected, "{} mismatch", name);
+
+                    let mut expected = vec![0; n];
+                    let mut actual = vec![0; n];
+
+                    (scalar.f32_to_i32)(src, &mut expected);
+                    (kernels.f32_to_i32)(src, &mut actual);
+                    assert_eq!(actual, expected, "{} mismatch", name);
+                }
+
+                let mut expected = vec![0.0; n];
+                let mut actual = vec![0.0; n];
+
+                (scalar.i16_to_f32)(&ints16, &mut expected);
+                (kernels.i16_to_f32)(&ints16, &mut actual);
+                assert_bits_eq(&actual, &expected, name);
+
+                (scalar.i32_to_f32)(&ints32, &mut expected);
+                (kernels.i32_to_f32)(&ints32, &mut actual);
+                assert_bits_eq(&actual, &expected, name);
+            }
+        }
+    }
+
+    /// Gets the best mean time, in nanoseconds, of `f` with each set of kernels over 15 runs of
+    /// 10,000 iterations. The sets take turns each run so that they are affected alike by changes
+    /// in the load of the machine.
+    fn time<F: FnMut(&Kernels)>(sets: &[Kernels], mut f: F) -> Vec<u128> {
+        const ITERS: u32 = 10_000;
+
+        let mut best = vec![u128::MAX; sets.len()];
+
+        for _ in 0..15 {
+            for (kernels, best) in sets.iter().zip(best.iter_mut()) {
+                let start = std::time::Instant::now();
+
+                for _ in 0..ITERS {
+                    f(kernels);
+                }
+
+                *best = (*best).min(start.elapsed().as_nanos() / u128::from(ITERS));
+            }
+        }
+
+        best
+    }
+
+    /// Benchmarks the kernels of every instruction set supported by the CPU on 1024 elements, and
+    /// prints the results as a Markdown table. Run with:
+    ///
+    /// `cargo test -p symphonia-core --release --features simd bench_kernels -- --ignored
+    /// --nocapture`
+    #[test]
+    #[ignore]
+    fn bench_kernels() {
+        const N: usize = 1024;
+
+        let a = values(N, 1.0);
+        let b = values(N, 2.0);
+        let c: Vec<Complex> = a.iter().zip(&b).map(|(&re, &im)| Complex::new(re, im)).collect();
+
+        let ints16: Vec<i16> = a.iter().map(|&v| (v * 32767.0) as i16).collect();
+        let ints32: Vec<i32> = a.iter().map(|&v| (v * 2147483647.0) as i32).collect();
+
+        let mut out = vec![0.0; N];
+        let mut out16 = vec![0; N];
+        let mut out32 = vec![0; N];
+        let (mut even, mut odd) = (c[..N / 2].to_vec(), c[N / 2..].to_vec());
+
+        // Scalar first.
+        let sets: Vec<Kernels> = supported().into_iter().rev().collect();
+
+        let rows = vec![
+            ("`mul`", time(&sets, |k| (k.mul)(&mut out, &a, &b))),
+            ("`mul_acc`", time(&sets, |k| (k.mul_acc)(&mut out, &a, &b))),
+            ("`overlap_add`", time(&sets, |k| (k.overlap_add)(&mut out, &a, &b, &a))),
+            (
+                "FFT butterfly (512 pairs)",
+                time(&sets, |k| {
+                    // The butterflies are computed in-place, so the inputs are restored each
+                    // iteration to keep the values finite.
+                    even.copy_from_slice(&c[..N / 2]);
+                    odd.copy_from_slice(&c[N / 2..]);
+                    (k.fft_butterfly)(&mut even, &mut odd, &c[..N / 2])
+                }),
+            ),
+            ("`f32_to_i16`", time(&sets, |k| (k.f32_to_i16)(&b, &mut out16))),
+            ("`i16_to_f32`", time(&sets, |k| (k.i16_to_f32)(&ints16, &mut out))),
+            ("`f32_to_i32`", time(&sets, |k| (k.f32_to_i32)(&b, &mut out32))),
+            ("`i32_to_f32`", time(&sets, |k| (k.i32_to_f32)(&ints32, &mut out))),
+        ];
+
+        let names: Vec<String> = sets.iter().map(|k| format!("{} [ns]", k.name)).collect();
+
+        println!("| Kernel | {} |", names.join(" | "));
+
+        for (kernel, ns) in rows {
+            let ns: Vec<String> = ns.iter().map(|ns| ns.to_string()).collect();
+            println!("| {} | {} |", kernel, ns.join(" | "));
+        }
+    }
+
+    /// Benchmarks the FFT and IMDCT with the kernels selected at runtime, and prints the results as
+    /// a Markdown table. Run with, and without, `SYMPHONIA_SIMD=scalar`:
+    ///
+    /// `cargo test -p symphonia-core --release --features simd bench_transforms -- --ignored
+    /// --nocapture`
+    #[test]
+    #[ignore]
+    fn bench_transforms() {
+        use crate::dsp::fft::Fft;
+        use crate::dsp::mdct::Imdct;
+
+        // The transforms use the kernels selected at runtime.
+        let sets = [*kernels()];
+
+        println!("| Transform | {} [ns] |", instruction_set());
+
+        for &n in &[256, 1024, 4096] {
+            let fft = Fft::new(n);
+
+            let x: Vec<Complex> = (0..n)
+                .map(|i| Complex::new((0.1 * i as f32).sin(), (0.3 * i as f32).cos()))
+                .collect();
+            let mut y = vec![Complex::new(0.0, 0.0); n];
+
+            println!("| `Fft`, {}-point | {} |", n, time(&sets, |_| fft.fft(&x, &mut y))[0]);
+        }
+
+        let mut imdct = Imdct::new(1024);
+
+        let spec: Vec<f32> = (0..1024).map(|i| (0.05 * i as f32).sin()).collect();
+        let mut out = vec![0.0; 2048];
+
+        let ns = time(&sets, |_| imdct.imdct(&spec, &mut out))[0];
+
+        println!("| `Imdct`, 1024 coefficients | {} |", ns);
+    }
+}
@@ -0,0 +1,203 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! NEON kernels for AArch64.
+//!
+//! Every kernel performs the same floating-point operations, in the same order, as its scalar
+//! counterpart. Fused multiply-add instructions are not used since they round differently.
+
+#![allow(unsafe_code)]
+
+use std::arch::aarch64::*;
+
+use crate::dsp::complex::Complex;
+
+use super::{scalar, Kernels};
+
+/// Gets the kernels supported by the CPU, fastest first.
+pub(super) fn detect() -> Vec<Kernels> {
+    // NEON is a mandatory part of the AArch64 architecture, and is enabled for all standard
+    // AArch64 targets.
+    if cfg!(target_feature = "neon") {
+        vec![NEON]
+    }
+    else {
+        Vec::new()
+    }
+}
+
+/// Defines a safe wrapper around a kernel that requires CPU features.
+macro_rules! safe_kernel {
+    ($name:ident, $kernel:ident, ($($arg:ident: $ty:ty),*)) => {
+        fn $name($($arg: $ty),*) {
+            // Safety: the kernel table containing these wrappers is only used if the target
+            // supports NEON. The public entry points validate the slice lengths.
+            unsafe { $kernel($($arg),*) }
+        }
+    };
+}
+
+const NEON: Kernels = Kernels {
+    name: "neon",
+    mul: neon_mul,
+    mul_acc: neon_mul_acc,
+    overlap_add: neon_overlap_add,
+    fft_butterfly: neon_fft_butterfly,
+    f32_to_i16: neon_f32_to_i16,
+    i16_to_f32: neon_i16_to_f32,
+    f32_to_i32: neon_f32_to_i32,
+    i32_to_f32: neon_i32_to_f32,
+};
+
+safe_kernel!(neon_mul, mul_neon, (dst: &mut [f32], a: &[f32], b: &[f32]));
+safe_kernel!(neon_mul_acc, mul_acc_neon, (acc: &mut [f32], a: &[f32], b: &[f32]));
+safe_kernel!(neon_overlap_add, overlap_add_neon, (o: &mut [f32], l: &[f32], r: &[f32], w: &[f32]));
+safe_kernel!(neon_fft_butterfly, fft_butterfly_neon, (e: &mut [Complex], o: &mut [Complex], w: &[Complex]));
+safe_kernel!(neon_f32_to_i16, f32_to_i16_neon, (src: &[f32], dst: &mut [i16]));
+safe_kernel!(neon_i16_to_f32, i16_to_f32_neon, (src: &[i16], dst: &mut [f32]));
+safe_kernel!(neon_f32_to_i32, f32_to_i32_neon, (src: &[f32], dst: &mut [i32]));
+safe_kernel!(neon_i32_to_f32, i32_to_f32_neon, (src: &[i32], dst: &mut [f32]));
+
+#[target_feature(enable = "neon")]
+unsafe fn mul_neon(dst: &mut [f32], a: &[f32], b: &[f32]) {
+    let n = dst.len() & !3;
+
+    for i in (0..n).step_by(4) {
+        let a = vld1q_f32(a.as_ptr().add(i));
+        let b = vld1q_f32(b.as_ptr().add(i));
+        vst1q_f32(dst.as_mut_ptr().add(i), vmulq_f32(a, b));
+    }
+
+    scalar::mul(&mut dst[n..], &a[n..], &b[n..]);
+}
+
+#[target_feature(enable = "neon")]
+unsafe fn mul_acc_neon(acc: &mut [f32], a: &[f32], b: &[f32]) {
+    let n = acc.len() & !3;
+
+    for i in (0..n).step_by(4) {
+        let prod = vmulq_f32(vld1q_f32(a.as_ptr().add(i)), vld1q_f32(b.as_ptr().add(i)));
+        let sum = vaddq_f32(vld1q_f32(acc.as_ptr().add(i)), prod);
+        vst1q_f32(acc.as_mut_ptr().add(i), sum);
+    }
+
+    scalar::mul_acc(&mut acc[n..], &a[n..], &b[n..]);
+}
+
+#[target_feature(enable = "neon")]
+unsafe fn overlap_add_neon(out: &mut [f32], left: &[f32], right: &[f32], win: &[f32]) {
+    let len = out.len();
+    let n = len & !3;
+
+    for i in (0..n).step_by(4) {
+        // Reverse the order of the 4 elements: reverse each pair, then swap the pairs.
+        let w_rev = vrev64q_f32(vld1q_f32(win.as_ptr().add(len - 4 - i)));
+        let w_rev = vextq_f32(w_rev, w_rev, 2);
+
+        let s0 = vmulq_f32(vld1q_f32(left.as_ptr().add(i)), w_rev);
+        let s1 = vmulq_f32(vld1q_f32(right.as_ptr().add(i)), vld1q_f32(win.as_ptr().add(i)));
+
+        vst1q_f32(out.as_mut_ptr().add(i), vaddq_f32(s0, s1));
+    }
+
+    for i in n..len {
+        out[i] = left[i] * win[len - 1 - i] + right[i] * win[i];
+    }
+}
+
+#[target_feature(enable = "neon")]
+unsafe fn fft_butterfly_neon(even: &mut [Complex], odd: &mut [Complex], twiddle: &[Complex]) {
+    let n = even.len() & !3;
+
+    let e_ptr = even.as_mut_ptr() as *mut f32;
+    let o_ptr = odd.as_mut_ptr() as *mut f32;
+    let w_ptr = twiddle.as_ptr() as *const f32;
+
+    for i in (0..2 * n).step_by(8) {
+        // De-interleave 4 complex numbers into their real and imaginary components.
+        let p = vld2q_f32(e_ptr.add(i));
+        let a = vld2q_f32(o_ptr.add(i));
+        let b = vld2q_f32(w_ptr.add(i));
+
+        let q_re = vsubq_f32(vmulq_f32(a.0, b.0), vmulq_f32(a.1, b.1));
+        let q_im = vaddq_f32(vmulq_f32(a.1, b.0), vmulq_f32(a.0, b.1));
+
+        vst2q_f32(e_ptr.add(i), float32x4x2_t(vaddq_f32(p.0, q_re), vaddq_f32(p.1, q_im)));
+        vst2q_f32(o_ptr.add(i), float32x4x2_t(vsubq_f32(p.0, q_re), vsubq_f32(p.1, q_im)));
+    }
+
+    scalar::fft_butterfly(&mut even[n..], &mut odd[n..], &twiddle[n..]);
+}
+
+/// Clamps to [-1.0, 1.0]. A NaN propagates, and is converted to 0 by the rounding conversion.
+#[inline(always)]
+unsafe fn clamp_neon(x: float32x4_t) -> float32x4_t {
+    vminq_f32(vmaxq_f32(x, vdupq_n_f32(-1.0)), vdupq_n_f32(1.0))
+}
+
+#[target_feature(enable = "neon")]
+unsafe fn f32_to_i16_neon(src: &[f32], dst: &mut [i16]) {
+    let n = src.len() & !7;
+
+    for i in (0..n).step_by(8) {
+        let a = vmulq_n_f32(clamp_neon(vld1q_f32(src.as_ptr().add(i))), 32_768.0);
+        let b = vmulq_n_f32(clamp_neon(vld1q_f32(src.as_ptr().add(i + 4))), 32_768.0);
+
+        // Round half-way cases away from zero, then narrow with saturation.
+        let a = vqmovn_s32(vcvtaq_s32_f32(a));
+        let b = vqmovn_s32(vcvtaq_s32_f32(b));
+
+        vst1q_s16(dst.as_mut_ptr().add(i), vcombine_s16(a, b));
+    }
+
+    scalar::f32_to_i16(&src[n..], &mut dst[n..]);
+}
+
+#[target_feature(enable = "neon")]
+unsafe fn i16_to_f32_neon(src: &[i16], dst: &mut [f32]) {
+    let n = src.len() & !7;
+    let scale = 1.0 / 32_768.0;
+
+    for i in (0..n).step_by(8) {
+        let x = vld1q_s16(src.as_ptr().add(i));
+
+        let lo = vcvtq_f32_s32(vmovl_s16(vget_low_s16(x)));
+        let hi = vcvtq_f32_s32(vmovl_s16(vget_high_s16(x)));
+
+        vst1q_f32(dst.as_mut_ptr().add(i), vmulq_n_f32(lo, scale));
+        vst1q_f32(dst.as_mut_ptr().add(i + 4), vmulq_n_f32(hi, scale));
+    }
+
+    scalar::i16_to_f32(&src[n..], &mut dst[n..]);
+}
+
+#[target_feature(enable = "neon")]
+unsafe fn f32_to_i32_neon(src: &[f32], dst: &mut [i32]) {
+    let n = src.len() & !3;
+
+    for i in (0..n).step_by(4) {
+        let x = vmulq_n_f32(clamp_neon(vld1q_f32(src.as_ptr().add(i))), 2_147_483_648.0);
+
+        // Round half-way cases away from zero. Positive full-scale saturates.
+        vst1q_s32(dst.as_mut_ptr().add(i), vcvtaq_s32_f32(x));
+    }
+
+    scalar::f32_to_i32(&src[n..], &mut dst[n..]);
+}
+
+#[target_feature(enable = "neon")]
+unsafe fn i32_to_f32_neon(src: &[i32], dst: &mut [f32]) {
+    let n = src.len() & !3;
+    let scale = 1.0 / 2_147_483_648.0;
+
+    for i in (0..n).step_by(4) {
+        let x = vcvtq_f32_s32(vld1q_s32(src.as_ptr().add(i)));
+        vst1q_f32(dst.as_mut_ptr().add(i), vmulq_n_f32(x, scale));
+    }
+
+    scalar::i32_to_f32(&src[n..], &mut dst[n..]);
+}
@@ -0,0 +1,80 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Portable scalar kernels. These are the reference implementations that all other kernels must
+//! match bit-for-bit.
+
+use crate::conv::FromSample;
+use crate::dsp::complex::Complex;
+
+use super::Kernels;
+
+pub(super) const KERNELS: Kernels = Kernels {
+    name: "scalar",
+    mul,
+    mul_acc,
+    overlap_add,
+    fft_butterfly,
+    f32_to_i16,
+    i16_to_f32,
+    f32_to_i32,
+    i32_to_f32,
+};
+
+pub(super) fn mul(dst: &mut [f32], a: &[f32], b: &[f32]) {
+    for ((dst, &a), &b) in dst.iter_mut().zip(a).zip(b) {
+        *dst = a * b;
+    }
+}
+
+pub(super) fn mul_acc(acc: &mut [f32], a: &[f32], b: &[f32]) {
+    for ((acc, &a), &b) in acc.iter_mut().zip(a).zip(b) {
+        *acc += a * b;
+    }
+}
+
+pub(super) fn overlap_add(out: &mut [f32], left: &[f32], right: &[f32], win: &[f32]) {
+    let iter = left.iter().zip(right).zip(win.iter().rev()).zip(win).zip(out);
+
+    for ((((&s0, &s1), &w0), &w1), out) in iter {
+        *out = s0 * w0 + s1 * w1;
+    }
+}
+
+pub(super) fn fft_butterfly(even: &mut [Complex], odd: &mut [Complex], twiddle: &[Complex]) {
+    for ((e, o), &w) in even.iter_mut().zip(odd.iter_mut()).zip(twiddle) {
+        let p = *e;
+        let q = *o * w;
+
+        *e = p + q;
+        *o = p - q;
+    }
+}
+
+pub(super) fn f32_to_i16(src: &[f32], dst: &mut [i16]) {
+    for (dst, &src) in dst.iter_mut().zip(src) {
+        *dst = i16::from_sample(src);
+    }
+}
+
+pub(super) fn i16_to_f32(src: &[i16], dst: &mut [f32]) {
+    for (dst, &src) in dst.iter_mut().zip(src) {
+        *dst = f32::from_sample(src);
+    }
+}
+
+pub(super) fn f32_to_i32(src: &[f32], dst: &mut [i32]) {
+    for (dst, &src) in dst.iter_mut().zip(src) {
+        *dst = i32::from_sample(src);
+    }
+}
+
+pub(super) fn i32_to_f32(src: &[i32], dst: &mut [f32]) {
+    for (dst, &src) in dst.iter_mut().zip(src) {
+        *dst = f32::from_sample(src);
+    }
+}
@@ -0,0 +1,320 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! SSE2 and AVX2 kernels for x86 and x86-64.
+//!
+//! Every kernel performs the same floating-point operations, in the same order, as its scalar
+//! counterpart. Fused multiply-add instructions are not used since they round differently.
+
+#![allow(unsafe_code)]
+
+#[cfg(target_arch = "x86")]
+use std::arch::x86::*;
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64::*;
+
+use crate::dsp::complex::Complex;
+
+use super::{scalar, Kernels};
+
+/// Gets the kernels supported by the CPU, fastest first.
+pub(super) fn detect() -> Vec<Kernels> {
+    let mut kernels = Vec::new();
+
+    if is_x86_feature_detected!("avx2") {
+        kernels.push(AVX2);
+    }
+
+    if is_x86_feature_detected!("sse2") {
+        kernels.push(SSE2);
+    }
+
+    kernels
+}
+
+/// Defines a safe wrapper around a kernel that requires CPU features.
+macro_rules! safe_kernel {
+    ($name:ident, $kernel:ident, ($($arg:ident: $ty:ty),*)) => {
+        fn $name($($arg: $ty),*) {
+            // Safety: the kernel tables containing these wrappers are only used after the CPU
+            // features the kernels require have been detected. The public entry points validate
+            // the slice lengths.
+            unsafe { $kernel($($arg),*) }
+        }
+    };
+}
+
+/// The SSE2 kernels are only faster than the scalar kernels, which LLVM auto-vectorizes with
+/// SSE2, for the float-to-integer conversions. See the benchmarks in `BENCHMARKS.md`.
+const SSE2: Kernels = Kernels {
+    name: "sse2",
+    mul: scalar::mul,
+    mul_acc: scalar::mul_acc,
+    overlap_add: scalar::overlap_add,
+    fft_butterfly: scalar::fft_butterfly,
+    f32_to_i16: sse2_f32_to_i16,
+    i16_to_f32: scalar::i16_to_f32,
+    f32_to_i32: sse2_f32_to_i32,
+    i32_to_f32: scalar::i32_to_f32,
+};
+
+const AVX2: Kernels = Kernels {
+    name: "avx2",
+    mul: avx2_mul,
+    mul_acc: avx2_mul_acc,
+    overlap_add: avx2_overlap_add,
+    fft_butterfly: avx2_fft_butterfly,
+    f32_to_i16: avx2_f32_to_i16,
+    i16_to_f32: avx2_i16_to_f32,
+    f32_to_i32: avx2_f32_to_i32,
+    i32_to_f32: avx2_i32_to_f32,
+};
+
+safe_kernel!(sse2_f32_to_i16, f32_to_i16_sse2, (src: &[f32], dst: &mut [i16]));
+safe_kernel!(sse2_f32_to_i32, f32_to_i32_sse2, (src: &[f32], dst: &mut [i32]));
+
+safe_kernel!(avx2_mul, mul_avx2, (dst: &mut [f32], a: &[f32], b: &[f32]));
+safe_kernel!(avx2_mul_acc, mul_acc_avx2, (acc: &mut [f32], a: &[f32], b: &[f32]));
+safe_kernel!(avx2_overlap_add, overlap_add_avx2, (o: &mut [f32], l: &[f32], r: &[f32], w: &[f32]));
+safe_kernel!(avx2_fft_butterfly, fft_butterfly_avx2, (e: &mut [Complex], o: &mut [Complex], w: &[Complex]));
+safe_kernel!(avx2_f32_to_i16, f32_to_i16_avx2, (src: &[f32], dst: &mut [i16]));
+safe_kernel!(avx2_i16_to_f32, i16_to_f32_avx2, (src: &[i16], dst: &mut [f32]));
+safe_kernel!(avx2_f32_to_i32, f32_to_i32_avx2, (src: &[f32], dst: &mut [i32]));
+safe_kernel!(avx2_i32_to_f32, i32_to_f32_avx2, (src: &[i32], dst: &mut [f32]));
+
+/// Computes the overlap-add of the tail elements `start..` that were not vectorized.
+#[inline(always)]
+fn overlap_add_tail(start: usize, out: &mut [f32], left: &[f32], right: &[f32], win: &[f32]) {
+    let n = out.len();
+
+    for i in start..n {
+        out[i] = left[i] * win[n - 1 - i] + right[i] * win[i];
+    }
+}
+
+// SSE2
+
+/// Clamps to [-1.0, 1.0], and replaces NaN with 0.0 to match the scalar conversion.
+#[inline(always)]
+unsafe fn clamp_sse2(x: __m128) -> __m128 {
+    let x = _mm_and_ps(x, _mm_cmpord_ps(x, x));
+    _mm_min_ps(_mm_max_ps(x, _mm_set1_ps(-1.0)), _mm_set1_ps(1.0))
+}
+
+/// Rounds half-way cases away from zero, matching `f32::round`.
+#[inline(always)]
+unsafe fn round_sse2(x: __m128) -> __m128i {
+    let trunc = _mm_cvttps_epi32(x);
+    let frac = _mm_sub_ps(x, _mm_cvtepi32_ps(trunc));
+
+    // The comparison masks are -1 where true.
+    let up = _mm_castps_si128(_mm_cmpge_ps(frac, _mm_set1_ps(0.5)));
+    let down = _mm_castps_si128(_mm_cmple_ps(frac, _mm_set1_ps(-0.5)));
+
+    _mm_add_epi32(_mm_sub_epi32(trunc, up), down)
+}
+
+#[target_feature(enable = "sse2")]
+unsafe fn f32_to_i16_sse2(src: &[f32], dst: &mut [i16]) {
+    let n = src.len() & !7;
+    let scale = _mm_set1_ps(32_768.0);
+
+    for i in (0..n).step_by(8) {
+        let a = round_sse2(_mm_mul_ps(clamp_sse2(_mm_loadu_ps(src.as_ptr().add(i))), scale));
+        let b = round_sse2(_mm_mul_ps(clamp_sse2(_mm_loadu_ps(src.as_ptr().add(i + 4))), scale));
+
+        // Saturating pack to 16-bit.
+        _mm_storeu_si128(dst.as_mut_ptr().add(i) as *mut __m128i, _mm_packs_epi32(a, b));
+    }
+
+    scalar::f32_to_i16(&src[n..], &mut dst[n..]);
+}
+
+#[target_feature(enable = "sse2")]
+unsafe fn f32_to_i32_sse2(src: &[f32], dst: &mut [i32]) {
+    let n = src.len() & !3;
+    let scale = _mm_set1_ps(2_147_483_648.0);
+
+    for i in (0..n).step_by(4) {
+        let x = _mm_mul_ps(clamp_sse2(_mm_loadu_ps(src.as_ptr().add(i))), scale);
+
+        // Positive full-scale is out of range, and saturates.
+        let sat = _mm_castps_si128(_mm_cmpge_ps(x, scale));
+        let y = round_sse2(x);
+        let y =
+            _mm_or_si128(_mm_andnot_si128(sat, y), _mm_and_si128(sat, _mm_set1_epi32(i32::MAX)));
+
+        _mm_storeu_si128(dst.as_mut_ptr().add(i) as *mut __m128i, y);
+    }
+
+    scalar::f32_to_i32(&src[n..], &mut dst[n..]);
+}
+
+// AVX2
+
+#[target_feature(enable = "avx2")]
+unsafe fn mul_avx2(dst: &mut [f32], a: &[f32], b: &[f32]) {
+    let n = dst.len() & !7;
+
+    for i in (0..n).step_by(8) {
+        let a = _mm256_loadu_ps(a.as_ptr().add(i));
+        let b = _mm256_loadu_ps(b.as_ptr().add(i));
+        _mm256_storeu_ps(dst.as_mut_ptr().add(i), _mm256_mul_ps(a, b));
+    }
+
+    scalar::mul(&mut dst[n..], &a[n..], &b[n..]);
+}
+
+#[target_feature(enable = "avx2")]
+unsafe fn mul_acc_avx2(acc: &mut [f32], a: &[f32], b: &[f32]) {
+    let n = acc.len() & !7;
+
+    for i in (0..n).step_by(8) {
+        let prod =
+            _mm256_mul_ps(_mm256_loadu_ps(a.as_ptr().add(i)), _mm256_loadu_ps(b.as_ptr().add(i)));
+        let sum = _mm256_add_ps(_mm256_loadu_ps(acc.as_ptr().add(i)), prod);
+        _mm256_storeu_ps(acc.as_mut_ptr().add(i), sum);
+    }
+
+    scalar::mul_acc(&mut acc[n..], &a[n..], &b[n..]);
+}
+
+#[target_feature(enable = "avx2")]
+unsafe fn overlap_add_avx2(out: &mut [f32], left: &[f32], right: &[f32], win: &[f32]) {
+    let len = out.len();
+    let n = len & !7;
+
+    for i in (0..n).step_by(8) {
+        // Reverse the order of the 8 elements: swap the 128-bit halves, then reverse each half.
+        let w_rev = _mm256_loadu_ps(win.as_ptr().add(len - 8 - i));
+        let w_rev = _mm256_permute2f128_ps(w_rev, w_rev, 0x01);
+        let w_rev = _mm256_shuffle_ps(w_rev, w_rev, 0x1b);
+
+        let s0 = _mm256_mul_ps(_mm256_loadu_ps(left.as_ptr().add(i)), w_rev);
+        let s1 = _mm256_mul_ps(
+            _mm256_loadu_ps(right.as_ptr().add(i)),
+            _mm256_loadu_ps(win.as_ptr().add(i)),
+        );
+
+        _mm256_storeu_ps(out.as_mut_ptr().add(i), _mm256_add_ps(s0, s1));
+    }
+
+    overlap_add_tail(n, out, left, right, win);
+}
+
+#[target_feature(enable = "avx2")]
+unsafe fn fft_butterfly_avx2(even: &mut [Complex], odd: &mut [Complex], twiddle: &[Complex]) {
+    let n = even.len() & !3;
+
+    let e_ptr = even.as_mut_ptr() as *mut f32;
+    let o_ptr = odd.as_mut_ptr() as *mut f32;
+    let w_ptr = twiddle.as_ptr() as *const f32;
+
+    let sign = _mm256_set_ps(0.0, -0.0, 0.0, -0.0, 0.0, -0.0, 0.0, -0.0);
+
+    for i in (0..2 * n).step_by(8) {
+        let p = _mm256_loadu_ps(e_ptr.add(i));
+        let a = _mm256_loadu_ps(o_ptr.add(i));
+        let b = _mm256_loadu_ps(w_ptr.add(i));
+
+        let b_re = _mm256_shuffle_ps(b, b, 0xa0);
+        let b_im = _mm256_shuffle_ps(b, b, 0xf5);
+        let a_swap = _mm256_shuffle_ps(a, a, 0xb1);
+
+        let t0 = _mm256_mul_ps(a, b_re);
+        let t1 = _mm256_xor_ps(_mm256_mul_ps(a_swap, b_im), sign);
+        let q = _mm256_add_ps(t0, t1);
+
+        _mm256_storeu_ps(e_ptr.add(i), _mm256_add_ps(p, q));
+        _mm256_storeu_ps(o_ptr.add(i), _mm256_sub_ps(p, q));
+    }
+
+    scalar::fft_butterfly(&mut even[n..], &mut odd[n..], &twiddle[n..]);
+}
+
+/// Clamps to [-1.0, 1.0], and replaces NaN with 0.0 to match the scalar conversion.
+#[inline(always)]
+unsafe fn clamp_avx2(x: __m256) -> __m256 {
+    let x = _mm256_and_ps(x, _mm256_cmp_ps(x, x, _CMP_ORD_Q));
+    _mm256_min_ps(_mm256_max_ps(x, _mm256_set1_ps(-1.0)), _mm256_set1_ps(1.0))
+}
+
+/// Rounds half-way cases away from zero, matching `f32::round`.
+#[inline(always)]
+unsafe fn round_avx2(x: __m256) -> __m256i {
+    let trunc = _mm256_cvttps_epi32(x);
+    let frac = _mm256_sub_ps(x, _mm256_cvtepi32_ps(trunc));
+
+    let up = _mm256_castps_si256(_mm256_cmp_ps(frac, _mm256_set1_ps(0.5), _CMP_GE_OQ));
+    let down = _mm256_castps_si256(_mm256_cmp_ps(frac, _mm256_set1_ps(-0.5), _CMP_LE_OQ));
+
+    _mm256_add_epi32(_mm256_sub_epi32(trunc, up), down)
+}
+
+#[target_feature(enable = "avx2")]
+unsafe fn f32_to_i16_avx2(src: &[f32], dst: &mut [i16]) {
+    let n = src.len() & !15;
+    let scale = _mm256_set1_ps(32_768.0);
+
+    for i in (0..n).step_by(16) {
+        let a = round_avx2(_mm256_mul_ps(clamp_avx2(_mm256_loadu_ps(src.as_ptr().add(i))), scale));
+        let b =
+            round_avx2(_mm256_mul_ps(clamp_avx2(_mm256_loadu_ps(src.as_ptr().add(i + 8))), scale));
+
+        // The pack operates on each 128-bit half independently. Restore the element order.
+        let packed = _mm256_permute4x64_epi64(_mm256_packs_epi32(a, b), 0xd8);
+
+        _mm256_storeu_si256(dst.as_mut_ptr().add(i) as *mut __m256i, packed);
+    }
+
+    f32_to_i16_sse2(&src[n..], &mut dst[n..]);
+}
+
+#[target_feature(enable = "avx2")]
+unsafe fn i16_to_f32_avx2(src: &[i16], dst: &mut [f32]) {
+    let n = src.len() & !7;
+    let scale = _mm256_set1_ps(1.0 / 32_768.0);
+
+    for i in (0..n).step_by(8) {
+        let x = _mm256_cvtepi16_epi32(_mm_loadu_si128(src.as_ptr().add(i) as *const __m128i));
+        _mm256_storeu_ps(dst.as_mut_ptr().add(i), _mm256_mul_ps(_mm256_cvtepi32_ps(x), scale));
+    }
+
+    scalar::i16_to_f32(&src[n..], &mut dst[n..]);
+}
+
+#[target_feature(enable = "avx2")]
+unsafe fn f32_to_i32_avx2(src: &[f32], dst: &mut [i32]) {
+    let n = src.len() & !7;
+    let scale = _mm256_set1_ps(2_147_483_648.0);
+
+    for i in (0..n).step_by(8) {
+        let x = _mm256_mul_ps(clamp_avx2(_mm256_loadu_ps(src.as_ptr().add(i))), scale);
+
+        // Positive full-scale is out of range, and saturates.
+        let sat = _mm256_castps_si256(_mm256_cmp_ps(x, scale, _CMP_GE_OQ));
+        let y = round_avx2(x);
+        let y = _mm256_blendv_epi8(y, _mm256_set1_epi32(i32::MAX), sat);
+
+        _mm256_storeu_si256(dst.as_mut_ptr().add(i) as *mut __m256i, y);
+    }
+
+    f32_to_i32_sse2(&src[n..], &mut dst[n..]);
+}
+
+#[target_feature(enable = "avx2")]
+unsafe fn i32_to_f32_avx2(src: &[i32], dst: &mut [f32]) {
+    let n = src.len() & !7;
+    let scale = _mm256_set1_ps(1.0 / 2_147_483_648.0);
+
+    for i in (0..n).step_by(8) {
+        let x = _mm256_cvtepi32_ps(_mm256_loadu_si256(src.as_ptr().add(i) as *const __m256i));
+        _mm256_storeu_ps(dst.as_mut_ptr().add(i), _mm256_mul_ps(x, scale));
+    }
+
+    scalar::i32_to_f32(&src[n..], &mut dst[n..]);
+}
@@ -0,0 +1,156 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! The `window` module implements window functions.
+//!
+//! The sine, Vorbis, and Kaiser-Bessel-derived windows are power-complementary, and are suitable
+//! for use with the MDCT.
+
+use std::f64::consts::PI;
+
+use super::complex::Float;
+
+/// The zeroth-order modified Bessel function of the first kind.
+pub(crate) fn bessel_i0(x: f64) -> f64 {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let half_x = 0.5 * x;
+
+    for k in 1..64 {
+        term *= half_x / k as f64;
+        let t2 = term * term;
+
+        sum += t2;
+
+        if t2 < sum * 1e-16 {
+            break;
+        }
+    }
+
+    sum
+}
+
+/// Generates a window of length `n` from a function of the sample index.
+fn generate<T: Float, F: Fn(f64) -> f64>(n: usize, func: F) -> Vec<T> {
+    (0..n).map(|i| T::from_f64(func(i as f64))).collect()
+}
+
+/// Generates a periodic Hann window of length `n`.
+///
+/// The periodic window is suitable for spectral analysis. The last sample of the symmetric window
+/// of length `n + 1` is omitted.
+pub fn hann<T: Float>(n: usize) -> Vec<T> {
+    let n_f = n as f64;
+    generate(n, |i| 0.5 - 0.5 * (2.0 * PI * i / n_f).cos())
+}
+
+/// Generates a sine window of length `n`.
+pub fn sine<T: Float>(n: usize) -> Vec<T> {
+    let n_f = n as f64;
+    generate(n, |i| (PI * (i + 0.5) / n_f).sin())
+}
+
+/// Generates a Vorbis power-complementary window of length `n`.
+pub fn vorbis<T: Float>(n: usize) -> Vec<T> {
+    let n_f = n as f64;
+
+    generate(n, |i| {
+        let s = (PI * (i + 0.5) / n_f).sin();
+        (0.5 * PI * s * s).sin()
+    })
+}
+
+/// Generates a symmetric Kaiser window of length `n` with the shape parameter `beta`.
+pub fn kaiser<T: Float>(n: usize, beta: f64) -> Vec<T> {
+    if n == 1 {
+        return vec![T::ONE];
+    }
+
+    let i0_beta = bessel_i0(beta);
+    let n_f = (n - 1) as f64;
+
+    generate(n, |i| {
+        let r = 2.0 * i / n_f - 1.0;
+        bessel_i0(beta * (1.0 - r * r).max(0.0).sqrt()) / i0_beta
+    })
+}
+
+/// Generates a Kaiser-Bessel-derived (KBD) window of length `n` with the shape parameter `alpha`.
+/// The value of `n` must be even.
+///
+/// AAC uses KBD windows with an `alpha` of 4 for long blocks, and 6 for short blocks.
+pub fn kaiser_bessel_derived<T: Float>(n: usize, alpha: f64) -> Vec<T> {
+    assert!(n % 2 == 0, "n must be even");
+
+    let half = n / 2;
+
+    // The cumulative sum of a Kaiser window of length N/2 + 1.
+    let kaiser: Vec<f64> = kaiser(half + 1, PI * alpha);
+
+    let total: f64 = kaiser.iter().sum();
+
+    let mut window = vec![T::ZERO; n];
+    let mut sum = 0.0;
+
+    for i in 0..half {
+        sum += kaiser[i];
+
+        let w = T::from_f64((sum / total).sqrt());
+
+        window[i] = w;
+        window[n - 1 - i] = w;
+    }
+
+    window
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_power_complementary_windows() {
+        let windows: Vec<Vec<f64>> = vec![
+            sine(256),
+            vorbis(256),
+            kaiser_bessel_derived(256, 4.0),
+            kaiser_bessel_derived(16, 6.0),
+        ];
+
+        for window in windows {
+            let half = window.len() / 2;
+
+            // Princen-Bradley condition.
+            for i in 0..half {
+                let sum = window[i] * window[i] + window[i + half] * window[i + half];
+                assert!((sum - 1.0).abs() < 1e-12);
+            }
+
+            // Symmetry.
+            for i in 0..half {
+                assert!((window[i] - window[window.len() - 1 - i]).abs() < 1e-12);
+            }
+        }
+
+        // Single precision.
+        let window: Vec<f32> = vorbis(8);
+        assert!((window[0] - 0.05975).abs() < 1e-4);
+    }
+
+    #[test]
+    fn verify_hann_and_kaiser() {
+        let window: Vec<f64> = hann(8);
+        assert_eq!(window[0], 0.0);
+        assert!((window[4] - 1.0).abs() < 1e-12);
+        assert!((window[2] - 0.5).abs() < 1e-12);
+
+        let window: Vec<f32> = kaiser(9, 8.6);
+        assert!((window[4] - 1.0).abs() < 1e-6);
+        assert!((window[0] - window[8]).abs() < 1e-6);
+        assert!(window[0] < 2e-3);
+    }
+}
@@ -11,7 +11,7 @@
 use crate::codecs::CodecParameters;
 use crate::errors::Result;
 use crate::io::{BufReader, MediaSourceStream};
-use crate::meta::{Metadata, Tag};
+use crate::meta::{GainInfo, Metadata, MetadataLog, Tag, Visual};
 use crate::units::{Time, TimeStamp};
 
 pub mod prelude {
@@ -114,6 +114,8 @@ pub struct Cue {
     pub start_ts: u64,
     /// A list of `Tag`s associated with the `Cue`.
     pub tags: Vec<Tag>,
+    /// A list of `Visual`s associated with the `Cue`.
+    pub visuals: Vec<Visual>,
     /// A list of `CuePoints`s that are contained within this `Cue`. These points are children of
     /// the `Cue` since the `Cue` itself is an implicit `CuePoint`.
     pub points: Vec<CuePoint>,
@@ -141,11 +143,13 @@ pub struct Track {
     pub codec_params: CodecParameters,
     /// The language of the track. May be unknown.
     pub language: Option<String>,
+    /// The loudness normalisation information of the track. May be unknown.
+    pub gain: Option<GainInfo>,
 }
 
 impl Track {
     pub fn new(id: u32, codec_params: CodecParameters) -> Self {
-        Track { id, codec_params, language: None }
+        Track { id, codec_params, language: None, gain: None }
     }
 }
 
@@ -173,6 +177,12 @@ pub trait FormatReader: Send + Sync {
     where
         Self: Sized;
 
+    /// Provides the metadata read by the probe before the container was found, such as a leading
+    /// ID3v2 tag. A `FormatReader` may use it to obtain information that describes the stream,
+    /// such as chapters or a seek table, without reading the metadata again. The default
+    /// implementation ignores the metadata.
+    fn attach_probed_metadata(&mut self, _metadata: &MetadataLog) {}
+
     /// Gets a list of all `Cue`s.
     fn cues(&self) -> &[Cue];
 
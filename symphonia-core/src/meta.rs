@@ -379,6 +379,74 @@ pub struct Visual {
     pub data: Box<[u8]>,
 }
 
+/// `TimedTextContentType` describes the content of `TimedText`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TimedTextContentType {
+    /// The content is of an unknown or other type.
+    Other,
+    /// Song lyrics.
+    Lyrics,
+    /// A transcription of spoken text.
+    TextTranscription,
+    /// The names of movements or parts.
+    MovementName,
+    /// Events, such as "Don Quijote enters the stage".
+    Events,
+    /// Chord names.
+    Chord,
+    /// Trivia, or "pop-up" information.
+    Trivia,
+    /// URLs of webpages.
+    WebpageUrls,
+    /// URLs of images.
+    ImageUrls,
+}
+
+/// `TimedTextUnit` is the unit of the timestamps of `TimedText`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TimedTextUnit {
+    /// Timestamps are in milliseconds from the start of the stream.
+    Milliseconds,
+    /// Timestamps are a count of MPEG audio frames from the start of the stream.
+    MpegFrames,
+}
+
+/// A `TimedTextLine` is a single line, or syllable, of `TimedText`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TimedTextLine {
+    /// The time at which the line begins, in units given by the parent `TimedText`.
+    pub ts: u64,
+    /// The text of the line.
+    pub text: String,
+}
+
+/// `TimedText` is time-coded text, such as synchronized lyrics, that may be displayed in sync
+/// with playback.
+#[derive(Clone, Debug)]
+pub struct TimedText {
+    /// The content of the text.
+    pub content_type: TimedTextContentType,
+    /// The ISO-639-2 language code of the text, if known.
+    pub language: Option<String>,
+    /// A description of the text, if any.
+    pub description: Option<String>,
+    /// The unit of the timestamps of the lines.
+    pub unit: TimedTextUnit,
+    /// The lines of text, ordered by timestamp.
+    pub lines: Vec<TimedTextLine>,
+}
+
+impl TimedText {
+    /// Gets the line that is current at the timestamp `ts`. That is, the last line that begins at,
+    /// or before, `ts`.
+    pub fn line_at(&self, ts: u64) -> Option<&TimedTextLine> {
+        match self.lines.partition_point(|line| line.ts <= ts) {
+            0 => None,
+            idx => Some(&self.lines[idx - 1]),
+        }
+    }
+}
+
 /// `VendorData` is any binary metadata that is proprietary to a certain application or vendor.
 #[derive(Clone, Debug)]
 pub struct VendorData {
@@ -388,11 +456,248 @@ pub struct VendorData {
     pub data: Box<[u8]>,
 }
 
+/// An `Attachment` is an arbitrary file embedded in the metadata, such as an ID3v2 general
+/// encapsulated object.
+#[derive(Clone, Debug)]
+pub struct Attachment {
+    /// The Media Type (MIME Type) of the attached file.
+    pub media_type: String,
+    /// The file name of the attached file, if known.
+    pub file_name: Option<String>,
+    /// A description of the attached file, if any.
+    pub description: Option<String>,
+    /// The attached file.
+    pub data: Box<[u8]>,
+}
+
+/// The loudness, in LUFS, that ReplayGain gains normalise to.
+const REPLAYGAIN_REFERENCE_LOUDNESS: f32 = -18.0;
+
+/// The loudness, in LUFS, that EBU R128 gains normalise to.
+const R128_REFERENCE_LOUDNESS: f32 = -23.0;
+
+/// `GainMode` selects which gain of a `GainInfo` should be applied.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GainMode {
+    /// Normalise the loudness of each track independently.
+    Track,
+    /// Normalise the loudness of an album as a whole, preserving the relative loudness of its
+    /// tracks.
+    Album,
+}
+
+/// `GainInfo` is the loudness normalisation information of a track.
+///
+/// Depending on the format, it may be derived from ReplayGain tags, EBU R128 tags, a LAME header,
+/// ID3v2 `RVA2` frames, or iTunes Sound Check (`iTunNORM`) data.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct GainInfo {
+    /// The gain, in dB, that normalises the loudness of the track.
+    pub track_gain: Option<f32>,
+    /// The peak sample amplitude of the track, where 1.0 is full scale.
+    pub track_peak: Option<f32>,
+    /// The gain, in dB, that normalises the loudness of the album the track belongs to.
+    pub album_gain: Option<f32>,
+    /// The peak sample amplitude of the album, where 1.0 is full scale.
+    pub album_peak: Option<f32>,
+    /// The loudness, in LUFS, that the gains normalise to, if known.
+    pub reference_loudness: Option<f32>,
+}
+
+impl GainInfo {
+    /// Derives `GainInfo` from a list of `Tag`s. ReplayGain tags are preferred over EBU R128 tags,
+    /// which are preferred over iTunes Sound Check data. Returns `None` if the tags do not contain
+    /// a gain.
+    pub fn from_tags(tags: &[Tag]) -> Option<GainInfo> {
+        GainInfo::from_replaygain_tags(tags)
+            .or_else(|| GainInfo::from_r128_tags(tags))
+            .or_else(|| GainInfo::from_itunnorm_tags(tags))
+    }
+
+    fn from_replaygain_tags(tags: &[Tag]) -> Option<GainInfo> {
+        let mut info = GainInfo::default();
+
+        for tag in tags {
+            // ReplayGain tags from formats without a standard key mapping are matched by name.
+            let std_key = tag.std_key.or_else(|| {
+                [
+                    ("REPLAYGAIN_TRACK_GAIN", StandardTagKey::ReplayGainTrackGain),
+                    ("REPLAYGAIN_TRACK_PEAK", StandardTagKey::ReplayGainTrackPeak),
+                    ("REPLAYGAIN_ALBUM_GAIN", StandardTagKey::ReplayGainAlbumGain),
+                    ("REPLAYGAIN_ALBUM_PEAK", StandardTagKey::ReplayGainAlbumPeak),
+                ]
+                .iter()
+                .find(|(name, _)| tag.key.eq_ignore_ascii_case(name))
+                .map(|&(_, std_key)| std_key)
+            });
+
+            let field = match std_key {
+                Some(StandardTagKey::ReplayGainTrackGain) => &mut info.track_gain,
+                Some(StandardTagKey::ReplayGainTrackPeak) => &mut info.track_peak,
+                Some(StandardTagKey::ReplayGainAlbumGain) => &mut info.album_gain,
+                Some(StandardTagKey::ReplayGainAlbumPeak) => &mut info.album_peak,
+                _ if key_ends_with(&tag.key, "REPLAYGAIN_REFERENCE_LOUDNESS") => {
+                    // The reference is either a loudness in LUFS, or a sound pressure level in dB
+                    // where 89 dB SPL is equivalent to -18 LUFS.
+                    info.reference_loudness = parse_number(&tag.value).map(|level| {
+                        if level < 0.0 {
+                            level
+                        }
+                        else {
+                            level - 107.0
+                        }
+                    });
+                    continue;
+                }
+                _ => continue,
+            };
+
+            // If a gain or peak is tagged more than once, the first takes precedence.
+            if field.is_none() {
+                *field = parse_number(&tag.value);
+            }
+        }
+
+        if info.track_gain.is_none() && info.album_gain.is_none() {
+            return None;
+        }
+
+        info.reference_loudness = info.reference_loudness.or(Some(REPLAYGAIN_REFERENCE_LOUDNESS));
+
+        Some(info)
+    }
+
+    fn from_r128_tags(tags: &[Tag]) -> Option<GainInfo> {
+        // EBU R128 gains, as used by Opus, are Q7.8 fixed-point numbers of decibels.
+        let find_gain = |suffix: &str| {
+            tags.iter()
+                .find(|tag| key_ends_with(&tag.key, suffix))
+                .and_then(|tag| parse_number(&tag.value))
+                .map(|gain| gain / 256.0)
+        };
+
+        let track_gain = find_gain("R128_TRACK_GAIN");
+        let album_gain = find_gain("R128_ALBUM_GAIN");
+
+        if track_gain.is_none() && album_gain.is_none() {
+            return None;
+        }
+
+        Some(GainInfo {
+            track_gain,
+            album_gain,
+            reference_loudness: Some(R128_REFERENCE_LOUDNESS),
+            ..Default::default()
+        })
+    }
+
+    fn from_itunnorm_tags(tags: &[Tag]) -> Option<GainInfo> {
+        let tag = tags.iter().find(|tag| key_ends_with(&tag.key, "iTunNORM"))?;
+
+        let text = match &tag.value {
+            Value::String(text) => text,
+            _ => return None,
+        };
+
+        // Sound Check data is a list of 10 hexadecimal numbers. The first pair are the left and
+        // right channel loudness in milliwatts, and the seventh pair are the peak sample values.
+        let values = text
+            .split_whitespace()
+            .map(|word| u32::from_str_radix(word, 16))
+            .collect::<std::result::Result<Vec<u32>, _>>()
+            .ok()?;
+
+        if values.len() < 2 {
+            return None;
+        }
+
+        let loudness = values[0].max(values[1]);
+
+        if loudness == 0 {
+            return None;
+        }
+
+        let track_gain = Some(-10.0 * (loudness as f32 / 1000.0).log10());
+
+        let track_peak = values.get(6..8).map(|peaks| peaks[0].max(peaks[1]) as f32 / 32768.0);
+
+        Some(GainInfo { track_gain, track_peak, ..Default::default() })
+    }
+
+    /// Gets the gain, in dB, for `mode`. If the gain for `mode` is unknown, the gain for the other
+    /// mode is returned instead.
+    ///
+    /// If a target loudness, in LUFS, is provided and the reference loudness is known, the gain is
+    /// adjusted to normalise the loudness to the target instead of the reference.
+    pub fn gain(&self, mode: GainMode, target_loudness: Option<f32>) -> Option<f32> {
+        let gain = match mode {
+            GainMode::Track => self.track_gain.or(self.album_gain),
+            GainMode::Album => self.album_gain.or(self.track_gain),
+        }?;
+
+        match (target_loudness, self.reference_loudness) {
+            (Some(target), Some(reference)) => Some(gain + target - reference),
+            _ => Some(gain),
+        }
+    }
+
+    /// Gets the peak sample amplitude for `mode`. If the peak for `mode` is unknown, the peak for
+    /// the other mode is returned instead.
+    pub fn peak(&self, mode: GainMode) -> Option<f32> {
+        match mode {
+            GainMode::Track => self.track_peak.or(self.album_peak),
+            GainMode::Album => self.album_peak.or(self.track_peak),
+        }
+    }
+
+    /// Gets the linear scale factor that applies the gain for `mode`. See [`GainInfo::gain`].
+    ///
+    /// If `prevent_clipping` is true, and the peak is known, the scale factor is limited such that
+    /// the peak sample will not exceed full scale.
+    pub fn scale(
+        &self,
+        mode: GainMode,
+        target_loudness: Option<f32>,
+        prevent_clipping: bool,
+    ) -> f32 {
+        let scale = self.gain(mode, target_loudness).map_or(1.0, |gain| 10f32.powf(gain / 20.0));
+
+        match self.peak(mode) {
+            Some(peak) if prevent_clipping && peak > 0.0 => scale.min(1.0 / peak),
+            _ => scale,
+        }
+    }
+}
+
+/// Returns true if `key` ends with `suffix`, ignoring ASCII case.
+fn key_ends_with(key: &str, suffix: &str) -> bool {
+    let key = key.as_bytes();
+    key.len() >= suffix.len()
+        && key[key.len() - suffix.len()..].eq_ignore_ascii_case(suffix.as_bytes())
+}
+
+/// Parses a number from a `Value`, ignoring any trailing unit such as "dB" or "LUFS".
+fn parse_number(value: &Value) -> Option<f32> {
+    match value {
+        Value::Float(value) => Some(*value as f32),
+        Value::SignedInt(value) => Some(*value as f32),
+        Value::UnsignedInt(value) => Some(*value as f32),
+        Value::String(text) => text
+            .trim()
+            .trim_end_matches(|c: char| c.is_ascii_alphabetic() || c.is_whitespace())
+            .parse()
+            .ok(),
+        _ => None,
+    }
+}
+
 /// `Metadata` is a container for a single discrete revision of metadata information.
 #[derive(Clone, Debug, Default)]
 pub struct MetadataRevision {
     tags: Vec<Tag>,
     visuals: Vec<Visual>,
+    timed_text: Vec<TimedText>,
+    attachments: Vec<Attachment>,
     vendor_data: Vec<VendorData>,
 }
 
@@ -407,6 +712,16 @@ impl MetadataRevision {
         &self.visuals
     }
 
+    /// Gets an immutable slice to the `TimedText` in this revision.
+    pub fn timed_text(&self) -> &[TimedText] {
+        &self.timed_text
+    }
+
+    /// Gets an immutable slice to the `Attachment`s in this revision.
+    pub fn attachments(&self) -> &[Attachment] {
+        &self.attachments
+    }
+
     /// Gets an immutable slice to the `VendorData` in this revision.
     pub fn vendor_data(&self) -> &[VendorData] {
         &self.vendor_data
@@ -437,6 +752,18 @@ impl MetadataBuilder {
         self
     }
 
+    /// Add `TimedText` to the metadata.
+    pub fn add_timed_text(&mut self, timed_text: TimedText) -> &mut Self {
+        self.metadata.timed_text.push(timed_text);
+        self
+    }
+
+    /// Add an `Attachment` to the metadata.
+    pub fn add_attachment(&mut self, attachment: Attachment) -> &mut Self {
+        self.metadata.attachments.push(attachment);
+        self
+    }
+
     /// Add `VendorData` to the metadata.
     pub fn add_vendor_data(&mut self, vendor_data: VendorData) -> &mut Self {
         self.metadata.vendor_data.push(vendor_data);
@@ -506,6 +833,11 @@ impl MetadataLog {
     pub fn push(&mut self, rev: MetadataRevision) {
         self.revisions.push_back(rev);
     }
+
+    /// Gets an iterator over all `Metadata` revisions in the log, from oldest to newest.
+    pub fn revisions(&self) -> impl Iterator<Item = &MetadataRevision> {
+        self.revisions.iter()
+    }
 }
 
 pub trait MetadataReader: Send + Sync {
@@ -517,3 +849,60 @@ pub trait MetadataReader: Send + Sync {
     /// Read all metadata and return it if successful.
     fn read_all(&mut self, reader: &mut MediaSourceStream) -> Result<MetadataRevision>;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tag(std_key: Option<StandardTagKey>, key: &str, value: &str) -> Tag {
+        Tag::new(std_key, key, Value::from(value))
+    }
+
+    #[test]
+    fn verify_gain_info_from_tags() {
+        // ReplayGain.
+        let tags = [
+            tag(Some(StandardTagKey::ReplayGainTrackGain), "REPLAYGAIN_TRACK_GAIN", "-6.50 dB"),
+            tag(Some(StandardTagKey::ReplayGainTrackPeak), "REPLAYGAIN_TRACK_PEAK", "0.988"),
+            tag(Some(StandardTagKey::ReplayGainAlbumGain), "REPLAYGAIN_ALBUM_GAIN", "+1.25 dB"),
+            tag(None, "REPLAYGAIN_REFERENCE_LOUDNESS", "89.0 dB"),
+            tag(None, "R128_TRACK_GAIN", "-512"),
+        ];
+
+        let info = GainInfo::from_tags(&tags).unwrap();
+
+        assert_eq!(info.track_gain, Some(-6.5));
+        assert_eq!(info.track_peak, Some(0.988));
+        assert_eq!(info.album_gain, Some(1.25));
+        assert_eq!(info.album_peak, None);
+        assert_eq!(info.reference_loudness, Some(-18.0));
+
+        assert_eq!(info.gain(GainMode::Album, None), Some(1.25));
+        assert_eq!(info.gain(GainMode::Track, Some(-23.0)), Some(-11.5));
+        assert_eq!(info.peak(GainMode::Album), Some(0.988));
+        assert!((info.scale(GainMode::Album, None, true) - 1.0 / 0.988).abs() < 1e-6);
+
+        // EBU R128.
+        let info = GainInfo::from_tags(&tags[4..]).unwrap();
+
+        assert_eq!(info.track_gain, Some(-2.0));
+        assert_eq!(info.reference_loudness, Some(-23.0));
+        assert_eq!(info.gain(GainMode::Track, Some(-18.0)), Some(3.0));
+
+        // iTunes Sound Check.
+        let tags = [tag(
+            None,
+            "com.apple.iTunes:iTunNORM",
+            " 00000FA0 00000C80 00003A1D 00003A1D 00024CA8 00024CA8 00004000 00007FFF 00024CA8 \
+             00024CA8",
+        )];
+
+        let info = GainInfo::from_tags(&tags).unwrap();
+
+        assert!((info.track_gain.unwrap() + 6.0206).abs() < 1e-4);
+        assert!((info.track_peak.unwrap() - 32767.0 / 32768.0).abs() < 1e-6);
+        assert_eq!(info.reference_loudness, None);
+
+        assert!(GainInfo::from_tags(&[tag(None, "TITLE", "Title")]).is_none());
+    }
+}
@@ -182,7 +182,8 @@ pub struct ProbeResult {
     /// An instance of a `FormatReader` for the probed format
     pub format: Box<dyn FormatReader>,
     /// A log of `Metadata` revisions read during the probe operation before the instantiation of
-    /// the `FormatReader`.
+    /// the `FormatReader`. The log is also provided to the `FormatReader` through
+    /// `FormatReader::attach_probed_metadata`.
     ///
     /// Metadata that was part of the container format itself can be read by calling `.metadata()`
     /// on `format`.
@@ -314,7 +315,9 @@ impl Probe {
             match self.next(&mut mss)? {
                 // If a container format is found, return an instance to it's reader.
                 Instantiate::Format(fmt) => {
-                    let format = fmt(mss, format_opts)?;
+                    let mut format = fmt(mss, format_opts)?;
+
+                    format.attach_probed_metadata(&metadata);
 
                     let metadata =
                         if metadata.metadata().current().is_some() { Some(metadata) } else { None };
@@ -5,6 +5,8 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
+use std::io::Write;
+
 use symphonia_core::errors::{decode_error, Result};
 use symphonia_core::io::{BufReader, ReadBytes};
 use symphonia_core::meta::{
@@ -12,7 +14,7 @@ use symphonia_core::meta::{
 };
 use symphonia_core::meta::{Value, Visual};
 use symphonia_core::util::bits;
-use symphonia_metadata::{id3v1, itunes};
+use symphonia_metadata::{id3v1, itunes, lrc};
 
 use crate::atoms::{Atom, AtomHeader, AtomIterator, AtomType};
 
@@ -273,6 +275,31 @@ fn add_generic_tag<B: ReadBytes>(
     Ok(())
 }
 
+fn add_lyrics_tag<B: ReadBytes>(
+    iter: &mut AtomIterator<B>,
+    builder: &mut MetadataBuilder,
+) -> Result<()> {
+    let tag = iter.read_atom::<MetaTagAtom>()?;
+
+    for value_atom in tag.values.iter() {
+        if let Some(value) = parse_tag_value(value_atom.data_type, &value_atom.data) {
+            // Lyrics may be LRC formatted timed lyrics.
+            if let Value::String(ref text) = value {
+                if let Some(timed_text) = lrc::parse_lrc(text) {
+                    builder.add_timed_text(timed_text);
+                }
+            }
+
+            builder.add_tag(Tag::new(Some(StandardTagKey::Lyrics), "", value));
+        }
+        else {
+            warn!("unsupported data type {:?} for lyrics tag", value_atom.data_type);
+        }
+    }
+
+    Ok(())
+}
+
 fn add_var_unsigned_int_tag<B: ReadBytes>(
     iter: &mut AtomIterator<B>,
     builder: &mut MetadataBuilder,
@@ -691,9 +718,7 @@ impl Atom for IlstAtom {
                 AtomType::LongDescriptionTag => {
                     add_generic_tag(&mut iter, &mut mb, Some(StandardTagKey::Description))?
                 }
-                AtomType::LyricsTag => {
-                    add_generic_tag(&mut iter, &mut mb, Some(StandardTagKey::Lyrics))?
-                }
+                AtomType::LyricsTag => add_lyrics_tag(&mut iter, &mut mb)?,
                 AtomType::MediaTypeTag => add_media_type_tag(&mut iter, &mut mb)?,
                 AtomType::OwnerTag => {
                     add_generic_tag(&mut iter, &mut mb, Some(StandardTagKey::Owner))?
@@ -760,3 +785,282 @@ impl Atom for IlstAtom {
         Ok(IlstAtom { header, metadata: mb.metadata() })
     }
 }
+
+/// The namespace of iTunes free-form tags.
+const ITUNES_MEAN: &str = "com.apple.iTunes";
+
+/// The data type written for each kind of value atom.
+const WRITE_TYPE_IMPLICIT: u32 = 0;
+const WRITE_TYPE_UTF8: u32 = 1;
+const WRITE_TYPE_JPEG: u32 = 13;
+const WRITE_TYPE_PNG: u32 = 14;
+const WRITE_TYPE_SIGNED_INT: u32 = 21;
+const WRITE_TYPE_BMP: u32 = 27;
+
+/// The values of the media type (`stik`) atom, and their names.
+const MEDIA_TYPES: &[(u8, &str)] = &[
+    (0, "Movie"),
+    (1, "Normal"),
+    (2, "Audio Book"),
+    (5, "Whacked Bookmark"),
+    (6, "Music Video"),
+    (9, "Short Film"),
+    (10, "TV Show"),
+    (11, "Booklet"),
+];
+
+/// How a standard tag is stored in an `ilst` atom.
+enum IlstTarget {
+    /// A UTF-8 text atom.
+    Text(&'static [u8; 4]),
+    /// A big-endian signed integer atom of the given width in bytes.
+    Int(&'static [u8; 4], usize),
+    /// A boolean flag atom.
+    Flag(&'static [u8; 4]),
+}
+
+/// Gets the atom a standard tag is written to. Track and disc numbers, and the media type, are
+/// handled separately.
+fn std_key_target(std_key: StandardTagKey) -> Option<IlstTarget> {
+    let target = match std_key {
+        StandardTagKey::Album => IlstTarget::Text(b"\xa9alb"),
+        StandardTagKey::AlbumArtist => IlstTarget::Text(b"aART"),
+        StandardTagKey::Artist => IlstTarget::Text(b"\xa9ART"),
+        StandardTagKey::Bpm => IlstTarget::Int(b"tmpo", 2),
+        StandardTagKey::Comment => IlstTarget::Text(b"\xa9cmt"),
+        StandardTagKey::Compilation => IlstTarget::Flag(b"cpil"),
+        StandardTagKey::Composer => IlstTarget::Text(b"\xa9wrt"),
+        StandardTagKey::ContentGroup => IlstTarget::Text(b"\xa9grp"),
+        StandardTagKey::Copyright => IlstTarget::Text(b"cprt"),
+        StandardTagKey::Date => IlstTarget::Text(b"\xa9day"),
+        StandardTagKey::Description => IlstTarget::Text(b"desc"),
+        StandardTagKey::EncodedBy => IlstTarget::Text(b"\xa9enc"),
+        StandardTagKey::Encoder => IlstTarget::Text(b"\xa9too"),
+        StandardTagKey::Genre => IlstTarget::Text(b"\xa9gen"),
+        StandardTagKey::IdentPodcast => IlstTarget::Text(b"egid"),
+        StandardTagKey::Lyrics => IlstTarget::Text(b"\xa9lyr"),
+        StandardTagKey::Owner => IlstTarget::Text(b"ownr"),
+        StandardTagKey::Podcast => IlstTarget::Flag(b"pcst"),
+        StandardTagKey::PodcastCategory => IlstTarget::Text(b"catg"),
+        StandardTagKey::PodcastKeywords => IlstTarget::Text(b"keyw"),
+        StandardTagKey::PurchaseDate => IlstTarget::Text(b"purd"),
+        StandardTagKey::Rating => IlstTarget::Text(b"rate"),
+        StandardTagKey::SortAlbum => IlstTarget::Text(b"soal"),
+        StandardTagKey::SortAlbumArtist => IlstTarget::Text(b"soaa"),
+        StandardTagKey::SortArtist => IlstTarget::Text(b"soar"),
+        StandardTagKey::SortComposer => IlstTarget::Text(b"soco"),
+        StandardTagKey::SortTrackTitle => IlstTarget::Text(b"sonm"),
+        StandardTagKey::TrackTitle => IlstTarget::Text(b"\xa9nam"),
+        StandardTagKey::TvEpisode => IlstTarget::Int(b"tves", 4),
+        StandardTagKey::TvEpisodeTitle => IlstTarget::Text(b"tven"),
+        StandardTagKey::TvNetwork => IlstTarget::Text(b"tvnn"),
+        StandardTagKey::TvSeason => IlstTarget::Int(b"tvsn", 4),
+        StandardTagKey::TvShowTitle => IlstTarget::Text(b"tvsh"),
+        StandardTagKey::UrlPodcast => IlstTarget::Text(b"purl"),
+        _ => return None,
+    };
+
+    Some(target)
+}
+
+/// Converts a tag value to an integer, if possible. For strings, only the leading digits are
+/// considered such that "3/12" is converted to 3.
+fn value_to_int(value: &Value) -> Option<i64> {
+    match value {
+        Value::Boolean(value) => Some(i64::from(*value)),
+        Value::Flag => Some(1),
+        Value::SignedInt(value) => Some(*value),
+        Value::UnsignedInt(value) => Some(*value as i64),
+        Value::String(value) => {
+            let value = value.trim();
+            let end = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+            value[..end].parse().ok()
+        }
+        _ => None,
+    }
+}
+
+/// A metadata item atom to be written.
+struct IlstItem {
+    /// The item atom type.
+    atype: [u8; 4],
+    /// For free-form items, the tag key namespace and name.
+    freeform: Option<(String, String)>,
+    /// The data type and payload of each value atom.
+    values: Vec<(u32, Vec<u8>)>,
+}
+
+/// The items of an `ilst` atom to be written. Values written to the same item are merged into a
+/// single item atom with multiple value atoms.
+#[derive(Default)]
+struct IlstItems {
+    items: Vec<IlstItem>,
+}
+
+impl IlstItems {
+    fn push(&mut self, atype: &[u8; 4], freeform: Option<(&str, &str)>, value: (u32, Vec<u8>)) {
+        let freeform = freeform.map(|(mean, name)| (mean.to_string(), name.to_string()));
+
+        match self.items.iter_mut().find(|item| item.atype == *atype && item.freeform == freeform) {
+            Some(item) => item.values.push(value),
+            None => self.items.push(IlstItem { atype: *atype, freeform, values: vec![value] }),
+        }
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        let mut buf = Vec::new();
+
+        for item in &self.items {
+            let mut item_buf = Vec::new();
+
+            if let Some((mean, name)) = &item.freeform {
+                for (atype, text) in [(b"mean", mean), (b"name", name)].iter() {
+                    AtomHeader::write(&mut item_buf, atype, 4 + text.len() as u64)?;
+                    item_buf.write_all(&[0; 4])?;
+                    item_buf.write_all(text.as_bytes())?;
+                }
+            }
+
+            for (data_type, data) in &item.values {
+                AtomHeader::write(&mut item_buf, b"data", 8 + data.len() as u64)?;
+                item_buf.write_all(&data_type.to_be_bytes())?;
+                item_buf.write_all(&[0; 4])?;
+                item_buf.write_all(data)?;
+            }
+
+            AtomHeader::write(&mut buf, &item.atype, item_buf.len() as u64)?;
+            buf.write_all(&item_buf)?;
+        }
+
+        AtomHeader::write(writer, b"ilst", buf.len() as u64)?;
+        writer.write_all(&buf)?;
+
+        Ok(())
+    }
+}
+
+/// Writes the tags and visuals of a `MetadataRevision` as an `ilst` atom.
+///
+/// Standard tags are written to their well-known iTunes atoms, or as iTunes free-form atoms if
+/// there is no well-known atom. Tags without a standard key are written as free-form atoms using
+/// their key. A key of the form "mean:name" is split into the free-form namespace and name,
+/// otherwise the iTunes namespace is used.
+pub fn write_ilst_atom<W: Write>(writer: &mut W, revision: &MetadataRevision) -> Result<()> {
+    let mut items = IlstItems::default();
+
+    // The track and disc numbers, and their totals, are stored together in a single atom.
+    let mut track = [None; 2];
+    let mut disc = [None; 2];
+
+    for tag in revision.tags() {
+        let text = match &tag.value {
+            Value::Binary(_) => None,
+            value => Some(value.to_string()),
+        };
+
+        if let Some(std_key) = tag.std_key {
+            let m_of_n = match std_key {
+                StandardTagKey::TrackNumber => Some((&mut track, 0)),
+                StandardTagKey::TrackTotal => Some((&mut track, 1)),
+                StandardTagKey::DiscNumber => Some((&mut disc, 0)),
+                StandardTagKey::DiscTotal => Some((&mut disc, 1)),
+                _ => None,
+            };
+
+            if let Some((m_of_n, idx)) = m_of_n {
+                m_of_n[idx] = value_to_int(&tag.value);
+
+                // A number may also contain the total in the form "m/n".
+                if let (0, Some(text), None) = (idx, &text, m_of_n[1]) {
+                    m_of_n[1] = text.split('/').nth(1).and_then(|n| n.trim().parse().ok());
+                }
+                continue;
+            }
+
+            if std_key == StandardTagKey::MediaFormat {
+                let media_type = text
+                    .as_ref()
+                    .and_then(|text| MEDIA_TYPES.iter().find(|(_, name)| name == text));
+
+                if let Some((media_type, _)) = media_type {
+                    items.push(b"stik", None, (WRITE_TYPE_SIGNED_INT, vec![*media_type]));
+                    continue;
+                }
+            }
+
+            match (std_key_target(std_key), &text) {
+                (Some(IlstTarget::Text(atype)), Some(text)) => {
+                    items.push(atype, None, (WRITE_TYPE_UTF8, text.as_bytes().to_vec()));
+                    continue;
+                }
+                (Some(IlstTarget::Int(atype, width)), _) => {
+                    match value_to_int(&tag.value) {
+                        Some(value) => {
+                            let data = value.to_be_bytes()[8 - width..].to_vec();
+                            items.push(atype, None, (WRITE_TYPE_SIGNED_INT, data));
+                        }
+                        None => warn!("isomp4: skipping non-integer {:?} tag", std_key),
+                    }
+                    continue;
+                }
+                (Some(IlstTarget::Flag(atype)), _) => {
+                    let flag = value_to_int(&tag.value).map_or(0, |value| (value != 0) as u8);
+                    items.push(atype, None, (WRITE_TYPE_SIGNED_INT, vec![flag]));
+                    continue;
+                }
+                _ => (),
+            }
+        }
+
+        // Write all other tags as free-form tags.
+        let full_name = match tag.std_key.and_then(itunes::tag_from_std_key) {
+            Some(full_name) => full_name,
+            None if !tag.key.is_empty() => &tag.key,
+            None => {
+                warn!("isomp4: skipping tag {:?} without a key", tag.std_key);
+                continue;
+            }
+        };
+
+        let (mean, name) = match full_name.find(':') {
+            Some(pos) => (&full_name[..pos], &full_name[pos + 1..]),
+            None => (ITUNES_MEAN, full_name),
+        };
+
+        let value = match &tag.value {
+            Value::Binary(data) => (WRITE_TYPE_IMPLICIT, data.to_vec()),
+            _ => (WRITE_TYPE_UTF8, text.unwrap_or_default().into_bytes()),
+        };
+
+        items.push(b"----", Some((mean, name)), value);
+    }
+
+    for (atype, m_of_n) in [(b"trkn", track), (b"disk", disc)].iter() {
+        if let [Some(m), n] = m_of_n {
+            let m = *m as u16;
+            let n = n.unwrap_or(0) as u16;
+
+            let mut data = vec![0; 8];
+            data[2..4].copy_from_slice(&m.to_be_bytes());
+            data[4..6].copy_from_slice(&n.to_be_bytes());
+
+            items.push(atype, None, (WRITE_TYPE_IMPLICIT, data));
+        }
+    }
+
+    for visual in revision.visuals() {
+        let data_type = match visual.media_type.as_str() {
+            "image/bmp" => WRITE_TYPE_BMP,
+            "image/jpeg" | "image/jpg" => WRITE_TYPE_JPEG,
+            "image/png" => WRITE_TYPE_PNG,
+            _ => {
+                warn!("isomp4: skipping cover art with media type '{}'", visual.media_type);
+                continue;
+            }
+        };
+
+        items.push(b"covr", None, (data_type, visual.data.to_vec()));
+    }
+
+    items.write(writer)
+}
@@ -5,6 +5,8 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
+use std::io::Write;
+
 use symphonia_core::errors::{decode_error, Result};
 use symphonia_core::io::ReadBytes;
 
@@ -363,6 +365,24 @@ impl AtomHeader {
         }
     }
 
+    /// Writes an atom header for an atom of type `atype` with a payload of `data_len` bytes. An
+    /// extended header is written if the atom size cannot be represented in 32-bits.
+    pub fn write<W: Write>(writer: &mut W, atype: &[u8; 4], data_len: u64) -> Result<()> {
+        let atom_len = data_len + AtomHeader::HEADER_SIZE;
+
+        if atom_len <= u64::from(u32::MAX) {
+            writer.write_all(&(atom_len as u32).to_be_bytes())?;
+            writer.write_all(atype)?;
+        }
+        else {
+            writer.write_all(&1u32.to_be_bytes())?;
+            writer.write_all(atype)?;
+            writer.write_all(&(data_len + AtomHeader::EXTENDED_HEADER_SIZE).to_be_bytes())?;
+        }
+
+        Ok(())
+    }
+
     /// For applicable atoms, reads the atom header extra data: a tuple composed of a u8 version
     /// number, and a u24 bitset of flags.
     pub fn read_extra<B: ReadBytes>(reader: &mut B) -> Result<(u8, u32)> {
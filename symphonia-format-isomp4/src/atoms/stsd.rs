@@ -304,20 +304,12 @@ fn pcm_channels(num_channels: u32) -> Result<Channels> {
 
 /// Gets the audio channels for a version 2 LPCM sample entry.
 fn lpcm_channels(num_channels: u32) -> Result<Channels> {
-    if num_channels < 1 {
-        return decode_error("isomp4: invalid number of channels");
-    }
-
-    if num_channels > 32 {
-        return unsupported_error("isomp4: maximum 32 channels");
-    }
-
-    // TODO: For LPCM, the channels are "auxilary". They do not have a speaker assignment. Symphonia
-    // does not have a way to represent this yet.
-    let channel_mask = !((!0 << 1) << (num_channels - 1));
-
-    match Channels::from_bits(channel_mask) {
-        Some(channels) => Ok(channels),
+    match num_channels {
+        0 => decode_error("isomp4: invalid number of channels"),
+        // Mono and stereo are assumed, as for version 0 and 1 sample entries.
+        1 | 2 => pcm_channels(num_channels),
+        // Otherwise, the channels are "auxilary". They do not have a speaker assignment.
+        n if n <= u32::from(u16::MAX) => Ok(Channels::discrete(n as u16)),
         _ => unsupported_error("isomp4: unsupported number of channels"),
     }
 }
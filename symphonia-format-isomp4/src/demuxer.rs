@@ -11,7 +11,7 @@ use symphonia_core::codecs::CodecParameters;
 use symphonia_core::errors::{decode_error, seek_error, unsupported_error, Result, SeekErrorKind};
 use symphonia_core::formats::prelude::*;
 use symphonia_core::io::{MediaSource, MediaSourceStream, ReadBytes, SeekBuffered};
-use symphonia_core::meta::{Metadata, MetadataLog};
+use symphonia_core::meta::{GainInfo, Metadata, MetadataLog};
 use symphonia_core::probe::{Descriptor, Instantiate, QueryDescriptor};
 use symphonia_core::units::Time;
 
@@ -450,7 +450,11 @@ impl FormatReader for IsoMp4Reader {
             }
         }
 
+        // The gain is derived from the file-level metadata, and therefore applies to all tracks.
+        let mut gain = None;
+
         if let Some(rev) = moov.take_metadata() {
+            gain = GainInfo::from_tags(rev.tags());
             metadata.push(rev);
         }
 
@@ -465,7 +469,11 @@ impl FormatReader for IsoMp4Reader {
         // Instantiate a Tracks for all tracks above.
         let tracks = track_states
             .iter()
-            .map(|track| Track::new(track.track_num as u32, track.codec_params()))
+            .map(|track| {
+                let mut track = Track::new(track.track_num as u32, track.codec_params());
+                track.gain = gain;
+                track
+            })
             .collect();
 
         // A Movie Extends (mvex) atom is required to support segmented streams. If the mvex atom is
@@ -18,5 +18,9 @@ mod demuxer;
 mod fourcc;
 mod fp;
 mod stream;
+mod writer;
 
 pub use demuxer::IsoMp4Reader;
+pub use writer::{
+    update_mp4_file, write_mp4_metadata, write_mp4_metadata_in_place, Mp4WriteOptions,
+};
@@ -0,0 +1,712 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::fs::OpenOptions;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use symphonia_core::errors::{decode_error, unsupported_error, Result};
+use symphonia_core::meta::MetadataRevision;
+use symphonia_metadata::file::replace_file;
+
+use crate::atoms::ilst::write_ilst_atom;
+use crate::atoms::AtomHeader;
+
+/// The container atoms that are traversed to reach the metadata and chunk offset atoms.
+const CONTAINERS: &[&[u8; 4]] = &[b"moov", b"trak", b"mdia", b"minf", b"stbl", b"udta", b"meta"];
+
+/// The payload of the handler atom of an iTunes metadata atom.
+const MDIR_HANDLER: [u8; 25] = *b"\0\0\0\0\0\0\0\0mdirappl\0\0\0\0\0\0\0\0\0";
+
+/// The length of a basic atom header.
+const HEADER_LEN: u64 = 8;
+
+/// Options for writing MP4 metadata.
+#[derive(Copy, Clone, Debug)]
+pub struct Mp4WriteOptions {
+    /// The length of the `free` atom to write after the `moov` atom when the file must be
+    /// rewritten, such that later edits may be performed in-place.
+    pub padding: usize,
+}
+
+impl Default for Mp4WriteOptions {
+    fn default() -> Self {
+        Mp4WriteOptions { padding: 2048 }
+    }
+}
+
+/// The payload of an atom.
+#[derive(Clone)]
+enum Body {
+    /// An atom with opaque data.
+    Leaf(Vec<u8>),
+    /// An atom containing other atoms. Some containers have a prefix (e.g., the version and flags
+    /// of the `meta` atom), or a trailer (e.g., the null terminator of a QuickTime `udta` atom).
+    Container { prefix: Vec<u8>, children: Vec<RawAtom>, trailer: Vec<u8> },
+}
+
+/// An atom read into memory.
+#[derive(Clone)]
+struct RawAtom {
+    atype: [u8; 4],
+    body: Body,
+}
+
+impl RawAtom {
+    fn container(atype: &[u8; 4], prefix: Vec<u8>) -> Self {
+        RawAtom {
+            atype: *atype,
+            body: Body::Container { prefix, children: Vec::new(), trailer: Vec::new() },
+        }
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<RawAtom> {
+        match &mut self.body {
+            Body::Container { children, .. } => children,
+            Body::Leaf(_) => unreachable!(),
+        }
+    }
+
+    /// Gets the length of the atom payload.
+    fn data_len(&self) -> u64 {
+        match &self.body {
+            Body::Leaf(data) => data.len() as u64,
+            Body::Container { prefix, children, trailer } => {
+                let children_len: u64 = children.iter().map(|child| child.len()).sum();
+                prefix.len() as u64 + children_len + trailer.len() as u64
+            }
+        }
+    }
+
+    /// Gets the total length of the atom, including the header.
+    fn len(&self) -> u64 {
+        let data_len = self.data_len();
+
+        match data_len + HEADER_LEN {
+            len if len <= u64::from(u32::MAX) => len,
+            len => len + 8,
+        }
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        AtomHeader::write(writer, &self.atype, self.data_len())?;
+
+        match &self.body {
+            Body::Leaf(data) => writer.write_all(data)?,
+            Body::Container { prefix, children, trailer } => {
+                writer.write_all(prefix)?;
+                for child in children {
+                    child.write(writer)?;
+                }
+                writer.write_all(trailer)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Finds the first child of the given type, or appends one created by `new`.
+    fn child_or_insert_with<F>(&mut self, atype: &[u8; 4], new: F) -> &mut RawAtom
+    where
+        F: FnOnce() -> RawAtom,
+    {
+        let children = self.children_mut();
+
+        let idx = match children.iter().position(|child| &child.atype == atype) {
+            Some(idx) => idx,
+            None => {
+                children.push(new());
+                children.len() - 1
+            }
+        };
+
+        &mut children[idx]
+    }
+}
+
+/// Parses a sequence of atoms, recursing into known containers. Returns the atoms, and any
+/// trailing bytes too short to be an atom.
+fn parse_atoms(buf: &[u8]) -> Result<(Vec<RawAtom>, Vec<u8>)> {
+    let mut atoms = Vec::new();
+    let mut pos = 0;
+
+    while buf.len() - pos >= HEADER_LEN as usize {
+        let size = u32::from_be_bytes([buf[pos], buf[pos + 1], buf[pos + 2], buf[pos + 3]]);
+
+        let mut atype = [0; 4];
+        atype.copy_from_slice(&buf[pos + 4..pos + 8]);
+
+        let (header_len, atom_len) = match size {
+            0 => (8, buf.len() - pos),
+            1 if buf.len() - pos >= 16 => {
+                let mut len = [0; 8];
+                len.copy_from_slice(&buf[pos + 8..pos + 16]);
+                (16, u64::from_be_bytes(len) as usize)
+            }
+            _ => (8, size as usize),
+        };
+
+        if atom_len < header_len || atom_len > buf.len() - pos {
+            return decode_error("isomp4: atom size is invalid");
+        }
+
+        let data = &buf[pos + header_len..pos + atom_len];
+
+        let body = if CONTAINERS.contains(&&atype) {
+            // An ISO meta atom has a version and flags, while a QuickTime meta atom does not.
+            let prefix_len =
+                if &atype == b"meta" && data.get(4..8) != Some(b"hdlr") { 4 } else { 0 };
+
+            if data.len() < prefix_len {
+                return decode_error("isomp4: atom size is invalid");
+            }
+
+            let (children, trailer) = parse_atoms(&data[prefix_len..])?;
+
+            Body::Container { prefix: data[..prefix_len].to_vec(), children, trailer }
+        }
+        else {
+            Body::Leaf(data.to_vec())
+        };
+
+        atoms.push(RawAtom { atype, body });
+
+        pos += atom_len;
+    }
+
+    Ok((atoms, buf[pos..].to_vec()))
+}
+
+/// Replaces the `ilst` atom of a `moov` atom, creating the `udta` and `meta` atoms if required.
+/// Any `free` atoms within the `udta` and `meta` atoms are removed.
+fn replace_ilst(moov: &mut RawAtom, revision: &MetadataRevision) -> Result<()> {
+    let mut buf = Vec::new();
+    write_ilst_atom(&mut buf, revision)?;
+
+    let ilst = parse_atoms(&buf)?.0.remove(0);
+
+    let udta = moov.child_or_insert_with(b"udta", || RawAtom::container(b"udta", Vec::new()));
+    udta.children_mut().retain(|child| &child.atype != b"free");
+
+    let meta = udta.child_or_insert_with(b"meta", || {
+        let mut meta = RawAtom::container(b"meta", vec![0; 4]);
+        meta.children_mut()
+            .push(RawAtom { atype: *b"hdlr", body: Body::Leaf(MDIR_HANDLER.to_vec()) });
+        meta
+    });
+
+    meta.children_mut().retain(|child| &child.atype != b"free");
+
+    match meta.children_mut().iter_mut().find(|child| &child.atype == b"ilst") {
+        Some(child) => *child = ilst,
+        None => meta.children_mut().push(ilst),
+    }
+
+    Ok(())
+}
+
+/// Adds `delta` to all chunk offsets at, or after, `from`. If `promote` is true, `stco` atoms are
+/// converted to `co64` atoms. Returns false if a `stco` offset would overflow.
+fn fixup_chunk_offsets(atom: &mut RawAtom, from: u64, delta: i64, promote: bool) -> Result<bool> {
+    let data = match &mut atom.body {
+        Body::Container { children, .. } => {
+            let mut ok = true;
+            for child in children {
+                ok &= fixup_chunk_offsets(child, from, delta, promote)?;
+            }
+            return Ok(ok);
+        }
+        Body::Leaf(data) => data,
+    };
+
+    let width = match &atom.atype {
+        b"stco" => 4,
+        b"co64" => 8,
+        _ => return Ok(true),
+    };
+
+    if data.len() < 8 {
+        return decode_error("isomp4: invalid chunk offset atom");
+    }
+
+    let count = u32::from_be_bytes([data[4], data[5], data[6], data[7]]) as usize;
+
+    if data.len() < 8 + width * count {
+        return decode_error("isomp4: invalid chunk offset atom");
+    }
+
+    let mut offsets = Vec::with_capacity(count);
+
+    for entry in data[8..8 + width * count].chunks_exact(width) {
+        let mut buf = [0; 8];
+        buf[8 - width..].copy_from_slice(entry);
+
+        let offset = u64::from_be_bytes(buf);
+
+        let offset = if offset >= from {
+            match (offset as i64).checked_add(delta) {
+                Some(offset) if offset >= 0 => offset as u64,
+                _ => return decode_error("isomp4: invalid chunk offset"),
+            }
+        }
+        else {
+            offset
+        };
+
+        offsets.push(offset);
+    }
+
+    let width = if promote && width == 4 {
+        atom.atype = *b"co64";
+        8
+    }
+    else if width == 4 && offsets.iter().any(|&offset| offset > u64::from(u32::MAX)) {
+        return Ok(false);
+    }
+    else {
+        width
+    };
+
+    let mut buf = data[..8].to_vec();
+
+    for offset in offsets {
+        buf.extend_from_slice(&offset.to_be_bytes()[8 - width..]);
+    }
+
+    *data = buf;
+
+    Ok(true)
+}
+
+/// A top-level atom.
+struct TopLevelAtom {
+    atype: [u8; 4],
+    pos: u64,
+    len: u64,
+}
+
+/// Reads the types and positions of all top-level atoms.
+fn read_top_level_atoms<R: Read + Seek>(reader: &mut R) -> Result<Vec<TopLevelAtom>> {
+    let end = reader.seek(SeekFrom::End(0))?;
+
+    let mut atoms = Vec::new();
+    let mut pos = 0;
+
+    while end - pos >= HEADER_LEN {
+        reader.seek(SeekFrom::Start(pos))?;
+
+        let mut header = [0; 8];
+        reader.read_exact(&mut header)?;
+
+        let mut atype = [0; 4];
+        atype.copy_from_slice(&header[4..]);
+
+        let len = match u32::from_be_bytes([header[0], header[1], header[2], header[3]]) {
+            0 => end - pos,
+            1 => {
+                let mut len = [0; 8];
+                reader.read_exact(&mut len)?;
+                u64::from_be_bytes(len)
+            }
+            len => u64::from(len),
+        };
+
+        if len < HEADER_LEN || len > end - pos {
+            return decode_error("isomp4: atom size is invalid");
+        }
+
+        atoms.push(TopLevelAtom { atype, pos, len });
+
+        pos += len;
+    }
+
+    Ok(atoms)
+}
+
+/// The location of the `moov` atom, and any `free` or `skip` atoms immediately following it.
+struct MoovRegion {
+    pos: u64,
+    len: u64,
+}
+
+/// Finds the `moov` atom and reads it into memory.
+fn read_moov<R: Read + Seek>(
+    reader: &mut R,
+    atoms: &[TopLevelAtom],
+) -> Result<(RawAtom, MoovRegion)> {
+    let idx = match atoms.iter().position(|atom| &atom.atype == b"moov") {
+        Some(idx) => idx,
+        None => return unsupported_error("isomp4: missing moov atom"),
+    };
+
+    let moov = &atoms[idx];
+
+    let padding_len: u64 = atoms[idx + 1..]
+        .iter()
+        .take_while(|atom| &atom.atype == b"free" || &atom.atype == b"skip")
+        .map(|atom| atom.len)
+        .sum();
+
+    let mut buf = vec![0; moov.len as usize];
+    reader.seek(SeekFrom::Start(moov.pos))?;
+    reader.read_exact(&mut buf)?;
+
+    let atom = parse_atoms(&buf)?.0.remove(0);
+
+    Ok((atom, MoovRegion { pos: moov.pos, len: moov.len + padding_len }))
+}
+
+/// Writes a `free` atom with a total length of `len` bytes.
+fn write_free_atom<W: Write>(writer: &mut W, len: u64) -> Result<()> {
+    AtomHeader::write(writer, b"free", len - HEADER_LEN)?;
+    io::copy(&mut io::repeat(0).take(len - HEADER_LEN), writer)?;
+    Ok(())
+}
+
+/// Attempts to replace the tags and cover art of a MP4 file without moving any other atoms.
+///
+/// The new metadata is written in-place if the `moov` atom, after replacing its `ilst` atom, fits
+/// within the space occupied by the existing `moov` atom and any `free` atoms immediately following
+/// it. The remaining space is filled with a new `free` atom.
+///
+/// Returns `true` if the metadata was written, or `false` if there is insufficient space and the
+/// stream was left untouched.
+pub fn write_mp4_metadata_in_place<S: Read + Write + Seek>(
+    stream: &mut S,
+    revision: &MetadataRevision,
+) -> Result<bool> {
+    let atoms = read_top_level_atoms(stream)?;
+
+    let (mut moov, region) = read_moov(stream, &atoms)?;
+
+    replace_ilst(&mut moov, revision)?;
+
+    let moov_len = moov.len();
+
+    let padding = if moov_len == region.len {
+        None
+    }
+    else if moov_len + HEADER_LEN <= region.len {
+        Some(region.len - moov_len)
+    }
+    else {
+        return Ok(false);
+    };
+
+    let mut buf = Vec::with_capacity(region.len as usize);
+
+    moov.write(&mut buf)?;
+
+    if let Some(padding) = padding {
+        write_free_atom(&mut buf, padding)?;
+    }
+
+    stream.seek(SeekFrom::Start(region.pos))?;
+    stream.write_all(&buf)?;
+    stream.flush()?;
+
+    Ok(true)
+}
+
+/// Copies a MP4 file from `reader` to `writer` while replacing its tags and cover art.
+///
+/// The `moov` atom is always rewritten and followed by a `free` atom of the length specified in
+/// `options`. All chunk offsets that point past the `moov` atom are corrected, and `stco` atoms are
+/// promoted to `co64` atoms if required.
+pub fn write_mp4_metadata<R: Read + Seek, W: Write>(
+    reader: &mut R,
+    writer: &mut W,
+    revision: &MetadataRevision,
+    options: &Mp4WriteOptions,
+) -> Result<()> {
+    let atoms = read_top_level_atoms(reader)?;
+
+    let (mut moov, region) = read_moov(reader, &atoms)?;
+
+    replace_ilst(&mut moov, revision)?;
+
+    let region_end = region.pos + region.len;
+
+    // Chunk offsets and fragments that follow the moov atom will be moved.
+    if atoms.iter().any(|atom| &atom.atype == b"moof" && atom.pos >= region_end) {
+        return unsupported_error("isomp4: cannot rewrite a fragmented file");
+    }
+
+    let padding = options.padding as u64 + HEADER_LEN;
+
+    let mut fixed = moov.clone();
+
+    let delta = (moov.len() + padding) as i64 - region.len as i64;
+
+    if !fixup_chunk_offsets(&mut fixed, region_end, delta, false)? {
+        // Promoting stco atoms grows the moov atom, therefore the delta must be recalculated.
+        fixup_chunk_offsets(&mut moov, 0, 0, true)?;
+
+        fixed = moov.clone();
+
+        let delta = (moov.len() + padding) as i64 - region.len as i64;
+
+        fixup_chunk_offsets(&mut fixed, region_end, delta, false)?;
+    }
+
+    reader.seek(SeekFrom::Start(0))?;
+    io::copy(&mut reader.by_ref().take(region.pos), writer)?;
+
+    fixed.write(writer)?;
+    write_free_atom(writer, padding)?;
+
+    reader.seek(SeekFrom::Start(region_end))?;
+    io::copy(reader, writer)?;
+
+    Ok(())
+}
+
+/// Replaces the tags and cover art of a MP4 file.
+///
+/// The metadata is written in-place if possible. Otherwise, the file is rewritten to a temporary
+/// file in the same directory as described by `write_mp4_metadata`, and then renamed over the
+/// original file.
+pub fn update_mp4_file<P: AsRef<Path>>(
+    path: P,
+    revision: &MetadataRevision,
+    options: &Mp4WriteOptions,
+) -> Result<()> {
+    let path = path.as_ref();
+
+    let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+
+    if write_mp4_metadata_in_place(&mut file, revision)? {
+        return Ok(());
+    }
+
+    replace_file(path, |temp| write_mp4_metadata(&mut file, temp, revision, options))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::io::Cursor;
+
+    use symphonia_core::io::BufReader;
+    use symphonia_core::meta::{MetadataBuilder, StandardTagKey, StandardVisualKey, Tag};
+    use symphonia_core::meta::{Value, Visual};
+
+    use super::*;
+    use crate::atoms::{Atom, IlstAtom};
+
+    fn leaf(atype: &[u8; 4], data: Vec<u8>) -> RawAtom {
+        RawAtom { atype: *atype, body: Body::Leaf(data) }
+    }
+
+    fn container(atype: &[u8; 4], children: Vec<RawAtom>) -> RawAtom {
+        RawAtom {
+            atype: *atype,
+            body: Body::Container { prefix: Vec::new(), children, trailer: Vec::new() },
+        }
+    }
+
+    fn stco(offsets: &[u32]) -> RawAtom {
+        let mut data = vec![0; 4];
+        data.extend_from_slice(&(offsets.len() as u32).to_be_bytes());
+        for offset in offsets {
+            data.extend_from_slice(&offset.to_be_bytes());
+        }
+        leaf(b"stco", data)
+    }
+
+    /// Builds a file with a moov atom that preceeds the media data, and `padding` bytes of free
+    /// atoms after it.
+    fn make_file(padding: Option<u64>) -> Vec<u8> {
+        let build = |offsets: &[u32]| {
+            let stbl = container(b"stbl", vec![stco(offsets)]);
+            let trak =
+                container(b"trak", vec![container(b"mdia", vec![container(b"minf", vec![stbl])])]);
+            container(b"moov", vec![leaf(b"mvhd", vec![0; 100]), trak])
+        };
+
+        let ftyp = leaf(b"ftyp", b"M4A \0\0\0\0M4A mp42isom".to_vec());
+
+        // The chunk offsets depend on the length of the moov atom, which does not depend on the
+        // value of the offsets.
+        let mdat_pos = ftyp.len() + build(&[0, 0]).len() + padding.unwrap_or(0) + HEADER_LEN;
+
+        let mut file = Vec::new();
+        ftyp.write(&mut file).unwrap();
+        build(&[mdat_pos as u32, mdat_pos as u32 + 6]).write(&mut file).unwrap();
+        if let Some(padding) = padding {
+            write_free_atom(&mut file, padding).unwrap();
+        }
+        leaf(b"mdat", b"chunk0chunk1".to_vec()).write(&mut file).unwrap();
+        file
+    }
+
+    /// Finds an atom by its path.
+    fn find<'a>(atoms: &'a [RawAtom], path: &[&[u8; 4]]) -> &'a RawAtom {
+        let atom = atoms.iter().find(|atom| &atom.atype == path[0]).unwrap();
+
+        match (&atom.body, path.len()) {
+            (_, 1) => atom,
+            (Body::Container { children, .. }, _) => find(children, &path[1..]),
+            _ => panic!(),
+        }
+    }
+
+    /// Reads the chunk offsets and metadata of a file.
+    fn read_file(file: &[u8]) -> (Vec<u64>, MetadataRevision) {
+        let (atoms, _) = parse_atoms(file).unwrap();
+
+        let offsets = match &find(&atoms, &[b"moov", b"trak", b"mdia", b"minf", b"stbl"]).body {
+            Body::Container { children, .. } => match (&children[0].atype, &children[0].body) {
+                (b"stco", Body::Leaf(data)) => data[8..]
+                    .chunks(4)
+                    .map(|c| u64::from(u32::from_be_bytes([c[0], c[1], c[2], c[3]])))
+                    .collect(),
+                (b"co64", Body::Leaf(data)) => data[8..]
+                    .chunks(8)
+                    .map(|c| u64::from_be_bytes([c[0], c[1], c[2], c[3], c[4], c[5], c[6], c[7]]))
+                    .collect(),
+                _ => panic!(),
+            },
+            _ => panic!(),
+        };
+
+        let mut buf = Vec::new();
+        find(&atoms, &[b"moov", b"udta", b"meta", b"ilst"]).write(&mut buf).unwrap();
+
+        let mut reader = BufReader::new(&buf);
+        let header = AtomHeader::read(&mut reader).unwrap();
+        let ilst = IlstAtom::read(&mut reader, header).unwrap();
+
+        (offsets, ilst.metadata)
+    }
+
+    fn make_revision(title: &str) -> MetadataRevision {
+        let mut builder = MetadataBuilder::new();
+        builder.add_tag(Tag::new(Some(StandardTagKey::TrackTitle), "", Value::from(title)));
+        builder.add_tag(Tag::new(Some(StandardTagKey::Artist), "", Value::from("A")));
+        builder.add_tag(Tag::new(Some(StandardTagKey::Artist), "", Value::from("B")));
+        builder.add_tag(Tag::new(Some(StandardTagKey::TrackNumber), "", Value::from("3/12")));
+        builder.add_tag(Tag::new(Some(StandardTagKey::Compilation), "", Value::Flag));
+        builder.add_tag(Tag::new(Some(StandardTagKey::Label), "", Value::from("L")));
+        builder.add_tag(Tag::new(None, "org.example:CUSTOM", Value::from("C")));
+        builder.add_visual(Visual {
+            media_type: "image/png".to_string(),
+            dimensions: None,
+            bits_per_pixel: None,
+            color_mode: None,
+            usage: Some(StandardVisualKey::FrontCover),
+            tags: Vec::new(),
+            data: Box::new([1, 2, 3]),
+        });
+        builder.metadata()
+    }
+
+    fn verify_chunks(file: &[u8], offsets: &[u64]) {
+        assert_eq!(&file[offsets[0] as usize..][..6], b"chunk0");
+        assert_eq!(&file[offsets[1] as usize..][..6], b"chunk1");
+    }
+
+    #[test]
+    fn verify_write_mp4_metadata() {
+        let file = make_file(None);
+
+        // There is no space for the metadata.
+        let mut stream = Cursor::new(file.clone());
+        assert!(!write_mp4_metadata_in_place(&mut stream, &make_revision("Title")).unwrap());
+        assert_eq!(stream.into_inner(), file);
+
+        // Rewrite the file with padding.
+        let mut rewritten = Vec::new();
+        let options = Mp4WriteOptions { padding: 64 };
+        write_mp4_metadata(
+            &mut Cursor::new(&file),
+            &mut rewritten,
+            &make_revision("Title"),
+            &options,
+        )
+        .unwrap();
+
+        let (offsets, metadata) = read_file(&rewritten);
+        verify_chunks(&rewritten, &offsets);
+
+        let tags: Vec<(Option<StandardTagKey>, String, String)> = metadata
+            .tags()
+            .iter()
+            .map(|tag| (tag.std_key, tag.key.clone(), tag.value.to_string()))
+            .collect();
+
+        assert_eq!(
+            tags,
+            vec![
+                (Some(StandardTagKey::TrackTitle), "".to_string(), "Title".to_string()),
+                (Some(StandardTagKey::Artist), "".to_string(), "A".to_string()),
+                (Some(StandardTagKey::Artist), "".to_string(), "B".to_string()),
+                (Some(StandardTagKey::Compilation), "".to_string(), "1".to_string()),
+                (
+                    Some(StandardTagKey::Label),
+                    "com.apple.iTunes:LABEL".to_string(),
+                    "L".to_string()
+                ),
+                (None, "org.example:CUSTOM".to_string(), "C".to_string()),
+                (Some(StandardTagKey::TrackNumber), "".to_string(), "3".to_string()),
+                (Some(StandardTagKey::TrackTotal), "".to_string(), "12".to_string()),
+            ]
+        );
+
+        assert_eq!(metadata.visuals().len(), 1);
+        assert_eq!(metadata.visuals()[0].media_type, "image/png");
+        assert_eq!(&*metadata.visuals()[0].data, &[1, 2, 3]);
+
+        // A slightly longer title fits within the padding.
+        let mut stream = Cursor::new(rewritten.clone());
+        assert!(write_mp4_metadata_in_place(&mut stream, &make_revision("Title 2")).unwrap());
+
+        let updated = stream.into_inner();
+        assert_eq!(updated.len(), rewritten.len());
+
+        let (offsets, metadata) = read_file(&updated);
+        verify_chunks(&updated, &offsets);
+        assert_eq!(metadata.tags()[0].value.to_string(), "Title 2");
+
+        // Update a file on disk, which requires rewriting the file.
+        let path =
+            std::env::temp_dir().join(format!("symphonia-isomp4-{}.m4a", std::process::id()));
+        fs::write(&path, &file).unwrap();
+
+        update_mp4_file(&path, &make_revision("Title"), &options).unwrap();
+
+        let updated = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(updated, rewritten);
+    }
+
+    #[test]
+    fn verify_fixup_chunk_offsets() {
+        let mut atom = container(b"stbl", vec![stco(&[10, 100, u32::MAX - 10])]);
+
+        // Offsets would overflow.
+        assert!(!fixup_chunk_offsets(&mut atom.clone(), 50, 20, false).unwrap());
+
+        // Promote to co64.
+        assert!(fixup_chunk_offsets(&mut atom, 50, 20, true).unwrap());
+
+        match &atom.body {
+            Body::Container { children, .. } => match &children[0].body {
+                Body::Leaf(data) => {
+                    assert_eq!(&children[0].atype, b"co64");
+                    assert_eq!(&data[4..8], &[0, 0, 0, 3]);
+                    assert_eq!(&data[8..16], &10u64.to_be_bytes());
+                    assert_eq!(&data[16..24], &120u64.to_be_bytes());
+                    assert_eq!(&data[24..32], &(u64::from(u32::MAX) + 10).to_be_bytes());
+                }
+                _ => panic!(),
+            },
+            _ => panic!(),
+        }
+    }
+}
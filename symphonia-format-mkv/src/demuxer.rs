@@ -9,8 +9,10 @@ use std::collections::{HashMap, VecDeque};
 use std::convert::TryFrom;
 use std::io::{Seek, SeekFrom};
 
-use symphonia_core::audio::Layout;
-use symphonia_core::codecs::{CodecParameters, CODEC_TYPE_FLAC, CODEC_TYPE_VORBIS};
+use symphonia_core::audio::{Channels, Layout};
+use symphonia_core::codecs::{
+    CodecParameters, CODEC_TYPE_FLAC, CODEC_TYPE_OPUS, CODEC_TYPE_VORBIS,
+};
 use symphonia_core::errors::{
     decode_error, end_of_stream_error, seek_error, unsupported_error, Error, Result, SeekErrorKind,
 };
@@ -18,13 +20,14 @@ use symphonia_core::formats::{
     Cue, FormatOptions, FormatReader, Packet, SeekMode, SeekTo, SeekedTo, Track,
 };
 use symphonia_core::io::{BufReader, MediaSource, MediaSourceStream, ReadBytes};
-use symphonia_core::meta::{Metadata, MetadataLog};
+use symphonia_core::meta::{GainInfo, Metadata, MetadataLog};
 use symphonia_core::probe::Instantiate;
 use symphonia_core::probe::{Descriptor, QueryDescriptor};
 use symphonia_core::sample::SampleFormat;
 use symphonia_core::support_format;
 use symphonia_core::units::TimeBase;
 use symphonia_utils_xiph::flac::metadata::{MetadataBlockHeader, MetadataBlockType};
+use symphonia_utils_xiph::opus::opus_head_channels;
 
 use crate::codecs::codec_id_to_type;
 use crate::ebml::{EbmlElement, ElementHeader, ElementIterator};
@@ -432,6 +435,9 @@ impl FormatReader for MkvReader {
         let time_base = TimeBase::new(u32::try_from(info.timestamp_scale).unwrap(), 1_000_000_000);
 
         let mut tracks = Vec::new();
+
+        // Tags are not associated with their target tracks, so the gain applies to all tracks.
+        let gain = metadata.metadata().current().and_then(|rev| GainInfo::from_tags(rev.tags()));
         let mut states = HashMap::new();
         for track in segment_tracks.tracks.into_vec() {
             let codec_type = codec_id_to_type(&track);
@@ -462,23 +468,33 @@ impl FormatReader for MkvReader {
                     codec_params.with_bits_per_sample(bits as u32);
                 }
 
-                let layout = match audio.channels {
-                    1 => Some(Layout::Mono),
-                    2 => Some(Layout::Stereo),
-                    3 => Some(Layout::TwoPointOne),
-                    6 => Some(Layout::FivePointOne),
-                    other => {
-                        log::warn!(
-                            "track #{} has custom number of channels: {}",
-                            track.number,
-                            other
-                        );
-                        None
-                    }
+                // For Opus, the channels are described by the Opus identification header, which may
+                // signal ambisonics.
+                let channels = match (codec_type, track.codec_private.as_deref()) {
+                    (Some(CODEC_TYPE_OPUS), Some(opus_head)) => opus_head_channels(opus_head),
+                    _ => None,
+                };
+
+                let channels = match channels {
+                    Some(channels) => Some(channels),
+                    None => match audio.channels {
+                        1 => Some(Layout::Mono.into_channels()),
+                        2 => Some(Layout::Stereo.into_channels()),
+                        3 => Some(Layout::TwoPointOne.into_channels()),
+                        6 => Some(Layout::FivePointOne.into_channels()),
+                        other => {
+                            log::warn!(
+                                "track #{} has custom number of channels: {}",
+                                track.number,
+                                other
+                            );
+                            u16::try_from(other).ok().filter(|&n| n > 0).map(Channels::discrete)
+                        }
+                    },
                 };
 
-                if let Some(layout) = layout {
-                    codec_params.with_channel_layout(layout);
+                if let Some(channels) = channels {
+                    codec_params.with_channels(channels);
                 }
 
                 if let Some(codec_type) = codec_type {
@@ -501,6 +517,7 @@ impl FormatReader for MkvReader {
                 id: track_id,
                 codec_params: codec_params.clone(),
                 language: track.language,
+                gain,
             });
 
             states.insert(
@@ -12,7 +12,7 @@ use symphonia_core::errors::{reset_error, seek_error, unsupported_error};
 use symphonia_core::errors::{Error, Result, SeekErrorKind};
 use symphonia_core::formats::prelude::*;
 use symphonia_core::io::{MediaSource, MediaSourceStream, ReadBytes, SeekBuffered};
-use symphonia_core::meta::{Metadata, MetadataLog};
+use symphonia_core::meta::{GainInfo, Metadata, MetadataLog};
 use symphonia_core::probe::{Descriptor, Instantiate, QueryDescriptor};
 use symphonia_core::support_format;
 
@@ -218,6 +218,9 @@ impl OggReader {
         // The new mapper set.
         let mut streams = BTreeMap::<u32, LogicalStream>::new();
 
+        // The gain of each logical stream, derived from its metadata.
+        let mut gains = BTreeMap::<u32, GainInfo>::new();
+
         // The start of page position.
         let mut byte_range_start = self.reader.pos();
 
@@ -274,7 +277,13 @@ impl OggReader {
                 // Consume each piece of side data.
                 for data in side_data {
                     match data {
-                        SideData::Metadata(rev) => self.metadata.push(rev),
+                        SideData::Metadata(rev) => {
+                            if let Some(gain) = GainInfo::from_tags(rev.tags()) {
+                                gains.insert(page.header.serial, gain);
+                            }
+
+                            self.metadata.push(rev)
+                        }
                     }
                 }
 
@@ -322,7 +331,10 @@ impl OggReader {
                 warn!("track for serial={:#x} may not be ready", serial);
             }
 
-            self.tracks.push(Track::new(serial, stream.codec_params().clone()));
+            let mut track = Track::new(serial, stream.codec_params().clone());
+            track.gain = gains.get(&serial).copied();
+
+            self.tracks.push(track);
         }
 
         // Third, replace all logical streams with the new set.
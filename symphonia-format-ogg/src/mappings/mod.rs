@@ -12,6 +12,7 @@ use symphonia_core::errors::Result;
 
 mod flac;
 mod opus;
+mod speex;
 mod vorbis;
 
 /// Detect a `Mapper` for a logical stream given the identification packet of the stream.
@@ -19,6 +20,7 @@ pub fn detect(buf: &[u8]) -> Result<Option<Box<dyn Mapper>>> {
     let mapper = flac::detect(buf)?
         .or(vorbis::detect(buf)?)
         .or(opus::detect(buf)?)
+        .or(speex::detect(buf)?)
         .or_else(make_null_mapper);
 
     Ok(mapper)
@@ -9,7 +9,6 @@ use crate::common::SideData;
 
 use super::{MapResult, Mapper, PacketParser};
 
-use symphonia_core::audio::Channels;
 use symphonia_core::codecs::{CodecParameters, CODEC_TYPE_OPUS};
 use symphonia_core::errors::Result;
 use symphonia_core::io::{BufReader, ReadBytes};
@@ -17,6 +16,7 @@ use symphonia_core::meta::MetadataBuilder;
 use symphonia_core::units::TimeBase;
 
 use symphonia_metadata::vorbis;
+use symphonia_utils_xiph::opus::opus_channels;
 
 use log::warn;
 
@@ -75,58 +75,8 @@ pub fn detect(buf: &[u8]) -> Result<Option<Box<dyn Mapper>>> {
     // The next byte indicates the channel mapping. Most of these values are reserved.
     let channel_mapping = reader.read_byte()?;
 
-    let channels = match channel_mapping {
-        // RTP Mapping
-        0 if channel_count == 1 => Channels::FRONT_LEFT,
-        0 if channel_count == 2 => Channels::FRONT_LEFT | Channels::FRONT_RIGHT,
-        // Vorbis Mapping
-        1 => match channel_count {
-            1 => Channels::FRONT_LEFT,
-            2 => Channels::FRONT_LEFT | Channels::FRONT_RIGHT,
-            3 => Channels::FRONT_LEFT | Channels::FRONT_CENTRE | Channels::FRONT_RIGHT,
-            4 => {
-                Channels::FRONT_LEFT
-                    | Channels::FRONT_RIGHT
-                    | Channels::REAR_LEFT
-                    | Channels::REAR_RIGHT
-            }
-            5 => {
-                Channels::FRONT_LEFT
-                    | Channels::FRONT_CENTRE
-                    | Channels::FRONT_RIGHT
-                    | Channels::REAR_LEFT
-                    | Channels::REAR_RIGHT
-            }
-            6 => {
-                Channels::FRONT_LEFT
-                    | Channels::FRONT_CENTRE
-                    | Channels::FRONT_RIGHT
-                    | Channels::REAR_LEFT
-                    | Channels::REAR_RIGHT
-                    | Channels::LFE1
-            }
-            7 => {
-                Channels::FRONT_LEFT
-                    | Channels::FRONT_CENTRE
-                    | Channels::FRONT_RIGHT
-                    | Channels::SIDE_LEFT
-                    | Channels::SIDE_RIGHT
-                    | Channels::REAR_CENTRE
-                    | Channels::LFE1
-            }
-            8 => {
-                Channels::FRONT_LEFT
-                    | Channels::FRONT_CENTRE
-                    | Channels::FRONT_RIGHT
-                    | Channels::SIDE_LEFT
-                    | Channels::SIDE_RIGHT
-                    | Channels::REAR_LEFT
-                    | Channels::REAR_RIGHT
-                    | Channels::LFE1
-            }
-            _ => return Ok(None),
-        },
-        // Reserved, and should NOT be supported for playback.
+    let channels = match opus_channels(channel_mapping, channel_count) {
+        Some(channels) => channels,
         _ => return Ok(None),
     };
 
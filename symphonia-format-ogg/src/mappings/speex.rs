@@ -0,0 +1,165 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use crate::common::SideData;
+
+use super::{MapResult, Mapper, PacketParser};
+
+use symphonia_core::audio::Channels;
+use symphonia_core::codecs::{CodecParameters, CODEC_TYPE_SPEEX};
+use symphonia_core::errors::Result;
+use symphonia_core::io::{BufReader, ReadBytes};
+use symphonia_core::meta::MetadataBuilder;
+use symphonia_core::units::TimeBase;
+
+use symphonia_metadata::vorbis;
+
+use log::warn;
+
+/// The size of a Speex header packet.
+const OGG_SPEEX_HEADER_PACKET_SIZE: usize = 80;
+
+/// The signature for a Speex header packet.
+const OGG_SPEEX_MAGIC_SIGNATURE: &[u8] = b"Speex   ";
+
+/// The maximum number of frames per packet. The reference encoder supports up-to 10.
+const MAX_FRAMES_PER_PACKET: u32 = 10;
+
+pub fn detect(buf: &[u8]) -> Result<Option<Box<dyn Mapper>>> {
+    // The header packet for Speex must be a minimum size.
+    if buf.len() < OGG_SPEEX_HEADER_PACKET_SIZE {
+        return Ok(None);
+    }
+
+    let mut reader = BufReader::new(buf);
+
+    // The first 8 bytes are the magic signature ASCII bytes.
+    let mut magic = [0; 8];
+    reader.read_buf_exact(&mut magic)?;
+
+    if magic != *OGG_SPEEX_MAGIC_SIGNATURE {
+        return Ok(None);
+    }
+
+    // The next 20 bytes are the version string of the encoder, followed by the 32-bit version
+    // identifier of the header, and the header size.
+    reader.ignore_bytes(20 + 4 + 4)?;
+
+    let rate = reader.read_u32()?;
+
+    // The mode determines the number of samples in a frame.
+    let frame_size = match reader.read_u32()? {
+        0 => 160,
+        1 => 320,
+        2 => 640,
+        _ => return Ok(None),
+    };
+
+    // The bitstream version of the mode.
+    let _ = reader.read_u32()?;
+
+    let channels = match reader.read_u32()? {
+        2 => Channels::FRONT_LEFT | Channels::FRONT_RIGHT,
+        _ => Channels::FRONT_LEFT,
+    };
+
+    // The bitrate, frame size, and variable bitrate flag.
+    reader.ignore_bytes(4 + 4 + 4)?;
+
+    let frames_per_packet = reader.read_u32()?.max(1);
+
+    // The number of additional header packets following the comment packet.
+    let extra_headers = reader.read_u32()?;
+
+    if rate == 0 || frames_per_packet > MAX_FRAMES_PER_PACKET {
+        return Ok(None);
+    }
+
+    let packet_dur = u64::from(frames_per_packet) * frame_size;
+
+    // Populate the codec parameters with the information read from the header.
+    let mut codec_params = CodecParameters::new();
+
+    codec_params
+        .for_codec(CODEC_TYPE_SPEEX)
+        .with_sample_rate(rate)
+        .with_time_base(TimeBase::new(1, rate))
+        .with_channels(channels)
+        .with_max_frames_per_packet(packet_dur)
+        .with_extra_data(Box::from(buf));
+
+    // Instantiate the Speex mapper.
+    let mapper =
+        Box::new(SpeexMapper { codec_params, packet_dur, need_comment: true, extra_headers });
+
+    Ok(Some(mapper))
+}
+
+struct SpeexPacketParser {
+    packet_dur: u64,
+}
+
+impl PacketParser for SpeexPacketParser {
+    fn parse_next_packet_dur(&mut self, _packet: &[u8]) -> u64 {
+        // Every packet contains the same number of frames.
+        self.packet_dur
+    }
+}
+
+struct SpeexMapper {
+    codec_params: CodecParameters,
+    packet_dur: u64,
+    need_comment: bool,
+    extra_headers: u32,
+}
+
+impl Mapper for SpeexMapper {
+    fn name(&self) -> &'static str {
+        "speex"
+    }
+
+    fn reset(&mut self) {
+        // Nothing to do.
+    }
+
+    fn codec_params(&self) -> &CodecParameters {
+        &self.codec_params
+    }
+
+    fn codec_params_mut(&mut self) -> &mut CodecParameters {
+        &mut self.codec_params
+    }
+
+    fn make_parser(&self) -> Option<Box<dyn super::PacketParser>> {
+        Some(Box::new(SpeexPacketParser { packet_dur: self.packet_dur }))
+    }
+
+    fn map_packet(&mut self, packet: &[u8]) -> Result<MapResult> {
+        if self.need_comment {
+            // The packet following the header packet is a Vorbis Comment without a signature.
+            let mut builder = MetadataBuilder::new();
+
+            self.need_comment = false;
+
+            match vorbis::read_comment_no_framing(&mut BufReader::new(packet), &mut builder) {
+                Ok(_) => Ok(MapResult::SideData { data: SideData::Metadata(builder.metadata()) }),
+                Err(_) => {
+                    warn!("ogg (speex): invalid comment packet");
+                    Ok(MapResult::Unknown)
+                }
+            }
+        }
+        else if self.extra_headers > 0 {
+            // Additional header packets are not used.
+            self.extra_headers -= 1;
+            Ok(MapResult::Setup)
+        }
+        else {
+            Ok(MapResult::StreamData { dur: self.packet_dur })
+        }
+    }
+}
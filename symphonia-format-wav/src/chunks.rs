@@ -138,6 +138,10 @@ impl<P: ParseChunk> ChunkParser<P> {
     pub fn parse<B: ReadBytes>(&self, reader: &mut B) -> Result<P> {
         P::parse(reader, self.tag, self.len)
     }
+
+    pub fn skip<B: ReadBytes>(&self, reader: &mut B) -> Result<()> {
+        Ok(reader.ignore_bytes(u64::from(self.len))?)
+    }
 }
 
 pub enum WaveFormatData {
@@ -344,16 +348,9 @@ impl WaveFormatChunk {
 
         let channel_mask = reader.read_u32()?;
 
-        // The number of ones in the channel mask should match the number of channels.
-        if channel_mask.count_ones() != u32::from(n_channels) {
-            return decode_error("wav: channel mask mismatch with number of channels for fmt_ext");
-        }
-
-        // Try to map channels.
-        let channels = match Channels::from_bits(channel_mask) {
-            Some(channels) => channels,
-            _ => return unsupported_error("wav: too many channels in mask for fmt_ext"),
-        };
+        // The channel mask assigns speaker positions to the first channels of a frame. Any further
+        // channels are unassigned, and any excess assignments are ignored.
+        let channels = Channels::from_wave_channel_mask(channel_mask, n_channels);
 
         let mut sub_format_guid = [0u8; 16];
         reader.read_buf_exact(&mut sub_format_guid)?;
@@ -656,11 +653,350 @@ impl ParseChunk for DataChunk {
     }
 }
 
+/// Reads a fixed-length, and optionally null-terminated, string.
+fn read_fixed_str<B: ReadBytes>(reader: &mut B, len: usize) -> Result<String> {
+    let mut buf = vec![0; len];
+    reader.read_buf_exact(&mut buf)?;
+    Ok(buf_to_string(&buf))
+}
+
+/// Converts a buffer containing an optionally null-terminated string into a string.
+fn buf_to_string(buf: &[u8]) -> String {
+    let end = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+    String::from_utf8_lossy(&buf[..end]).trim_end().to_string()
+}
+
+/// The loudness fields of a broadcast extension chunk. Each field is `None` if it is not set.
+pub struct BextLoudness {
+    /// Integrated loudness in LUFS.
+    pub loudness_value: Option<f32>,
+    /// Loudness range in LU.
+    pub loudness_range: Option<f32>,
+    /// Maximum true peak level in dBTP.
+    pub max_true_peak_level: Option<f32>,
+    /// Maximum momentary loudness in LUFS.
+    pub max_momentary_loudness: Option<f32>,
+    /// Maximum short-term loudness in LUFS.
+    pub max_short_term_loudness: Option<f32>,
+}
+
+/// The broadcast extension chunk of a Broadcast Wave Format (BWF) file as defined in EBU Tech 3285.
+pub struct BextChunk {
+    pub description: String,
+    pub originator: String,
+    pub originator_reference: String,
+    /// The origination date in the format "yyyy-mm-dd".
+    pub origination_date: String,
+    /// The origination time in the format "hh-mm-ss".
+    pub origination_time: String,
+    /// The number of samples since midnight of the first sample.
+    pub time_reference: u64,
+    pub version: u16,
+    /// The SMPTE unique material identifier, if set. Only available for version 1 and above.
+    pub umid: Option<[u8; 64]>,
+    /// The loudness fields. Only available for version 2 and above.
+    pub loudness: Option<BextLoudness>,
+    pub coding_history: String,
+}
+
+impl BextChunk {
+    /// The length of all fixed length fields.
+    const FIXED_LEN: u32 = 602;
+}
+
+impl ParseChunk for BextChunk {
+    fn parse<B: ReadBytes>(reader: &mut B, _tag: [u8; 4], len: u32) -> Result<BextChunk> {
+        if len < BextChunk::FIXED_LEN {
+            return decode_error("wav: malformed bext chunk");
+        }
+
+        let description = read_fixed_str(reader, 256)?;
+        let originator = read_fixed_str(reader, 32)?;
+        let originator_reference = read_fixed_str(reader, 32)?;
+        let origination_date = read_fixed_str(reader, 10)?;
+        let origination_time = read_fixed_str(reader, 8)?;
+
+        let time_reference_low = reader.read_u32()?;
+        let time_reference_high = reader.read_u32()?;
+        let time_reference = (u64::from(time_reference_high) << 32) | u64::from(time_reference_low);
+
+        let version = reader.read_u16()?;
+
+        let mut umid = [0; 64];
+        reader.read_buf_exact(&mut umid)?;
+
+        let umid = if version >= 1 && umid.iter().any(|&b| b != 0) { Some(umid) } else { None };
+
+        // Loudness values are stored as 16-bit signed integers scaled by 100. A value of 0x7fff
+        // indicates the value is not set.
+        let mut loudness = [None; 5];
+
+        for value in loudness.iter_mut() {
+            let raw = reader.read_u16()? as i16;
+
+            if raw != 0x7fff {
+                *value = Some(f32::from(raw) / 100.0);
+            }
+        }
+
+        let loudness = if version >= 2 {
+            Some(BextLoudness {
+                loudness_value: loudness[0],
+                loudness_range: loudness[1],
+                max_true_peak_level: loudness[2],
+                max_momentary_loudness: loudness[3],
+                max_short_term_loudness: loudness[4],
+            })
+        }
+        else {
+            None
+        };
+
+        // Reserved.
+        reader.ignore_bytes(180)?;
+
+        let coding_history = read_fixed_str(reader, (len - BextChunk::FIXED_LEN) as usize)?;
+
+        Ok(BextChunk {
+            description,
+            originator,
+            originator_reference,
+            origination_date,
+            origination_time,
+            time_reference,
+            version,
+            umid,
+            loudness,
+            coding_history,
+        })
+    }
+}
+
+/// A chunk read as an opaque binary buffer.
+pub struct BinaryChunk {
+    pub tag: [u8; 4],
+    pub data: Box<[u8]>,
+}
+
+impl ParseChunk for BinaryChunk {
+    fn parse<B: ReadBytes>(reader: &mut B, tag: [u8; 4], len: u32) -> Result<BinaryChunk> {
+        Ok(BinaryChunk { tag, data: reader.read_boxed_slice_exact(len as usize)? })
+    }
+}
+
+/// A cue point of a cue chunk.
+pub struct CuePointEntry {
+    /// The unique identifier of the cue point.
+    pub id: u32,
+    /// The identifier of the chunk containing the cue point.
+    pub chunk_id: [u8; 4],
+    /// The position of the cue point in sample frames relative to the start of the data chunk.
+    pub sample_offset: u32,
+}
+
+pub struct CueChunk {
+    pub points: Vec<CuePointEntry>,
+}
+
+impl ParseChunk for CueChunk {
+    fn parse<B: ReadBytes>(reader: &mut B, _tag: [u8; 4], len: u32) -> Result<CueChunk> {
+        if len < 4 {
+            return decode_error("wav: malformed cue chunk");
+        }
+
+        let n_points = reader.read_u32()?;
+
+        // Each cue point is 24 bytes long.
+        if u64::from(n_points) * 24 > u64::from(len - 4) {
+            return decode_error("wav: malformed cue chunk");
+        }
+
+        let mut points = Vec::with_capacity(n_points as usize);
+
+        for _ in 0..n_points {
+            let id = reader.read_u32()?;
+            // Ignore the play order position.
+            let _position = reader.read_u32()?;
+            let chunk_id = reader.read_quad_bytes()?;
+            // Ignore the chunk and block start positions that are only used for wave lists.
+            let _chunk_start = reader.read_u32()?;
+            let _block_start = reader.read_u32()?;
+            let sample_offset = reader.read_u32()?;
+
+            points.push(CuePointEntry { id, chunk_id, sample_offset });
+        }
+
+        // Ignore any remaining data.
+        reader.ignore_bytes(u64::from(len - 4) - u64::from(n_points) * 24)?;
+
+        Ok(CueChunk { points })
+    }
+}
+
+/// A label (`labl`) or note (`note`) chunk of an associated data list.
+pub struct AdtlLabelChunk {
+    /// The identifier of the cue point the label is associated with.
+    pub cue_point_id: u32,
+    pub text: String,
+}
+
+impl ParseChunk for AdtlLabelChunk {
+    fn parse<B: ReadBytes>(reader: &mut B, _tag: [u8; 4], len: u32) -> Result<AdtlLabelChunk> {
+        if len < 4 {
+            return decode_error("wav: malformed label chunk");
+        }
+
+        let cue_point_id = reader.read_u32()?;
+        let text = read_fixed_str(reader, len as usize - 4)?;
+
+        Ok(AdtlLabelChunk { cue_point_id, text })
+    }
+}
+
+/// A labeled text (`ltxt`) chunk of an associated data list.
+pub struct AdtlLabeledTextChunk {
+    /// The identifier of the cue point the text is associated with.
+    pub cue_point_id: u32,
+    /// The length of the segment of audio starting at the cue point in sample frames.
+    pub sample_len: u32,
+    /// The purpose of the text.
+    pub purpose: [u8; 4],
+    pub text: String,
+}
+
+impl ParseChunk for AdtlLabeledTextChunk {
+    fn parse<B: ReadBytes>(reader: &mut B, _tag: [u8; 4], len: u32) -> Result<Self> {
+        if len < 20 {
+            return decode_error("wav: malformed labeled text chunk");
+        }
+
+        let cue_point_id = reader.read_u32()?;
+        let sample_len = reader.read_u32()?;
+        let purpose = reader.read_quad_bytes()?;
+
+        // Ignore the country, language, dialect, and code page.
+        reader.ignore_bytes(8)?;
+
+        let text = read_fixed_str(reader, len as usize - 20)?;
+
+        Ok(AdtlLabeledTextChunk { cue_point_id, sample_len, purpose, text })
+    }
+}
+
+/// A sample loop of a sampler chunk.
+pub struct SampleLoop {
+    /// The loop type. 0 is forward, 1 is alternating, and 2 is backward.
+    pub loop_type: u32,
+    /// The first sample frame of the loop.
+    pub start: u32,
+    /// The last sample frame of the loop.
+    pub end: u32,
+    /// The number of times to play the loop. 0 is infinite.
+    pub play_count: u32,
+}
+
+/// The sampler (`smpl`) chunk.
+pub struct SmplChunk {
+    /// The MIDI note number that plays the sample at its original pitch.
+    pub midi_unity_note: u32,
+    /// The fraction of a semitone up from the unity note.
+    pub midi_pitch_fraction: u32,
+    pub loops: Vec<SampleLoop>,
+}
+
+impl ParseChunk for SmplChunk {
+    fn parse<B: ReadBytes>(reader: &mut B, _tag: [u8; 4], len: u32) -> Result<SmplChunk> {
+        if len < 36 {
+            return decode_error("wav: malformed smpl chunk");
+        }
+
+        // Ignore the manufacturer, product, and sample period.
+        reader.ignore_bytes(12)?;
+
+        let midi_unity_note = reader.read_u32()?;
+        let midi_pitch_fraction = reader.read_u32()?;
+
+        // Ignore the SMPTE format and offset.
+        reader.ignore_bytes(8)?;
+
+        let n_loops = reader.read_u32()?;
+        let _sampler_data_len = reader.read_u32()?;
+
+        // Each sample loop is 24 bytes long.
+        if u64::from(n_loops) * 24 > u64::from(len - 36) {
+            return decode_error("wav: malformed smpl chunk");
+        }
+
+        let mut loops = Vec::with_capacity(n_loops as usize);
+
+        for _ in 0..n_loops {
+            let _cue_point_id = reader.read_u32()?;
+            let loop_type = reader.read_u32()?;
+            let start = reader.read_u32()?;
+            let end = reader.read_u32()?;
+            let _fraction = reader.read_u32()?;
+            let play_count = reader.read_u32()?;
+
+            loops.push(SampleLoop { loop_type, start, end, play_count });
+        }
+
+        // Ignore the sampler specific data.
+        reader.ignore_bytes(u64::from(len - 36) - u64::from(n_loops) * 24)?;
+
+        Ok(SmplChunk { midi_unity_note, midi_pitch_fraction, loops })
+    }
+}
+
+/// The ACID loop (`acid`) chunk.
+pub struct AcidChunk {
+    /// Flags. Bit 0 indicates a one-shot, bit 1 indicates the root note is set, bit 2 indicates
+    /// stretching is enabled, bit 3 indicates disk-based, and bit 4 is unknown.
+    pub flags: u32,
+    /// The MIDI note number of the root note.
+    pub root_note: u16,
+    pub n_beats: u32,
+    pub meter_denominator: u16,
+    pub meter_numerator: u16,
+    /// The tempo in beats per minute.
+    pub tempo: f32,
+}
+
+impl AcidChunk {
+    pub const FLAG_ONE_SHOT: u32 = 0x01;
+    pub const FLAG_ROOT_NOTE: u32 = 0x02;
+}
+
+impl ParseChunk for AcidChunk {
+    fn parse<B: ReadBytes>(reader: &mut B, _tag: [u8; 4], len: u32) -> Result<AcidChunk> {
+        if len < 24 {
+            return decode_error("wav: malformed acid chunk");
+        }
+
+        let flags = reader.read_u32()?;
+        let root_note = reader.read_u16()?;
+        reader.ignore_bytes(6)?;
+        let n_beats = reader.read_u32()?;
+        let meter_denominator = reader.read_u16()?;
+        let meter_numerator = reader.read_u16()?;
+        let tempo = reader.read_f32()?;
+
+        reader.ignore_bytes(u64::from(len - 24))?;
+
+        Ok(AcidChunk { flags, root_note, n_beats, meter_denominator, meter_numerator, tempo })
+    }
+}
+
 pub enum RiffWaveChunks {
     Format(ChunkParser<WaveFormatChunk>),
     List(ChunkParser<ListChunk>),
     Fact(ChunkParser<FactChunk>),
     Data(ChunkParser<DataChunk>),
+    Bext(ChunkParser<BextChunk>),
+    Cue(ChunkParser<CueChunk>),
+    Smpl(ChunkParser<SmplChunk>),
+    Acid(ChunkParser<AcidChunk>),
+    VendorData(ChunkParser<BinaryChunk>),
+    Id3(ChunkParser<BinaryChunk>),
 }
 
 macro_rules! parser {
@@ -676,6 +1012,12 @@ impl ParseChunkTag for RiffWaveChunks {
             b"LIST" => parser!(RiffWaveChunks::List, ListChunk, tag, len),
             b"fact" => parser!(RiffWaveChunks::Fact, FactChunk, tag, len),
             b"data" => parser!(RiffWaveChunks::Data, DataChunk, tag, len),
+            b"bext" => parser!(RiffWaveChunks::Bext, BextChunk, tag, len),
+            b"cue " => parser!(RiffWaveChunks::Cue, CueChunk, tag, len),
+            b"smpl" => parser!(RiffWaveChunks::Smpl, SmplChunk, tag, len),
+            b"acid" => parser!(RiffWaveChunks::Acid, AcidChunk, tag, len),
+            b"iXML" | b"axml" => parser!(RiffWaveChunks::VendorData, BinaryChunk, tag, len),
+            b"id3 " | b"ID3 " => parser!(RiffWaveChunks::Id3, BinaryChunk, tag, len),
             _ => None,
         }
     }
@@ -694,3 +1036,254 @@ impl ParseChunkTag for RiffInfoListChunks {
         parser!(RiffInfoListChunks::Info, InfoChunk, tag, len)
     }
 }
+
+pub enum RiffAdtlListChunks {
+    Label(ChunkParser<AdtlLabelChunk>),
+    Note(ChunkParser<AdtlLabelChunk>),
+    LabeledText(ChunkParser<AdtlLabeledTextChunk>),
+}
+
+impl ParseChunkTag for RiffAdtlListChunks {
+    fn parse_tag(tag: [u8; 4], len: u32) -> Option<Self> {
+        match &tag {
+            b"labl" => parser!(RiffAdtlListChunks::Label, AdtlLabelChunk, tag, len),
+            b"note" => parser!(RiffAdtlListChunks::Note, AdtlLabelChunk, tag, len),
+            b"ltxt" => parser!(RiffAdtlListChunks::LabeledText, AdtlLabeledTextChunk, tag, len),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use symphonia_core::io::BufReader;
+
+    use super::*;
+
+    fn fixed_str(text: &str, len: usize) -> Vec<u8> {
+        let mut buf = text.as_bytes().to_vec();
+        buf.resize(len, 0);
+        buf
+    }
+
+    fn u32_le(values: &[u32]) -> Vec<u8> {
+        values.iter().flat_map(|value| value.to_le_bytes()).collect()
+    }
+
+    /// Parses `data` as a chunk of type `P`, and returns the chunk and the number of bytes read.
+    fn parse<P: ParseChunk>(tag: &[u8; 4], data: &[u8]) -> Result<(P, u64)> {
+        let mut reader = BufReader::new(data);
+        let chunk = P::parse(&mut reader, *tag, data.len() as u32)?;
+        Ok((chunk, reader.pos()))
+    }
+
+    fn make_bext(version: u16, time_reference: u64, coding_history: &str) -> Vec<u8> {
+        let mut bext = Vec::new();
+        bext.extend(fixed_str("A description", 256));
+        bext.extend(fixed_str("Originator  ", 32));
+        bext.extend(fixed_str("Reference", 32));
+        bext.extend(fixed_str("2022-01-02", 10));
+        bext.extend(fixed_str("03-04-05", 8));
+        bext.extend_from_slice(&time_reference.to_le_bytes());
+        bext.extend_from_slice(&version.to_le_bytes());
+        bext.extend_from_slice(&[0xab; 64]);
+        for loudness in [-2300i16, 500, -100, 0x7fff, 1].iter() {
+            bext.extend_from_slice(&loudness.to_le_bytes());
+        }
+        bext.extend_from_slice(&[0; 180]);
+        bext.extend_from_slice(coding_history.as_bytes());
+        bext
+    }
+
+    fn make_cue_points(points: &[(u32, u32)]) -> Vec<u8> {
+        let mut cue = u32_le(&[points.len() as u32]);
+        for &(id, offset) in points {
+            cue.extend(u32_le(&[id, 0]));
+            cue.extend_from_slice(b"data");
+            cue.extend(u32_le(&[0, 0, offset]));
+        }
+        cue
+    }
+
+    #[test]
+    fn verify_bext_chunk() {
+        // The time reference is split into a low and high 32-bit word.
+        let time_reference = (5 << 32) | 48000;
+
+        let data = make_bext(2, time_reference, "A=PCM,F=48000\r\n\0\0");
+        let (bext, read) = parse::<BextChunk>(b"bext", &data).unwrap();
+
+        assert_eq!(read, data.len() as u64);
+        assert_eq!(bext.description, "A description");
+        assert_eq!(bext.originator, "Originator");
+        assert_eq!(bext.originator_reference, "Reference");
+        assert_eq!(bext.origination_date, "2022-01-02");
+        assert_eq!(bext.origination_time, "03-04-05");
+        assert_eq!(bext.time_reference, time_reference);
+        assert_eq!(bext.version, 2);
+        assert_eq!(bext.umid, Some([0xab; 64]));
+        assert_eq!(bext.coding_history, "A=PCM,F=48000");
+
+        let loudness = bext.loudness.unwrap();
+        assert_eq!(loudness.loudness_value, Some(-23.0));
+        assert_eq!(loudness.loudness_range, Some(5.0));
+        assert_eq!(loudness.max_true_peak_level, Some(-1.0));
+        assert_eq!(loudness.max_momentary_loudness, None);
+        assert_eq!(loudness.max_short_term_loudness, Some(0.01));
+
+        // The UMID and loudness fields are ignored in earlier versions.
+        let data = make_bext(0, 0, "");
+        let (bext, _) = parse::<BextChunk>(b"bext", &data).unwrap();
+
+        assert_eq!(bext.version, 0);
+        assert!(bext.umid.is_none());
+        assert!(bext.loudness.is_none());
+        assert_eq!(bext.coding_history, "");
+
+        // The fixed length fields are truncated.
+        let data = make_bext(2, 0, "");
+        assert!(parse::<BextChunk>(b"bext", &data[..BextChunk::FIXED_LEN as usize - 1]).is_err());
+    }
+
+    #[test]
+    fn verify_cue_chunk() {
+        let mut data = make_cue_points(&[(2, 3), (1, 100)]);
+        // Trailing data is ignored.
+        data.extend_from_slice(&[0; 4]);
+
+        let (cue, read) = parse::<CueChunk>(b"cue ", &data).unwrap();
+
+        assert_eq!(read, data.len() as u64);
+        assert_eq!(cue.points.len(), 2);
+        assert_eq!(cue.points[0].id, 2);
+        assert_eq!(&cue.points[0].chunk_id, b"data");
+        assert_eq!(cue.points[0].sample_offset, 3);
+        assert_eq!(cue.points[1].id, 1);
+        assert_eq!(cue.points[1].sample_offset, 100);
+
+        // The number of cue points exceeds the length of the chunk.
+        let data = make_cue_points(&[(1, 1), (2, 2)]);
+        assert!(parse::<CueChunk>(b"cue ", &data[..data.len() - 1]).is_err());
+
+        let mut data = make_cue_points(&[(1, 1)]);
+        data[..4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(parse::<CueChunk>(b"cue ", &data).is_err());
+
+        // The chunk is too short to contain the number of cue points.
+        assert!(parse::<CueChunk>(b"cue ", &[0; 3]).is_err());
+    }
+
+    #[test]
+    fn verify_adtl_chunks() {
+        // Label and note text is null-terminated, and may be padded.
+        let (labl, _) = parse::<AdtlLabelChunk>(b"labl", b"\x01\0\0\0Marker 1\0\0\0").unwrap();
+
+        assert_eq!(labl.cue_point_id, 1);
+        assert_eq!(labl.text, "Marker 1");
+
+        let (note, _) = parse::<AdtlLabelChunk>(b"note", b"\x02\0\0\0Note").unwrap();
+
+        assert_eq!(note.cue_point_id, 2);
+        assert_eq!(note.text, "Note");
+
+        assert!(parse::<AdtlLabelChunk>(b"labl", b"\x01\0\0").is_err());
+
+        let mut data = u32_le(&[3, 4800]);
+        data.extend_from_slice(b"rgn ");
+        data.extend_from_slice(&[0; 8]);
+        data.extend_from_slice(b"Region\0");
+
+        let (ltxt, read) = parse::<AdtlLabeledTextChunk>(b"ltxt", &data).unwrap();
+
+        assert_eq!(read, data.len() as u64);
+        assert_eq!(ltxt.cue_point_id, 3);
+        assert_eq!(ltxt.sample_len, 4800);
+        assert_eq!(&ltxt.purpose, b"rgn ");
+        assert_eq!(ltxt.text, "Region");
+
+        assert!(parse::<AdtlLabeledTextChunk>(b"ltxt", &data[..19]).is_err());
+    }
+
+    #[test]
+    fn verify_smpl_chunk() {
+        let mut data = vec![0; 12];
+        data.extend(u32_le(&[60, 0x8000_0000, 0, 0, 2, 4]));
+        data.extend(u32_le(&[0, 0, 100, 200, 0, 0]));
+        data.extend(u32_le(&[1, 1, 300, 400, 0, 3]));
+        // Sampler specific data is ignored.
+        data.extend_from_slice(&[0xff; 4]);
+
+        let (smpl, read) = parse::<SmplChunk>(b"smpl", &data).unwrap();
+
+        assert_eq!(read, data.len() as u64);
+        assert_eq!(smpl.midi_unity_note, 60);
+        assert_eq!(smpl.midi_pitch_fraction, 0x8000_0000);
+        assert_eq!(smpl.loops.len(), 2);
+        assert_eq!(smpl.loops[0].loop_type, 0);
+        assert_eq!((smpl.loops[0].start, smpl.loops[0].end), (100, 200));
+        assert_eq!(smpl.loops[0].play_count, 0);
+        assert_eq!(smpl.loops[1].loop_type, 1);
+        assert_eq!((smpl.loops[1].start, smpl.loops[1].end), (300, 400));
+        assert_eq!(smpl.loops[1].play_count, 3);
+
+        // The number of loops exceeds the length of the chunk.
+        assert!(parse::<SmplChunk>(b"smpl", &data[..36 + 24 + 23]).is_err());
+
+        let mut data = data[..36].to_vec();
+        data[28..32].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(parse::<SmplChunk>(b"smpl", &data).is_err());
+
+        // The chunk is too short to contain the header.
+        assert!(parse::<SmplChunk>(b"smpl", &data[..35]).is_err());
+    }
+
+    #[test]
+    fn verify_acid_chunk() {
+        let mut data = u32_le(&[AcidChunk::FLAG_ONE_SHOT | AcidChunk::FLAG_ROOT_NOTE]);
+        data.extend_from_slice(&48u16.to_le_bytes());
+        data.extend_from_slice(&[0; 6]);
+        data.extend(u32_le(&[8]));
+        data.extend_from_slice(&4u16.to_le_bytes());
+        data.extend_from_slice(&3u16.to_le_bytes());
+        data.extend_from_slice(&92.5f32.to_le_bytes());
+        // Trailing data is ignored.
+        data.extend_from_slice(&[0; 2]);
+
+        let (acid, read) = parse::<AcidChunk>(b"acid", &data).unwrap();
+
+        assert_eq!(read, data.len() as u64);
+        assert_eq!(acid.flags, AcidChunk::FLAG_ONE_SHOT | AcidChunk::FLAG_ROOT_NOTE);
+        assert_eq!(acid.root_note, 48);
+        assert_eq!(acid.n_beats, 8);
+        assert_eq!(acid.meter_denominator, 4);
+        assert_eq!(acid.meter_numerator, 3);
+        assert_eq!(acid.tempo, 92.5);
+
+        assert!(parse::<AcidChunk>(b"acid", &data[..23]).is_err());
+    }
+
+    #[test]
+    fn verify_vendor_data_chunks() {
+        for tag in [b"iXML", b"axml"].iter() {
+            let data = b"<BWFXML><PROJECT>P</PROJECT></BWFXML>";
+
+            let parser = match RiffWaveChunks::parse_tag(**tag, data.len() as u32) {
+                Some(RiffWaveChunks::VendorData(parser)) => parser,
+                _ => panic!("expected a vendor data chunk"),
+            };
+
+            let chunk = parser.parse(&mut BufReader::new(data)).unwrap();
+
+            assert_eq!(&chunk.tag, *tag);
+            assert_eq!(&chunk.data[..], &data[..]);
+        }
+
+        // The data is truncated.
+        let parser = match RiffWaveChunks::parse_tag(*b"iXML", 16) {
+            Some(RiffWaveChunks::VendorData(parser)) => parser,
+            _ => panic!("expected a vendor data chunk"),
+        };
+
+        assert!(parser.parse(&mut BufReader::new(&[0; 15])).is_err());
+    }
+}
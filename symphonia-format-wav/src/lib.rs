@@ -14,6 +14,7 @@
 #![allow(clippy::identity_op)]
 #![allow(clippy::manual_range_contains)]
 
+use std::collections::HashMap;
 use std::io::{Seek, SeekFrom};
 
 use symphonia_core::codecs::CodecParameters;
@@ -21,11 +22,14 @@ use symphonia_core::errors::{end_of_stream_error, seek_error, unsupported_error}
 use symphonia_core::errors::{Result, SeekErrorKind};
 use symphonia_core::formats::prelude::*;
 use symphonia_core::io::*;
-use symphonia_core::meta::{Metadata, MetadataBuilder, MetadataLog, MetadataRevision};
+use symphonia_core::meta::VendorData;
+use symphonia_core::meta::{GainInfo, Metadata, MetadataBuilder, MetadataLog};
+use symphonia_core::meta::{StandardTagKey, Tag, Value};
 use symphonia_core::probe::{Descriptor, Instantiate, QueryDescriptor};
 use symphonia_core::support_format;
+use symphonia_metadata::id3v2;
 
-use log::{debug, error};
+use log::{debug, error, warn};
 
 mod chunks;
 
@@ -49,6 +53,7 @@ pub struct WavReader {
     cues: Vec<Cue>,
     metadata: MetadataLog,
     frame_len: u16,
+    start_ts: u64,
     data_start_pos: u64,
     data_end_pos: u64,
 }
@@ -97,10 +102,10 @@ impl FormatReader for WavReader {
         let mut riff_chunks = ChunksReader::<RiffWaveChunks>::new(riff_len);
 
         let mut codec_params = CodecParameters::new();
-        let mut metadata: MetadataLog = Default::default();
+        let mut chunk_metadata: ChunkMetadata = Default::default();
         let mut frame_len = 0;
 
-        loop {
+        let data = loop {
             let chunk = riff_chunks.next(&mut source)?;
 
             // The last chunk should always be a data chunk, if it is not, then the stream is
@@ -127,41 +132,68 @@ impl FormatReader for WavReader {
                     // Append Fact chunk fields to codec parameters.
                     append_fact_params(&mut codec_params, &fact);
                 }
-                RiffWaveChunks::List(lst) => {
-                    let list = lst.parse(&mut source)?;
-
-                    // Riff Lists can have many different forms, but WavReader only supports Info
-                    // lists.
-                    match &list.form {
-                        b"INFO" => metadata.push(read_info_chunk(&mut source, list.len)?),
-                        _ => list.skip(&mut source)?,
+                RiffWaveChunks::Data(dat) => break dat.parse(&mut source)?,
+                chunk => chunk_metadata.read_chunk(&mut source, chunk)?,
+            }
+        };
+
+        // Record the bounds of the data chunk.
+        let data_start_pos = source.pos();
+        let data_end_pos = data_start_pos + u64::from(data.len);
+
+        // Metadata chunks, such as cue and associated data lists, may follow the data chunk. If the
+        // stream is seekable, and the data chunk has a known length, read those chunks and then
+        // return to the start of the data chunk.
+        if source.is_seekable() && data.len != u32::MAX {
+            if let Some(byte_len) = source.byte_len() {
+                if data_end_pos < byte_len {
+                    source.seek(SeekFrom::Start(data_end_pos))?;
+
+                    if let Err(err) =
+                        read_trailing_chunks(&mut source, &mut riff_chunks, &mut chunk_metadata)
+                    {
+                        warn!("wav: failed to read chunks following the data chunk: {}", err);
                     }
-                }
-                RiffWaveChunks::Data(dat) => {
-                    let data = dat.parse(&mut source)?;
-
-                    // Record the bounds of the data chunk.
-                    let data_start_pos = source.pos();
-                    let data_end_pos = data_start_pos + u64::from(data.len);
-
-                    // Append Data chunk fields to codec parameters.
-                    append_data_params(&mut codec_params, &data, frame_len);
-
-                    // Add a new track using the collected codec parameters.
-                    return Ok(WavReader {
-                        reader: source,
-                        tracks: vec![Track::new(0, codec_params)],
-                        cues: Vec::new(),
-                        metadata,
-                        frame_len,
-                        data_start_pos,
-                        data_end_pos,
-                    });
+
+                    source.seek(SeekFrom::Start(data_start_pos))?;
                 }
             }
         }
 
-        // Chunks are processed until the Data chunk is found, or an error occurs.
+        // The time reference of a broadcast wave file is the timestamp of the first frame.
+        let start_ts = chunk_metadata.time_reference.unwrap_or(0);
+
+        codec_params.with_start_ts(start_ts);
+
+        // Append Data chunk fields to codec parameters.
+        append_data_params(&mut codec_params, &data, frame_len);
+
+        let cues = chunk_metadata.cues(start_ts);
+
+        let mut metadata: MetadataLog = Default::default();
+
+        let mut track = Track::new(0, codec_params);
+
+        // Tagged gains are preferred over the loudness of a broadcast wave file.
+        track.gain = chunk_metadata.bext_gain;
+
+        if chunk_metadata.has_metadata {
+            let revision = chunk_metadata.builder.metadata();
+            track.gain = GainInfo::from_tags(revision.tags()).or(track.gain);
+            metadata.push(revision);
+        }
+
+        // Add a new track using the collected codec parameters.
+        Ok(WavReader {
+            reader: source,
+            tracks: vec![track],
+            cues,
+            metadata,
+            frame_len,
+            start_ts,
+            data_start_pos,
+            data_end_pos,
+        })
     }
 
     fn next_packet(&mut self) -> Result<Packet> {
@@ -188,7 +220,7 @@ impl FormatReader for WavReader {
 
         // The packet timestamp is the position of the first byte of the first frame in the
         // packet relative to the start of the data chunk divided by the length per frame.
-        let pts = (pos - self.data_start_pos) / u64::from(self.frame_len);
+        let pts = self.start_ts + (pos - self.data_start_pos) / u64::from(self.frame_len);
 
         Ok(Packet::new_from_boxed_slice(0, pts, dur, packet_buf))
     }
@@ -228,10 +260,15 @@ impl FormatReader for WavReader {
             }
         };
 
+        // The desired frame timestamp cannot preceed the first frame.
+        if ts < self.start_ts {
+            return seek_error(SeekErrorKind::OutOfRange);
+        }
+
         // If the total number of frames in the track is known, verify the desired frame timestamp
         // does not exceed it.
         if let Some(n_frames) = params.n_frames {
-            if ts > n_frames {
+            if ts > self.start_ts + n_frames {
                 return seek_error(SeekErrorKind::OutOfRange);
             }
         }
@@ -244,10 +281,13 @@ impl FormatReader for WavReader {
         // determinstic, instead of seeking to the exact timestamp requested and starting the next
         // packet there, seek to a packet boundary. In this way, packets will have have the same
         // timestamps regardless if the stream was seeked or not.
-        let actual_ts = (ts / WAVE_MAX_FRAMES_PER_PACKET) * WAVE_MAX_FRAMES_PER_PACKET;
+        let frame =
+            ((ts - self.start_ts) / WAVE_MAX_FRAMES_PER_PACKET) * WAVE_MAX_FRAMES_PER_PACKET;
+
+        let actual_ts = self.start_ts + frame;
 
         // Calculate the absolute byte offset of the desired audio frame.
-        let seek_pos = self.data_start_pos + (actual_ts * u64::from(self.frame_len));
+        let seek_pos = self.data_start_pos + (frame * u64::from(self.frame_len));
 
         // If the reader supports seeking we can seek directly to the frame's offset wherever it may
         // be.
@@ -276,26 +316,272 @@ impl FormatReader for WavReader {
     }
 }
 
-fn read_info_chunk(source: &mut MediaSourceStream, len: u32) -> Result<MetadataRevision> {
-    let mut info_list = ChunksReader::<RiffInfoListChunks>::new(len);
+/// Reads the chunks following the data chunk.
+fn read_trailing_chunks(
+    source: &mut MediaSourceStream,
+    riff_chunks: &mut ChunksReader<RiffWaveChunks>,
+    chunk_metadata: &mut ChunkMetadata,
+) -> Result<()> {
+    while let Some(chunk) = riff_chunks.next(source)? {
+        match chunk {
+            RiffWaveChunks::Format(chunk) => chunk.skip(source)?,
+            RiffWaveChunks::Fact(chunk) => chunk.skip(source)?,
+            RiffWaveChunks::Data(chunk) => chunk.skip(source)?,
+            chunk => chunk_metadata.read_chunk(source, chunk)?,
+        }
+    }
+
+    Ok(())
+}
+
+/// Derives the gain that normalises a broadcast wave file to the EBU R128 reference loudness from
+/// its integrated loudness.
+fn bext_gain(loudness: &BextLoudness) -> Option<GainInfo> {
+    const R128_REFERENCE_LOUDNESS: f32 = -23.0;
 
-    let mut metadata_builder = MetadataBuilder::new();
+    let loudness_value = loudness.loudness_value?;
 
-    loop {
-        let chunk = info_list.next(source)?;
+    Some(GainInfo {
+        track_gain: Some(R128_REFERENCE_LOUDNESS - loudness_value),
+        track_peak: loudness.max_true_peak_level.map(|level| 10f32.powf(level / 20.0)),
+        reference_loudness: Some(R128_REFERENCE_LOUDNESS),
+        ..Default::default()
+    })
+}
 
-        if let Some(RiffInfoListChunks::Info(info)) = chunk {
+/// The metadata and cue points read from the chunks of a WAVE file.
+#[derive(Default)]
+struct ChunkMetadata {
+    builder: MetadataBuilder,
+    has_metadata: bool,
+    /// The time reference of a broadcast wave file.
+    time_reference: Option<u64>,
+    /// The gain derived from the loudness of a broadcast wave file.
+    bext_gain: Option<GainInfo>,
+    cue_points: Vec<CuePointEntry>,
+    /// The tags of each cue point, from the associated data list, indexed by cue point identifier.
+    cue_tags: HashMap<u32, Vec<Tag>>,
+}
+
+impl ChunkMetadata {
+    /// Reads a metadata chunk.
+    fn read_chunk(&mut self, source: &mut MediaSourceStream, chunk: RiffWaveChunks) -> Result<()> {
+        match chunk {
+            RiffWaveChunks::List(lst) => {
+                let list = lst.parse(source)?;
+
+                // Riff Lists can have many different forms, but WavReader only supports Info and
+                // associated data lists.
+                match &list.form {
+                    b"INFO" => self.read_info_list(source, list.len)?,
+                    b"adtl" => self.read_adtl_list(source, list.len)?,
+                    _ => list.skip(source)?,
+                }
+            }
+            RiffWaveChunks::Bext(bext) => {
+                let bext = bext.parse(source)?;
+                self.time_reference = Some(bext.time_reference);
+                self.bext_gain = bext.loudness.as_ref().and_then(bext_gain);
+                append_bext_tags(&mut self.builder, &bext);
+                self.has_metadata = true;
+            }
+            RiffWaveChunks::Cue(cue) => self.cue_points.extend(cue.parse(source)?.points),
+            RiffWaveChunks::Smpl(smpl) => {
+                append_smpl_tags(&mut self.builder, &smpl.parse(source)?);
+                self.has_metadata = true;
+            }
+            RiffWaveChunks::Acid(acid) => {
+                append_acid_tags(&mut self.builder, &acid.parse(source)?);
+                self.has_metadata = true;
+            }
+            RiffWaveChunks::VendorData(chunk) => {
+                let chunk = chunk.parse(source)?;
+
+                self.builder.add_vendor_data(VendorData {
+                    ident: String::from_utf8_lossy(&chunk.tag).into_owned(),
+                    data: chunk.data,
+                });
+                self.has_metadata = true;
+            }
+            RiffWaveChunks::Id3(chunk) => {
+                let chunk = chunk.parse(source)?;
+
+                // A malformed ID3v2 tag should not prevent the audio from being read.
+                match id3v2::read_id3v2(&mut BufReader::new(&chunk.data), &mut self.builder) {
+                    Ok(_) => self.has_metadata = true,
+                    Err(err) => warn!("wav: failed to read id3 chunk: {}", err),
+                }
+            }
+            _ => (),
+        }
+
+        Ok(())
+    }
+
+    fn read_info_list(&mut self, source: &mut MediaSourceStream, len: u32) -> Result<()> {
+        let mut info_list = ChunksReader::<RiffInfoListChunks>::new(len);
+
+        while let Some(RiffInfoListChunks::Info(info)) = info_list.next(source)? {
             let parsed_info = info.parse(source)?;
-            metadata_builder.add_tag(parsed_info.tag);
+            self.builder.add_tag(parsed_info.tag);
+            self.has_metadata = true;
         }
-        else {
-            break;
+
+        info_list.finish(source)
+    }
+
+    fn read_adtl_list(&mut self, source: &mut MediaSourceStream, len: u32) -> Result<()> {
+        let mut adtl_list = ChunksReader::<RiffAdtlListChunks>::new(len);
+
+        while let Some(chunk) = adtl_list.next(source)? {
+            let (id, tags) = match chunk {
+                RiffAdtlListChunks::Label(labl) => {
+                    let labl = labl.parse(source)?;
+                    (labl.cue_point_id, vec![Tag::new(None, "LABEL", Value::from(labl.text))])
+                }
+                RiffAdtlListChunks::Note(note) => {
+                    let note = note.parse(source)?;
+                    (note.cue_point_id, vec![Tag::new(None, "NOTE", Value::from(note.text))])
+                }
+                RiffAdtlListChunks::LabeledText(ltxt) => {
+                    let ltxt = ltxt.parse(source)?;
+
+                    let mut tags = vec![
+                        Tag::new(None, "LENGTH", Value::from(ltxt.sample_len)),
+                        Tag::new(
+                            None,
+                            "PURPOSE",
+                            Value::from(String::from_utf8_lossy(&ltxt.purpose)),
+                        ),
+                    ];
+
+                    if !ltxt.text.is_empty() {
+                        tags.push(Tag::new(None, "TEXT", Value::from(ltxt.text)));
+                    }
+
+                    (ltxt.cue_point_id, tags)
+                }
+            };
+
+            self.cue_tags.entry(id).or_default().extend(tags);
         }
+
+        adtl_list.finish(source)
     }
 
-    info_list.finish(source)?;
+    /// Gets the cues, in timestamp order, given the timestamp of the first frame.
+    fn cues(&mut self, start_ts: u64) -> Vec<Cue> {
+        // Only cue points in the data chunk are supported.
+        self.cue_points.retain(|point| &point.chunk_id == b"data");
+        self.cue_points.sort_by_key(|point| point.sample_offset);
+
+        let cue_tags = &mut self.cue_tags;
+
+        self.cue_points
+            .iter()
+            .map(|point| Cue {
+                index: point.id,
+                start_ts: start_ts + u64::from(point.sample_offset),
+                tags: cue_tags.remove(&point.id).unwrap_or_default(),
+                visuals: Vec::new(),
+                points: Vec::new(),
+            })
+            .collect()
+    }
+}
 
-    Ok(metadata_builder.metadata())
+fn append_bext_tags(builder: &mut MetadataBuilder, bext: &BextChunk) {
+    let mut add_text = |std_key, key, text: &str| {
+        if !text.is_empty() {
+            builder.add_tag(Tag::new(std_key, key, Value::from(text)));
+        }
+    };
+
+    add_text(Some(StandardTagKey::Description), "BEXT:DESCRIPTION", &bext.description);
+    add_text(None, "BEXT:ORIGINATOR", &bext.originator);
+    add_text(None, "BEXT:ORIGINATOR_REFERENCE", &bext.originator_reference);
+    add_text(Some(StandardTagKey::EncodingDate), "BEXT:ORIGINATION_DATE", &bext.origination_date);
+    add_text(None, "BEXT:ORIGINATION_TIME", &bext.origination_time);
+    add_text(None, "BEXT:CODING_HISTORY", &bext.coding_history);
+
+    builder.add_tag(Tag::new(None, "BEXT:TIME_REFERENCE", Value::from(bext.time_reference)));
+    builder.add_tag(Tag::new(None, "BEXT:VERSION", Value::from(bext.version)));
+
+    if let Some(umid) = &bext.umid {
+        let umid: String = umid.iter().map(|b| format!("{:02x}", b)).collect();
+        builder.add_tag(Tag::new(None, "BEXT:UMID", Value::from(umid)));
+    }
+
+    if let Some(loudness) = &bext.loudness {
+        let fields = [
+            ("BEXT:LOUDNESS_VALUE", loudness.loudness_value),
+            ("BEXT:LOUDNESS_RANGE", loudness.loudness_range),
+            ("BEXT:MAX_TRUE_PEAK_LEVEL", loudness.max_true_peak_level),
+            ("BEXT:MAX_MOMENTARY_LOUDNESS", loudness.max_momentary_loudness),
+            ("BEXT:MAX_SHORT_TERM_LOUDNESS", loudness.max_short_term_loudness),
+        ];
+
+        for (key, value) in fields.iter() {
+            if let Some(value) = value {
+                builder.add_tag(Tag::new(None, key, Value::from(*value)));
+            }
+        }
+    }
+}
+
+fn append_smpl_tags(builder: &mut MetadataBuilder, smpl: &SmplChunk) {
+    builder.add_tag(Tag::new(None, "SMPL:MIDI_UNITY_NOTE", Value::from(smpl.midi_unity_note)));
+
+    if smpl.midi_pitch_fraction != 0 {
+        // The pitch fraction is the fraction of a semitone in units of 1/2^32.
+        let cents = f64::from(smpl.midi_pitch_fraction) / f64::from(u32::MAX) * 100.0;
+        builder.add_tag(Tag::new(None, "SMPL:MIDI_PITCH_FRACTION", Value::from(cents)));
+    }
+
+    for (i, sample_loop) in smpl.loops.iter().enumerate() {
+        let loop_type = match sample_loop.loop_type {
+            0 => "forward".to_string(),
+            1 => "alternating".to_string(),
+            2 => "backward".to_string(),
+            loop_type => loop_type.to_string(),
+        };
+
+        let prefix = format!("SMPL:LOOP{}", i + 1);
+
+        builder.add_tag(Tag::new(None, &format!("{}_TYPE", prefix), Value::from(loop_type)));
+        builder.add_tag(Tag::new(
+            None,
+            &format!("{}_START", prefix),
+            Value::from(sample_loop.start),
+        ));
+        builder.add_tag(Tag::new(None, &format!("{}_END", prefix), Value::from(sample_loop.end)));
+        builder.add_tag(Tag::new(
+            None,
+            &format!("{}_PLAY_COUNT", prefix),
+            Value::from(sample_loop.play_count),
+        ));
+    }
+}
+
+fn append_acid_tags(builder: &mut MetadataBuilder, acid: &AcidChunk) {
+    if acid.tempo > 0.0 {
+        builder.add_tag(Tag::new(Some(StandardTagKey::Bpm), "ACID:TEMPO", Value::from(acid.tempo)));
+    }
+
+    if acid.flags & AcidChunk::FLAG_ROOT_NOTE != 0 {
+        builder.add_tag(Tag::new(None, "ACID:ROOT_NOTE", Value::from(acid.root_note)));
+    }
+
+    if acid.flags & AcidChunk::FLAG_ONE_SHOT != 0 {
+        builder.add_tag(Tag::new(None, "ACID:ONE_SHOT", Value::Flag));
+    }
+
+    builder.add_tag(Tag::new(None, "ACID:BEATS", Value::from(acid.n_beats)));
+
+    if acid.meter_numerator > 0 && acid.meter_denominator > 0 {
+        let meter = format!("{}/{}", acid.meter_numerator, acid.meter_denominator);
+        builder.add_tag(Tag::new(None, "ACID:METER", Value::from(meter)));
+    }
 }
 
 fn append_format_params(codec_params: &mut CodecParameters, format: &WaveFormatChunk) {
@@ -341,3 +627,197 @@ fn append_data_params(codec_params: &mut CodecParameters, data: &DataChunk, fram
         codec_params.with_n_frames(u64::from(n_frames));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use symphonia_core::meta::MetadataBuilder;
+    use symphonia_metadata::id3v2::{write_id3v2_tag, Id3v2WriteOptions};
+
+    use super::*;
+
+    fn chunk(tag: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        let mut buf = tag.to_vec();
+        buf.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        buf.extend_from_slice(data);
+        if data.len() & 1 == 1 {
+            buf.push(0);
+        }
+        buf
+    }
+
+    fn fixed_str(text: &str, len: usize) -> Vec<u8> {
+        let mut buf = text.as_bytes().to_vec();
+        buf.resize(len, 0);
+        buf
+    }
+
+    fn make_wav(time_reference: u64) -> Vec<u8> {
+        // Mono, 48kHz, 16-bit PCM.
+        let mut fmt = Vec::new();
+        fmt.extend_from_slice(&1u16.to_le_bytes());
+        fmt.extend_from_slice(&1u16.to_le_bytes());
+        fmt.extend_from_slice(&48000u32.to_le_bytes());
+        fmt.extend_from_slice(&96000u32.to_le_bytes());
+        fmt.extend_from_slice(&2u16.to_le_bytes());
+        fmt.extend_from_slice(&16u16.to_le_bytes());
+
+        let mut bext = Vec::new();
+        bext.extend(fixed_str("A description", 256));
+        bext.extend(fixed_str("Originator", 32));
+        bext.extend(fixed_str("Reference", 32));
+        bext.extend(fixed_str("2022-01-02", 10));
+        bext.extend(fixed_str("03-04-05", 8));
+        bext.extend_from_slice(&time_reference.to_le_bytes());
+        bext.extend_from_slice(&2u16.to_le_bytes());
+        bext.extend_from_slice(&[0; 64]);
+        for loudness in [-2300i16, 500, -100, 0x7fff, 0x7fff].iter() {
+            bext.extend_from_slice(&loudness.to_le_bytes());
+        }
+        bext.extend_from_slice(&[0; 180]);
+        bext.extend_from_slice(b"A=PCM,F=48000\r\n");
+
+        let mut smpl = vec![0; 12];
+        smpl.extend_from_slice(&60u32.to_le_bytes());
+        smpl.extend_from_slice(&[0; 12]);
+        smpl.extend_from_slice(&1u32.to_le_bytes());
+        smpl.extend_from_slice(&0u32.to_le_bytes());
+        for value in [0u32, 0, 100, 200, 0, 0].iter() {
+            smpl.extend_from_slice(&value.to_le_bytes());
+        }
+
+        let mut acid = Vec::new();
+        acid.extend_from_slice(&AcidChunk::FLAG_ROOT_NOTE.to_le_bytes());
+        acid.extend_from_slice(&48u16.to_le_bytes());
+        acid.extend_from_slice(&[0; 6]);
+        acid.extend_from_slice(&8u32.to_le_bytes());
+        acid.extend_from_slice(&4u16.to_le_bytes());
+        acid.extend_from_slice(&4u16.to_le_bytes());
+        acid.extend_from_slice(&120f32.to_le_bytes());
+
+        let mut builder = MetadataBuilder::new();
+        builder.add_tag(Tag::new(Some(StandardTagKey::TrackTitle), "TIT2", Value::from("Title")));
+        let options = Id3v2WriteOptions { padding: 0, ..Default::default() };
+        let mut id3 = Vec::new();
+        write_id3v2_tag(&mut id3, &builder.metadata(), &options).unwrap();
+
+        let mut cue = 2u32.to_le_bytes().to_vec();
+        for (id, offset) in [(2u32, 3u32), (1, 1)].iter() {
+            cue.extend_from_slice(&id.to_le_bytes());
+            cue.extend_from_slice(&0u32.to_le_bytes());
+            cue.extend_from_slice(b"data");
+            cue.extend_from_slice(&[0; 8]);
+            cue.extend_from_slice(&offset.to_le_bytes());
+        }
+
+        let mut adtl = b"adtl".to_vec();
+        adtl.extend(chunk(b"labl", b"\x01\0\0\0Marker 1\0"));
+        adtl.extend(chunk(b"note", b"\x02\0\0\0Note 2\0"));
+
+        let mut wave = b"WAVE".to_vec();
+        wave.extend(chunk(b"fmt ", &fmt));
+        wave.extend(chunk(b"bext", &bext));
+        wave.extend(chunk(b"iXML", b"<BWFXML/>"));
+        wave.extend(chunk(b"smpl", &smpl));
+        wave.extend(chunk(b"acid", &acid));
+        wave.extend(chunk(b"id3 ", &id3));
+        wave.extend(chunk(b"data", &[0; 8]));
+        wave.extend(chunk(b"cue ", &cue));
+        wave.extend(chunk(b"LIST", &adtl));
+
+        chunk(b"RIFF", &wave)
+    }
+
+    #[test]
+    fn verify_read_broadcast_wave_chunks() {
+        let time_reference = 48000 * 3600;
+
+        let mss = MediaSourceStream::new(
+            Box::new(Cursor::new(make_wav(time_reference))),
+            Default::default(),
+        );
+
+        let mut reader = WavReader::try_new(mss, &Default::default()).unwrap();
+
+        assert_eq!(reader.tracks()[0].codec_params.start_ts, time_reference);
+        assert_eq!(reader.tracks()[0].codec_params.n_frames, Some(4));
+
+        // The gain is derived from the integrated loudness.
+        let gain = reader.tracks()[0].gain.unwrap();
+        assert_eq!(gain.track_gain, Some(0.0));
+        assert_eq!(gain.reference_loudness, Some(-23.0));
+        assert!((gain.track_peak.unwrap() - 0.891251).abs() < 1e-6);
+
+        // Cues are read from the chunks following the data chunk.
+        let cues: Vec<(u32, u64, String)> = reader
+            .cues()
+            .iter()
+            .map(|cue| (cue.index, cue.start_ts, cue.tags[0].value.to_string()))
+            .collect();
+
+        assert_eq!(
+            cues,
+            vec![
+                (1, time_reference + 1, "Marker 1".to_string()),
+                (2, time_reference + 3, "Note 2".to_string()),
+            ]
+        );
+
+        {
+            let metadata = reader.metadata();
+            let rev = metadata.current().unwrap();
+
+            let find = |key: &str| {
+                rev.tags().iter().find(|tag| tag.key == key).map(|tag| tag.value.to_string())
+            };
+
+            assert_eq!(find("BEXT:DESCRIPTION").as_deref(), Some("A description"));
+            assert_eq!(find("BEXT:ORIGINATION_DATE").as_deref(), Some("2022-01-02"));
+            assert_eq!(find("BEXT:CODING_HISTORY").as_deref(), Some("A=PCM,F=48000"));
+            assert_eq!(find("BEXT:LOUDNESS_VALUE").as_deref(), Some("-23"));
+            assert_eq!(find("BEXT:MAX_TRUE_PEAK_LEVEL").as_deref(), Some("-1"));
+            assert_eq!(find("BEXT:MAX_MOMENTARY_LOUDNESS"), None);
+            assert_eq!(find("BEXT:UMID"), None);
+            assert_eq!(find("SMPL:MIDI_UNITY_NOTE").as_deref(), Some("60"));
+            assert_eq!(find("SMPL:LOOP1_START").as_deref(), Some("100"));
+            assert_eq!(find("SMPL:LOOP1_END").as_deref(), Some("200"));
+            assert_eq!(find("ACID:TEMPO").as_deref(), Some("120"));
+            assert_eq!(find("ACID:ROOT_NOTE").as_deref(), Some("48"));
+            assert_eq!(find("ACID:METER").as_deref(), Some("4/4"));
+            assert_eq!(find("TIT2").as_deref(), Some("Title"));
+
+            assert_eq!(rev.vendor_data()[0].ident, "iXML");
+            assert_eq!(&*rev.vendor_data()[0].data, b"<BWFXML/>");
+        }
+
+        // Packet timestamps start at the time reference.
+        let packet = reader.next_packet().unwrap();
+        assert_eq!(packet.ts(), time_reference);
+        assert_eq!(packet.dur(), 4);
+
+        let seeked = reader
+            .seek(SeekMode::Accurate, SeekTo::TimeStamp { ts: time_reference + 2, track_id: 0 })
+            .unwrap();
+        assert_eq!(seeked.actual_ts, time_reference);
+
+        assert!(reader.seek(SeekMode::Accurate, SeekTo::TimeStamp { ts: 2, track_id: 0 }).is_err());
+    }
+
+    #[test]
+    fn verify_bext_time_reference() {
+        // The time reference of the bext chunk is a 64-bit sample count.
+        for &time_reference in [0, (1 << 32) + 7, u64::from(u32::MAX)].iter() {
+            let mss = MediaSourceStream::new(
+                Box::new(Cursor::new(make_wav(time_reference))),
+                Default::default(),
+            );
+
+            let mut reader = WavReader::try_new(mss, &Default::default()).unwrap();
+
+            assert_eq!(reader.tracks()[0].codec_params.start_ts, time_reference);
+            assert_eq!(reader.cues()[0].start_ts, time_reference + 1);
+            assert_eq!(reader.next_packet().unwrap().ts(), time_reference);
+        }
+    }
+}
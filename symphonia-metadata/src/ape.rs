@@ -0,0 +1,889 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! An APEv1 and APEv2 metadata reader, and APEv2 metadata writer.
+
+use std::collections::HashMap;
+use std::io::{Seek, SeekFrom, Write};
+
+use lazy_static::lazy_static;
+use symphonia_core::errors::{decode_error, unsupported_error, Result};
+use symphonia_core::io::{MediaSourceStream, ReadBytes};
+use symphonia_core::meta::{MetadataBuilder, MetadataRevision, StandardTagKey, StandardVisualKey};
+use symphonia_core::meta::{Tag, Value, Visual};
+
+use log::warn;
+
+use crate::id3v1::ID3V1_TAG_LEN;
+
+/// The APE tag preamble: "APETAGEX" in ASCII.
+pub const APE_TAG_PREAMBLE: [u8; 8] = *b"APETAGEX";
+
+/// The size of an APE tag header or footer in bytes.
+pub const APE_TAG_HEADER_SIZE: u64 = 32;
+
+/// The APE tag version written by the writer.
+const APE_TAG_VERSION_2: u32 = 2000;
+
+/// Tag flag: the tag contains a header.
+const APE_FLAG_HAS_HEADER: u32 = 0x8000_0000;
+
+/// Tag flag: the tag does not contain a footer.
+const APE_FLAG_HAS_NO_FOOTER: u32 = 0x4000_0000;
+
+/// Tag flag: this is the header of the tag.
+const APE_FLAG_IS_HEADER: u32 = 0x2000_0000;
+
+/// Item type: UTF-8 text.
+const APE_ITEM_TEXT: u32 = 0 << 1;
+
+/// Item type: binary data.
+const APE_ITEM_BINARY: u32 = 1 << 1;
+
+/// The APEv2 item keys written for each standard tag key.
+#[rustfmt::skip]
+const APE_WRITE_KEYS: &[(StandardTagKey, &str)] = &[
+    (StandardTagKey::Album                    , "Album"),
+    (StandardTagKey::AlbumArtist              , "Album Artist"),
+    (StandardTagKey::Arranger                 , "Arranger"),
+    (StandardTagKey::Artist                   , "Artist"),
+    (StandardTagKey::Bpm                      , "BPM"),
+    (StandardTagKey::Comment                  , "Comment"),
+    (StandardTagKey::Compilation              , "Compilation"),
+    (StandardTagKey::Composer                 , "Composer"),
+    (StandardTagKey::Conductor                , "Conductor"),
+    (StandardTagKey::Copyright                , "Copyright"),
+    (StandardTagKey::Date                     , "Year"),
+    (StandardTagKey::DiscNumber               , "Disc"),
+    (StandardTagKey::DiscSubtitle             , "DiscSubtitle"),
+    (StandardTagKey::EncodedBy                , "EncodedBy"),
+    (StandardTagKey::Encoder                  , "Encoder"),
+    (StandardTagKey::Engineer                 , "Engineer"),
+    (StandardTagKey::Genre                    , "Genre"),
+    (StandardTagKey::IdentBarcode             , "Barcode"),
+    (StandardTagKey::IdentCatalogNumber       , "CatalogNumber"),
+    (StandardTagKey::IdentEanUpn              , "EAN/UPC"),
+    (StandardTagKey::IdentIsrc                , "ISRC"),
+    (StandardTagKey::Label                    , "Label"),
+    (StandardTagKey::Language                 , "Language"),
+    (StandardTagKey::Lyricist                 , "Lyricist"),
+    (StandardTagKey::Lyrics                   , "Lyrics"),
+    (StandardTagKey::MediaFormat              , "Media"),
+    (StandardTagKey::MixDj                    , "DJMixer"),
+    (StandardTagKey::MixEngineer              , "Mixer"),
+    (StandardTagKey::Mood                     , "Mood"),
+    (StandardTagKey::MusicBrainzAlbumArtistId , "MUSICBRAINZ_ALBUMARTISTID"),
+    (StandardTagKey::MusicBrainzAlbumId       , "MUSICBRAINZ_ALBUMID"),
+    (StandardTagKey::MusicBrainzArtistId      , "MUSICBRAINZ_ARTISTID"),
+    (StandardTagKey::MusicBrainzDiscId        , "MUSICBRAINZ_DISCID"),
+    (StandardTagKey::MusicBrainzReleaseGroupId, "MUSICBRAINZ_RELEASEGROUPID"),
+    (StandardTagKey::MusicBrainzReleaseStatus , "MUSICBRAINZ_ALBUMSTATUS"),
+    (StandardTagKey::MusicBrainzReleaseTrackId, "MUSICBRAINZ_RELEASETRACKID"),
+    (StandardTagKey::MusicBrainzReleaseType   , "MUSICBRAINZ_ALBUMTYPE"),
+    (StandardTagKey::MusicBrainzTrackId       , "MUSICBRAINZ_TRACKID"),
+    (StandardTagKey::MusicBrainzWorkId        , "MUSICBRAINZ_WORKID"),
+    (StandardTagKey::OriginalAlbum            , "Debut Album"),
+    (StandardTagKey::OriginalArtist           , "Original Artist"),
+    (StandardTagKey::OriginalDate             , "OriginalDate"),
+    (StandardTagKey::OriginalFile             , "File"),
+    (StandardTagKey::Performer                , "Performer"),
+    (StandardTagKey::Producer                 , "Producer"),
+    (StandardTagKey::ReleaseCountry           , "ReleaseCountry"),
+    (StandardTagKey::Remixer                  , "Remixer"),
+    (StandardTagKey::ReplayGainAlbumGain      , "REPLAYGAIN_ALBUM_GAIN"),
+    (StandardTagKey::ReplayGainAlbumPeak      , "REPLAYGAIN_ALBUM_PEAK"),
+    (StandardTagKey::ReplayGainTrackGain      , "REPLAYGAIN_TRACK_GAIN"),
+    (StandardTagKey::ReplayGainTrackPeak      , "REPLAYGAIN_TRACK_PEAK"),
+    (StandardTagKey::Script                   , "Script"),
+    (StandardTagKey::SortAlbum                , "ALBUMSORT"),
+    (StandardTagKey::SortAlbumArtist          , "ALBUMARTISTSORT"),
+    (StandardTagKey::SortArtist               , "ARTISTSORT"),
+    (StandardTagKey::SortTrackTitle           , "TITLESORT"),
+    (StandardTagKey::TrackNumber              , "Track"),
+    (StandardTagKey::TrackSubtitle            , "Subtitle"),
+    (StandardTagKey::TrackTitle               , "Title"),
+    (StandardTagKey::UrlOfficial              , "Weblink"),
+    (StandardTagKey::Writer                   , "Writer"),
+];
+
+/// The APEv2 cover art item keys for each standard visual key.
+#[rustfmt::skip]
+const APE_VISUAL_KEYS: &[(StandardVisualKey, &str)] = &[
+    (StandardVisualKey::FileIcon                  , "Cover Art (Icon)"),
+    (StandardVisualKey::OtherIcon                 , "Cover Art (Other Icon)"),
+    (StandardVisualKey::FrontCover                , "Cover Art (Front)"),
+    (StandardVisualKey::BackCover                 , "Cover Art (Back)"),
+    (StandardVisualKey::Leaflet                   , "Cover Art (Leaflet)"),
+    (StandardVisualKey::Media                     , "Cover Art (Media)"),
+    (StandardVisualKey::LeadArtistPerformerSoloist, "Cover Art (Lead Artist)"),
+    (StandardVisualKey::ArtistPerformer           , "Cover Art (Artist)"),
+    (StandardVisualKey::Conductor                 , "Cover Art (Conductor)"),
+    (StandardVisualKey::BandOrchestra             , "Cover Art (Band)"),
+    (StandardVisualKey::Composer                  , "Cover Art (Composer)"),
+    (StandardVisualKey::Lyricist                  , "Cover Art (Lyricist)"),
+    (StandardVisualKey::RecordingLocation         , "Cover Art (Recording Location)"),
+    (StandardVisualKey::RecordingSession          , "Cover Art (During Recording)"),
+    (StandardVisualKey::Performance               , "Cover Art (During Performance)"),
+    (StandardVisualKey::ScreenCapture             , "Cover Art (Video Capture)"),
+    (StandardVisualKey::Illustration              , "Cover Art (Illustration)"),
+    (StandardVisualKey::BandArtistLogo            , "Cover Art (Band Logotype)"),
+    (StandardVisualKey::PublisherStudioLogo       , "Cover Art (Publisher Logotype)"),
+];
+
+/// The cover art item key for a visual without a known usage.
+const APE_VISUAL_KEY_OTHER: &str = "Cover Art (Other)";
+
+lazy_static! {
+    #[rustfmt::skip]
+    static ref APE_ITEM_MAP: HashMap<&'static str, StandardTagKey> = {
+        let mut m = HashMap::new();
+        m.insert("album artist"                , StandardTagKey::AlbumArtist);
+        m.insert("album"                       , StandardTagKey::Album);
+        m.insert("albumartist"                 , StandardTagKey::AlbumArtist);
+        m.insert("albumartistsort"             , StandardTagKey::SortAlbumArtist);
+        m.insert("albumsort"                   , StandardTagKey::SortAlbum);
+        m.insert("arranger"                    , StandardTagKey::Arranger);
+        m.insert("artist"                      , StandardTagKey::Artist);
+        m.insert("artistsort"                  , StandardTagKey::SortArtist);
+        m.insert("barcode"                     , StandardTagKey::IdentBarcode);
+        m.insert("bpm"                         , StandardTagKey::Bpm);
+        m.insert("catalog"                     , StandardTagKey::IdentCatalogNumber);
+        m.insert("catalognumber"               , StandardTagKey::IdentCatalogNumber);
+        m.insert("comment"                     , StandardTagKey::Comment);
+        m.insert("compilation"                 , StandardTagKey::Compilation);
+        m.insert("composer"                    , StandardTagKey::Composer);
+        m.insert("conductor"                   , StandardTagKey::Conductor);
+        m.insert("copyright"                   , StandardTagKey::Copyright);
+        m.insert("debut album"                 , StandardTagKey::OriginalAlbum);
+        m.insert("disc"                        , StandardTagKey::DiscNumber);
+        m.insert("discnumber"                  , StandardTagKey::DiscNumber);
+        m.insert("discsubtitle"                , StandardTagKey::DiscSubtitle);
+        m.insert("djmixer"                     , StandardTagKey::MixDj);
+        m.insert("ean/upc"                     , StandardTagKey::IdentEanUpn);
+        m.insert("encodedby"                   , StandardTagKey::EncodedBy);
+        m.insert("encoder"                     , StandardTagKey::Encoder);
+        m.insert("engineer"                    , StandardTagKey::Engineer);
+        m.insert("file"                        , StandardTagKey::OriginalFile);
+        m.insert("genre"                       , StandardTagKey::Genre);
+        m.insert("isrc"                        , StandardTagKey::IdentIsrc);
+        m.insert("label"                       , StandardTagKey::Label);
+        m.insert("language"                    , StandardTagKey::Language);
+        m.insert("lyricist"                    , StandardTagKey::Lyricist);
+        m.insert("lyrics"                      , StandardTagKey::Lyrics);
+        m.insert("media"                       , StandardTagKey::MediaFormat);
+        m.insert("mixer"                       , StandardTagKey::MixEngineer);
+        m.insert("mood"                        , StandardTagKey::Mood);
+        m.insert("musicbrainz_albumartistid"   , StandardTagKey::MusicBrainzAlbumArtistId);
+        m.insert("musicbrainz_albumid"         , StandardTagKey::MusicBrainzAlbumId);
+        m.insert("musicbrainz_albumstatus"     , StandardTagKey::MusicBrainzReleaseStatus);
+        m.insert("musicbrainz_albumtype"       , StandardTagKey::MusicBrainzReleaseType);
+        m.insert("musicbrainz_artistid"        , StandardTagKey::MusicBrainzArtistId);
+        m.insert("musicbrainz_discid"          , StandardTagKey::MusicBrainzDiscId);
+        m.insert("musicbrainz_releasegroupid"  , StandardTagKey::MusicBrainzReleaseGroupId);
+        m.insert("musicbrainz_releasetrackid"  , StandardTagKey::MusicBrainzReleaseTrackId);
+        m.insert("musicbrainz_trackid"         , StandardTagKey::MusicBrainzTrackId);
+        m.insert("musicbrainz_workid"          , StandardTagKey::MusicBrainzWorkId);
+        m.insert("original artist"             , StandardTagKey::OriginalArtist);
+        m.insert("originaldate"                , StandardTagKey::OriginalDate);
+        m.insert("performer"                   , StandardTagKey::Performer);
+        m.insert("producer"                    , StandardTagKey::Producer);
+        m.insert("publisher"                   , StandardTagKey::Label);
+        m.insert("record date"                 , StandardTagKey::Date);
+        m.insert("releasecountry"              , StandardTagKey::ReleaseCountry);
+        m.insert("remixer"                     , StandardTagKey::Remixer);
+        m.insert("replaygain_album_gain"       , StandardTagKey::ReplayGainAlbumGain);
+        m.insert("replaygain_album_peak"       , StandardTagKey::ReplayGainAlbumPeak);
+        m.insert("replaygain_track_gain"       , StandardTagKey::ReplayGainTrackGain);
+        m.insert("replaygain_track_peak"       , StandardTagKey::ReplayGainTrackPeak);
+        m.insert("script"                      , StandardTagKey::Script);
+        m.insert("subtitle"                    , StandardTagKey::TrackSubtitle);
+        m.insert("title"                       , StandardTagKey::TrackTitle);
+        m.insert("titlesort"                   , StandardTagKey::SortTrackTitle);
+        m.insert("track"                       , StandardTagKey::TrackNumber);
+        m.insert("weblink"                     , StandardTagKey::UrlOfficial);
+        m.insert("writer"                      , StandardTagKey::Writer);
+        m.insert("year"                        , StandardTagKey::Date);
+        m
+    };
+}
+
+/// The header or footer of an APE tag. Both have an identical layout and differ only in flags.
+#[derive(Copy, Clone, Debug)]
+pub struct ApeTagHeader {
+    /// The APE tag version. 1000 for APEv1, 2000 for APEv2.
+    pub version: u32,
+    /// The size of the tag in bytes including all items and the footer, but excluding the header.
+    pub tag_size: u32,
+    /// The number of items in the tag.
+    pub n_items: u32,
+    /// The global tag flags.
+    pub flags: u32,
+}
+
+impl ApeTagHeader {
+    /// Reads an APE tag header or footer, including the preamble.
+    pub fn read<B: ReadBytes>(reader: &mut B) -> Result<ApeTagHeader> {
+        let mut preamble = [0; 8];
+        reader.read_buf_exact(&mut preamble)?;
+
+        if preamble != APE_TAG_PREAMBLE {
+            return unsupported_error("ape: not an ape tag");
+        }
+
+        let version = reader.read_u32()?;
+        let tag_size = reader.read_u32()?;
+        let n_items = reader.read_u32()?;
+        let flags = reader.read_u32()?;
+
+        // Reserved, must be 0.
+        reader.ignore_bytes(8)?;
+
+        // The tag size must atleast be large enough to contain the footer.
+        if u64::from(tag_size) < APE_TAG_HEADER_SIZE {
+            return decode_error("ape: invalid tag size");
+        }
+
+        Ok(ApeTagHeader { version, tag_size, n_items, flags })
+    }
+
+    /// Returns true if the tag contains a header.
+    pub fn has_header(&self) -> bool {
+        self.flags & APE_FLAG_HAS_HEADER != 0
+    }
+
+    /// Returns true if the tag contains a footer.
+    pub fn has_footer(&self) -> bool {
+        self.flags & APE_FLAG_HAS_NO_FOOTER == 0
+    }
+
+    /// Returns true if this is the header of the tag, or false if it is the footer.
+    pub fn is_header(&self) -> bool {
+        self.flags & APE_FLAG_IS_HEADER != 0
+    }
+
+    /// Gets the total size of the tag in bytes including the header, if present.
+    pub fn total_size(&self) -> u64 {
+        u64::from(self.tag_size) + if self.has_header() { APE_TAG_HEADER_SIZE } else { 0 }
+    }
+
+    /// Writes the APE tag header or footer, including the preamble.
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writer.write_all(&APE_TAG_PREAMBLE)?;
+        writer.write_all(&self.version.to_le_bytes())?;
+        writer.write_all(&self.tag_size.to_le_bytes())?;
+        writer.write_all(&self.n_items.to_le_bytes())?;
+        writer.write_all(&self.flags.to_le_bytes())?;
+        writer.write_all(&[0; 8])?;
+        Ok(())
+    }
+}
+
+/// The location of an APE tag within a stream.
+#[derive(Copy, Clone, Debug)]
+pub struct ApeTagLocation {
+    /// The position of the first byte of the tag, including the header if present.
+    pub pos: u64,
+    /// The footer of the tag.
+    pub footer: ApeTagHeader,
+}
+
+impl ApeTagLocation {
+    /// Gets the total size of the tag in bytes, including the header, if present.
+    pub fn total_size(&self) -> u64 {
+        self.footer.total_size()
+    }
+
+    /// Gets the position of the first item of the tag.
+    pub fn items_pos(&self) -> u64 {
+        self.pos + if self.footer.has_header() { APE_TAG_HEADER_SIZE } else { 0 }
+    }
+}
+
+/// Checks if an APE tag footer ends exactly at the byte position `end` of the stream. If one
+/// does, returns the location of the tag. The position of the stream is undefined afterwards.
+pub fn find_ape_tag_ending_at(
+    reader: &mut MediaSourceStream,
+    end: u64,
+) -> Result<Option<ApeTagLocation>> {
+    if end < APE_TAG_HEADER_SIZE {
+        return Ok(None);
+    }
+
+    let footer_pos = end - APE_TAG_HEADER_SIZE;
+
+    reader.seek(SeekFrom::Start(footer_pos))?;
+
+    let footer = match ApeTagHeader::read(reader) {
+        Ok(footer) if !footer.is_header() => footer,
+        _ => return Ok(None),
+    };
+
+    // The tag may not extend past the start of the stream.
+    if footer.total_size() > end {
+        warn!("ape: tag size exceeds the stream length");
+        return Ok(None);
+    }
+
+    let pos = end - footer.total_size();
+
+    // If the tag states that it has a header, check that the header is where it is expected to be.
+    // A missing header is not fatal since the footer has all the information needed to read the
+    // tag.
+    if footer.has_header() {
+        reader.seek(SeekFrom::Start(pos))?;
+
+        match ApeTagHeader::read(reader) {
+            Ok(header) if header.is_header() => (),
+            _ => warn!("ape: tag header is missing or invalid"),
+        }
+    }
+
+    Ok(Some(ApeTagLocation { pos, footer }))
+}
+
+/// Searches for an APE tag at the end of a stream of length `end`. The APE tag may either be the
+/// very last tag of the stream, or immediately preceed an ID3v1 tag. The position of the stream
+/// is undefined afterwards.
+pub fn find_ape_tag(reader: &mut MediaSourceStream, end: u64) -> Result<Option<ApeTagLocation>> {
+    if let Some(location) = find_ape_tag_ending_at(reader, end)? {
+        return Ok(Some(location));
+    }
+
+    // Check for an ID3v1 tag at the end of the stream, and then for an APE tag before it.
+    if end >= ID3V1_TAG_LEN {
+        reader.seek(SeekFrom::Start(end - ID3V1_TAG_LEN))?;
+
+        if reader.read_triple_bytes()? == *b"TAG" {
+            return find_ape_tag_ending_at(reader, end - ID3V1_TAG_LEN);
+        }
+    }
+
+    Ok(None)
+}
+
+/// Reads the APE tag at the given location.
+pub fn read_ape_tag_at(
+    reader: &mut MediaSourceStream,
+    location: &ApeTagLocation,
+    metadata: &mut MetadataBuilder,
+) -> Result<()> {
+    reader.seek(SeekFrom::Start(location.items_pos()))?;
+    read_ape_items(reader, &location.footer, metadata)
+}
+
+/// Reads an APEv2 tag that starts with a header at the current position of the stream. The stream
+/// is positioned after the tag upon success.
+pub fn read_ape_tag<B: ReadBytes>(reader: &mut B, metadata: &mut MetadataBuilder) -> Result<()> {
+    let header = ApeTagHeader::read(reader)?;
+
+    if !header.is_header() {
+        return decode_error("ape: expected a tag header");
+    }
+
+    read_ape_items(reader, &header, metadata)?;
+
+    if header.has_footer() {
+        reader.ignore_bytes(APE_TAG_HEADER_SIZE)?;
+    }
+
+    Ok(())
+}
+
+/// Reads all the items of an APE tag. The reader must be positioned at the first item, and the
+/// header must be the tag's header or footer.
+pub fn read_ape_items<B: ReadBytes>(
+    reader: &mut B,
+    header: &ApeTagHeader,
+    metadata: &mut MetadataBuilder,
+) -> Result<()> {
+    // The number of bytes occupied by the items. The tag size includes the footer.
+    let mut items_len = u64::from(header.tag_size) - APE_TAG_HEADER_SIZE;
+
+    for _ in 0..header.n_items {
+        // Each item is atleast 11 bytes long: value size, flags, a 2 character key, and the
+        // key's null-terminator.
+        if items_len < 11 {
+            return decode_error("ape: item exceeds tag size");
+        }
+
+        let value_len = reader.read_u32()?;
+        let item_flags = reader.read_u32()?;
+
+        // The key is a null-terminated ASCII string of 2 to 255 characters.
+        let mut key = Vec::new();
+
+        loop {
+            let byte = reader.read_byte()?;
+
+            if byte == 0 {
+                break;
+            }
+
+            key.push(byte);
+
+            if key.len() > 255 {
+                return decode_error("ape: item key is too long");
+            }
+        }
+
+        let item_len = 8 + key.len() as u64 + 1 + u64::from(value_len);
+
+        if item_len > items_len {
+            return decode_error("ape: item exceeds tag size");
+        }
+
+        items_len -= item_len;
+
+        let value = reader.read_boxed_slice_exact(value_len as usize)?;
+
+        let key = String::from_utf8_lossy(&key);
+
+        // Bits 1 and 2 of the item flags designate the item type. In APEv1, all items are text.
+        let item_type = if header.version >= 2000 { (item_flags >> 1) & 0x3 } else { 0 };
+
+        match item_type {
+            // UTF-8 text, or an UTF-8 locator of external information. Since a text item can have
+            // a null-separated list of values, and Symphonia allows multiple tags with the same
+            // key, create one Tag per listed value.
+            0 | 2 => {
+                let std_key = map_key(&key);
+
+                for text in String::from_utf8_lossy(&value).split('\0').filter(|s| !s.is_empty()) {
+                    metadata.add_tag(Tag::new(std_key, &key, Value::from(text)));
+                }
+            }
+            // Binary data. Cover art is stored as binary data.
+            1 => match map_visual_key(&key) {
+                Some(usage) => {
+                    metadata.add_visual(read_cover_art(usage, &value));
+                }
+                None => {
+                    metadata.add_tag(Tag::new(map_key(&key), &key, Value::from(&*value)));
+                }
+            },
+            _ => warn!("ape: item '{}' has a reserved item type", key),
+        }
+    }
+
+    Ok(())
+}
+
+/// Maps an APE item key to a standard tag key.
+fn map_key(key: &str) -> Option<StandardTagKey> {
+    APE_ITEM_MAP.get(key.to_ascii_lowercase().as_str()).copied()
+}
+
+/// Maps an APE item key to a visual usage if the item is cover art. Unknown cover art types map
+/// to `Some(None)`.
+fn map_visual_key(key: &str) -> Option<Option<StandardVisualKey>> {
+    if !key.to_ascii_lowercase().starts_with("cover art") {
+        return None;
+    }
+
+    let usage = APE_VISUAL_KEYS
+        .iter()
+        .find(|(_, ape_key)| ape_key.eq_ignore_ascii_case(key))
+        .map(|&(usage, _)| usage);
+
+    Some(usage)
+}
+
+/// Reads a cover art item. The item value is a null-terminated file name, or description,
+/// followed by the image data.
+fn read_cover_art(usage: Option<StandardVisualKey>, value: &[u8]) -> Visual {
+    let (desc, data) = match value.iter().position(|&b| b == 0) {
+        Some(pos) => (String::from_utf8_lossy(&value[..pos]), &value[pos + 1..]),
+        None => (Default::default(), value),
+    };
+
+    let media_type = detect_media_type(data)
+        .or_else(|| media_type_from_file_name(&desc))
+        .unwrap_or_default()
+        .to_string();
+
+    let mut tags = Vec::new();
+
+    if !desc.is_empty() {
+        tags.push(Tag::new(Some(StandardTagKey::Description), "", Value::from(desc)));
+    }
+
+    Visual {
+        media_type,
+        dimensions: None,
+        bits_per_pixel: None,
+        color_mode: None,
+        usage,
+        tags,
+        data: Box::from(data),
+    }
+}
+
+/// Detects the media type of an image from its signature.
+fn detect_media_type(data: &[u8]) -> Option<&'static str> {
+    match data {
+        [0xff, 0xd8, 0xff, ..] => Some("image/jpeg"),
+        [0x89, b'P', b'N', b'G', ..] => Some("image/png"),
+        [b'G', b'I', b'F', b'8', ..] => Some("image/gif"),
+        [b'B', b'M', ..] => Some("image/bmp"),
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => Some("image/webp"),
+        _ => None,
+    }
+}
+
+/// Guesses the media type of an image from its file name extension.
+fn media_type_from_file_name(name: &str) -> Option<&'static str> {
+    let ext = name.rsplit('.').next()?.to_ascii_lowercase();
+
+    match ext.as_str() {
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "png" => Some("image/png"),
+        "gif" => Some("image/gif"),
+        "bmp" => Some("image/bmp"),
+        "webp" => Some("image/webp"),
+        _ => None,
+    }
+}
+
+/// Gets the file name extension for an image media type.
+fn file_name_extension(media_type: &str) -> &'static str {
+    match media_type {
+        "image/jpeg" | "image/jpg" => "jpg",
+        "image/png" => "png",
+        "image/gif" => "gif",
+        "image/bmp" => "bmp",
+        "image/webp" => "webp",
+        _ => "bin",
+    }
+}
+
+/// Returns true if the key is a valid APEv2 item key.
+fn is_valid_key(key: &str) -> bool {
+    // Keys are 2 to 255 printable ASCII characters long.
+    if key.len() < 2 || key.len() > 255 || !key.bytes().all(|b| (0x20..=0x7e).contains(&b)) {
+        return false;
+    }
+
+    // These keys are reserved to avoid confusion with other tag formats.
+    !["id3", "tag", "oggs", "mp+"].iter().any(|reserved| key.eq_ignore_ascii_case(reserved))
+}
+
+/// An APEv2 item pending serialization.
+struct ApeItem {
+    key: String,
+    flags: u32,
+    value: Vec<u8>,
+}
+
+impl ApeItem {
+    fn len(&self) -> usize {
+        8 + self.key.len() + 1 + self.value.len()
+    }
+}
+
+/// Serializes a `MetadataRevision` into an APEv2 tag, including a header and footer.
+///
+/// Tags with a standard key are written with the conventional APEv2 item key for that standard
+/// key. Other tags are written with their original key if it is a valid APEv2 item key. Since
+/// APEv2 item keys are unique, multiple tags with the same key are combined into a single text
+/// item containing a list of values. Visuals are written as binary cover art items.
+pub fn write_ape_tag<W: Write>(writer: &mut W, revision: &MetadataRevision) -> Result<()> {
+    let mut items: Vec<ApeItem> = Vec::new();
+
+    for tag in revision.tags() {
+        let key = match tag.std_key.and_then(|std_key| {
+            APE_WRITE_KEYS.iter().find(|(k, _)| *k == std_key).map(|&(_, key)| key)
+        }) {
+            Some(key) => key,
+            None if is_valid_key(&tag.key) => &tag.key,
+            None => {
+                warn!("ape: skipping tag with invalid item key '{}'", tag.key);
+                continue;
+            }
+        };
+
+        let (flags, value) = match &tag.value {
+            Value::Binary(buf) => (APE_ITEM_BINARY, buf.to_vec()),
+            Value::Boolean(value) => {
+                (APE_ITEM_TEXT, if *value { b"1".to_vec() } else { b"0".to_vec() })
+            }
+            Value::Flag => (APE_ITEM_TEXT, b"1".to_vec()),
+            value => (APE_ITEM_TEXT, value.to_string().into_bytes()),
+        };
+
+        match items.iter_mut().find(|item| item.key.eq_ignore_ascii_case(key)) {
+            // Append to the list of values of an existing text item.
+            Some(item) if item.flags == APE_ITEM_TEXT && flags == APE_ITEM_TEXT => {
+                item.value.push(0);
+                item.value.extend_from_slice(&value);
+            }
+            Some(_) => warn!("ape: skipping duplicate binary item '{}'", key),
+            None => items.push(ApeItem { key: key.to_string(), flags, value }),
+        }
+    }
+
+    for visual in revision.visuals() {
+        let key = match visual.usage {
+            Some(usage) => APE_VISUAL_KEYS
+                .iter()
+                .find(|(k, _)| *k == usage)
+                .map(|&(_, key)| key)
+                .unwrap_or(APE_VISUAL_KEY_OTHER),
+            None => APE_VISUAL_KEY_OTHER,
+        };
+
+        if items.iter().any(|item| item.key.eq_ignore_ascii_case(key)) {
+            warn!("ape: skipping duplicate cover art item '{}'", key);
+            continue;
+        }
+
+        // Use the description of the visual as the file name, if available.
+        let desc = visual.tags.iter().find(|tag| tag.std_key == Some(StandardTagKey::Description));
+
+        let mut value = match desc {
+            Some(tag) if !tag.value.to_string().is_empty() => tag.value.to_string().into_bytes(),
+            _ => format!("cover.{}", file_name_extension(&visual.media_type)).into_bytes(),
+        };
+
+        value.push(0);
+        value.extend_from_slice(&visual.data);
+
+        items.push(ApeItem { key: key.to_string(), flags: APE_ITEM_BINARY, value });
+    }
+
+    // Items should be sorted by size so that readers can skip large items easily.
+    items.sort_by_key(|item| item.len());
+
+    let items_len: usize = items.iter().map(|item| item.len()).sum();
+
+    if items_len as u64 + APE_TAG_HEADER_SIZE > u64::from(u32::MAX) {
+        return unsupported_error("ape: tag is too large");
+    }
+
+    let mut header = ApeTagHeader {
+        version: APE_TAG_VERSION_2,
+        tag_size: (items_len as u64 + APE_TAG_HEADER_SIZE) as u32,
+        n_items: items.len() as u32,
+        flags: APE_FLAG_HAS_HEADER | APE_FLAG_IS_HEADER,
+    };
+
+    header.write(writer)?;
+
+    for item in &items {
+        writer.write_all(&(item.value.len() as u32).to_le_bytes())?;
+        writer.write_all(&item.flags.to_le_bytes())?;
+        writer.write_all(item.key.as_bytes())?;
+        writer.write_all(&[0])?;
+        writer.write_all(&item.value)?;
+    }
+
+    header.flags = APE_FLAG_HAS_HEADER;
+    header.write(writer)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Seek, SeekFrom};
+
+    use symphonia_core::io::MediaSourceStream;
+    use symphonia_core::meta::{MetadataBuilder, StandardTagKey, StandardVisualKey};
+    use symphonia_core::meta::{Tag, Value, Visual};
+
+    use super::{find_ape_tag, find_ape_tag_ending_at, read_ape_tag, read_ape_tag_at};
+    use super::{write_ape_tag, APE_TAG_PREAMBLE};
+
+    fn visual(
+        media_type: &str,
+        usage: Option<StandardVisualKey>,
+        tags: Vec<Tag>,
+        data: &[u8],
+    ) -> Visual {
+        Visual {
+            media_type: media_type.to_string(),
+            dimensions: None,
+            bits_per_pixel: None,
+            color_mode: None,
+            usage,
+            tags,
+            data: Box::from(data),
+        }
+    }
+
+    #[test]
+    fn verify_ape_tag_round_trip() {
+        let mut builder = MetadataBuilder::new();
+
+        builder.add_tag(Tag::new(Some(StandardTagKey::TrackTitle), "TITLE", Value::from("Song")));
+        builder.add_tag(Tag::new(Some(StandardTagKey::Artist), "ARTIST", Value::from("A")));
+        builder.add_tag(Tag::new(Some(StandardTagKey::Artist), "ARTIST", Value::from("B")));
+        builder.add_tag(Tag::new(None, "Custom", Value::from(42u32)));
+        builder.add_visual(Visual {
+            media_type: "image/png".to_string(),
+            dimensions: None,
+            bits_per_pixel: None,
+            color_mode: None,
+            usage: Some(StandardVisualKey::FrontCover),
+            tags: Vec::new(),
+            data: Box::new([0x89, b'P', b'N', b'G', 1, 2, 3]),
+        });
+
+        // Write the tag after some audio data, and follow it with an ID3v1 tag.
+        let mut buf = vec![0xaa; 100];
+        write_ape_tag(&mut buf, &builder.metadata()).unwrap();
+        buf.extend_from_slice(b"TAG");
+        buf.resize(buf.len() + 125, 0);
+
+        let end = buf.len() as u64;
+
+        let mut mss = MediaSourceStream::new(Box::new(Cursor::new(buf)), Default::default());
+
+        let location = find_ape_tag(&mut mss, end).unwrap().unwrap();
+
+        assert_eq!(location.pos, 100);
+        assert_eq!(location.pos + location.total_size(), end - 128);
+
+        let mut builder = MetadataBuilder::new();
+        read_ape_tag_at(&mut mss, &location, &mut builder).unwrap();
+
+        let revision = builder.metadata();
+
+        let tags: Vec<_> =
+            revision.tags().iter().map(|tag| (tag.std_key, tag.value.to_string())).collect();
+
+        assert_eq!(tags.len(), 4);
+        assert!(tags.contains(&(Some(StandardTagKey::TrackTitle), "Song".to_string())));
+        assert!(tags.contains(&(Some(StandardTagKey::Artist), "A".to_string())));
+        assert!(tags.contains(&(Some(StandardTagKey::Artist), "B".to_string())));
+        assert!(tags.contains(&(None, "42".to_string())));
+
+        let visual = &revision.visuals()[0];
+
+        assert_eq!(visual.usage, Some(StandardVisualKey::FrontCover));
+        assert_eq!(visual.media_type, "image/png");
+        assert_eq!(&*visual.data, &[0x89, b'P', b'N', b'G', 1, 2, 3]);
+    }
+
+    #[test]
+    fn verify_apev1_footer() {
+        // An APEv1 tag has a footer, but no header.
+        let mut items = Vec::new();
+
+        items.extend_from_slice(&4u32.to_le_bytes());
+        items.extend_from_slice(&0u32.to_le_bytes());
+        items.extend_from_slice(b"Title\0Song");
+
+        // In APEv1, all items are text regardless of the item flags.
+        items.extend_from_slice(&4u32.to_le_bytes());
+        items.extend_from_slice(&2u32.to_le_bytes());
+        items.extend_from_slice(b"Year\x002001");
+
+        let mut buf = vec![0xaa; 100];
+        buf.extend_from_slice(&items);
+        buf.extend_from_slice(&APE_TAG_PREAMBLE);
+        buf.extend_from_slice(&1000u32.to_le_bytes());
+        buf.extend_from_slice(&(items.len() as u32 + 32).to_le_bytes());
+        buf.extend_from_slice(&2u32.to_le_bytes());
+        buf.extend_from_slice(&0u32.to_le_bytes());
+        buf.extend_from_slice(&[0; 8]);
+
+        let end = buf.len() as u64;
+
+        let mut mss = MediaSourceStream::new(Box::new(Cursor::new(buf)), Default::default());
+
+        let location = find_ape_tag_ending_at(&mut mss, end).unwrap().unwrap();
+
+        assert_eq!(location.pos, 100);
+        assert_eq!(location.items_pos(), 100);
+        assert_eq!(location.footer.version, 1000);
+        assert!(!location.footer.has_header());
+        assert!(location.footer.has_footer());
+        assert!(!location.footer.is_header());
+
+        let mut builder = MetadataBuilder::new();
+        read_ape_tag_at(&mut mss, &location, &mut builder).unwrap();
+
+        let revision = builder.metadata();
+        let tags = revision.tags();
+
+        assert_eq!(tags.len(), 2);
+        assert_eq!(tags[0].std_key, Some(StandardTagKey::TrackTitle));
+        assert_eq!(tags[0].value.to_string(), "Song");
+        assert_eq!(tags[1].std_key, Some(StandardTagKey::Date));
+        assert_eq!(tags[1].value.to_string(), "2001");
+
+        // A footer cannot be read as the start of a tag.
+        let mut builder = MetadataBuilder::new();
+        mss.seek(SeekFrom::Start(end - 32)).unwrap();
+
+        assert!(read_ape_tag(&mut mss, &mut builder).is_err());
+    }
+
+    #[test]
+    fn verify_ape_binary_round_trip() {
+        // Image data that contains null bytes, and every other byte value.
+        let mut jpeg = vec![0xff, 0xd8, 0xff, 0xe0, 0x00, 0x00];
+        jpeg.extend((0..=255u8).rev());
+
+        let data: Vec<u8> = (0..=255u8).collect();
+
+        let desc = Tag::new(Some(StandardTagKey::Description), "", Value::from("back.jpg"));
+
+        let mut builder = MetadataBuilder::new();
+        builder.add_tag(Tag::new(None, "Binary", Value::from(&data[..])));
+        builder.add_visual(visual(
+            "image/jpeg",
+            Some(StandardVisualKey::BackCover),
+            vec![desc],
+            &jpeg,
+        ));
+        builder.add_visual(visual("image/x-unknown", None, Vec::new(), &data));
+
+        let mut buf = Vec::new();
+        write_ape_tag(&mut buf, &builder.metadata()).unwrap();
+
+        let end = buf.len() as u64;
+
+        let mut mss = MediaSourceStream::new(Box::new(Cursor::new(buf)), Default::default());
+
+        let location = find_ape_tag(&mut mss, end).unwrap().unwrap();
+
+        assert_eq!(location.pos, 0);
+        assert!(location.footer.has_header());
+
+        let mut builder = MetadataBuilder::new();
+        read_ape_tag_at(&mut mss, &location, &mut builder).unwrap();
+
+        let revision = builder.metadata();
+
+        // Binary items that are not cover art are read as binary tags.
+        let tags = revision.tags();
+
+        assert_eq!(tags.len(), 1);
+        assert_eq!(tags[0].key, "Binary");
+        assert!(matches!(&tags[0].value, Value::Binary(value) if **value == *data));
+
+        // Cover art keeps its usage, description, and data.
+        let visuals = revision.visuals();
+
+        assert_eq!(visuals.len(), 2);
+
+        let back = visuals.iter().find(|v| v.usage == Some(StandardVisualKey::BackCover)).unwrap();
+
+        assert_eq!(back.media_type, "image/jpeg");
+        assert_eq!(back.tags[0].std_key, Some(StandardTagKey::Description));
+        assert_eq!(back.tags[0].value.to_string(), "back.jpg");
+        assert_eq!(&*back.data, &*jpeg);
+
+        // Cover art of an unknown usage and media type is written as other cover art with a
+        // generic file name.
+        let other = visuals.iter().find(|v| v.usage.is_none()).unwrap();
+
+        assert_eq!(other.tags[0].value.to_string(), "cover.bin");
+        assert_eq!(&*other.data, &*data);
+    }
+}
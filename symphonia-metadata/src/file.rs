@@ -0,0 +1,250 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Utilities for rewriting media files when metadata cannot be updated in-place.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use symphonia_core::errors::{unsupported_error, Result};
+
+/// The maximum number of temporary file names tried before giving up.
+const MAX_TEMP_FILE_ATTEMPTS: u32 = 16;
+
+/// Replaces the file at `path` with the content written by `write`.
+///
+/// If `path` is a symbolic link, the file it refers to is replaced, and the link is preserved.
+///
+/// The new content is written to a newly created temporary file in the same directory as the file.
+/// Once `write` returns, the temporary file is synced to disk, given the permissions of the
+/// original file, and renamed over the original file. Finally, the directory is synced such that
+/// the rename itself is durable. Therefore, the file at `path` always contains either the original
+/// or the new content, even if the process or system fails during the replacement.
+///
+/// If `write`, or any step before the rename fails, the temporary file is removed and the original
+/// file is left untouched.
+///
+/// Since the original file is replaced by a new file, rather than rewritten, the following files
+/// are refused on Unix platforms: files with multiple hard links, since the other links would keep
+/// the original content, and files owned by another user or group, since the new file would be
+/// owned by the current user and their group. Extended attributes and ACLs are not preserved.
+pub fn replace_file<P, F>(path: P, write: F) -> Result<()>
+where
+    P: AsRef<Path>,
+    F: FnOnce(&mut io::BufWriter<File>) -> Result<()>,
+{
+    // Follow symbolic links such that the link is not replaced by a regular file.
+    let path = fs::canonicalize(path)?;
+
+    let metadata = fs::metadata(&path)?;
+
+    if !metadata.is_file() {
+        return unsupported_error("meta: path is not a file");
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+
+        if metadata.nlink() > 1 {
+            return unsupported_error("meta: cannot replace a file with multiple hard links");
+        }
+    }
+
+    let (temp, temp_path) = create_temp_file(&path)?;
+
+    let result = (|| -> Result<()> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+
+            let temp_metadata = temp.metadata()?;
+
+            if temp_metadata.uid() != metadata.uid() || temp_metadata.gid() != metadata.gid() {
+                return unsupported_error("meta: cannot replace a file owned by another user");
+            }
+        }
+
+        let mut temp = io::BufWriter::new(temp);
+
+        write(&mut temp)?;
+
+        temp.flush()?;
+        temp.get_ref().sync_all()?;
+        temp.get_ref().set_permissions(metadata.permissions())?;
+
+        Ok(())
+    })();
+
+    if let Err(err) = result.and_then(|_| Ok(fs::rename(&temp_path, &path)?)) {
+        let _ = fs::remove_file(&temp_path);
+        return Err(err);
+    }
+
+    sync_parent_dir(&path)
+}
+
+/// Creates a new temporary file next to `path`. An existing file is never opened, even if it is
+/// left over from a previous failed replacement.
+fn create_temp_file(path: &Path) -> Result<(File, PathBuf)> {
+    let file_name = match path.file_name() {
+        Some(file_name) => file_name.to_string_lossy(),
+        None => return unsupported_error("meta: path is not a file"),
+    };
+
+    for attempt in 0..MAX_TEMP_FILE_ATTEMPTS {
+        let temp_path = path.with_file_name(format!(
+            ".{}.{}-{}.symphonia-tmp",
+            file_name,
+            std::process::id(),
+            attempt
+        ));
+
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+
+        // Only the owner may access the temporary file until it is given the permissions of the
+        // original file.
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+
+        match options.open(&temp_path) {
+            Ok(file) => return Ok((file, temp_path)),
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err.into()),
+        }
+    }
+
+    Err(io::Error::new(io::ErrorKind::AlreadyExists, "meta: failed to create a temporary file")
+        .into())
+}
+
+/// Syncs the directory containing `path` such that changes to its entries are durable.
+#[cfg(unix)]
+fn sync_parent_dir(path: &Path) -> Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+
+    File::open(dir)?.sync_all()?;
+
+    Ok(())
+}
+
+/// Syncs the directory containing `path` such that changes to its entries are durable.
+///
+/// Directories cannot be opened as files on this platform. The rename is made durable by the
+/// file system.
+#[cfg(not(unix))]
+fn sync_parent_dir(_path: &Path) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use symphonia_core::errors::decode_error;
+
+    use super::*;
+
+    fn test_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("symphonia-file-{}-{}.bin", name, std::process::id()))
+    }
+
+    /// Returns true if a temporary file for `path` exists.
+    fn has_temp_file(path: &Path) -> bool {
+        let prefix = format!(".{}.", path.file_name().unwrap().to_string_lossy());
+
+        fs::read_dir(path.parent().unwrap()).unwrap().any(|entry| {
+            let name = entry.unwrap().file_name().to_string_lossy().into_owned();
+            name.starts_with(&prefix) && name.ends_with(".symphonia-tmp")
+        })
+    }
+
+    #[test]
+    fn verify_replace_file() {
+        let path = test_path("replace");
+
+        fs::write(&path, b"original").unwrap();
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+        }
+
+        let permissions = fs::metadata(&path).unwrap().permissions();
+
+        // The original file is untouched if writing the new content fails.
+        let result = replace_file(&path, |writer| {
+            writer.write_all(b"partial")?;
+            decode_error("test: failed")
+        });
+
+        assert!(result.is_err());
+        assert_eq!(fs::read(&path).unwrap(), b"original");
+        assert!(!has_temp_file(&path));
+
+        // Otherwise, the file is replaced, and keeps its permissions.
+        replace_file(&path, |writer| Ok(writer.write_all(b"replaced")?)).unwrap();
+
+        assert_eq!(fs::read(&path).unwrap(), b"replaced");
+        assert_eq!(fs::metadata(&path).unwrap().permissions(), permissions);
+        assert!(!has_temp_file(&path));
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn verify_replace_file_links() {
+        use std::os::unix::fs::symlink;
+
+        let path = test_path("target");
+        let link = test_path("symlink");
+        let hard_link = test_path("hardlink");
+        let victim = test_path("victim");
+
+        fs::write(&path, b"original").unwrap();
+        fs::write(&victim, b"victim").unwrap();
+        symlink(&path, &link).unwrap();
+
+        // A file left at a temporary file path, even a symbolic link, is never written through.
+        let stale = fs::canonicalize(&path).unwrap().with_file_name(format!(
+            ".{}.{}-0.symphonia-tmp",
+            path.file_name().unwrap().to_string_lossy(),
+            std::process::id()
+        ));
+        symlink(&victim, &stale).unwrap();
+
+        // Replacing through a symbolic link replaces the file it refers to.
+        replace_file(&link, |writer| Ok(writer.write_all(b"replaced")?)).unwrap();
+
+        assert!(fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+        assert_eq!(fs::read(&path).unwrap(), b"replaced");
+        assert_eq!(fs::read(&victim).unwrap(), b"victim");
+
+        fs::remove_file(&stale).unwrap();
+
+        // Files with multiple hard links are refused.
+        fs::hard_link(&path, &hard_link).unwrap();
+
+        let result = replace_file(&path, |writer| Ok(writer.write_all(b"refused")?));
+
+        assert!(result.is_err());
+        assert_eq!(fs::read(&path).unwrap(), b"replaced");
+        assert!(!has_temp_file(&path));
+
+        for path in &[path, link, hard_link, victim] {
+            fs::remove_file(path).unwrap();
+        }
+    }
+}
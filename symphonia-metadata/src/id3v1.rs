@@ -11,6 +11,13 @@ use symphonia_core::errors::{unsupported_error, Result};
 use symphonia_core::io::ReadBytes;
 use symphonia_core::meta::{MetadataBuilder, StandardTagKey, Tag, Value};
 
+/// The length of an ID3v1 tag in bytes.
+pub const ID3V1_TAG_LEN: u64 = 128;
+
+/// The length of an enhanced ID3v1 (TAG+) tag in bytes. An enhanced tag immediately preceeds an
+/// ID3v1 tag.
+pub const ID3V1_ENHANCED_TAG_LEN: u64 = 227;
+
 static GENRES: &[&str] = &[
     // Standard Genres as per ID3v1 specificaation
     "Blues",
@@ -210,6 +217,35 @@ static GENRES: &[&str] = &[
 ];
 
 pub fn read_id3v1<B: ReadBytes>(reader: &mut B, metadata: &mut MetadataBuilder) -> Result<()> {
+    let buf = read_id3v1_body(reader)?;
+
+    add_id3v1_tags(&buf, None, metadata);
+
+    Ok(())
+}
+
+/// Reads an enhanced ID3v1 tag (TAG+) followed by the ID3v1 tag it extends.
+pub fn read_id3v1_enhanced<B: ReadBytes>(
+    reader: &mut B,
+    metadata: &mut MetadataBuilder,
+) -> Result<()> {
+    // Read the "TAG+" header.
+    let marker = reader.read_quad_bytes()?;
+
+    if marker != *b"TAG+" {
+        return unsupported_error("id3v1: Not an enhanced ID3v1 tag.");
+    }
+
+    let ext = reader.read_boxed_slice_exact(ID3V1_ENHANCED_TAG_LEN as usize - 4)?;
+
+    let buf = read_id3v1_body(reader)?;
+
+    add_id3v1_tags(&buf, Some(&ext), metadata);
+
+    Ok(())
+}
+
+fn read_id3v1_body<B: ReadBytes>(reader: &mut B) -> Result<Box<[u8]>> {
     // Read the "TAG" header.
     let marker = reader.read_triple_bytes()?;
 
@@ -217,19 +253,28 @@ pub fn read_id3v1<B: ReadBytes>(reader: &mut B, metadata: &mut MetadataBuilder)
         return unsupported_error("id3v1: Not an ID3v1 tag.");
     }
 
-    let buf = reader.read_boxed_slice_exact(125)?;
+    Ok(reader.read_boxed_slice_exact(125)?)
+}
 
-    let title = decode_iso8859_text(&buf[0..30]);
+/// Adds the tags of an ID3v1 tag, and the optional enhanced tag, to the metadata builder.
+fn add_id3v1_tags(buf: &[u8], ext: Option<&[u8]>, metadata: &mut MetadataBuilder) {
+    // The enhanced tag continues the title, artist, and album fields with 60 more characters each.
+    let extend = |field: &[u8], ext_range: std::ops::Range<usize>| match ext {
+        Some(ext) => decode_iso8859_text(&[field, &ext[ext_range]].concat()),
+        None => decode_iso8859_text(field),
+    };
+
+    let title = extend(&buf[0..30], 0..60);
     if !title.is_empty() {
         metadata.add_tag(Tag::new(Some(StandardTagKey::TrackTitle), "TITLE", Value::from(title)));
     }
 
-    let artist = decode_iso8859_text(&buf[30..60]);
+    let artist = extend(&buf[30..60], 60..120);
     if !artist.is_empty() {
         metadata.add_tag(Tag::new(Some(StandardTagKey::Artist), "ARTIST", Value::from(artist)));
     }
 
-    let album = decode_iso8859_text(&buf[60..90]);
+    let album = extend(&buf[60..90], 120..180);
     if !album.is_empty() {
         metadata.add_tag(Tag::new(Some(StandardTagKey::Album), "ALBUM", Value::from(album)));
     }
@@ -254,11 +299,17 @@ pub fn read_id3v1<B: ReadBytes>(reader: &mut B, metadata: &mut MetadataBuilder)
         metadata.add_tag(Tag::new(Some(StandardTagKey::Comment), "COMMENT", Value::from(comment)));
     }
 
+    // The enhanced tag may contain a free-form genre that supersedes the genre index.
+    let ext_genre = ext.map(|ext| decode_iso8859_text(&ext[181..211])).unwrap_or_default();
+
     let genre_idx = buf[124] as usize;
 
+    if !ext_genre.is_empty() {
+        metadata.add_tag(Tag::new(Some(StandardTagKey::Genre), "GENRE", Value::from(ext_genre)));
+    }
     // Convert the genre index to an actual genre name using the GENRES lookup table. Genre #133 is
     // an offensive term and is excluded from Symphonia.
-    if genre_idx < GENRES.len() && genre_idx != 133 {
+    else if genre_idx < GENRES.len() && genre_idx != 133 {
         metadata.add_tag(Tag::new(
             Some(StandardTagKey::Genre),
             "GENRE",
@@ -266,10 +317,33 @@ pub fn read_id3v1<B: ReadBytes>(reader: &mut B, metadata: &mut MetadataBuilder)
         ));
     }
 
-    Ok(())
+    if let Some(ext) = ext {
+        let speed = match ext[180] {
+            1 => "slow",
+            2 => "medium",
+            3 => "fast",
+            4 => "hardcore",
+            _ => "",
+        };
+
+        if !speed.is_empty() {
+            metadata.add_tag(Tag::new(None, "SPEED", Value::from(speed)));
+        }
+
+        // The start and end times of the music in the file, formatted as "mmm:ss".
+        let start_time = decode_iso8859_text(&ext[211..217]);
+        if !start_time.is_empty() {
+            metadata.add_tag(Tag::new(None, "START_TIME", Value::from(start_time)));
+        }
+
+        let end_time = decode_iso8859_text(&ext[217..223]);
+        if !end_time.is_empty() {
+            metadata.add_tag(Tag::new(None, "END_TIME", Value::from(end_time)));
+        }
+    }
 }
 
-fn decode_iso8859_text(data: &[u8]) -> String {
+pub(crate) fn decode_iso8859_text(data: &[u8]) -> String {
     data.iter().filter(|&b| *b > 0x1f).map(|&b| b as char).collect()
 }
 
@@ -0,0 +1,234 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::formats::Cue;
+use symphonia_core::meta::{Tag, Visual};
+
+/// A chapter read from an ID3v2 `CHAP` frame.
+#[derive(Clone, Debug)]
+pub struct Chapter {
+    /// The element identifier of the chapter.
+    pub id: String,
+    /// The start time of the chapter in milliseconds.
+    pub start_ms: u32,
+    /// The end time of the chapter in milliseconds.
+    pub end_ms: u32,
+    /// The byte offset of the first audio frame of the chapter, if set.
+    pub start_byte: Option<u32>,
+    /// The byte offset of the first audio frame after the chapter, if set.
+    pub end_byte: Option<u32>,
+    /// The tags read from the embedded sub-frames.
+    pub tags: Vec<Tag>,
+    /// The visuals read from the embedded sub-frames.
+    pub visuals: Vec<Visual>,
+}
+
+/// A table of contents read from an ID3v2 `CTOC` frame.
+#[derive(Clone, Debug)]
+pub struct TableOfContents {
+    /// The element identifier of the table of contents.
+    pub id: String,
+    /// If true, this is the root table of contents.
+    pub is_top_level: bool,
+    /// If true, the items are in playback order.
+    pub is_ordered: bool,
+    /// The element identifiers of the child chapters, or tables of contents.
+    pub items: Vec<String>,
+    /// The tags read from the embedded sub-frames.
+    pub tags: Vec<Tag>,
+    /// The visuals read from the embedded sub-frames.
+    pub visuals: Vec<Visual>,
+}
+
+/// All the chapters and tables of contents of an ID3v2 tag.
+#[derive(Clone, Debug, Default)]
+pub struct Chapters {
+    pub chapters: Vec<Chapter>,
+    pub tables: Vec<TableOfContents>,
+}
+
+impl Chapters {
+    /// Returns `true` if there are no chapters.
+    pub fn is_empty(&self) -> bool {
+        self.chapters.is_empty()
+    }
+
+    /// Converts the chapters into a list of `Cue`s with timestamps in units of samples at the
+    /// given sample rate.
+    ///
+    /// If there is a top-level table of contents, the chapters it refers to, either directly or
+    /// through nested tables of contents, are returned in the order given by the tables. Otherwise,
+    /// all chapters are returned in order of their start time.
+    pub fn to_cues(&self, sample_rate: u32) -> Vec<Cue> {
+        let mut ordered = Vec::new();
+
+        match self.tables.iter().find(|toc| toc.is_top_level) {
+            Some(root) => {
+                let mut visited = Vec::new();
+                self.flatten(root, &mut visited, &mut ordered);
+            }
+            None => {
+                ordered.extend(self.chapters.iter());
+                ordered.sort_by_key(|chapter| chapter.start_ms);
+            }
+        }
+
+        ordered
+            .into_iter()
+            .enumerate()
+            .map(|(index, chapter)| Cue {
+                index: index as u32,
+                start_ts: u64::from(chapter.start_ms) * u64::from(sample_rate) / 1000,
+                tags: chapter.tags.clone(),
+                visuals: chapter.visuals.clone(),
+                points: Vec::new(),
+            })
+            .collect()
+    }
+
+    /// Appends the chapters referred to by a table of contents, depth-first, to `ordered`.
+    fn flatten<'a>(
+        &'a self,
+        toc: &'a TableOfContents,
+        visited: &mut Vec<&'a str>,
+        ordered: &mut Vec<&'a Chapter>,
+    ) {
+        // Guard against tables of contents that refer to themselves.
+        if visited.contains(&toc.id.as_str()) {
+            return;
+        }
+
+        visited.push(&toc.id);
+
+        let first = ordered.len();
+
+        for item in &toc.items {
+            if let Some(chapter) = self.chapters.iter().find(|chapter| &chapter.id == item) {
+                ordered.push(chapter);
+            }
+            else if let Some(child) = self.tables.iter().find(|child| &child.id == item) {
+                self.flatten(child, visited, ordered);
+            }
+        }
+
+        // The items of an unordered table of contents are ordered by their start time instead.
+        if !toc.is_ordered {
+            ordered[first..].sort_by_key(|chapter| chapter.start_ms);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use symphonia_core::io::BufReader;
+    use symphonia_core::meta::{MetadataBuilder, StandardTagKey};
+
+    use super::super::read_id3v2_with_extras;
+    use super::*;
+
+    /// Makes an ID3v2.3 frame.
+    fn frame(id: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        let mut buf = id.to_vec();
+        buf.extend_from_slice(&(body.len() as u32).to_be_bytes());
+        buf.extend_from_slice(&[0, 0]);
+        buf.extend_from_slice(body);
+        buf
+    }
+
+    fn chapter(id: &str, start_ms: u32) -> Chapter {
+        Chapter {
+            id: id.to_string(),
+            start_ms,
+            end_ms: start_ms + 1000,
+            start_byte: None,
+            end_byte: None,
+            tags: Vec::new(),
+            visuals: Vec::new(),
+        }
+    }
+
+    fn toc(id: &str, is_top_level: bool, is_ordered: bool, items: &[&str]) -> TableOfContents {
+        TableOfContents {
+            id: id.to_string(),
+            is_top_level,
+            is_ordered,
+            items: items.iter().map(|item| item.to_string()).collect(),
+            tags: Vec::new(),
+            visuals: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn verify_chapters_to_cues() {
+        let mut chapters = Chapters {
+            chapters: vec![chapter("c3", 3000), chapter("c1", 1000), chapter("c2", 2000)],
+            tables: Vec::new(),
+        };
+
+        // Without a table of contents, chapters are ordered by start time.
+        let cues = chapters.to_cues(8000);
+        let starts: Vec<u64> = cues.iter().map(|cue| cue.start_ts).collect();
+        assert_eq!(starts, [8000, 16000, 24000]);
+
+        // A nested table of contents is flattened in place, and unknown items are ignored.
+        chapters.tables.push(toc("root", true, true, &["c3", "sub", "missing"]));
+        chapters.tables.push(toc("sub", false, false, &["c2", "c1", "root"]));
+
+        let cues = chapters.to_cues(1000);
+        let starts: Vec<u64> = cues.iter().map(|cue| cue.start_ts).collect();
+        assert_eq!(starts, [3000, 1000, 2000]);
+        assert_eq!(cues.iter().map(|cue| cue.index).collect::<Vec<_>>(), [0, 1, 2]);
+    }
+
+    #[test]
+    fn verify_read_chapter_frames() {
+        let mut chap = b"ch1\0".to_vec();
+        chap.extend_from_slice(&1500u32.to_be_bytes());
+        chap.extend_from_slice(&4000u32.to_be_bytes());
+        chap.extend_from_slice(&[0xff; 8]);
+        chap.extend(frame(b"TIT2", b"\0Intro"));
+        chap.extend(frame(b"APIC", b"\0image/png\0\x03\0\x89PNG"));
+
+        let mut ctoc = b"toc\0\x03\x01ch1\0".to_vec();
+        ctoc.extend(frame(b"TIT2", b"\0Contents"));
+
+        let mut frames = frame(b"CTOC", &ctoc);
+        frames.extend(frame(b"CHAP", &chap));
+        frames.extend(frame(b"TIT2", b"\0Episode"));
+
+        let mut tag = b"ID3\x03\0\0".to_vec();
+        let size = frames.len() as u32;
+        tag.extend_from_slice(&[
+            (size >> 21) as u8,
+            (size >> 14) as u8 & 0x7f,
+            (size >> 7) as u8 & 0x7f,
+            size as u8 & 0x7f,
+        ]);
+        tag.extend(frames);
+
+        let mut builder = MetadataBuilder::new();
+        let chapters =
+            read_id3v2_with_extras(&mut BufReader::new(&tag), &mut builder).unwrap().chapters;
+
+        // Sub-frames must not leak into the tag's own tags.
+        assert_eq!(builder.metadata().tags().len(), 1);
+
+        assert_eq!(chapters.tables.len(), 1);
+        assert!(chapters.tables[0].is_top_level && chapters.tables[0].is_ordered);
+        assert_eq!(chapters.tables[0].items, ["ch1"]);
+
+        let chapter = &chapters.chapters[0];
+        assert_eq!((chapter.start_ms, chapter.end_ms, chapter.start_byte), (1500, 4000, None));
+        assert_eq!(chapter.tags[0].std_key, Some(StandardTagKey::TrackTitle));
+        assert_eq!(chapter.tags[0].value.to_string(), "Intro");
+        assert_eq!(chapter.visuals[0].media_type, "image/png");
+
+        let cues = chapters.to_cues(44100);
+        assert_eq!(cues[0].start_ts, 66150);
+        assert_eq!(cues[0].visuals.len(), 1);
+    }
+}
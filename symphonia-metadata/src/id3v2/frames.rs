@@ -10,13 +10,15 @@ use std::io;
 use std::str;
 
 use symphonia_core::errors::{decode_error, unsupported_error, Result};
-use symphonia_core::io::{BufReader, FiniteStream, ReadBytes};
-use symphonia_core::meta::{StandardTagKey, Tag, Value, Visual};
+use symphonia_core::io::{BitReaderLtr, BufReader, FiniteStream, ReadBitsLtr, ReadBytes};
+use symphonia_core::meta::{Attachment, StandardTagKey, Tag, Value, VendorData, Visual};
+use symphonia_core::meta::{TimedText, TimedTextContentType, TimedTextLine, TimedTextUnit};
 
 use encoding_rs::UTF_16BE;
 use lazy_static::lazy_static;
 use log::warn;
 
+use super::timing::{EventTimingCodes, MpegLocationRef, MpegLocationTable, TimingEvent};
 use super::unsync::{decode_unsynchronisation, read_syncsafe_leq32};
 use super::util;
 
@@ -32,35 +34,35 @@ use super::util;
 //   -   ----   ----    ----    ----------------    ------------------------------------------------
 //   S   v2.2   v2.3    v2.4    Std. Key            Description
 //   -   ----   ----    ----    ----------------    ------------------------------------------------
-//       CRA    AENC                                Audio encryption
+//   x   CRA    AENC                                Audio encryption
 //       CRM                                        Encrypted meta frame
 //   x   PIC    APIC                                Attached picture
-//                      ASPI                        Audio seek point index
+//   x                  ASPI                        Audio seek point index
 //   x   COM    COMM             Comment            Comments
-//              COMR                                Commercial frame
-//              ENCR                                Encryption method registration
-//       EQU    EQUA                                Equalisation
-//                      EQU2                        Equalisation (2)
-//       ETC    ETCO                                Event timing codes
-//       GEO    GEOB                                General encapsulated object
-//              GRID                                Group identification registration
+//   x          COMR                                Commercial frame
+//   x          ENCR                                Encryption method registration
+//   x   EQU    EQUA                                Equalisation
+//   x                  EQU2                        Equalisation (2)
+//   x   ETC    ETCO                                Event timing codes
+//   x   GEO    GEOB                                General encapsulated object
+//   x          GRID                                Group identification registration
 //   x   IPL    IPLS    TIPL                        Involved people list
-//       LNK    LINK                                Linked information
+//   x   LNK    LINK                                Linked information
 //   x   MCI    MCDI                                Music CD identifier
-//       MLL    MLLT                                MPEG location lookup table
-//              OWNE                                Ownership frame
+//   x   MLL    MLLT                                MPEG location lookup table
+//   x          OWNE                                Ownership frame
 //   x          PRIV                                Private frame
 //   x   CNT    PCNT                                Play counter
 //   x   POP    POPM             Rating             Popularimeter
-//              POSS                                Position synchronisation frame
-//       BUF    RBUF                                Recommended buffer size
-//       RVA    RVAD                                Relative volume adjustment
-//                      RVA2                        Relative volume adjustment (2)
-//       REV    RVRB                                Reverb
-//                      SEEK                        Seek frame
-//                      SIGN                        Signature frame
-//       SLT    SYLT                                Synchronized lyric/text
-//       STC    SYTC                                Synchronized tempo codes
+//   x          POSS                                Position synchronisation frame
+//   x   BUF    RBUF                                Recommended buffer size
+//   x   RVA    RVAD                                Relative volume adjustment
+//   x                  RVA2                        Relative volume adjustment (2)
+//   x   REV    RVRB                                Reverb
+//   x                  SEEK                        Seek frame
+//   x                  SIGN                        Signature frame
+//   x   SLT    SYLT                                Synchronized lyric/text
+//   x   STC    SYTC                                Synchronized tempo codes
 //   x   TAL    TALB             Album              Album/Movie/Show title
 //   x   TBP    TBPM             Bpm                BPM (beats per minute)
 //   x   TCM    TCOM             Composer           Composer
@@ -112,8 +114,8 @@ use super::util;
 //   x                  TSST                        Set subtitle
 //   x   TYE    TYER    n/a      Date               Year
 //   x   TXX    TXXX                                User defined text information frame
-//       UFI    UFID                                Unique file identifier
-//              USER                                Terms of use
+//   x   UFI    UFID                                Unique file identifier
+//   x          USER                                Terms of use
 //   x   ULT    USLT             Lyrics             Unsychronized lyric/text transcription
 //   x   WCM    WCOM             UrlPurchase        Commercial information
 //   x   WCP    WCOP             UrlCopyright       Copyright/Legal information
@@ -138,12 +140,15 @@ use super::util;
 //   x   TSA                     SortAlbum          (Apple iTunes) Album sort order
 //   x   TS2    TSO2             SortAlbumArtist    (Apple iTunes) Album artist sort order
 //   x   TSC    TSOC             SortComposer       (Apple iTunes) Composer sort order
+//   x          CHAP                                (Addendum) Chapter
+//   x          CTOC                                (Addendum) Table of contents
 //
 // Information on these frames can be found at:
 //
 //     ID3v2.2: http://id3.org/id3v2-00
 //     ID3v2.3: http://id3.org/d3v2.3.0
 //     ID3v2.4: http://id3.org/id3v2.4.0-frames
+//     Chapters: http://id3.org/id3v2-chapters-1.0
 
 /// The result of parsing a frame.
 pub enum FrameResult {
@@ -159,6 +164,51 @@ pub enum FrameResult {
     Visual(Visual),
     /// A frame was parsed and yielded many `Tag`s.
     MultipleTags(Vec<Tag>),
+    /// A chapter frame was parsed.
+    Chapter(ChapterFrame),
+    /// A table of contents frame was parsed.
+    TableOfContents(TocFrame),
+    /// A frame was parsed and yielded `TimedText`.
+    TimedText(TimedText),
+    /// A frame was parsed and yielded an `Attachment`.
+    Attachment(Attachment),
+    /// A frame was parsed and yielded `VendorData`.
+    VendorData(VendorData),
+    /// An event timing codes frame was parsed.
+    EventTiming(EventTimingCodes),
+    /// A MPEG location lookup table frame was parsed.
+    MpegLocations(MpegLocationTable),
+    /// A frame describing the stream, rather than being metadata, was parsed.
+    StreamFrame(StreamFrame),
+}
+
+/// A frame that describes the stream the tag is attached to, such as a chapter or a seek table.
+/// The frame body is retained so that the frame may be parsed again by a format reader.
+pub struct StreamFrame {
+    pub id: String,
+    pub body: Box<[u8]>,
+    pub frame: Box<FrameResult>,
+}
+
+/// A `CHAP` (chapter) frame. The embedded sub-frames are left unparsed since they must be read
+/// with the frame reader for the major version of the tag.
+pub struct ChapterFrame {
+    pub id: String,
+    pub start_ms: u32,
+    pub end_ms: u32,
+    pub start_byte: Option<u32>,
+    pub end_byte: Option<u32>,
+    pub sub_frames: Box<[u8]>,
+}
+
+/// A `CTOC` (table of contents) frame. The embedded sub-frames are left unparsed since they must
+/// be read with the frame reader for the major version of the tag.
+pub struct TocFrame {
+    pub id: String,
+    pub is_top_level: bool,
+    pub is_ordered: bool,
+    pub items: Vec<String>,
+    pub sub_frames: Box<[u8]>,
 }
 
 /// Makes a frame result for a frame containing invalid data.
@@ -252,34 +302,36 @@ lazy_static! {
     static ref FRAME_PARSERS:
         HashMap<&'static [u8; 4], (FrameParser, Option<StandardTagKey>)> = {
             let mut m = HashMap::new();
-            // m.insert(b"AENC", read_null_frame);
-            m.insert(b"APIC", (read_apic_frame as FrameParser, None));
-            // m.insert(b"ASPI", read_null_frame);
+            m.insert(b"AENC", (read_owner_data_frame as FrameParser, None));
+            m.insert(b"APIC", (read_apic_frame, None));
+            m.insert(b"ASPI", (read_binary_frame, None));
+            m.insert(b"CHAP", (read_chap_frame, None));
             m.insert(b"COMM", (read_comm_uslt_frame, Some(StandardTagKey::Comment)));
-            // m.insert(b"COMR", read_null_frame);
-            // m.insert(b"ENCR", read_null_frame);
-            // m.insert(b"EQU2", read_null_frame);
-            // m.insert(b"EQUA", read_null_frame);
-            // m.insert(b"ETCO", read_null_frame);
-            // m.insert(b"GEOB", read_null_frame);
-            // m.insert(b"GRID", read_null_frame);
+            m.insert(b"COMR", (read_comr_frame, None));
+            m.insert(b"CTOC", (read_ctoc_frame, None));
+            m.insert(b"ENCR", (read_owner_data_frame, None));
+            m.insert(b"EQU2", (read_binary_frame, None));
+            m.insert(b"EQUA", (read_binary_frame, None));
+            m.insert(b"ETCO", (read_etco_frame, None));
+            m.insert(b"GEOB", (read_geob_frame, None));
+            m.insert(b"GRID", (read_owner_data_frame, None));
             m.insert(b"IPLS", (read_text_frame, None));
-            // m.insert(b"LINK", read_null_frame);
+            m.insert(b"LINK", (read_link_frame, None));
             m.insert(b"MCDI", (read_mcdi_frame, None));
-            // m.insert(b"MLLT", read_null_frame);
-            // m.insert(b"OWNE", read_null_frame);
+            m.insert(b"MLLT", (read_mllt_frame, None));
+            m.insert(b"OWNE", (read_owne_frame, None));
             m.insert(b"PCNT", (read_pcnt_frame, None));
             m.insert(b"POPM", (read_popm_frame, Some(StandardTagKey::Rating)));
-            // m.insert(b"POSS", read_null_frame);
+            m.insert(b"POSS", (read_poss_frame, None));
             m.insert(b"PRIV", (read_priv_frame, None));
-            // m.insert(b"RBUF", read_null_frame);
-            // m.insert(b"RVA2", read_null_frame);
-            // m.insert(b"RVAD", read_null_frame);
-            // m.insert(b"RVRB", read_null_frame);
-            // m.insert(b"SEEK", read_null_frame);
-            // m.insert(b"SIGN", read_null_frame);
-            // m.insert(b"SYLT", read_null_frame);
-            // m.insert(b"SYTC", read_null_frame);
+            m.insert(b"RBUF", (read_rbuf_frame, None));
+            m.insert(b"RVA2", (read_rva2_frame, None));
+            m.insert(b"RVAD", (read_binary_frame, None));
+            m.insert(b"RVRB", (read_binary_frame, None));
+            m.insert(b"SEEK", (read_seek_frame, None));
+            m.insert(b"SIGN", (read_binary_frame, None));
+            m.insert(b"SYLT", (read_sylt_frame, None));
+            m.insert(b"SYTC", (read_binary_frame, None));
             m.insert(b"TALB", (read_text_frame, Some(StandardTagKey::Album)));
             m.insert(b"TBPM", (read_text_frame, Some(StandardTagKey::Bpm)));
             m.insert(b"TCOM", (read_text_frame, Some(StandardTagKey::Composer)));
@@ -335,8 +387,8 @@ lazy_static! {
             m.insert(b"TSST", (read_text_frame, None));
             m.insert(b"TXXX", (read_txxx_frame, None));
             m.insert(b"TYER", (read_text_frame, Some(StandardTagKey::Date)));
-            // m.insert(b"UFID", read_null_frame);
-            // m.insert(b"USER", read_null_frame);
+            m.insert(b"UFID", (read_ufid_frame, None));
+            m.insert(b"USER", (read_user_frame, None));
             m.insert(b"USLT", (read_comm_uslt_frame, Some(StandardTagKey::Lyrics)));
             m.insert(b"WCOM", (read_url_frame, Some(StandardTagKey::UrlPurchase)));
             m.insert(b"WCOP", (read_url_frame, Some(StandardTagKey::UrlCopyright)));
@@ -385,6 +437,19 @@ lazy_static! {
     };
 }
 
+/// Gets the `TXXX` frame description for a standard key, if there is a conventional one.
+pub fn txxx_description(std_key: StandardTagKey) -> Option<&'static str> {
+    TXXX_FRAME_STD_KEYS.iter().find(|(_, &key)| key == std_key).map(|(&desc, _)| desc)
+}
+
+/// Returns true if a frame with the given identifier can be read.
+pub fn is_supported_frame_id(id: &[u8]) -> bool {
+    match id {
+        [a, b, c, d] => find_parser([*a, *b, *c, *d]).is_some(),
+        _ => false,
+    }
+}
+
 /// Validates that a frame id only contains the uppercase letters A-Z, and digits 0-9.
 fn validate_frame_id(id: &[u8]) -> bool {
     // Only frame IDs with 3 or 4 characters are valid.
@@ -460,7 +525,7 @@ pub fn read_id3v2p2_frame<B: ReadBytes>(reader: &mut B) -> Result<FrameResult> {
 
     let data = reader.read_boxed_slice_exact(size as usize)?;
 
-    parser(&mut BufReader::new(&data), *std_key, as_ascii_str(&id))
+    parse_frame(*parser, *std_key, &id, &data)
 }
 
 /// Read an ID3v2.3 frame.
@@ -526,7 +591,7 @@ pub fn read_id3v2p3_frame<B: ReadBytes>(reader: &mut B) -> Result<FrameResult> {
 
     let data = reader.read_boxed_slice_exact(size as usize)?;
 
-    parser(&mut BufReader::new(&data), *std_key, as_ascii_str(&id))
+    parse_frame(*parser, *std_key, &id, &data)
 }
 
 /// Read an ID3v2.4 frame.
@@ -623,12 +688,50 @@ pub fn read_id3v2p4_frame<B: ReadBytes + FiniteStream>(reader: &mut B) -> Result
     if flags & 0x2 != 0x0 {
         let unsync_data = decode_unsynchronisation(&mut raw_data);
 
-        parser(&mut BufReader::new(unsync_data), *std_key, as_ascii_str(&id))
+        parse_frame(*parser, *std_key, &id, unsync_data)
     }
     // The frame body has not been unsynchronised. Wrap the raw data buffer in BufStream without any
     // additional decoding.
     else {
-        parser(&mut BufReader::new(&raw_data), *std_key, as_ascii_str(&id))
+        parse_frame(*parser, *std_key, &id, &raw_data)
+    }
+}
+
+/// Parses a frame body. If the frame describes the stream, the body is retained alongside the
+/// parsed frame.
+fn parse_frame(
+    parser: FrameParser,
+    std_key: Option<StandardTagKey>,
+    id: &[u8],
+    body: &[u8],
+) -> Result<FrameResult> {
+    let frame = parser(&mut BufReader::new(body), std_key, as_ascii_str(id))?;
+
+    match frame {
+        FrameResult::Chapter(_)
+        | FrameResult::TableOfContents(_)
+        | FrameResult::EventTiming(_)
+        | FrameResult::MpegLocations(_) => Ok(FrameResult::StreamFrame(StreamFrame {
+            id: as_ascii_str(id).to_string(),
+            body: Box::from(body),
+            frame: Box::new(frame),
+        })),
+        _ => Ok(frame),
+    }
+}
+
+/// Parses the body of a frame previously retained by a `StreamFrame` for a tag of the given major
+/// version.
+pub fn read_stream_frame(id: &str, body: &[u8], major_version: u8) -> Result<FrameResult> {
+    let parser = match (major_version, id.as_bytes()) {
+        (2, &[a, b, c]) => find_parser_legacy([a, b, c]),
+        (3 | 4, &[a, b, c, d]) => find_parser([a, b, c, d]),
+        _ => None,
+    };
+
+    match parser {
+        Some((parser, std_key)) => parser(&mut BufReader::new(body), *std_key, id),
+        None => unsupported_frame(id.as_bytes()),
     }
 }
 
@@ -790,9 +893,17 @@ fn read_comm_uslt_frame(
         id.to_string()
     };
 
-    // Short text (content description) is next, but since there is no way to represent this in
-    // Symphonia, skip it.
-    scan_text(reader, encoding, reader.bytes_available() as usize)?;
+    // Short text (content description) is next. There is no way to represent this in Symphonia,
+    // except for iTunes data (e.g., iTunNORM, iTunSMPB), which is stored in comments described by
+    // its name. Since it is not a comment, key it by the description instead.
+    let desc = scan_text(reader, encoding, reader.bytes_available() as usize)?;
+
+    let (std_key, key) = if id == "COMM" && desc.starts_with("iTun") {
+        (None, format!("{}:{}", key, desc))
+    }
+    else {
+        (std_key, key)
+    };
 
     // Full text (lyrics) is last.
     let text = scan_text(reader, encoding, reader.bytes_available() as usize)?;
@@ -803,6 +914,68 @@ fn read_comm_uslt_frame(
     Ok(FrameResult::Tag(tag))
 }
 
+/// Reads a `SYLT` (synchronized lyrics/text) frame.
+fn read_sylt_frame(
+    reader: &mut BufReader<'_>,
+    _: Option<StandardTagKey>,
+    _: &str,
+) -> Result<FrameResult> {
+    // The first byte of the frame is the encoding of the description and text.
+    let encoding = match Encoding::parse(reader.read_byte()?) {
+        Some(encoding) => encoding,
+        _ => return decode_error("id3v2: invalid text encoding"),
+    };
+
+    // The next three bytes are the language. Invalid language codes are ignored.
+    let lang = reader.read_triple_bytes()?;
+
+    let language = Some(lang)
+        .filter(|&lang| validate_lang_code(lang))
+        .map(|lang| as_ascii_str(&lang).to_string());
+
+    let unit = match reader.read_byte()? {
+        1 => TimedTextUnit::MpegFrames,
+        2 => TimedTextUnit::Milliseconds,
+        _ => return invalid_data(b"SYLT"),
+    };
+
+    let content_type = match reader.read_byte()? {
+        1 => TimedTextContentType::Lyrics,
+        2 => TimedTextContentType::TextTranscription,
+        3 => TimedTextContentType::MovementName,
+        4 => TimedTextContentType::Events,
+        5 => TimedTextContentType::Chord,
+        6 => TimedTextContentType::Trivia,
+        7 => TimedTextContentType::WebpageUrls,
+        8 => TimedTextContentType::ImageUrls,
+        _ => TimedTextContentType::Other,
+    };
+
+    // Content descriptor.
+    let desc = scan_text(reader, encoding, reader.bytes_available() as usize)?;
+    let description = Some(desc.into_owned()).filter(|desc| !desc.is_empty());
+
+    // The remainder of the frame is a sequence of null-terminated text and 32-bit timestamp pairs.
+    let mut lines = Vec::new();
+
+    while reader.bytes_available() > 0 {
+        let text = scan_text(reader, encoding, reader.bytes_available() as usize)?.into_owned();
+
+        if reader.bytes_available() < 4 {
+            break;
+        }
+
+        let ts = u64::from(reader.read_be_u32()?);
+
+        lines.push(TimedTextLine { ts, text });
+    }
+
+    // The lines should be in chronological order, but don't rely on it.
+    lines.sort_by_key(|line| line.ts);
+
+    Ok(FrameResult::TimedText(TimedText { content_type, language, description, unit, lines }))
+}
+
 /// Reads a `PCNT` (total file play count) frame.
 fn read_pcnt_frame(
     reader: &mut BufReader<'_>,
@@ -911,6 +1084,548 @@ fn read_apic_frame(
     Ok(FrameResult::Visual(visual))
 }
 
+/// Reads a `CHAP` (chapter) frame.
+fn read_chap_frame(
+    reader: &mut BufReader<'_>,
+    _: Option<StandardTagKey>,
+    _: &str,
+) -> Result<FrameResult> {
+    // The element identifier is a null-terminated ISO-8859-1 string.
+    let id = scan_text(reader, Encoding::Iso8859_1, reader.bytes_available() as usize)?;
+
+    if id.is_empty() {
+        return invalid_data(b"CHAP");
+    }
+
+    let id = id.into_owned();
+
+    let start_ms = reader.read_be_u32()?;
+    let end_ms = reader.read_be_u32()?;
+
+    // A byte offset of 0xffffffff indicates that the offset is not set.
+    let start_byte = Some(reader.read_be_u32()?).filter(|&offset| offset != u32::MAX);
+    let end_byte = Some(reader.read_be_u32()?).filter(|&offset| offset != u32::MAX);
+
+    let sub_frames = Box::from(reader.read_buf_bytes_available_ref());
+
+    Ok(FrameResult::Chapter(ChapterFrame {
+        id,
+        start_ms,
+        end_ms,
+        start_byte,
+        end_byte,
+        sub_frames,
+    }))
+}
+
+/// Reads a `CTOC` (table of contents) frame.
+fn read_ctoc_frame(
+    reader: &mut BufReader<'_>,
+    _: Option<StandardTagKey>,
+    _: &str,
+) -> Result<FrameResult> {
+    // The element identifier is a null-terminated ISO-8859-1 string.
+    let id = scan_text(reader, Encoding::Iso8859_1, reader.bytes_available() as usize)?;
+
+    if id.is_empty() {
+        return invalid_data(b"CTOC");
+    }
+
+    let id = id.into_owned();
+
+    let flags = reader.read_u8()?;
+    let n_items = reader.read_u8()?;
+
+    // The child element identifiers are also null-terminated ISO-8859-1 strings.
+    let mut items = Vec::with_capacity(usize::from(n_items));
+
+    for _ in 0..n_items {
+        let item = scan_text(reader, Encoding::Iso8859_1, reader.bytes_available() as usize)?;
+        items.push(item.into_owned());
+    }
+
+    let sub_frames = Box::from(reader.read_buf_bytes_available_ref());
+
+    Ok(FrameResult::TableOfContents(TocFrame {
+        id,
+        is_top_level: flags & 0x2 != 0,
+        is_ordered: flags & 0x1 != 0,
+        items,
+        sub_frames,
+    }))
+}
+
+/// Reads a `UFID` (unique file identifier) frame.
+fn read_ufid_frame(
+    reader: &mut BufReader<'_>,
+    _: Option<StandardTagKey>,
+    id: &str,
+) -> Result<FrameResult> {
+    // The owner identifier is a null-terminated ISO-8859-1 string.
+    let owner = scan_text(reader, Encoding::Iso8859_1, reader.bytes_available() as usize)?;
+
+    if owner.is_empty() {
+        return invalid_data(id.as_bytes());
+    }
+
+    // MusicBrainz stores the recording identifier in an UFID frame.
+    let std_key = match owner.as_ref() {
+        "http://musicbrainz.org" => Some(StandardTagKey::MusicBrainzRecordingId),
+        _ => None,
+    };
+
+    let key = format!("{}:{}", id, owner);
+
+    // The identifier is up-to 64 bytes of binary data, but is usually printable text.
+    let ident = reader.read_buf_bytes_available_ref();
+
+    let value = if ident.iter().all(|&b| (0x20..0x7f).contains(&b)) {
+        Value::from(as_ascii_str(ident))
+    }
+    else {
+        Value::from(ident)
+    };
+
+    Ok(FrameResult::Tag(Tag::new(std_key, &key, value)))
+}
+
+/// Reads a `USER` (terms of use) frame.
+fn read_user_frame(
+    reader: &mut BufReader<'_>,
+    std_key: Option<StandardTagKey>,
+    id: &str,
+) -> Result<FrameResult> {
+    // The first byte of the frame is the encoding of the text.
+    let encoding = match Encoding::parse(reader.read_byte()?) {
+        Some(encoding) => encoding,
+        _ => return decode_error("id3v2: invalid text encoding"),
+    };
+
+    // The next three bytes are the language. Like comments, invalid language codes are skipped.
+    let lang = reader.read_triple_bytes()?;
+
+    let key = if validate_lang_code(lang) {
+        format!("{}!{}", id, as_ascii_str(&lang))
+    }
+    else {
+        id.to_string()
+    };
+
+    let text = scan_text(reader, encoding, reader.bytes_available() as usize)?;
+
+    Ok(FrameResult::Tag(Tag::new(std_key, &key, Value::from(text))))
+}
+
+/// Reads a `OWNE` (ownership) frame.
+fn read_owne_frame(
+    reader: &mut BufReader<'_>,
+    _: Option<StandardTagKey>,
+    id: &str,
+) -> Result<FrameResult> {
+    // The first byte of the frame is the encoding of the seller's name.
+    let encoding = match Encoding::parse(reader.read_byte()?) {
+        Some(encoding) => encoding,
+        _ => return decode_error("id3v2: invalid text encoding"),
+    };
+
+    // The price paid is an ISO-4217 currency code followed by the amount, e.g., "USD9.99".
+    let price = scan_text(reader, Encoding::Iso8859_1, reader.bytes_available() as usize)?;
+    let price_tag = Tag::new(None, &format!("{}:PRICE", id), Value::from(price));
+
+    // The date of purchase is always 8 characters in the form YYYYMMDD.
+    let date = reader.read_buf_bytes_ref(8)?;
+    let date_tag = Tag::new(
+        Some(StandardTagKey::PurchaseDate),
+        &format!("{}:DATE", id),
+        Value::from(decode_text(Encoding::Iso8859_1, date)),
+    );
+
+    let seller = scan_text(reader, encoding, reader.bytes_available() as usize)?;
+    let seller_tag = Tag::new(Some(StandardTagKey::Owner), id, Value::from(seller));
+
+    Ok(FrameResult::MultipleTags(vec![price_tag, date_tag, seller_tag]))
+}
+
+/// Reads a `COMR` (commercial) frame.
+fn read_comr_frame(
+    reader: &mut BufReader<'_>,
+    _: Option<StandardTagKey>,
+    id: &str,
+) -> Result<FrameResult> {
+    // The first byte of the frame is the encoding of the seller's name and description.
+    let encoding = match Encoding::parse(reader.read_byte()?) {
+        Some(encoding) => encoding,
+        _ => return decode_error("id3v2: invalid text encoding"),
+    };
+
+    let mut tags = Vec::new();
+
+    // One or more prices, separated by a '/', each being a ISO-4217 currency code followed by
+    // the amount.
+    let price = scan_text(reader, Encoding::Iso8859_1, reader.bytes_available() as usize)?;
+    tags.push(Tag::new(None, &format!("{}:PRICE", id), Value::from(price)));
+
+    // The date the price is valid until is always 8 characters in the form YYYYMMDD.
+    let valid_until = reader.read_buf_bytes_ref(8)?;
+    tags.push(Tag::new(
+        None,
+        &format!("{}:VALID_UNTIL", id),
+        Value::from(decode_text(Encoding::Iso8859_1, valid_until)),
+    ));
+
+    let url = scan_text(reader, Encoding::Iso8859_1, reader.bytes_available() as usize)?;
+    tags.push(Tag::new(
+        Some(StandardTagKey::UrlPurchase),
+        &format!("{}:URL", id),
+        Value::from(url),
+    ));
+
+    // How the audio is delivered, e.g., as a file over the internet, or on a CD.
+    let received_as = reader.read_u8()?;
+    tags.push(Tag::new(None, &format!("{}:RECEIVED_AS", id), Value::from(received_as)));
+
+    let seller = scan_text(reader, encoding, reader.bytes_available() as usize)?;
+    tags.push(Tag::new(None, &format!("{}:SELLER", id), Value::from(seller)));
+
+    let desc = scan_text(reader, encoding, reader.bytes_available() as usize)?;
+    tags.push(Tag::new(None, &format!("{}:DESCRIPTION", id), Value::from(desc)));
+
+    // The seller's logo is optional, and if present, is preceeded by its media type.
+    if reader.bytes_available() > 0 {
+        let media_type = scan_text(reader, Encoding::Iso8859_1, reader.bytes_available() as usize)?;
+        tags.push(Tag::new(None, &format!("{}:LOGO_TYPE", id), Value::from(media_type)));
+
+        let logo = reader.read_buf_bytes_available_ref();
+        tags.push(Tag::new(None, &format!("{}:LOGO", id), Value::from(logo)));
+    }
+
+    Ok(FrameResult::MultipleTags(tags))
+}
+
+/// Reads a `LINK` (linked information) frame.
+fn read_link_frame(
+    reader: &mut BufReader<'_>,
+    _: Option<StandardTagKey>,
+    id: &str,
+) -> Result<FrameResult> {
+    // The identifier of the linked frame is 4 characters in ID3v2.4, but only 3 characters in
+    // earlier versions. Since the URL that follows is not a valid frame identifier character, the
+    // length can be detected.
+    let buf = reader.read_buf_bytes_available_ref();
+
+    let id_len = match buf.get(3) {
+        Some(b) if b.is_ascii_uppercase() || b.is_ascii_digit() => 4,
+        _ => 3,
+    };
+
+    if buf.len() < id_len || !validate_frame_id(&buf[..id_len]) {
+        return invalid_data(id.as_bytes());
+    }
+
+    let key = format!("{}:{}", id, as_ascii_str(&buf[..id_len]));
+
+    // The URL of the file containing the linked frame. Additional identifying data may follow,
+    // but is ignored.
+    let mut rest = BufReader::new(&buf[id_len..]);
+    let url = scan_text(&mut rest, Encoding::Iso8859_1, buf.len() - id_len)?;
+
+    Ok(FrameResult::Tag(Tag::new(None, &key, Value::from(url))))
+}
+
+/// Reads a `POSS` (position synchronisation) frame.
+fn read_poss_frame(
+    reader: &mut BufReader<'_>,
+    _: Option<StandardTagKey>,
+    id: &str,
+) -> Result<FrameResult> {
+    let key = match reader.read_byte()? {
+        1 => format!("{}:FRAMES", id),
+        2 => format!("{}:MS", id),
+        _ => return invalid_data(id.as_bytes()),
+    };
+
+    // The position is a big-endian integer of any length, but only up-to 64 bits are supported.
+    let len = reader.bytes_available() as usize;
+
+    if len == 0 || len > 8 {
+        return invalid_data(id.as_bytes());
+    }
+
+    let mut buf = [0u8; 8];
+    reader.read_buf_exact(&mut buf[8 - len..])?;
+
+    Ok(FrameResult::Tag(Tag::new(None, &key, Value::from(u64::from_be_bytes(buf)))))
+}
+
+/// Reads a `RBUF` (recommended buffer size) frame.
+fn read_rbuf_frame(
+    reader: &mut BufReader<'_>,
+    _: Option<StandardTagKey>,
+    id: &str,
+) -> Result<FrameResult> {
+    let size = reader.read_be_u24()?;
+    let flags = reader.read_u8()?;
+
+    let mut tags = vec![
+        Tag::new(None, &format!("{}:SIZE", id), Value::from(size)),
+        Tag::new(None, &format!("{}:EMBEDDED_INFO", id), Value::from(flags & 0x1 != 0)),
+    ];
+
+    // The offset to the next tag is optional.
+    if reader.bytes_available() >= 4 {
+        let offset = reader.read_be_u32()?;
+        tags.push(Tag::new(None, &format!("{}:OFFSET", id), Value::from(offset)));
+    }
+
+    Ok(FrameResult::MultipleTags(tags))
+}
+
+/// Reads a `SEEK` frame.
+fn read_seek_frame(
+    reader: &mut BufReader<'_>,
+    _: Option<StandardTagKey>,
+    id: &str,
+) -> Result<FrameResult> {
+    // The offset from the end of this tag to the next tag.
+    let offset = reader.read_be_u32()?;
+
+    Ok(FrameResult::Tag(Tag::new(None, id, Value::from(offset))))
+}
+
+/// Reads a `RVA2` (relative volume adjustment) frame.
+fn read_rva2_frame(
+    reader: &mut BufReader<'_>,
+    _: Option<StandardTagKey>,
+    id: &str,
+) -> Result<FrameResult> {
+    // The identification string describes the situation the adjustment should be used in. By
+    // convention, "track" and "album" are equivalent to ReplayGain.
+    let ident = scan_text(reader, Encoding::Iso8859_1, reader.bytes_available() as usize)?;
+
+    let (gain_key, peak_key) = match ident.to_ascii_lowercase().as_str() {
+        "track" => {
+            (Some(StandardTagKey::ReplayGainTrackGain), Some(StandardTagKey::ReplayGainTrackPeak))
+        }
+        "album" => {
+            (Some(StandardTagKey::ReplayGainAlbumGain), Some(StandardTagKey::ReplayGainAlbumPeak))
+        }
+        _ => (None, None),
+    };
+
+    let base_key = format!("{}:{}", id, ident);
+
+    let mut tags = Vec::new();
+
+    // The remainder of the frame is a sequence of adjustments, one per channel.
+    while reader.bytes_available() >= 4 {
+        let channel = reader.read_u8()?;
+
+        // The volume adjustment is a signed fixed-point number of decibels with 9 fractional bits.
+        let gain = f64::from(reader.read_be_u16()? as i16) / 512.0;
+
+        // The peak volume is an unsigned integer of the given number of bits, where full-scale is
+        // 2^(bits - 1).
+        let peak_bits = reader.read_u8()?;
+        let peak_len = (usize::from(peak_bits) + 7) / 8;
+
+        if peak_len > reader.bytes_available() as usize {
+            return invalid_data(id.as_bytes());
+        }
+
+        let peak_buf = reader.read_buf_bytes_ref(peak_len)?;
+
+        let peak = if peak_bits > 0 {
+            let peak = peak_buf.iter().fold(0.0, |acc, &b| 256.0 * acc + f64::from(b));
+            Some(peak / f64::powi(2.0, i32::from(peak_bits) - 1))
+        }
+        else {
+            None
+        };
+
+        // Only the master volume channel has a standard key. Other channels are keyed by the
+        // channel name.
+        let (key, gain_key, peak_key) = match channel {
+            1 => (base_key.clone(), gain_key, peak_key),
+            _ => (format!("{}:{}", base_key, rva2_channel_name(channel)), None, None),
+        };
+
+        tags.push(Tag::new(gain_key, &key, Value::from(format!("{:.2} dB", gain))));
+
+        if let Some(peak) = peak {
+            tags.push(Tag::new(
+                peak_key,
+                &format!("{}:PEAK", key),
+                Value::from(format!("{:.6}", peak)),
+            ));
+        }
+    }
+
+    Ok(FrameResult::MultipleTags(tags))
+}
+
+/// Gets the name of a `RVA2` channel type.
+fn rva2_channel_name(channel: u8) -> &'static str {
+    match channel {
+        1 => "MASTER",
+        2 => "FRONT_RIGHT",
+        3 => "FRONT_LEFT",
+        4 => "BACK_RIGHT",
+        5 => "BACK_LEFT",
+        6 => "FRONT_CENTRE",
+        7 => "BACK_CENTRE",
+        8 => "SUBWOOFER",
+        _ => "OTHER",
+    }
+}
+
+/// Reads a `GEOB` (general encapsulated object) frame.
+fn read_geob_frame(
+    reader: &mut BufReader<'_>,
+    _: Option<StandardTagKey>,
+    _: &str,
+) -> Result<FrameResult> {
+    // The first byte of the frame is the encoding of the file name and description.
+    let encoding = match Encoding::parse(reader.read_byte()?) {
+        Some(encoding) => encoding,
+        _ => return decode_error("id3v2: invalid text encoding"),
+    };
+
+    // ASCII media (MIME) type.
+    let media_type =
+        scan_text(reader, Encoding::Iso8859_1, reader.bytes_available() as usize)?.into_owned();
+
+    let file_name = scan_text(reader, encoding, reader.bytes_available() as usize)?.into_owned();
+    let desc = scan_text(reader, encoding, reader.bytes_available() as usize)?.into_owned();
+
+    // The remainder of the frame is the encapsulated object.
+    let data = Box::from(reader.read_buf_bytes_available_ref());
+
+    Ok(FrameResult::Attachment(Attachment {
+        media_type,
+        file_name: Some(file_name).filter(|name| !name.is_empty()),
+        description: Some(desc).filter(|desc| !desc.is_empty()),
+        data,
+    }))
+}
+
+/// Reads a `ETCO` (event timing codes) frame.
+fn read_etco_frame(
+    reader: &mut BufReader<'_>,
+    _: Option<StandardTagKey>,
+    id: &str,
+) -> Result<FrameResult> {
+    let unit = match reader.read_byte()? {
+        1 => TimedTextUnit::MpegFrames,
+        2 => TimedTextUnit::Milliseconds,
+        _ => return invalid_data(id.as_bytes()),
+    };
+
+    // The remainder of the frame is a sequence of event type and 32-bit timestamp pairs.
+    let mut events = Vec::new();
+
+    while reader.bytes_available() >= 5 {
+        let kind = reader.read_u8()?;
+        let ts = reader.read_be_u32()?;
+
+        // Padding events have no meaning.
+        if kind != 0 {
+            events.push(TimingEvent { kind, ts });
+        }
+    }
+
+    // The events should be in chronological order, but don't rely on it.
+    events.sort_by_key(|event| event.ts);
+
+    Ok(FrameResult::EventTiming(EventTimingCodes { unit, events }))
+}
+
+/// Reads a `MLLT` (MPEG location lookup table) frame.
+fn read_mllt_frame(
+    reader: &mut BufReader<'_>,
+    _: Option<StandardTagKey>,
+    id: &str,
+) -> Result<FrameResult> {
+    let frames_between_refs = reader.read_be_u16()?;
+    let bytes_between_refs = reader.read_be_u24()?;
+    let ms_between_refs = reader.read_be_u24()?;
+
+    let bits_for_bytes = u32::from(reader.read_u8()?);
+    let bits_for_ms = u32::from(reader.read_u8()?);
+
+    if frames_between_refs == 0 || bits_for_bytes > 32 || bits_for_ms > 32 {
+        return invalid_data(id.as_bytes());
+    }
+
+    // The remainder of the frame is a packed sequence of deviations from the nominal number of
+    // bytes and milliseconds between each reference point.
+    let buf = reader.read_buf_bytes_available_ref();
+
+    let entry_bits = u64::from(bits_for_bytes + bits_for_ms);
+
+    if entry_bits == 0 {
+        return invalid_data(id.as_bytes());
+    }
+
+    let n_refs = 8 * buf.len() as u64 / entry_bits;
+
+    let mut bs = BitReaderLtr::new(buf);
+
+    let mut refs = Vec::with_capacity(n_refs as usize);
+    let mut last = MpegLocationRef { frame: 0, byte_offset: 0, ms: 0 };
+
+    for _ in 0..n_refs {
+        let byte_dev = bs.read_bits_leq32(bits_for_bytes)?;
+        let ms_dev = bs.read_bits_leq32(bits_for_ms)?;
+
+        last = MpegLocationRef {
+            frame: last.frame + u64::from(frames_between_refs),
+            byte_offset: last.byte_offset + u64::from(bytes_between_refs) + u64::from(byte_dev),
+            ms: last.ms + u64::from(ms_between_refs) + u64::from(ms_dev),
+        };
+
+        refs.push(last);
+    }
+
+    Ok(FrameResult::MpegLocations(MpegLocationTable {
+        frames_between_refs,
+        bytes_between_refs,
+        ms_between_refs,
+        refs,
+    }))
+}
+
+/// Reads a frame that begins with an owner identifier followed by binary data, such as `AENC`
+/// (audio encryption), `ENCR` (encryption method registration), or `GRID` (group identification
+/// registration).
+fn read_owner_data_frame(
+    reader: &mut BufReader<'_>,
+    _: Option<StandardTagKey>,
+    id: &str,
+) -> Result<FrameResult> {
+    // Scan for a ISO-8859-1 owner identifier.
+    let owner = scan_text(reader, Encoding::Iso8859_1, reader.bytes_available() as usize)?;
+
+    let ident = format!("{}:{}", id, owner);
+
+    // The remainder of the frame is binary data.
+    let data = Box::from(reader.read_buf_bytes_available_ref());
+
+    Ok(FrameResult::VendorData(VendorData { ident, data }))
+}
+
+/// Reads a frame that has no analogue in Symphonia, such as `ASPI` (audio seek point index),
+/// `EQU2` (equalisation), `RVRB` (reverb), `SIGN` (signature), or `SYTC` (synchronised tempo
+/// codes), as opaque binary data.
+fn read_binary_frame(
+    reader: &mut BufReader<'_>,
+    _: Option<StandardTagKey>,
+    id: &str,
+) -> Result<FrameResult> {
+    let data = Box::from(reader.read_buf_bytes_available_ref());
+
+    Ok(FrameResult::VendorData(VendorData { ident: id.to_string(), data }))
+}
+
 /// Enumeration of valid encodings for text fields in ID3v2 tags
 #[derive(Copy, Clone, Debug)]
 enum Encoding {
@@ -1002,3 +1717,200 @@ fn decode_text(encoding: Encoding, data: &[u8]) -> Cow<'_, str> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_read_sylt_frame() {
+        let mut body = b"\x00eng\x02\x01Desc\x00".to_vec();
+        body.extend_from_slice(b"Second\x00\x00\x00\x07\xd0");
+        body.extend_from_slice(b"First\x00\x00\x00\x03\xe8");
+
+        let timed_text = match read_sylt_frame(&mut BufReader::new(&body), None, "SYLT").unwrap() {
+            FrameResult::TimedText(timed_text) => timed_text,
+            _ => panic!("expected timed text"),
+        };
+
+        assert_eq!(timed_text.content_type, TimedTextContentType::Lyrics);
+        assert_eq!(timed_text.unit, TimedTextUnit::Milliseconds);
+        assert_eq!(timed_text.language.as_deref(), Some("eng"));
+        assert_eq!(timed_text.description.as_deref(), Some("Desc"));
+
+        let lines: Vec<(u64, &str)> =
+            timed_text.lines.iter().map(|line| (line.ts, line.text.as_str())).collect();
+
+        assert_eq!(lines, [(1000, "First"), (2000, "Second")]);
+    }
+
+    #[test]
+    fn verify_read_comm_itunes_frame() {
+        let body = b"\x00engiTunNORM\x00 000003E8 000003E8";
+
+        let tag = match read_comm_uslt_frame(
+            &mut BufReader::new(body),
+            Some(StandardTagKey::Comment),
+            "COMM",
+        )
+        .unwrap()
+        {
+            FrameResult::Tag(tag) => tag,
+            _ => panic!("expected a tag"),
+        };
+
+        assert_eq!(tag.std_key, None);
+        assert_eq!(tag.key, "COMM!eng:iTunNORM");
+
+        let gain = symphonia_core::meta::GainInfo::from_tags(&[tag]).unwrap();
+
+        assert_eq!(gain.track_gain, Some(0.0));
+    }
+
+    #[test]
+    fn verify_read_rva2_geob_frames() {
+        // Master volume -6.5 dB with a 16-bit peak of 0.5, and the subwoofer at +1 dB.
+        let body = b"track\x00\x01\xf3\x00\x10\x40\x00\x08\x02\x00\x00";
+
+        let tags = match read_rva2_frame(&mut BufReader::new(body), None, "RVA2").unwrap() {
+            FrameResult::MultipleTags(tags) => tags,
+            _ => panic!("expected tags"),
+        };
+
+        let tags: Vec<_> =
+            tags.iter().map(|tag| (tag.std_key, tag.key.as_str(), tag.value.to_string())).collect();
+
+        assert_eq!(
+            tags,
+            [
+                (Some(StandardTagKey::ReplayGainTrackGain), "RVA2:track", "-6.50 dB".to_string()),
+                (
+                    Some(StandardTagKey::ReplayGainTrackPeak),
+                    "RVA2:track:PEAK",
+                    "0.500000".to_string()
+                ),
+                (None, "RVA2:track:SUBWOOFER", "1.00 dB".to_string()),
+            ]
+        );
+
+        let body = b"\x00text/plain\x00notes.txt\x00\x00Hello";
+
+        let attachment = match read_geob_frame(&mut BufReader::new(body), None, "GEOB").unwrap() {
+            FrameResult::Attachment(attachment) => attachment,
+            _ => panic!("expected an attachment"),
+        };
+
+        assert_eq!(attachment.media_type, "text/plain");
+        assert_eq!(attachment.file_name.as_deref(), Some("notes.txt"));
+        assert_eq!(attachment.description, None);
+        assert_eq!(&attachment.data[..], b"Hello");
+    }
+
+    #[test]
+    fn verify_read_etco_mllt_frames() {
+        let body = b"\x02\x03\x00\x00\x07\xd0\x00\x00\x00\x00\x01\x02\x00\x00\x03\xe8";
+
+        let events = match read_etco_frame(&mut BufReader::new(body), None, "ETCO").unwrap() {
+            FrameResult::EventTiming(events) => events,
+            _ => panic!("expected event timing codes"),
+        };
+
+        assert_eq!(events.unit, TimedTextUnit::Milliseconds);
+        assert_eq!(
+            events.events,
+            [TimingEvent { kind: 0x02, ts: 1000 }, TimingEvent { kind: 0x03, ts: 2000 }]
+        );
+        assert_eq!(events.events[0].description(), "intro start");
+
+        // 10 frames, 4000 bytes, and 261 ms between references, with 4-bit deviations.
+        let body = b"\x00\x0a\x00\x0f\xa0\x00\x01\x05\x04\x04\x21\x30";
+
+        let table = match read_mllt_frame(&mut BufReader::new(body), None, "MLLT").unwrap() {
+            FrameResult::MpegLocations(table) => table,
+            _ => panic!("expected a mpeg location table"),
+        };
+
+        assert_eq!(
+            table.refs,
+            [
+                MpegLocationRef { frame: 10, byte_offset: 4002, ms: 262 },
+                MpegLocationRef { frame: 20, byte_offset: 8005, ms: 523 },
+            ]
+        );
+
+        assert_eq!(table.find_ref(9), None);
+        assert_eq!(table.find_ref(25).map(|r| r.frame), Some(20));
+    }
+
+    #[test]
+    fn verify_read_malformed_etco_frame() {
+        // The timestamp unit is invalid.
+        let body = b"\x03\x02\x00\x00\x03\xe8";
+
+        assert!(matches!(
+            read_etco_frame(&mut BufReader::new(body), None, "ETCO").unwrap(),
+            FrameResult::InvalidData(_)
+        ));
+
+        // A truncated event is ignored.
+        let body = b"\x01\x02\x00\x00\x00\x0a\x03\x00\x00";
+
+        let events = match read_etco_frame(&mut BufReader::new(body), None, "ETCO").unwrap() {
+            FrameResult::EventTiming(events) => events,
+            _ => panic!("expected event timing codes"),
+        };
+
+        assert_eq!(events.unit, TimedTextUnit::MpegFrames);
+        assert_eq!(events.events, [TimingEvent { kind: 0x02, ts: 10 }]);
+    }
+
+    #[test]
+    fn verify_read_unaligned_mllt_frame() {
+        let read_table =
+            |body: &[u8]| match read_mllt_frame(&mut BufReader::new(body), None, "MLLT") {
+                Ok(FrameResult::MpegLocations(table)) => Some(table.refs),
+                Ok(FrameResult::InvalidData(_)) => None,
+                _ => panic!("expected a mpeg location table"),
+            };
+
+        // 1 frame, 417 bytes, and 26 ms between references, with 5-bit byte deviations and 6-bit
+        // millisecond deviations. The 3 references are followed by 7 bits of padding which must not
+        // be read as another reference.
+        let body = b"\x00\x01\x00\x01\xa1\x00\x00\x1a\x05\x06\xff\xe1\x0a\x10\xff";
+
+        assert_eq!(
+            read_table(body).unwrap(),
+            [
+                MpegLocationRef { frame: 1, byte_offset: 448, ms: 89 },
+                MpegLocationRef { frame: 2, byte_offset: 866, ms: 117 },
+                MpegLocationRef { frame: 3, byte_offset: 1299, ms: 176 },
+            ]
+        );
+
+        // 2 frames, 1000 bytes, and 50 ms between references, with no byte deviations and 3-bit
+        // millisecond deviations.
+        let body = b"\x00\x02\x00\x03\xe8\x00\x00\x32\x00\x03\xa3\xa2";
+
+        let refs: Vec<(u64, u64, u64)> =
+            read_table(body).unwrap().iter().map(|r| (r.frame, r.byte_offset, r.ms)).collect();
+
+        assert_eq!(
+            refs,
+            [(2, 1000, 55), (4, 2000, 105), (6, 3000, 162), (8, 4000, 214), (10, 5000, 265)]
+        );
+
+        // 32-bit deviations.
+        let body = b"\x00\x01\x00\x00\x00\x00\x00\x00\x20\x20\xff\xff\xff\xff\x00\x00\x00\x01";
+
+        assert_eq!(
+            read_table(body).unwrap(),
+            [MpegLocationRef { frame: 1, byte_offset: 0xffff_ffff, ms: 1 }]
+        );
+
+        // Deviations wider than 32 bits, no deviations, or no frames between references are
+        // invalid.
+        assert!(read_table(b"\x00\x01\x00\x00\x00\x00\x00\x00\x21\x00\xff").is_none());
+        assert!(read_table(b"\x00\x01\x00\x00\x00\x00\x00\x00\x00\x00\xff").is_none());
+        assert!(read_table(b"\x00\x00\x00\x00\x00\x00\x00\x00\x04\x04\xff").is_none());
+    }
+}
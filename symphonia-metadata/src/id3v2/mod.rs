@@ -10,17 +10,32 @@
 use symphonia_core::errors::{decode_error, unsupported_error, Result};
 use symphonia_core::io::*;
 use symphonia_core::meta::{MetadataBuilder, MetadataOptions, MetadataReader, MetadataRevision};
+use symphonia_core::meta::{Tag, VendorData, Visual};
 use symphonia_core::probe::{Descriptor, Instantiate, QueryDescriptor};
 use symphonia_core::support_metadata;
 
 use log::{info, trace, warn};
 
+mod chapters;
 mod frames;
+mod timing;
 mod unsync;
+mod writer;
+
+pub use chapters::{Chapter, Chapters, TableOfContents};
+pub use timing::{EventTimingCodes, MpegLocationRef, MpegLocationTable, TimingEvent};
+pub use writer::Id3v2WriteOptions;
+pub use writer::{
+    read_id3v2_tag_len, update_id3v2_file, write_id3v2_tag, write_id3v2_tag_in_place,
+};
 
 use frames::*;
 use unsync::{read_syncsafe_leq32, UnsyncStream};
 
+/// The prefix of the vendor data identifier used to carry a frame that describes the stream. The
+/// prefix is followed by the major version of the tag, a colon, and the frame identifier.
+const STREAM_FRAME_IDENT_PREFIX: &str = "ID3v2.";
+
 #[derive(Debug)]
 #[allow(clippy::enum_variant_names)]
 enum TagSizeRestriction {
@@ -276,6 +291,7 @@ fn read_id3v2_body<B: ReadBytes + FiniteStream>(
     reader: &mut B,
     header: &Header,
     metadata: &mut MetadataBuilder,
+    extras: &mut Id3v2Extras,
 ) -> Result<()> {
     // If there is an extended header, read and parse it based on the major version of the tag.
     if header.has_extended_header {
@@ -287,15 +303,26 @@ fn read_id3v2_body<B: ReadBytes + FiniteStream>(
         trace!("{:#?}", &extended);
     }
 
-    let min_frame_size = match header.major_version {
+    read_id3v2_frames(reader, header.major_version, metadata, extras)
+}
+
+/// Reads frames until the end of the stream, or padding, is reached.
+fn read_id3v2_frames<B: ReadBytes + FiniteStream>(
+    reader: &mut B,
+    major_version: u8,
+    metadata: &mut MetadataBuilder,
+    extras: &mut Id3v2Extras,
+) -> Result<()> {
+    let min_frame_size = match major_version {
         2 => 6,
         3 | 4 => 10,
         _ => unreachable!(),
     };
 
-    loop {
+    // Read frames until there is not enough bytes available in the ID3v2 tag for another frame.
+    while reader.bytes_available() >= min_frame_size {
         // Read frames based on the major version of the tag.
-        let frame = match header.major_version {
+        let frame = match major_version {
             2 => read_id3v2p2_frame(reader),
             3 => read_id3v2p3_frame(reader),
             4 => read_id3v2p4_frame(reader),
@@ -305,40 +332,188 @@ fn read_id3v2_body<B: ReadBytes + FiniteStream>(
         match frame {
             // The padding has been reached, don't parse any further.
             FrameResult::Padding => break,
-            // A frame was parsed into a tag, add it to the tag collection.
-            FrameResult::Tag(tag) => {
+            frame => add_frame(frame, major_version, metadata, extras)?,
+        }
+    }
+
+    Ok(())
+}
+
+/// Adds a parsed frame to the metadata or extras of the tag.
+fn add_frame(
+    frame: FrameResult,
+    major_version: u8,
+    metadata: &mut MetadataBuilder,
+    extras: &mut Id3v2Extras,
+) -> Result<()> {
+    match frame {
+        // Padding is handled by the caller.
+        FrameResult::Padding => (),
+        // A frame was parsed into a tag, add it to the tag collection.
+        FrameResult::Tag(tag) => {
+            metadata.add_tag(tag);
+        }
+        // A frame was parsed into multiple tags, add them all to the tag collection.
+        FrameResult::MultipleTags(multi_tags) => {
+            for tag in multi_tags {
                 metadata.add_tag(tag);
             }
-            // A frame was parsed into multiple tags, add them all to the tag collection.
-            FrameResult::MultipleTags(multi_tags) => {
-                for tag in multi_tags {
-                    metadata.add_tag(tag);
-                }
-            }
-            // A frame was parsed into a visual, add it to the visual collection.
-            FrameResult::Visual(visual) => {
-                metadata.add_visual(visual);
-            }
-            // An unknown frame was encountered.
-            FrameResult::UnsupportedFrame(ref id) => {
-                info!("unsupported frame {}", id);
-            }
-            // The frame contained invalid data.
-            FrameResult::InvalidData(ref id) => {
-                warn!("invalid data for {} frame", id);
-            }
         }
-
-        // Read frames until there is not enough bytes available in the ID3v2 tag for another frame.
-        if reader.bytes_available() < min_frame_size {
-            break;
+        // A frame was parsed into a visual, add it to the visual collection.
+        FrameResult::Visual(visual) => {
+            metadata.add_visual(visual);
+        }
+        // A frame was parsed into timed text, add it to the timed text collection.
+        FrameResult::TimedText(timed_text) => {
+            metadata.add_timed_text(timed_text);
+        }
+        // A chapter was parsed, read its sub-frames and add it to the chapter collection.
+        FrameResult::Chapter(chap) => {
+            let (tags, visuals) = read_sub_frames(&chap.sub_frames, major_version)?;
+
+            extras.chapters.chapters.push(Chapter {
+                id: chap.id,
+                start_ms: chap.start_ms,
+                end_ms: chap.end_ms,
+                start_byte: chap.start_byte,
+                end_byte: chap.end_byte,
+                tags,
+                visuals,
+            });
+        }
+        // A table of contents was parsed, read its sub-frames and add it to the collection.
+        FrameResult::TableOfContents(toc) => {
+            let (tags, visuals) = read_sub_frames(&toc.sub_frames, major_version)?;
+
+            extras.chapters.tables.push(TableOfContents {
+                id: toc.id,
+                is_top_level: toc.is_top_level,
+                is_ordered: toc.is_ordered,
+                items: toc.items,
+                tags,
+                visuals,
+            });
+        }
+        // A frame was parsed into an attachment, add it to the attachment collection.
+        FrameResult::Attachment(attachment) => {
+            metadata.add_attachment(attachment);
+        }
+        // A frame was parsed into vendor data, add it to the vendor data collection.
+        FrameResult::VendorData(vendor_data) => {
+            metadata.add_vendor_data(vendor_data);
+        }
+        // Event timing codes were parsed. Only one such frame is allowed per tag.
+        FrameResult::EventTiming(events) => {
+            extras.events = Some(events);
+        }
+        // A MPEG location lookup table was parsed. Only one such frame is allowed per tag.
+        FrameResult::MpegLocations(table) => {
+            extras.mpeg_locations = Some(table);
+        }
+        // A frame describing the stream was parsed. Keep its body so that the frame may be passed
+        // on to the format reader, and add the parsed frame.
+        FrameResult::StreamFrame(stream) => {
+            extras.stream_frames.push(VendorData {
+                ident: format!("{}{}:{}", STREAM_FRAME_IDENT_PREFIX, major_version, stream.id),
+                data: stream.body,
+            });
+
+            add_frame(*stream.frame, major_version, metadata, extras)?;
+        }
+        // An unknown frame was encountered.
+        FrameResult::UnsupportedFrame(ref id) => {
+            info!("unsupported frame {}", id);
+        }
+        // The frame contained invalid data.
+        FrameResult::InvalidData(ref id) => {
+            warn!("invalid data for {} frame", id);
         }
     }
 
     Ok(())
 }
 
+/// Reads the sub-frames embedded in a chapter or table of contents frame.
+fn read_sub_frames(buf: &[u8], major_version: u8) -> Result<(Vec<Tag>, Vec<Visual>)> {
+    let mut builder = MetadataBuilder::new();
+
+    // Chapters may not be nested within chapters, so any found are discarded. The same applies to
+    // the other frames that describe the entire stream.
+    read_id3v2_frames(
+        &mut BufReader::new(buf),
+        major_version,
+        &mut builder,
+        &mut Id3v2Extras::default(),
+    )?;
+
+    let revision = builder.metadata();
+
+    Ok((revision.tags().to_vec(), revision.visuals().to_vec()))
+}
+
+/// The information of an ID3v2 tag that describes the stream the tag is attached to, rather than
+/// being metadata.
+#[derive(Clone, Debug, Default)]
+pub struct Id3v2Extras {
+    /// The chapters and tables of contents.
+    pub chapters: Chapters,
+    /// The event timing codes, if present.
+    pub events: Option<EventTimingCodes>,
+    /// The MPEG location lookup table, if present.
+    pub mpeg_locations: Option<MpegLocationTable>,
+    /// The bodies of the frames the extras were parsed from.
+    stream_frames: Vec<VendorData>,
+}
+
+impl Id3v2Extras {
+    /// Gets the extras of an ID3v2 tag read by `Id3v2Reader`.
+    ///
+    /// When an ID3v2 tag is read by the probe, the frames describing the stream are carried in the
+    /// vendor data of the metadata revision so that the format reader may use them without reading
+    /// the tag again.
+    pub fn from_revision(revision: &MetadataRevision) -> Id3v2Extras {
+        let mut extras = Id3v2Extras::default();
+
+        // Any metadata found in the frames is already in the revision.
+        let mut builder = MetadataBuilder::new();
+
+        for vendor_data in revision.vendor_data() {
+            let (major_version, id) = match vendor_data
+                .ident
+                .strip_prefix(STREAM_FRAME_IDENT_PREFIX)
+                .and_then(|ident| ident.split_once(':'))
+                .and_then(|(major, id)| Some((major.parse::<u8>().ok()?, id)))
+            {
+                Some(ident) => ident,
+                _ => continue,
+            };
+
+            let result = read_stream_frame(id, &vendor_data.data, major_version)
+                .and_then(|frame| add_frame(frame, major_version, &mut builder, &mut extras));
+
+            if let Err(err) = result {
+                warn!("invalid {} frame: {}", id, err);
+            }
+        }
+
+        extras
+    }
+}
+
+/// Reads an ID3v2 tag.
 pub fn read_id3v2<B: ReadBytes>(reader: &mut B, metadata: &mut MetadataBuilder) -> Result<()> {
+    read_id3v2_with_extras(reader, metadata)?;
+    Ok(())
+}
+
+/// Reads an ID3v2 tag, and returns the chapters, event timing codes, and MPEG location lookup
+/// table of the tag.
+pub fn read_id3v2_with_extras<B: ReadBytes>(
+    reader: &mut B,
+    metadata: &mut MetadataBuilder,
+) -> Result<Id3v2Extras> {
+    let mut extras = Id3v2Extras::default();
+
     // Read the (sorta) version agnostic tag header.
     let header = read_id3v2_header(reader)?;
 
@@ -347,7 +522,7 @@ pub fn read_id3v2<B: ReadBytes>(reader: &mut B, metadata: &mut MetadataBuilder)
     let mut scoped = if header.unsynchronisation && header.major_version < 4 {
         let mut unsync = UnsyncStream::new(ScopedStream::new(reader, u64::from(header.size)));
 
-        read_id3v2_body(&mut unsync, &header, metadata)?;
+        read_id3v2_body(&mut unsync, &header, metadata, &mut extras)?;
 
         unsync.into_inner()
     }
@@ -356,7 +531,7 @@ pub fn read_id3v2<B: ReadBytes>(reader: &mut B, metadata: &mut MetadataBuilder)
     else {
         let mut scoped = ScopedStream::new(reader, u64::from(header.size));
 
-        read_id3v2_body(&mut scoped, &header, metadata)?;
+        read_id3v2_body(&mut scoped, &header, metadata, &mut extras)?;
 
         scoped
     };
@@ -364,7 +539,7 @@ pub fn read_id3v2<B: ReadBytes>(reader: &mut B, metadata: &mut MetadataBuilder)
     // Ignore any remaining data in the tag.
     scoped.ignore()?;
 
-    Ok(())
+    Ok(extras)
 }
 
 pub mod util {
@@ -395,6 +570,33 @@ pub mod util {
             _ => None,
         }
     }
+
+    /// Get the APIC picture type identifier for a `StandardVisualKey`. A visual without a usage
+    /// has the "other" picture type.
+    pub fn visual_key_to_apic_picture_type(usage: Option<StandardVisualKey>) -> u8 {
+        match usage {
+            Some(StandardVisualKey::FileIcon) => 0x01,
+            Some(StandardVisualKey::OtherIcon) => 0x02,
+            Some(StandardVisualKey::FrontCover) => 0x03,
+            Some(StandardVisualKey::BackCover) => 0x04,
+            Some(StandardVisualKey::Leaflet) => 0x05,
+            Some(StandardVisualKey::Media) => 0x06,
+            Some(StandardVisualKey::LeadArtistPerformerSoloist) => 0x07,
+            Some(StandardVisualKey::ArtistPerformer) => 0x08,
+            Some(StandardVisualKey::Conductor) => 0x09,
+            Some(StandardVisualKey::BandOrchestra) => 0x0a,
+            Some(StandardVisualKey::Composer) => 0x0b,
+            Some(StandardVisualKey::Lyricist) => 0x0c,
+            Some(StandardVisualKey::RecordingLocation) => 0x0d,
+            Some(StandardVisualKey::RecordingSession) => 0x0e,
+            Some(StandardVisualKey::Performance) => 0x0f,
+            Some(StandardVisualKey::ScreenCapture) => 0x10,
+            Some(StandardVisualKey::Illustration) => 0x12,
+            Some(StandardVisualKey::BandArtistLogo) => 0x13,
+            Some(StandardVisualKey::PublisherStudioLogo) => 0x14,
+            None => 0x00,
+        }
+    }
 }
 
 pub struct Id3v2Reader;
@@ -416,7 +618,14 @@ impl MetadataReader for Id3v2Reader {
 
     fn read_all(&mut self, reader: &mut MediaSourceStream) -> Result<MetadataRevision> {
         let mut builder = MetadataBuilder::new();
-        read_id3v2(reader, &mut builder)?;
+
+        let extras = read_id3v2_with_extras(reader, &mut builder)?;
+
+        // Keep the frames describing the stream so that the format reader may use them.
+        for vendor_data in extras.stream_frames {
+            builder.add_vendor_data(vendor_data);
+        }
+
         Ok(builder.metadata())
     }
 }
@@ -0,0 +1,239 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::formats::{Cue, CuePoint};
+use symphonia_core::meta::{Tag, TimedTextUnit, Value};
+
+/// An event read from an ID3v2 `ETCO` (event timing codes) frame.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TimingEvent {
+    /// The event type code.
+    pub kind: u8,
+    /// The time of the event, in units given by the parent `EventTimingCodes`.
+    pub ts: u32,
+}
+
+impl TimingEvent {
+    /// Gets a description of the event type.
+    pub fn description(&self) -> String {
+        let desc = match self.kind {
+            0x00 => "padding",
+            0x01 => "end of initial silence",
+            0x02 => "intro start",
+            0x03 => "main part start",
+            0x04 => "outro start",
+            0x05 => "outro end",
+            0x06 => "verse start",
+            0x07 => "refrain start",
+            0x08 => "interlude start",
+            0x09 => "theme start",
+            0x0a => "variation start",
+            0x0b => "key change",
+            0x0c => "time change",
+            0x0d => "momentary unwanted noise",
+            0x0e => "sustained noise",
+            0x0f => "sustained noise end",
+            0x10 => "intro end",
+            0x11 => "main part end",
+            0x12 => "verse end",
+            0x13 => "refrain end",
+            0x14 => "theme end",
+            0x15 => "profanity",
+            0x16 => "profanity end",
+            0xe0..=0xef => return format!("synch {:x}", self.kind & 0xf),
+            0xfd => "audio end",
+            0xfe => "audio file ends",
+            _ => return format!("reserved ({:#04x})", self.kind),
+        };
+
+        desc.to_string()
+    }
+}
+
+/// The events read from an ID3v2 `ETCO` (event timing codes) frame.
+#[derive(Clone, Debug)]
+pub struct EventTimingCodes {
+    /// The unit of the event timestamps.
+    pub unit: TimedTextUnit,
+    /// The events, ordered by timestamp.
+    pub events: Vec<TimingEvent>,
+}
+
+impl EventTimingCodes {
+    /// Adds the events to a list of `Cue`s with timestamps in units of samples at the given sample
+    /// rate. A MPEG audio frame is `samples_per_mpeg_frame` samples long.
+    ///
+    /// If there are no cues, one cue is added per event. Otherwise, each event is added as a
+    /// `CuePoint` of the last cue that starts at, or before, the event. Events preceeding the first
+    /// cue are dropped.
+    pub fn add_to_cues(&self, cues: &mut Vec<Cue>, sample_rate: u32, samples_per_mpeg_frame: u64) {
+        let add_cues = cues.is_empty();
+
+        for event in &self.events {
+            let ts = match self.unit {
+                TimedTextUnit::Milliseconds => u64::from(event.ts) * u64::from(sample_rate) / 1000,
+                TimedTextUnit::MpegFrames => u64::from(event.ts) * samples_per_mpeg_frame,
+            };
+
+            let tags = vec![Tag::new(None, "ETCO", Value::from(event.description()))];
+
+            if add_cues {
+                cues.push(Cue {
+                    index: cues.len() as u32,
+                    start_ts: ts,
+                    tags,
+                    visuals: Vec::new(),
+                    points: Vec::new(),
+                });
+            }
+            else if let Some(cue) = cues.iter_mut().filter(|cue| cue.start_ts <= ts).last() {
+                cue.points.push(CuePoint { start_offset_ts: ts - cue.start_ts, tags });
+            }
+        }
+    }
+}
+
+/// A reference point of an ID3v2 `MLLT` (MPEG location lookup table) frame.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct MpegLocationRef {
+    /// The number of MPEG audio frames preceeding the reference point.
+    pub frame: u64,
+    /// The byte offset of the reference point relative to the first MPEG audio frame.
+    pub byte_offset: u64,
+    /// The time of the reference point in milliseconds.
+    pub ms: u64,
+}
+
+/// A seek table read from an ID3v2 `MLLT` (MPEG location lookup table) frame.
+#[derive(Clone, Debug)]
+pub struct MpegLocationTable {
+    /// The number of MPEG audio frames between reference points.
+    pub frames_between_refs: u16,
+    /// The nominal number of bytes between reference points.
+    pub bytes_between_refs: u32,
+    /// The nominal number of milliseconds between reference points.
+    pub ms_between_refs: u32,
+    /// The reference points, excluding the implicit reference point at the start of the stream.
+    pub refs: Vec<MpegLocationRef>,
+}
+
+impl MpegLocationTable {
+    /// Finds the last reference point that preceeds, or is, the MPEG audio frame `frame`.
+    pub fn find_ref(&self, frame: u64) -> Option<&MpegLocationRef> {
+        match self.refs.partition_point(|r| r.frame <= frame) {
+            0 => None,
+            idx => Some(&self.refs[idx - 1]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cue(index: u32, start_ts: u64) -> Cue {
+        Cue { index, start_ts, tags: Vec::new(), visuals: Vec::new(), points: Vec::new() }
+    }
+
+    #[test]
+    fn verify_timing_event_description() {
+        assert_eq!(TimingEvent { kind: 0x01, ts: 0 }.description(), "end of initial silence");
+        assert_eq!(TimingEvent { kind: 0x16, ts: 0 }.description(), "profanity end");
+        assert_eq!(TimingEvent { kind: 0xe0, ts: 0 }.description(), "synch 0");
+        assert_eq!(TimingEvent { kind: 0xeb, ts: 0 }.description(), "synch b");
+        assert_eq!(TimingEvent { kind: 0xfd, ts: 0 }.description(), "audio end");
+        assert_eq!(TimingEvent { kind: 0x17, ts: 0 }.description(), "reserved (0x17)");
+        assert_eq!(TimingEvent { kind: 0xff, ts: 0 }.description(), "reserved (0xff)");
+    }
+
+    #[test]
+    fn verify_add_events_as_cues() {
+        let etco = EventTimingCodes {
+            unit: TimedTextUnit::Milliseconds,
+            events: vec![TimingEvent { kind: 0x02, ts: 0 }, TimingEvent { kind: 0x03, ts: 1500 }],
+        };
+
+        // If there are no cues, one cue is added per event.
+        let mut cues = Vec::new();
+        etco.add_to_cues(&mut cues, 44100, 1152);
+
+        let cues: Vec<(u32, u64, String)> = cues
+            .iter()
+            .map(|cue| (cue.index, cue.start_ts, cue.tags[0].value.to_string()))
+            .collect();
+
+        assert_eq!(
+            cues,
+            [(0, 0, "intro start".to_string()), (1, 66150, "main part start".to_string())]
+        );
+
+        // MPEG frame timestamps are converted using the number of samples per frame.
+        let etco = EventTimingCodes {
+            unit: TimedTextUnit::MpegFrames,
+            events: vec![TimingEvent { kind: 0x04, ts: 10 }],
+        };
+
+        let mut cues = Vec::new();
+        etco.add_to_cues(&mut cues, 44100, 1152);
+
+        assert_eq!(cues[0].start_ts, 11520);
+    }
+
+    #[test]
+    fn verify_add_events_as_cue_points() {
+        let etco = EventTimingCodes {
+            unit: TimedTextUnit::Milliseconds,
+            events: vec![
+                TimingEvent { kind: 0x01, ts: 500 },
+                TimingEvent { kind: 0x06, ts: 1000 },
+                TimingEvent { kind: 0x07, ts: 2500 },
+                TimingEvent { kind: 0x0d, ts: 3000 },
+            ],
+        };
+
+        // Chapters start at 1 s and 2 s at a sample rate of 1 kHz.
+        let mut cues = vec![cue(0, 1000), cue(1, 2000)];
+        etco.add_to_cues(&mut cues, 1000, 1152);
+
+        // The event preceeding the first cue is dropped, and the others are added to the last cue
+        // starting at, or before, the event.
+        let points = |cue: &Cue| -> Vec<(u64, String)> {
+            cue.points
+                .iter()
+                .map(|point| (point.start_offset_ts, point.tags[0].value.to_string()))
+                .collect()
+        };
+
+        assert_eq!(cues.len(), 2);
+        assert_eq!(points(&cues[0]), [(0, "verse start".to_string())]);
+        assert_eq!(
+            points(&cues[1]),
+            [(500, "refrain start".to_string()), (1000, "momentary unwanted noise".to_string())]
+        );
+    }
+
+    #[test]
+    fn verify_mpeg_location_table_find_ref() {
+        let table = MpegLocationTable {
+            frames_between_refs: 10,
+            bytes_between_refs: 4000,
+            ms_between_refs: 261,
+            refs: (1..=3)
+                .map(|i| MpegLocationRef { frame: 10 * i, byte_offset: 4000 * i, ms: 261 * i })
+                .collect(),
+        };
+
+        assert_eq!(table.find_ref(0), None);
+        assert_eq!(table.find_ref(9), None);
+        assert_eq!(table.find_ref(10).map(|r| r.frame), Some(10));
+        assert_eq!(table.find_ref(29).map(|r| r.frame), Some(20));
+        assert_eq!(table.find_ref(1000).map(|r| r.frame), Some(30));
+
+        let empty = MpegLocationTable { refs: Vec::new(), ..table };
+        assert_eq!(empty.find_ref(1000), None);
+    }
+}
@@ -28,6 +28,38 @@ pub fn read_syncsafe_leq32<B: ReadBytes>(reader: &mut B, bit_width: u8) -> Resul
     Ok(result)
 }
 
+/// Encodes a 28-bit value as a 32-bit synchsafe integer.
+pub fn encode_syncsafe_u28(value: u32) -> [u8; 4] {
+    debug_assert!(value < 1 << 28);
+
+    [
+        (value >> 21) as u8 & 0x7f,
+        (value >> 14) as u8 & 0x7f,
+        (value >> 7) as u8 & 0x7f,
+        value as u8 & 0x7f,
+    ]
+}
+
+/// Applies the unsynchronisation scheme to a buffer. A 0x00 byte is inserted after every 0xff byte
+/// that is followed by a byte that could form a false synchronisation (0xe0 or greater), or by a
+/// 0x00 byte. A 0x00 byte is also appended if the last byte of the buffer is 0xff.
+pub fn encode_unsynchronisation(buf: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(buf.len());
+
+    for (i, &byte) in buf.iter().enumerate() {
+        out.push(byte);
+
+        if byte == 0xff {
+            match buf.get(i + 1) {
+                Some(&next) if next < 0xe0 && next != 0x00 => (),
+                _ => out.push(0x00),
+            }
+        }
+    }
+
+    out
+}
+
 pub fn decode_unsynchronisation(buf: &mut [u8]) -> &mut [u8] {
     let len = buf.len();
     let mut src = 0;
@@ -186,9 +218,19 @@ impl<B: ReadBytes + FiniteStream> ReadBytes for UnsyncStream<B> {
 
 #[cfg(test)]
 mod tests {
-    use super::read_syncsafe_leq32;
+    use super::{decode_unsynchronisation, encode_unsynchronisation, read_syncsafe_leq32};
     use symphonia_core::io::BufReader;
 
+    #[test]
+    fn verify_unsynchronisation_round_trip() {
+        let data = [0xff, 0xfb, 0x10, 0xff, 0x00, 0xff, 0x7f, 0xff];
+
+        let mut encoded = encode_unsynchronisation(&data);
+        assert_eq!(encoded, [0xff, 0x00, 0xfb, 0x10, 0xff, 0x00, 0x00, 0xff, 0x7f, 0xff, 0x00]);
+
+        assert_eq!(decode_unsynchronisation(&mut encoded), &data);
+    }
+
     #[test]
     fn verify_read_syncsafe_leq32() {
         let mut stream = BufReader::new(&[3, 4, 80, 1, 15]);
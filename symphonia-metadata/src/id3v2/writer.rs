@@ -0,0 +1,912 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::fs::OpenOptions;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use symphonia_core::errors::{unsupported_error, Result};
+use symphonia_core::meta::{Attachment, MetadataRevision, StandardTagKey, Tag, TimedText};
+use symphonia_core::meta::{TimedTextContentType, TimedTextUnit, Value, Visual};
+
+use log::warn;
+
+use crate::file::replace_file;
+
+use super::frames::{is_supported_frame_id, txxx_description};
+use super::unsync::{encode_syncsafe_u28, encode_unsynchronisation};
+use super::util::visual_key_to_apic_picture_type;
+
+/// The length of an ID3v2 tag header, and footer, in bytes.
+const ID3V2_HEADER_LEN: u64 = 10;
+
+/// The maximum size of an ID3v2 tag, or frame, since sizes are 28-bit synchsafe integers.
+const ID3V2_MAX_SIZE: usize = (1 << 28) - 1;
+
+/// Tag header flag: unsynchronisation is applied.
+const ID3V2_FLAG_UNSYNC: u8 = 0x80;
+
+/// Tag header flag: the tag has a footer (ID3v2.4 only).
+const ID3V2_FLAG_FOOTER: u8 = 0x10;
+
+/// ID3v2.4 frame flag: unsynchronisation is applied to the frame.
+const FRAME_FLAG_UNSYNC: u16 = 0x0002;
+
+/// Options for writing an ID3v2 tag.
+#[derive(Copy, Clone, Debug)]
+pub struct Id3v2WriteOptions {
+    /// The major version of the tag. Either 3 for ID3v2.3, or 4 for ID3v2.4.
+    pub major_version: u8,
+    /// If true, unsynchronisation is applied so that the tag can never contain a false MPEG frame
+    /// sync. Only very old players require this.
+    pub unsynchronisation: bool,
+    /// The number of bytes of padding to add after the frames so that the tag may later be
+    /// updated in-place.
+    pub padding: usize,
+}
+
+impl Default for Id3v2WriteOptions {
+    fn default() -> Self {
+        Id3v2WriteOptions { major_version: 4, unsynchronisation: false, padding: 1024 }
+    }
+}
+
+/// The frame a tag is written to.
+enum FrameTarget {
+    /// A text information frame.
+    Text([u8; 4]),
+    /// A user defined text information frame with a description.
+    Txxx(String),
+    /// A URL link frame.
+    Url([u8; 4]),
+    /// A user defined URL link frame with a description.
+    Wxxx(String),
+    /// A `COMM` (comment), `USLT` (unsychronised lyrics), or `USER` (terms of use) frame with an
+    /// optional language code.
+    LangText([u8; 4], Option<[u8; 3]>),
+    /// A frame with an owner identifier, such as `PRIV` or `UFID`, or `POPM` with an e-mail.
+    Owner([u8; 4], String),
+    /// A `PCNT` (play counter) frame.
+    PlayCounter,
+    /// A frame that contains a binary value as-is, such as `MCDI`.
+    Binary([u8; 4]),
+}
+
+/// Gets the frame identifier for a standard tag key, if there is a dedicated frame for it.
+fn std_key_frame_id(std_key: StandardTagKey, major_version: u8) -> Option<&'static [u8; 4]> {
+    let is_v4 = major_version >= 4;
+
+    let id = match std_key {
+        StandardTagKey::Album => b"TALB",
+        StandardTagKey::AlbumArtist => b"TPE2",
+        StandardTagKey::Artist => b"TPE1",
+        StandardTagKey::Bpm => b"TBPM",
+        StandardTagKey::Comment => b"COMM",
+        StandardTagKey::Composer => b"TCOM",
+        StandardTagKey::Conductor => b"TPE3",
+        StandardTagKey::ContentGroup => b"TIT1",
+        StandardTagKey::Copyright => b"TCOP",
+        StandardTagKey::Date if is_v4 => b"TDRC",
+        StandardTagKey::Date => b"TYER",
+        StandardTagKey::DiscNumber => b"TPOS",
+        StandardTagKey::EncodedBy => b"TENC",
+        StandardTagKey::Encoder => b"TSSE",
+        StandardTagKey::EncodingDate if is_v4 => b"TDEN",
+        StandardTagKey::Genre => b"TCON",
+        StandardTagKey::IdentIsrc => b"TSRC",
+        StandardTagKey::IdentPodcast => b"TGID",
+        StandardTagKey::Label => b"TPUB",
+        StandardTagKey::Language => b"TLAN",
+        StandardTagKey::Lyrics => b"USLT",
+        StandardTagKey::MediaFormat => b"TMED",
+        StandardTagKey::Mood if is_v4 => b"TMOO",
+        StandardTagKey::MovementName => b"MVNM",
+        StandardTagKey::MovementNumber => b"MVIN",
+        StandardTagKey::OriginalAlbum => b"TOAL",
+        StandardTagKey::OriginalArtist => b"TOPE",
+        StandardTagKey::OriginalDate if is_v4 => b"TDOR",
+        StandardTagKey::OriginalDate => b"TORY",
+        StandardTagKey::OriginalFile => b"TOFN",
+        StandardTagKey::OriginalWriter => b"TOLY",
+        StandardTagKey::PodcastCategory => b"TCAT",
+        StandardTagKey::PodcastDescription => b"TDES",
+        StandardTagKey::PodcastKeywords => b"TKWD",
+        StandardTagKey::Rating => b"POPM",
+        StandardTagKey::ReleaseDate if is_v4 => b"TDRL",
+        StandardTagKey::Remixer => b"TPE4",
+        StandardTagKey::SortAlbum => b"TSOA",
+        StandardTagKey::SortAlbumArtist => b"TSO2",
+        StandardTagKey::SortArtist => b"TSOP",
+        StandardTagKey::SortComposer => b"TSOC",
+        StandardTagKey::SortTrackTitle => b"TSOT",
+        StandardTagKey::TaggingDate if is_v4 => b"TDTG",
+        StandardTagKey::TrackNumber => b"TRCK",
+        StandardTagKey::TrackSubtitle => b"TIT3",
+        StandardTagKey::TrackTitle => b"TIT2",
+        StandardTagKey::Url => b"WXXX",
+        StandardTagKey::UrlArtist => b"WOAR",
+        StandardTagKey::UrlCopyright => b"WCOP",
+        StandardTagKey::UrlInternetRadio => b"WORS",
+        StandardTagKey::UrlLabel => b"WPUB",
+        StandardTagKey::UrlOfficial => b"WOAF",
+        StandardTagKey::UrlPayment => b"WPAY",
+        StandardTagKey::UrlPurchase => b"WCOM",
+        StandardTagKey::UrlSource => b"WOAS",
+        StandardTagKey::Writer => b"TEXT",
+        _ => return None,
+    };
+
+    Some(id)
+}
+
+/// Converts a frame identifier string into an array if it is a valid 4 character identifier.
+fn to_frame_id(id: &str) -> Option<[u8; 4]> {
+    match id.as_bytes() {
+        &[a, b, c, d] => Some([a, b, c, d]),
+        _ => None,
+    }
+}
+
+/// Splits a key of the form "<ID><sep><suffix>" into the frame identifier and suffix.
+fn split_key(key: &str, sep: char) -> (&str, Option<&str>) {
+    match key.find(sep) {
+        Some(pos) => (&key[..pos], Some(&key[pos + 1..])),
+        None => (key, None),
+    }
+}
+
+/// Gets the frame a tag should be written to.
+fn frame_target(tag: &Tag, major_version: u8) -> FrameTarget {
+    // Tags read from an ID3v2 tag use the frame identifier, possibly with a language code or a
+    // description, as the key.
+    let (id, lang) = split_key(&tag.key, '!');
+    let (id, desc) = if lang.is_some() { (id, None) } else { split_key(id, ':') };
+
+    let lang = lang.and_then(|lang| match lang.as_bytes() {
+        &[a, b, c] if lang.bytes().all(|b| b.is_ascii_lowercase()) => Some([a, b, c]),
+        _ => None,
+    });
+
+    // Prefer the frame for the standard key, if there is one.
+    let std_id = tag.std_key.and_then(|std_key| std_key_frame_id(std_key, major_version));
+
+    let id = match std_id {
+        Some(std_id) => Some(*std_id),
+        None => to_frame_id(id).filter(|id| is_supported_frame_id(id)),
+    };
+
+    let id = match id {
+        Some(id) => id,
+        None => return txxx_target(tag),
+    };
+
+    match &id {
+        b"TXXX" => FrameTarget::Txxx(desc.unwrap_or("").to_string()),
+        b"WXXX" => FrameTarget::Wxxx(desc.unwrap_or("").to_string()),
+        b"COMM" | b"USLT" | b"USER" => FrameTarget::LangText(id, lang),
+        b"PRIV" | b"UFID" | b"POPM" => FrameTarget::Owner(id, desc.unwrap_or("").to_string()),
+        b"PCNT" => FrameTarget::PlayCounter,
+        b"MCDI" => FrameTarget::Binary(id),
+        [b'T', ..] => FrameTarget::Text(id),
+        [b'W', ..] => FrameTarget::Url(id),
+        _ => txxx_target(tag),
+    }
+}
+
+/// Gets the user defined text frame a tag should be written to. Standard keys use the
+/// conventional description if there is one, otherwise the key of the tag is used.
+fn txxx_target(tag: &Tag) -> FrameTarget {
+    let desc = match (tag.std_key.and_then(txxx_description), tag.std_key) {
+        (Some(desc), _) => desc.to_string(),
+        (None, Some(std_key)) if tag.key.is_empty() => format!("{:?}", std_key).to_uppercase(),
+        _ => tag.key.clone(),
+    };
+
+    FrameTarget::Txxx(desc)
+}
+
+/// Converts a tag value to text. Binary values cannot be converted.
+fn value_to_text(value: &Value) -> Option<String> {
+    match value {
+        Value::Binary(_) => None,
+        Value::Boolean(value) => Some(if *value { "1" } else { "0" }.to_string()),
+        Value::Flag => Some("1".to_string()),
+        value => Some(value.to_string()),
+    }
+}
+
+/// The text encodings used by the writer.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum TextEncoding {
+    Iso8859_1 = 0,
+    Utf16Bom = 1,
+    Utf8 = 3,
+}
+
+impl TextEncoding {
+    /// Chooses the most compact encoding that can represent all the strings.
+    ///
+    /// ISO-8859-1 is only chosen if all characters are printable since the reader discards control
+    /// characters in ISO-8859-1 text. Otherwise, UTF-8 is used for ID3v2.4, and UTF-16 for
+    /// ID3v2.3, which does not support UTF-8.
+    fn choose(texts: &[&str], major_version: u8) -> TextEncoding {
+        let is_latin1 = |text: &str| text.chars().all(|c| ('\u{20}'..='\u{ff}').contains(&c));
+
+        if texts.iter().all(|text| is_latin1(text)) {
+            TextEncoding::Iso8859_1
+        }
+        else if major_version >= 4 {
+            TextEncoding::Utf8
+        }
+        else {
+            TextEncoding::Utf16Bom
+        }
+    }
+
+    /// Appends encoded text to `buf`, optionally followed by a null terminator.
+    fn encode(self, buf: &mut Vec<u8>, text: &str, terminate: bool) {
+        match self {
+            TextEncoding::Iso8859_1 => {
+                buf.extend(text.chars().map(|c| c as u32 as u8));
+
+                if terminate {
+                    buf.push(0);
+                }
+            }
+            TextEncoding::Utf8 => {
+                buf.extend_from_slice(text.as_bytes());
+
+                if terminate {
+                    buf.push(0);
+                }
+            }
+            TextEncoding::Utf16Bom => {
+                // Little-endian with a byte-order-mark.
+                buf.extend_from_slice(&[0xff, 0xfe]);
+
+                for unit in text.encode_utf16() {
+                    buf.extend_from_slice(&unit.to_le_bytes());
+                }
+
+                if terminate {
+                    buf.extend_from_slice(&[0, 0]);
+                }
+            }
+        }
+    }
+}
+
+/// Appends ISO-8859-1 text, followed by a null terminator, to `buf`. Characters that cannot be
+/// represented are replaced with '?'.
+fn encode_latin1(buf: &mut Vec<u8>, text: &str, terminate: bool) {
+    buf.extend(text.chars().map(|c| {
+        if (c as u32) < 0x100 {
+            c as u32 as u8
+        }
+        else {
+            b'?'
+        }
+    }));
+
+    if terminate {
+        buf.push(0);
+    }
+}
+
+/// A frame pending serialization.
+struct PendingFrame {
+    id: [u8; 4],
+    body: Vec<u8>,
+}
+
+/// A text frame, or user defined text frame, with a list of values pending serialization.
+struct PendingText {
+    id: [u8; 4],
+    desc: Option<String>,
+    values: Vec<String>,
+}
+
+impl PendingText {
+    fn to_frame(&self, major_version: u8) -> PendingFrame {
+        // ID3v2.4 separates multiple values with a null character. ID3v2.3 has no such provision,
+        // so a '/' is conventionally used instead.
+        let text = match major_version {
+            4 => self.values.join("\0"),
+            _ => self.values.join("/"),
+        };
+
+        let desc = self.desc.as_deref().unwrap_or("");
+
+        let encoding = TextEncoding::choose(&[desc, text.as_str()], major_version);
+
+        let mut body = vec![encoding as u8];
+
+        if self.desc.is_some() {
+            encoding.encode(&mut body, desc, true);
+        }
+
+        // The null separators must be encoded in the chosen encoding, so encode each value
+        // separately.
+        for (i, value) in text.split('\0').enumerate() {
+            if i > 0 {
+                match encoding {
+                    TextEncoding::Utf16Bom => body.extend_from_slice(&[0, 0]),
+                    _ => body.push(0),
+                }
+            }
+            encoding.encode(&mut body, value, false);
+        }
+
+        PendingFrame { id: self.id, body }
+    }
+}
+
+/// Serializes a tag into a frame body, or `None` if it cannot be represented.
+fn tag_to_frame(tag: &Tag, target: FrameTarget, major_version: u8) -> Option<PendingFrame> {
+    let frame = match target {
+        FrameTarget::Url(id) => {
+            let mut body = Vec::new();
+            encode_latin1(&mut body, &value_to_text(&tag.value)?, false);
+            PendingFrame { id, body }
+        }
+        FrameTarget::Wxxx(desc) => {
+            let encoding = TextEncoding::choose(&[&desc], major_version);
+
+            let mut body = vec![encoding as u8];
+            encoding.encode(&mut body, &desc, true);
+            encode_latin1(&mut body, &value_to_text(&tag.value)?, false);
+            PendingFrame { id: *b"WXXX", body }
+        }
+        FrameTarget::LangText(id, lang) => {
+            let text = value_to_text(&tag.value)?;
+            let encoding = TextEncoding::choose(&[&text], major_version);
+
+            let mut body = vec![encoding as u8];
+            body.extend_from_slice(&lang.unwrap_or(*b"XXX"));
+
+            // The terms of use frame has no content descriptor.
+            if &id != b"USER" {
+                encoding.encode(&mut body, "", true);
+            }
+
+            encoding.encode(&mut body, &text, false);
+            PendingFrame { id, body }
+        }
+        FrameTarget::Owner(id, owner) => {
+            let mut body = Vec::new();
+            encode_latin1(&mut body, &owner, true);
+
+            match (&id, &tag.value) {
+                (b"POPM", Value::UnsignedInt(rating)) => body.push((*rating).min(255) as u8),
+                (b"POPM", _) => return None,
+                (_, Value::Binary(data)) => body.extend_from_slice(data),
+                (_, value) => body.extend_from_slice(value_to_text(value)?.as_bytes()),
+            }
+
+            PendingFrame { id, body }
+        }
+        FrameTarget::PlayCounter => {
+            let count = match tag.value {
+                Value::UnsignedInt(count) => count,
+                _ => return None,
+            };
+
+            // The counter is atleast 32 bits, but may be extended if required.
+            let body = if count <= u64::from(u32::MAX) {
+                (count as u32).to_be_bytes().to_vec()
+            }
+            else {
+                count.to_be_bytes().to_vec()
+            };
+
+            PendingFrame { id: *b"PCNT", body }
+        }
+        FrameTarget::Binary(id) => match &tag.value {
+            Value::Binary(data) => PendingFrame { id, body: data.to_vec() },
+            _ => return None,
+        },
+        // Text frames are merged before serialization.
+        FrameTarget::Text(_) | FrameTarget::Txxx(_) => unreachable!(),
+    };
+
+    Some(frame)
+}
+
+/// Serializes a `Visual` into an `APIC` frame.
+fn visual_to_frame(visual: &Visual, major_version: u8) -> PendingFrame {
+    let desc = visual
+        .tags
+        .iter()
+        .find(|tag| tag.std_key == Some(StandardTagKey::Description))
+        .map(|tag| tag.value.to_string())
+        .unwrap_or_default();
+
+    let encoding = TextEncoding::choose(&[&desc], major_version);
+
+    let mut body = vec![encoding as u8];
+    encode_latin1(&mut body, &visual.media_type, true);
+    body.push(visual_key_to_apic_picture_type(visual.usage));
+    encoding.encode(&mut body, &desc, true);
+    body.extend_from_slice(&visual.data);
+
+    PendingFrame { id: *b"APIC", body }
+}
+
+/// Serializes an `Attachment` into a `GEOB` frame.
+fn attachment_to_frame(attachment: &Attachment, major_version: u8) -> PendingFrame {
+    let file_name = attachment.file_name.as_deref().unwrap_or("");
+    let desc = attachment.description.as_deref().unwrap_or("");
+
+    let encoding = TextEncoding::choose(&[file_name, desc], major_version);
+
+    let mut body = vec![encoding as u8];
+    encode_latin1(&mut body, &attachment.media_type, true);
+    encoding.encode(&mut body, file_name, true);
+    encoding.encode(&mut body, desc, true);
+    body.extend_from_slice(&attachment.data);
+
+    PendingFrame { id: *b"GEOB", body }
+}
+
+/// Serializes `TimedText` into a `SYLT` frame.
+fn timed_text_to_frame(timed_text: &TimedText, major_version: u8) -> PendingFrame {
+    let desc = timed_text.description.as_deref().unwrap_or("");
+
+    let mut texts = vec![desc];
+    texts.extend(timed_text.lines.iter().map(|line| line.text.as_str()));
+
+    let encoding = TextEncoding::choose(&texts, major_version);
+
+    let mut body = vec![encoding as u8];
+
+    match timed_text.language.as_deref().map(str::as_bytes) {
+        Some(&[a, b, c]) => body.extend_from_slice(&[a, b, c]),
+        _ => body.extend_from_slice(b"XXX"),
+    }
+
+    body.push(match timed_text.unit {
+        TimedTextUnit::MpegFrames => 1,
+        TimedTextUnit::Milliseconds => 2,
+    });
+
+    body.push(match timed_text.content_type {
+        TimedTextContentType::Other => 0,
+        TimedTextContentType::Lyrics => 1,
+        TimedTextContentType::TextTranscription => 2,
+        TimedTextContentType::MovementName => 3,
+        TimedTextContentType::Events => 4,
+        TimedTextContentType::Chord => 5,
+        TimedTextContentType::Trivia => 6,
+        TimedTextContentType::WebpageUrls => 7,
+        TimedTextContentType::ImageUrls => 8,
+    });
+
+    encoding.encode(&mut body, desc, true);
+
+    for line in &timed_text.lines {
+        encoding.encode(&mut body, &line.text, true);
+        body.extend_from_slice(&(line.ts.min(u64::from(u32::MAX)) as u32).to_be_bytes());
+    }
+
+    PendingFrame { id: *b"SYLT", body }
+}
+
+/// Serializes all the frames of a `MetadataRevision`, applying unsynchronisation if requested.
+/// Returns the serialized frames, and if the tag-wide unsynchronisation flag should be set.
+fn write_frames(
+    revision: &MetadataRevision,
+    options: &Id3v2WriteOptions,
+) -> Result<(Vec<u8>, bool)> {
+    let major_version = options.major_version;
+
+    if major_version != 3 && major_version != 4 {
+        return unsupported_error("id3v2: only ID3v2.3 and ID3v2.4 tags may be written");
+    }
+
+    let mut texts: Vec<PendingText> = Vec::new();
+    let mut frames = Vec::new();
+
+    for tag in revision.tags() {
+        match frame_target(tag, major_version) {
+            // Text frames may contain a list of values, so all tags for the same frame are merged
+            // into a single frame.
+            FrameTarget::Text(id) => {
+                let mut value = match value_to_text(&tag.value) {
+                    Some(value) => value,
+                    None => {
+                        warn!("id3v2: skipping binary value for text frame '{}'", tag.key);
+                        continue;
+                    }
+                };
+
+                // ID3v2.3 years are always 4 characters.
+                if &id == b"TYER" || &id == b"TORY" {
+                    value.truncate(value.char_indices().nth(4).map_or(value.len(), |(i, _)| i));
+                }
+
+                match texts.iter_mut().find(|text| text.id == id && text.desc.is_none()) {
+                    Some(text) => text.values.push(value),
+                    None => texts.push(PendingText { id, desc: None, values: vec![value] }),
+                }
+            }
+            FrameTarget::Txxx(desc) => {
+                let value = match value_to_text(&tag.value) {
+                    Some(value) => value,
+                    None => {
+                        warn!("id3v2: skipping binary value for user text frame '{}'", desc);
+                        continue;
+                    }
+                };
+
+                let desc = Some(desc);
+
+                match texts.iter_mut().find(|text| &text.id == b"TXXX" && text.desc == desc) {
+                    Some(text) => text.values.push(value),
+                    None => texts.push(PendingText { id: *b"TXXX", desc, values: vec![value] }),
+                }
+            }
+            target => match tag_to_frame(tag, target, major_version) {
+                Some(frame) => frames.push(frame),
+                None => warn!("id3v2: skipping tag '{}' with an unsupported value", tag.key),
+            },
+        }
+    }
+
+    let mut all_frames: Vec<PendingFrame> =
+        texts.iter().map(|text| text.to_frame(major_version)).collect();
+
+    all_frames.extend(frames);
+
+    for timed_text in revision.timed_text() {
+        all_frames.push(timed_text_to_frame(timed_text, major_version));
+    }
+
+    // Large binary frames are placed last so that readers may find the text frames quickly.
+    for attachment in revision.attachments() {
+        all_frames.push(attachment_to_frame(attachment, major_version));
+    }
+
+    for visual in revision.visuals() {
+        all_frames.push(visual_to_frame(visual, major_version));
+    }
+
+    let mut buf = Vec::new();
+    let mut is_unsynchronised = false;
+
+    for frame in all_frames {
+        // ID3v2.4 applies unsynchronisation to each frame individually.
+        let (body, flags) = if options.unsynchronisation && major_version == 4 {
+            let body = encode_unsynchronisation(&frame.body);
+
+            if body.len() != frame.body.len() {
+                is_unsynchronised = true;
+                (body, FRAME_FLAG_UNSYNC)
+            }
+            else {
+                (body, 0)
+            }
+        }
+        else {
+            (frame.body, 0)
+        };
+
+        if body.len() > ID3V2_MAX_SIZE {
+            return unsupported_error("id3v2: frame is too large");
+        }
+
+        buf.extend_from_slice(&frame.id);
+
+        match major_version {
+            4 => buf.extend_from_slice(&encode_syncsafe_u28(body.len() as u32)),
+            _ => buf.extend_from_slice(&(body.len() as u32).to_be_bytes()),
+        }
+
+        buf.extend_from_slice(&flags.to_be_bytes());
+        buf.extend_from_slice(&body);
+    }
+
+    // ID3v2.3 applies unsynchronisation to the entire tag.
+    if options.unsynchronisation && major_version == 3 {
+        let unsync = encode_unsynchronisation(&buf);
+
+        if unsync.len() != buf.len() {
+            is_unsynchronised = true;
+            buf = unsync;
+        }
+    }
+
+    Ok((buf, is_unsynchronised))
+}
+
+/// Writes an ID3v2 tag containing `frames` padded to `size` bytes, excluding the header.
+fn write_tag<W: Write>(
+    writer: &mut W,
+    frames: &[u8],
+    size: usize,
+    is_unsynchronised: bool,
+    options: &Id3v2WriteOptions,
+) -> Result<()> {
+    debug_assert!(frames.len() <= size);
+
+    if size > ID3V2_MAX_SIZE {
+        return unsupported_error("id3v2: tag is too large");
+    }
+
+    let flags = if is_unsynchronised { ID3V2_FLAG_UNSYNC } else { 0 };
+
+    writer.write_all(b"ID3")?;
+    writer.write_all(&[options.major_version, 0, flags])?;
+    writer.write_all(&encode_syncsafe_u28(size as u32))?;
+    writer.write_all(frames)?;
+
+    // Padding must be all zeros.
+    io::copy(&mut io::repeat(0).take((size - frames.len()) as u64), writer)?;
+
+    Ok(())
+}
+
+/// Serializes a `MetadataRevision` into an ID3v2.3 or ID3v2.4 tag, followed by padding.
+///
+/// Tags with a standard key are written to the conventional frame for that key, or a `TXXX` frame
+/// with the conventional description if there is no such frame. Tags read from an ID3v2 tag are
+/// written back to the frame they were read from. All other tags are written as `TXXX` frames
+/// with their key as the description. Tags for the same text frame are merged into a single
+/// frame. Visuals are written as `APIC` frames, attachments as `GEOB` frames, and timed text as
+/// `SYLT` frames.
+pub fn write_id3v2_tag<W: Write>(
+    writer: &mut W,
+    revision: &MetadataRevision,
+    options: &Id3v2WriteOptions,
+) -> Result<()> {
+    let (frames, is_unsynchronised) = write_frames(revision, options)?;
+
+    let size = frames.len() + options.padding;
+
+    write_tag(writer, &frames, size, is_unsynchronised, options)
+}
+
+/// Gets the total length of the ID3v2 tag at the current position of the stream, including its
+/// header, padding, and footer, or `None` if there is no tag.
+///
+/// The header of the tag is consumed from the stream.
+pub fn read_id3v2_tag_len<R: Read>(reader: &mut R) -> Result<Option<u64>> {
+    let mut header = [0; ID3V2_HEADER_LEN as usize];
+
+    match reader.read_exact(&mut header) {
+        Ok(_) => (),
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(err) => return Err(err.into()),
+    }
+
+    let is_valid = &header[..3] == b"ID3"
+        && (2..=4).contains(&header[3])
+        && header[4] != 0xff
+        && header[6..].iter().all(|&b| b < 0x80);
+
+    if !is_valid {
+        return Ok(None);
+    }
+
+    let size = header[6..].iter().fold(0, |acc, &b| (acc << 7) | u64::from(b));
+
+    let footer_len =
+        if header[3] == 4 && header[5] & ID3V2_FLAG_FOOTER != 0 { ID3V2_HEADER_LEN } else { 0 };
+
+    Ok(Some(ID3V2_HEADER_LEN + size + footer_len))
+}
+
+/// Replaces the ID3v2 tag at the start of a stream with a new tag if the new tag fits within the
+/// existing tag, including its padding and footer. The new tag is padded to exactly the size of
+/// the existing tag so that no other data in the stream is moved.
+///
+/// Returns `false`, without modifying the stream, if there is no existing tag, or if the new tag
+/// does not fit.
+pub fn write_id3v2_tag_in_place<S: Read + Write + Seek>(
+    stream: &mut S,
+    revision: &MetadataRevision,
+    options: &Id3v2WriteOptions,
+) -> Result<bool> {
+    stream.seek(SeekFrom::Start(0))?;
+
+    let tag_len = match read_id3v2_tag_len(stream)? {
+        Some(tag_len) => tag_len,
+        None => return Ok(false),
+    };
+
+    let (frames, is_unsynchronised) = write_frames(revision, options)?;
+
+    // The new tag has no footer, so the existing footer becomes padding.
+    let size = (tag_len - ID3V2_HEADER_LEN) as usize;
+
+    if frames.len() > size {
+        return Ok(false);
+    }
+
+    let mut buf = Vec::with_capacity(tag_len as usize);
+    write_tag(&mut buf, &frames, size, is_unsynchronised, options)?;
+
+    stream.seek(SeekFrom::Start(0))?;
+    stream.write_all(&buf)?;
+    stream.flush()?;
+
+    Ok(true)
+}
+
+/// Updates the ID3v2 tag at the start of the file at `path`, or adds one if there is none.
+///
+/// If the new tag fits within the existing tag, the tag is rewritten in-place. Otherwise, the file
+/// is rewritten, with `options.padding` bytes of padding, to a temporary file in the same
+/// directory which then replaces the original file.
+pub fn update_id3v2_file<P: AsRef<Path>>(
+    path: P,
+    revision: &MetadataRevision,
+    options: &Id3v2WriteOptions,
+) -> Result<()> {
+    let path = path.as_ref();
+
+    let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+
+    if write_id3v2_tag_in_place(&mut file, revision, options)? {
+        return Ok(());
+    }
+
+    // Find where the audio data begins after the existing tag, if there is one.
+    file.seek(SeekFrom::Start(0))?;
+    let audio_start = read_id3v2_tag_len(&mut file)?.unwrap_or(0);
+
+    replace_file(path, |temp| {
+        write_id3v2_tag(temp, revision, options)?;
+
+        file.seek(SeekFrom::Start(audio_start))?;
+        io::copy(&mut file, temp)?;
+
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::io::Cursor;
+
+    use symphonia_core::io::BufReader;
+    use symphonia_core::meta::{MetadataBuilder, StandardVisualKey};
+
+    use super::super::read_id3v2;
+    use super::*;
+
+    fn revision() -> MetadataRevision {
+        let mut builder = MetadataBuilder::new();
+
+        builder.add_tag(Tag::new(
+            Some(StandardTagKey::TrackTitle),
+            "TITLE",
+            Value::from("Ünïcode ✓"),
+        ));
+        builder.add_tag(Tag::new(Some(StandardTagKey::Artist), "ARTIST", Value::from("A")));
+        builder.add_tag(Tag::new(Some(StandardTagKey::Artist), "ARTIST", Value::from("B")));
+        builder.add_tag(Tag::new(Some(StandardTagKey::Comment), "COMM!eng", Value::from("Hi")));
+        builder.add_tag(Tag::new(
+            Some(StandardTagKey::ReplayGainTrackGain),
+            "",
+            Value::from("-1 dB"),
+        ));
+        builder.add_tag(Tag::new(None, "CUSTOM", Value::from(42u32)));
+        builder.add_visual(Visual {
+            media_type: "image/png".to_string(),
+            dimensions: None,
+            bits_per_pixel: None,
+            color_mode: None,
+            usage: Some(StandardVisualKey::FrontCover),
+            tags: Vec::new(),
+            data: Box::new([0x89, b'P', b'N', b'G', 0xff, 0xe0, 0xff]),
+        });
+
+        builder.metadata()
+    }
+
+    fn read(buf: &[u8]) -> Vec<(Option<StandardTagKey>, String, String)> {
+        let mut builder = MetadataBuilder::new();
+        read_id3v2(&mut BufReader::new(buf), &mut builder).unwrap();
+
+        let revision = builder.metadata();
+
+        assert_eq!(&revision.visuals()[0].data[..], &[0x89, b'P', b'N', b'G', 0xff, 0xe0, 0xff]);
+        assert_eq!(revision.visuals()[0].usage, Some(StandardVisualKey::FrontCover));
+
+        revision
+            .tags()
+            .iter()
+            .map(|tag| (tag.std_key, tag.key.clone(), tag.value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn verify_write_id3v2_round_trip() {
+        for &major_version in &[3, 4] {
+            for &unsynchronisation in &[false, true] {
+                let options = Id3v2WriteOptions { major_version, unsynchronisation, padding: 16 };
+
+                let mut buf = Vec::new();
+                write_id3v2_tag(&mut buf, &revision(), &options).unwrap();
+
+                assert!(buf.ends_with(&[0; 16]));
+
+                let tags = read(&buf);
+
+                let artists = if major_version == 4 {
+                    vec!["A".to_string(), "B".to_string()]
+                }
+                else {
+                    vec!["A/B".to_string()]
+                };
+
+                let find = |std_key| -> Vec<String> {
+                    tags.iter().filter(|t| t.0 == Some(std_key)).map(|t| t.2.clone()).collect()
+                };
+
+                assert_eq!(find(StandardTagKey::TrackTitle), ["Ünïcode ✓"]);
+                assert_eq!(find(StandardTagKey::Artist), artists);
+                assert_eq!(find(StandardTagKey::Comment), ["Hi"]);
+                assert_eq!(find(StandardTagKey::ReplayGainTrackGain), ["-1 dB"]);
+                assert!(tags.contains(&(None, "TXXX:CUSTOM".to_string(), "42".to_string())));
+                assert!(tags.iter().any(|t| t.1 == "COMM!eng"));
+            }
+        }
+    }
+
+    #[test]
+    fn verify_write_id3v2_tag_in_place() {
+        let options = Id3v2WriteOptions { padding: 64, ..Default::default() };
+
+        let mut file = Vec::new();
+        write_id3v2_tag(&mut file, &revision(), &options).unwrap();
+        let tag_len = file.len();
+        file.extend_from_slice(b"audio");
+
+        // A smaller tag fits in the existing tag's padding.
+        let mut builder = MetadataBuilder::new();
+        builder.add_tag(Tag::new(Some(StandardTagKey::Album), "TALB", Value::from("Album")));
+        let smaller = builder.metadata();
+
+        let mut stream = Cursor::new(file.clone());
+        assert!(write_id3v2_tag_in_place(&mut stream, &smaller, &options).unwrap());
+
+        let file = stream.into_inner();
+        assert_eq!(file.len(), tag_len + 5);
+        assert!(file.ends_with(b"audio"));
+
+        let mut builder = MetadataBuilder::new();
+        read_id3v2(&mut BufReader::new(&file), &mut builder).unwrap();
+        assert_eq!(builder.metadata().tags()[0].value.to_string(), "Album");
+
+        // A larger tag does not fit, and the stream is left untouched.
+        let mut builder = MetadataBuilder::new();
+        builder.add_tag(Tag::new(None, "BIG", Value::from("x".repeat(4096))));
+        let larger = builder.metadata();
+
+        let mut stream = Cursor::new(file.clone());
+        assert!(!write_id3v2_tag_in_place(&mut stream, &larger, &options).unwrap());
+        assert_eq!(stream.into_inner(), file);
+
+        // Update a file on disk, which requires rewriting the file.
+        let path = std::env::temp_dir().join(format!("symphonia-id3v2-{}.mp3", std::process::id()));
+        fs::write(&path, &file).unwrap();
+
+        update_id3v2_file(&path, &larger, &options).unwrap();
+
+        let updated = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert!(updated.len() > 4096 + 64);
+        assert!(updated.ends_with(b"audio"));
+        assert_eq!(
+            read_id3v2_tag_len(&mut Cursor::new(&updated)).unwrap(),
+            Some(updated.len() as u64 - 5)
+        );
+    }
+}
@@ -60,6 +60,10 @@ lazy_static! {
         m.insert("com.apple.iTunes:originaldate", StandardTagKey::OriginalDate);
         m.insert("com.apple.iTunes:PRODUCER", StandardTagKey::Producer);
         m.insert("com.apple.iTunes:REMIXER", StandardTagKey::Remixer);
+        m.insert("com.apple.iTunes:replaygain_album_gain", StandardTagKey::ReplayGainAlbumGain);
+        m.insert("com.apple.iTunes:replaygain_album_peak", StandardTagKey::ReplayGainAlbumPeak);
+        m.insert("com.apple.iTunes:replaygain_track_gain", StandardTagKey::ReplayGainTrackGain);
+        m.insert("com.apple.iTunes:replaygain_track_peak", StandardTagKey::ReplayGainTrackPeak);
         m.insert("com.apple.iTunes:SCRIPT", StandardTagKey::Script);
         m.insert("com.apple.iTunes:SUBTITLE", StandardTagKey::TrackSubtitle);
         m
@@ -70,3 +74,8 @@ lazy_static! {
 pub fn std_key_from_tag(key: &str) -> Option<StandardTagKey> {
     ITUNES_TAG_MAP.get(key).copied()
 }
+
+/// Try to map a `StandardTagKey` to a fully qualified iTunes free-form tag name.
+pub fn tag_from_std_key(std_key: StandardTagKey) -> Option<&'static str> {
+    ITUNES_TAG_MAP.iter().find(|(_, &key)| key == std_key).map(|(&name, _)| name)
+}
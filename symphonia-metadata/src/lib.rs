@@ -14,8 +14,13 @@
 #![allow(clippy::identity_op)]
 #![allow(clippy::manual_range_contains)]
 
+pub mod ape;
+pub mod file;
 pub mod id3v1;
 pub mod id3v2;
 pub mod itunes;
+pub mod lrc;
+pub mod lyrics3;
 pub mod riff;
+pub mod trailing;
 pub mod vorbis;
@@ -0,0 +1,182 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! An LRC (timed lyrics) parser.
+//!
+//! LRC lyrics are plain text where each line is prefixed by one or more `[mm:ss.xx]` time tags.
+//! LRC lyrics are commonly stored in lyrics tags, such as the Vorbis comment `LYRICS` field, or
+//! the MP4 `©lyr` atom.
+
+use symphonia_core::meta::{TimedText, TimedTextContentType, TimedTextLine, TimedTextUnit};
+
+/// Parses a `mm:ss`, `mm:ss.xx`, or `mm:ss:xx` time tag into milliseconds.
+fn parse_time(tag: &str) -> Option<u64> {
+    let (min, rest) = tag.split_at(tag.find(':')?);
+    let rest = &rest[1..];
+
+    // The fractional part may be separated by either a period or a colon.
+    let (sec, frac) = match rest.find(|c| c == '.' || c == ':') {
+        Some(pos) => (&rest[..pos], &rest[pos + 1..]),
+        None => (rest, ""),
+    };
+
+    let is_digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+
+    if !is_digits(min) || !is_digits(sec) || !(frac.is_empty() || is_digits(frac)) {
+        return None;
+    }
+
+    let min: u64 = min.parse().ok()?;
+    let sec: u64 = sec.parse().ok()?;
+
+    // The fraction is usually hundredths of a second, but may be of any precision. Only
+    // millisecond precision is retained.
+    let frac_ms = frac
+        .bytes()
+        .chain(std::iter::repeat(b'0'))
+        .take(3)
+        .fold(0, |acc, digit| 10 * acc + u64::from(digit - b'0'));
+
+    // Reject time tags that overflow rather than wrapping, or panicking.
+    min.checked_mul(60)?.checked_add(sec)?.checked_mul(1000)?.checked_add(frac_ms)
+}
+
+/// Parses LRC formatted lyrics into `TimedText`. Returns `None` if the text does not contain any
+/// time tags.
+pub fn parse_lrc(text: &str) -> Option<TimedText> {
+    let mut lines = Vec::new();
+    let mut offset: i64 = 0;
+
+    for line in text.lines() {
+        let mut rest = line.trim();
+        let mut times = Vec::new();
+
+        // Consume all the tags at the start of the line.
+        while rest.starts_with('[') {
+            let end = match rest.find(']') {
+                Some(end) => end,
+                None => break,
+            };
+
+            let tag = &rest[1..end];
+
+            if let Some(ms) = parse_time(tag) {
+                times.push(ms);
+            }
+            else if let Some(value) = tag.strip_prefix("offset:") {
+                // A positive offset shifts the lyrics to appear sooner.
+                offset = value.trim().parse().unwrap_or(0);
+            }
+
+            rest = &rest[end + 1..];
+        }
+
+        // Each time tag repeats the line at that time.
+        for ms in times {
+            lines.push(TimedTextLine { ts: ms, text: rest.trim().to_string() });
+        }
+    }
+
+    if lines.is_empty() {
+        return None;
+    }
+
+    for line in lines.iter_mut() {
+        line.ts = if offset >= 0 {
+            line.ts.saturating_sub(offset as u64)
+        }
+        else {
+            line.ts.saturating_add(offset.unsigned_abs())
+        };
+    }
+
+    // Lines with multiple time tags are out of order.
+    lines.sort_by_key(|line| line.ts);
+
+    Some(TimedText {
+        content_type: TimedTextContentType::Lyrics,
+        language: None,
+        description: None,
+        unit: TimedTextUnit::Milliseconds,
+        lines,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_parse_lrc() {
+        let lrc = "[ar:Artist]\n[offset:+500]\n[00:12.00]First\r\n[00:05.5][01:00.123]Chorus\n\n";
+
+        let timed = parse_lrc(lrc).unwrap();
+
+        let lines: Vec<(u64, &str)> =
+            timed.lines.iter().map(|line| (line.ts, line.text.as_str())).collect();
+
+        assert_eq!(lines, [(5000, "Chorus"), (11500, "First"), (59623, "Chorus")]);
+
+        assert_eq!(timed.line_at(4999), None);
+        assert_eq!(timed.line_at(11500).map(|line| line.text.as_str()), Some("First"));
+
+        assert!(parse_lrc("Plain lyrics\n[not a time]").is_none());
+    }
+
+    #[test]
+    fn verify_parse_lrc_malformed_time_tags() {
+        let lines = |lrc: &str| -> Vec<(u64, String)> {
+            parse_lrc(lrc)
+                .map(|timed| timed.lines.into_iter().map(|line| (line.ts, line.text)).collect())
+                .unwrap_or_default()
+        };
+
+        // Time tags with missing, or non-numeric, fields are ignored.
+        for tag in &[
+            "[:30.00]",
+            "[00:]",
+            "[00.30]",
+            "[0a:30]",
+            "[00:3b]",
+            "[00:30.5x]",
+            "[-1:30]",
+            "[+1:30]",
+            "[ 00:30]",
+            "[]",
+        ] {
+            assert!(parse_lrc(&format!("{}Text", tag)).is_none(), "{}", tag);
+        }
+
+        // A time tag without a closing bracket ends the tags of a line.
+        assert!(parse_lrc("[00:30.00 Text").is_none());
+        assert_eq!(lines("[00:01][00:30.00 Text"), [(1000, "[00:30.00 Text".to_string())]);
+
+        // Time tags that overflow are ignored.
+        assert!(parse_lrc("[99999999999999999999:00]Text").is_none());
+        assert!(parse_lrc(&format!("[{}:00]Text", u64::MAX / 60)).is_none());
+
+        // Malformed tags are skipped, but valid time tags on the same line are kept.
+        assert_eq!(
+            lines("[bad][00:02]Two\n[00:1x][00:01]One"),
+            [(1000, "One".to_string()), (2000, "Two".to_string())]
+        );
+
+        // The fraction may be separated by a colon, and only milliseconds are retained.
+        assert_eq!(
+            lines("[00:01:5]A\n[00:02.123456]B\n[00:03.]C"),
+            [(1000 + 500, "A".to_string()), (2000 + 123, "B".to_string()), (3000, "C".to_string())]
+        );
+
+        // A malformed offset is ignored, and offsets do not overflow the time.
+        assert_eq!(lines("[offset:abc][00:01]A"), [(1000, "A".to_string())]);
+        assert_eq!(lines("[offset:+2000][00:01]A"), [(0, "A".to_string())]);
+
+        let late = 1000 + i64::MIN.unsigned_abs();
+
+        assert_eq!(lines(&format!("[offset:{}][00:01]A", i64::MIN)), [(late, "A".to_string())]);
+    }
+}
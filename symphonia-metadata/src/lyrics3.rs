@@ -0,0 +1,256 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! A Lyrics3v2 metadata reader.
+
+use std::io::{Seek, SeekFrom};
+
+use symphonia_core::errors::{decode_error, Result};
+use symphonia_core::io::{MediaSourceStream, ReadBytes};
+use symphonia_core::meta::{MetadataBuilder, StandardTagKey, Tag, Value};
+
+use log::warn;
+
+use crate::id3v1::decode_iso8859_text;
+use crate::lrc::parse_lrc;
+
+/// The Lyrics3v2 begin marker: "LYRICSBEGIN" in ASCII.
+const LYRICS3_BEGIN_MARKER: &[u8; 11] = b"LYRICSBEGIN";
+
+/// The Lyrics3v2 end marker: "LYRICS200" in ASCII.
+const LYRICS3V2_END_MARKER: &[u8; 9] = b"LYRICS200";
+
+/// The length of the Lyrics3v2 trailer: a 6 digit size followed by the end marker.
+const LYRICS3V2_TRAILER_LEN: u64 = 15;
+
+/// The location of a Lyrics3v2 tag within a stream.
+#[derive(Copy, Clone, Debug)]
+pub struct Lyrics3Location {
+    /// The position of the "LYRICSBEGIN" marker.
+    pub pos: u64,
+    /// The size of the tag, excluding the trailer.
+    pub size: u64,
+}
+
+impl Lyrics3Location {
+    /// Gets the total size of the tag in bytes, including the trailer.
+    pub fn total_size(&self) -> u64 {
+        self.size + LYRICS3V2_TRAILER_LEN
+    }
+}
+
+/// Parses a fixed-width decimal number.
+fn parse_decimal(buf: &[u8]) -> Option<u64> {
+    if buf.is_empty() || !buf.iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+
+    Some(buf.iter().fold(0, |acc, &digit| 10 * acc + u64::from(digit - b'0')))
+}
+
+/// Checks if a Lyrics3v2 tag ends exactly at the byte position `end` of the stream. If one does,
+/// returns the location of the tag. The position of the stream is undefined afterwards.
+pub fn find_lyrics3v2_ending_at(
+    reader: &mut MediaSourceStream,
+    end: u64,
+) -> Result<Option<Lyrics3Location>> {
+    if end < LYRICS3V2_TRAILER_LEN {
+        return Ok(None);
+    }
+
+    reader.seek(SeekFrom::Start(end - LYRICS3V2_TRAILER_LEN))?;
+
+    let mut trailer = [0; LYRICS3V2_TRAILER_LEN as usize];
+    reader.read_buf_exact(&mut trailer)?;
+
+    if &trailer[6..] != LYRICS3V2_END_MARKER {
+        return Ok(None);
+    }
+
+    let size = match parse_decimal(&trailer[..6]) {
+        Some(size) if size >= LYRICS3_BEGIN_MARKER.len() as u64 => size,
+        _ => {
+            warn!("lyrics3: invalid tag size");
+            return Ok(None);
+        }
+    };
+
+    if size + LYRICS3V2_TRAILER_LEN > end {
+        warn!("lyrics3: tag size exceeds the stream length");
+        return Ok(None);
+    }
+
+    let pos = end - LYRICS3V2_TRAILER_LEN - size;
+
+    reader.seek(SeekFrom::Start(pos))?;
+
+    let mut marker = [0; 11];
+    reader.read_buf_exact(&mut marker)?;
+
+    if &marker != LYRICS3_BEGIN_MARKER {
+        warn!("lyrics3: missing begin marker");
+        return Ok(None);
+    }
+
+    Ok(Some(Lyrics3Location { pos, size }))
+}
+
+/// Reads the Lyrics3v2 tag at the given location.
+pub fn read_lyrics3v2_at(
+    reader: &mut MediaSourceStream,
+    location: &Lyrics3Location,
+    metadata: &mut MetadataBuilder,
+) -> Result<()> {
+    reader.seek(SeekFrom::Start(location.pos))?;
+    read_lyrics3v2(reader, location.size, metadata)
+}
+
+/// Reads a Lyrics3v2 tag of `size` bytes, excluding the trailer, that starts at the current
+/// position of the stream.
+pub fn read_lyrics3v2<B: ReadBytes>(
+    reader: &mut B,
+    size: u64,
+    metadata: &mut MetadataBuilder,
+) -> Result<()> {
+    let buf = reader.read_boxed_slice_exact(size as usize)?;
+
+    if !buf.starts_with(LYRICS3_BEGIN_MARKER) {
+        return decode_error("lyrics3: missing begin marker");
+    }
+
+    let mut fields = &buf[LYRICS3_BEGIN_MARKER.len()..];
+
+    // Each field consists of a 3 character identifier, a 5 digit length, and the field data.
+    while !fields.is_empty() {
+        if fields.len() < 8 {
+            return decode_error("lyrics3: truncated field");
+        }
+
+        let id = &fields[..3];
+
+        let len = match parse_decimal(&fields[3..8]) {
+            Some(len) if len as usize <= fields.len() - 8 => len as usize,
+            _ => return decode_error("lyrics3: invalid field length"),
+        };
+
+        let data = &fields[8..8 + len];
+
+        fields = &fields[8 + len..];
+
+        let std_key = match id {
+            b"LYR" => Some(StandardTagKey::Lyrics),
+            b"INF" => Some(StandardTagKey::Comment),
+            b"AUT" => Some(StandardTagKey::Writer),
+            b"EAL" => Some(StandardTagKey::Album),
+            b"EAR" => Some(StandardTagKey::Artist),
+            b"ETT" => Some(StandardTagKey::TrackTitle),
+            // The indications field, and image links, are not useful outside of Lyrics3 players.
+            b"IND" | b"IMG" => continue,
+            _ => None,
+        };
+
+        // The key must be printable ASCII.
+        if !id.iter().all(u8::is_ascii_alphanumeric) {
+            return decode_error("lyrics3: invalid field identifier");
+        }
+
+        let key = String::from_utf8_lossy(id);
+
+        // Lines are separated by a CR-LF pair.
+        let value: String =
+            data.split(|&b| b == b'\n').map(decode_iso8859_text).collect::<Vec<_>>().join("\n");
+
+        // Lyrics may contain LRC style time stamps.
+        if std_key == Some(StandardTagKey::Lyrics) {
+            if let Some(timed_text) = parse_lrc(&value) {
+                metadata.add_timed_text(timed_text);
+            }
+        }
+
+        metadata.add_tag(Tag::new(std_key, &key, Value::from(value)));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_lyrics3v2() {
+        let mut tag = Vec::new();
+        tag.extend_from_slice(b"LYRICSBEGIN");
+        tag.extend_from_slice(b"IND00003110");
+        tag.extend_from_slice(b"ETT00005Title");
+        tag.extend_from_slice(b"LYR00027[00:01]Line one\r\n[00:02]Two");
+
+        let size = tag.len();
+        tag.extend_from_slice(format!("{:06}LYRICS200", size).as_bytes());
+
+        let mut data = vec![0xff; 10];
+        data.extend_from_slice(&tag);
+
+        let len = data.len() as u64;
+
+        let mut reader =
+            MediaSourceStream::new(Box::new(std::io::Cursor::new(data)), Default::default());
+
+        let location = find_lyrics3v2_ending_at(&mut reader, len).unwrap().unwrap();
+
+        assert_eq!(location.pos, 10);
+        assert_eq!(location.total_size(), tag.len() as u64);
+
+        let mut builder = MetadataBuilder::new();
+        read_lyrics3v2_at(&mut reader, &location, &mut builder).unwrap();
+
+        let revision = builder.metadata();
+        let tags = revision.tags();
+
+        assert_eq!(tags.len(), 2);
+        assert_eq!(tags[0].std_key, Some(StandardTagKey::TrackTitle));
+        assert_eq!(tags[0].value.to_string(), "Title");
+        assert_eq!(tags[1].std_key, Some(StandardTagKey::Lyrics));
+        assert_eq!(tags[1].value.to_string(), "[00:01]Line one\n[00:02]Two");
+    }
+
+    #[test]
+    fn verify_lyrics3v2_before_id3v1() {
+        let mut data = vec![0xff; 10];
+
+        let tag = b"LYRICSBEGINEAR00006Artist";
+        data.extend_from_slice(tag);
+        data.extend_from_slice(format!("{:06}LYRICS200", tag.len()).as_bytes());
+
+        let mut id3v1 = [0; 128];
+        id3v1[..3].copy_from_slice(b"TAG");
+        data.extend_from_slice(&id3v1);
+
+        let len = data.len() as u64;
+
+        let mut reader =
+            MediaSourceStream::new(Box::new(std::io::Cursor::new(data)), Default::default());
+
+        // The tag does not end at the end of the stream, but immediately before the ID3v1 tag.
+        assert!(find_lyrics3v2_ending_at(&mut reader, len).unwrap().is_none());
+
+        let location = find_lyrics3v2_ending_at(&mut reader, len - 128).unwrap().unwrap();
+
+        assert_eq!(location.pos, 10);
+        assert_eq!(location.pos + location.total_size(), len - 128);
+
+        let mut builder = MetadataBuilder::new();
+        read_lyrics3v2_at(&mut reader, &location, &mut builder).unwrap();
+
+        let revision = builder.metadata();
+        let tags = revision.tags();
+
+        assert_eq!(tags.len(), 1);
+        assert_eq!(tags[0].std_key, Some(StandardTagKey::Artist));
+        assert_eq!(tags[0].value.to_string(), "Artist");
+    }
+}
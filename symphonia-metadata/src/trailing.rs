@@ -0,0 +1,271 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! A scanner for tags appended to the end of a stream.
+//!
+//! Many raw elementary streams (MP3, ADTS, FLAC, etc.) are commonly tagged by appending one or
+//! more tags after the audio data. In the order they usually appear in, these are: an APEv2 tag,
+//! a Lyrics3v2 tag, an enhanced ID3v1 (TAG+) tag, and an ID3v1 tag.
+
+use std::io::{Seek, SeekFrom};
+
+use symphonia_core::errors::Result;
+use symphonia_core::io::{MediaSource, MediaSourceStream, ReadBytes};
+use symphonia_core::meta::{MetadataBuilder, MetadataLog, MetadataRevision};
+
+use log::warn;
+
+use crate::ape::{find_ape_tag_ending_at, read_ape_tag_at};
+use crate::id3v1::{read_id3v1, read_id3v1_enhanced, ID3V1_ENHANCED_TAG_LEN, ID3V1_TAG_LEN};
+use crate::lyrics3::{find_lyrics3v2_ending_at, read_lyrics3v2_at};
+
+/// The tags found at the end of a stream.
+#[derive(Default)]
+pub struct TrailingTags {
+    /// The metadata revisions read from the tags, ordered from least to most preferred. ID3v1 is
+    /// the least capable format, and is therefore ordered first.
+    pub revisions: Vec<MetadataRevision>,
+    /// The total length of all the tags in bytes.
+    pub len: u64,
+}
+
+/// Checks if the bytes at position `pos` of the stream match `marker`.
+fn has_marker(reader: &mut MediaSourceStream, pos: u64, marker: &[u8]) -> Result<bool> {
+    let mut buf = [0; 4];
+    let buf = &mut buf[..marker.len()];
+
+    reader.seek(SeekFrom::Start(pos))?;
+    reader.read_buf_exact(buf)?;
+
+    Ok(buf == marker)
+}
+
+/// Scans backwards from the byte position `end` of the stream for trailing ID3v1, ID3v1 enhanced,
+/// Lyrics3v2, and APEv2 tags, and reads all the tags found. Tags that fail to be read are skipped,
+/// but are still excluded from the returned length.
+///
+/// The stream must be seekable. The position of the stream is undefined afterwards.
+pub fn read_trailing_tags(reader: &mut MediaSourceStream, end: u64) -> Result<TrailingTags> {
+    let mut tags = TrailingTags::default();
+
+    let stream_end = end;
+    let mut end = end;
+
+    // An ID3v1 tag is always the very last tag of the stream. An enhanced tag may preceed it.
+    if end >= ID3V1_TAG_LEN && has_marker(reader, end - ID3V1_TAG_LEN, b"TAG")? {
+        let has_enhanced = end >= ID3V1_TAG_LEN + ID3V1_ENHANCED_TAG_LEN
+            && has_marker(reader, end - ID3V1_TAG_LEN - ID3V1_ENHANCED_TAG_LEN, b"TAG+")?;
+
+        let len = if has_enhanced { ID3V1_TAG_LEN + ID3V1_ENHANCED_TAG_LEN } else { ID3V1_TAG_LEN };
+
+        reader.seek(SeekFrom::Start(end - len))?;
+
+        let mut builder = MetadataBuilder::new();
+
+        let result = if has_enhanced {
+            read_id3v1_enhanced(reader, &mut builder)
+        }
+        else {
+            read_id3v1(reader, &mut builder)
+        };
+
+        match result {
+            Ok(_) => tags.revisions.push(builder.metadata()),
+            Err(err) => warn!("failed to read id3v1 tag: {}", err),
+        }
+
+        end -= len;
+    }
+
+    // The APEv2 and Lyrics3v2 tags are found in any order preceeding the ID3v1 tag. Since an APEv2
+    // tag is the most capable, its revision is ordered last regardless of where it was found.
+    let mut lyrics = Vec::new();
+    let mut ape = Vec::new();
+
+    loop {
+        let mut builder = MetadataBuilder::new();
+
+        if let Some(location) = find_ape_tag_ending_at(reader, end)? {
+            match read_ape_tag_at(reader, &location, &mut builder) {
+                Ok(_) => ape.push(builder.metadata()),
+                Err(err) => warn!("failed to read ape tag: {}", err),
+            }
+
+            end -= location.total_size();
+        }
+        else if let Some(location) = find_lyrics3v2_ending_at(reader, end)? {
+            match read_lyrics3v2_at(reader, &location, &mut builder) {
+                Ok(_) => lyrics.push(builder.metadata()),
+                Err(err) => warn!("failed to read lyrics3 tag: {}", err),
+            }
+
+            end -= location.total_size();
+        }
+        else {
+            break;
+        }
+    }
+
+    tags.revisions.extend(lyrics);
+    tags.revisions.extend(ape);
+
+    tags.len = stream_end - end;
+
+    Ok(tags)
+}
+
+/// Reads the trailing tags of a seekable stream with a known length into the metadata log, and
+/// returns the position where the tags begin, or in other words, the end of the audio data.
+///
+/// If the stream is not seekable, or its length is unknown, no tags are read and `None` is
+/// returned. The position of the stream is restored afterwards.
+pub fn read_trailing_metadata(
+    reader: &mut MediaSourceStream,
+    metadata: &mut MetadataLog,
+) -> Result<Option<u64>> {
+    let end = match reader.byte_len() {
+        Some(end) if reader.is_seekable() => end,
+        _ => return Ok(None),
+    };
+
+    let pos = reader.pos();
+
+    // Failing to scan for tags is not fatal, the stream may still be played.
+    let tags = read_trailing_tags(reader, end).unwrap_or_else(|err| {
+        warn!("failed to scan for trailing tags: {}", err);
+        Default::default()
+    });
+
+    reader.seek(SeekFrom::Start(pos))?;
+
+    for revision in tags.revisions {
+        metadata.push(revision);
+    }
+
+    Ok(Some(end - tags.len))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use symphonia_core::meta::{StandardTagKey, Tag, Value};
+
+    use super::*;
+    use crate::ape::write_ape_tag;
+
+    #[test]
+    fn verify_read_trailing_tags() {
+        let mut buf = vec![0xaa; 100];
+
+        // An APEv2 tag.
+        let mut builder = MetadataBuilder::new();
+        builder.add_tag(Tag::new(Some(StandardTagKey::Album), "ALBUM", Value::from("Ape")));
+        write_ape_tag(&mut buf, &builder.metadata()).unwrap();
+
+        // A Lyrics3v2 tag.
+        let lyrics = b"LYRICSBEGINLYR00005Hello";
+        buf.extend_from_slice(lyrics);
+        buf.extend_from_slice(format!("{:06}LYRICS200", lyrics.len()).as_bytes());
+
+        // An enhanced ID3v1 tag, followed by an ID3v1 tag. The title continues in the enhanced tag.
+        let mut enhanced = [0; ID3V1_ENHANCED_TAG_LEN as usize];
+        enhanced[..4].copy_from_slice(b"TAG+");
+        enhanced[4..8].copy_from_slice(b"Tail");
+        buf.extend_from_slice(&enhanced);
+
+        let mut id3v1 = [0; ID3V1_TAG_LEN as usize];
+        id3v1[..3].copy_from_slice(b"TAG");
+        id3v1[3..33].copy_from_slice(&[b'x'; 30]);
+        id3v1[127] = 255;
+        buf.extend_from_slice(&id3v1);
+
+        let end = buf.len() as u64;
+
+        let mut mss = MediaSourceStream::new(Box::new(Cursor::new(buf)), Default::default());
+
+        let tags = read_trailing_tags(&mut mss, end).unwrap();
+
+        assert_eq!(tags.len, end - 100);
+        assert_eq!(tags.revisions.len(), 3);
+
+        let value = |revision: &MetadataRevision, key| {
+            revision.tags().iter().find(|tag| tag.std_key == Some(key)).map(|t| t.value.to_string())
+        };
+
+        let title = format!("{}Tail", "x".repeat(30));
+
+        assert_eq!(value(&tags.revisions[0], StandardTagKey::TrackTitle), Some(title));
+        assert_eq!(value(&tags.revisions[1], StandardTagKey::Lyrics), Some("Hello".to_string()));
+        assert_eq!(value(&tags.revisions[2], StandardTagKey::Album), Some("Ape".to_string()));
+    }
+
+    fn id3v1_tag(title: &str) -> [u8; ID3V1_TAG_LEN as usize] {
+        let mut id3v1 = [0; ID3V1_TAG_LEN as usize];
+        id3v1[..3].copy_from_slice(b"TAG");
+        id3v1[3..3 + title.len()].copy_from_slice(title.as_bytes());
+        id3v1[127] = 255;
+        id3v1
+    }
+
+    fn title(revision: &MetadataRevision) -> Option<String> {
+        revision
+            .tags()
+            .iter()
+            .find(|tag| tag.std_key == Some(StandardTagKey::TrackTitle))
+            .map(|tag| tag.value.to_string())
+    }
+
+    #[test]
+    fn verify_read_ape_before_id3v1() {
+        let mut buf = vec![0xaa; 100];
+
+        let mut builder = MetadataBuilder::new();
+        builder.add_tag(Tag::new(Some(StandardTagKey::TrackTitle), "TITLE", Value::from("Ape")));
+        write_ape_tag(&mut buf, &builder.metadata()).unwrap();
+
+        buf.extend_from_slice(&id3v1_tag("Id3v1"));
+
+        let end = buf.len() as u64;
+
+        let mut mss = MediaSourceStream::new(Box::new(Cursor::new(buf)), Default::default());
+
+        let tags = read_trailing_tags(&mut mss, end).unwrap();
+
+        assert_eq!(tags.len, end - 100);
+        assert_eq!(tags.revisions.len(), 2);
+        assert_eq!(title(&tags.revisions[0]), Some("Id3v1".to_string()));
+        assert_eq!(title(&tags.revisions[1]), Some("Ape".to_string()));
+    }
+
+    #[test]
+    fn verify_read_lyrics3v2_before_id3v1() {
+        let mut buf = vec![0xaa; 100];
+
+        let lyrics = b"LYRICSBEGINETT00006LyricsLYR00005Hello";
+        buf.extend_from_slice(lyrics);
+        buf.extend_from_slice(format!("{:06}LYRICS200", lyrics.len()).as_bytes());
+
+        buf.extend_from_slice(&id3v1_tag("Id3v1"));
+
+        let mut mss = MediaSourceStream::new(Box::new(Cursor::new(buf)), Default::default());
+        mss.seek(SeekFrom::Start(10)).unwrap();
+
+        // The metadata is read, the end of the audio data is returned, and the position of the
+        // stream is restored.
+        let mut log = MetadataLog::default();
+
+        assert_eq!(read_trailing_metadata(&mut mss, &mut log).unwrap(), Some(100));
+        assert_eq!(mss.pos(), 10);
+
+        // The ID3v1 revision is ordered before the Lyrics3v2 revision.
+        let mut metadata = log.metadata();
+
+        assert_eq!(metadata.current().and_then(title), Some("Id3v1".to_string()));
+        assert_eq!(metadata.skip_to_latest().and_then(title), Some("Lyrics".to_string()));
+    }
+}
@@ -5,13 +5,18 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-//! A Vorbic COMMENT metadata reader for FLAC or OGG formats.
+//! A Vorbic COMMENT metadata reader and writer for FLAC or OGG formats.
 
 use lazy_static::lazy_static;
 use std::collections::HashMap;
-use symphonia_core::errors::Result;
+use std::io::Write;
+use symphonia_core::errors::{unsupported_error, Result};
 use symphonia_core::io::ReadBytes;
-use symphonia_core::meta::{MetadataBuilder, StandardTagKey, Tag, Value};
+use symphonia_core::meta::{MetadataBuilder, MetadataRevision, StandardTagKey, Tag, Value};
+
+use log::warn;
+
+use crate::lrc::parse_lrc;
 
 lazy_static! {
     static ref VORBIS_COMMENT_MAP: HashMap<&'static str, StandardTagKey> = {
@@ -109,6 +114,87 @@ lazy_static! {
     };
 }
 
+/// The preferred field names used when writing standard tags. Standard tags not listed here have
+/// no widely supported Vorbis Comment field name.
+#[rustfmt::skip]
+const VORBIS_WRITE_KEYS: &[(StandardTagKey, &str)] = &[
+    (StandardTagKey::Album                      , "ALBUM"),
+    (StandardTagKey::AlbumArtist                , "ALBUMARTIST"),
+    (StandardTagKey::Arranger                   , "ARRANGER"),
+    (StandardTagKey::Artist                     , "ARTIST"),
+    (StandardTagKey::Bpm                        , "BPM"),
+    (StandardTagKey::Comment                    , "COMMENT"),
+    (StandardTagKey::Compilation                , "COMPILATION"),
+    (StandardTagKey::Composer                   , "COMPOSER"),
+    (StandardTagKey::Conductor                  , "CONDUCTOR"),
+    (StandardTagKey::Copyright                  , "COPYRIGHT"),
+    (StandardTagKey::Date                       , "DATE"),
+    (StandardTagKey::Description                , "DESCRIPTION"),
+    (StandardTagKey::DiscNumber                 , "DISCNUMBER"),
+    (StandardTagKey::DiscSubtitle               , "DISCSUBTITLE"),
+    (StandardTagKey::DiscTotal                  , "DISCTOTAL"),
+    (StandardTagKey::EncodedBy                  , "ENCODED-BY"),
+    (StandardTagKey::Encoder                    , "ENCODER"),
+    (StandardTagKey::EncoderSettings            , "ENCODING"),
+    (StandardTagKey::Engineer                   , "ENGINEER"),
+    (StandardTagKey::Ensemble                   , "ENSEMBLE"),
+    (StandardTagKey::Genre                      , "GENRE"),
+    (StandardTagKey::IdentBarcode               , "BARCODE"),
+    (StandardTagKey::IdentCatalogNumber         , "CATALOGNUMBER"),
+    (StandardTagKey::IdentEanUpn                , "EAN/UPN"),
+    (StandardTagKey::IdentIsrc                  , "ISRC"),
+    (StandardTagKey::IdentPn                    , "PRODUCTNUMBER"),
+    (StandardTagKey::IdentUpc                   , "UPC"),
+    (StandardTagKey::Label                      , "LABEL"),
+    (StandardTagKey::Language                   , "LANGUAGE"),
+    (StandardTagKey::License                    , "LICENSE"),
+    (StandardTagKey::Lyricist                   , "LYRICIST"),
+    (StandardTagKey::Lyrics                     , "LYRICS"),
+    (StandardTagKey::MediaFormat                , "MEDIA"),
+    (StandardTagKey::MixDj                      , "DJMIXER"),
+    (StandardTagKey::MixEngineer                , "MIXER"),
+    (StandardTagKey::Mood                       , "MOOD"),
+    (StandardTagKey::MusicBrainzAlbumArtistId   , "MUSICBRAINZ_ALBUMARTISTID"),
+    (StandardTagKey::MusicBrainzAlbumId         , "MUSICBRAINZ_ALBUMID"),
+    (StandardTagKey::MusicBrainzArtistId        , "MUSICBRAINZ_ARTISTID"),
+    (StandardTagKey::MusicBrainzDiscId          , "MUSICBRAINZ_DISCID"),
+    (StandardTagKey::MusicBrainzOriginalAlbumId , "MUSICBRAINZ_ORIGINALALBUMID"),
+    (StandardTagKey::MusicBrainzOriginalArtistId, "MUSICBRAINZ_ORIGINALARTISTID"),
+    (StandardTagKey::MusicBrainzRecordingId     , "MUSICBRAINZ_RECORDINGID"),
+    (StandardTagKey::MusicBrainzReleaseGroupId  , "MUSICBRAINZ_RELEASEGROUPID"),
+    (StandardTagKey::MusicBrainzReleaseTrackId  , "MUSICBRAINZ_RELEASETRACKID"),
+    (StandardTagKey::MusicBrainzTrackId         , "MUSICBRAINZ_TRACKID"),
+    (StandardTagKey::MusicBrainzWorkId          , "MUSICBRAINZ_WORKID"),
+    (StandardTagKey::Opus                       , "OPUS"),
+    (StandardTagKey::OriginalDate               , "ORIGINALDATE"),
+    (StandardTagKey::Part                       , "PART"),
+    (StandardTagKey::Performer                  , "PERFORMER"),
+    (StandardTagKey::Producer                   , "PRODUCER"),
+    (StandardTagKey::Rating                     , "RATING"),
+    (StandardTagKey::ReleaseCountry             , "RELEASECOUNTRY"),
+    (StandardTagKey::Remixer                    , "REMIXER"),
+    (StandardTagKey::ReplayGainAlbumGain        , "REPLAYGAIN_ALBUM_GAIN"),
+    (StandardTagKey::ReplayGainAlbumPeak        , "REPLAYGAIN_ALBUM_PEAK"),
+    (StandardTagKey::ReplayGainTrackGain        , "REPLAYGAIN_TRACK_GAIN"),
+    (StandardTagKey::ReplayGainTrackPeak        , "REPLAYGAIN_TRACK_PEAK"),
+    (StandardTagKey::Script                     , "SCRIPT"),
+    (StandardTagKey::SortAlbum                  , "ALBUMSORT"),
+    (StandardTagKey::SortAlbumArtist            , "ALBUMARTISTSORT"),
+    (StandardTagKey::SortArtist                 , "ARTISTSORT"),
+    (StandardTagKey::SortTrackTitle             , "TITLESORT"),
+    (StandardTagKey::TrackNumber                , "TRACKNUMBER"),
+    (StandardTagKey::TrackSubtitle              , "SUBTITLE"),
+    (StandardTagKey::TrackTitle                 , "TITLE"),
+    (StandardTagKey::TrackTotal                 , "TRACKTOTAL"),
+    (StandardTagKey::Version                    , "VERSION"),
+    (StandardTagKey::Writer                     , "WRITER"),
+];
+
+/// Returns true if `key` is a valid Vorbis Comment field name.
+fn is_valid_key(key: &str) -> bool {
+    !key.is_empty() && key.bytes().all(|b| (0x20..=0x7d).contains(&b) && b != b'=')
+}
+
 /// Parse the given Vorbis Comment string into a `Tag`.
 fn parse(tag: &str) -> Tag {
     // Vorbis Comments (aka tags) are stored as <key>=<value> where <key> is
@@ -154,8 +240,133 @@ pub fn read_comment_no_framing<B: ReadBytes>(
         reader.read_buf_exact(&mut comment_byte)?;
 
         // Parse the comment string into a Tag and insert it into the parsed tag list.
-        metadata.add_tag(parse(&String::from_utf8_lossy(&comment_byte)));
+        let tag = parse(&String::from_utf8_lossy(&comment_byte));
+
+        // Lyrics may be LRC formatted timed lyrics.
+        if let (Some(StandardTagKey::Lyrics), Value::String(text)) = (tag.std_key, &tag.value) {
+            if let Some(timed_text) = parse_lrc(text) {
+                metadata.add_timed_text(timed_text);
+            }
+        }
+
+        metadata.add_tag(tag);
+    }
+
+    Ok(())
+}
+
+/// Write the tags of a `MetadataRevision` as a Vorbis Comment without the framing bit. This is the
+/// format of a FLAC `VORBIS_COMMENT` metadata block, and of the comment header of Ogg Opus and
+/// Speex after their magic signature.
+///
+/// Standard tags are written using their preferred field name, while all other tags are written
+/// using their original key. Tags with binary values, or keys that are not valid field names, are
+/// skipped.
+pub fn write_comment_no_framing<W: Write>(
+    writer: &mut W,
+    vendor: &str,
+    revision: &MetadataRevision,
+) -> Result<()> {
+    let mut comments = Vec::new();
+
+    for tag in revision.tags() {
+        let key = match tag.std_key.and_then(|std_key| {
+            VORBIS_WRITE_KEYS.iter().find(|(k, _)| *k == std_key).map(|&(_, key)| key)
+        }) {
+            Some(key) => key,
+            None if is_valid_key(&tag.key) => &tag.key,
+            None => {
+                warn!("vorbis: skipping tag with invalid field name '{}'", tag.key);
+                continue;
+            }
+        };
+
+        let value = match &tag.value {
+            Value::Binary(_) => {
+                warn!("vorbis: skipping tag '{}' with binary value", key);
+                continue;
+            }
+            Value::Boolean(value) => if *value { "1" } else { "0" }.to_string(),
+            Value::Flag => "1".to_string(),
+            value => value.to_string(),
+        };
+
+        comments.push(format!("{}={}", key, value));
+    }
+
+    let len_too_large = |len: usize| len as u64 > u64::from(u32::MAX);
+
+    if len_too_large(vendor.len()) || comments.iter().any(|comment| len_too_large(comment.len())) {
+        return unsupported_error("vorbis: comment is too large");
+    }
+
+    writer.write_all(&(vendor.len() as u32).to_le_bytes())?;
+    writer.write_all(vendor.as_bytes())?;
+
+    writer.write_all(&(comments.len() as u32).to_le_bytes())?;
+
+    for comment in &comments {
+        writer.write_all(&(comment.len() as u32).to_le_bytes())?;
+        writer.write_all(comment.as_bytes())?;
     }
 
     Ok(())
 }
+
+/// Write the tags of a `MetadataRevision` as a complete Ogg Vorbis comment header packet,
+/// including the packet type, the Vorbis signature, and the framing bit.
+pub fn write_comment_packet<W: Write>(
+    writer: &mut W,
+    vendor: &str,
+    revision: &MetadataRevision,
+) -> Result<()> {
+    writer.write_all(b"\x03vorbis")?;
+    write_comment_no_framing(writer, vendor, revision)?;
+    writer.write_all(&[1])?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use symphonia_core::io::BufReader;
+
+    #[test]
+    fn verify_write_comment_round_trip() {
+        let mut builder = MetadataBuilder::new();
+        builder.add_tag(Tag::new(Some(StandardTagKey::TrackTitle), "Title", Value::from("Title")));
+        builder.add_tag(Tag::new(Some(StandardTagKey::Artist), "ARTIST", Value::from("A")));
+        builder.add_tag(Tag::new(Some(StandardTagKey::Artist), "ARTIST", Value::from("B")));
+        builder.add_tag(Tag::new(None, "CUSTOM", Value::from(5u32)));
+        builder.add_tag(Tag::new(None, "BAD=KEY", Value::from("skipped")));
+        builder.add_tag(Tag::new(None, "BINARY", Value::from(&[1u8, 2][..])));
+        let revision = builder.metadata();
+
+        let mut buf = Vec::new();
+        write_comment_packet(&mut buf, "vendor", &revision).unwrap();
+
+        assert_eq!(&buf[..7], b"\x03vorbis");
+        assert_eq!(buf.last(), Some(&1));
+
+        let mut builder = MetadataBuilder::new();
+        let mut reader = BufReader::new(&buf[7..buf.len() - 1]);
+        read_comment_no_framing(&mut reader, &mut builder).unwrap();
+        let read = builder.metadata();
+
+        let tags: Vec<(Option<StandardTagKey>, &str, String)> = read
+            .tags()
+            .iter()
+            .map(|tag| (tag.std_key, tag.key.as_str(), tag.value.to_string()))
+            .collect();
+
+        assert_eq!(
+            tags,
+            vec![
+                (Some(StandardTagKey::TrackTitle), "TITLE", "Title".to_string()),
+                (Some(StandardTagKey::Artist), "ARTIST", "A".to_string()),
+                (Some(StandardTagKey::Artist), "ARTIST", "B".to_string()),
+                (None, "CUSTOM", "5".to_string()),
+            ]
+        );
+    }
+}
@@ -18,6 +18,7 @@ use std::path::Path;
 
 use lazy_static::lazy_static;
 use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::dsp::analysis::{Analysis, Analyzer, Region};
 use symphonia::core::errors::{Error, Result};
 use symphonia::core::formats::{Cue, FormatOptions, FormatReader, SeekMode, SeekTo, Track};
 use symphonia::core::io::{MediaSource, MediaSourceStream, ReadOnlySource};
@@ -54,6 +55,12 @@ fn main() {
                 .help("Decode, but do not play the audio")
                 .conflicts_with_all(&["probe-only", "verify-only", "verify"]),
         )
+        .arg(
+            Arg::new("analyze")
+                .long("analyze")
+                .help("Analyze the decoded audio for silence, clipping, DC offset, and dropouts")
+                .requires("decode-only"),
+        )
         .arg(
             Arg::new("probe-only")
                 .long("probe-only")
@@ -130,11 +137,20 @@ fn main() {
         Ok(mut probed) => {
             let result = if matches.is_present("verify-only") {
                 // Verify-only mode decodes and verifies the audio, but does not play it.
-                decode_only(probed.format, &DecoderOptions { verify: true, ..Default::default() })
+                decode_only(
+                    probed.format,
+                    &DecoderOptions { verify: true, ..Default::default() },
+                    false,
+                )
             }
             else if matches.is_present("decode-only") {
-                // Decode-only mode decodes the audio, but does not play or verify it.
-                decode_only(probed.format, &DecoderOptions { verify: false, ..Default::default() })
+                // Decode-only mode decodes the audio, but does not play or verify it. Optionally,
+                // the decoded audio is analyzed.
+                decode_only(
+                    probed.format,
+                    &DecoderOptions { verify: false, ..Default::default() },
+                    matches.is_present("analyze"),
+                )
             }
             else if matches.is_present("probe-only") {
                 // Probe-only mode only prints information about the format, tracks, metadata, etc.
@@ -167,15 +183,26 @@ fn main() {
     }
 }
 
-fn decode_only(mut reader: Box<dyn FormatReader>, decode_opts: &DecoderOptions) -> Result<()> {
+fn decode_only(
+    mut reader: Box<dyn FormatReader>,
+    decode_opts: &DecoderOptions,
+    analyze: bool,
+) -> Result<()> {
     // Get the default track.
     // TODO: Allow track selection.
     let track = reader.default_track().unwrap();
     let track_id = track.id;
+    let time_base = track.codec_params.time_base;
 
     // Create a decoder for the track.
     let mut decoder = symphonia::default::get_codecs().make(&track.codec_params, decode_opts)?;
 
+    // The analyzer is created once the signal specification of the decoded audio is known.
+    let mut analyzer: Option<Analyzer> = None;
+
+    // The timestamp after the last packet of the track, decoded or not.
+    let mut end_ts = 0;
+
     // Decode all packets, ignoring all decode errors.
     let result = loop {
         let packet = match reader.next_packet() {
@@ -188,14 +215,48 @@ fn decode_only(mut reader: Box<dyn FormatReader>, decode_opts: &DecoderOptions)
             continue;
         }
 
+        end_ts = packet.ts() + packet.dur();
+
         // Decode the packet into audio samples.
         match decoder.decode(&packet) {
+            Ok(decoded) if analyze => {
+                let spec = *decoded.spec();
+
+                // Report positions in the time base of the track, or in frames if there is none.
+                let analyzer = analyzer.get_or_insert_with(|| {
+                    let tb = time_base.unwrap_or_else(|| TimeBase::new(1, spec.rate));
+                    Analyzer::new(spec, tb, &Default::default())
+                });
+
+                if analyzer.spec() == &spec {
+                    // Position the analyzer at the packet so that audio lost to decode errors is
+                    // reported as a dropout. Packet timestamps are only meaningful with a time
+                    // base.
+                    if time_base.is_some() {
+                        analyzer.set_position(packet.ts());
+                    }
+
+                    analyzer.process_ref(&decoded);
+                }
+                else {
+                    warn!("signal specification changed, not analyzing packet");
+                }
+            }
             Ok(_decoded) => continue,
             Err(Error::DecodeError(err)) => warn!("decode error: {}", err),
             Err(err) => break Err(err),
         }
     };
 
+    if let Some(analyzer) = &mut analyzer {
+        // Audio lost to decode errors at the end of the track is also a dropout.
+        if time_base.is_some() {
+            analyzer.set_position(end_ts);
+        }
+
+        print_analysis(&analyzer.analysis(), time_base);
+    }
+
     // Regardless of result, finalize the decoder to get the verification result.
     let finalize_result = decoder.finalize();
 
@@ -501,6 +562,23 @@ fn print_tracks(tracks: &[Track]) {
             if let Some(language) = &track.language {
                 println!("|          Language:        {}", language);
             }
+            if let Some(gain) = &track.gain {
+                if let Some(track_gain) = gain.track_gain {
+                    println!("|          Track Gain:      {:+.2} dB", track_gain);
+                }
+                if let Some(track_peak) = gain.track_peak {
+                    println!("|          Track Peak:      {:.6}", track_peak);
+                }
+                if let Some(album_gain) = gain.album_gain {
+                    println!("|          Album Gain:      {:+.2} dB", album_gain);
+                }
+                if let Some(album_peak) = gain.album_peak {
+                    println!("|          Album Peak:      {:.6}", album_peak);
+                }
+                if let Some(reference) = gain.reference_loudness {
+                    println!("|          Reference:       {:.1} LUFS", reference);
+                }
+            }
         }
     }
 }
@@ -531,6 +609,11 @@ fn print_cues(cues: &[Cue]) {
                 }
             }
 
+            // Count the visuals associated with the Cue, but don't print them.
+            if !cue.visuals.is_empty() {
+                println!("|          Visuals:    {} (not listed)", cue.visuals.len());
+            }
+
             // Print any sub-cues.
             if !cue.points.is_empty() {
                 println!("|          Sub-Cues:");
@@ -555,6 +638,69 @@ fn print_cues(cues: &[Cue]) {
     }
 }
 
+fn print_analysis(analysis: &Analysis, tb: Option<TimeBase>) {
+    let fmt_region = |region: &Region| match tb {
+        Some(tb) => format!(
+            "{} - {} ({})",
+            fmt_time(region.ts, tb),
+            fmt_time(region.ts + region.dur, tb),
+            region.ts
+        ),
+        None => format!("{} - {}", region.ts, region.ts + region.dur),
+    };
+
+    println!("+ Analysis");
+    println!("|");
+    println!("| // Silence //");
+
+    if let Some(region) = &analysis.leading_silence {
+        println!("|     Leading:         {}", fmt_region(region));
+    }
+    if let Some(region) = &analysis.trailing_silence {
+        println!("|     Trailing:        {}", fmt_region(region));
+    }
+    for region in &analysis.dropouts {
+        println!("|     Dropout:         {}", fmt_region(region));
+    }
+
+    println!("|");
+    println!("| // Channels //");
+
+    for (idx, channel) in analysis.channels.iter().enumerate() {
+        println!("|     [{:0>2}] Peak:         {:.2} dBFS", idx + 1, 20.0 * channel.peak.log10());
+        println!(
+            "|          DC Offset:    {:+.6}{}",
+            channel.dc_offset,
+            if channel.has_dc_offset { " (!)" } else { "" }
+        );
+        println!("|          Clipped:      {} sample(s)", channel.clipped_samples);
+
+        for region in &channel.clip_runs {
+            println!("|          Clip Run:     {}", fmt_region(region));
+        }
+    }
+
+    if let Some(correlation) = analysis.stereo_correlation {
+        println!("|");
+        println!("| // Stereo //");
+        println!(
+            "|     Correlation:     {:+.3}{}",
+            correlation,
+            if analysis.phase_inverted { " (phase-inverted)" } else { "" }
+        );
+    }
+
+    println!(":");
+    println!();
+
+    if analysis.has_defects() {
+        info!("analysis found defects");
+    }
+    else {
+        info!("analysis found no defects");
+    }
+}
+
 fn print_tags(tags: &[Tag]) {
     if !tags.is_empty() {
         println!("|");
@@ -125,13 +125,33 @@ mod pulseaudio {
 
     /// Maps a set of Symphonia `Channels` to a PulseAudio channel map.
     fn map_channels_to_pa_channelmap(channels: Channels) -> Option<pulse::channelmap::Map> {
+        if channels.count() > usize::from(pulse::channelmap::Map::CHANNELS_MAX) {
+            warn!("too many channels ({}) for output", channels.count());
+            return None;
+        }
+
         let mut map: pulse::channelmap::Map = Default::default();
         map.init();
         map.set_len(channels.count() as u8);
 
+        // Ambisonic and discrete channels have no position, and are mapped to auxiliary channels.
+        let mut aux: pulse::channelmap::Map = Default::default();
+        aux.init_auto(channels.count() as u8, pulse::channelmap::MapDef::Aux);
+
+        let mut n_aux = 0;
+
         let is_mono = channels.count() == 1;
 
-        for (i, channel) in channels.iter().enumerate() {
+        for (i, label) in channels.labels().enumerate() {
+            let channel = match label {
+                ChannelLabel::Positioned(channel) => channel,
+                _ => {
+                    map.get_mut()[i] = aux.get()[n_aux];
+                    n_aux += 1;
+                    continue;
+                }
+            };
+
             map.get_mut()[i] = match channel {
                 Channels::FRONT_LEFT if is_mono => pulse::channelmap::Position::Mono,
                 Channels::FRONT_LEFT => pulse::channelmap::Position::FrontLeft,
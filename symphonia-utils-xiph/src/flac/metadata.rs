@@ -6,19 +6,21 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 use std::ascii;
+use std::io::Write;
 use std::num::NonZeroU32;
 
 use symphonia_core::audio::Channels;
-use symphonia_core::errors::{decode_error, Result};
+use symphonia_core::errors::{decode_error, unsupported_error, Result};
 use symphonia_core::formats::util::SeekIndex;
 use symphonia_core::formats::{Cue, CuePoint};
 use symphonia_core::io::*;
-use symphonia_core::meta::{ColorMode, MetadataBuilder, Size, StandardTagKey, Tag, Value};
+use symphonia_core::meta::{ColorMode, MetadataBuilder, MetadataRevision, Size, StandardTagKey};
+use symphonia_core::meta::{Tag, Value};
 use symphonia_core::meta::{VendorData, Visual};
 
 use symphonia_metadata::{id3v2, vorbis};
 
-#[derive(PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum MetadataBlockType {
     StreamInfo,
     Padding,
@@ -30,6 +32,22 @@ pub enum MetadataBlockType {
     Unknown(u8),
 }
 
+impl MetadataBlockType {
+    /// Gets the block type identifier.
+    pub fn id(&self) -> u8 {
+        match self {
+            MetadataBlockType::StreamInfo => 0,
+            MetadataBlockType::Padding => 1,
+            MetadataBlockType::Application => 2,
+            MetadataBlockType::SeekTable => 3,
+            MetadataBlockType::VorbisComment => 4,
+            MetadataBlockType::Cuesheet => 5,
+            MetadataBlockType::Picture => 6,
+            MetadataBlockType::Unknown(id) => *id,
+        }
+    }
+}
+
 fn flac_channels_to_channels(channels: u32) -> Channels {
     debug_assert!(channels > 0 && channels < 9);
 
@@ -203,6 +221,15 @@ pub fn read_comment_block<B: ReadBytes>(
     vorbis::read_comment_no_framing(reader, metadata)
 }
 
+/// Write a comment block containing the tags of a `MetadataRevision`.
+pub fn write_comment_block<W: Write>(
+    writer: &mut W,
+    vendor: &str,
+    revision: &MetadataRevision,
+) -> Result<()> {
+    vorbis::write_comment_no_framing(writer, vendor, revision)
+}
+
 /// Try to read a seek table block.
 pub fn read_seek_table_block<B: ReadBytes>(
     reader: &mut B,
@@ -360,8 +387,13 @@ fn read_cuesheet_track<B: ReadBytes>(
         return decode_error("flac: cuesheet track indicies cannot exceed 100 for CD-DA");
     }
 
-    let mut cue =
-        Cue { index: number, start_ts: n_offset_samples, tags: Vec::new(), points: Vec::new() };
+    let mut cue = Cue {
+        index: number,
+        start_ts: n_offset_samples,
+        tags: Vec::new(),
+        visuals: Vec::new(),
+        points: Vec::new(),
+    };
 
     // Push the ISRC as a tag.
     cue.tags.push(Tag::new(Some(StandardTagKey::IdentIsrc), "ISRC", Value::from(isrc)));
@@ -483,6 +515,47 @@ pub fn read_picture_block<B: ReadBytes>(
     Ok(())
 }
 
+/// Write a picture block for a `Visual`. The description of the picture is taken from the
+/// `Description` tag of the visual, if there is one.
+pub fn write_picture_block<W: Write>(writer: &mut W, visual: &Visual) -> Result<()> {
+    let desc = visual
+        .tags
+        .iter()
+        .find(|tag| tag.std_key == Some(StandardTagKey::Description))
+        .map(|tag| tag.value.to_string())
+        .unwrap_or_default();
+
+    if visual.data.len() as u64 > u64::from(u32::MAX) {
+        return unsupported_error("flac: picture is too large");
+    }
+
+    let (width, height) = match visual.dimensions {
+        Some(Size { width, height }) => (width, height),
+        None => (0, 0),
+    };
+
+    let indexed_colours = match visual.color_mode {
+        Some(ColorMode::Indexed(colours)) => colours.get(),
+        _ => 0,
+    };
+
+    let picture_type = id3v2::util::visual_key_to_apic_picture_type(visual.usage);
+
+    writer.write_all(&u32::from(picture_type).to_be_bytes())?;
+    writer.write_all(&(visual.media_type.len() as u32).to_be_bytes())?;
+    writer.write_all(visual.media_type.as_bytes())?;
+    writer.write_all(&(desc.len() as u32).to_be_bytes())?;
+    writer.write_all(desc.as_bytes())?;
+    writer.write_all(&width.to_be_bytes())?;
+    writer.write_all(&height.to_be_bytes())?;
+    writer.write_all(&visual.bits_per_pixel.map_or(0, |bpp| bpp.get()).to_be_bytes())?;
+    writer.write_all(&indexed_colours.to_be_bytes())?;
+    writer.write_all(&(visual.data.len() as u32).to_be_bytes())?;
+    writer.write_all(&visual.data)?;
+
+    Ok(())
+}
+
 pub struct MetadataBlockHeader {
     pub is_last: bool,
     pub block_type: MetadataBlockType,
@@ -515,4 +588,19 @@ impl MetadataBlockHeader {
 
         Ok(MetadataBlockHeader { is_last, block_type, block_len })
     }
+
+    /// Write a metadata block header.
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        if self.block_len > 0xff_ffff {
+            return unsupported_error("flac: metadata block is too large");
+        }
+
+        let header_enc = if self.is_last { 0x80 } else { 0x00 } | (self.block_type.id() & 0x7f);
+
+        let len = self.block_len.to_be_bytes();
+
+        writer.write_all(&[header_enc, len[1], len[2], len[3]])?;
+
+        Ok(())
+    }
 }
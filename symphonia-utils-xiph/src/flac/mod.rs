@@ -6,3 +6,4 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 pub mod metadata;
+pub mod writer;
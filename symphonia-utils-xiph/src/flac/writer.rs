@@ -0,0 +1,443 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::fs::OpenOptions;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+use std::path::Path;
+
+use symphonia_core::errors::{decode_error, unsupported_error, Result};
+use symphonia_core::io::BufReader;
+use symphonia_core::meta::MetadataRevision;
+use symphonia_metadata::file::replace_file;
+use symphonia_metadata::id3v2::read_id3v2_tag_len;
+
+use super::metadata::{write_comment_block, write_picture_block};
+use super::metadata::{MetadataBlockHeader, MetadataBlockType};
+
+/// The FLAC start of stream marker: "fLaC" in ASCII.
+const FLAC_STREAM_MARKER: [u8; 4] = *b"fLaC";
+
+/// The length of a metadata block header.
+const BLOCK_HEADER_LEN: usize = 4;
+
+/// The maximum length of a metadata block.
+const MAX_BLOCK_LEN: usize = 0xff_ffff;
+
+/// The vendor string used if the stream does not already have a comment block.
+const DEFAULT_VENDOR: &str = concat!("Symphonia ", env!("CARGO_PKG_VERSION"));
+
+/// Options for writing FLAC metadata.
+#[derive(Copy, Clone, Debug)]
+pub struct FlacWriteOptions {
+    /// The length of the `PADDING` block to write when the metadata must be rewritten, such that
+    /// later edits may be performed in-place.
+    pub padding: usize,
+}
+
+impl Default for FlacWriteOptions {
+    fn default() -> Self {
+        FlacWriteOptions { padding: 8192 }
+    }
+}
+
+/// A raw metadata block.
+struct Block {
+    block_type: MetadataBlockType,
+    data: Vec<u8>,
+}
+
+/// Reads all metadata blocks following the stream marker, and returns them along with the
+/// position range of the stream marker and metadata blocks. The range ends at the first audio
+/// frame.
+///
+/// An ID3v2 tag preceding the stream marker is not part of the FLAC stream, but is commonly added
+/// by taggers, and is skipped when a FLAC file is probed. Likewise, the tag is skipped here, and the
+/// range begins after it.
+fn read_blocks<R: Read + Seek>(reader: &mut R) -> Result<(Vec<Block>, Range<u64>)> {
+    reader.seek(SeekFrom::Start(0))?;
+
+    let start = read_id3v2_tag_len(reader)?.unwrap_or(0);
+
+    reader.seek(SeekFrom::Start(start))?;
+
+    let mut marker = [0; 4];
+    reader.read_exact(&mut marker)?;
+
+    if marker != FLAC_STREAM_MARKER {
+        return unsupported_error("flac: missing flac stream marker");
+    }
+
+    let mut blocks = Vec::new();
+    let mut pos = start + marker.len() as u64;
+
+    loop {
+        let mut header_buf = [0; BLOCK_HEADER_LEN];
+        reader.read_exact(&mut header_buf)?;
+
+        let header = MetadataBlockHeader::read(&mut BufReader::new(&header_buf))?;
+
+        let mut data = vec![0; header.block_len as usize];
+        reader.read_exact(&mut data)?;
+
+        pos += (BLOCK_HEADER_LEN + data.len()) as u64;
+
+        blocks.push(Block { block_type: header.block_type, data });
+
+        if header.is_last {
+            break;
+        }
+    }
+
+    match blocks.first() {
+        Some(block) if block.block_type == MetadataBlockType::StreamInfo => (),
+        _ => return decode_error("flac: first block is not stream info"),
+    }
+
+    Ok((blocks, start..pos))
+}
+
+/// Reads the vendor string of an existing comment block.
+fn read_vendor(data: &[u8]) -> Option<String> {
+    let len = u32::from_le_bytes([*data.first()?, *data.get(1)?, *data.get(2)?, *data.get(3)?]);
+    let vendor = data.get(4..4 + len as usize)?;
+    Some(String::from_utf8_lossy(vendor).into_owned())
+}
+
+/// Builds the new list of metadata blocks. All existing blocks except for `VORBIS_COMMENT`,
+/// `PICTURE`, and `PADDING` blocks are preserved in their original order. These are followed by a
+/// new comment block and one picture block per visual.
+fn build_blocks(existing: Vec<Block>, revision: &MetadataRevision) -> Result<Vec<Block>> {
+    let vendor = existing
+        .iter()
+        .find(|block| block.block_type == MetadataBlockType::VorbisComment)
+        .and_then(|block| read_vendor(&block.data))
+        .unwrap_or_else(|| DEFAULT_VENDOR.to_string());
+
+    let mut blocks: Vec<Block> = existing
+        .into_iter()
+        .filter(|block| {
+            !matches!(
+                block.block_type,
+                MetadataBlockType::Padding
+                    | MetadataBlockType::VorbisComment
+                    | MetadataBlockType::Picture
+            )
+        })
+        .collect();
+
+    let mut data = Vec::new();
+    write_comment_block(&mut data, &vendor, revision)?;
+    blocks.push(Block { block_type: MetadataBlockType::VorbisComment, data });
+
+    for visual in revision.visuals() {
+        let mut data = Vec::new();
+        write_picture_block(&mut data, visual)?;
+        blocks.push(Block { block_type: MetadataBlockType::Picture, data });
+    }
+
+    if blocks.iter().any(|block| block.data.len() > MAX_BLOCK_LEN) {
+        return unsupported_error("flac: metadata block is too large");
+    }
+
+    Ok(blocks)
+}
+
+/// Gets the total length of the metadata blocks, including their headers.
+fn blocks_len(blocks: &[Block]) -> usize {
+    blocks.iter().map(|block| BLOCK_HEADER_LEN + block.data.len()).sum()
+}
+
+/// Writes the stream marker, the metadata blocks, and, optionally, a padding block.
+fn write_blocks<W: Write>(writer: &mut W, blocks: &[Block], padding: Option<usize>) -> Result<()> {
+    writer.write_all(&FLAC_STREAM_MARKER)?;
+
+    for (i, block) in blocks.iter().enumerate() {
+        let header = MetadataBlockHeader {
+            is_last: padding.is_none() && i == blocks.len() - 1,
+            block_type: block.block_type,
+            block_len: block.data.len() as u32,
+        };
+
+        header.write(writer)?;
+        writer.write_all(&block.data)?;
+    }
+
+    if let Some(padding) = padding {
+        let header = MetadataBlockHeader {
+            is_last: true,
+            block_type: MetadataBlockType::Padding,
+            block_len: padding as u32,
+        };
+
+        header.write(writer)?;
+        io::copy(&mut io::repeat(0).take(padding as u64), writer)?;
+    }
+
+    Ok(())
+}
+
+/// Attempts to replace the tags and pictures of a FLAC stream without moving the audio data.
+///
+/// The new metadata is written in-place if it fits within the space occupied by the existing
+/// metadata blocks. Any existing `PADDING` blocks are consumed, and a single `PADDING` block is
+/// written to fill the remaining space. `STREAMINFO`, `SEEKTABLE`, `CUESHEET`, `APPLICATION`, and
+/// any unknown blocks are preserved.
+///
+/// Returns `true` if the metadata was written, or `false` if there is insufficient space and the
+/// stream was left untouched.
+pub fn write_flac_metadata_in_place<S: Read + Write + Seek>(
+    stream: &mut S,
+    revision: &MetadataRevision,
+) -> Result<bool> {
+    let (existing, range) = read_blocks(stream)?;
+
+    let blocks = build_blocks(existing, revision)?;
+
+    let avail = (range.end - range.start) as usize - FLAC_STREAM_MARKER.len();
+    let needed = blocks_len(&blocks);
+
+    // The new blocks must exactly fill the existing space, or leave enough space for the header of
+    // a padding block.
+    let padding = if needed == avail {
+        None
+    }
+    else if needed + BLOCK_HEADER_LEN <= avail
+        && avail - needed - BLOCK_HEADER_LEN <= MAX_BLOCK_LEN
+    {
+        Some(avail - needed - BLOCK_HEADER_LEN)
+    }
+    else {
+        return Ok(false);
+    };
+
+    let mut buf = Vec::with_capacity((range.end - range.start) as usize);
+    write_blocks(&mut buf, &blocks, padding)?;
+
+    debug_assert!(buf.len() as u64 == range.end - range.start);
+
+    stream.seek(SeekFrom::Start(range.start))?;
+    stream.write_all(&buf)?;
+    stream.flush()?;
+
+    Ok(true)
+}
+
+/// Replaces the tags and pictures of a FLAC file.
+///
+/// The metadata is written in-place if possible. Otherwise, the file is rewritten with a new
+/// `PADDING` block of the length specified in `options`. The rewritten file is first written to a
+/// temporary file in the same directory, and then renamed over the original file.
+pub fn update_flac_file<P: AsRef<Path>>(
+    path: P,
+    revision: &MetadataRevision,
+    options: &FlacWriteOptions,
+) -> Result<()> {
+    let path = path.as_ref();
+
+    let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+
+    if write_flac_metadata_in_place(&mut file, revision)? {
+        return Ok(());
+    }
+
+    let (existing, range) = read_blocks(&mut file)?;
+
+    let blocks = build_blocks(existing, revision)?;
+
+    replace_file(path, |temp| {
+        // Preserve any ID3v2 tag preceding the stream.
+        file.seek(SeekFrom::Start(0))?;
+        io::copy(&mut (&mut file).take(range.start), temp)?;
+
+        write_blocks(temp, &blocks, Some(options.padding.min(MAX_BLOCK_LEN)))?;
+
+        file.seek(SeekFrom::Start(range.end))?;
+        io::copy(&mut file, temp)?;
+
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::io::Cursor;
+
+    use symphonia_core::meta::{MetadataBuilder, StandardTagKey, StandardVisualKey, Tag, Value};
+    use symphonia_core::meta::{Size, Visual};
+
+    use symphonia_metadata::id3v2::{write_id3v2_tag, Id3v2WriteOptions};
+
+    use super::super::metadata::{read_comment_block, read_picture_block};
+    use super::*;
+
+    /// Builds a FLAC stream with the given metadata blocks followed by some fake audio data.
+    fn make_stream(blocks: &[Block], padding: Option<usize>) -> Vec<u8> {
+        let mut buf = Vec::new();
+        write_blocks(&mut buf, blocks, padding).unwrap();
+        buf.extend_from_slice(b"audio");
+        buf
+    }
+
+    fn read_metadata(stream: &[u8]) -> (Vec<Block>, MetadataRevision) {
+        let (blocks, range) = read_blocks(&mut Cursor::new(stream)).unwrap();
+        assert_eq!(&stream[range.end as usize..], b"audio");
+
+        let mut builder = MetadataBuilder::new();
+
+        for block in &blocks {
+            let mut reader = BufReader::new(&block.data);
+            match block.block_type {
+                MetadataBlockType::VorbisComment => {
+                    read_comment_block(&mut reader, &mut builder).unwrap()
+                }
+                MetadataBlockType::Picture => {
+                    read_picture_block(&mut reader, &mut builder).unwrap()
+                }
+                _ => (),
+            }
+        }
+
+        (blocks, builder.metadata())
+    }
+
+    fn block_ids(blocks: &[Block]) -> Vec<u8> {
+        blocks.iter().map(|block| block.block_type.id()).collect()
+    }
+
+    #[test]
+    fn verify_write_flac_metadata() {
+        let mut comment = Vec::new();
+        write_comment_block(&mut comment, "vendor", &MetadataBuilder::new().metadata()).unwrap();
+
+        let original = vec![
+            Block { block_type: MetadataBlockType::StreamInfo, data: vec![0x11; 34] },
+            Block { block_type: MetadataBlockType::SeekTable, data: vec![0x22; 18] },
+            Block { block_type: MetadataBlockType::VorbisComment, data: comment },
+            Block { block_type: MetadataBlockType::Cuesheet, data: vec![0x33; 10] },
+        ];
+
+        let file = make_stream(&original, Some(256));
+
+        let mut builder = MetadataBuilder::new();
+        builder.add_tag(Tag::new(Some(StandardTagKey::TrackTitle), "TITLE", Value::from("Title")));
+        builder.add_visual(Visual {
+            media_type: "image/png".to_string(),
+            dimensions: Some(Size { width: 2, height: 3 }),
+            bits_per_pixel: None,
+            color_mode: None,
+            usage: Some(StandardVisualKey::FrontCover),
+            tags: vec![Tag::new(
+                Some(StandardTagKey::Description),
+                "DESCRIPTION",
+                Value::from("cover"),
+            )],
+            data: Box::new([1, 2, 3, 4]),
+        });
+        let revision = builder.metadata();
+
+        // The new metadata fits within the existing padding.
+        let mut stream = Cursor::new(file.clone());
+        assert!(write_flac_metadata_in_place(&mut stream, &revision).unwrap());
+
+        let updated = stream.into_inner();
+        assert_eq!(updated.len(), file.len());
+
+        let (blocks, read) = read_metadata(&updated);
+
+        assert_eq!(block_ids(&blocks), vec![0, 3, 5, 4, 6, 1]);
+        assert_eq!(blocks[0].data, original[0].data);
+        assert_eq!(blocks[1].data, original[1].data);
+        assert_eq!(blocks[2].data, original[3].data);
+        assert_eq!(read_vendor(&blocks[3].data).as_deref(), Some("vendor"));
+
+        assert_eq!(read.tags()[0].key, "TITLE");
+        assert_eq!(read.tags()[0].value.to_string(), "Title");
+
+        let visual = &read.visuals()[0];
+        assert_eq!(visual.media_type, "image/png");
+        assert!(matches!(visual.dimensions, Some(Size { width: 2, height: 3 })));
+        assert_eq!(visual.usage, Some(StandardVisualKey::FrontCover));
+        assert_eq!(visual.tags[0].value.to_string(), "cover");
+        assert_eq!(&*visual.data, &[1, 2, 3, 4]);
+
+        // The new metadata does not fit without any padding.
+        let file = make_stream(&original, None);
+
+        let mut stream = Cursor::new(file.clone());
+        assert!(!write_flac_metadata_in_place(&mut stream, &revision).unwrap());
+        assert_eq!(stream.into_inner(), file);
+
+        // Update a file on disk, which requires rewriting the file.
+        let path = std::env::temp_dir().join(format!("symphonia-flac-{}.flac", std::process::id()));
+        fs::write(&path, &file).unwrap();
+
+        let options = FlacWriteOptions { padding: 100 };
+        update_flac_file(&path, &revision, &options).unwrap();
+
+        let updated = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let (blocks, read) = read_metadata(&updated);
+
+        assert_eq!(block_ids(&blocks), vec![0, 3, 5, 4, 6, 1]);
+        assert_eq!(blocks[5].data.len(), 100);
+        assert_eq!(read.tags().len(), 1);
+        assert_eq!(read.visuals().len(), 1);
+    }
+
+    #[test]
+    fn verify_write_flac_metadata_after_id3v2() {
+        let original = vec![Block { block_type: MetadataBlockType::StreamInfo, data: vec![0; 34] }];
+
+        let mut builder = MetadataBuilder::new();
+        builder.add_tag(Tag::new(Some(StandardTagKey::Artist), "ARTIST", Value::from("Artist")));
+        let revision = builder.metadata();
+
+        let mut id3v2 = Vec::new();
+        let options = Id3v2WriteOptions { padding: 16, ..Default::default() };
+        write_id3v2_tag(&mut id3v2, &revision, &options).unwrap();
+
+        // The new metadata is written in-place after the ID3v2 tag.
+        let mut file = id3v2.clone();
+        file.extend(make_stream(&original, Some(256)));
+
+        let mut stream = Cursor::new(file.clone());
+        assert!(write_flac_metadata_in_place(&mut stream, &revision).unwrap());
+
+        let updated = stream.into_inner();
+        assert_eq!(updated.len(), file.len());
+        assert_eq!(&updated[..id3v2.len()], &id3v2[..]);
+
+        let (blocks, read) = read_metadata(&updated);
+
+        assert_eq!(block_ids(&blocks), vec![0, 4, 1]);
+        assert_eq!(read.tags()[0].value.to_string(), "Artist");
+
+        // The ID3v2 tag is preserved when the file is rewritten.
+        let mut file = id3v2.clone();
+        file.extend(make_stream(&original, None));
+
+        let path =
+            std::env::temp_dir().join(format!("symphonia-flac-id3v2-{}.flac", std::process::id()));
+        fs::write(&path, &file).unwrap();
+
+        update_flac_file(&path, &revision, &FlacWriteOptions { padding: 100 }).unwrap();
+
+        let updated = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(&updated[..id3v2.len()], &id3v2[..]);
+
+        let (blocks, read) = read_metadata(&updated);
+
+        assert_eq!(block_ids(&blocks), vec![0, 4, 1]);
+        assert_eq!(blocks[2].data.len(), 100);
+        assert_eq!(read.tags()[0].value.to_string(), "Artist");
+    }
+}
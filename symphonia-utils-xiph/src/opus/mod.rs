@@ -0,0 +1,61 @@
+// Symphonia
+// Copyright (c) 2019-2022 The Project Symphonia Developers.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use symphonia_core::audio::Channels;
+
+use crate::vorbis::vorbis_channels_to_channels;
+
+/// The minimum size of an Opus identification header.
+const OPUS_HEAD_MIN_SIZE: usize = 19;
+
+/// The signature of an Opus identification header.
+const OPUS_HEAD_SIGNATURE: &[u8] = b"OpusHead";
+
+/// The maximum ambisonic order of channel mapping families 2 and 3.
+const OPUS_AMBISONIC_ORDER_MAX: u8 = 14;
+
+/// Get the channels for the given Opus channel mapping family and number of channels.
+///
+/// Mapping families 2 and 3 contain a full-sphere ambisonic sound field, optionally followed by a
+/// non-diegetic (head-locked) stereo pair. Mapping family 255 contains discrete channels.
+pub fn opus_channels(mapping_family: u8, num_channels: u8) -> Option<Channels> {
+    match mapping_family {
+        // RTP mapping.
+        0 if num_channels <= 2 => vorbis_channels_to_channels(num_channels),
+        // Vorbis mapping.
+        1 if num_channels <= 8 => vorbis_channels_to_channels(num_channels),
+        // Ambisonic mappings.
+        2 | 3 => {
+            let order = (f64::from(num_channels).sqrt() as u8).checked_sub(1)?;
+
+            if order > OPUS_AMBISONIC_ORDER_MAX {
+                return None;
+            }
+
+            match num_channels - (order + 1) * (order + 1) {
+                0 => Channels::ambisonic(order),
+                2 => {
+                    Some(Channels::ambisonic(order)? | Channels::FRONT_LEFT | Channels::FRONT_RIGHT)
+                }
+                _ => None,
+            }
+        }
+        // Discrete channels.
+        255 if num_channels > 0 => Some(Channels::discrete(u16::from(num_channels))),
+        // Reserved, and should NOT be supported for playback.
+        _ => None,
+    }
+}
+
+/// Get the channels described by an Opus identification header (`OpusHead`).
+pub fn opus_head_channels(buf: &[u8]) -> Option<Channels> {
+    if buf.len() < OPUS_HEAD_MIN_SIZE || &buf[..8] != OPUS_HEAD_SIGNATURE {
+        return None;
+    }
+
+    opus_channels(buf[18], buf[9])
+}
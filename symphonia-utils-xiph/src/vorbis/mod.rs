@@ -53,7 +53,9 @@ pub fn vorbis_channels_to_channels(num_channels: u8) -> Option<Channels> {
                 | Channels::REAR_RIGHT
                 | Channels::LFE1
         }
-        _ => return None,
+        // The channel order for more than 8 channels is application defined.
+        0 => return None,
+        n => Channels::discrete(u16::from(n)),
     };
 
     Some(channels)
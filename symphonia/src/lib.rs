@@ -24,6 +24,9 @@
 //!
 //! | Format   | Feature Flag | Gapless* | Default |
 //! |----------|--------------|----------|---------|
+//! | APE      | `ape`        | Yes      | No      |
+//! | DSDIFF   | `dsd`        | No       | No      |
+//! | DSF      | `dsd`        | No       | No      |
 //! | ISO/MP4  | `isomp4`     | No       | No      |
 //! | MKV/WebM | `mkv`        | No       | Yes     |
 //! | OGG      | `ogg`        | Yes      | Yes     |
@@ -39,15 +42,19 @@
 //! |----------|--------------|---------|---------|
 //! | AAC-LC   | `aac`        | No      | No      |
 //! | ALAC     | `alac`       | Yes     | No      |
+//! | APE      | `ape`        | Yes     | No      |
+//! | DSD      | `dsd`        | No      | No      |
 //! | FLAC     | `flac`       | Yes     | Yes     |
 //! | MP3      | `mp3`        | Yes     | No      |
 //! | PCM      | `pcm`        | Yes     | Yes     |
+//! | Speex    | `speex`      | Yes     | No      |
 //! | Vorbis   | `vorbis`     | Yes     | Yes     |
 //!
 //! ## Metadata
 //!
 //! The following metadata tagging formats are supported. These are always enabled.
 //!
+//! * APEv1 & APEv2 (in APE)
 //! * ID3v1
 //! * ID3v2
 //! * ISO/MP4
@@ -116,6 +123,10 @@ pub mod default {
     pub mod codecs {
         //! The `codecs` module re-exports all enabled Symphonia decoders.
 
+        #[cfg(feature = "ape")]
+        pub use symphonia_bundle_ape::ApeDecoder;
+        #[cfg(feature = "dsd")]
+        pub use symphonia_bundle_dsd::DsdDecoder;
         #[cfg(feature = "flac")]
         pub use symphonia_bundle_flac::FlacDecoder;
         #[cfg(feature = "mp3")]
@@ -126,6 +137,8 @@ pub mod default {
         pub use symphonia_codec_alac::AlacDecoder;
         #[cfg(feature = "pcm")]
         pub use symphonia_codec_pcm::PcmDecoder;
+        #[cfg(feature = "speex")]
+        pub use symphonia_codec_speex::SpeexDecoder;
         #[cfg(feature = "vorbis")]
         pub use symphonia_codec_vorbis::VorbisDecoder;
     }
@@ -133,6 +146,10 @@ pub mod default {
     pub mod formats {
         //! The `formats` module re-exports all enabled Symphonia format readers.
 
+        #[cfg(feature = "ape")]
+        pub use symphonia_bundle_ape::ApeReader;
+        #[cfg(feature = "dsd")]
+        pub use symphonia_bundle_dsd::{DsdiffReader, DsfReader};
         #[cfg(feature = "flac")]
         pub use symphonia_bundle_flac::FlacReader;
         #[cfg(feature = "mp3")]
@@ -202,6 +219,12 @@ pub mod default {
         #[cfg(feature = "alac")]
         registry.register_all::<codecs::AlacDecoder>();
 
+        #[cfg(feature = "ape")]
+        registry.register_all::<codecs::ApeDecoder>();
+
+        #[cfg(feature = "dsd")]
+        registry.register_all::<codecs::DsdDecoder>();
+
         #[cfg(feature = "flac")]
         registry.register_all::<codecs::FlacDecoder>();
 
@@ -211,6 +234,9 @@ pub mod default {
         #[cfg(feature = "pcm")]
         registry.register_all::<codecs::PcmDecoder>();
 
+        #[cfg(feature = "speex")]
+        registry.register_all::<codecs::SpeexDecoder>();
+
         #[cfg(feature = "vorbis")]
         registry.register_all::<codecs::VorbisDecoder>();
     }
@@ -227,6 +253,15 @@ pub mod default {
         #[cfg(feature = "aac")]
         probe.register_all::<formats::AdtsReader>();
 
+        #[cfg(feature = "ape")]
+        probe.register_all::<formats::ApeReader>();
+
+        #[cfg(feature = "dsd")]
+        probe.register_all::<formats::DsdiffReader>();
+
+        #[cfg(feature = "dsd")]
+        probe.register_all::<formats::DsfReader>();
+
         #[cfg(feature = "flac")]
         probe.register_all::<formats::FlacReader>();
 
@@ -0,0 +1,226 @@
+/*
+ * Generates the Speex test fixtures with libspeex 1.2.1.
+ *
+ * A synthetic voice-like signal is encoded, written to an Ogg Speex file, and decoded again by
+ * libspeex (with the perceptual enhancer enabled) to produce the reference output. The reference
+ * output is written as interleaved, signed 16-bit, little-endian PCM.
+ *
+ * Usage: generate <mode> <quality> <channels> <vbr> <frames per packet> <packets> <output stem>
+ *
+ * Build: cc -O2 -o generate generate.c -I<speex>/include -L<speex>/lib -lspeex -lm
+ *
+ * The fixtures were generated with:
+ *
+ *   generate 0 8 1 0 2 25 nb
+ *   generate 1 8 1 0 1 25 wb
+ *   generate 2 8 1 1 1 25 uwb_vbr
+ *   generate 0 6 2 0 1 50 stereo
+ */
+#include <math.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include <speex/speex.h>
+#include <speex/speex_callbacks.h>
+#include <speex/speex_header.h>
+#include <speex/speex_stereo.h>
+
+#define PACKETS_PER_PAGE 10
+
+static unsigned int crc32(const unsigned char *buf, size_t len, unsigned int crc) {
+    for (size_t i = 0; i < len; i++) {
+        crc ^= (unsigned int)buf[i] << 24;
+        for (int k = 0; k < 8; k++) {
+            crc = (crc & 0x80000000) ? (crc << 1) ^ 0x04c11db7 : crc << 1;
+        }
+    }
+    return crc;
+}
+
+static void put_le(unsigned char *buf, unsigned long long value, int len) {
+    for (int i = 0; i < len; i++) {
+        buf[i] = (unsigned char)(value >> (8 * i));
+    }
+}
+
+/* Writes one Ogg page containing complete packets. */
+static void write_page(FILE *f, unsigned char **packets, int *lens, int n, long long granule,
+                       int seq, int flags) {
+    unsigned char hdr[27 + 255];
+    int n_segs = 0;
+
+    memcpy(hdr, "OggS", 4);
+    hdr[4] = 0;
+    hdr[5] = (unsigned char)flags;
+    put_le(hdr + 6, (unsigned long long)granule, 8);
+    put_le(hdr + 14, 0x5350580a, 4);
+    put_le(hdr + 18, (unsigned long long)seq, 4);
+    put_le(hdr + 22, 0, 4);
+
+    for (int i = 0; i < n; i++) {
+        int len = lens[i];
+        while (len >= 255) {
+            hdr[27 + n_segs++] = 255;
+            len -= 255;
+        }
+        hdr[27 + n_segs++] = (unsigned char)len;
+    }
+    hdr[26] = (unsigned char)n_segs;
+
+    unsigned int crc = crc32(hdr, 27 + n_segs, 0);
+    for (int i = 0; i < n; i++) {
+        crc = crc32(packets[i], lens[i], crc);
+    }
+    put_le(hdr + 22, crc, 4);
+
+    fwrite(hdr, 1, 27 + n_segs, f);
+    for (int i = 0; i < n; i++) {
+        fwrite(packets[i], 1, lens[i], f);
+    }
+}
+
+int main(int argc, char **argv) {
+    if (argc != 8) {
+        fprintf(stderr, "usage: %s mode quality channels vbr fpp packets stem\n", argv[0]);
+        return 1;
+    }
+
+    int mode_id = atoi(argv[1]), quality = atoi(argv[2]), channels = atoi(argv[3]);
+    int vbr = atoi(argv[4]), fpp = atoi(argv[5]), n_packets = atoi(argv[6]);
+    const char *stem = argv[7];
+
+    const SpeexMode *mode = speex_lib_get_mode(mode_id);
+    void *enc = speex_encoder_init(mode);
+    void *dec = speex_decoder_init(mode);
+    int rate = 8000 << mode_id;
+    int frame_size;
+    int one = 1;
+
+    speex_encoder_ctl(enc, SPEEX_GET_FRAME_SIZE, &frame_size);
+    speex_encoder_ctl(enc, SPEEX_SET_QUALITY, &quality);
+    if (vbr) {
+        speex_encoder_ctl(enc, SPEEX_SET_VBR, &one);
+    }
+    speex_decoder_ctl(dec, SPEEX_SET_ENH, &one);
+
+    SpeexStereoState *stereo = speex_stereo_state_init();
+    SpeexCallback callback;
+    callback.callback_id = SPEEX_INBAND_STEREO;
+    callback.func = speex_std_stereo_request_handler;
+    callback.data = stereo;
+    speex_decoder_ctl(dec, SPEEX_SET_HANDLER, &callback);
+
+    char path[512];
+    snprintf(path, sizeof(path), "%s.spx", stem);
+    FILE *spx = fopen(path, "wb");
+    snprintf(path, sizeof(path), "%s.pcm", stem);
+    FILE *pcm = fopen(path, "wb");
+    if (!spx || !pcm) {
+        perror("fopen");
+        return 1;
+    }
+
+    /* Identification and comment header pages. */
+    SpeexHeader header;
+    int header_len;
+    speex_init_header(&header, rate, channels, mode);
+    header.frames_per_packet = fpp;
+    header.vbr = vbr;
+    unsigned char *header_packet = (unsigned char *)speex_header_to_packet(&header, &header_len);
+    write_page(spx, &header_packet, &header_len, 1, 0, 0, 2);
+
+    static unsigned char comment[] = "\x08\0\0\0libspeex\x01\0\0\0\x0b\0\0\0TITLE=Speex";
+    unsigned char *comment_packet = comment;
+    int comment_len = sizeof(comment) - 1;
+    write_page(spx, &comment_packet, &comment_len, 1, 0, 1, 0);
+
+    SpeexBits enc_bits, dec_bits;
+    speex_bits_init(&enc_bits);
+    speex_bits_init(&dec_bits);
+
+    static unsigned char packets[PACKETS_PER_PAGE][4096];
+    unsigned char *page_packets[PACKETS_PER_PAGE];
+    int page_lens[PACKETS_PER_PAGE];
+    int n_page_packets = 0;
+    int seq = 2;
+
+    float in[2 * 640], out[2 * 640];
+    double phase = 0.0;
+    unsigned int seed = 1;
+    long long n = 0;
+
+    for (int p = 0; p < n_packets; p++) {
+        speex_bits_reset(&enc_bits);
+
+        for (int k = 0; k < fpp; k++) {
+            for (int i = 0; i < frame_size; i++, n++) {
+                double t = (double)n / rate;
+                double f0 = 110.0 + 60.0 * sin(2.0 * M_PI * 0.7 * t);
+                double env = fmod(t, 0.4) < 0.3 ? 1.0 : 0.02;
+                double s = 0.0;
+
+                phase += 2.0 * M_PI * f0 / rate;
+                for (int h = 1; h < 30 && h * f0 < rate / 2; h++) {
+                    s += sin(h * phase) / h;
+                }
+
+                seed = seed * 1103515245 + 12345;
+                double noise = ((seed >> 16) & 0x7fff) / 32768.0 - 0.5;
+                double left = env * (6000.0 * s + 800.0 * noise);
+                double right = 0.5 * left + env * 300.0 * sin(2.0 * M_PI * 1000.0 * t);
+
+                if (channels == 2) {
+                    in[2 * i] = (float)left;
+                    in[2 * i + 1] = (float)right;
+                }
+                else {
+                    in[i] = (float)left;
+                }
+            }
+
+            if (channels == 2) {
+                speex_encode_stereo(in, frame_size, &enc_bits);
+            }
+            speex_encode(enc, in, &enc_bits);
+        }
+
+        speex_bits_insert_terminator(&enc_bits);
+        unsigned char *packet = packets[n_page_packets];
+        int len = speex_bits_write(&enc_bits, (char *)packet, sizeof(packets[0]));
+
+        /* Decode the packet to produce the reference output. */
+        speex_bits_read_from(&dec_bits, (char *)packet, len);
+
+        for (int k = 0; k < fpp; k++) {
+            if (speex_decode(dec, &dec_bits, out) != 0) {
+                fprintf(stderr, "failed to decode packet %d\n", p);
+                return 1;
+            }
+            if (channels == 2) {
+                speex_decode_stereo(out, frame_size, stereo);
+            }
+            for (int i = 0; i < frame_size * channels; i++) {
+                float v = floorf(out[i] + 0.5f);
+                short sample = v > 32767.0f ? 32767 : v < -32768.0f ? -32768 : (short)v;
+                unsigned char le[2] = { (unsigned char)sample, (unsigned char)(sample >> 8) };
+                fwrite(le, 1, 2, pcm);
+            }
+        }
+
+        page_packets[n_page_packets] = packet;
+        page_lens[n_page_packets] = len;
+        n_page_packets++;
+
+        if (n_page_packets == PACKETS_PER_PAGE || p == n_packets - 1) {
+            long long granule = (long long)(p + 1) * fpp * frame_size;
+            int flags = p == n_packets - 1 ? 4 : 0;
+            write_page(spx, page_packets, page_lens, n_page_packets, granule, seq++, flags);
+            n_page_packets = 0;
+        }
+    }
+
+    fclose(spx);
+    fclose(pcm);
+    return 0;
+}
@@ -0,0 +1,89 @@
+// Verifies that decoders produce bit-identical audio regardless of the instruction set used by the
+// DSP kernels. The kernels are selected once per process, therefore, the scalar kernels are tested
+// by running this test again in a child process with `SYMPHONIA_SIMD=scalar`.
+//
+// `fixtures/sine_440hz_stereo.ogg` is from the samples of audrey 0.3.0 (MIT/Apache-2.0).
+
+#![cfg(all(feature = "ogg", feature = "vorbis"))]
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::Hasher;
+use std::process::Command;
+
+use symphonia::core::audio::{AudioBufferRef, Signal};
+use symphonia::core::dsp::simd;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::probe::Hint;
+
+/// If set, the test only prints the checksum of the decoded audio.
+const CHILD_ENV: &str = "SYMPHONIA_SIMD_TEST_CHILD";
+
+/// Decodes the first track of `data`, and returns a checksum of the decoded audio and the number
+/// of decoded frames.
+fn decode_checksum(data: &'static [u8], extension: &str) -> (u64, u64) {
+    let source = MediaSourceStream::new(Box::new(std::io::Cursor::new(data)), Default::default());
+
+    let mut hint = Hint::new();
+    hint.with_extension(extension);
+
+    let probed = symphonia::default::get_probe()
+        .format(&hint, source, &Default::default(), &Default::default())
+        .unwrap();
+
+    let mut reader = probed.format;
+
+    let params = reader.default_track().unwrap().codec_params.clone();
+
+    let mut decoder = symphonia::default::get_codecs().make(&params, &Default::default()).unwrap();
+
+    let mut hasher = DefaultHasher::new();
+    let mut n_frames = 0;
+
+    while let Ok(packet) = reader.next_packet() {
+        match decoder.decode(&packet).unwrap() {
+            AudioBufferRef::F32(buf) => {
+                for ch in 0..buf.spec().channels.count() {
+                    buf.chan(ch).iter().for_each(|s| hasher.write_u32(s.to_bits()));
+                }
+                n_frames += buf.frames() as u64;
+            }
+            _ => panic!("expected 32-bit floating point samples"),
+        }
+    }
+
+    (hasher.finish(), n_frames)
+}
+
+/// Runs `test` in a child process using the scalar kernels, and returns its output.
+fn run_scalar(test: &str) -> String {
+    let output = Command::new(std::env::current_exe().unwrap())
+        .args([test, "--exact", "--nocapture", "--test-threads=1"])
+        .env(CHILD_ENV, "1")
+        .env("SYMPHONIA_SIMD", "scalar")
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn verify_vorbis_simd_matches_scalar() {
+    let (checksum, n_frames) =
+        decode_checksum(include_bytes!("fixtures/sine_440hz_stereo.ogg"), "ogg");
+
+    assert!(n_frames > 0);
+
+    let result =
+        format!("instruction set = {}, checksum = {:016x}", simd::instruction_set(), checksum);
+
+    if std::env::var_os(CHILD_ENV).is_some() {
+        println!("{}", result);
+        return;
+    }
+
+    let expected = format!("instruction set = scalar, checksum = {:016x}", checksum);
+
+    assert!(run_scalar("verify_vorbis_simd_matches_scalar").contains(&expected), "{}", result);
+}
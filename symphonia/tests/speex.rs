@@ -0,0 +1,136 @@
+// Verifies the Speex decoder, and the Ogg Speex mapping, against the output of libspeex 1.2.1.
+//
+// The fixtures in `fixtures/speex` are generated by `fixtures/speex/generate.c`. Each `.spx` file
+// is accompanied by a `.pcm` file containing the output of the reference decoder as interleaved,
+// signed 16-bit, little-endian PCM. Since the reference decoder is a floating point
+// implementation, the decoded audio is not expected to be bit-exact.
+
+#![cfg(all(feature = "ogg", feature = "speex"))]
+
+use symphonia::core::audio::{AudioBufferRef, Signal};
+use symphonia::core::codecs::CODEC_TYPE_SPEEX;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::StandardTagKey;
+use symphonia::core::probe::Hint;
+
+/// The minimum signal-to-noise ratio, in dB, of the decoded audio relative to the reference.
+const MIN_SNR: f64 = 60.0;
+
+/// The maximum difference between a decoded sample and its reference.
+const MAX_DIFF: f32 = 16.0;
+
+/// Decodes `spx`, compares it against the reference `pcm`, and returns the sample rate and number
+/// of channels.
+fn decode_and_compare(spx: &'static [u8], pcm: &'static [u8]) -> (u32, usize) {
+    let source = MediaSourceStream::new(Box::new(std::io::Cursor::new(spx)), Default::default());
+
+    let mut hint = Hint::new();
+    hint.with_extension("spx");
+
+    let mut probed = symphonia::default::get_probe()
+        .format(&hint, source, &Default::default(), &Default::default())
+        .unwrap();
+
+    // The comment header.
+    let title = probed.format.metadata().current().and_then(|rev| {
+        rev.tags().iter().find(|tag| tag.std_key == Some(StandardTagKey::TrackTitle)).cloned()
+    });
+    assert_eq!(title.map(|tag| tag.value.to_string()).as_deref(), Some("Speex"));
+
+    let mut reader = probed.format;
+
+    let params = reader.default_track().unwrap().codec_params.clone();
+    assert_eq!(params.codec, CODEC_TYPE_SPEEX);
+
+    let sample_rate = params.sample_rate.unwrap();
+    let n_channels = params.channels.unwrap().count();
+
+    let mut decoder = symphonia::default::get_codecs().make(&params, &Default::default()).unwrap();
+
+    let reference: Vec<f32> =
+        pcm.chunks_exact(2).map(|s| f32::from(i16::from_le_bytes([s[0], s[1]]))).collect();
+
+    let mut pos = 0;
+    let mut next_ts = 0;
+    let mut signal = 0.0;
+    let mut noise = 0.0;
+
+    while let Ok(packet) = reader.next_packet() {
+        assert_eq!(packet.ts(), next_ts);
+        next_ts += packet.dur();
+
+        let buf = match decoder.decode(&packet).unwrap() {
+            AudioBufferRef::F32(buf) => buf,
+            _ => panic!("expected 32-bit floating point samples"),
+        };
+
+        assert_eq!(buf.frames() as u64, packet.dur());
+        assert!(pos + buf.frames() * n_channels <= reference.len());
+
+        for i in 0..buf.frames() {
+            for ch in 0..n_channels {
+                let expected = reference[pos + i * n_channels + ch];
+                let actual = buf.chan(ch)[i] * 32768.0;
+
+                assert!(
+                    (expected - actual).abs() <= MAX_DIFF,
+                    "frame {}, channel {}: expected {}, decoded {}",
+                    pos / n_channels + i,
+                    ch,
+                    expected,
+                    actual
+                );
+
+                signal += f64::from(expected).powi(2);
+                noise += f64::from(expected - actual).powi(2);
+            }
+        }
+
+        pos += buf.frames() * n_channels;
+    }
+
+    // All the reference audio must have been decoded.
+    assert_eq!(pos, reference.len());
+
+    let snr = 10.0 * (signal / noise).log10();
+    assert!(snr >= MIN_SNR, "snr = {:.1} dB", snr);
+
+    (sample_rate, n_channels)
+}
+
+#[test]
+fn verify_narrowband() {
+    // Quality 8, two frames per packet.
+    let result = decode_and_compare(
+        include_bytes!("fixtures/speex/nb.spx"),
+        include_bytes!("fixtures/speex/nb.pcm"),
+    );
+    assert_eq!(result, (8000, 1));
+}
+
+#[test]
+fn verify_wideband() {
+    let result = decode_and_compare(
+        include_bytes!("fixtures/speex/wb.spx"),
+        include_bytes!("fixtures/speex/wb.pcm"),
+    );
+    assert_eq!(result, (16000, 1));
+}
+
+#[test]
+fn verify_ultra_wideband_vbr() {
+    let result = decode_and_compare(
+        include_bytes!("fixtures/speex/uwb_vbr.spx"),
+        include_bytes!("fixtures/speex/uwb_vbr.pcm"),
+    );
+    assert_eq!(result, (32000, 1));
+}
+
+#[test]
+fn verify_intensity_stereo() {
+    let result = decode_and_compare(
+        include_bytes!("fixtures/speex/stereo.spx"),
+        include_bytes!("fixtures/speex/stereo.pcm"),
+    );
+    assert_eq!(result, (8000, 2));
+}